@@ -1,9 +1,11 @@
 use std::fs;
 use std::fs::{copy, create_dir, create_dir_all, exists, remove_dir_all, remove_file};
+use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use file_structure::{Dir, Kind};
+use kernel_cpio::ArchiveBuilder;
 use ovmf_prebuilt::{Arch, FileType, Prebuilt, Source};
 
 fn main() {
@@ -18,7 +20,12 @@ fn main() {
     );
     println!("cargo:rustc-env=KERNEL_BINARY={}", kernel.display());
 
-    let iso = build_iso(&limine_dir, &kernel);
+    let disk_dir = build_os_disk_dir();
+
+    let initramfs = build_initramfs(&disk_dir);
+    println!("cargo:rustc-env=INITRAMFS={}", initramfs.display());
+
+    let iso = build_iso(&limine_dir, &kernel, &initramfs);
     println!("cargo:rustc-env=BOOTABLE_ISO={}", iso.display());
 
     let ovmf = ovmf();
@@ -31,12 +38,11 @@ fn main() {
         ovmf.get_file(Arch::X64, FileType::Vars).display()
     );
 
-    let disk_image = build_os_disk_image();
+    let disk_image = build_os_disk_image(&disk_dir);
     println!("cargo:rustc-env=DISK_IMAGE={}", disk_image.display());
 }
 
-fn build_os_disk_image() -> PathBuf {
-    let disk_dir = build_os_disk_dir();
+fn build_os_disk_image(disk_dir: &Path) -> PathBuf {
     let disk_image = disk_dir.with_extension("img");
 
     let _ = remove_file(&disk_image); // if this fails, doesn't matter
@@ -67,6 +73,55 @@ fn build_os_disk_image() -> PathBuf {
     disk_image
 }
 
+/// Packs the contents of the disk directory into a cpio newc archive, which
+/// is loaded by limine as the initramfs.
+fn build_initramfs(disk_dir: &Path) -> PathBuf {
+    let mut builder = ArchiveBuilder::new();
+    add_to_initramfs(&mut builder, disk_dir, "");
+
+    let initramfs = out_dir().join("initramfs.cpio");
+    fs::write(&initramfs, builder.finish()).expect("should be able to write initramfs");
+    initramfs
+}
+
+fn add_to_initramfs(builder: &mut ArchiveBuilder, dir: &Path, prefix: &str) {
+    let mut entries = fs::read_dir(dir)
+        .expect("should be able to read directory")
+        .map(|entry| entry.expect("should be able to read directory entry"))
+        .collect::<Vec<_>>();
+    // sort to make the archive reproducible
+    entries.sort_by_key(fs::DirEntry::file_name);
+
+    for entry in entries {
+        let file_name = entry.file_name();
+        let name = format!(
+            "{prefix}{}",
+            file_name
+                .to_str()
+                .expect("file names should be valid UTF-8")
+        );
+        let path = entry.path();
+        let metadata = fs::symlink_metadata(&path).expect("should be able to read metadata");
+        let permissions = metadata.permissions().mode() & 0o7777;
+
+        if metadata.is_dir() {
+            builder.add_directory(&name, permissions);
+            add_to_initramfs(builder, &path, &format!("{name}/"));
+        } else if metadata.is_symlink() {
+            let target = fs::read_link(&path).expect("should be able to read symlink");
+            builder.add_symlink(
+                &name,
+                target
+                    .to_str()
+                    .expect("symlink targets should be valid UTF-8"),
+            );
+        } else {
+            let data = fs::read(&path).expect("should be able to read file");
+            builder.add_file(&name, permissions, &data);
+        }
+    }
+}
+
 fn build_os_disk_dir() -> PathBuf {
     let disk = out_dir().join("disk");
     let _ = remove_dir_all(&disk);
@@ -110,9 +165,14 @@ fn ovmf() -> Prebuilt {
         .expect("should be able to fetch OVMF prebuilt firmware")
 }
 
-fn build_iso(limine_checkout: impl AsRef<Path>, kernel_binary: impl AsRef<Path>) -> PathBuf {
+fn build_iso(
+    limine_checkout: impl AsRef<Path>,
+    kernel_binary: impl AsRef<Path>,
+    initramfs: impl AsRef<Path>,
+) -> PathBuf {
     let limine_checkout = limine_checkout.as_ref();
     let kernel_binary = kernel_binary.as_ref();
+    let initramfs = initramfs.as_ref();
 
     let out_dir = out_dir();
 
@@ -136,6 +196,7 @@ fn build_iso(limine_checkout: impl AsRef<Path>, kernel_binary: impl AsRef<Path>)
 
     // copy the kernel binary to the location that is specified in limine.conf
     copy(kernel_binary, boot_dir.join("kernel")).expect("should be able to copy kernel binary");
+    copy(initramfs, boot_dir.join("initramfs.cpio")).expect("should be able to copy initramfs");
 
     // the following is x86_64 specific
 
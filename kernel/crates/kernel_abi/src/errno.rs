@@ -134,4 +134,5 @@ n! {
     ETXTBSY = 75,
     EWOULDBLOCK = 76,
     EXDEV = 77,
+    ECONNRESET = 78,
 }
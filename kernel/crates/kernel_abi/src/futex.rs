@@ -0,0 +1,28 @@
+//! The operations of `futex`, with the same values as on Linux.
+
+use core::ffi::c_int;
+
+/// Waits on a futex, if it still holds the expected value.
+pub const FUTEX_WAIT: c_int = 0;
+/// Wakes waiters of a futex.
+pub const FUTEX_WAKE: c_int = 1;
+/// Wakes waiters of a futex and moves the others to a second futex.
+pub const FUTEX_REQUEUE: c_int = 3;
+/// Like [`FUTEX_REQUEUE`], if the first futex still holds the expected
+/// value.
+pub const FUTEX_CMP_REQUEUE: c_int = 4;
+/// Like [`FUTEX_WAIT`], with a bitset that wakes must match and an
+/// absolute timeout.
+pub const FUTEX_WAIT_BITSET: c_int = 9;
+/// Like [`FUTEX_WAKE`], for the waiters whose bitset matches.
+pub const FUTEX_WAKE_BITSET: c_int = 10;
+
+/// The futex isn't shared with other processes. Futexes are identified by
+/// their physical address either way, so this has no effect.
+pub const FUTEX_PRIVATE_FLAG: c_int = 128;
+/// The timeout of [`FUTEX_WAIT_BITSET`] is measured against the realtime
+/// clock, which doesn't exist yet.
+pub const FUTEX_CLOCK_REALTIME: c_int = 256;
+
+/// The bitset that matches every other one.
+pub const FUTEX_BITSET_MATCH_ANY: u32 = u32::MAX;
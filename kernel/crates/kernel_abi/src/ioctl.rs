@@ -0,0 +1,22 @@
+//! Request numbers for `ioctl`. The values are the same as on Linux, so that
+//! ported programs don't have to be changed.
+
+pub const TCGETS: u64 = 0x5401;
+pub const TCSETS: u64 = 0x5402;
+pub const TCSETSW: u64 = 0x5403;
+pub const TCSETSF: u64 = 0x5404;
+pub const TIOCSCTTY: u64 = 0x540E;
+pub const TIOCGPGRP: u64 = 0x540F;
+pub const TIOCSPGRP: u64 = 0x5410;
+pub const TIOCGWINSZ: u64 = 0x5413;
+pub const TIOCSWINSZ: u64 = 0x5414;
+pub const TIOCNOTTY: u64 = 0x5422;
+pub const TIOCGPTN: u64 = 0x8004_5430;
+pub const TIOCSPTLCK: u64 = 0x4004_5431;
+pub const BLKSSZGET: u64 = 0x1268;
+pub const BLKGETSIZE64: u64 = 0x8008_1272;
+pub const FBIOGET_VSCREENINFO: u64 = 0x4600;
+pub const FBIOGET_FSCREENINFO: u64 = 0x4602;
+/// Makes the changes to a framebuffer visible. Not part of Linux, where
+/// this happens through DRM.
+pub const FBIO_FLUSH: u64 = 0x46f0;
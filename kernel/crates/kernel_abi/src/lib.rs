@@ -1,13 +1,35 @@
 #![no_std]
 
 mod errno;
+mod fb;
 mod fcntl;
+mod futex;
+mod ioctl;
 mod limits;
 mod mman;
+mod mount;
+mod poll;
+mod random;
+mod sched;
+mod signal;
+mod socket;
 mod syscall;
+mod syslog;
+mod termios;
 
 pub use errno::*;
+pub use fb::*;
 pub use fcntl::*;
+pub use futex::*;
+pub use ioctl::*;
 pub use limits::*;
 pub use mman::*;
+pub use mount::*;
+pub use poll::*;
+pub use random::*;
+pub use sched::*;
+pub use signal::*;
+pub use socket::*;
 pub use syscall::*;
+pub use syslog::*;
+pub use termios::*;
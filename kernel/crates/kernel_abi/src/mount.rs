@@ -0,0 +1,31 @@
+use bitflags::bitflags;
+
+/// The magic number that old programs pass in the upper 16 bits of the
+/// mount flags.
+pub const MS_MGC_VAL: u64 = 0xC0ED_0000;
+pub const MS_MGC_MSK: u64 = 0xFFFF_0000;
+
+bitflags! {
+    /// Flags for mount, corresponding to the `MS_*` flags of the same name
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MountFlags: u64 {
+        const RDONLY = 0x1;
+        const NOSUID = 0x2;
+        const NODEV = 0x4;
+        const NOEXEC = 0x8;
+        const REMOUNT = 0x20;
+        const BIND = 0x1000;
+        const MOVE = 0x2000;
+        const REC = 0x4000;
+        const SILENT = 0x8000;
+    }
+}
+
+bitflags! {
+    /// Flags for umount2, corresponding to `MNT_FORCE` and `MNT_DETACH`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct UmountFlags: i32 {
+        const FORCE = 0x1;
+        const DETACH = 0x2;
+    }
+}
@@ -0,0 +1,122 @@
+//! `poll`, `select` and `epoll`, with the same values and layouts as on
+//! Linux.
+
+use core::ffi::{c_int, c_short};
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// The events of [`PollFd`] and [`EpollEvent`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct PollEvents: u32 {
+        /// There is data to read.
+        const IN = 0x1;
+        /// There is urgent data to read, which is never the case.
+        const PRI = 0x2;
+        /// Writing doesn't block.
+        const OUT = 0x4;
+        /// An error is pending. Always reported, even if not requested.
+        const ERR = 0x8;
+        /// The other end is gone. Always reported, even if not requested.
+        const HUP = 0x10;
+        /// The file descriptor isn't open, only for `poll`.
+        const NVAL = 0x20;
+        const RDNORM = 0x40;
+        const RDBAND = 0x80;
+        const WRNORM = 0x100;
+        const WRBAND = 0x200;
+        /// The peer shut down its side of a stream for writing.
+        const RDHUP = 0x2000;
+    }
+}
+
+/// A file descriptor of `poll`, and the events that are waited for.
+/// Negative file descriptors are ignored.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct PollFd {
+    pub fd: c_int,
+    pub events: c_short,
+    /// The events that occurred, which `poll` writes.
+    pub revents: c_short,
+}
+
+/// A timeout of `ppoll`.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct TimeSpec {
+    pub tv_sec: i64,
+    pub tv_nsec: i64,
+}
+
+/// A timeout of `select`.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct TimeVal {
+    pub tv_sec: i64,
+    pub tv_usec: i64,
+}
+
+/// The number of file descriptors in an [`FdSet`].
+pub const FD_SETSIZE: usize = 1024;
+
+/// A set of file descriptors of `select`, one bit for each.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FdSet {
+    pub fds_bits: [u64; FD_SETSIZE / 64],
+}
+
+impl Default for FdSet {
+    fn default() -> Self {
+        Self {
+            fds_bits: [0; FD_SETSIZE / 64],
+        }
+    }
+}
+
+impl FdSet {
+    #[must_use]
+    pub fn contains(&self, fd: usize) -> bool {
+        fd < FD_SETSIZE && self.fds_bits[fd / 64] & (1 << (fd % 64)) != 0
+    }
+
+    /// Adds `fd`, which has to be less than [`FD_SETSIZE`].
+    pub fn insert(&mut self, fd: usize) {
+        self.fds_bits[fd / 64] |= 1 << (fd % 64);
+    }
+}
+
+/// Closes the epoll file descriptor on `exec`, the only flag of
+/// `epoll_create1`.
+pub const EPOLL_CLOEXEC: c_int = 0o2_000_000;
+
+pub const EPOLL_CTL_ADD: c_int = 1;
+pub const EPOLL_CTL_DEL: c_int = 2;
+pub const EPOLL_CTL_MOD: c_int = 3;
+
+bitflags! {
+    /// The flags of an [`EpollEvent`] that change how its events are
+    /// reported, besides the [`PollEvents`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct EpollFlags: u32 {
+        /// Wakes only one of the epoll instances that wait for the file.
+        const EXCLUSIVE = 1 << 28;
+        /// Prevents the system from suspending, which has no effect.
+        const WAKEUP = 1 << 29;
+        /// Disables the file after its events were reported once, until it
+        /// is modified with [`EPOLL_CTL_MOD`].
+        const ONESHOT = 1 << 30;
+        /// Reports events only when they occur, not as long as they last.
+        const ET = 1 << 31;
+    }
+}
+
+/// A file of an epoll instance, and the events that are waited for. `data`
+/// is returned with the events that occurred.
+#[repr(C, packed)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct EpollEvent {
+    pub events: u32,
+    pub data: u64,
+}
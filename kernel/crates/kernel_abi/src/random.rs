@@ -0,0 +1,15 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// Flags for getrandom
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct GetRandomFlags: u32 {
+        /// Fail with `EAGAIN` instead of waiting for the generator to be
+        /// seeded.
+        const NONBLOCK = 0x1;
+        /// Has no effect, the same generator is used either way.
+        const RANDOM = 0x2;
+        /// Return random bytes even if the generator isn't seeded yet.
+        const INSECURE = 0x4;
+    }
+}
@@ -0,0 +1,89 @@
+//! Threads and scheduling.
+
+use core::ffi::c_int;
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// The flags of [`CloneArgs`], with the same values as on Linux. Only
+    /// threads can be created, so [`VM`](Self::VM),
+    /// [`SIGHAND`](Self::SIGHAND) and [`THREAD`](Self::THREAD) are
+    /// required.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct CloneFlags: u64 {
+        /// Share the address space.
+        const VM = 0x100;
+        /// Share the working directory, which threads always do.
+        const FS = 0x200;
+        /// Share the file descriptors, which threads always do.
+        const FILES = 0x400;
+        /// Share the signal handlers, which threads always do.
+        const SIGHAND = 0x800;
+        /// Create a thread in the same process.
+        const THREAD = 0x10000;
+        /// Has no effect, there are no System V semaphores.
+        const SYSVSEM = 0x40000;
+        /// Use [`CloneArgs::tls`] as the FS base of the thread.
+        const SETTLS = 0x80000;
+        /// Write the thread id to [`CloneArgs::parent_tid`].
+        const PARENT_SETTID = 0x10_0000;
+        /// When the thread exits, write zero to [`CloneArgs::child_tid`]
+        /// and wake a waiter of the futex there.
+        const CHILD_CLEARTID = 0x20_0000;
+        /// Write the thread id to [`CloneArgs::child_tid`].
+        const CHILD_SETTID = 0x100_0000;
+    }
+}
+
+/// The arguments of `clone`. Unlike on Linux, the new thread doesn't return
+/// from `clone`, but starts with a call of `entry` with `arg`. `entry` must
+/// not return.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct CloneArgs {
+    pub flags: u64,
+    pub entry: u64,
+    pub arg: u64,
+    /// The lowest address of the stack of the thread, or zero to have the
+    /// kernel allocate one.
+    pub stack: u64,
+    pub stack_size: u64,
+    pub tls: u64,
+    pub parent_tid: u64,
+    pub child_tid: u64,
+}
+
+/// The largest number of CPUs. CPU affinity masks have one bit per CPU, so
+/// `sched_getaffinity` writes this many bits.
+pub const MAX_CPUS: usize = 64;
+
+/// The default policy, which shares the CPUs fairly between the threads
+/// according to their nice values.
+pub const SCHED_OTHER: c_int = 0;
+/// A real-time policy. The thread runs until it blocks, yields, or a thread
+/// with a higher real-time priority becomes ready.
+pub const SCHED_FIFO: c_int = 1;
+/// Like [`SCHED_FIFO`], but threads with the same priority take turns.
+pub const SCHED_RR: c_int = 2;
+/// The thread only runs when no thread with another policy is ready.
+pub const SCHED_IDLE: c_int = 5;
+
+/// The lowest and highest priority of [`SCHED_FIFO`] and [`SCHED_RR`]
+/// threads. The other policies only have priority zero.
+pub const SCHED_PRIORITY_MIN: c_int = 1;
+pub const SCHED_PRIORITY_MAX: c_int = 99;
+
+/// The parameters of `sched_setscheduler`.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct SchedParam {
+    pub sched_priority: c_int,
+}
+
+/// `setpriority` and `getpriority` refer to a thread. There are no process
+/// groups or users, so this is the only option.
+pub const PRIO_PROCESS: c_int = 0;
+
+/// The lowest and highest nice value.
+pub const NICE_MIN: c_int = -20;
+pub const NICE_MAX: c_int = 19;
@@ -0,0 +1,9 @@
+use core::ffi::c_int;
+
+pub const SIGHUP: c_int = 1;
+pub const SIGINT: c_int = 2;
+pub const SIGQUIT: c_int = 3;
+pub const SIGKILL: c_int = 9;
+pub const SIGTERM: c_int = 15;
+pub const SIGTSTP: c_int = 20;
+pub const SIGWINCH: c_int = 28;
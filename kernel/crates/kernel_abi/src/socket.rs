@@ -0,0 +1,216 @@
+//! The socket interface, with the same values and layouts as on Linux.
+
+use bitflags::bitflags;
+
+pub type SaFamily = u16;
+pub type SockLen = u32;
+
+pub const AF_UNSPEC: i32 = 0;
+pub const AF_UNIX: i32 = 1;
+pub const AF_INET: i32 = 2;
+pub const AF_INET6: i32 = 10;
+
+pub const SOCK_STREAM: i32 = 1;
+pub const SOCK_DGRAM: i32 = 2;
+pub const SOCK_RAW: i32 = 3;
+/// The bits of the type argument of `socket` that select the type, the
+/// others are [`SocketFlags`].
+pub const SOCK_TYPE_MASK: i32 = 0xf;
+
+bitflags! {
+    /// Flags that can be combined with the type argument of `socket`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct SocketFlags: i32 {
+        const NONBLOCK = 0o4000;
+        const CLOEXEC = 0o2_000_000;
+    }
+}
+
+pub const IPPROTO_IP: i32 = 0;
+pub const IPPROTO_ICMP: i32 = 1;
+pub const IPPROTO_TCP: i32 = 6;
+pub const IPPROTO_UDP: i32 = 17;
+pub const IPPROTO_IPV6: i32 = 41;
+
+/// The level of the options that apply to all sockets.
+pub const SOL_SOCKET: i32 = 1;
+
+pub const SO_REUSEADDR: i32 = 2;
+pub const SO_TYPE: i32 = 3;
+pub const SO_ERROR: i32 = 4;
+pub const SO_BROADCAST: i32 = 6;
+pub const SO_SNDBUF: i32 = 7;
+pub const SO_RCVBUF: i32 = 8;
+pub const SO_KEEPALIVE: i32 = 9;
+/// The credentials of the peer of a Unix socket, as [`UCred`].
+pub const SO_PEERCRED: i32 = 17;
+
+/// Sends segments as soon as possible instead of collecting small writes,
+/// on the level [`IPPROTO_TCP`].
+pub const TCP_NODELAY: i32 = 1;
+
+pub const SHUT_RD: i32 = 0;
+pub const SHUT_WR: i32 = 1;
+pub const SHUT_RDWR: i32 = 2;
+
+bitflags! {
+    /// Flags of `sendto` and `recvfrom`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct MsgFlags: i32 {
+        /// Reads data without removing it from the socket.
+        const PEEK = 0x2;
+        /// Fails with `EAGAIN` instead of waiting, like a non-blocking
+        /// socket.
+        const DONTWAIT = 0x40;
+        /// Doesn't raise `SIGPIPE` if the connection was closed.
+        const NOSIGNAL = 0x4000;
+        /// Set by `recvmsg` if control messages were discarded because the
+        /// control buffer was too small.
+        const CTRUNC = 0x8;
+    }
+}
+
+/// The address that binds to all local addresses.
+pub const INADDR_ANY: u32 = 0;
+
+/// The part of every socket address that says which kind of address
+/// follows.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct SockAddr {
+    pub sa_family: SaFamily,
+    pub sa_data: [u8; 14],
+}
+
+/// An IPv4 address in network byte order.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct InAddr {
+    pub s_addr: u32,
+}
+
+/// An IPv4 address and port, both in network byte order.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct SockAddrIn {
+    pub sin_family: SaFamily,
+    pub sin_port: u16,
+    pub sin_addr: InAddr,
+    pub sin_zero: [u8; 8],
+}
+
+/// An IPv6 address in network byte order.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct In6Addr {
+    pub s6_addr: [u8; 16],
+}
+
+/// An IPv6 address and port. The port and the flow label are in network
+/// byte order, the scope id, which selects the interface of a link-local
+/// address, is in host byte order.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct SockAddrIn6 {
+    pub sin6_family: SaFamily,
+    pub sin6_port: u16,
+    pub sin6_flowinfo: u32,
+    pub sin6_addr: In6Addr,
+    pub sin6_scope_id: u32,
+}
+
+/// The longest path of a Unix socket address, including the terminating
+/// null byte.
+pub const UNIX_PATH_MAX: usize = 108;
+
+/// The address of a Unix socket. A path that starts with a null byte is a
+/// name in the abstract namespace, which isn't in the file system, and its
+/// length is given by the address length instead of a terminating null
+/// byte.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct SockAddrUn {
+    pub sun_family: SaFamily,
+    pub sun_path: [u8; UNIX_PATH_MAX],
+}
+
+impl Default for SockAddrUn {
+    fn default() -> Self {
+        Self {
+            sun_family: AF_UNIX as SaFamily,
+            sun_path: [0; UNIX_PATH_MAX],
+        }
+    }
+}
+
+/// The credentials of a process, see [`SO_PEERCRED`].
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct UCred {
+    pub pid: i32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// The maximum number of buffers of `sendmsg` and `recvmsg`.
+pub const IOV_MAX: usize = 1024;
+
+/// A buffer of `sendmsg` and `recvmsg`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct IoVec {
+    pub iov_base: *mut u8,
+    pub iov_len: usize,
+}
+
+/// The argument of `sendmsg` and `recvmsg`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct MsgHdr {
+    /// The address to send to, or that receives the address of the sender.
+    pub msg_name: *mut u8,
+    pub msg_namelen: SockLen,
+    pub msg_iov: *mut IoVec,
+    pub msg_iovlen: usize,
+    /// The control messages, each a [`CMsgHdr`] followed by its data.
+    pub msg_control: *mut u8,
+    pub msg_controllen: usize,
+    /// The [`MsgFlags`] of a received message.
+    pub msg_flags: i32,
+}
+
+/// The header of a control message, which is followed by its data and
+/// padding up to the next control message.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct CMsgHdr {
+    /// The length of the header and the data, without the padding.
+    pub cmsg_len: usize,
+    pub cmsg_level: i32,
+    pub cmsg_type: i32,
+}
+
+/// The type of control messages on the level [`SOL_SOCKET`] that pass open
+/// files as an array of file descriptors.
+pub const SCM_RIGHTS: i32 = 1;
+/// The most file descriptors that a single message can pass.
+pub const SCM_MAX_FD: usize = 253;
+
+/// Rounds `len` up to the alignment of control messages.
+#[must_use]
+pub const fn cmsg_align(len: usize) -> usize {
+    len.next_multiple_of(size_of::<usize>())
+}
+
+/// The `cmsg_len` of a control message with `len` bytes of data.
+#[must_use]
+pub const fn cmsg_len(len: usize) -> usize {
+    cmsg_align(size_of::<CMsgHdr>()) + len
+}
+
+/// The space that a control message with `len` bytes of data takes up in
+/// the control buffer, including the padding.
+#[must_use]
+pub const fn cmsg_space(len: usize) -> usize {
+    cmsg_align(size_of::<CMsgHdr>()) + cmsg_align(len)
+}
@@ -18,22 +18,7 @@ n! {
     SYS_OPEN = 3,
     SYS_STAT = 4,
     SYS_FSTAT = 5,
-    SYS_PTHREAD_COND_INIT = 6,
-    SYS_PTHREAD_COND_WAIT = 7,
-    SYS_PTHREAD_COND_SIGNAL = 8,
-    SYS_PTHREAD_COND_DESTROY = 9,
     SYS_PTHREAD_SETSPECIFIC = 10,
-    SYS_PTHREAD_MUTEXATTR_INIT = 11,
-    SYS_PTHREAD_MUTEXATTR_DESTROY = 12,
-    SYS_PTHREAD_MUTEXATTR_SETTYPE = 13,
-    SYS_PTHREAD_MUTEX_INIT = 14,
-    SYS_PTHREAD_MUTEX_LOCK = 15,
-    SYS_PTHREAD_MUTEX_TRYLOCK = 16,
-    SYS_PTHREAD_MUTEX_UNLOCK = 17,
-    SYS_PTHREAD_MUTEX_DESTROY = 18,
-    SYS_PTHREAD_CONDATTR_INIT = 19,
-    SYS_PTHREAD_CONDATTR_SETCLOCK = 20,
-    SYS_PTHREAD_CONDATTR_DESTROY = 21,
     SYS_PTHREAD_KEY_CREATE = 22,
     SYS_PTHREAD_KEY_DELETE = 23,
     SYS_POLL = 24,
@@ -54,4 +39,39 @@ n! {
     SYS_LSEEK = 39,
     SYS_CLOSE = 40,
     SYS_MMAP = 41,
+    SYS_MOUNT = 42,
+    SYS_UMOUNT2 = 43,
+    SYS_IOCTL = 44,
+    SYS_GETRANDOM = 45,
+    SYS_SYSLOG = 46,
+    SYS_SOCKET = 47,
+    SYS_BIND = 48,
+    SYS_LISTEN = 49,
+    SYS_ACCEPT = 50,
+    SYS_CONNECT = 51,
+    SYS_SENDTO = 52,
+    SYS_RECVFROM = 53,
+    SYS_SHUTDOWN = 54,
+    SYS_SETSOCKOPT = 55,
+    SYS_GETSOCKOPT = 56,
+    SYS_GETSOCKNAME = 57,
+    SYS_GETPEERNAME = 58,
+    SYS_SOCKETPAIR = 59,
+    SYS_SENDMSG = 60,
+    SYS_RECVMSG = 61,
+    SYS_PPOLL = 62,
+    SYS_SELECT = 63,
+    SYS_EPOLL_CREATE1 = 64,
+    SYS_EPOLL_CTL = 65,
+    SYS_EPOLL_WAIT = 66,
+    SYS_FUTEX = 67,
+    SYS_CLONE = 68,
+    SYS_EXIT_GROUP = 69,
+    SYS_SCHED_SETAFFINITY = 70,
+    SYS_SCHED_GETAFFINITY = 71,
+    SYS_SCHED_YIELD = 72,
+    SYS_SCHED_SETSCHEDULER = 73,
+    SYS_SCHED_GETSCHEDULER = 74,
+    SYS_SETPRIORITY = 75,
+    SYS_GETPRIORITY = 76,
 }
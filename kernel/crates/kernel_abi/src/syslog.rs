@@ -0,0 +1,34 @@
+//! Actions of `syslog(2)`.
+
+/// Does nothing.
+pub const SYSLOG_ACTION_CLOSE: i32 = 0;
+/// Does nothing.
+pub const SYSLOG_ACTION_OPEN: i32 = 1;
+/// Reads records that weren't read with this action before, waiting for
+/// new ones if there are none.
+pub const SYSLOG_ACTION_READ: i32 = 2;
+/// Reads the newest records since the log was last cleared.
+pub const SYSLOG_ACTION_READ_ALL: i32 = 3;
+/// Like [`SYSLOG_ACTION_READ_ALL`], then clears the log.
+pub const SYSLOG_ACTION_READ_CLEAR: i32 = 4;
+/// Hides the current records from [`SYSLOG_ACTION_READ_ALL`].
+pub const SYSLOG_ACTION_CLEAR: i32 = 5;
+/// Stops printing records on the console.
+pub const SYSLOG_ACTION_CONSOLE_OFF: i32 = 6;
+/// Undoes [`SYSLOG_ACTION_CONSOLE_OFF`].
+pub const SYSLOG_ACTION_CONSOLE_ON: i32 = 7;
+/// Sets the level from which records are printed on the console. Records
+/// with a priority lower than the level, which is between 1 and 8, are
+/// printed.
+pub const SYSLOG_ACTION_CONSOLE_LEVEL: i32 = 8;
+/// Returns the number of bytes that [`SYSLOG_ACTION_READ`] would return.
+pub const SYSLOG_ACTION_SIZE_UNREAD: i32 = 9;
+/// Returns the size of the log buffer.
+pub const SYSLOG_ACTION_SIZE_BUFFER: i32 = 10;
+
+/// Sets which records are logged at all, from a filter like
+/// `info,kernel::driver=trace` in the buffer. This is specific to MuffinOS.
+pub const SYSLOG_ACTION_SET_FILTER: i32 = 0x100;
+/// Reads the current filter in the form of [`SYSLOG_ACTION_SET_FILTER`].
+/// This is specific to MuffinOS.
+pub const SYSLOG_ACTION_GET_FILTER: i32 = 0x101;
@@ -0,0 +1,99 @@
+//! The terminal attributes that are read and written with the `TCGETS` and
+//! `TCSETS` ioctls. The layout and the flag values are the same as on Linux.
+
+use core::ffi::c_uint;
+
+pub type TcFlag = c_uint;
+pub type Cc = u8;
+
+pub const NCCS: usize = 19;
+
+// indices into `Termios::c_cc`
+pub const VINTR: usize = 0;
+pub const VQUIT: usize = 1;
+pub const VERASE: usize = 2;
+pub const VKILL: usize = 3;
+pub const VEOF: usize = 4;
+pub const VTIME: usize = 5;
+pub const VMIN: usize = 6;
+pub const VSTART: usize = 8;
+pub const VSTOP: usize = 9;
+pub const VSUSP: usize = 10;
+pub const VEOL: usize = 11;
+
+// input flags
+pub const IGNBRK: TcFlag = 0o000_001;
+pub const BRKINT: TcFlag = 0o000_002;
+pub const INLCR: TcFlag = 0o000_100;
+pub const IGNCR: TcFlag = 0o000_200;
+pub const ICRNL: TcFlag = 0o000_400;
+pub const IXON: TcFlag = 0o002_000;
+
+// output flags
+pub const OPOST: TcFlag = 0o000_001;
+pub const ONLCR: TcFlag = 0o000_004;
+
+// control flags
+pub const B38400: TcFlag = 0o000_017;
+pub const CS8: TcFlag = 0o000_060;
+pub const CREAD: TcFlag = 0o000_200;
+pub const HUPCL: TcFlag = 0o002_000;
+
+// local flags
+pub const ISIG: TcFlag = 0o000_001;
+pub const ICANON: TcFlag = 0o000_002;
+pub const ECHO: TcFlag = 0o000_010;
+pub const ECHOE: TcFlag = 0o000_020;
+pub const ECHOK: TcFlag = 0o000_040;
+pub const ECHONL: TcFlag = 0o000_100;
+pub const NOFLSH: TcFlag = 0o000_200;
+pub const ECHOCTL: TcFlag = 0o001_000;
+pub const ECHOKE: TcFlag = 0o004_000;
+pub const IEXTEN: TcFlag = 0o100_000;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Termios {
+    pub c_iflag: TcFlag,
+    pub c_oflag: TcFlag,
+    pub c_cflag: TcFlag,
+    pub c_lflag: TcFlag,
+    pub c_line: Cc,
+    pub c_cc: [Cc; NCCS],
+}
+
+impl Default for Termios {
+    /// The attributes of a freshly opened terminal: canonical mode with echo
+    /// and signal characters enabled.
+    fn default() -> Self {
+        let mut c_cc = [0; NCCS];
+        c_cc[VINTR] = 0x03; // ^C
+        c_cc[VQUIT] = 0x1c; // ^\
+        c_cc[VERASE] = 0x7f; // DEL
+        c_cc[VKILL] = 0x15; // ^U
+        c_cc[VEOF] = 0x04; // ^D
+        c_cc[VMIN] = 1;
+        c_cc[VSTART] = 0x11; // ^Q
+        c_cc[VSTOP] = 0x13; // ^S
+        c_cc[VSUSP] = 0x1a; // ^Z
+
+        Self {
+            c_iflag: ICRNL | IXON,
+            c_oflag: OPOST | ONLCR,
+            c_cflag: B38400 | CS8 | CREAD | HUPCL,
+            c_lflag: ISIG | ICANON | ECHO | ECHOE | ECHOK | ECHOCTL | ECHOKE | IEXTEN,
+            c_line: 0,
+            c_cc,
+        }
+    }
+}
+
+/// The size of a terminal window, see `TIOCGWINSZ` and `TIOCSWINSZ`.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Winsize {
+    pub ws_row: u16,
+    pub ws_col: u16,
+    pub ws_xpixel: u16,
+    pub ws_ypixel: u16,
+}
@@ -0,0 +1,371 @@
+//! Parsing of the kernel command line into a typed [`BootConfig`].
+//!
+//! The command line is a whitespace separated list of `key=value` pairs and
+//! flags. Values may be enclosed in double quotes to contain whitespace.
+#![no_std]
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use kernel_device::partition::{Guid, PartitionSelector};
+use kernel_vfs::path::AbsoluteOwnedPath;
+use log::LevelFilter;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ParseError {
+    #[error("option {0} requires a value")]
+    MissingValue(&'static str),
+    #[error("invalid value for {0}")]
+    InvalidValue(&'static str),
+    #[error("unterminated quote")]
+    UnterminatedQuote,
+}
+
+/// The device that the root file system is mounted from.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RootSource {
+    /// `/dev/blkN` or `/dev/blkNpM`.
+    Device { disk: u64, partition: Option<usize> },
+    /// `PARTUUID=<guid>` or `PARTLABEL=<label>`, only GPT partitions carry these.
+    Partition(PartitionSelector),
+    /// `initramfs`, the archive that the bootloader loaded as a module.
+    Initramfs,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct BootConfig {
+    /// `root=`, the device to mount at `/`.
+    pub root: Option<RootSource>,
+    /// `rootfstype=`, the file system driver used for the root device.
+    pub root_fs_type: Option<String>,
+    /// `init=`, the executable that is started as the first process.
+    pub init: Option<AbsoluteOwnedPath>,
+    /// `loglevel=`, either a level name (`off`, `error`, ..., `trace`) or
+    /// the corresponding number `0`-`5`.
+    pub log_level: Option<LevelFilter>,
+    /// `ro` or `rw`, the last one wins.
+    pub read_only: bool,
+    /// Options that the kernel doesn't know about, in the order of their
+    /// appearance.
+    pub unknown: Vec<String>,
+}
+
+impl BootConfig {
+    pub const DEFAULT_INIT: &'static str = "/bin/init";
+
+    /// # Errors
+    /// Returns an error if a known option has a missing or malformed value,
+    /// or if a quote isn't terminated.
+    pub fn parse(cmdline: &str) -> Result<Self, ParseError> {
+        let mut config = Self::default();
+        for option in tokenize(cmdline)? {
+            let (key, value) = match option.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (option.as_str(), None),
+            };
+
+            match key {
+                "root" => {
+                    config.root = Some(value.ok_or(ParseError::MissingValue("root"))?.parse()?);
+                }
+                "rootfstype" => {
+                    config.root_fs_type = Some(
+                        value
+                            .filter(|v| !v.is_empty())
+                            .ok_or(ParseError::MissingValue("rootfstype"))?
+                            .to_string(),
+                    );
+                }
+                "init" => {
+                    let value = value.ok_or(ParseError::MissingValue("init"))?;
+                    config.init = Some(
+                        AbsoluteOwnedPath::try_from(value)
+                            .map_err(|_| ParseError::InvalidValue("init"))?,
+                    );
+                }
+                "loglevel" => {
+                    config.log_level = Some(parse_log_level(
+                        value.ok_or(ParseError::MissingValue("loglevel"))?,
+                    )?);
+                }
+                "ro" if value.is_none() => config.read_only = true,
+                "rw" if value.is_none() => config.read_only = false,
+                _ => config.unknown.push(option),
+            }
+        }
+        Ok(config)
+    }
+
+    /// The path of the init executable, [`Self::DEFAULT_INIT`] if none
+    /// was given.
+    #[must_use]
+    pub fn init_path(&self) -> &str {
+        self.init
+            .as_ref()
+            .map_or(Self::DEFAULT_INIT, |path| path.as_str())
+    }
+}
+
+impl FromStr for RootSource {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        const INVALID: ParseError = ParseError::InvalidValue("root");
+
+        if value == "initramfs" {
+            return Ok(Self::Initramfs);
+        }
+        if let Some(guid) = value.strip_prefix("PARTUUID=") {
+            let guid = Guid::from_str(guid).map_err(|_| INVALID)?;
+            return Ok(Self::Partition(PartitionSelector::Guid(guid)));
+        }
+        if let Some(label) = value.strip_prefix("PARTLABEL=") {
+            if label.is_empty() {
+                return Err(INVALID);
+            }
+            return Ok(Self::Partition(PartitionSelector::Label(label.to_string())));
+        }
+
+        let device = value.strip_prefix("/dev/blk").ok_or(INVALID)?;
+        let (disk, partition) = match device.split_once('p') {
+            Some((disk, partition)) => (disk, Some(partition)),
+            None => (device, None),
+        };
+        Ok(Self::Device {
+            disk: parse_number(disk).ok_or(INVALID)?,
+            partition: partition
+                .map(|p| parse_number(p).filter(|&p| p > 0).ok_or(INVALID))
+                .transpose()?,
+        })
+    }
+}
+
+impl Display for RootSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Device {
+                disk,
+                partition: None,
+            } => write!(f, "/dev/blk{disk}"),
+            Self::Device {
+                disk,
+                partition: Some(partition),
+            } => write!(f, "/dev/blk{disk}p{partition}"),
+            Self::Partition(PartitionSelector::Guid(guid)) => write!(f, "PARTUUID={guid}"),
+            Self::Partition(PartitionSelector::Label(label)) => write!(f, "PARTLABEL={label}"),
+            Self::Initramfs => write!(f, "initramfs"),
+        }
+    }
+}
+
+/// Parses a decimal number without sign or leading `+`.
+fn parse_number<T: FromStr>(s: &str) -> Option<T> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn parse_log_level(value: &str) -> Result<LevelFilter, ParseError> {
+    if let Some(level) = parse_number::<usize>(value) {
+        return LevelFilter::iter()
+            .nth(level)
+            .ok_or(ParseError::InvalidValue("loglevel"));
+    }
+    LevelFilter::from_str(value).map_err(|_| ParseError::InvalidValue("loglevel"))
+}
+
+/// Splits the command line at whitespace, honoring double quotes. The
+/// quotes themselves are removed.
+fn tokenize(cmdline: &str) -> Result<Vec<String>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut in_quotes = false;
+
+    for c in cmdline.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_token = true;
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if in_token {
+                    tokens.push(core::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(ParseError::UnterminatedQuote);
+    }
+    if in_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(Ok(BootConfig::default()), BootConfig::parse(""));
+        assert_eq!(Ok(BootConfig::default()), BootConfig::parse("  \t "));
+        assert_eq!("/bin/init", BootConfig::default().init_path());
+    }
+
+    #[test]
+    fn test_full() {
+        let config = BootConfig::parse(
+            "root=/dev/blk1p2 rootfstype=ext2 init=/sbin/init loglevel=debug ro quiet foo=bar",
+        )
+        .unwrap();
+        assert_eq!(
+            BootConfig {
+                root: Some(RootSource::Device {
+                    disk: 1,
+                    partition: Some(2)
+                }),
+                root_fs_type: Some("ext2".into()),
+                init: Some(AbsoluteOwnedPath::try_from("/sbin/init").unwrap()),
+                log_level: Some(LevelFilter::Debug),
+                read_only: true,
+                unknown: vec!["quiet".into(), "foo=bar".into()],
+            },
+            config
+        );
+        assert_eq!("/sbin/init", config.init_path());
+    }
+
+    #[test]
+    fn test_root() {
+        for (input, expected) in [
+            (
+                "root=/dev/blk0",
+                RootSource::Device {
+                    disk: 0,
+                    partition: None,
+                },
+            ),
+            (
+                "root=/dev/blk12p7",
+                RootSource::Device {
+                    disk: 12,
+                    partition: Some(7),
+                },
+            ),
+            (
+                "root=PARTUUID=6a1b8f4e-2c3d-4e5f-8a9b-0c1d2e3f4a5b",
+                RootSource::Partition(PartitionSelector::Guid(
+                    "6a1b8f4e-2c3d-4e5f-8a9b-0c1d2e3f4a5b".parse().unwrap(),
+                )),
+            ),
+            (
+                "root=\"PARTLABEL=muffin root\"",
+                RootSource::Partition(PartitionSelector::Label("muffin root".into())),
+            ),
+            ("root=initramfs", RootSource::Initramfs),
+        ] {
+            assert_eq!(
+                Some(expected),
+                BootConfig::parse(input).unwrap().root,
+                "{input}"
+            );
+        }
+
+        for input in [
+            "/dev/blk3",
+            "/dev/blk0p1",
+            "PARTUUID=6a1b8f4e-2c3d-4e5f-8a9b-0c1d2e3f4a5b",
+            "PARTLABEL=muffin",
+            "initramfs",
+        ] {
+            assert_eq!(input, input.parse::<RootSource>().unwrap().to_string());
+        }
+
+        for input in [
+            "root",
+            "root=",
+            "root=/dev/sda",
+            "root=/dev/blk",
+            "root=/dev/blk0p",
+            "root=/dev/blk0p0",
+            "root=/dev/blk+1",
+            "root=PARTUUID=nope",
+            "root=PARTLABEL=",
+        ] {
+            assert!(BootConfig::parse(input).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_log_level() {
+        for (input, expected) in [
+            ("loglevel=0", LevelFilter::Off),
+            ("loglevel=3", LevelFilter::Info),
+            ("loglevel=5", LevelFilter::Trace),
+            ("loglevel=warn", LevelFilter::Warn),
+            ("loglevel=TRACE", LevelFilter::Trace),
+        ] {
+            assert_eq!(
+                Some(expected),
+                BootConfig::parse(input).unwrap().log_level,
+                "{input}"
+            );
+        }
+        assert_eq!(
+            Err(ParseError::InvalidValue("loglevel")),
+            BootConfig::parse("loglevel=6")
+        );
+        assert_eq!(
+            Err(ParseError::InvalidValue("loglevel")),
+            BootConfig::parse("loglevel=loud")
+        );
+    }
+
+    #[test]
+    fn test_ro_rw_last_wins() {
+        assert!(BootConfig::parse("rw ro").unwrap().read_only);
+        assert!(!BootConfig::parse("ro rw").unwrap().read_only);
+        // not a flag if it carries a value
+        assert_eq!(
+            vec!["ro=1".to_string()],
+            BootConfig::parse("ro=1").unwrap().unknown
+        );
+    }
+
+    #[test]
+    fn test_invalid_init() {
+        assert_eq!(
+            Err(ParseError::InvalidValue("init")),
+            BootConfig::parse("init=bin/init")
+        );
+        assert_eq!(
+            Err(ParseError::MissingValue("init")),
+            BootConfig::parse("init")
+        );
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            vec!["a=b c".to_string(), "d".into(), String::new()],
+            tokenize("  a=\"b c\"  d \"\"").unwrap()
+        );
+        assert_eq!(Err(ParseError::UnterminatedQuote), tokenize("a=\"b"));
+    }
+}
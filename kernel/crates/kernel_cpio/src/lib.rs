@@ -0,0 +1,53 @@
+//! Reading and writing of cpio archives in the "new ASCII" (newc) format,
+//! which is the format used for initramfs images.
+//!
+//! Every entry consists of a 110 byte header, the NUL terminated name and
+//! the data. The name and the data are each padded to a multiple of four
+//! bytes. The archive ends with an entry named [`TRAILER`].
+#![no_std]
+extern crate alloc;
+
+mod read;
+mod write;
+
+pub use read::*;
+pub use write::*;
+
+/// The magic number at the start of every newc header.
+pub const MAGIC: &[u8; 6] = b"070701";
+
+/// The name of the entry that marks the end of the archive.
+pub const TRAILER: &str = "TRAILER!!!";
+
+const HEADER_LEN: usize = 110;
+
+pub const MODE_TYPE_MASK: u32 = 0o170_000;
+pub const MODE_DIRECTORY: u32 = 0o040_000;
+pub const MODE_REGULAR_FILE: u32 = 0o100_000;
+pub const MODE_SYMLINK: u32 = 0o120_000;
+
+/// The type of an entry, as encoded in the upper bits of its mode.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EntryType {
+    Directory,
+    RegularFile,
+    Symlink,
+    /// Device files, FIFOs and sockets.
+    Other,
+}
+
+impl EntryType {
+    #[must_use]
+    pub fn from_mode(mode: u32) -> Self {
+        match mode & MODE_TYPE_MASK {
+            MODE_DIRECTORY => Self::Directory,
+            MODE_REGULAR_FILE => Self::RegularFile,
+            MODE_SYMLINK => Self::Symlink,
+            _ => Self::Other,
+        }
+    }
+}
+
+fn align4(n: usize) -> usize {
+    n.next_multiple_of(4)
+}
@@ -0,0 +1,150 @@
+use thiserror::Error;
+
+use crate::{EntryType, HEADER_LEN, MAGIC, TRAILER, align4};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum ReadError {
+    #[error("unexpected end of archive")]
+    UnexpectedEof,
+    #[error("invalid magic number")]
+    InvalidMagic,
+    #[error("invalid header field")]
+    InvalidHeader,
+    #[error("entry name is not valid UTF-8")]
+    InvalidName,
+}
+
+/// A single entry of a cpio archive, borrowing its name and data from the
+/// archive.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Entry<'a> {
+    pub name: &'a str,
+    pub ino: u32,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub nlink: u32,
+    pub mtime: u32,
+    /// The contents of a regular file, or the target of a symlink.
+    pub data: &'a [u8],
+}
+
+impl Entry<'_> {
+    #[must_use]
+    pub fn entry_type(&self) -> EntryType {
+        EntryType::from_mode(self.mode)
+    }
+
+    /// The permission bits of the mode, without the file type.
+    #[must_use]
+    pub fn permissions(&self) -> u32 {
+        self.mode & 0o7777
+    }
+}
+
+/// A cpio newc archive in memory.
+#[derive(Debug, Copy, Clone)]
+pub struct Archive<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Archive<'a> {
+    #[must_use]
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Returns an iterator over the entries of the archive, not including
+    /// the trailer. The iterator ends after the first error.
+    #[must_use]
+    pub fn entries(&self) -> Entries<'a> {
+        Entries {
+            data: self.data,
+            done: false,
+        }
+    }
+}
+
+pub struct Entries<'a> {
+    data: &'a [u8],
+    done: bool,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = Result<Entry<'a>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_entry() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl<'a> Entries<'a> {
+    fn read_entry(&mut self) -> Result<Option<Entry<'a>>, ReadError> {
+        let header = self
+            .data
+            .get(..HEADER_LEN)
+            .ok_or(ReadError::UnexpectedEof)?;
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(ReadError::InvalidMagic);
+        }
+        let field = |index: usize| {
+            let start = MAGIC.len() + index * 8;
+            parse_hex(&header[start..start + 8])
+        };
+
+        let file_size = field(6)? as usize;
+        let name_size = field(11)? as usize;
+        if name_size == 0 {
+            return Err(ReadError::InvalidHeader);
+        }
+
+        let name_end = HEADER_LEN + name_size;
+        let name = self
+            .data
+            .get(HEADER_LEN..name_end - 1)
+            .ok_or(ReadError::UnexpectedEof)?;
+        let name = core::str::from_utf8(name).map_err(|_| ReadError::InvalidName)?;
+
+        let data_start = align4(name_end);
+        let data_end = data_start + file_size;
+        let data = self
+            .data
+            .get(data_start..data_end)
+            .ok_or(ReadError::UnexpectedEof)?;
+
+        if name == TRAILER {
+            return Ok(None);
+        }
+
+        let entry = Entry {
+            name,
+            ino: field(0)?,
+            mode: field(1)?,
+            uid: field(2)?,
+            gid: field(3)?,
+            nlink: field(4)?,
+            mtime: field(5)?,
+            data,
+        };
+        self.data = self.data.get(align4(data_end)..).unwrap_or_default();
+        Ok(Some(entry))
+    }
+}
+
+fn parse_hex(field: &[u8]) -> Result<u32, ReadError> {
+    let s = core::str::from_utf8(field).map_err(|_| ReadError::InvalidHeader)?;
+    u32::from_str_radix(s, 16).map_err(|_| ReadError::InvalidHeader)
+}
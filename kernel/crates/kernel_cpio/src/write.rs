@@ -0,0 +1,141 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::{HEADER_LEN, MAGIC, MODE_DIRECTORY, MODE_REGULAR_FILE, MODE_SYMLINK, TRAILER, align4};
+
+/// Builds a cpio newc archive in memory. Entries are written in the order
+/// in which they are added, so directories must be added before their
+/// contents. Inode numbers are assigned sequentially.
+#[derive(Debug, Default)]
+pub struct ArchiveBuilder {
+    data: Vec<u8>,
+    next_ino: u32,
+}
+
+impl ArchiveBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a directory. `name` is the path relative to the archive root,
+    /// without a leading `/`.
+    pub fn add_directory(&mut self, name: &str, permissions: u32) -> &mut Self {
+        self.add_entry(name, MODE_DIRECTORY | permissions, 2, &[])
+    }
+
+    pub fn add_file(&mut self, name: &str, permissions: u32, data: &[u8]) -> &mut Self {
+        self.add_entry(name, MODE_REGULAR_FILE | permissions, 1, data)
+    }
+
+    pub fn add_symlink(&mut self, name: &str, target: &str) -> &mut Self {
+        self.add_entry(name, MODE_SYMLINK | 0o777, 1, target.as_bytes())
+    }
+
+    /// Appends the trailer and returns the archive.
+    #[must_use]
+    pub fn finish(mut self) -> Vec<u8> {
+        self.write_header(0, 0, 1, 0, TRAILER);
+        self.data
+    }
+
+    fn add_entry(&mut self, name: &str, mode: u32, nlink: u32, data: &[u8]) -> &mut Self {
+        self.next_ino += 1;
+        self.write_header(self.next_ino, mode, nlink, data.len(), name);
+        self.data.extend_from_slice(data);
+        self.pad();
+        self
+    }
+
+    fn write_header(&mut self, ino: u32, mode: u32, nlink: u32, file_size: usize, name: &str) {
+        let file_size = u32::try_from(file_size).expect("cpio entries are limited to 4 GiB");
+        let name_size = u32::try_from(name.len() + 1).expect("name should fit into u32");
+
+        let mut header = String::with_capacity(HEADER_LEN);
+        header.push_str(core::str::from_utf8(MAGIC).unwrap());
+        // ino, mode, uid, gid, nlink, mtime, filesize, devmajor, devminor,
+        // rdevmajor, rdevminor, namesize, check
+        for value in [
+            ino, mode, 0, 0, nlink, 0, file_size, 0, 0, 0, 0, name_size, 0,
+        ] {
+            let _ = write!(header, "{value:08x}");
+        }
+        self.data.extend_from_slice(header.as_bytes());
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.push(0);
+        self.pad();
+    }
+
+    fn pad(&mut self) {
+        self.data.resize(align4(self.data.len()), 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use crate::{Archive, ArchiveBuilder, EntryType, ReadError};
+
+    #[test]
+    fn test_roundtrip() {
+        let mut builder = ArchiveBuilder::new();
+        builder
+            .add_directory("bin", 0o755)
+            .add_file("bin/init", 0o755, b"\x7fELF")
+            .add_file("empty", 0o644, &[])
+            .add_symlink("sbin", "bin");
+        let data = builder.finish();
+        assert_eq!(0, data.len() % 4);
+
+        let entries = Archive::new(&data)
+            .entries()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                ("bin", EntryType::Directory, 0o755, &b""[..]),
+                ("bin/init", EntryType::RegularFile, 0o755, &b"\x7fELF"[..]),
+                ("empty", EntryType::RegularFile, 0o644, &b""[..]),
+                ("sbin", EntryType::Symlink, 0o777, &b"bin"[..]),
+            ],
+            entries
+                .iter()
+                .map(|e| (e.name, e.entry_type(), e.permissions(), e.data))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![1, 2, 3, 4],
+            entries.iter().map(|e| e.ino).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_empty_archive() {
+        let data = ArchiveBuilder::new().finish();
+        assert_eq!(0, Archive::new(&data).entries().count());
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let mut data = ArchiveBuilder::new().finish();
+        data[0] = b'1';
+        assert_eq!(
+            vec![Err(ReadError::InvalidMagic)],
+            Archive::new(&data).entries().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut builder = ArchiveBuilder::new();
+        builder.add_file("file", 0o644, b"hello world");
+        let data = builder.finish();
+        assert_eq!(
+            vec![Err(ReadError::UnexpectedEof)],
+            Archive::new(&data[..120]).entries().collect::<Vec<_>>()
+        );
+    }
+}
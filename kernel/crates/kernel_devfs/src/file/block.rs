@@ -1,8 +1,11 @@
+use core::ffi::c_int;
 use core::marker::PhantomData;
 
+use kernel_abi::{BLKGETSIZE64, BLKSSZGET};
 use kernel_device::DeviceId;
 use kernel_device::block::{BlockBuf, BlockDevice};
-use kernel_vfs::{ReadError, Stat, StatError, WriteError};
+use kernel_syscall::ioctl::write_arg;
+use kernel_vfs::{FileType, IoctlError, ReadError, Stat, StatError, WriteError};
 
 use crate::DevFile;
 
@@ -182,9 +185,25 @@ where
     }
 
     fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
-        *stat = Stat { size: stat.size };
+        stat.file_type = FileType::BlockDevice;
         Ok(())
     }
+
+    fn ioctl(&mut self, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        match request {
+            BLKGETSIZE64 => write_arg(arg, (self.device.block_count() * N) as u64)?,
+            BLKSSZGET => write_arg(
+                arg,
+                c_int::try_from(N).map_err(|_| IoctlError::Unsupported)?,
+            )?,
+            _ => return Err(IoctlError::Unsupported),
+        }
+        Ok(0)
+    }
+
+    fn file_type() -> FileType {
+        FileType::BlockDevice
+    }
 }
 
 #[cfg(test)]
@@ -529,4 +548,24 @@ mod tests {
         assert_eq!(bytes_read, 0);
         assert!(buf.is_empty());
     }
+
+    #[test]
+    fn test_ioctl() {
+        let device = TestBlockDevice::<512> {
+            id: TestDeviceId(1),
+            data: vec![0; 512 * 3],
+        };
+        let mut file = BlockDeviceFile::new(device);
+
+        let mut size = 0_u64;
+        assert_eq!(Ok(0), file.ioctl(BLKGETSIZE64, &raw mut size as usize));
+        assert_eq!(512 * 3, size);
+
+        let mut sector_size: c_int = 0;
+        assert_eq!(Ok(0), file.ioctl(BLKSSZGET, &raw mut sector_size as usize));
+        assert_eq!(512, sector_size);
+
+        assert_eq!(Err(IoctlError::BadAddress), file.ioctl(BLKGETSIZE64, 0));
+        assert_eq!(Err(IoctlError::Unsupported), file.ioctl(0x5401, 0));
+    }
 }
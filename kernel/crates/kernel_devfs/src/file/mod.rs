@@ -1,11 +1,13 @@
-use kernel_vfs::{ReadError, Stat, StatError, WriteError};
+use core::task::Waker;
+
+use kernel_vfs::{
+    FileType, IoctlError, MmapError, ReadError, Readiness, Stat, StatError, WriteError,
+};
 
 mod block;
 pub use block::*;
 mod null;
 pub use null::*;
-mod serial;
-pub use serial::*;
 mod zero;
 pub use zero::*;
 
@@ -13,4 +15,39 @@ pub trait DevFile: Send + Sync {
     fn read(&mut self, buf: &mut [u8], offset: usize) -> Result<usize, ReadError>;
     fn write(&mut self, buf: &[u8], offset: usize) -> Result<usize, WriteError>;
     fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError>;
+
+    /// Performs a device specific operation, see
+    /// [`FileSystem::ioctl`](kernel_vfs::fs::FileSystem::ioctl).
+    fn ioctl(&mut self, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        let _ = (request, arg);
+        Err(IoctlError::Unsupported)
+    }
+
+    /// Returns the physical address of device memory, see
+    /// [`FileSystem::mmap`](kernel_vfs::fs::FileSystem::mmap).
+    fn mmap(&mut self, offset: usize, len: usize) -> Result<u64, MmapError> {
+        let _ = (offset, len);
+        Err(MmapError::Unsupported)
+    }
+
+    /// Returns whether the device can be read or written without blocking,
+    /// see [`FileSystem::poll`](kernel_vfs::fs::FileSystem::poll).
+    fn poll(&mut self) -> Readiness {
+        Readiness::READY
+    }
+
+    /// Wakes `waker` the next time that the result of [`poll`](Self::poll)
+    /// may change, see
+    /// [`FileSystem::register_waker`](kernel_vfs::fs::FileSystem::register_waker).
+    fn register_waker(&mut self, waker: &Waker) {
+        let _ = waker;
+    }
+
+    /// The type of the file as it is listed in its directory.
+    fn file_type() -> FileType
+    where
+        Self: Sized,
+    {
+        FileType::CharDevice
+    }
 }
@@ -1,4 +1,4 @@
-use kernel_vfs::{ReadError, Stat, StatError, WriteError};
+use kernel_vfs::{FileType, ReadError, Stat, StatError, WriteError};
 
 use crate::DevFile;
 
@@ -15,6 +15,7 @@ impl DevFile for Null {
     }
 
     fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.file_type = FileType::CharDevice;
         stat.size = 0;
         Ok(())
     }
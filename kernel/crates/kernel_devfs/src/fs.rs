@@ -1,12 +1,18 @@
+use alloc::borrow::ToOwned;
 use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
-
-use kernel_vfs::fs::{FileSystem, FsHandle};
-use kernel_vfs::path::{AbsolutePath, ROOT};
-use kernel_vfs::{CloseError, FsError, OpenError, ReadError, Stat, StatError, WriteError};
+use core::task::Waker;
+
+use kernel_vfs::fs::{DirEntry, FileSystem, FsHandle};
+use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
+use kernel_vfs::{
+    CloseError, FileType, FsError, IoctlError, MmapError, OpenError, ReadDirError, ReadError,
+    Readiness, Stat, StatError, WriteError,
+};
 use thiserror::Error;
 
 use crate::node::{DevDirectoryNode, DevFileNode, DevNode, DevNodeKind};
@@ -28,6 +34,12 @@ pub enum ResolveError {
     ParentNotDirectory,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum UnregisterError {
+    #[error("there is no file at the specified path")]
+    NotFound,
+}
+
 impl From<ResolveError> for OpenError {
     fn from(_: ResolveError) -> Self {
         OpenError::NotFound
@@ -36,7 +48,14 @@ impl From<ResolveError> for OpenError {
 
 pub struct DevFs {
     root: DevNode,
-    open_files: BTreeMap<FsHandle, Box<dyn DevFile>>,
+    open_files: BTreeMap<FsHandle, OpenNode>,
+}
+
+enum OpenNode {
+    File(Box<dyn DevFile>),
+    /// Directories are resolved again on every access, so that files that
+    /// were registered after opening are visible.
+    Directory(AbsoluteOwnedPath),
 }
 
 impl Default for DevFs {
@@ -47,13 +66,7 @@ impl Default for DevFs {
 
 impl DevFs {
     pub fn new() -> Self {
-        let mut v = Self {
-            root: DevNode::new(
-                String::from("/"),
-                DevNodeKind::Directory(DevDirectoryNode::new()),
-            ),
-            open_files: BTreeMap::new(),
-        };
+        let mut v = Self::empty();
 
         fn setup(v: &mut DevFs) -> Result<(), RegisterError> {
             v.register_file(AbsolutePath::try_new("/null").unwrap(), || Ok(Null))?;
@@ -65,6 +78,17 @@ impl DevFs {
         v
     }
 
+    /// Creates a device file system without the default files.
+    pub fn empty() -> Self {
+        Self {
+            root: DevNode::new(
+                String::from("/"),
+                DevNodeKind::Directory(DevDirectoryNode::new()),
+            ),
+            open_files: BTreeMap::new(),
+        }
+    }
+
     pub fn register_file<O, F>(
         &mut self,
         path: &AbsolutePath,
@@ -74,6 +98,26 @@ impl DevFs {
         O: Fn() -> Result<F, OpenError> + Send + Sync + 'static,
         F: DevFile + 'static,
     {
+        self.register_node(
+            path,
+            DevNodeKind::File(DevFileNode::new(
+                F::file_type(),
+                Box::new(move || open_fn().map(|file| Box::new(file) as Box<dyn DevFile>)),
+            )),
+        )
+    }
+
+    /// Registers an empty directory, which can hold device files or serve as
+    /// a mount point.
+    pub fn register_directory(&mut self, path: &AbsolutePath) -> Result<(), RegisterError> {
+        self.register_node(path, DevNodeKind::Directory(DevDirectoryNode::new()))
+    }
+
+    fn register_node(
+        &mut self,
+        path: &AbsolutePath,
+        kind: DevNodeKind,
+    ) -> Result<(), RegisterError> {
         let parent = path.parent().unwrap_or(ROOT);
         let filename = path.file_name().ok_or(ResolveError::ParentNotFound)?;
 
@@ -87,13 +131,29 @@ impl DevFs {
             return Err(RegisterError::AlreadyExists);
         }
 
-        let file_node = DevNode::new(
-            filename.to_string(),
-            DevNodeKind::File(DevFileNode::new(Box::new(move || {
-                open_fn().map(|file| Box::new(file) as Box<dyn DevFile>)
-            }))),
-        );
-        parent_dir.children_mut().push(file_node);
+        parent_dir
+            .children_mut()
+            .push(DevNode::new(filename.to_string(), kind));
+        Ok(())
+    }
+
+    /// Removes the file or directory at `path`. Files that are open stay
+    /// usable until they are closed.
+    pub fn unregister(&mut self, path: &AbsolutePath) -> Result<(), UnregisterError> {
+        let parent = path.parent().unwrap_or(ROOT);
+        let filename = path.file_name().ok_or(UnregisterError::NotFound)?;
+
+        let parent_dir = self
+            .resolve_node_mut(parent)
+            .ok()
+            .and_then(|node| node.directory_mut())
+            .ok_or(UnregisterError::NotFound)?;
+        let children = parent_dir.children_mut();
+        let index = children
+            .iter()
+            .position(|node| node.name() == filename)
+            .ok_or(UnregisterError::NotFound)?;
+        children.remove(index);
         Ok(())
     }
 
@@ -134,7 +194,7 @@ impl DevFs {
         FsHandle::from(FS_COUNTER.fetch_add(1, Relaxed))
     }
 
-    fn resolve_handle(&mut self, handle: FsHandle) -> Result<&mut Box<dyn DevFile>, FsError> {
+    fn resolve_handle(&mut self, handle: FsHandle) -> Result<&mut OpenNode, FsError> {
         self.open_files
             .get_mut(&handle)
             .ok_or(FsError::InvalidHandle)
@@ -144,12 +204,12 @@ impl DevFs {
 impl FileSystem for DevFs {
     fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
         let node = self.resolve_node(path)?;
-        let file_node = node
-            .file()
-            .expect("should be regular file, opening directories is not yet supported");
-        let file = file_node.open_fn()()?;
+        let open_node = match node.file() {
+            Some(file_node) => OpenNode::File(file_node.open_fn()()?),
+            None => OpenNode::Directory(path.to_owned()),
+        };
         let handle = Self::new_fs_handle();
-        self.open_files.insert(handle, file);
+        self.open_files.insert(handle, open_node);
         Ok(handle)
     }
 
@@ -164,15 +224,81 @@ impl FileSystem for DevFs {
         buf: &mut [u8],
         offset: usize,
     ) -> Result<usize, ReadError> {
-        self.resolve_handle(handle)?.read(buf, offset)
+        match self.resolve_handle(handle)? {
+            OpenNode::File(file) => file.read(buf, offset),
+            OpenNode::Directory(_) => Err(ReadError::NotReadable),
+        }
     }
 
     fn write(&mut self, handle: FsHandle, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
-        self.resolve_handle(handle)?.write(buf, offset)
+        match self.resolve_handle(handle)? {
+            OpenNode::File(file) => file.write(buf, offset),
+            OpenNode::Directory(_) => Err(WriteError::NotWritable),
+        }
     }
 
     fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
-        self.resolve_handle(handle)?.stat(stat)
+        match self.resolve_handle(handle)? {
+            OpenNode::File(file) => file.stat(stat),
+            OpenNode::Directory(_) => {
+                *stat = Stat {
+                    file_type: FileType::Directory,
+                    ..Stat::default()
+                };
+                Ok(())
+            }
+        }
+    }
+
+    fn read_dir(&mut self, handle: FsHandle) -> Result<Vec<DirEntry>, ReadDirError> {
+        let OpenNode::Directory(path) = self.resolve_handle(handle)? else {
+            return Err(ReadDirError::NotADirectory);
+        };
+        let path = path.clone();
+        let directory = self
+            .resolve_node(path.as_ref())
+            .ok()
+            .and_then(|node| node.directory())
+            .ok_or(ReadDirError::ReadFailed)?;
+        Ok(directory
+            .children()
+            .iter()
+            .map(|child| DirEntry {
+                name: child.name().to_string(),
+                file_type: child.file_type(),
+            })
+            .collect())
+    }
+
+    fn ioctl(&mut self, handle: FsHandle, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        match self.resolve_handle(handle)? {
+            OpenNode::File(file) => file.ioctl(request, arg),
+            OpenNode::Directory(_) => Err(IoctlError::Unsupported),
+        }
+    }
+
+    fn mmap(&mut self, handle: FsHandle, offset: usize, len: usize) -> Result<u64, MmapError> {
+        match self.resolve_handle(handle)? {
+            OpenNode::File(file) => file.mmap(offset, len),
+            OpenNode::Directory(_) => Err(MmapError::Unsupported),
+        }
+    }
+
+    fn poll(&mut self, handle: FsHandle) -> Readiness {
+        match self.resolve_handle(handle) {
+            Ok(OpenNode::File(file)) => file.poll(),
+            Ok(OpenNode::Directory(_)) => Readiness::READY,
+            Err(_) => Readiness {
+                error: true,
+                ..Readiness::READY
+            },
+        }
+    }
+
+    fn register_waker(&mut self, handle: FsHandle, waker: &Waker) {
+        if let Ok(OpenNode::File(file)) = self.resolve_handle(handle) {
+            file.register_waker(waker);
+        }
     }
 }
 
@@ -266,6 +392,63 @@ mod tests {
         devfs.close(file).expect("should be able to close file");
     }
 
+    #[test]
+    fn test_read_dir() {
+        let mut devfs = DevFs::new();
+        let root = devfs.open(ROOT).expect("should be able to open root");
+
+        let mut stat = Stat::default();
+        devfs.stat(root, &mut stat).unwrap();
+        assert_eq!(FileType::Directory, stat.file_type);
+
+        let entries = devfs.read_dir(root).unwrap();
+        assert_eq!(
+            vec!["null", "zero"],
+            entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>()
+        );
+        assert!(entries.iter().all(|e| e.file_type == FileType::CharDevice));
+
+        // files registered after opening the directory are visible
+        devfs
+            .register_file(AbsolutePath::try_new("/testfile").unwrap(), || {
+                Ok(TestDevFile::new())
+            })
+            .unwrap();
+        assert_eq!(3, devfs.read_dir(root).unwrap().len());
+
+        let null = devfs.open(AbsolutePath::try_new("/null").unwrap()).unwrap();
+        assert_eq!(Err(ReadDirError::NotADirectory), devfs.read_dir(null));
+    }
+
+    #[test]
+    fn test_register_directory_unregister() {
+        let mut devfs = DevFs::empty();
+        let root = devfs.open(ROOT).unwrap();
+        assert!(devfs.read_dir(root).unwrap().is_empty());
+
+        let dir = AbsolutePath::try_new("/pts").unwrap();
+        let file = AbsolutePath::try_new("/pts/0").unwrap();
+        devfs.register_directory(dir).unwrap();
+        assert_eq!(
+            Err(RegisterError::AlreadyExists),
+            devfs.register_directory(dir)
+        );
+        devfs
+            .register_file(file, || Ok(TestDevFile::new()))
+            .unwrap();
+
+        let dir_handle = devfs.open(dir).unwrap();
+        assert_eq!(1, devfs.read_dir(dir_handle).unwrap().len());
+
+        // open files stay usable after unregistering
+        let file_handle = devfs.open(file).unwrap();
+        devfs.unregister(file).unwrap();
+        assert_eq!(Err(UnregisterError::NotFound), devfs.unregister(file));
+        assert_eq!(Err(OpenError::NotFound), devfs.open(file));
+        assert!(devfs.read_dir(dir_handle).unwrap().is_empty());
+        assert_eq!(Ok(2), devfs.write(file_handle, b"hi", 0));
+    }
+
     #[test]
     fn test_open_multiple() {
         let path = AbsolutePath::try_new("/testfile").unwrap();
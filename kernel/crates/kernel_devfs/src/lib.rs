@@ -4,7 +4,9 @@ extern crate alloc;
 mod file;
 
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::ops::Deref;
+use core::task::Waker;
 
 pub use file::*;
 use spin::RwLock;
@@ -12,9 +14,12 @@ mod fs;
 mod node;
 
 pub use fs::*;
-use kernel_vfs::fs::{FileSystem, FsHandle};
+use kernel_vfs::fs::{DirEntry, FileSystem, FsHandle};
 use kernel_vfs::path::AbsolutePath;
-use kernel_vfs::{CloseError, OpenError, ReadError, Stat, StatError, WriteError};
+use kernel_vfs::{
+    CloseError, IoctlError, MmapError, OpenError, ReadDirError, ReadError, Readiness, Stat,
+    StatError, WriteError,
+};
 
 #[derive(Clone)]
 pub struct ArcLockedDevFs {
@@ -27,6 +32,13 @@ impl ArcLockedDevFs {
             inner: Arc::new(RwLock::new(DevFs::new())),
         }
     }
+
+    /// See [`DevFs::empty`].
+    pub fn empty() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(DevFs::empty())),
+        }
+    }
 }
 
 impl Default for ArcLockedDevFs {
@@ -68,4 +80,24 @@ impl FileSystem for ArcLockedDevFs {
     fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
         self.inner.write().stat(handle, stat)
     }
+
+    fn read_dir(&mut self, handle: FsHandle) -> Result<Vec<DirEntry>, ReadDirError> {
+        self.inner.write().read_dir(handle)
+    }
+
+    fn ioctl(&mut self, handle: FsHandle, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        self.inner.write().ioctl(handle, request, arg)
+    }
+
+    fn mmap(&mut self, handle: FsHandle, offset: usize, len: usize) -> Result<u64, MmapError> {
+        self.inner.write().mmap(handle, offset, len)
+    }
+
+    fn poll(&mut self, handle: FsHandle) -> Readiness {
+        self.inner.write().poll(handle)
+    }
+
+    fn register_waker(&mut self, handle: FsHandle, waker: &Waker) {
+        self.inner.write().register_waker(handle, waker);
+    }
 }
@@ -3,7 +3,7 @@ use alloc::string::String;
 use alloc::vec::Vec;
 use core::ops::{Deref, DerefMut};
 
-use kernel_vfs::OpenError;
+use kernel_vfs::{FileType, OpenError};
 
 use crate::DevFile;
 
@@ -58,6 +58,13 @@ impl DevNodeKind {
         }
     }
 
+    pub fn file_type(&self) -> FileType {
+        match self {
+            DevNodeKind::Directory(_) => FileType::Directory,
+            DevNodeKind::File(file) => file.file_type(),
+        }
+    }
+
     pub fn file(&self) -> Option<&DevFileNode> {
         if let DevNodeKind::File(file) = self {
             Some(file)
@@ -92,25 +99,35 @@ impl DevDirectoryNode {
         self.children.iter_mut().find(|node| node.name() == name)
     }
 
+    pub fn children(&self) -> &[DevNode] {
+        &self.children
+    }
+
     pub fn children_mut(&mut self) -> &mut Vec<DevNode> {
         &mut self.children
     }
 }
 
 pub struct DevFileNode {
+    file_type: FileType,
     open_fn: Box<dyn Fn() -> Result<Box<dyn DevFile>, OpenError> + Send + Sync>,
 }
 
 impl DevFileNode {
-    pub fn new<F>(open_fn: F) -> Self
+    pub fn new<F>(file_type: FileType, open_fn: F) -> Self
     where
         F: Fn() -> Result<Box<dyn DevFile>, OpenError> + Send + Sync + 'static,
     {
         Self {
+            file_type,
             open_fn: Box::new(open_fn),
         }
     }
 
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
     pub fn open_fn(&self) -> &(dyn Fn() -> Result<Box<dyn DevFile>, OpenError> + Send + Sync) {
         &self.open_fn
     }
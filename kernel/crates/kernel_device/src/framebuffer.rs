@@ -0,0 +1,40 @@
+use thiserror::Error;
+
+use crate::DeviceId;
+use crate::raw::RawDevice;
+
+/// The layout of the memory of a [`FramebufferDevice`]. Each color channel
+/// is 8 bits wide and starts at the given bit of the little endian pixel
+/// value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct FramebufferInfo {
+    /// The name of the driver, which userspace sees as the id of the
+    /// framebuffer.
+    pub name: &'static str,
+    pub width: usize,
+    pub height: usize,
+    /// The number of bytes from the start of one row to the next.
+    pub pitch: usize,
+    pub bits_per_pixel: u8,
+    pub red_offset: u8,
+    pub green_offset: u8,
+    pub blue_offset: u8,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum FlushError {
+    #[error("the device failed to update the display")]
+    DeviceError,
+}
+
+/// A raw device whose physical memory is a linear framebuffer.
+pub trait FramebufferDevice<Id: DeviceId>: RawDevice<Id> {
+    fn info(&self) -> FramebufferInfo;
+
+    /// Makes the changes to the framebuffer visible, for devices that
+    /// don't scan out their memory continuously.
+    ///
+    /// # Errors
+    /// Returns an error if the device couldn't update the display.
+    fn flush(&mut self) -> Result<(), FlushError>;
+}
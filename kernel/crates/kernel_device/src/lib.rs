@@ -7,6 +7,9 @@ use core::ops::Deref;
 use thiserror::Error;
 
 pub mod block;
+pub mod framebuffer;
+pub mod network;
+pub mod partition;
 pub mod raw;
 
 pub trait Device<Id: DeviceId> {
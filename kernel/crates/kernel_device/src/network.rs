@@ -0,0 +1,181 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+use thiserror::Error;
+
+use crate::{Device, DeviceId};
+
+/// The length of the Ethernet header in front of the payload.
+pub const ETHERNET_HEADER_LEN: usize = 14;
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct MacAddress(pub [u8; 6]);
+
+impl Display for MacAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02x}:{b:02x}:{c:02x}:{d:02x}:{e:02x}:{g:02x}")
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum TransmitError {
+    #[error("the frame is larger than the mtu allows")]
+    FrameTooLarge,
+    #[error("the link is down")]
+    LinkDown,
+    #[error("the device failed to send the frame")]
+    DeviceError,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum ReceiveError {
+    #[error("no frame was received")]
+    Empty,
+    #[error("the buffer is too small for the next frame")]
+    BufferTooSmall,
+}
+
+/// A device that sends and receives Ethernet frames. Frames are passed
+/// with their Ethernet header, but without preamble and checksum.
+pub trait NetworkDevice<Id: DeviceId>: Device<Id> {
+    fn mac_address(&self) -> MacAddress;
+
+    /// The largest payload of a frame, without the Ethernet header.
+    fn mtu(&self) -> usize;
+
+    /// Whether the device is connected to a network.
+    fn link_up(&self) -> bool;
+
+    /// Sends a frame.
+    ///
+    /// # Errors
+    /// Returns an error if the frame is too large, the link is down or the
+    /// device failed to send it.
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), TransmitError>;
+
+    /// Takes the oldest frame from the receive queue and copies it into
+    /// `buf`. A buffer of [`ETHERNET_HEADER_LEN`] plus the
+    /// [`mtu`](Self::mtu) bytes fits every frame.
+    ///
+    /// # Errors
+    /// Returns an error if no frame is queued or if the next frame doesn't
+    /// fit into `buf`, in which case the frame stays queued.
+    fn receive(&mut self, buf: &mut [u8]) -> Result<usize, ReceiveError>;
+}
+
+/// Received frames that weren't taken yet. All buffers are allocated up
+/// front, so that frames can be queued in interrupt handlers. When the queue
+/// is full, new frames are dropped.
+#[derive(Debug)]
+pub struct ReceiveQueue {
+    buffers: Vec<Vec<u8>>,
+    lens: Vec<usize>,
+    head: usize,
+    len: usize,
+    dropped: u64,
+}
+
+impl ReceiveQueue {
+    /// Creates a queue for `capacity` frames of at most `max_frame_len`
+    /// bytes.
+    #[must_use]
+    pub fn new(capacity: usize, max_frame_len: usize) -> Self {
+        Self {
+            buffers: vec![vec![0; max_frame_len]; capacity],
+            lens: vec![0; capacity],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The number of frames that were dropped because the queue was full or
+    /// they were too large.
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Appends a copy of `frame`. Returns whether the frame was queued.
+    pub fn push(&mut self, frame: &[u8]) -> bool {
+        let capacity = self.buffers.len();
+        if self.len == capacity || frame.len() > self.buffers.first().map_or(0, Vec::len) {
+            self.dropped += 1;
+            return false;
+        }
+        let index = (self.head + self.len) % capacity;
+        self.buffers[index][..frame.len()].copy_from_slice(frame);
+        self.lens[index] = frame.len();
+        self.len += 1;
+        true
+    }
+
+    /// Removes the oldest frame and copies it into `buf`.
+    ///
+    /// # Errors
+    /// Returns an error if the queue is empty or if the frame doesn't fit
+    /// into `buf`, in which case it stays queued.
+    pub fn pop(&mut self, buf: &mut [u8]) -> Result<usize, ReceiveError> {
+        if self.len == 0 {
+            return Err(ReceiveError::Empty);
+        }
+        let len = self.lens[self.head];
+        buf.get_mut(..len)
+            .ok_or(ReceiveError::BufferTooSmall)?
+            .copy_from_slice(&self.buffers[self.head][..len]);
+        self.head = (self.head + 1) % self.buffers.len();
+        self.len -= 1;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_mac_address_display() {
+        let mac = MacAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0xab]);
+        assert_eq!("52:54:00:12:34:ab", mac.to_string());
+    }
+
+    #[test]
+    fn test_receive_queue() {
+        let mut queue = ReceiveQueue::new(2, 4);
+        let mut buf = [0; 4];
+        assert_eq!(Err(ReceiveError::Empty), queue.pop(&mut buf));
+
+        assert!(queue.push(&[1, 2]));
+        assert!(queue.push(&[3, 4, 5, 6]));
+        assert!(!queue.push(&[7]));
+        assert_eq!(2, queue.len());
+        assert_eq!(1, queue.dropped());
+
+        assert_eq!(Ok(2), queue.pop(&mut buf));
+        assert_eq!([1, 2], buf[..2]);
+        assert!(queue.push(&[8, 9, 10]));
+        assert!(!queue.push(&[0; 5]));
+        assert_eq!(2, queue.dropped());
+
+        assert_eq!(Err(ReceiveError::BufferTooSmall), queue.pop(&mut buf[..3]));
+        assert_eq!(Ok(4), queue.pop(&mut buf));
+        assert_eq!([3, 4, 5, 6], buf);
+        assert_eq!(Ok(3), queue.pop(&mut buf));
+        assert_eq!([8, 9, 10], buf[..3]);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,342 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::DeviceId;
+use crate::block::{BlockBuf, BlockDevice};
+use crate::partition::{Guid, Partition, PartitionError, PartitionType, SECTOR_SIZE, read};
+
+const SIGNATURE: &[u8; 8] = b"EFI PART";
+const MIN_HEADER_SIZE: usize = 92;
+const MIN_ENTRY_SIZE: usize = 128;
+/// Entries are 128 bytes in practice. The spec allows larger ones, but not
+/// larger than this.
+const MAX_ENTRY_SIZE: usize = 4096;
+/// The UEFI spec requires space for at least 128 entries, anything
+/// significantly larger is most likely garbage.
+const MAX_ENTRIES: usize = 1024;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+struct Header {
+    entries_lba: usize,
+    entry_count: usize,
+    entry_size: usize,
+    entries_crc32: u32,
+}
+
+/// Reads the partitions from the primary GPT, falling back to the backup
+/// GPT in the last block of the device if the primary one is corrupt.
+pub fn read_partitions<Id, D>(device: &mut D) -> Result<Vec<Partition>, PartitionError>
+where
+    Id: DeviceId,
+    D: BlockDevice<Id, SECTOR_SIZE>,
+{
+    match read_header(device, 1).and_then(|header| read_entries(device, &header)) {
+        Ok(partitions) => Ok(partitions),
+        Err(PartitionError::ReadFailed) => Err(PartitionError::ReadFailed),
+        Err(primary_error) => {
+            let last_block = device.block_count().saturating_sub(1);
+            read_header(device, last_block)
+                .and_then(|header| read_entries(device, &header))
+                .map_err(|_| primary_error)
+        }
+    }
+}
+
+fn read_header<Id, D>(device: &mut D, lba: usize) -> Result<Header, PartitionError>
+where
+    Id: DeviceId,
+    D: BlockDevice<Id, SECTOR_SIZE>,
+{
+    let mut buf = BlockBuf::new();
+    read(device, lba, &mut buf)?;
+
+    if &buf[0..8] != SIGNATURE {
+        return Err(PartitionError::InvalidGptHeader);
+    }
+
+    let header_size = u32_at(&buf[..], 12) as usize;
+    if !(MIN_HEADER_SIZE..=SECTOR_SIZE).contains(&header_size) {
+        return Err(PartitionError::InvalidGptHeader);
+    }
+    let header_crc32 = u32_at(&buf[..], 16);
+    buf[16..20].fill(0);
+    if crc32(&buf[..header_size]) != header_crc32 {
+        return Err(PartitionError::InvalidGptHeader);
+    }
+
+    let header = Header {
+        entries_lba: usize::try_from(u64_at(&buf[..], 72))
+            .map_err(|_| PartitionError::InvalidGptHeader)?,
+        entry_count: u32_at(&buf[..], 80) as usize,
+        entry_size: u32_at(&buf[..], 84) as usize,
+        entries_crc32: u32_at(&buf[..], 88),
+    };
+    if header.entry_count > MAX_ENTRIES
+        || !(MIN_ENTRY_SIZE..=MAX_ENTRY_SIZE).contains(&header.entry_size)
+        || !header.entry_size.is_power_of_two()
+    {
+        return Err(PartitionError::InvalidGptHeader);
+    }
+    // the entry array must not extend past the last addressable block
+    let blocks = (header.entry_count * header.entry_size).div_ceil(SECTOR_SIZE);
+    if header.entries_lba.checked_add(blocks).is_none() {
+        return Err(PartitionError::InvalidGptHeader);
+    }
+
+    Ok(header)
+}
+
+fn read_entries<Id, D>(device: &mut D, header: &Header) -> Result<Vec<Partition>, PartitionError>
+where
+    Id: DeviceId,
+    D: BlockDevice<Id, SECTOR_SIZE>,
+{
+    let len = header
+        .entry_count
+        .checked_mul(header.entry_size)
+        .ok_or(PartitionError::InvalidGptHeader)?;
+    let mut data = Vec::with_capacity(len.next_multiple_of(SECTOR_SIZE));
+    let mut buf = BlockBuf::new();
+    for block in 0..len.div_ceil(SECTOR_SIZE) {
+        let lba = header
+            .entries_lba
+            .checked_add(block)
+            .ok_or(PartitionError::InvalidGptHeader)?;
+        read(device, lba, &mut buf)?;
+        data.extend_from_slice(&buf[..]);
+    }
+    let data = &data[..len];
+
+    if crc32(data) != header.entries_crc32 {
+        return Err(PartitionError::InvalidGptEntries);
+    }
+
+    let mut partitions = Vec::new();
+    for (index, entry) in data.chunks_exact(header.entry_size).enumerate() {
+        let type_guid = Guid::from_bytes(entry[0..16].try_into().unwrap());
+        if type_guid.is_nil() {
+            continue;
+        }
+
+        let first_lba = u64_at(entry, 32);
+        let last_lba = u64_at(entry, 40);
+        if last_lba < first_lba {
+            return Err(PartitionError::InvalidGptEntries);
+        }
+        let start_block =
+            usize::try_from(first_lba).map_err(|_| PartitionError::InvalidGptEntries)?;
+        let block_count = usize::try_from(last_lba - first_lba + 1)
+            .map_err(|_| PartitionError::InvalidGptEntries)?;
+
+        partitions.push(Partition {
+            number: index + 1,
+            start_block,
+            block_count,
+            partition_type: PartitionType::Gpt(type_guid),
+            guid: Some(Guid::from_bytes(entry[16..32].try_into().unwrap())),
+            label: Some(decode_name(&entry[56..128])),
+        });
+    }
+    Ok(partitions)
+}
+
+/// Decodes the UTF-16LE, NUL-padded partition name.
+fn decode_name(raw: &[u8]) -> String {
+    let units = raw
+        .as_chunks::<2>()
+        .0
+        .iter()
+        .map(|&c| u16::from_le_bytes(c))
+        .take_while(|&c| c != 0);
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// CRC-32 (IEEE 802.3), as used by the GPT header and entry array checksums.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0_u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::partition::testing::*;
+    use crate::partition::{PartitionSelector, PartitionTableKind, read_partition_table};
+
+    const LINUX_FS: &str = "0fc63daf-8483-4772-8e79-3d69d8477de4";
+    const ESP: &str = "c12a7328-f81f-11d2-ba4b-00a0c93ec93b";
+
+    struct TestEntry {
+        type_guid: &'static str,
+        guid: &'static str,
+        first_lba: u64,
+        last_lba: u64,
+        name: &'static str,
+    }
+
+    fn write_gpt(
+        disk: &mut MemoryDisk,
+        header_lba: usize,
+        entries_lba: usize,
+        entries: &[TestEntry],
+    ) {
+        const ENTRY_COUNT: usize = 128;
+
+        let mut array = vec![0_u8; ENTRY_COUNT * MIN_ENTRY_SIZE];
+        for (i, e) in entries.iter().enumerate() {
+            let raw = &mut array[i * MIN_ENTRY_SIZE..(i + 1) * MIN_ENTRY_SIZE];
+            raw[0..16].copy_from_slice(e.type_guid.parse::<Guid>().unwrap().as_bytes());
+            raw[16..32].copy_from_slice(e.guid.parse::<Guid>().unwrap().as_bytes());
+            raw[32..40].copy_from_slice(&e.first_lba.to_le_bytes());
+            raw[40..48].copy_from_slice(&e.last_lba.to_le_bytes());
+            for (j, unit) in e.name.encode_utf16().enumerate() {
+                raw[56 + j * 2..58 + j * 2].copy_from_slice(&unit.to_le_bytes());
+            }
+        }
+        for (i, chunk) in array.chunks(SECTOR_SIZE).enumerate() {
+            disk.block_mut(entries_lba + i).copy_from_slice(chunk);
+        }
+
+        let header = disk.block_mut(header_lba);
+        header[0..8].copy_from_slice(SIGNATURE);
+        header[8..12].copy_from_slice(&0x0001_0000_u32.to_le_bytes());
+        header[12..16].copy_from_slice(&(MIN_HEADER_SIZE as u32).to_le_bytes());
+        header[72..80].copy_from_slice(&(entries_lba as u64).to_le_bytes());
+        header[80..84].copy_from_slice(&(ENTRY_COUNT as u32).to_le_bytes());
+        header[84..88].copy_from_slice(&(MIN_ENTRY_SIZE as u32).to_le_bytes());
+        header[88..92].copy_from_slice(&crc32(&array).to_le_bytes());
+        let crc = crc32(&header[..MIN_HEADER_SIZE]);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Overwrites a field of the header at `header_lba` and updates the
+    /// header checksum.
+    fn patch_header(disk: &mut MemoryDisk, header_lba: usize, offset: usize, value: &[u8]) {
+        let header = disk.block_mut(header_lba);
+        header[offset..offset + value.len()].copy_from_slice(value);
+        header[16..20].fill(0);
+        let crc = crc32(&header[..MIN_HEADER_SIZE]);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    fn test_disk() -> MemoryDisk {
+        let mut disk = MemoryDisk::new(256);
+        write_mbr_entry(&mut disk, 0, 0, 0xEE, 1, 255);
+        let entries = [
+            TestEntry {
+                type_guid: ESP,
+                guid: "d3f6e1a0-5d2b-4c8e-9a41-0b1c2d3e4f50",
+                first_lba: 40,
+                last_lba: 79,
+                name: "EFI system",
+            },
+            TestEntry {
+                type_guid: LINUX_FS,
+                guid: "6a1b8f4e-2c3d-4e5f-8a9b-0c1d2e3f4a5b",
+                first_lba: 80,
+                last_lba: 200,
+                name: "muffin-root",
+            },
+        ];
+        write_gpt(&mut disk, 1, 2, &entries);
+        write_gpt(&mut disk, 255, 223, &entries);
+        disk
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn test_read_gpt() {
+        let mut disk = test_disk();
+        let table = read_partition_table(&mut disk).unwrap().unwrap();
+        assert_eq!(PartitionTableKind::Gpt, table.kind);
+        assert_eq!(2, table.partitions.len());
+
+        let root = &table.partitions[1];
+        assert_eq!(2, root.number);
+        assert_eq!(80, root.start_block);
+        assert_eq!(121, root.block_count);
+        assert_eq!(
+            PartitionType::Gpt(LINUX_FS.parse().unwrap()),
+            root.partition_type
+        );
+        assert_eq!(Some("muffin-root"), root.label.as_deref());
+
+        assert!(root.matches(&PartitionSelector::Label("muffin-root".into())));
+        assert!(root.matches(&PartitionSelector::Guid(
+            "6a1b8f4e-2c3d-4e5f-8a9b-0c1d2e3f4a5b".parse().unwrap()
+        )));
+        assert!(!table.partitions[0].matches(&PartitionSelector::Label("muffin-root".into())));
+    }
+
+    #[test]
+    fn test_fall_back_to_backup_gpt() {
+        let mut disk = test_disk();
+        // corrupt the primary header, the checksum no longer matches
+        disk.block_mut(1)[40] ^= 0xFF;
+
+        let table = read_partition_table(&mut disk).unwrap().unwrap();
+        assert_eq!(2, table.partitions.len());
+        assert_eq!(Some("EFI system"), table.partitions[0].label.as_deref());
+    }
+
+    #[test]
+    fn test_corrupt_gpt() {
+        let mut disk = test_disk();
+        disk.block_mut(2)[0] ^= 0xFF;
+        disk.block_mut(223)[0] ^= 0xFF;
+
+        assert_eq!(
+            Err(PartitionError::InvalidGptEntries),
+            read_partition_table(&mut disk)
+        );
+    }
+
+    #[test]
+    fn test_oversized_entry_size() {
+        let mut disk = test_disk();
+        for lba in [1, 255] {
+            patch_header(&mut disk, lba, 84, &(1_u32 << 31).to_le_bytes());
+        }
+
+        assert_eq!(
+            Err(PartitionError::InvalidGptHeader),
+            read_partition_table(&mut disk)
+        );
+    }
+
+    #[test]
+    fn test_entries_lba_overflow() {
+        let mut disk = test_disk();
+        for lba in [1, 255] {
+            patch_header(&mut disk, lba, 72, &u64::MAX.to_le_bytes());
+        }
+
+        assert_eq!(
+            Err(PartitionError::InvalidGptHeader),
+            read_partition_table(&mut disk)
+        );
+    }
+}
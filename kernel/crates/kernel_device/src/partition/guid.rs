@@ -0,0 +1,139 @@
+use core::fmt::{Debug, Display, Formatter};
+use core::str::FromStr;
+
+use thiserror::Error;
+
+/// A GUID as it is stored on disk in a GPT, i.e. with the first three
+/// fields in little endian byte order.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct Guid([u8; 16]);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+#[error("invalid guid")]
+pub struct ParseGuidError;
+
+impl Guid {
+    pub const NIL: Guid = Guid([0; 16]);
+
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    #[must_use]
+    pub fn is_nil(&self) -> bool {
+        *self == Self::NIL
+    }
+}
+
+impl Display for Guid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let b = &self.0;
+        write!(
+            f,
+            "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+            b[3],
+            b[2],
+            b[1],
+            b[0],
+            b[5],
+            b[4],
+            b[7],
+            b[6],
+            b[8],
+            b[9],
+            b[10],
+            b[11],
+            b[12],
+            b[13],
+            b[14],
+            b[15]
+        )
+    }
+}
+
+impl Debug for Guid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Guid({self})")
+    }
+}
+
+impl FromStr for Guid {
+    type Err = ParseGuidError;
+
+    /// Parses the canonical textual representation
+    /// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` (case insensitive).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+
+        let mut text = [0_u8; 16];
+        let mut groups = s.split('-');
+        let mut index = 0;
+        for len in GROUPS {
+            let group = groups.next().ok_or(ParseGuidError)?;
+            if group.len() != len {
+                return Err(ParseGuidError);
+            }
+            for pair in group.as_bytes().chunks(2) {
+                let pair = core::str::from_utf8(pair).map_err(|_| ParseGuidError)?;
+                text[index] = u8::from_str_radix(pair, 16).map_err(|_| ParseGuidError)?;
+                index += 1;
+            }
+        }
+        if groups.next().is_some() {
+            return Err(ParseGuidError);
+        }
+
+        // the textual representation is big endian, the on-disk one is mixed endian
+        let t = text;
+        Ok(Self([
+            t[3], t[2], t[1], t[0], t[5], t[4], t[7], t[6], t[8], t[9], t[10], t[11], t[12], t[13],
+            t[14], t[15],
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_display_roundtrip() {
+        let s = "c12a7328-f81f-11d2-ba4b-00a0c93ec93b";
+        let guid = s.parse::<Guid>().unwrap();
+        assert_eq!(
+            guid.as_bytes(),
+            &[
+                0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e,
+                0xc9, 0x3b
+            ]
+        );
+        assert_eq!(s, guid.to_string());
+        assert_eq!(
+            guid,
+            "C12A7328-F81F-11D2-BA4B-00A0C93EC93B"
+                .parse::<Guid>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        for s in [
+            "",
+            "c12a7328-f81f-11d2-ba4b",
+            "c12a7328-f81f-11d2-ba4b-00a0c93ec93b-",
+            "c12a7328f81f-11d2-ba4b-00a0c93ec93b",
+            "g12a7328-f81f-11d2-ba4b-00a0c93ec93b",
+        ] {
+            assert_eq!(Err(ParseGuidError), s.parse::<Guid>(), "{s}");
+        }
+    }
+}
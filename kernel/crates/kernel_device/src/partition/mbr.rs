@@ -0,0 +1,195 @@
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use crate::DeviceId;
+use crate::block::{BlockBuf, BlockDevice};
+use crate::partition::{Partition, PartitionError, PartitionType, SECTOR_SIZE, read};
+
+pub const PROTECTIVE_GPT: u8 = 0xEE;
+const EXTENDED: [u8; 3] = [0x05, 0x0F, 0x85];
+
+const ENTRY_OFFSET: usize = 446;
+const ENTRY_SIZE: usize = 16;
+/// Upper bound for the number of logical partitions, guards against EBR
+/// chains that are too long to be real.
+const MAX_LOGICAL_PARTITIONS: usize = 128;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct MbrEntry {
+    /// The index of the entry in the table, `0..4`.
+    pub index: usize,
+    pub system_id: u8,
+    pub start_lba: u32,
+    pub sector_count: u32,
+}
+
+/// Parses the four partition entries of an MBR or EBR, skipping empty
+/// slots. Returns `None` if the sector doesn't carry the boot signature.
+pub fn parse_entries(sector: &BlockBuf<SECTOR_SIZE>) -> Option<Vec<MbrEntry>> {
+    if sector[510..512] != [0x55, 0xAA] {
+        return None;
+    }
+
+    Some(
+        (0..4)
+            .map(|index| {
+                let raw = &sector[ENTRY_OFFSET + index * ENTRY_SIZE..][..ENTRY_SIZE];
+                MbrEntry {
+                    index,
+                    system_id: raw[4],
+                    start_lba: u32::from_le_bytes(raw[8..12].try_into().unwrap()),
+                    sector_count: u32::from_le_bytes(raw[12..16].try_into().unwrap()),
+                }
+            })
+            .filter(|e| e.system_id != 0 && e.sector_count != 0)
+            .collect(),
+    )
+}
+
+pub fn read_partitions<Id, D>(
+    device: &mut D,
+    entries: &[MbrEntry],
+) -> Result<Vec<Partition>, PartitionError>
+where
+    Id: DeviceId,
+    D: BlockDevice<Id, SECTOR_SIZE>,
+{
+    let mut partitions = Vec::new();
+    for entry in entries {
+        if EXTENDED.contains(&entry.system_id) {
+            read_logical_partitions(device, entry.start_lba as usize, &mut partitions)?;
+        } else {
+            partitions.push(partition(entry.index + 1, 0, entry));
+        }
+    }
+    partitions.sort_by_key(|p| p.number);
+    Ok(partitions)
+}
+
+/// Walks the chain of extended boot records starting at `extended_start`.
+/// Logical partitions are numbered from 5 onwards. The walk stops at an EBR
+/// that was already visited, so that a cyclic chain registers every
+/// partition only once.
+fn read_logical_partitions<Id, D>(
+    device: &mut D,
+    extended_start: usize,
+    partitions: &mut Vec<Partition>,
+) -> Result<(), PartitionError>
+where
+    Id: DeviceId,
+    D: BlockDevice<Id, SECTOR_SIZE>,
+{
+    let mut ebr_lba = extended_start;
+    let mut visited = BTreeSet::new();
+    for number in 5..5 + MAX_LOGICAL_PARTITIONS {
+        if !visited.insert(ebr_lba) {
+            return Ok(());
+        }
+        let mut buf = BlockBuf::new();
+        read(device, ebr_lba, &mut buf)?;
+        let Some(entries) = parse_entries(&buf) else {
+            return Ok(());
+        };
+
+        if let Some(logical) = entries.iter().find(|e| e.index == 0) {
+            partitions.push(partition(number, ebr_lba, logical));
+        }
+
+        match entries.iter().find(|e| e.index == 1) {
+            Some(next) if EXTENDED.contains(&next.system_id) => {
+                ebr_lba = extended_start + next.start_lba as usize;
+            }
+            _ => return Ok(()),
+        }
+    }
+    Ok(())
+}
+
+fn partition(number: usize, base_lba: usize, entry: &MbrEntry) -> Partition {
+    Partition {
+        number,
+        start_block: base_lba + entry.start_lba as usize,
+        block_count: entry.sector_count as usize,
+        partition_type: PartitionType::Mbr(entry.system_id),
+        guid: None,
+        label: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::partition::testing::*;
+    use crate::partition::{PartitionTableKind, read_partition_table};
+
+    #[test]
+    fn test_primary_partitions() {
+        let mut disk = MemoryDisk::new(64);
+        write_mbr_entry(&mut disk, 0, 0, 0x83, 2, 10);
+        write_mbr_entry(&mut disk, 0, 2, 0x0C, 20, 30);
+
+        let table = read_partition_table(&mut disk).unwrap().unwrap();
+        assert_eq!(PartitionTableKind::Mbr, table.kind);
+        assert_eq!(2, table.partitions.len());
+
+        let p = &table.partitions[0];
+        assert_eq!(1, p.number);
+        assert_eq!(2, p.start_block);
+        assert_eq!(10, p.block_count);
+        assert_eq!(PartitionType::Mbr(0x83), p.partition_type);
+        assert_eq!(None, p.guid);
+
+        let p = &table.partitions[1];
+        assert_eq!(3, p.number);
+        assert_eq!(20, p.start_block);
+        assert_eq!(30, p.block_count);
+        assert_eq!(PartitionType::Mbr(0x0C), p.partition_type);
+    }
+
+    #[test]
+    fn test_logical_partitions() {
+        let mut disk = MemoryDisk::new(128);
+        write_mbr_entry(&mut disk, 0, 0, 0x83, 1, 15);
+        write_mbr_entry(&mut disk, 0, 1, 0x05, 16, 100);
+        // first EBR at 16, logical partition at 16+2, next EBR at 16+40
+        write_mbr_entry(&mut disk, 16, 0, 0x83, 2, 20);
+        write_mbr_entry(&mut disk, 16, 1, 0x05, 40, 50);
+        // second EBR at 56, logical partition at 56+1
+        write_mbr_entry(&mut disk, 56, 0, 0x82, 1, 30);
+
+        let table = read_partition_table(&mut disk).unwrap().unwrap();
+        let summary = table
+            .partitions
+            .iter()
+            .map(|p| (p.number, p.start_block, p.block_count))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(1, 1, 15), (5, 18, 20), (6, 57, 30)], summary);
+    }
+
+    #[test]
+    fn test_cyclic_ebr_chain_terminates() {
+        let mut disk = MemoryDisk::new(64);
+        write_mbr_entry(&mut disk, 0, 0, 0x05, 8, 50);
+        write_mbr_entry(&mut disk, 8, 0, 0x83, 1, 2);
+        // points back to itself
+        write_mbr_entry(&mut disk, 8, 1, 0x05, 0, 50);
+
+        let table = read_partition_table(&mut disk).unwrap().unwrap();
+        assert_eq!(1, table.partitions.len());
+        assert_eq!(9, table.partitions[0].start_block);
+
+        // two EBRs that point to each other
+        write_mbr_entry(&mut disk, 8, 1, 0x05, 16, 50);
+        write_mbr_entry(&mut disk, 24, 0, 0x83, 1, 2);
+        write_mbr_entry(&mut disk, 24, 1, 0x05, 0, 50);
+        let table = read_partition_table(&mut disk).unwrap().unwrap();
+        let summary = table
+            .partitions
+            .iter()
+            .map(|p| (p.number, p.start_block))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(5, 9), (6, 25)], summary);
+    }
+}
@@ -0,0 +1,350 @@
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::error::Error;
+
+pub use guid::*;
+use thiserror::Error;
+
+use crate::block::{BlockBuf, BlockDevice};
+use crate::{Device, DeviceId};
+
+mod gpt;
+mod guid;
+mod mbr;
+
+pub const SECTOR_SIZE: usize = 512;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum PartitionError {
+    #[error("failed to read from the device")]
+    ReadFailed,
+    #[error("invalid gpt header")]
+    InvalidGptHeader,
+    #[error("invalid gpt partition entry array")]
+    InvalidGptEntries,
+    #[error("partition exceeds the device")]
+    OutOfBounds,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PartitionTableKind {
+    Mbr,
+    Gpt,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PartitionType {
+    /// The system id byte of an MBR partition entry.
+    Mbr(u8),
+    /// The partition type GUID of a GPT partition entry.
+    Gpt(Guid),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Partition {
+    /// The 1-based partition number, as it appears in device names like
+    /// `blk0p1`. Logical MBR partitions start at 5.
+    pub number: usize,
+    pub start_block: usize,
+    pub block_count: usize,
+    pub partition_type: PartitionType,
+    /// The unique partition GUID. Only available for GPT partitions.
+    pub guid: Option<Guid>,
+    /// The partition name. Only available for GPT partitions.
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PartitionSelector {
+    Guid(Guid),
+    Label(String),
+}
+
+impl Partition {
+    #[must_use]
+    pub fn matches(&self, selector: &PartitionSelector) -> bool {
+        match selector {
+            PartitionSelector::Guid(guid) => self.guid.as_ref() == Some(guid),
+            PartitionSelector::Label(label) => self.label.as_ref() == Some(label),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PartitionTable {
+    pub kind: PartitionTableKind,
+    pub partitions: Vec<Partition>,
+}
+
+/// Reads the partition table of the given device.
+///
+/// A protective MBR is followed to the GPT behind it. Returns `Ok(None)`
+/// if the device doesn't carry a partition table, which is the case for
+/// whole-disk file systems.
+///
+/// # Errors
+/// Returns an error if the device can't be read, or if the partition
+/// table is corrupt or describes partitions outside the device.
+pub fn read_partition_table<Id, D>(device: &mut D) -> Result<Option<PartitionTable>, PartitionError>
+where
+    Id: DeviceId,
+    D: BlockDevice<Id, SECTOR_SIZE>,
+{
+    let mut buf = BlockBuf::new();
+    read(device, 0, &mut buf)?;
+    let Some(entries) = mbr::parse_entries(&buf) else {
+        return Ok(None);
+    };
+
+    let table = if entries.iter().any(|e| e.system_id == mbr::PROTECTIVE_GPT) {
+        PartitionTable {
+            kind: PartitionTableKind::Gpt,
+            partitions: gpt::read_partitions(device)?,
+        }
+    } else {
+        PartitionTable {
+            kind: PartitionTableKind::Mbr,
+            partitions: mbr::read_partitions(device, &entries)?,
+        }
+    };
+
+    let block_count = device.block_count();
+    if table
+        .partitions
+        .iter()
+        .any(|p| p.start_block.saturating_add(p.block_count) > block_count)
+    {
+        return Err(PartitionError::OutOfBounds);
+    }
+
+    Ok(Some(table))
+}
+
+fn read<Id, D>(
+    device: &mut D,
+    block: usize,
+    buf: &mut BlockBuf<SECTOR_SIZE>,
+) -> Result<(), PartitionError>
+where
+    Id: DeviceId,
+    D: BlockDevice<Id, SECTOR_SIZE>,
+{
+    device
+        .read_block(block, buf)
+        .map_err(|_| PartitionError::ReadFailed)
+}
+
+/// A block device that is a window into another block device, as
+/// described by a [`Partition`].
+pub struct PartitionBlockDevice<Id, D> {
+    id: Id,
+    device: D,
+    start_block: usize,
+    block_count: usize,
+}
+
+impl<Id, D> PartitionBlockDevice<Id, D> {
+    pub fn new(id: Id, device: D, partition: &Partition) -> Self {
+        Self {
+            id,
+            device,
+            start_block: partition.start_block,
+            block_count: partition.block_count,
+        }
+    }
+
+    fn translate(&self, block_num: usize) -> Result<usize, Box<dyn Error>> {
+        if block_num >= self.block_count {
+            return Err(Box::new(PartitionError::OutOfBounds));
+        }
+        Ok(self.start_block + block_num)
+    }
+}
+
+impl<Id, D> Device<Id> for PartitionBlockDevice<Id, D>
+where
+    Id: DeviceId,
+{
+    fn id(&self) -> Id {
+        self.id
+    }
+}
+
+impl<Id, D> BlockDevice<Id, SECTOR_SIZE> for PartitionBlockDevice<Id, D>
+where
+    Id: DeviceId,
+    D: BlockDevice<Id, SECTOR_SIZE>,
+{
+    fn block_count(&self) -> usize {
+        self.block_count
+    }
+
+    fn read_block(
+        &mut self,
+        block_num: usize,
+        buf: &mut BlockBuf<SECTOR_SIZE>,
+    ) -> Result<(), Box<dyn Error>> {
+        let block_num = self.translate(block_num)?;
+        self.device.read_block(block_num, buf)
+    }
+
+    fn write_block(
+        &mut self,
+        block_num: usize,
+        buf: &BlockBuf<SECTOR_SIZE>,
+    ) -> Result<(), Box<dyn Error>> {
+        let block_num = self.translate(block_num)?;
+        self.device.write_block(block_num, buf)
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        self.device.flush()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::error::Error;
+
+    use super::*;
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    pub struct TestDeviceId(pub usize);
+    impl DeviceId for TestDeviceId {}
+
+    /// An in-memory disk image.
+    pub struct MemoryDisk {
+        pub data: Vec<u8>,
+    }
+
+    impl MemoryDisk {
+        pub fn new(blocks: usize) -> Self {
+            Self {
+                data: vec![0; blocks * SECTOR_SIZE],
+            }
+        }
+
+        pub fn block_mut(&mut self, block: usize) -> &mut [u8] {
+            &mut self.data[block * SECTOR_SIZE..(block + 1) * SECTOR_SIZE]
+        }
+    }
+
+    impl Device<TestDeviceId> for MemoryDisk {
+        fn id(&self) -> TestDeviceId {
+            TestDeviceId(0)
+        }
+    }
+
+    impl BlockDevice<TestDeviceId, SECTOR_SIZE> for MemoryDisk {
+        fn block_count(&self) -> usize {
+            self.data.len() / SECTOR_SIZE
+        }
+
+        fn read_block(
+            &mut self,
+            block_num: usize,
+            buf: &mut BlockBuf<SECTOR_SIZE>,
+        ) -> Result<(), Box<dyn Error>> {
+            if block_num >= self.block_count() {
+                return Err(Box::new(PartitionError::OutOfBounds));
+            }
+            buf.copy_from_slice(self.block_mut(block_num));
+            Ok(())
+        }
+
+        fn write_block(
+            &mut self,
+            block_num: usize,
+            buf: &BlockBuf<SECTOR_SIZE>,
+        ) -> Result<(), Box<dyn Error>> {
+            if block_num >= self.block_count() {
+                return Err(Box::new(PartitionError::OutOfBounds));
+            }
+            self.block_mut(block_num).copy_from_slice(&buf[..]);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+            Ok(())
+        }
+    }
+
+    pub fn write_mbr_entry(
+        disk: &mut MemoryDisk,
+        block: usize,
+        index: usize,
+        system_id: u8,
+        start: u32,
+        len: u32,
+    ) {
+        let sector = disk.block_mut(block);
+        let entry = &mut sector[446 + index * 16..446 + (index + 1) * 16];
+        entry[4] = system_id;
+        entry[8..12].copy_from_slice(&start.to_le_bytes());
+        entry[12..16].copy_from_slice(&len.to_le_bytes());
+        sector[510] = 0x55;
+        sector[511] = 0xAA;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+
+    #[test]
+    fn test_no_partition_table() {
+        let mut disk = MemoryDisk::new(16);
+        assert_eq!(Ok(None), read_partition_table(&mut disk));
+    }
+
+    #[test]
+    fn test_partition_out_of_bounds() {
+        let mut disk = MemoryDisk::new(16);
+        write_mbr_entry(&mut disk, 0, 0, 0x83, 8, 9);
+        assert_eq!(
+            Err(PartitionError::OutOfBounds),
+            read_partition_table(&mut disk)
+        );
+    }
+
+    #[test]
+    fn test_partition_block_device() {
+        let mut disk = MemoryDisk::new(16);
+        for block in 1..16 {
+            disk.block_mut(block).fill(block as u8);
+        }
+        write_mbr_entry(&mut disk, 0, 0, 0x83, 4, 8);
+        let table = read_partition_table(&mut disk).unwrap().unwrap();
+
+        let mut device = PartitionBlockDevice::new(TestDeviceId(1), disk, &table.partitions[0]);
+        assert_eq!(TestDeviceId(1), device.id());
+        assert_eq!(8, device.block_count());
+
+        let mut buf = BlockBuf::new();
+        device.read_block(0, &mut buf).unwrap();
+        assert!(buf.iter().all(|&b| b == 4));
+        device.read_block(7, &mut buf).unwrap();
+        assert!(buf.iter().all(|&b| b == 11));
+        assert!(device.read_block(8, &mut buf).is_err());
+
+        buf.fill(0xFF);
+        device.write_block(1, &buf).unwrap();
+        assert!(device.write_block(8, &buf).is_err());
+        let disk = device.device;
+        assert!(
+            disk.data[5 * SECTOR_SIZE..6 * SECTOR_SIZE]
+                .iter()
+                .all(|&b| b == 0xFF)
+        );
+        assert!(
+            disk.data[6 * SECTOR_SIZE..7 * SECTOR_SIZE]
+                .iter()
+                .all(|&b| b == 6)
+        );
+    }
+}
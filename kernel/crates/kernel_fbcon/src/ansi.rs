@@ -0,0 +1,253 @@
+/// The most parameters that a control sequence can have. Further parameters
+/// are ignored.
+const MAX_PARAMS: usize = 8;
+
+/// What the console should do for the bytes that were passed to
+/// [`Parser::advance`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Action {
+    Print(char),
+    /// A C0 control character, like `\n` or `\r`.
+    Execute(u8),
+    /// A control sequence `ESC [ params final`, see [`Csi`].
+    Csi(Csi),
+}
+
+/// A control sequence introduced by `ESC [`. Parameters that were omitted
+/// are zero.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Csi {
+    params: [u16; MAX_PARAMS],
+    len: usize,
+    /// The byte that ends the sequence and selects the function, for example
+    /// `m` for the graphic rendition.
+    pub function: u8,
+}
+
+impl Csi {
+    #[must_use]
+    pub fn params(&self) -> &[u16] {
+        &self.params[..self.len]
+    }
+
+    /// Returns the parameter at `index`, or `default` if it is missing or
+    /// zero.
+    #[must_use]
+    pub fn param_or(&self, index: usize, default: u16) -> u16 {
+        match self.params().get(index) {
+            Some(&param) if param != 0 => param,
+            _ => default,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum State {
+    Ground,
+    Escape,
+    CsiEntry,
+    /// A control sequence with characters that aren't supported, which is
+    /// skipped until its final byte.
+    CsiIgnore,
+    /// Inside a multi-byte UTF-8 sequence with this many bytes left.
+    Utf8(u8),
+}
+
+/// A parser for a subset of the VT100 and ANSI escape sequences, which
+/// passes anything it doesn't understand through as text or drops it.
+#[derive(Debug)]
+pub struct Parser {
+    state: State,
+    params: [u16; MAX_PARAMS],
+    len: usize,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: State::Ground,
+            params: [0; MAX_PARAMS],
+            len: 0,
+        }
+    }
+
+    /// Processes the next byte. Returns the action that it completes, if
+    /// any.
+    pub fn advance(&mut self, byte: u8) -> Option<Action> {
+        match self.state {
+            State::Ground => self.ground(byte),
+            State::Escape => {
+                if byte == b'[' {
+                    self.params = [0; MAX_PARAMS];
+                    self.len = 0;
+                    self.state = State::CsiEntry;
+                } else {
+                    // other escape sequences aren't supported, they consist
+                    // of a single byte after ESC in most cases
+                    self.state = State::Ground;
+                }
+                None
+            }
+            State::CsiEntry => match byte {
+                b'0'..=b'9' => {
+                    if self.len == 0 {
+                        self.len = 1;
+                    }
+                    if let Some(param) = self.params.get_mut(self.len - 1) {
+                        *param = param
+                            .saturating_mul(10)
+                            .saturating_add(u16::from(byte - b'0'));
+                    }
+                    None
+                }
+                b';' => {
+                    self.len = (self.len.max(1) + 1).min(MAX_PARAMS + 1);
+                    None
+                }
+                0x40..=0x7e => {
+                    self.state = State::Ground;
+                    Some(Action::Csi(Csi {
+                        params: self.params,
+                        len: self.len.min(MAX_PARAMS),
+                        function: byte,
+                    }))
+                }
+                0x1b => {
+                    self.state = State::Escape;
+                    None
+                }
+                _ => {
+                    // private modes like `ESC [ ? 25 l` and intermediates
+                    self.state = State::CsiIgnore;
+                    None
+                }
+            },
+            State::CsiIgnore => {
+                if (0x40..=0x7e).contains(&byte) {
+                    self.state = State::Ground;
+                }
+                None
+            }
+            State::Utf8(remaining) => {
+                if byte & 0xc0 != 0x80 {
+                    // the sequence was cut off
+                    self.state = State::Ground;
+                    return self.ground(byte);
+                }
+                if remaining == 1 {
+                    self.state = State::Ground;
+                    // there are no glyphs for non-ASCII characters anyway
+                    return Some(Action::Print(char::REPLACEMENT_CHARACTER));
+                }
+                self.state = State::Utf8(remaining - 1);
+                None
+            }
+        }
+    }
+
+    fn ground(&mut self, byte: u8) -> Option<Action> {
+        match byte {
+            0x1b => {
+                self.state = State::Escape;
+                None
+            }
+            0x00..=0x1f | 0x7f => Some(Action::Execute(byte)),
+            0x20..=0x7e => Some(Action::Print(char::from(byte))),
+            0xc0..=0xdf => {
+                self.state = State::Utf8(1);
+                None
+            }
+            0xe0..=0xef => {
+                self.state = State::Utf8(2);
+                None
+            }
+            0xf0..=0xf7 => {
+                self.state = State::Utf8(3);
+                None
+            }
+            _ => Some(Action::Print(char::REPLACEMENT_CHARACTER)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Vec<Action> {
+        let mut parser = Parser::new();
+        bytes.iter().filter_map(|&b| parser.advance(b)).collect()
+    }
+
+    fn csi(params: &[u16], function: u8) -> Action {
+        let mut csi = Csi {
+            params: [0; MAX_PARAMS],
+            len: params.len(),
+            function,
+        };
+        csi.params[..params.len()].copy_from_slice(params);
+        Action::Csi(csi)
+    }
+
+    #[test]
+    fn test_text_and_controls() {
+        assert_eq!(
+            vec_of(&[
+                Action::Print('a'),
+                Action::Execute(b'\r'),
+                Action::Execute(b'\n'),
+                Action::Print(char::REPLACEMENT_CHARACTER),
+                Action::Print(char::REPLACEMENT_CHARACTER),
+                Action::Print('b'),
+            ]),
+            parse("a\r\n\u{e4}\u{1F600}b".as_bytes())
+        );
+        // a truncated UTF-8 sequence doesn't swallow the next character
+        assert_eq!(vec_of(&[Action::Print('c')]), parse(b"\xe2\x82c"));
+    }
+
+    #[test]
+    fn test_csi() {
+        assert_eq!(
+            vec_of(&[csi(&[1, 31], b'm'), Action::Print('x'), csi(&[], b'm')]),
+            parse(b"\x1b[1;31mx\x1b[m")
+        );
+        assert_eq!(vec_of(&[csi(&[0, 5], b'H')]), parse(b"\x1b[;5H"));
+        assert_eq!(vec_of(&[csi(&[2], b'J')]), parse(b"\x1b[2J"));
+
+        let actions = parse(b"\x1b[;7f");
+        let Action::Csi(csi) = actions[0] else {
+            panic!("expected a control sequence");
+        };
+        assert_eq!(1, csi.param_or(0, 1));
+        assert_eq!(7, csi.param_or(1, 1));
+        assert_eq!(1, csi.param_or(2, 1));
+    }
+
+    #[test]
+    fn test_unsupported_sequences_are_dropped() {
+        assert_eq!(
+            vec_of(&[Action::Print('a'), Action::Print('b')]),
+            parse(b"\x1b[?25la\x1bcb")
+        );
+        // too many parameters
+        let actions = parse(b"\x1b[1;2;3;4;5;6;7;8;9;10m");
+        let Action::Csi(csi) = actions[0] else {
+            panic!("expected a control sequence");
+        };
+        assert_eq!(&[1, 2, 3, 4, 5, 6, 7, 8], csi.params());
+    }
+
+    fn vec_of(actions: &[Action]) -> Vec<Action> {
+        actions.to_vec()
+    }
+}
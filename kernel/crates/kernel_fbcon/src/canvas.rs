@@ -0,0 +1,176 @@
+use alloc::boxed::Box;
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    #[must_use]
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b }
+    }
+}
+
+/// Something that the console can draw on, usually a framebuffer.
+pub trait Canvas {
+    /// The width in pixels.
+    fn width(&self) -> usize;
+
+    /// The height in pixels.
+    fn height(&self) -> usize;
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color);
+
+    /// Moves the content up by `rows` pixel rows and fills the rows that
+    /// became free at the bottom with `fill`.
+    fn scroll_up(&mut self, rows: usize, fill: Color);
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        for y in y..(y + height).min(self.height()) {
+            for x in x..(x + width).min(self.width()) {
+                self.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    /// Makes the changes visible, if the device needs to be told about them.
+    fn flush(&mut self) {}
+}
+
+impl<C: Canvas + ?Sized> Canvas for Box<C> {
+    fn width(&self) -> usize {
+        (**self).width()
+    }
+
+    fn height(&self) -> usize {
+        (**self).height()
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        (**self).set_pixel(x, y, color);
+    }
+
+    fn scroll_up(&mut self, rows: usize, fill: Color) {
+        (**self).scroll_up(rows, fill);
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        (**self).fill_rect(x, y, width, height, color);
+    }
+
+    fn flush(&mut self) {
+        (**self).flush();
+    }
+}
+
+/// The layout of a pixel in a [`LinearFramebuffer`]. Each color channel is 8
+/// bits wide and starts at the given bit of the little endian pixel value.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct PixelFormat {
+    pub bytes_per_pixel: usize,
+    pub red_shift: u8,
+    pub green_shift: u8,
+    pub blue_shift: u8,
+}
+
+impl PixelFormat {
+    /// 32 bit pixels with the bytes in the order blue, green, red and an
+    /// unused byte, which is what most firmware and virtual GPUs use.
+    pub const BGRX: Self = Self {
+        bytes_per_pixel: 4,
+        red_shift: 16,
+        green_shift: 8,
+        blue_shift: 0,
+    };
+}
+
+/// A framebuffer in memory, where the rows of pixels follow each other at a
+/// fixed distance (the pitch).
+#[derive(Debug)]
+pub struct LinearFramebuffer<'a> {
+    buffer: &'a mut [u8],
+    width: usize,
+    height: usize,
+    pitch: usize,
+    format: PixelFormat,
+}
+
+impl<'a> LinearFramebuffer<'a> {
+    /// # Panics
+    /// Panics if `buffer` is too small for `height` rows of `pitch` bytes, or
+    /// if a row of `width` pixels doesn't fit into `pitch` bytes.
+    #[must_use]
+    pub fn new(
+        buffer: &'a mut [u8],
+        width: usize,
+        height: usize,
+        pitch: usize,
+        format: PixelFormat,
+    ) -> Self {
+        assert!(width * format.bytes_per_pixel <= pitch);
+        assert!(height * pitch <= buffer.len());
+        Self {
+            buffer,
+            width,
+            height,
+            pitch,
+            format,
+        }
+    }
+}
+
+impl Canvas for LinearFramebuffer<'_> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let format = self.format;
+        let value = (u32::from(color.r) << format.red_shift)
+            | (u32::from(color.g) << format.green_shift)
+            | (u32::from(color.b) << format.blue_shift);
+        let offset = y * self.pitch + x * format.bytes_per_pixel;
+        let len = format.bytes_per_pixel.min(4);
+        self.buffer[offset..offset + len].copy_from_slice(&value.to_le_bytes()[..len]);
+    }
+
+    fn scroll_up(&mut self, rows: usize, fill: Color) {
+        let rows = rows.min(self.height);
+        self.buffer
+            .copy_within(rows * self.pitch..self.height * self.pitch, 0);
+        self.fill_rect(0, self.height - rows, self.width, rows, fill);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_linear_framebuffer() {
+        let mut buffer = vec![0; 4 * 12];
+        let mut fb = LinearFramebuffer::new(&mut buffer, 2, 4, 12, PixelFormat::BGRX);
+        fb.set_pixel(1, 1, Color::new(1, 2, 3));
+        fb.set_pixel(2, 1, Color::new(4, 5, 6));
+        assert_eq!(&[3, 2, 1, 0], &buffer[16..20]);
+        assert!(buffer[20..24].iter().all(|&b| b == 0));
+
+        let mut fb = LinearFramebuffer::new(&mut buffer, 2, 4, 12, PixelFormat::BGRX);
+        fb.scroll_up(1, Color::new(9, 9, 9));
+        assert_eq!(&[3, 2, 1, 0], &buffer[4..8]);
+        assert_eq!(&[9, 9, 9, 0], &buffer[36..40]);
+        assert_eq!(&[9, 9, 9, 0], &buffer[40..44]);
+    }
+}
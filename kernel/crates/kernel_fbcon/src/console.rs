@@ -0,0 +1,383 @@
+use core::fmt;
+
+use crate::ansi::{Action, Csi, Parser};
+use crate::canvas::{Canvas, Color};
+use crate::font::{GLYPH_HEIGHT, GLYPH_WIDTH, glyph};
+
+/// The colors of the VGA text mode. The first eight are the ANSI colors, the
+/// others are their bright variants.
+const PALETTE: [Color; 16] = [
+    Color::new(0, 0, 0),
+    Color::new(170, 0, 0),
+    Color::new(0, 170, 0),
+    Color::new(170, 85, 0),
+    Color::new(0, 0, 170),
+    Color::new(170, 0, 170),
+    Color::new(0, 170, 170),
+    Color::new(170, 170, 170),
+    Color::new(85, 85, 85),
+    Color::new(255, 85, 85),
+    Color::new(85, 255, 85),
+    Color::new(255, 255, 85),
+    Color::new(85, 85, 255),
+    Color::new(255, 85, 255),
+    Color::new(85, 255, 255),
+    Color::new(255, 255, 255),
+];
+
+const DEFAULT_FOREGROUND: usize = 7;
+const DEFAULT_BACKGROUND: usize = 0;
+const TAB_WIDTH: usize = 8;
+
+/// A text console that draws on a [`Canvas`]. It interprets a subset of the
+/// VT100 control sequences: cursor movement, erasing and colors.
+///
+/// A line feed also returns the cursor to the start of the line, like on the
+/// consoles of most kernels.
+pub struct Console<C> {
+    canvas: C,
+    parser: Parser,
+    columns: usize,
+    rows: usize,
+    column: usize,
+    row: usize,
+    /// Indices into [`PALETTE`].
+    foreground: usize,
+    background: usize,
+    bold: bool,
+}
+
+impl<C: Canvas> Console<C> {
+    /// Creates a console that covers the whole canvas and clears it.
+    pub fn new(canvas: C) -> Self {
+        let mut console = Self {
+            columns: (canvas.width() / GLYPH_WIDTH).max(1),
+            rows: (canvas.height() / GLYPH_HEIGHT).max(1),
+            canvas,
+            parser: Parser::new(),
+            column: 0,
+            row: 0,
+            foreground: DEFAULT_FOREGROUND,
+            background: DEFAULT_BACKGROUND,
+            bold: false,
+        };
+        console.erase(0, 0, console.columns, console.rows);
+        console.canvas.flush();
+        console
+    }
+
+    /// The size in characters, as `(columns, rows)`.
+    #[must_use]
+    pub fn size(&self) -> (usize, usize) {
+        (self.columns, self.rows)
+    }
+
+    /// The position of the cursor, as `(column, row)`.
+    #[must_use]
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.column, self.row)
+    }
+
+    #[must_use]
+    pub fn canvas(&self) -> &C {
+        &self.canvas
+    }
+
+    /// Writes text and control sequences and makes them visible.
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.process(bytes);
+        self.flush();
+    }
+
+    pub fn flush(&mut self) {
+        self.canvas.flush();
+    }
+
+    fn process(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match self.parser.advance(byte) {
+                Some(Action::Print(c)) => self.print(c),
+                Some(Action::Execute(byte)) => self.execute(byte),
+                Some(Action::Csi(csi)) => self.csi(&csi),
+                None => {}
+            }
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        if self.column >= self.columns {
+            self.column = 0;
+            self.line_feed();
+        }
+
+        let foreground = if self.bold && self.foreground < 8 {
+            PALETTE[self.foreground + 8]
+        } else {
+            PALETTE[self.foreground]
+        };
+        let background = PALETTE[self.background];
+        let x = self.column * GLYPH_WIDTH;
+        let y = self.row * GLYPH_HEIGHT;
+        for (dy, bits) in glyph(c).iter().enumerate() {
+            for dx in 0..GLYPH_WIDTH {
+                let color = if bits & (0x80 >> dx) != 0 {
+                    foreground
+                } else {
+                    background
+                };
+                self.canvas.set_pixel(x + dx, y + dy, color);
+            }
+        }
+        self.column += 1;
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\n' | 0x0b | 0x0c => {
+                self.column = 0;
+                self.line_feed();
+            }
+            b'\r' => self.column = 0,
+            0x08 => self.column = self.column.min(self.columns - 1).saturating_sub(1),
+            b'\t' => self.column = ((self.column / TAB_WIDTH + 1) * TAB_WIDTH).min(self.columns),
+            _ => {}
+        }
+    }
+
+    fn line_feed(&mut self) {
+        if self.row + 1 < self.rows {
+            self.row += 1;
+        } else {
+            self.canvas
+                .scroll_up(GLYPH_HEIGHT, PALETTE[DEFAULT_BACKGROUND]);
+            // the canvas may have rows of pixels below the last row of text
+            self.erase(0, self.rows - 1, self.columns, 1);
+        }
+    }
+
+    fn csi(&mut self, csi: &Csi) {
+        let n = usize::from(csi.param_or(0, 1));
+        match csi.function {
+            b'A' => self.row = self.row.saturating_sub(n),
+            b'B' => self.row = (self.row + n).min(self.rows - 1),
+            b'C' => self.column = (self.column + n).min(self.columns - 1),
+            b'D' => self.column = self.column.min(self.columns - 1).saturating_sub(n),
+            b'G' => self.column = (n - 1).min(self.columns - 1),
+            b'H' | b'f' => {
+                self.row = (n - 1).min(self.rows - 1);
+                self.column = (usize::from(csi.param_or(1, 1)) - 1).min(self.columns - 1);
+            }
+            b'J' => {
+                let column = self.column.min(self.columns);
+                match csi.params().first().copied().unwrap_or(0) {
+                    0 => {
+                        self.erase(column, self.row, self.columns - column, 1);
+                        self.erase(0, self.row + 1, self.columns, self.rows - self.row - 1);
+                    }
+                    1 => {
+                        self.erase(0, 0, self.columns, self.row);
+                        self.erase(0, self.row, column + 1, 1);
+                    }
+                    2 | 3 => self.erase(0, 0, self.columns, self.rows),
+                    _ => {}
+                }
+            }
+            b'K' => {
+                let column = self.column.min(self.columns);
+                match csi.params().first().copied().unwrap_or(0) {
+                    0 => self.erase(column, self.row, self.columns - column, 1),
+                    1 => self.erase(0, self.row, column + 1, 1),
+                    2 => self.erase(0, self.row, self.columns, 1),
+                    _ => {}
+                }
+            }
+            b'm' => self.select_graphic_rendition(csi.params()),
+            _ => {}
+        }
+    }
+
+    fn select_graphic_rendition(&mut self, params: &[u16]) {
+        if params.is_empty() {
+            self.reset_graphic_rendition();
+        }
+        for &param in params {
+            let param = usize::from(param);
+            match param {
+                0 => self.reset_graphic_rendition(),
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.foreground = param - 30,
+                39 => self.foreground = DEFAULT_FOREGROUND,
+                40..=47 => self.background = param - 40,
+                49 => self.background = DEFAULT_BACKGROUND,
+                90..=97 => self.foreground = param - 90 + 8,
+                100..=107 => self.background = param - 100 + 8,
+                _ => {}
+            }
+        }
+    }
+
+    fn reset_graphic_rendition(&mut self) {
+        self.foreground = DEFAULT_FOREGROUND;
+        self.background = DEFAULT_BACKGROUND;
+        self.bold = false;
+    }
+
+    /// Fills a rectangle of character cells with the background color.
+    fn erase(&mut self, column: usize, row: usize, columns: usize, rows: usize) {
+        self.canvas.fill_rect(
+            column * GLYPH_WIDTH,
+            row * GLYPH_HEIGHT,
+            columns * GLYPH_WIDTH,
+            rows * GLYPH_HEIGHT,
+            PALETTE[self.background],
+        );
+    }
+}
+
+/// Writes without flushing, so that formatted output is made visible at
+/// once with [`Console::flush`].
+impl<C: Canvas> fmt::Write for Console<C> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.process(s.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::fmt::Write;
+
+    use super::*;
+
+    struct TestCanvas {
+        width: usize,
+        height: usize,
+        pixels: Vec<Color>,
+        flushes: usize,
+    }
+
+    impl TestCanvas {
+        fn new(columns: usize, rows: usize) -> Self {
+            let width = columns * GLYPH_WIDTH;
+            let height = rows * GLYPH_HEIGHT;
+            Self {
+                width,
+                height,
+                pixels: vec![Color::new(1, 2, 3); width * height],
+                flushes: 0,
+            }
+        }
+
+        /// Whether the cell shows any pixel in `color`.
+        fn cell_has(&self, column: usize, row: usize, color: Color) -> bool {
+            (0..GLYPH_HEIGHT).any(|dy| {
+                (0..GLYPH_WIDTH).any(|dx| {
+                    let x = column * GLYPH_WIDTH + dx;
+                    let y = row * GLYPH_HEIGHT + dy;
+                    self.pixels[y * self.width + x] == color
+                })
+            })
+        }
+    }
+
+    impl Canvas for TestCanvas {
+        fn width(&self) -> usize {
+            self.width
+        }
+
+        fn height(&self) -> usize {
+            self.height
+        }
+
+        fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+            self.pixels[y * self.width + x] = color;
+        }
+
+        fn scroll_up(&mut self, rows: usize, fill: Color) {
+            self.pixels.drain(..rows * self.width);
+            self.pixels.resize(self.width * self.height, fill);
+        }
+
+        fn flush(&mut self) {
+            self.flushes += 1;
+        }
+    }
+
+    const WHITE: Color = PALETTE[7];
+    const BLACK: Color = PALETTE[0];
+
+    #[test]
+    fn test_new_clears() {
+        let console = Console::new(TestCanvas::new(4, 3));
+        assert_eq!((4, 3), console.size());
+        assert!(console.canvas().pixels.iter().all(|&p| p == BLACK));
+        assert_eq!(1, console.canvas().flushes);
+    }
+
+    #[test]
+    fn test_print_and_wrap() {
+        let mut console = Console::new(TestCanvas::new(10, 3));
+        console.write(b"ab\tcd");
+        assert_eq!((10, 0), console.cursor());
+        assert!(console.canvas().cell_has(0, 0, WHITE));
+        assert!(console.canvas().cell_has(1, 0, WHITE));
+        assert!(!console.canvas().cell_has(2, 0, WHITE));
+        assert!(console.canvas().cell_has(8, 0, WHITE));
+        assert!(console.canvas().cell_has(9, 0, WHITE));
+
+        // the line only wraps when the next character is printed
+        console.write(b"e");
+        assert_eq!((1, 1), console.cursor());
+        assert!(console.canvas().cell_has(0, 1, WHITE));
+        assert_eq!(3, console.canvas().flushes);
+
+        write!(console, "f{}", 1).unwrap();
+        assert_eq!((3, 1), console.cursor());
+        assert_eq!(3, console.canvas().flushes);
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut console = Console::new(TestCanvas::new(4, 3));
+        console.write(b"a\nb\nc\nd");
+        assert_eq!((1, 2), console.cursor());
+        for row in 0..3 {
+            assert!(console.canvas().cell_has(0, row, WHITE));
+            assert!(!console.canvas().cell_has(1, row, WHITE));
+        }
+
+        console.write(b"\x1b[2J");
+        assert!(console.canvas().pixels.iter().all(|&p| p == BLACK));
+    }
+
+    #[test]
+    fn test_cursor_movement() {
+        let mut console = Console::new(TestCanvas::new(10, 5));
+        console.write(b"\x1b[3;4H");
+        assert_eq!((3, 2), console.cursor());
+        console.write(b"\x1b[2A\x1b[5C");
+        assert_eq!((8, 0), console.cursor());
+        console.write(b"\x1b[9B\x1b[20D");
+        assert_eq!((0, 4), console.cursor());
+        console.write(b"\x1b[H\r\x08");
+        assert_eq!((0, 0), console.cursor());
+    }
+
+    #[test]
+    fn test_colors_and_erase() {
+        let mut console = Console::new(TestCanvas::new(10, 2));
+        console.write(b"\x1b[31ma\x1b[1mb\x1b[0;44mc\x1b[m");
+        assert!(console.canvas().cell_has(0, 0, PALETTE[1]));
+        assert!(console.canvas().cell_has(1, 0, PALETTE[9]));
+        assert!(console.canvas().cell_has(2, 0, WHITE));
+        assert!(console.canvas().cell_has(2, 0, PALETTE[4]));
+
+        console.write(b"\x1b[2D\x1b[K");
+        assert!(console.canvas().cell_has(0, 0, PALETTE[1]));
+        assert!(!console.canvas().cell_has(1, 0, PALETTE[9]));
+        assert!(!console.canvas().cell_has(2, 0, PALETTE[4]));
+    }
+}
@@ -0,0 +1,16 @@
+//! A text console for framebuffers. The [`Console`] renders an embedded
+//! bitmap font onto a [`Canvas`], scrolls when the cursor moves past the last
+//! line, and understands the escape sequences that are commonly used for
+//! colors and moving the cursor.
+#![no_std]
+extern crate alloc;
+
+mod ansi;
+mod canvas;
+mod console;
+mod font;
+
+pub use ansi::*;
+pub use canvas::*;
+pub use console::*;
+pub use font::*;
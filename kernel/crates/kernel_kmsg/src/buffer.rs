@@ -0,0 +1,278 @@
+use core::fmt::{self, Write};
+use core::time::Duration;
+
+use log::Level;
+use thiserror::Error;
+
+use crate::record::{InlineStr, MAX_MESSAGE_LEN, MAX_TARGET_LEN, Record};
+
+/// A record formatted for `/dev/kmsg` or `syslog(2)` is never longer than
+/// this, even if every byte of the message is escaped.
+pub const MAX_FORMATTED_LEN: usize = MAX_MESSAGE_LEN * 4 + MAX_TARGET_LEN + 128;
+
+type Formatted = InlineStr<MAX_FORMATTED_LEN>;
+
+/// The length of a record in the format of `syslog(2)` with a message that
+/// is as long as possible.
+const MAX_SYSLOG_LEN: usize = MAX_MESSAGE_LEN + 40;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum ReadKmsgError {
+    #[error("no new records")]
+    Empty,
+    #[error("buffer too small for the next record")]
+    BufferTooSmall,
+}
+
+/// The kernel log, which keeps the last `N` records. When it is full, new
+/// records overwrite the oldest ones.
+///
+/// Readers keep their own position as a sequence number, so that several of
+/// them can read the log independently. A reader whose next record was
+/// already overwritten continues with the oldest record that is left.
+#[derive(Debug)]
+pub struct LogBuffer<const N: usize> {
+    records: [Record; N],
+    next_seq: u64,
+}
+
+impl<const N: usize> Default for LogBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> LogBuffer<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            records: [Record::EMPTY; N],
+            next_seq: 0,
+        }
+    }
+
+    /// Appends a record and returns its sequence number. This doesn't
+    /// allocate, so it can be used while the heap is locked.
+    pub fn push(
+        &mut self,
+        timestamp: Duration,
+        level: Level,
+        cpu: Option<usize>,
+        pid: Option<u64>,
+        target: &str,
+        args: fmt::Arguments,
+    ) -> u64 {
+        let seq = self.next_seq;
+        let record = &mut self.records[Self::index(seq)];
+        record.seq = seq;
+        record.timestamp = timestamp;
+        record.level = level;
+        record.cpu = cpu;
+        record.pid = pid;
+        record.target.clear();
+        let _ = record.target.write_str(target);
+        record.message.clear();
+        let _ = record.message.write_fmt(args);
+        self.next_seq += 1;
+        seq
+    }
+
+    /// The sequence number of the oldest record that is still in the buffer.
+    #[must_use]
+    pub fn first_seq(&self) -> u64 {
+        self.next_seq.saturating_sub(N as u64)
+    }
+
+    /// The sequence number that the next record will get.
+    #[must_use]
+    pub fn next_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// The record with the sequence number `seq`, unless it was overwritten
+    /// or doesn't exist yet.
+    #[must_use]
+    pub fn get(&self, seq: u64) -> Option<&Record> {
+        (self.first_seq()..self.next_seq)
+            .contains(&seq)
+            .then(|| &self.records[Self::index(seq)])
+    }
+
+    /// Iterates over the records from `seq` on, or from the oldest record if
+    /// `seq` was already overwritten.
+    pub fn iter_from(&self, seq: u64) -> impl DoubleEndedIterator<Item = &Record> {
+        (seq.max(self.first_seq())..self.next_seq).map(|seq| &self.records[Self::index(seq)])
+    }
+
+    /// Reads the record at `*seq` in the format of `/dev/kmsg` and advances
+    /// `*seq` past it. Records that were overwritten are skipped.
+    ///
+    /// # Errors
+    /// Returns [`ReadKmsgError::Empty`] if there is no record at `*seq` yet,
+    /// and [`ReadKmsgError::BufferTooSmall`] if the record doesn't fit into
+    /// `buf`, in which case `*seq` stays where it is.
+    pub fn read_kmsg(&self, seq: &mut u64, buf: &mut [u8]) -> Result<usize, ReadKmsgError> {
+        let record = self.iter_from(*seq).next().ok_or(ReadKmsgError::Empty)?;
+        let formatted = format(|w| record.write_kmsg(w));
+        let len = formatted.as_bytes().len();
+        if len > buf.len() {
+            return Err(ReadKmsgError::BufferTooSmall);
+        }
+        buf[..len].copy_from_slice(formatted.as_bytes());
+        *seq = record.seq + 1;
+        Ok(len)
+    }
+
+    /// Reads as many whole records from `*seq` on as fit into `buf` in the
+    /// format of `syslog(2)`, and advances `*seq` past them.
+    pub fn read_syslog(&self, seq: &mut u64, buf: &mut [u8]) -> usize {
+        let mut len = 0;
+        for record in self.iter_from(*seq) {
+            let formatted = format(|w| record.write_syslog(w));
+            let bytes = formatted.as_bytes();
+            if len + bytes.len() > buf.len() {
+                break;
+            }
+            buf[len..len + bytes.len()].copy_from_slice(bytes);
+            len += bytes.len();
+            *seq = record.seq + 1;
+        }
+        len
+    }
+
+    /// Reads the newest records from `seq` on that fit into `buf` in the
+    /// format of `syslog(2)`, oldest first.
+    pub fn read_syslog_tail(&self, seq: u64, buf: &mut [u8]) -> usize {
+        let mut start = self.next_seq;
+        let mut len = 0;
+        for record in self.iter_from(seq).rev() {
+            let record_len = format(|w| record.write_syslog(w)).as_bytes().len();
+            if len + record_len > buf.len() {
+                break;
+            }
+            len += record_len;
+            start = record.seq;
+        }
+        self.read_syslog(&mut start, &mut buf[..len])
+    }
+
+    /// The number of bytes that the records from `seq` on take in the format
+    /// of `syslog(2)`.
+    #[must_use]
+    pub fn syslog_len(&self, seq: u64) -> usize {
+        self.iter_from(seq)
+            .map(|record| format(|w| record.write_syslog(w)).as_bytes().len())
+            .sum()
+    }
+
+    /// The number of bytes that the buffer holds in the format of
+    /// `syslog(2)` if all messages are as long as possible.
+    #[must_use]
+    pub const fn syslog_capacity(&self) -> usize {
+        N * MAX_SYSLOG_LEN
+    }
+
+    fn index(seq: u64) -> usize {
+        (seq % N as u64) as usize
+    }
+}
+
+fn format(f: impl FnOnce(&mut Formatted) -> fmt::Result) -> Formatted {
+    let mut formatted = Formatted::new();
+    // writing to an inline string never fails
+    let _ = f(&mut formatted);
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+
+    use super::*;
+
+    fn push<const N: usize>(buffer: &mut LogBuffer<N>, message: &str) -> u64 {
+        buffer.push(
+            Duration::from_secs(buffer.next_seq()),
+            Level::Info,
+            None,
+            Some(1),
+            "kernel",
+            format_args!("{message}"),
+        )
+    }
+
+    fn syslog(seq: u64, message: &str) -> alloc::string::String {
+        format!("<6>[{seq:5}.000000] {message}\n")
+    }
+
+    #[test]
+    fn test_overwrite_oldest() {
+        let mut buffer = LogBuffer::<3>::new();
+        assert!(buffer.get(0).is_none());
+        for i in 0..5 {
+            assert_eq!(i, push(&mut buffer, &format!("{i}")));
+        }
+        assert_eq!(2, buffer.first_seq());
+        assert_eq!(5, buffer.next_seq());
+        assert!(buffer.get(1).is_none());
+        assert_eq!("2", buffer.get(2).unwrap().message());
+        assert_eq!("4", buffer.get(4).unwrap().message());
+        assert!(buffer.get(5).is_none());
+    }
+
+    #[test]
+    fn test_read_kmsg() {
+        let mut buffer = LogBuffer::<2>::new();
+        let mut buf = [0; 128];
+        let mut seq = 0;
+        assert_eq!(
+            Err(ReadKmsgError::Empty),
+            buffer.read_kmsg(&mut seq, &mut buf)
+        );
+
+        push(&mut buffer, "a");
+        let len = buffer.read_kmsg(&mut seq, &mut buf).unwrap();
+        assert_eq!(b"6,0,0,-;a\n TARGET=kernel\n PID=1\n", &buf[..len]);
+        assert_eq!(1, seq);
+        assert_eq!(
+            Err(ReadKmsgError::BufferTooSmall),
+            buffer.read_kmsg(&mut 0, &mut buf[..10])
+        );
+
+        // a reader that fell behind skips the lost records
+        for i in 0..3 {
+            push(&mut buffer, &format!("{i}"));
+        }
+        let len = buffer.read_kmsg(&mut seq, &mut buf).unwrap();
+        assert!(buf[..len].starts_with(b"6,2,2000000,-;1\n"));
+        assert_eq!(3, seq);
+    }
+
+    #[test]
+    fn test_read_syslog() {
+        let mut buffer = LogBuffer::<4>::new();
+        for message in ["a", "b", "c"] {
+            push(&mut buffer, message);
+        }
+        let record_len = syslog(0, "a").len();
+        assert_eq!(3 * record_len, buffer.syslog_len(0));
+        assert_eq!(record_len, buffer.syslog_len(2));
+
+        let mut buf = [0; 64];
+        let mut seq = 0;
+        let len = buffer.read_syslog(&mut seq, &mut buf[..2 * record_len + 1]);
+        assert_eq!(
+            syslog(0, "a") + &syslog(1, "b"),
+            str::from_utf8(&buf[..len]).unwrap()
+        );
+        assert_eq!(2, seq);
+
+        let len = buffer.read_syslog_tail(0, &mut buf[..2 * record_len + 1]);
+        assert_eq!(
+            syslog(1, "b") + &syslog(2, "c"),
+            str::from_utf8(&buf[..len]).unwrap()
+        );
+        let len = buffer.read_syslog_tail(2, &mut buf);
+        assert_eq!(syslog(2, "c"), str::from_utf8(&buf[..len]).unwrap());
+    }
+}
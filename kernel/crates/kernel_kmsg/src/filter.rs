@@ -0,0 +1,176 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use log::{Level, LevelFilter};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ParseFilterError {
+    #[error("invalid log level '{0}'")]
+    InvalidLevel(String),
+    #[error("empty target")]
+    EmptyTarget,
+}
+
+/// Decides which records are logged, with a default level and levels for
+/// targets that start with a given prefix. If several prefixes match, the
+/// longest one wins.
+///
+/// The textual form is a comma separated list of a default level and
+/// `target=level` pairs, like `info,kernel::driver=trace,kernel_vfs=off`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Filter {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Default for Filter {
+    /// Debug records of all targets and everything of the kernel itself.
+    fn default() -> Self {
+        Self::new(LevelFilter::Debug).with_target("kernel", LevelFilter::Trace)
+    }
+}
+
+impl Filter {
+    /// A filter that applies the same level to all targets.
+    #[must_use]
+    pub const fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            targets: Vec::new(),
+        }
+    }
+
+    /// Sets the level for targets that start with `prefix`.
+    #[must_use]
+    pub fn with_target(mut self, prefix: &str, level: LevelFilter) -> Self {
+        self.set_target(prefix, level);
+        self
+    }
+
+    pub fn set_default(&mut self, level: LevelFilter) {
+        self.default = level;
+    }
+
+    /// Sets the level for targets that start with `prefix`.
+    pub fn set_target(&mut self, prefix: &str, level: LevelFilter) {
+        match self.targets.iter_mut().find(|(p, _)| p == prefix) {
+            Some((_, l)) => *l = level,
+            None => self.targets.push((prefix.to_string(), level)),
+        }
+    }
+
+    /// The level that applies to `target`.
+    #[must_use]
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |&(_, level)| level)
+    }
+
+    #[must_use]
+    pub fn enabled(&self, target: &str, level: Level) -> bool {
+        level <= self.level(target)
+    }
+
+    /// The most verbose level of all targets, which records have to pass
+    /// before the filter is even asked.
+    #[must_use]
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|&(_, level)| level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseFilterError;
+
+    /// Parses the textual form. A level without target sets the default,
+    /// which is [`LevelFilter::Off`] if there is none.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_level = |level: &str| {
+            LevelFilter::from_str(level.trim())
+                .map_err(|_| ParseFilterError::InvalidLevel(level.trim().to_string()))
+        };
+
+        let mut filter = Self::new(LevelFilter::Off);
+        for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+            match item.split_once('=') {
+                Some((target, level)) => {
+                    let target = target.trim();
+                    if target.is_empty() {
+                        return Err(ParseFilterError::EmptyTarget);
+                    }
+                    filter.set_target(target, parse_level(level)?);
+                }
+                None => filter.default = parse_level(item)?,
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.as_str().to_ascii_lowercase())?;
+        for (target, level) in &self.targets {
+            write!(f, ",{target}={}", level.as_str().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_filter() {
+        let filter = Filter::default();
+        assert!(filter.enabled("kernel::driver", Level::Trace));
+        assert!(filter.enabled("kernel_vfs", Level::Trace));
+        assert!(filter.enabled("virtio_drivers", Level::Debug));
+        assert!(!filter.enabled("virtio_drivers", Level::Trace));
+        assert_eq!(LevelFilter::Trace, filter.max_level());
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let filter = Filter::new(LevelFilter::Warn)
+            .with_target("kernel::driver", LevelFilter::Off)
+            .with_target("kernel", LevelFilter::Info);
+        assert_eq!(LevelFilter::Info, filter.level("kernel::mem"));
+        assert_eq!(LevelFilter::Off, filter.level("kernel::driver::virtio"));
+        assert_eq!(LevelFilter::Warn, filter.level("acpi"));
+        assert_eq!(LevelFilter::Info, filter.max_level());
+    }
+
+    #[test]
+    fn test_parse() {
+        let filter: Filter = " info , kernel::driver=TRACE,acpi=off,".parse().unwrap();
+        assert_eq!(
+            Filter::new(LevelFilter::Info)
+                .with_target("kernel::driver", LevelFilter::Trace)
+                .with_target("acpi", LevelFilter::Off),
+            filter
+        );
+        assert_eq!("info,kernel::driver=trace,acpi=off", filter.to_string());
+        assert_eq!(filter, filter.to_string().parse().unwrap());
+
+        assert_eq!(Filter::new(LevelFilter::Off), "".parse().unwrap());
+        assert_eq!(
+            Err(ParseFilterError::InvalidLevel("loud".to_string())),
+            "kernel=loud".parse::<Filter>()
+        );
+        assert_eq!(
+            Err(ParseFilterError::EmptyTarget),
+            "=info".parse::<Filter>()
+        );
+    }
+}
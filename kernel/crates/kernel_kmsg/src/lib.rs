@@ -0,0 +1,14 @@
+//! The kernel log. Records are kept in a [`LogBuffer`] of fixed size, which
+//! overwrites the oldest records when it is full, and can be read in the
+//! formats of `/dev/kmsg` and `syslog(2)`. A [`Filter`] decides which
+//! records are logged at all.
+#![no_std]
+extern crate alloc;
+
+mod buffer;
+mod filter;
+mod record;
+
+pub use buffer::*;
+pub use filter::*;
+pub use record::*;
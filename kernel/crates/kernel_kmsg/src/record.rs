@@ -0,0 +1,254 @@
+use core::fmt::{self, Write};
+use core::time::Duration;
+
+use log::Level;
+
+/// Targets that are longer are cut off.
+pub const MAX_TARGET_LEN: usize = 48;
+
+/// Messages that are longer are cut off.
+pub const MAX_MESSAGE_LEN: usize = 256;
+
+/// A string of at most `N` bytes that is stored inline, so that records can
+/// be written without allocating. Text that doesn't fit is cut off at a
+/// character boundary.
+#[derive(Copy, Clone)]
+pub struct InlineStr<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for InlineStr<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> InlineStr<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        // only whole characters are ever copied into the buffer
+        core::str::from_utf8(&self.buf[..self.len]).unwrap()
+    }
+
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Write for InlineStr<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut len = s.len().min(N - self.len);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.buf[self.len..self.len + len].copy_from_slice(&s.as_bytes()[..len]);
+        self.len += len;
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::Debug for InlineStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// Splits a syslog priority like `<6>` off the start of `message` and
+/// returns the matching level, if there is a priority.
+#[must_use]
+pub fn split_priority(message: &str) -> (Option<Level>, &str) {
+    let Some((priority, rest)) = message
+        .strip_prefix('<')
+        .and_then(|message| message.split_once('>'))
+    else {
+        return (None, message);
+    };
+    let Ok(priority) = priority.parse::<u32>() else {
+        return (None, message);
+    };
+    // the facility in the upper bits is ignored
+    let level = match priority & 7 {
+        0..=3 => Level::Error,
+        4 => Level::Warn,
+        5 | 6 => Level::Info,
+        _ => Level::Debug,
+    };
+    (Some(level), rest)
+}
+
+/// An entry of the kernel log.
+#[derive(Debug, Copy, Clone)]
+pub struct Record {
+    /// The number of the record, which counts all records since boot.
+    pub seq: u64,
+    /// The time since boot.
+    pub timestamp: Duration,
+    pub level: Level,
+    /// The CPU that logged the record, unless it was logged during early
+    /// boot.
+    pub cpu: Option<usize>,
+    /// The process that was running when the record was logged.
+    pub pid: Option<u64>,
+    pub(crate) target: InlineStr<MAX_TARGET_LEN>,
+    pub(crate) message: InlineStr<MAX_MESSAGE_LEN>,
+}
+
+impl Record {
+    pub(crate) const EMPTY: Self = Self {
+        seq: 0,
+        timestamp: Duration::ZERO,
+        level: Level::Trace,
+        cpu: None,
+        pid: None,
+        target: InlineStr::new(),
+        message: InlineStr::new(),
+    };
+
+    #[must_use]
+    pub fn target(&self) -> &str {
+        self.target.as_str()
+    }
+
+    #[must_use]
+    pub fn message(&self) -> &str {
+        self.message.as_str()
+    }
+
+    /// The syslog priority, which is the severity for the kernel facility.
+    #[must_use]
+    pub fn priority(&self) -> u8 {
+        match self.level {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        }
+    }
+
+    /// Writes the record in the format of `/dev/kmsg`, where the fields are
+    /// separated by commas and the message is followed by key-value pairs on
+    /// continuation lines. Non-printable characters in the message are
+    /// escaped.
+    ///
+    /// # Errors
+    /// Returns an error if `w` fails.
+    pub fn write_kmsg(&self, w: &mut impl Write) -> fmt::Result {
+        write!(
+            w,
+            "{},{},{},-;",
+            self.priority(),
+            self.seq,
+            self.timestamp.as_micros()
+        )?;
+        for c in self.message().chars() {
+            if c.is_control() || c == '\\' {
+                for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                    write!(w, "\\x{byte:02x}")?;
+                }
+            } else {
+                w.write_char(c)?;
+            }
+        }
+        writeln!(w)?;
+        writeln!(w, " TARGET={}", self.target())?;
+        if let Some(cpu) = self.cpu {
+            writeln!(w, " CPU={cpu}")?;
+        }
+        if let Some(pid) = self.pid {
+            writeln!(w, " PID={pid}")?;
+        }
+        Ok(())
+    }
+
+    /// Writes the record in the format of `syslog(2)`, like
+    /// `<6>[    1.000000] message`.
+    ///
+    /// # Errors
+    /// Returns an error if `w` fails.
+    pub fn write_syslog(&self, w: &mut impl Write) -> fmt::Result {
+        writeln!(
+            w,
+            "<{}>[{:5}.{:06}] {}",
+            self.priority(),
+            self.timestamp.as_secs(),
+            self.timestamp.subsec_micros(),
+            self.message()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+
+    use super::*;
+
+    fn record(message: &str) -> Record {
+        let mut record = Record {
+            seq: 7,
+            timestamp: Duration::from_micros(1_500_002),
+            level: Level::Warn,
+            cpu: Some(1),
+            pid: None,
+            ..Record::EMPTY
+        };
+        record.target.write_str("kernel::test").unwrap();
+        record.message.write_str(message).unwrap();
+        record
+    }
+
+    #[test]
+    fn test_inline_str_truncates() {
+        let mut s = InlineStr::<4>::new();
+        write!(s, "ab\u{e4}").unwrap();
+        assert_eq!("ab\u{e4}", s.as_str());
+        write!(s, "cd").unwrap();
+        assert_eq!("ab\u{e4}", s.as_str());
+
+        let mut s = InlineStr::<4>::new();
+        write!(s, "abc\u{e4}").unwrap();
+        assert_eq!("abc", s.as_str());
+    }
+
+    #[test]
+    fn test_split_priority() {
+        assert_eq!((Some(Level::Error), "a"), split_priority("<2>a"));
+        assert_eq!((Some(Level::Warn), ""), split_priority("<4>"));
+        assert_eq!((Some(Level::Info), "<b>"), split_priority("<14><b>"));
+        assert_eq!((Some(Level::Debug), " c"), split_priority("<7> c"));
+        assert_eq!((None, "<x>d"), split_priority("<x>d"));
+        assert_eq!((None, "<3"), split_priority("<3"));
+        assert_eq!((None, "e"), split_priority("e"));
+    }
+
+    #[test]
+    fn test_kmsg_format() {
+        let mut s = String::new();
+        record("a\\b\nc").write_kmsg(&mut s).unwrap();
+        assert_eq!(
+            "4,7,1500002,-;a\\x5cb\\x0ac\n TARGET=kernel::test\n CPU=1\n",
+            s
+        );
+    }
+
+    #[test]
+    fn test_syslog_format() {
+        let mut s = String::new();
+        record("hello").write_syslog(&mut s).unwrap();
+        assert_eq!("<4>[    1.500002] hello\n", s);
+    }
+}
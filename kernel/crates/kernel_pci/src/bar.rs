@@ -0,0 +1,120 @@
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+/// A decoded base address register of a PCI function.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Bar {
+    Io { port: u32 },
+    Memory32 { address: u32, prefetchable: bool },
+    Memory64 { address: u64, prefetchable: bool },
+}
+
+impl Display for Bar {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Bar::Io { port } => write!(f, "io {port:#x}"),
+            Bar::Memory32 {
+                address,
+                prefetchable,
+            } => write!(
+                f,
+                "mem32 {address:#x}{}",
+                if *prefetchable { " prefetchable" } else { "" }
+            ),
+            Bar::Memory64 {
+                address,
+                prefetchable,
+            } => write!(
+                f,
+                "mem64 {address:#x}{}",
+                if *prefetchable { " prefetchable" } else { "" }
+            ),
+        }
+    }
+}
+
+impl Bar {
+    /// Decodes the raw values of consecutive base address registers,
+    /// returning every non-empty BAR together with its index. A 64-bit
+    /// memory BAR consumes the following register as its upper half.
+    #[must_use]
+    pub fn decode_all(raw: &[u32]) -> Vec<(u8, Bar)> {
+        let mut bars = Vec::new();
+        let mut index = 0;
+        while index < raw.len() {
+            let value = raw[index];
+            let bar = if value & 1 == 1 {
+                Bar::Io {
+                    port: value & !0b11,
+                }
+            } else {
+                let prefetchable = value & 0b1000 != 0;
+                let low = value & !0b1111;
+                if (value >> 1) & 0b11 == 0b10 {
+                    let high = raw.get(index + 1).copied().unwrap_or(0);
+                    Bar::Memory64 {
+                        address: (u64::from(high) << 32) | u64::from(low),
+                        prefetchable,
+                    }
+                } else {
+                    Bar::Memory32 {
+                        address: low,
+                        prefetchable,
+                    }
+                }
+            };
+
+            let is_empty = match bar {
+                Bar::Io { port } => port == 0,
+                Bar::Memory32 { address, .. } => address == 0,
+                Bar::Memory64 { address, .. } => address == 0,
+            };
+            if !is_empty {
+                bars.push((index as u8, bar));
+            }
+            index += if matches!(bar, Bar::Memory64 { .. }) {
+                2
+            } else {
+                1
+            };
+        }
+        bars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+
+    #[test]
+    fn test_decode_all() {
+        let raw = [0xc041, 0xfebd_1000, 0x0000_000c, 0x0000_0001, 0, 0];
+        assert_eq!(
+            vec![
+                (0, Bar::Io { port: 0xc040 }),
+                (
+                    1,
+                    Bar::Memory32 {
+                        address: 0xfebd_1000,
+                        prefetchable: false
+                    }
+                ),
+                (
+                    2,
+                    Bar::Memory64 {
+                        address: 0x1_0000_0000,
+                        prefetchable: true
+                    }
+                ),
+            ],
+            Bar::decode_all(&raw)
+        );
+    }
+
+    #[test]
+    fn test_decode_all_empty() {
+        assert!(Bar::decode_all(&[0; 6]).is_empty());
+    }
+}
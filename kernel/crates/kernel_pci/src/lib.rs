@@ -1,12 +1,16 @@
 #![no_std]
 extern crate alloc;
 
+use alloc::vec::Vec;
 use core::fmt::Display;
 
 use crate::config::{ConfigKey, ReadConfig};
 
+mod bar;
 pub mod config;
 
+pub use bar::*;
+
 /// The description of a pci address consisting of bus, device and function.
 /// A pci address does not imply that a device is present at that address.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -53,8 +57,38 @@ impl PciAddress {
     getter!(bar4: u32 = ConfigKey::BAR4);
     getter!(bar5: u32 = ConfigKey::BAR5);
     getter!(subsystem_id: u16 = ConfigKey::SUBSYSTEM_ID);
+    getter!(revision_id: u8 = ConfigKey::REVISION_ID);
+    getter!(prog_if: u8 = ConfigKey::PROG_IF);
+    getter!(subclass: u8 = ConfigKey::SUBCLASS);
+    getter!(class: u8 = ConfigKey::CLASS);
+    getter!(interrupt_line: u8 = ConfigKey::INTERRUPT_LINE);
+    getter!(interrupt_pin: u8 = ConfigKey::INTERRUPT_PIN);
 
     pub fn is_multifunction<C: ReadConfig<u8>>(&self, config: &C) -> bool {
         self.header_type(config) & 0x80 != 0
     }
+
+    /// Reads and decodes the base address registers of the function,
+    /// together with their index. Unused registers are skipped, and the
+    /// upper half of a 64-bit memory BAR is not reported separately.
+    ///
+    /// This only reads the registers, so the size of the regions is not
+    /// known.
+    pub fn bars<C: ReadConfig<u8> + ReadConfig<u32> + ?Sized>(&self, config: &C) -> Vec<(u8, Bar)> {
+        let raw = [
+            self.bar0(config),
+            self.bar1(config),
+            self.bar2(config),
+            self.bar3(config),
+            self.bar4(config),
+            self.bar5(config),
+        ];
+        // PCI-to-PCI bridges only have two BARs, CardBus bridges none
+        let count = match self.header_type(config) & 0x7F {
+            0x00 => 6,
+            0x01 => 2,
+            _ => 0,
+        };
+        Bar::decode_all(&raw[..count])
+    }
 }
@@ -56,6 +56,27 @@ impl PhysicalMemoryManager {
         }
     }
 
+    /// Returns the number of 4 KiB frames that are usable, whether they are
+    /// allocated or not.
+    #[must_use]
+    pub fn usable_frames(&self) -> usize {
+        self.count_frames(FrameState::is_usable)
+    }
+
+    /// Returns the number of 4 KiB frames that are free.
+    #[must_use]
+    pub fn free_frames(&self) -> usize {
+        self.count_frames(|state| state == FrameState::Free)
+    }
+
+    fn count_frames(&self, f: impl Fn(FrameState) -> bool) -> usize {
+        self.regions
+            .iter()
+            .flat_map(MemoryRegion::frames)
+            .filter(|&&state| f(state))
+            .count()
+    }
+
     /// Find the region and local index for a given physical address
     fn find_frame_location(regions: &[MemoryRegion], addr: u64) -> Option<RegionFrameIndex> {
         for (region_idx, region) in regions.iter().enumerate() {
@@ -334,6 +355,26 @@ mod tests {
         assert_eq!(&states[..], pmm.regions[0].frames());
     }
 
+    #[test]
+    fn test_frame_counts() {
+        let states = vec![
+            FrameState::Free,
+            FrameState::Allocated,
+            FrameState::Unusable,
+            FrameState::Free,
+        ];
+        let mut pmm = PhysicalMemoryManager::new(vec![
+            MemoryRegion::with_frames(0, states),
+            MemoryRegion::new(0x10_0000, 2, FrameState::Free),
+        ]);
+        assert_eq!(5, pmm.usable_frames());
+        assert_eq!(4, pmm.free_frames());
+
+        let _: PhysFrame<Size4KiB> = pmm.allocate_frame().unwrap();
+        assert_eq!(5, pmm.usable_frames());
+        assert_eq!(3, pmm.free_frames());
+    }
+
     #[test]
     fn test_new_no_frames() {
         let pmm = PhysicalMemoryManager::new(vec![]);
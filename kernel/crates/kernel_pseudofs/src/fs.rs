@@ -0,0 +1,236 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+use kernel_vfs::fs::{DirEntry, FileSystem, FsHandle};
+use kernel_vfs::path::{AbsolutePath, OwnedPath};
+use kernel_vfs::{
+    CloseError, FileType, FsError, OpenError, ReadDirError, ReadError, ReadLinkError, Stat,
+    StatError, WriteError,
+};
+
+use crate::{Provider, PseudoNode};
+
+/// A read-only [`FileSystem`] that asks a [`Provider`] for its nodes.
+pub struct PseudoFs<P> {
+    provider: P,
+    open_files: BTreeMap<FsHandle, OpenNode>,
+    next_handle: u64,
+}
+
+struct OpenNode {
+    node: PseudoNode,
+    ino: u64,
+}
+
+impl<P> PseudoFs<P>
+where
+    P: Provider,
+{
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            open_files: BTreeMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    fn open_node(&self, handle: FsHandle) -> Result<&OpenNode, FsError> {
+        self.open_files.get(&handle).ok_or(FsError::InvalidHandle)
+    }
+}
+
+impl<P> FileSystem for PseudoFs<P>
+where
+    P: Provider,
+{
+    fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
+        let node = self.provider.node(path).ok_or(OpenError::NotFound)?;
+
+        let handle = FsHandle::from(self.next_handle);
+        self.next_handle += 1;
+        self.open_files.insert(
+            handle,
+            OpenNode {
+                node,
+                ino: inode_number(path),
+            },
+        );
+        Ok(handle)
+    }
+
+    fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
+        self.open_files
+            .remove(&handle)
+            .map(|_| ())
+            .ok_or(CloseError::NotOpen)
+    }
+
+    fn read(
+        &mut self,
+        handle: FsHandle,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<usize, ReadError> {
+        let PseudoNode::File(data) = &self.open_node(handle)?.node else {
+            return Err(ReadError::NotReadable);
+        };
+        if offset >= data.len() {
+            return Err(ReadError::EndOfFile);
+        }
+
+        let n = buf.len().min(data.len() - offset);
+        buf[..n].copy_from_slice(&data[offset..offset + n]);
+        Ok(n)
+    }
+
+    fn write(
+        &mut self,
+        handle: FsHandle,
+        _buf: &[u8],
+        _offset: usize,
+    ) -> Result<usize, WriteError> {
+        self.open_node(handle)?;
+        Err(WriteError::NotWritable)
+    }
+
+    fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
+        let open = self.open_node(handle)?;
+        let (file_type, mode, nlink, size) = match &open.node {
+            PseudoNode::Directory(_) => (FileType::Directory, 0o555, 2, 0),
+            PseudoNode::File(data) => (FileType::RegularFile, 0o444, 1, data.len()),
+            PseudoNode::Symlink(target) => (FileType::Symlink, 0o777, 1, target.len()),
+        };
+        *stat = Stat {
+            file_type,
+            mode,
+            nlink,
+            ino: open.ino,
+            size,
+            ..Stat::default()
+        };
+        Ok(())
+    }
+
+    fn read_dir(&mut self, handle: FsHandle) -> Result<Vec<DirEntry>, ReadDirError> {
+        match &self.open_node(handle)?.node {
+            PseudoNode::Directory(entries) => Ok(entries.clone()),
+            _ => Err(ReadDirError::NotADirectory),
+        }
+    }
+
+    fn readlink(&mut self, path: &AbsolutePath) -> Result<OwnedPath, ReadLinkError> {
+        match self.provider.node(path) {
+            Some(PseudoNode::Symlink(target)) => Ok(target),
+            Some(_) => Err(ReadLinkError::NotASymlink),
+            None => Err(ReadLinkError::NotFound),
+        }
+    }
+}
+
+/// Derives a stable inode number from the path with FNV-1a, since pseudo
+/// nodes have no identity of their own.
+fn inode_number(path: &AbsolutePath) -> u64 {
+    path.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{format, vec};
+    use core::sync::atomic::AtomicUsize;
+    use core::sync::atomic::Ordering::Relaxed;
+
+    use kernel_vfs::fs::FileSystem;
+    use kernel_vfs::path::AbsolutePath;
+    use kernel_vfs::{FileType, ReadError, ReadLinkError, Stat, WriteError};
+
+    use crate::{Provider, PseudoFs, PseudoNode, entry};
+
+    /// Provides `/counter`, which counts how often it was generated, and
+    /// `/link`, which points to it.
+    #[derive(Default)]
+    struct CounterProvider {
+        counter: AtomicUsize,
+    }
+
+    impl Provider for CounterProvider {
+        fn node(&self, path: &AbsolutePath) -> Option<PseudoNode> {
+            let path: &str = path;
+            match path {
+                "/" => Some(PseudoNode::Directory(vec![
+                    entry("counter", FileType::RegularFile),
+                    entry("link", FileType::Symlink),
+                ])),
+                "/counter" => {
+                    let n = self.counter.fetch_add(1, Relaxed);
+                    Some(PseudoNode::file(format!("{n}\n")))
+                }
+                "/link" => Some(PseudoNode::symlink("counter".into())),
+                _ => None,
+            }
+        }
+    }
+
+    fn path(s: &str) -> &AbsolutePath {
+        AbsolutePath::try_new(s).unwrap()
+    }
+
+    #[test]
+    fn test_read() {
+        let mut fs = PseudoFs::new(CounterProvider::default());
+        let first = fs.open(path("/counter")).unwrap();
+        let second = fs.open(path("/counter")).unwrap();
+
+        let mut buf = [0; 8];
+        assert_eq!(Ok(2), fs.read(first, &mut buf, 0));
+        assert_eq!(b"0\n", &buf[..2]);
+        assert_eq!(Err(ReadError::EndOfFile), fs.read(first, &mut buf, 2));
+        // the contents are a snapshot taken when opening
+        assert_eq!(Ok(1), fs.read(second, &mut buf[..1], 0));
+        assert_eq!(b"1", &buf[..1]);
+        assert_eq!(Ok(2), fs.read(first, &mut buf, 0));
+        assert_eq!(b"0\n", &buf[..2]);
+
+        assert_eq!(Err(WriteError::NotWritable), fs.write(first, b"x", 0));
+        assert!(fs.open(path("/missing")).is_err());
+    }
+
+    #[test]
+    fn test_stat_and_read_dir() {
+        let mut fs = PseudoFs::new(CounterProvider::default());
+        let root = fs.open(path("/")).unwrap();
+        let mut stat = Stat::default();
+        fs.stat(root, &mut stat).unwrap();
+        assert_eq!(FileType::Directory, stat.file_type);
+        assert_eq!(0o555, stat.mode);
+        assert_eq!(2, fs.read_dir(root).unwrap().len());
+
+        let link = fs.open(path("/link")).unwrap();
+        fs.stat(link, &mut stat).unwrap();
+        assert_eq!(FileType::Symlink, stat.file_type);
+        let ino = stat.ino;
+        let counter = fs.open(path("/counter")).unwrap();
+        fs.stat(counter, &mut stat).unwrap();
+        assert_eq!((FileType::RegularFile, 2), (stat.file_type, stat.size));
+        assert_ne!(ino, stat.ino);
+    }
+
+    #[test]
+    fn test_readlink() {
+        let mut fs = PseudoFs::new(CounterProvider::default());
+        assert_eq!(
+            Ok("counter"),
+            fs.readlink(path("/link")).as_ref().map(|p| p.as_str())
+        );
+        assert_eq!(
+            Err(ReadLinkError::NotASymlink),
+            fs.readlink(path("/counter"))
+        );
+        assert_eq!(Err(ReadLinkError::NotFound), fs.readlink(path("/nope")));
+    }
+}
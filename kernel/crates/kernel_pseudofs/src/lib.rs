@@ -0,0 +1,56 @@
+//! Read-only file systems whose contents are generated on demand by a
+//! [`Provider`], such as procfs.
+#![no_std]
+extern crate alloc;
+
+mod fs;
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+pub use fs::*;
+use kernel_vfs::FileType;
+use kernel_vfs::fs::DirEntry;
+use kernel_vfs::path::{AbsolutePath, OwnedPath};
+
+/// A node of a pseudo file system, as generated by a [`Provider`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PseudoNode {
+    /// A directory with the given entries, not including `.` and `..`.
+    Directory(Vec<DirEntry>),
+    /// A regular file with the given contents.
+    File(Vec<u8>),
+    /// A symbolic link to the given target.
+    Symlink(OwnedPath),
+}
+
+impl PseudoNode {
+    /// A regular file with the given text as its contents.
+    #[must_use]
+    pub fn file(content: String) -> Self {
+        Self::File(content.into_bytes())
+    }
+
+    /// A symbolic link to the given target.
+    #[must_use]
+    pub fn symlink(target: String) -> Self {
+        Self::Symlink(OwnedPath::new(target))
+    }
+}
+
+/// An entry of a [`PseudoNode::Directory`].
+#[must_use]
+pub fn entry(name: &str, file_type: FileType) -> DirEntry {
+    DirEntry {
+        name: name.to_owned(),
+        file_type,
+    }
+}
+
+pub trait Provider: Send + Sync {
+    /// Generates the node at `path`, or returns `None` if there is no such
+    /// node. This is called whenever a node is opened, so the contents of a
+    /// file are a snapshot taken when the file was opened.
+    fn node(&self, path: &AbsolutePath) -> Option<PseudoNode>;
+}
@@ -0,0 +1,88 @@
+/// "expand 32-byte k", the constant of the ChaCha state.
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+/// The number of 32-bit words in a ChaCha block.
+pub const BLOCK_WORDS: usize = 16;
+
+fn quarter_round(state: &mut [u32; BLOCK_WORDS], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// Applies the 20 rounds of ChaCha to `state` and adds the original state
+/// to the result, which makes the function one-way.
+pub fn permute(state: &mut [u32; BLOCK_WORDS]) {
+    let original = *state;
+    for _ in 0..10 {
+        quarter_round(state, 0, 4, 8, 12);
+        quarter_round(state, 1, 5, 9, 13);
+        quarter_round(state, 2, 6, 10, 14);
+        quarter_round(state, 3, 7, 11, 15);
+        quarter_round(state, 0, 5, 10, 15);
+        quarter_round(state, 1, 6, 11, 12);
+        quarter_round(state, 2, 7, 8, 13);
+        quarter_round(state, 3, 4, 9, 14);
+    }
+    for (word, original) in state.iter_mut().zip(original) {
+        *word = word.wrapping_add(original);
+    }
+}
+
+/// Computes the ChaCha20 block for the given key, block counter and nonce,
+/// as described in RFC 8439.
+#[must_use]
+pub fn chacha20_block(key: &[u32; 8], counter: u32, nonce: &[u32; 3]) -> [u32; BLOCK_WORDS] {
+    let mut state = [0; BLOCK_WORDS];
+    state[..4].copy_from_slice(&CONSTANTS);
+    state[4..12].copy_from_slice(key);
+    state[12] = counter;
+    state[13..].copy_from_slice(nonce);
+    permute(&mut state);
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The test vector from section 2.3.2 of RFC 8439.
+    #[test]
+    fn test_rfc8439_block() {
+        let key = [
+            0x0302_0100,
+            0x0706_0504,
+            0x0b0a_0908,
+            0x0f0e_0d0c,
+            0x1312_1110,
+            0x1716_1514,
+            0x1b1a_1918,
+            0x1f1e_1d1c,
+        ];
+        let nonce = [0x0900_0000, 0x4a00_0000, 0x0000_0000];
+        let expected = [
+            0xe4e7_f110,
+            0x1559_3bd1,
+            0x1fdd_0f50,
+            0xc471_20a3,
+            0xc7f4_d1c7,
+            0x0368_c033,
+            0x9aaa_2204,
+            0x4e6c_d4c3,
+            0x4664_82d2,
+            0x09aa_9f07,
+            0x05d7_c214,
+            0xa202_8bd9,
+            0xd19c_12b5,
+            0xb94e_16de,
+            0xe883_d0cb,
+            0x4e3c_50a2,
+        ];
+        assert_eq!(expected, chacha20_block(&key, 1, &nonce));
+    }
+}
@@ -0,0 +1,10 @@
+//! The random number generator of the kernel. Entropy from hardware and
+//! timing sources is mixed into an [`Rng`], which expands it into an
+//! unlimited stream of random bytes with ChaCha20.
+#![no_std]
+
+mod chacha;
+mod rng;
+
+pub use chacha::*;
+pub use rng::*;
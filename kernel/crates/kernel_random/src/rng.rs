@@ -0,0 +1,168 @@
+use crate::chacha::{BLOCK_WORDS, chacha20_block, permute};
+
+/// The entropy in bits that the generator needs before its output is
+/// considered unpredictable, and that the pool collects before the key is
+/// replaced.
+pub const SEED_BITS: usize = 256;
+
+/// The pool can't hold more entropy than its state.
+const MAX_POOL_BITS: usize = BLOCK_WORDS * 32;
+
+/// A cryptographically secure random number generator.
+///
+/// Entropy is mixed into a pool with the ChaCha permutation. Once the pool
+/// collected [`SEED_BITS`], it is compressed into a new ChaCha20 key, which
+/// generates the output. The key is replaced after every request, so that
+/// output that was already handed out can't be reconstructed from the
+/// state.
+#[derive(Debug)]
+pub struct Rng {
+    pool: [u32; BLOCK_WORDS],
+    /// The entropy in the pool that was credited since the last reseed.
+    pool_bits: usize,
+    key: [u32; 8],
+    seeded: bool,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rng {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            pool: [0; BLOCK_WORDS],
+            pool_bits: 0,
+            key: [0; 8],
+            seeded: false,
+        }
+    }
+
+    /// Whether the generator collected enough entropy for its output to be
+    /// unpredictable.
+    #[must_use]
+    pub fn is_seeded(&self) -> bool {
+        self.seeded
+    }
+
+    /// Mixes `data` into the pool and credits it with `bits` of entropy.
+    /// Data that may be predictable can be added with zero bits, which never
+    /// makes the output worse.
+    pub fn add_entropy(&mut self, data: &[u8], bits: usize) {
+        for chunk in data.chunks(32) {
+            let mut bytes = [0_u8; 32];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            let (words, _) = bytes.as_chunks::<4>();
+            for (word, bytes) in self.pool[..8].iter_mut().zip(words) {
+                *word ^= u32::from_le_bytes(*bytes);
+            }
+            // the length separates inputs that only differ in trailing zeros
+            self.pool[8] ^= chunk.len() as u32;
+            permute(&mut self.pool);
+        }
+        self.pool_bits = (self.pool_bits + bits).min(MAX_POOL_BITS);
+        if self.pool_bits >= SEED_BITS {
+            self.reseed();
+        }
+    }
+
+    /// Replaces the key with one that is derived from the old key and the
+    /// pool.
+    fn reseed(&mut self) {
+        let mut state = self.pool;
+        for (word, key) in state.iter_mut().zip(self.key) {
+            *word ^= key;
+        }
+        permute(&mut state);
+        self.key.copy_from_slice(&state[..8]);
+
+        // the pool must not reveal the new key
+        self.pool[BLOCK_WORDS - 1] ^= 1;
+        permute(&mut self.pool);
+        self.pool_bits = 0;
+        self.seeded = true;
+    }
+
+    /// Fills `buf` with random bytes. The output is only unpredictable once
+    /// the generator [is seeded](Self::is_seeded).
+    pub fn fill_bytes(&mut self, buf: &mut [u8]) {
+        let nonce = [0; 3];
+        let mut counter = 0;
+        for chunk in buf.chunks_mut(BLOCK_WORDS * 4) {
+            counter += 1;
+            let block = chacha20_block(&self.key, counter, &nonce);
+            for (dst, src) in chunk
+                .iter_mut()
+                .zip(block.iter().flat_map(|w| w.to_le_bytes()))
+            {
+                *dst = src;
+            }
+        }
+        // the block with counter 0 is never output and becomes the next key
+        let block = chacha20_block(&self.key, 0, &nonce);
+        self.key.copy_from_slice(&block[..8]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeding() {
+        let mut rng = Rng::new();
+        assert!(!rng.is_seeded());
+        rng.add_entropy(&[1; 64], 0);
+        rng.add_entropy(&[2; 16], SEED_BITS - 1);
+        assert!(!rng.is_seeded());
+        rng.add_entropy(&[3; 1], 1);
+        assert!(rng.is_seeded());
+    }
+
+    #[test]
+    fn test_output_depends_on_all_input() {
+        let output = |data: &[u8]| {
+            let mut rng = Rng::new();
+            rng.add_entropy(data, SEED_BITS);
+            let mut buf = [0; 100];
+            rng.fill_bytes(&mut buf);
+            buf
+        };
+
+        assert_eq!(output(&[1, 2, 3]), output(&[1, 2, 3]));
+        assert_ne!(output(&[1, 2, 3]), output(&[1, 2, 4]));
+        assert_ne!(output(&[1, 2, 3]), output(&[1, 2, 3, 0]));
+        assert_ne!(output(&[0; 40]), output(&[0; 41]));
+    }
+
+    #[test]
+    fn test_output_never_repeats() {
+        let mut rng = Rng::new();
+        rng.add_entropy(b"seed", SEED_BITS);
+
+        let mut first = [0; 130];
+        let mut second = [0; 130];
+        rng.fill_bytes(&mut first);
+        rng.fill_bytes(&mut second);
+        assert_ne!(first, second);
+        assert_ne!(first[..64], first[64..128]);
+        assert!(first.iter().any(|&b| b != 0));
+
+        // adding entropy without credit still changes the output, but only
+        // after the next reseed
+        let mut other = Rng::new();
+        other.add_entropy(b"seed", SEED_BITS);
+        other.add_entropy(b"more", 0);
+        let mut third = [0; 130];
+        other.fill_bytes(&mut third);
+        assert_eq!(first, third);
+        other.add_entropy(b"", SEED_BITS);
+        rng.add_entropy(b"", SEED_BITS);
+        other.fill_bytes(&mut third);
+        rng.fill_bytes(&mut second);
+        assert_ne!(second, third);
+    }
+}
@@ -0,0 +1,303 @@
+use core::time::Duration;
+
+use crate::{Entity, NICE_0_WEIGHT, Policy};
+
+/// How long real-time round-robin tasks run before another task with the
+/// same priority gets its turn.
+pub const RR_TIMESLICE: Duration = Duration::from_millis(100);
+
+/// How far the virtual runtime of a running normal task may get ahead of a
+/// ready one before it is preempted, so that tasks with the same share
+/// don't switch on every reschedule.
+pub const FAIR_GRANULARITY: Duration = Duration::from_millis(1);
+
+/// How far below the other tasks of a queue a normal task may be placed
+/// when it is woken. It runs soon, but can't catch up on all the time it
+/// was blocked.
+pub const SLEEPER_CREDIT: Duration = Duration::from_millis(3);
+
+pub(crate) static REAL_TIME: RealTimeClass = RealTimeClass;
+pub(crate) static FAIR: FairClass = FairClass;
+pub(crate) static IDLE: IdleClass = IdleClass;
+
+/// The state of the scheduling classes in one run queue.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct QueueState {
+    /// The virtual runtime of the normal task that was picked last, which
+    /// only grows. The virtual runtimes of the queued normal tasks are
+    /// close to it.
+    min_vruntime: u64,
+}
+
+impl QueueState {
+    #[must_use]
+    pub fn min_vruntime(&self) -> u64 {
+        self.min_vruntime
+    }
+}
+
+/// A scheduling class, which orders the ready tasks of its policies and
+/// decides when the running task makes way for another one of the class.
+pub trait SchedClass: Sync {
+    /// Tasks of a class with a lower rank run before tasks of classes with
+    /// a higher rank, and preempt them.
+    fn rank(&self) -> u8;
+
+    /// Whether `entity` runs before `other`. Tasks that run before neither
+    /// of each other run in the order that they were queued in.
+    fn runs_before(&self, entity: &Entity, other: &Entity) -> bool;
+
+    /// Whether the running task `current`, which ran for `ran` since it was
+    /// switched to, is preempted by the ready task `next`.
+    fn preempts(&self, current: &Entity, ran: Duration, next: &Entity) -> bool;
+
+    /// Whether the running task `current` makes way for the ready task
+    /// `next` when it yields.
+    fn yields_to(&self, _current: &Entity, _next: &Entity) -> bool {
+        true
+    }
+
+    /// Accounts that `entity` ran for `ran`.
+    fn charge(&self, _entity: &mut Entity, _ran: Duration) {}
+
+    /// Prepares `entity` for being queued in the run queue with `queue`.
+    /// `migrated` is set if the task ran on another CPU last, or didn't run
+    /// at all yet.
+    fn place(&self, _entity: &mut Entity, _queue: &QueueState, _migrated: bool) {}
+
+    /// Accounts that `entity` was taken from the run queue with `queue` to
+    /// run.
+    fn pick(&self, _entity: &Entity, _queue: &mut QueueState) {}
+}
+
+/// The class of [`Policy::Fifo`] and [`Policy::RoundRobin`]. The task with
+/// the highest priority runs, and only makes way for a task with the same
+/// priority if it is a round-robin task that used up its time slice.
+#[derive(Debug)]
+pub struct RealTimeClass;
+
+impl SchedClass for RealTimeClass {
+    fn rank(&self) -> u8 {
+        0
+    }
+
+    fn runs_before(&self, entity: &Entity, other: &Entity) -> bool {
+        entity.params.priority() > other.params.priority()
+    }
+
+    fn preempts(&self, current: &Entity, ran: Duration, next: &Entity) -> bool {
+        let (current_priority, next_priority) = (current.params.priority(), next.params.priority());
+        next_priority > current_priority
+            || (next_priority == current_priority
+                && current.params.policy() == Policy::RoundRobin
+                && ran >= RR_TIMESLICE)
+    }
+
+    fn yields_to(&self, current: &Entity, next: &Entity) -> bool {
+        next.params.priority() >= current.params.priority()
+    }
+}
+
+/// The class of [`Policy::Normal`]. The task that ran the least, weighted
+/// with its nice value, runs, so that every task gets a share of the CPU
+/// time that is proportional to the weight of its nice value.
+#[derive(Debug)]
+pub struct FairClass;
+
+impl SchedClass for FairClass {
+    fn rank(&self) -> u8 {
+        1
+    }
+
+    fn runs_before(&self, entity: &Entity, other: &Entity) -> bool {
+        entity.vruntime < other.vruntime
+    }
+
+    fn preempts(&self, current: &Entity, _ran: Duration, next: &Entity) -> bool {
+        current.vruntime > next.vruntime.saturating_add(nanos(FAIR_GRANULARITY))
+    }
+
+    fn charge(&self, entity: &mut Entity, ran: Duration) {
+        let weighted = nanos(ran).saturating_mul(NICE_0_WEIGHT) / entity.params.nice().weight();
+        entity.vruntime = entity.vruntime.saturating_add(weighted);
+    }
+
+    /// A task from another CPU starts with the tasks of this queue, since
+    /// the virtual runtimes of different queues can't be compared. A task
+    /// that was blocked gets at most [`SLEEPER_CREDIT`].
+    fn place(&self, entity: &mut Entity, queue: &QueueState, migrated: bool) {
+        entity.vruntime = if migrated {
+            queue.min_vruntime
+        } else {
+            entity
+                .vruntime
+                .max(queue.min_vruntime.saturating_sub(nanos(SLEEPER_CREDIT)))
+        };
+    }
+
+    fn pick(&self, entity: &Entity, queue: &mut QueueState) {
+        queue.min_vruntime = queue.min_vruntime.max(entity.vruntime);
+    }
+}
+
+/// The class of [`Policy::Idle`]. Its tasks only run if no other task is
+/// ready, and take turns on every reschedule.
+#[derive(Debug)]
+pub struct IdleClass;
+
+impl SchedClass for IdleClass {
+    fn rank(&self) -> u8 {
+        2
+    }
+
+    fn runs_before(&self, _entity: &Entity, _other: &Entity) -> bool {
+        false
+    }
+
+    fn preempts(&self, _current: &Entity, _ran: Duration, _next: &Entity) -> bool {
+        true
+    }
+}
+
+fn nanos(duration: Duration) -> u64 {
+    u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::collections::VecDeque;
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::{Nice, Params};
+
+    const TICK: Duration = Duration::from_millis(1);
+
+    fn entity(policy: Policy, priority: i32, nice: i32) -> Entity {
+        let params = Params::new(policy, priority, Nice::new_clamped(nice)).unwrap();
+        Entity::new(params, 0)
+    }
+
+    /// Runs `entities` on one CPU for `ticks` ticks, the way the kernel
+    /// does, and returns for how many ticks each of them ran.
+    fn simulate(entities: &[Entity], ticks: usize) -> Vec<usize> {
+        let mut entities = entities.to_vec();
+        let mut queue = QueueState::default();
+        let mut ready = (1..entities.len()).collect::<VecDeque<_>>();
+        let mut current = 0;
+        let mut ran = Duration::ZERO;
+        let mut ticks_run = alloc::vec![0; entities.len()];
+
+        for _ in 0..ticks {
+            ticks_run[current] += 1;
+            ran += TICK;
+            let entity = &mut entities[current];
+            entity.class().charge(entity, TICK);
+
+            let Some(best) = (0..ready.len()).reduce(|best, i| {
+                if entities[ready[i]].runs_before(&entities[ready[best]]) {
+                    i
+                } else {
+                    best
+                }
+            }) else {
+                continue;
+            };
+            if entities[current].is_preempted_by(ran, &entities[ready[best]]) {
+                let next = ready.remove(best).unwrap();
+                let entity = &mut entities[current];
+                entity.class().place(entity, &queue, false);
+                ready.push_back(current);
+                entities[next].class().pick(&entities[next], &mut queue);
+                current = next;
+                ran = Duration::ZERO;
+            }
+        }
+        ticks_run
+    }
+
+    #[test]
+    fn test_fair_share() {
+        let ticks = simulate(&[entity(Policy::Normal, 0, 0); 3], 3000);
+        assert!(
+            ticks.iter().all(|&ticks| ticks.abs_diff(1000) <= 2),
+            "{ticks:?}"
+        );
+
+        // nice 5 gets about a third of the time of nice 0
+        let ticks = simulate(
+            &[entity(Policy::Normal, 0, 0), entity(Policy::Normal, 0, 5)],
+            10_000,
+        );
+        let expected = 10_000 * 1024 / (1024 + 335);
+        assert!(ticks[0].abs_diff(expected) <= 10, "{ticks:?}");
+    }
+
+    #[test]
+    fn test_real_time_starves_normal() {
+        let ticks = simulate(
+            &[entity(Policy::Normal, 0, -20), entity(Policy::Fifo, 1, 0)],
+            1000,
+        );
+        assert_eq!(1, ticks[0]);
+        assert_eq!(999, ticks[1]);
+
+        let ticks = simulate(
+            &[entity(Policy::Normal, 0, 0), entity(Policy::Idle, 0, 0)],
+            1000,
+        );
+        assert_eq!([1000, 0], ticks[..]);
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let slice = usize::try_from(RR_TIMESLICE.as_millis()).unwrap();
+
+        // round-robin tasks take turns after a time slice
+        let rr = entity(Policy::RoundRobin, 10, 0);
+        assert!(!rr.is_preempted_by(RR_TIMESLICE - TICK, &rr));
+        assert!(rr.is_preempted_by(RR_TIMESLICE, &rr));
+        let ticks = simulate(&[rr; 2], 4 * slice);
+        assert_eq!([2 * slice; 2], ticks[..]);
+
+        // FIFO tasks don't
+        let fifo = entity(Policy::Fifo, 10, 0);
+        let ticks = simulate(&[fifo; 2], 4 * slice);
+        assert_eq!([4 * slice, 0], ticks[..]);
+
+        // and neither does a lower priority
+        let ticks = simulate(&[entity(Policy::RoundRobin, 20, 0), rr], 4 * slice);
+        assert_eq!([4 * slice, 0], ticks[..]);
+        assert!(rr.is_preempted_by(Duration::ZERO, &entity(Policy::Fifo, 11, 0)));
+    }
+
+    #[test]
+    fn test_placement() {
+        let mut queue = QueueState::default();
+        let mut picked = entity(Policy::Normal, 0, 0);
+        picked.vruntime = 100_000_000;
+        FAIR.pick(&picked, &mut queue);
+        assert_eq!(100_000_000, queue.min_vruntime());
+
+        // a woken task gets a bounded credit
+        let mut woken = entity(Policy::Normal, 0, 0);
+        FAIR.place(&mut woken, &queue, false);
+        assert_eq!(100_000_000 - nanos(SLEEPER_CREDIT), woken.vruntime);
+
+        // a task that is ahead stays ahead
+        let mut ahead = entity(Policy::Normal, 0, 0);
+        ahead.vruntime = 200_000_000;
+        FAIR.place(&mut ahead, &queue, false);
+        assert_eq!(200_000_000, ahead.vruntime);
+
+        // a task from another CPU starts with the others
+        FAIR.place(&mut ahead, &queue, true);
+        assert_eq!(100_000_000, ahead.vruntime);
+
+        // the minimum never decreases
+        FAIR.pick(&woken, &mut queue);
+        assert_eq!(100_000_000, queue.min_vruntime());
+    }
+}
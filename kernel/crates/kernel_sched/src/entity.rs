@@ -0,0 +1,108 @@
+use core::cmp::Ordering;
+use core::time::Duration;
+
+use crate::{Params, SchedClass};
+
+/// What the scheduling classes know about a task.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Entity {
+    pub params: Params,
+    /// The time that the task ran in nanoseconds, weighted with its nice
+    /// value. Only the fair class uses this.
+    pub vruntime: u64,
+}
+
+impl Entity {
+    #[must_use]
+    pub fn new(params: Params, vruntime: u64) -> Self {
+        Self { params, vruntime }
+    }
+
+    #[must_use]
+    pub fn class(&self) -> &'static dyn SchedClass {
+        self.params.policy().class()
+    }
+
+    /// Whether `self` should run before `other` if both are ready. Tasks of
+    /// a class with a lower rank always run first.
+    #[must_use]
+    pub fn runs_before(&self, other: &Self) -> bool {
+        let class = self.class();
+        match class.rank().cmp(&other.class().rank()) {
+            Ordering::Less => true,
+            Ordering::Greater => false,
+            Ordering::Equal => class.runs_before(self, other),
+        }
+    }
+
+    /// Whether the running task `self`, which ran for `ran` since it was
+    /// switched to, should make way for the ready task `next`.
+    #[must_use]
+    pub fn is_preempted_by(&self, ran: Duration, next: &Self) -> bool {
+        let class = self.class();
+        match class.rank().cmp(&next.class().rank()) {
+            Ordering::Less => false,
+            Ordering::Greater => true,
+            Ordering::Equal => class.preempts(self, ran, next),
+        }
+    }
+
+    /// Whether the running task `self` makes way for the ready task `next`
+    /// when it yields. It never does for a task of a class that only runs
+    /// when its own class has nothing to run.
+    #[must_use]
+    pub fn yields_to(&self, next: &Self) -> bool {
+        let class = self.class();
+        match class.rank().cmp(&next.class().rank()) {
+            Ordering::Less => false,
+            Ordering::Greater => true,
+            Ordering::Equal => class.yields_to(self, next),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Nice, Policy};
+
+    fn entity(policy: Policy, priority: i32) -> Entity {
+        let params = Params::new(policy, priority, Nice::default()).unwrap();
+        Entity::new(params, 0)
+    }
+
+    #[test]
+    fn test_classes_run_in_order() {
+        let high = entity(Policy::Fifo, 50);
+        let low = entity(Policy::RoundRobin, 10);
+        let normal = entity(Policy::Normal, 0);
+        let idle = entity(Policy::Idle, 0);
+
+        assert!(high.runs_before(&low));
+        assert!(!low.runs_before(&high));
+        assert!(low.runs_before(&normal));
+        assert!(normal.runs_before(&idle));
+        assert!(!idle.runs_before(&normal));
+
+        let long = Duration::from_secs(10);
+        assert!(normal.is_preempted_by(Duration::ZERO, &low));
+        assert!(idle.is_preempted_by(Duration::ZERO, &normal));
+        assert!(!low.is_preempted_by(long, &normal));
+        assert!(!normal.is_preempted_by(long, &idle));
+    }
+
+    #[test]
+    fn test_yield() {
+        let fifo = entity(Policy::Fifo, 10);
+        let normal = entity(Policy::Normal, 0);
+        let idle = entity(Policy::Idle, 0);
+
+        assert!(normal.yields_to(&normal));
+        assert!(normal.yields_to(&fifo));
+        assert!(!normal.yields_to(&idle));
+        // a real-time task only yields to the same or a higher priority
+        assert!(fifo.yields_to(&entity(Policy::RoundRobin, 10)));
+        assert!(!fifo.yields_to(&entity(Policy::RoundRobin, 9)));
+        assert!(!fifo.yields_to(&normal));
+    }
+}
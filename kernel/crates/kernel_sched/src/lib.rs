@@ -0,0 +1,14 @@
+//! The scheduling policies of the kernel. The [`Policy`] of a task puts it
+//! into a [`SchedClass`], which orders the ready tasks of the class and
+//! decides when the running task is preempted. Real-time tasks run before
+//! the fair share of the normal tasks, and idle tasks only run if neither
+//! is ready. The run queues themselves belong to the kernel.
+#![no_std]
+
+mod class;
+mod entity;
+mod params;
+
+pub use class::*;
+pub use entity::*;
+pub use params::*;
@@ -0,0 +1,195 @@
+use core::ffi::c_int;
+
+use kernel_abi::{
+    NICE_MAX, NICE_MIN, SCHED_FIFO, SCHED_IDLE, SCHED_OTHER, SCHED_PRIORITY_MAX,
+    SCHED_PRIORITY_MIN, SCHED_RR,
+};
+
+use crate::{FAIR, IDLE, REAL_TIME, SchedClass};
+
+/// The weight of a task with nice value zero.
+pub const NICE_0_WEIGHT: u64 = 1024;
+
+/// The weights of the nice values from -20 to 19, the same as on Linux.
+/// Every step is worth about 10% of CPU time, so that the weight of two
+/// tasks that are one nice value apart differs by about 25%.
+const NICE_WEIGHTS: [u64; 40] = [
+    88761, 71755, 56483, 46273, 36291, 29154, 23254, 18705, 14949, 11916, 9548, 7620, 6100, 4904,
+    3906, 3121, 2501, 1991, 1586, 1277, 1024, 820, 655, 526, 423, 335, 272, 215, 172, 137, 110, 87,
+    70, 56, 45, 36, 29, 23, 18, 15,
+];
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidParams;
+
+/// How a task is scheduled, see [`Policy::class`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Policy {
+    /// Shares the CPUs with the other normal tasks according to the nice
+    /// values.
+    #[default]
+    Normal,
+    /// Runs until it blocks or yields, unless a task with a higher priority
+    /// becomes ready.
+    Fifo,
+    /// Like [`Policy::Fifo`], but tasks with the same priority take turns.
+    RoundRobin,
+    /// Only runs if no other task is ready.
+    Idle,
+}
+
+impl Policy {
+    #[must_use]
+    pub fn class(self) -> &'static dyn SchedClass {
+        match self {
+            Self::Normal => &FAIR,
+            Self::Fifo | Self::RoundRobin => &REAL_TIME,
+            Self::Idle => &IDLE,
+        }
+    }
+
+    /// Whether tasks with this policy have a real-time priority.
+    #[must_use]
+    pub fn is_real_time(self) -> bool {
+        matches!(self, Self::Fifo | Self::RoundRobin)
+    }
+}
+
+impl TryFrom<c_int> for Policy {
+    type Error = InvalidParams;
+
+    fn try_from(policy: c_int) -> Result<Self, Self::Error> {
+        match policy {
+            SCHED_OTHER => Ok(Self::Normal),
+            SCHED_FIFO => Ok(Self::Fifo),
+            SCHED_RR => Ok(Self::RoundRobin),
+            SCHED_IDLE => Ok(Self::Idle),
+            _ => Err(InvalidParams),
+        }
+    }
+}
+
+impl From<Policy> for c_int {
+    fn from(policy: Policy) -> Self {
+        match policy {
+            Policy::Normal => SCHED_OTHER,
+            Policy::Fifo => SCHED_FIFO,
+            Policy::RoundRobin => SCHED_RR,
+            Policy::Idle => SCHED_IDLE,
+        }
+    }
+}
+
+/// A nice value from -20 to 19. Tasks with a lower nice value get more CPU
+/// time.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Nice(i8);
+
+impl Nice {
+    /// Clamps `nice` to the valid range, like `setpriority` does.
+    #[must_use]
+    pub fn new_clamped(nice: c_int) -> Self {
+        Self(i8::try_from(nice.clamp(NICE_MIN, NICE_MAX)).expect("nice should be in range"))
+    }
+
+    #[must_use]
+    pub fn get(self) -> c_int {
+        c_int::from(self.0)
+    }
+
+    /// The share of the CPU time of a task with this nice value, relative to
+    /// [`NICE_0_WEIGHT`].
+    #[must_use]
+    pub fn weight(self) -> u64 {
+        NICE_WEIGHTS[usize::try_from(self.get() - NICE_MIN).expect("nice should be in range")]
+    }
+}
+
+/// The scheduling parameters of a task.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Params {
+    policy: Policy,
+    /// The real-time priority, or zero if the policy isn't real-time.
+    priority: u8,
+    /// The nice value, which is kept when the task switches to another
+    /// policy and back.
+    nice: Nice,
+}
+
+impl Params {
+    /// # Errors
+    /// Returns an error if `priority` isn't valid for `policy`. Real-time
+    /// tasks have a priority from 1 to 99, and all other tasks have
+    /// priority zero.
+    pub fn new(policy: Policy, priority: c_int, nice: Nice) -> Result<Self, InvalidParams> {
+        let valid = if policy.is_real_time() {
+            (SCHED_PRIORITY_MIN..=SCHED_PRIORITY_MAX).contains(&priority)
+        } else {
+            priority == 0
+        };
+        if !valid {
+            return Err(InvalidParams);
+        }
+        Ok(Self {
+            policy,
+            priority: u8::try_from(priority).map_err(|_| InvalidParams)?,
+            nice,
+        })
+    }
+
+    #[must_use]
+    pub fn policy(self) -> Policy {
+        self.policy
+    }
+
+    #[must_use]
+    pub fn priority(self) -> u8 {
+        self.priority
+    }
+
+    #[must_use]
+    pub fn nice(self) -> Nice {
+        self.nice
+    }
+
+    #[must_use]
+    pub fn with_nice(self, nice: Nice) -> Self {
+        Self { nice, ..self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_validation() {
+        let nice = Nice::default();
+        assert!(Params::new(Policy::Fifo, 1, nice).is_ok());
+        assert!(Params::new(Policy::RoundRobin, 99, nice).is_ok());
+        assert!(Params::new(Policy::Normal, 0, nice).is_ok());
+        assert!(Params::new(Policy::Idle, 0, nice).is_ok());
+
+        assert_eq!(Err(InvalidParams), Params::new(Policy::Fifo, 0, nice));
+        assert_eq!(
+            Err(InvalidParams),
+            Params::new(Policy::RoundRobin, 100, nice)
+        );
+        assert_eq!(Err(InvalidParams), Params::new(Policy::Normal, 1, nice));
+        assert_eq!(Err(InvalidParams), Params::new(Policy::Idle, -1, nice));
+
+        assert_eq!(Ok(Policy::RoundRobin), Policy::try_from(SCHED_RR));
+        assert_eq!(Err(InvalidParams), Policy::try_from(3));
+        assert_eq!(SCHED_IDLE, c_int::from(Policy::Idle));
+    }
+
+    #[test]
+    fn test_nice() {
+        assert_eq!(-20, Nice::new_clamped(-100).get());
+        assert_eq!(19, Nice::new_clamped(20).get());
+        assert_eq!(NICE_0_WEIGHT, Nice::default().weight());
+        assert_eq!(88761, Nice::new_clamped(-20).weight());
+        assert_eq!(15, Nice::new_clamped(19).weight());
+        assert!(Nice::new_clamped(-1) < Nice::new_clamped(1));
+    }
+}
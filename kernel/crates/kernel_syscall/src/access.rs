@@ -1,9 +1,25 @@
 mod cwd;
 mod file;
+mod futex;
+mod log;
 mod mem;
+mod mount;
+mod poll;
+mod random;
 mod region;
+mod sched;
+mod socket;
+mod thread;
 
 pub use cwd::*;
 pub use file::*;
+pub use futex::*;
+pub use log::*;
 pub use mem::*;
+pub use mount::*;
+pub use poll::*;
+pub use random::*;
 pub use region::*;
+pub use sched::*;
+pub use socket::*;
+pub use thread::*;
@@ -1,6 +1,20 @@
-use kernel_vfs::path::AbsoluteOwnedPath;
+use alloc::borrow::ToOwned;
+
+use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, Path};
 use spin::RwLock;
 
 pub trait CwdAccess {
     fn current_working_directory(&self) -> &RwLock<AbsoluteOwnedPath>;
+
+    /// Resolves `path` against the current working directory, unless it is
+    /// already absolute.
+    fn absolute_path(&self, path: &Path) -> AbsoluteOwnedPath {
+        if let Ok(p) = AbsolutePath::try_new(path) {
+            p.to_owned()
+        } else {
+            let mut p = self.current_working_directory().read().clone();
+            p.push(path);
+            p
+        }
+    }
 }
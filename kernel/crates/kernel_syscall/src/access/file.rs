@@ -11,6 +11,7 @@ pub trait FileAccess {
     type ReadError;
     type WriteError;
     type CloseError;
+    type IoctlError;
 
     fn file_info(&self, path: &AbsolutePath) -> Option<Self::FileInfo>;
 
@@ -21,6 +22,8 @@ pub trait FileAccess {
     fn write(&self, fd: Self::Fd, buf: &[u8]) -> Result<usize, Self::WriteError>;
 
     fn close(&self, fd: Self::Fd) -> Result<(), Self::CloseError>;
+
+    fn ioctl(&self, fd: Self::Fd, request: u64, arg: usize) -> Result<usize, Self::IoctlError>;
 }
 
 #[cfg(test)]
@@ -33,6 +36,7 @@ pub mod testing {
     use core::sync::atomic::AtomicUsize;
     use core::sync::atomic::Ordering::Relaxed;
 
+    use kernel_abi::{EBADF, ENOTTY, Errno};
     use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
     use spin::mutex::Mutex;
     use spin::rwlock::RwLock;
@@ -111,6 +115,7 @@ pub mod testing {
         type ReadError = ();
         type WriteError = ();
         type CloseError = ();
+        type IoctlError = Errno;
 
         fn file_info(&self, path: &AbsolutePath) -> Option<Self::FileInfo> {
             let guard = self.lock();
@@ -178,5 +183,14 @@ pub mod testing {
                 Err(())
             }
         }
+
+        fn ioctl(&self, fd: Self::Fd, _: u64, _: usize) -> Result<usize, Errno> {
+            // memory files are never devices
+            if self.lock().open_fds.contains_key(&fd) {
+                Err(ENOTTY)
+            } else {
+                Err(EBADF)
+            }
+        }
     }
 }
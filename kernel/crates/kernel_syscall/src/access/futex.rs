@@ -0,0 +1,30 @@
+use core::task::Waker;
+use core::time::Duration;
+
+use crate::UserspacePtr;
+use crate::access::Interrupted;
+use crate::futex::{FutexTable, Waiter};
+
+pub trait FutexAccess {
+    /// The physical address of the futex word at `addr`, which identifies
+    /// the futex in every address space that maps it, or `None` if `addr`
+    /// isn't mapped.
+    fn futex_key(&self, addr: UserspacePtr<u32>) -> Option<u64>;
+
+    /// The waiters of all futexes.
+    fn futexes(&self) -> &FutexTable;
+
+    /// The time since boot, which timeouts are measured against.
+    fn now(&self) -> Duration;
+
+    /// Returns a waker for the current task, which is woken when its futex
+    /// is.
+    fn waker(&self) -> Waker;
+
+    /// Waits until `waiter` was woken, or until `deadline` passed.
+    /// Returning early is fine, since both are checked again anyway.
+    ///
+    /// # Errors
+    /// Returns an error if the wait was interrupted by a signal.
+    fn wait(&self, waiter: &Waiter, deadline: Option<Duration>) -> Result<(), Interrupted>;
+}
@@ -0,0 +1,40 @@
+use kernel_kmsg::Filter;
+use log::LevelFilter;
+
+use crate::access::Interrupted;
+
+pub trait LogAccess {
+    /// Reads whole records that weren't read with this method before into
+    /// `buf`, waiting until there is at least one. The position is shared
+    /// by all processes.
+    ///
+    /// # Errors
+    /// Returns an error if the wait was interrupted by a signal.
+    fn read_log(&self, buf: &mut [u8]) -> Result<usize, Interrupted>;
+
+    /// Reads the newest records since the log was last cleared, as many as
+    /// fit into `buf`.
+    fn read_log_all(&self, buf: &mut [u8]) -> usize;
+
+    /// Hides the current records from [`read_log_all`](Self::read_log_all).
+    fn clear_log(&self);
+
+    /// The number of bytes that [`read_log`](Self::read_log) has left to
+    /// read.
+    fn unread_log_len(&self) -> usize;
+
+    /// The number of bytes that the log can hold.
+    fn log_buffer_len(&self) -> usize;
+
+    /// Turns printing records on the console on or off, without changing
+    /// the console level.
+    fn set_console_enabled(&self, enabled: bool);
+
+    /// Sets the least important level that is printed on the console.
+    fn set_console_level(&self, level: LevelFilter);
+
+    fn log_filter(&self) -> Filter;
+
+    /// Replaces the filter that decides which records are logged at all.
+    fn set_log_filter(&self, filter: Filter);
+}
@@ -21,6 +21,12 @@ pub trait Mapping {
 pub enum CreateMappingError {
     LocationAlreadyMapped,
     OutOfMemory,
+    /// The file descriptor of a file mapping is not open.
+    BadFileDescriptor,
+    /// The file of a file mapping can't be mapped.
+    NotMappable,
+    /// The range of a file mapping exceeds the file.
+    InvalidRange,
 }
 
 pub trait MemoryAccess {
@@ -0,0 +1,45 @@
+use kernel_vfs::path::AbsolutePath;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum MountError {
+    /// The source or the mount point doesn't exist.
+    NotFound,
+    NotADirectory,
+    NotEmpty,
+    Busy,
+    UnknownFileSystemType,
+    /// The source doesn't contain a file system of the requested type.
+    InvalidSource,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnmountError {
+    NotMounted,
+    Busy,
+}
+
+pub trait MountAccess {
+    /// Mounts a new instance of the file system type `fs_type` at `target`.
+    /// `source` is usually a device, file systems that aren't backed by a
+    /// device may ignore it. `data` holds file system specific options.
+    fn mount(
+        &self,
+        source: Option<&str>,
+        target: &AbsolutePath,
+        fs_type: &str,
+        data: &str,
+        read_only: bool,
+    ) -> Result<(), MountError>;
+
+    /// Makes the directory `source` additionally visible at `target`.
+    fn bind(
+        &self,
+        source: &AbsolutePath,
+        target: &AbsolutePath,
+        read_only: bool,
+    ) -> Result<(), MountError>;
+
+    /// Unmounts the file system at `target`. If `detach` is set, the
+    /// mount is removed immediately even if it's busy.
+    fn unmount(&self, target: &AbsolutePath, detach: bool) -> Result<(), UnmountError>;
+}
@@ -0,0 +1,62 @@
+use alloc::sync::Arc;
+use core::ffi::c_int;
+use core::task::Waker;
+use core::time::Duration;
+
+use kernel_vfs::Readiness;
+
+use crate::access::Interrupted;
+use crate::epoll::Epoll;
+
+/// An open file that `poll`, `select` and epoll instances wait for.
+pub trait Pollable {
+    fn readiness(&self) -> Readiness;
+
+    /// Wakes `waker` the next time that the readiness of the file may
+    /// change.
+    fn register_waker(&self, waker: &Waker);
+
+    /// A number that changes whenever the file is read or written. An
+    /// edge-triggered epoll instance reports a file again after it was used,
+    /// even if its readiness looks the same, since whatever made it ready may
+    /// have happened again in the meantime.
+    fn generation(&self) -> u64;
+
+    /// Whether the file is an epoll instance, which can't be added to
+    /// another one.
+    fn is_epoll(&self) -> bool;
+}
+
+pub trait PollAccess {
+    type Fd: From<c_int> + Into<c_int>;
+    type File: Pollable;
+
+    /// The open file of `fd`, or `None` if `fd` isn't open.
+    fn pollable(&self, fd: Self::Fd) -> Option<Arc<Self::File>>;
+
+    /// Creates an epoll instance and returns a new file descriptor for it.
+    fn create_epoll(&self) -> Self::Fd;
+
+    /// Calls `f` with the epoll instance of `epfd`, or returns `None` if
+    /// `epfd` isn't one.
+    fn with_epoll<R>(
+        &self,
+        epfd: Self::Fd,
+        f: impl FnOnce(&mut Epoll<Self::File>) -> R,
+    ) -> Option<R>;
+
+    /// The time since boot, which timeouts are measured against.
+    fn now(&self) -> Duration;
+
+    /// Calls `ready` until it returns `true` or `deadline` passed. `ready`
+    /// registers the waker that it gets with the files that it checks, which
+    /// call it again when they change.
+    ///
+    /// # Errors
+    /// Returns an error if the wait was interrupted by a signal.
+    fn wait_until(
+        &self,
+        deadline: Option<Duration>,
+        ready: impl FnMut(&Waker) -> bool,
+    ) -> Result<(), Interrupted>;
+}
@@ -0,0 +1,18 @@
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Interrupted;
+
+pub trait RandomAccess {
+    /// Whether the random number generator collected enough entropy for
+    /// its output to be unpredictable.
+    fn is_seeded(&self) -> bool;
+
+    /// Blocks until the random number generator is seeded.
+    ///
+    /// # Errors
+    /// Returns an error if the wait was interrupted by a signal.
+    fn wait_until_seeded(&self) -> Result<(), Interrupted>;
+
+    /// Fills `buf` with random bytes, whether or not the generator is
+    /// seeded.
+    fn fill_random(&self, buf: &mut [u8]);
+}
@@ -1,3 +1,5 @@
+use core::ffi::c_int;
+
 use crate::UserspacePtr;
 use crate::access::{AllocationStrategy, CreateMappingError, Location};
 
@@ -25,6 +27,18 @@ pub trait MemoryRegionAccess {
         allocation_strategy: AllocationStrategy,
     ) -> Result<UserspacePtr<u8>, CreateMappingError>;
 
+    /// Maps `size` bytes of the file `fd`, starting at `offset`, and tracks
+    /// the mapping as a memory region. The mapping is shared with the file,
+    /// so only files that are backed by device memory can be mapped.
+    /// Returns the address of the created mapping.
+    fn create_and_track_file_mapping(
+        &self,
+        location: Location,
+        size: usize,
+        fd: c_int,
+        offset: usize,
+    ) -> Result<UserspacePtr<u8>, CreateMappingError>;
+
     /// Adds a memory region to the process's memory region tracking.
     /// This makes the region available to other kernel components.
     fn add_memory_region(&self, region: Self::Region);
@@ -0,0 +1,46 @@
+use core::ffi::c_int;
+
+use kernel_sched::Params;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NoSuchThread;
+
+/// The threads are those of the current process, so a thread id of another
+/// process is treated like one that doesn't exist.
+pub trait SchedAccess {
+    /// The number of CPUs, which are numbered from zero.
+    fn cpu_count(&self) -> usize;
+
+    /// The CPU affinity mask of the thread `tid`, or of the current thread
+    /// if `tid` is zero.
+    ///
+    /// # Errors
+    /// Returns an error if there is no thread `tid`.
+    fn affinity(&self, tid: c_int) -> Result<u64, NoSuchThread>;
+
+    /// Restricts the thread `tid`, or the current thread if `tid` is zero,
+    /// to the CPUs in `mask`. The mask contains at least one CPU, and only
+    /// CPUs that exist.
+    ///
+    /// # Errors
+    /// Returns an error if there is no thread `tid`.
+    fn set_affinity(&self, tid: c_int, mask: u64) -> Result<(), NoSuchThread>;
+
+    /// The scheduling parameters of the thread `tid`, or of the current
+    /// thread if `tid` is zero.
+    ///
+    /// # Errors
+    /// Returns an error if there is no thread `tid`.
+    fn params(&self, tid: c_int) -> Result<Params, NoSuchThread>;
+
+    /// Changes the scheduling parameters of the thread `tid`, or of the
+    /// current thread if `tid` is zero.
+    ///
+    /// # Errors
+    /// Returns an error if there is no thread `tid`.
+    fn set_params(&self, tid: c_int, params: Params) -> Result<(), NoSuchThread>;
+
+    /// Lets the ready threads that the current thread yields to run first,
+    /// see [`Entity::yields_to`](kernel_sched::Entity::yields_to).
+    fn yield_now(&self);
+}
@@ -0,0 +1,226 @@
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::net::{SocketAddrV4, SocketAddrV6};
+
+use kernel_vfs::path::OwnedPath;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SocketDomain {
+    Inet,
+    Inet6,
+    Unix,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SocketType {
+    /// A connection, which is TCP for [`SocketDomain::Inet`] and
+    /// [`SocketDomain::Inet6`].
+    Stream,
+    /// Datagrams, which are UDP for [`SocketDomain::Inet`] and
+    /// [`SocketDomain::Inet6`].
+    Datagram,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SocketAddress {
+    Inet(SocketAddrV4),
+    Inet6(SocketAddrV6),
+    Unix(UnixAddress),
+}
+
+impl SocketAddress {
+    #[must_use]
+    pub fn domain(&self) -> SocketDomain {
+        match self {
+            Self::Inet(_) => SocketDomain::Inet,
+            Self::Inet6(_) => SocketDomain::Inet6,
+            Self::Unix(_) => SocketDomain::Unix,
+        }
+    }
+}
+
+/// The name of a Unix socket.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum UnixAddress {
+    /// The socket isn't bound to a name.
+    Unnamed,
+    /// A path in the file system, which may be relative to the working
+    /// directory.
+    Path(OwnedPath),
+    /// A name in the abstract namespace, which is independent of the file
+    /// system, without the leading null byte.
+    Abstract(Vec<u8>),
+}
+
+/// The credentials of the process that created a socket, see
+/// [`SocketAccess::peer_credentials`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Credentials {
+    pub pid: u64,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// What [`SocketAccess::recv_from`] received.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Received<Fd> {
+    pub len: usize,
+    /// The address of the sender, which streams don't have.
+    pub sender: Option<SocketAddress>,
+    /// The file descriptors that the received files were installed as.
+    pub rights: Vec<Fd>,
+    /// Whether more files were passed than could be received, in which
+    /// case the others were closed.
+    pub rights_truncated: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Shutdown {
+    Read,
+    Write,
+    Both,
+}
+
+/// The options of `setsockopt` and `getsockopt`, which all have an `int`
+/// as value. Boolean options are zero or one.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SocketOption {
+    ReuseAddress,
+    /// The type of the socket, only for `getsockopt`.
+    Type,
+    /// The pending error of the socket, which is cleared when it's read.
+    /// Only for `getsockopt`.
+    Error,
+    Broadcast,
+    SendBuffer,
+    ReceiveBuffer,
+    KeepAlive,
+    NoDelay,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SocketError {
+    BadFileDescriptor,
+    NotASocket,
+    InvalidArgument,
+    /// The operation isn't supported by this type of socket.
+    NotSupported,
+    /// The option isn't known for this type of socket.
+    InvalidOption,
+    AddressFamilyNotSupported,
+    AddressInUse,
+    AddressNotAvailable,
+    /// `sendto` without an address on a socket that isn't connected.
+    DestinationRequired,
+    AlreadyConnected,
+    NotConnected,
+    /// A non-blocking `connect` was already started.
+    AlreadyInProgress,
+    /// A non-blocking `connect` was started, but didn't finish yet.
+    InProgress,
+    ConnectionRefused,
+    ConnectionReset,
+    /// The path of a Unix socket address doesn't exist.
+    NotFound,
+    ReadOnlyFileSystem,
+    /// A Unix socket was connected to a socket of another type.
+    WrongProtocolType,
+    /// There is no network interface.
+    NetworkDown,
+    NetworkUnreachable,
+    /// A broadcast was sent without [`SocketOption::Broadcast`].
+    PermissionDenied,
+    /// The socket was shut down for writing.
+    BrokenPipe,
+    MessageTooLarge,
+    /// The socket has no space for the data, which happens for datagrams
+    /// that are larger than the send buffer.
+    NoBufferSpace,
+    /// The operation would have to wait, but the socket is non-blocking.
+    WouldBlock,
+    /// The wait was interrupted by a signal.
+    Interrupted,
+}
+
+pub trait SocketAccess {
+    type Fd: From<c_int> + Into<c_int>;
+
+    /// Creates a socket and returns a new file descriptor for it.
+    fn socket(
+        &self,
+        domain: SocketDomain,
+        typ: SocketType,
+        nonblocking: bool,
+    ) -> Result<Self::Fd, SocketError>;
+
+    /// Creates a pair of connected sockets and returns the new file
+    /// descriptors for them.
+    fn socket_pair(
+        &self,
+        domain: SocketDomain,
+        typ: SocketType,
+        nonblocking: bool,
+    ) -> Result<(Self::Fd, Self::Fd), SocketError>;
+
+    /// Gives the socket a local address. A port of zero picks a free port,
+    /// and a Unix socket is bound to its path by creating a file there.
+    fn bind(&self, fd: Self::Fd, address: SocketAddress) -> Result<(), SocketError>;
+
+    /// Makes the stream socket accept connections, of which up to `backlog`
+    /// can be established before they are [accepted](Self::accept).
+    fn listen(&self, fd: Self::Fd, backlog: usize) -> Result<(), SocketError>;
+
+    /// Waits for an established connection on a listening socket and
+    /// returns a new file descriptor for it, together with the address of
+    /// the peer.
+    fn accept(&self, fd: Self::Fd) -> Result<(Self::Fd, SocketAddress), SocketError>;
+
+    /// Connects a stream socket to `address` and waits until the connection
+    /// is established. Datagram sockets only remember `address` as the
+    /// default destination and the only source that is received from.
+    fn connect(&self, fd: Self::Fd, address: SocketAddress) -> Result<(), SocketError>;
+
+    /// Sends `buf` to `address`, or to the connected peer if there is no
+    /// address, and returns how much was sent. The open files of `rights`
+    /// are passed along with the data, which only Unix sockets support.
+    fn send_to(
+        &self,
+        fd: Self::Fd,
+        buf: &[u8],
+        address: Option<SocketAddress>,
+        rights: &[Self::Fd],
+        nonblocking: bool,
+    ) -> Result<usize, SocketError>;
+
+    /// Receives into `buf`, waiting until there is data. Zero bytes are
+    /// received once a stream is closed by the peer. Files that were passed
+    /// with the data are installed as new file descriptors, at most
+    /// `max_rights` of them.
+    fn recv_from(
+        &self,
+        fd: Self::Fd,
+        buf: &mut [u8],
+        peek: bool,
+        nonblocking: bool,
+        max_rights: usize,
+    ) -> Result<Received<Self::Fd>, SocketError>;
+
+    fn shutdown(&self, fd: Self::Fd, how: Shutdown) -> Result<(), SocketError>;
+
+    fn socket_option(&self, fd: Self::Fd, option: SocketOption) -> Result<c_int, SocketError>;
+
+    fn set_socket_option(
+        &self,
+        fd: Self::Fd,
+        option: SocketOption,
+        value: c_int,
+    ) -> Result<(), SocketError>;
+
+    fn local_address(&self, fd: Self::Fd) -> Result<SocketAddress, SocketError>;
+
+    fn peer_address(&self, fd: Self::Fd) -> Result<SocketAddress, SocketError>;
+
+    /// The credentials of the process on the other end of a connected Unix
+    /// socket, from when the connection was established.
+    fn peer_credentials(&self, fd: Self::Fd) -> Result<Credentials, SocketError>;
+}
@@ -0,0 +1,39 @@
+use core::ffi::c_int;
+
+use crate::{UserspaceMutPtr, UserspacePtr};
+
+/// A thread to create in the current process, see [`CloneArgs`].
+///
+/// [`CloneArgs`]: kernel_abi::CloneArgs
+pub struct NewThread {
+    pub entry: UserspacePtr<u8>,
+    pub arg: usize,
+    /// The stack and its size, or `None` to allocate one.
+    pub stack: Option<(UserspacePtr<u8>, usize)>,
+    /// The FS base, or `None` to allocate the thread-local storage from the
+    /// TLS image of the executable.
+    pub tls: Option<UserspacePtr<u8>>,
+    /// Where the id of the thread is written before it starts.
+    pub parent_tid: Option<UserspaceMutPtr<c_int>>,
+    /// Where the id of the thread is written before it starts.
+    pub child_tid: Option<UserspaceMutPtr<c_int>>,
+    /// Where zero is written when the thread exits, which also wakes a
+    /// waiter of the futex there.
+    pub clear_child_tid: Option<UserspaceMutPtr<c_int>>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SpawnThreadError {
+    OutOfMemory,
+}
+
+pub trait ThreadAccess {
+    /// Creates a thread in the current process and starts it.
+    ///
+    /// Returns the id of the new thread.
+    ///
+    /// # Errors
+    /// Returns an error if the stack or the thread-local storage can't be
+    /// allocated.
+    fn spawn_thread(&self, thread: NewThread) -> Result<c_int, SpawnThreadError>;
+}
@@ -0,0 +1,486 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::task::Waker;
+
+use kernel_abi::{
+    EBADF, EEXIST, EFAULT, EINVAL, ENOENT, EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLL_CTL_DEL,
+    EPOLL_CTL_MOD, EpollEvent, EpollFlags, Errno, PollEvents,
+};
+
+use crate::access::{PollAccess, Pollable};
+use crate::poll::{poll_events, timeout_from_millis, wait_until_ready};
+
+/// The interest list of an epoll instance: the files that it waits for,
+/// by file descriptor. The instance doesn't keep the files open, and files
+/// that were closed everywhere are dropped from the list.
+#[derive(Debug)]
+pub struct Epoll<F> {
+    entries: BTreeMap<c_int, Entry<F>>,
+    /// Where the next collection starts, so that a file that is always
+    /// ready doesn't crowd out the others.
+    next: c_int,
+}
+
+#[derive(Debug)]
+struct Entry<F> {
+    file: Weak<F>,
+    events: PollEvents,
+    flags: EpollFlags,
+    data: u64,
+    /// Set once the events of a [`EpollFlags::ONESHOT`] entry were reported.
+    disabled: bool,
+    /// The events and generation of the file when an edge-triggered entry
+    /// was last collected, until it isn't ready anymore.
+    last: Option<(PollEvents, u64)>,
+}
+
+impl<F> Default for Epoll<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F> Epoll<F> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            next: 0,
+        }
+    }
+}
+
+impl<F: Pollable> Epoll<F> {
+    /// Adds, modifies or removes the entry of `fd`, whose file is `file`.
+    /// An entry of a file descriptor that now refers to another file is
+    /// treated as if it didn't exist.
+    ///
+    /// # Errors
+    /// Returns [`EEXIST`] when adding an entry that exists, [`ENOENT`] when
+    /// modifying or removing one that doesn't, and [`EINVAL`] for an
+    /// unknown operation or for modifying an exclusive entry.
+    pub fn ctl(
+        &mut self,
+        op: c_int,
+        fd: c_int,
+        file: &Arc<F>,
+        event: EpollEvent,
+    ) -> Result<(), Errno> {
+        let exists = self
+            .entries
+            .get(&fd)
+            .is_some_and(|entry| core::ptr::eq(entry.file.as_ptr(), Arc::as_ptr(file)));
+        let bits = event.events;
+        let flags = EpollFlags::from_bits_truncate(bits);
+        let entry = Entry {
+            file: Arc::downgrade(file),
+            events: PollEvents::from_bits_truncate(bits),
+            flags,
+            data: event.data,
+            disabled: false,
+            last: None,
+        };
+        match op {
+            EPOLL_CTL_ADD => {
+                if exists {
+                    return Err(EEXIST);
+                }
+                self.entries.insert(fd, entry);
+            }
+            EPOLL_CTL_MOD => {
+                if !exists {
+                    return Err(ENOENT);
+                }
+                if flags.contains(EpollFlags::EXCLUSIVE)
+                    || self.entries[&fd].flags.contains(EpollFlags::EXCLUSIVE)
+                {
+                    return Err(EINVAL);
+                }
+                self.entries.insert(fd, entry);
+            }
+            EPOLL_CTL_DEL => {
+                if !exists {
+                    return Err(ENOENT);
+                }
+                self.entries.remove(&fd);
+            }
+            _ => return Err(EINVAL),
+        }
+        Ok(())
+    }
+
+    /// Writes the events of the files that are ready into `events`, and
+    /// returns how many were written.
+    pub fn collect(&mut self, events: &mut [EpollEvent]) -> usize {
+        let fds = self
+            .entries
+            .range(self.next..)
+            .chain(self.entries.range(..self.next))
+            .map(|(&fd, _)| fd)
+            .collect::<Vec<_>>();
+        let mut count = 0;
+        for fd in fds {
+            if count == events.len() {
+                self.next = fd;
+                return count;
+            }
+            let entry = self.entries.get_mut(&fd).unwrap();
+            let Some(file) = entry.file.upgrade() else {
+                self.entries.remove(&fd);
+                continue;
+            };
+            if let Some(ready) = entry.collect(&*file) {
+                events[count] = EpollEvent {
+                    events: ready.bits(),
+                    data: entry.data,
+                };
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Wakes `waker` the next time that the readiness of one of the files
+    /// may change.
+    pub fn register_waker(&self, waker: &Waker) {
+        for file in self
+            .entries
+            .values()
+            .filter_map(|entry| entry.file.upgrade())
+        {
+            file.register_waker(waker);
+        }
+    }
+
+    /// Whether [`collect`](Self::collect) would return events, which makes
+    /// the epoll instance itself readable.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.entries.values().any(|entry| {
+            entry
+                .file
+                .upgrade()
+                .is_some_and(|file| entry.pending(&*file).is_some())
+        })
+    }
+}
+
+impl<F> Entry<F> {
+    fn ready(&self, file: &impl Pollable) -> PollEvents {
+        if self.disabled {
+            return PollEvents::empty();
+        }
+        poll_events(file.readiness()) & (self.events | PollEvents::ERR | PollEvents::HUP)
+    }
+
+    /// The events that would be reported, together with the generation of
+    /// the file.
+    fn pending(&self, file: &impl Pollable) -> Option<(PollEvents, u64)> {
+        let ready = self.ready(file);
+        if ready.is_empty() {
+            return None;
+        }
+        let generation = file.generation();
+        if self.flags.contains(EpollFlags::ET)
+            && let Some((last, last_generation)) = self.last
+            && last_generation == generation
+            && last.contains(ready)
+        {
+            return None;
+        }
+        Some((ready, generation))
+    }
+
+    /// Returns the events to report, and remembers them for the next time.
+    fn collect(&mut self, file: &impl Pollable) -> Option<PollEvents> {
+        let Some((ready, generation)) = self.pending(file) else {
+            // an edge-triggered entry that lost some of its events reports
+            // them again once they are back
+            let ready = self.ready(file);
+            self.last = self.last.map(|(_, generation)| (ready, generation));
+            return None;
+        };
+        self.last = Some((ready, generation));
+        if self.flags.contains(EpollFlags::ONESHOT) {
+            self.disabled = true;
+        }
+        Some(ready)
+    }
+}
+
+/// Creates an epoll instance. File descriptors are never inherited, so
+/// [`EPOLL_CLOEXEC`] has no effect.
+pub fn sys_epoll_create1<Cx: PollAccess>(cx: &Cx, flags: c_int) -> Result<usize, Errno> {
+    if flags & !EPOLL_CLOEXEC != 0 {
+        return Err(EINVAL);
+    }
+    let fd: c_int = cx.create_epoll().into();
+    Ok(usize::try_from(fd).unwrap())
+}
+
+/// Adds `fd` to the epoll instance `epfd`, or modifies or removes its entry.
+/// `event` is ignored when removing. Epoll instances can't be nested.
+pub fn sys_epoll_ctl<Cx: PollAccess>(
+    cx: &Cx,
+    epfd: c_int,
+    op: c_int,
+    fd: c_int,
+    event: Option<EpollEvent>,
+) -> Result<usize, Errno> {
+    cx.pollable(epfd.into()).ok_or(EBADF)?;
+    let file = cx.pollable(fd.into()).ok_or(EBADF)?;
+    if epfd == fd || file.is_epoll() {
+        return Err(EINVAL);
+    }
+    let event = match op {
+        EPOLL_CTL_DEL => EpollEvent::default(),
+        _ => event.ok_or(EFAULT)?,
+    };
+    cx.with_epoll(epfd.into(), |epoll| epoll.ctl(op, fd, &file, event))
+        .ok_or(EINVAL)??;
+    Ok(0)
+}
+
+/// Waits until one of the files of the epoll instance `epfd` is ready, or
+/// until `timeout` milliseconds passed, and writes their events into
+/// `events`. A negative timeout waits forever.
+///
+/// Returns how many events were written.
+pub fn sys_epoll_wait<Cx: PollAccess>(
+    cx: &Cx,
+    epfd: c_int,
+    events: &mut [EpollEvent],
+    timeout: c_int,
+) -> Result<usize, Errno> {
+    cx.pollable(epfd.into()).ok_or(EBADF)?;
+    if events.is_empty() {
+        return Err(EINVAL);
+    }
+    cx.with_epoll(epfd.into(), |_| ()).ok_or(EINVAL)?;
+    wait_until_ready(cx, timeout_from_millis(timeout), |waker| {
+        cx.with_epoll(epfd.into(), |epoll| {
+            epoll.register_waker(waker);
+            epoll.collect(events)
+        })
+        .unwrap_or(0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use kernel_abi::{EBADF, EEXIST, EINVAL, ENOENT, EpollEvent, EpollFlags, PollEvents};
+    use kernel_vfs::Readiness;
+
+    use super::*;
+    use crate::poll::testing::TestPollAccess;
+
+    const READABLE: Readiness = Readiness {
+        readable: true,
+        writable: false,
+        hangup: false,
+        error: false,
+    };
+
+    fn event(events: PollEvents, flags: EpollFlags, data: u64) -> Option<EpollEvent> {
+        Some(EpollEvent {
+            events: events.bits() | flags.bits(),
+            data,
+        })
+    }
+
+    fn wait(cx: &TestPollAccess, epfd: c_int) -> Vec<(PollEvents, u64)> {
+        let mut events = [EpollEvent::default(); 8];
+        let count = sys_epoll_wait(cx, epfd, &mut events, 0).unwrap();
+        events[..count]
+            .iter()
+            .map(|event| (PollEvents::from_bits_truncate(event.events), event.data))
+            .collect()
+    }
+
+    #[test]
+    fn test_epoll_create1() {
+        let cx = TestPollAccess::default();
+        assert_eq!(Ok(0), sys_epoll_create1(&cx, 0));
+        assert_eq!(Ok(1), sys_epoll_create1(&cx, EPOLL_CLOEXEC));
+        assert_eq!(Err(EINVAL), sys_epoll_create1(&cx, 1));
+    }
+
+    #[test]
+    fn test_epoll_ctl_errors() {
+        let cx = TestPollAccess::default();
+        let epfd = sys_epoll_create1(&cx, 0).unwrap() as c_int;
+        let other = sys_epoll_create1(&cx, 0).unwrap() as c_int;
+        cx.add(5, READABLE);
+        let ev = event(PollEvents::IN, EpollFlags::empty(), 0);
+
+        assert_eq!(Err(EBADF), sys_epoll_ctl(&cx, 9, EPOLL_CTL_ADD, 5, ev));
+        assert_eq!(Err(EBADF), sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 9, ev));
+        assert_eq!(Err(EINVAL), sys_epoll_ctl(&cx, 5, EPOLL_CTL_ADD, 5, ev));
+        assert_eq!(
+            Err(EINVAL),
+            sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, epfd, ev)
+        );
+        assert_eq!(
+            Err(EINVAL),
+            sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, other, ev)
+        );
+        assert_eq!(Err(ENOENT), sys_epoll_ctl(&cx, epfd, EPOLL_CTL_MOD, 5, ev));
+        assert_eq!(
+            Err(ENOENT),
+            sys_epoll_ctl(&cx, epfd, EPOLL_CTL_DEL, 5, None)
+        );
+        assert_eq!(Err(EINVAL), sys_epoll_ctl(&cx, epfd, 4, 5, ev));
+
+        assert_eq!(Ok(0), sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 5, ev));
+        assert_eq!(Err(EEXIST), sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 5, ev));
+        let exclusive = event(PollEvents::IN, EpollFlags::EXCLUSIVE, 0);
+        assert_eq!(
+            Err(EINVAL),
+            sys_epoll_ctl(&cx, epfd, EPOLL_CTL_MOD, 5, exclusive)
+        );
+        assert_eq!(Ok(0), sys_epoll_ctl(&cx, epfd, EPOLL_CTL_DEL, 5, None));
+
+        let mut events = [EpollEvent::default(); 1];
+        assert_eq!(Err(EINVAL), sys_epoll_wait(&cx, epfd, &mut [], 0));
+        assert_eq!(Err(EINVAL), sys_epoll_wait(&cx, 5, &mut events, 0));
+        assert_eq!(Err(EBADF), sys_epoll_wait(&cx, 9, &mut events, 0));
+    }
+
+    #[test]
+    fn test_epoll_level_triggered() {
+        let cx = TestPollAccess::default();
+        let epfd = sys_epoll_create1(&cx, 0).unwrap() as c_int;
+        let file = cx.add(5, Readiness::default());
+        cx.add(6, Readiness::READY);
+        let ev = event(PollEvents::IN, EpollFlags::empty(), 55);
+        sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 5, ev).unwrap();
+        let ev = event(PollEvents::OUT, EpollFlags::empty(), 66);
+        sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 6, ev).unwrap();
+
+        assert_eq!(vec![(PollEvents::OUT, 66)], wait(&cx, epfd));
+        file.set(READABLE);
+        assert!(cx.epolls.borrow()[&epfd].is_ready());
+        let ready = vec![(PollEvents::IN, 55), (PollEvents::OUT, 66)];
+        assert_eq!(ready, wait(&cx, epfd));
+        assert_eq!(
+            ready,
+            wait(&cx, epfd),
+            "events are reported while they last"
+        );
+
+        file.set(Readiness {
+            hangup: true,
+            ..Readiness::default()
+        });
+        assert_eq!(
+            vec![(PollEvents::HUP, 55), (PollEvents::OUT, 66)],
+            wait(&cx, epfd)
+        );
+    }
+
+    #[test]
+    fn test_epoll_edge_triggered() {
+        let cx = TestPollAccess::default();
+        let epfd = sys_epoll_create1(&cx, 0).unwrap() as c_int;
+        let file = cx.add(5, Readiness::default());
+        let ev = event(PollEvents::IN | PollEvents::OUT, EpollFlags::ET, 5);
+        sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 5, ev).unwrap();
+
+        assert_eq!(Vec::<(PollEvents, u64)>::new(), wait(&cx, epfd));
+        file.set(READABLE);
+        assert_eq!(vec![(PollEvents::IN, 5)], wait(&cx, epfd));
+        assert!(wait(&cx, epfd).is_empty(), "an edge is reported once");
+        assert!(!cx.epolls.borrow()[&epfd].is_ready());
+
+        // a read may have consumed the event, which may have happened again
+        file.touch();
+        assert_eq!(vec![(PollEvents::IN, 5)], wait(&cx, epfd));
+
+        file.set(Readiness::READY);
+        assert_eq!(vec![(PollEvents::IN | PollEvents::OUT, 5)], wait(&cx, epfd));
+        file.set(Readiness {
+            writable: true,
+            ..Readiness::default()
+        });
+        assert!(wait(&cx, epfd).is_empty());
+        file.set(Readiness::READY);
+        assert_eq!(vec![(PollEvents::IN | PollEvents::OUT, 5)], wait(&cx, epfd));
+
+        file.set(Readiness::default());
+        assert!(wait(&cx, epfd).is_empty());
+        file.set(READABLE);
+        assert_eq!(vec![(PollEvents::IN, 5)], wait(&cx, epfd));
+    }
+
+    #[test]
+    fn test_epoll_oneshot() {
+        let cx = TestPollAccess::default();
+        let epfd = sys_epoll_create1(&cx, 0).unwrap() as c_int;
+        cx.add(5, READABLE);
+        let ev = event(PollEvents::IN, EpollFlags::ONESHOT, 5);
+        sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 5, ev).unwrap();
+
+        assert_eq!(vec![(PollEvents::IN, 5)], wait(&cx, epfd));
+        assert!(wait(&cx, epfd).is_empty());
+        sys_epoll_ctl(&cx, epfd, EPOLL_CTL_MOD, 5, ev).unwrap();
+        assert_eq!(vec![(PollEvents::IN, 5)], wait(&cx, epfd));
+    }
+
+    #[test]
+    fn test_epoll_closed_file() {
+        let cx = TestPollAccess::default();
+        let epfd = sys_epoll_create1(&cx, 0).unwrap() as c_int;
+        cx.add(5, READABLE);
+        let ev = event(PollEvents::IN, EpollFlags::empty(), 5);
+        sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 5, ev).unwrap();
+
+        cx.files.borrow_mut().remove(&5);
+        assert!(wait(&cx, epfd).is_empty());
+        // the number can be added again for another file
+        cx.add(5, READABLE);
+        assert_eq!(Ok(0), sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 5, ev));
+    }
+
+    #[test]
+    fn test_epoll_wait_round_robin() {
+        let cx = TestPollAccess::default();
+        let epfd = sys_epoll_create1(&cx, 0).unwrap() as c_int;
+        for fd in 3..6 {
+            cx.add(fd, READABLE);
+            let ev = event(PollEvents::IN, EpollFlags::empty(), fd as u64);
+            sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, fd, ev).unwrap();
+        }
+
+        let mut events = [EpollEvent::default(); 2];
+        let mut data = Vec::new();
+        for _ in 0..3 {
+            let count = sys_epoll_wait(&cx, epfd, &mut events, 0).unwrap();
+            data.extend(events[..count].iter().map(|event| event.data));
+        }
+        assert_eq!(vec![3, 4, 5, 3, 4, 5], data);
+    }
+
+    #[test]
+    fn test_epoll_wait_waits() {
+        let mut cx = TestPollAccess::default();
+        let epfd = sys_epoll_create1(&cx, 0).unwrap() as c_int;
+        cx.add(5, Readiness::default());
+        let ev = event(PollEvents::IN, EpollFlags::empty(), 5);
+        sys_epoll_ctl(&cx, epfd, EPOLL_CTL_ADD, 5, ev).unwrap();
+
+        let mut events = [EpollEvent::default(); 1];
+        assert_eq!(Ok(0), sys_epoll_wait(&cx, epfd, &mut events, 30));
+        assert_eq!(3, cx.waits.get());
+        assert_eq!(4, cx.files.borrow()[&5].registered());
+
+        cx.on_wait = Some(alloc::boxed::Box::new(|cx: &TestPollAccess| {
+            cx.files.borrow()[&5].set(READABLE);
+        }));
+        assert_eq!(Ok(1), sys_epoll_wait(&cx, epfd, &mut events, -1));
+        assert_eq!(4, cx.waits.get());
+    }
+}
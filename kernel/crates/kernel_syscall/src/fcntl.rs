@@ -1,9 +1,8 @@
-use alloc::borrow::ToOwned;
 use core::ffi::c_int;
 use core::slice::from_raw_parts;
 
 use kernel_abi::{EINVAL, ENAMETOOLONG, ENOENT, Errno, PATH_MAX};
-use kernel_vfs::path::{AbsolutePath, Path};
+use kernel_vfs::path::Path;
 use log::debug;
 
 use crate::access::{CwdAccess, FileAccess};
@@ -23,14 +22,7 @@ pub fn sys_open<Cx: CwdAccess + FileAccess>(
     let path = {
         let path_bytes = unsafe { from_raw_parts(path.as_ptr(), path_len) };
         let path = core::str::from_utf8(path_bytes).map_err(|_| EINVAL)?;
-        let path = Path::new(path);
-        if let Ok(p) = AbsolutePath::try_new(path) {
-            p.to_owned()
-        } else {
-            let mut p = cx.current_working_directory().read().clone();
-            p.push(path);
-            p
-        }
+        cx.absolute_path(Path::new(path))
     };
 
     debug!("path: {path:?}");
@@ -93,6 +85,7 @@ mod tests {
         type ReadError = F::ReadError;
         type WriteError = F::WriteError;
         type CloseError = F::CloseError;
+        type IoctlError = F::IoctlError;
 
         fn file_info(&self, path: &AbsolutePath) -> Option<Self::FileInfo> {
             self.file_access.file_info(path)
@@ -113,6 +106,10 @@ mod tests {
         fn close(&self, fd: Self::Fd) -> Result<(), Self::CloseError> {
             self.file_access.close(fd)
         }
+
+        fn ioctl(&self, fd: Self::Fd, request: u64, arg: usize) -> Result<usize, Self::IoctlError> {
+            self.file_access.ioctl(fd, request, arg)
+        }
     }
 
     #[test]
@@ -0,0 +1,680 @@
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use core::ffi::c_int;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release, SeqCst};
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicU64};
+use core::task::Waker;
+use core::time::Duration;
+
+use kernel_abi::{
+    EAGAIN, EFAULT, EINTR, EINVAL, ENOSYS, ETIMEDEOUT, Errno, FUTEX_BITSET_MATCH_ANY,
+    FUTEX_CLOCK_REALTIME, FUTEX_CMP_REQUEUE, FUTEX_PRIVATE_FLAG, FUTEX_REQUEUE, FUTEX_WAIT,
+    FUTEX_WAIT_BITSET, FUTEX_WAKE, FUTEX_WAKE_BITSET, TimeSpec,
+};
+use spin::Mutex;
+
+use crate::UserspacePtr;
+use crate::access::FutexAccess;
+use crate::poll::duration_from_timespec;
+
+/// The number of buckets of a [`FutexTable`].
+const BUCKETS: usize = 64;
+
+/// A task that waits on a futex.
+#[derive(Debug)]
+pub struct Waiter {
+    /// The futex that the task waits on, which changes when the waiter is
+    /// requeued. It only changes while the buckets of both the old and the
+    /// new futex are locked.
+    key: AtomicU64,
+    bitset: u32,
+    /// Set when the waiter is woken, at which point it was removed from
+    /// its bucket.
+    woken: AtomicBool,
+    /// Wakes the task after `woken` was set.
+    waker: Waker,
+}
+
+impl Waiter {
+    /// Whether the waiter was woken.
+    pub fn is_woken(&self) -> bool {
+        self.woken.load(Acquire)
+    }
+}
+
+/// The waiters of all futexes, in buckets by their key. A futex only exists
+/// while tasks wait on it.
+#[derive(Debug)]
+pub struct FutexTable {
+    buckets: [Mutex<VecDeque<Arc<Waiter>>>; BUCKETS],
+}
+
+impl Default for FutexTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FutexTable {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buckets: [const { Mutex::new(VecDeque::new()) }; BUCKETS],
+        }
+    }
+
+    fn index(key: u64) -> usize {
+        // futex words are aligned, so the lowest bits are always zero
+        (key >> 2) as usize % BUCKETS
+    }
+
+    /// Queues a waiter for the futex `key` that wakes the task with
+    /// `waker`, unless `word` doesn't hold `expected`. The word is checked
+    /// while the bucket is locked, so a wake that follows a change of the
+    /// word can't be missed.
+    ///
+    /// # Errors
+    /// Returns [`EAGAIN`] if `word` doesn't hold `expected`.
+    pub(crate) fn enqueue(
+        &self,
+        key: u64,
+        word: &AtomicU32,
+        expected: u32,
+        bitset: u32,
+        waker: Waker,
+    ) -> Result<Arc<Waiter>, Errno> {
+        let mut bucket = self.buckets[Self::index(key)].lock();
+        if word.load(SeqCst) != expected {
+            return Err(EAGAIN);
+        }
+        let waiter = Arc::new(Waiter {
+            key: AtomicU64::new(key),
+            bitset,
+            woken: AtomicBool::new(false),
+            waker,
+        });
+        bucket.push_back(waiter.clone());
+        Ok(waiter)
+    }
+
+    /// Removes `waiter` from its bucket. Returns `false` if it isn't queued
+    /// anymore, because it was woken.
+    pub(crate) fn dequeue(&self, waiter: &Arc<Waiter>) -> bool {
+        loop {
+            let key = waiter.key.load(Relaxed);
+            let mut bucket = self.buckets[Self::index(key)].lock();
+            if waiter.key.load(Relaxed) != key {
+                // requeued before the bucket was locked
+                continue;
+            }
+            let Some(index) = bucket.iter().position(|queued| Arc::ptr_eq(queued, waiter)) else {
+                return false;
+            };
+            bucket.remove(index);
+            return true;
+        }
+    }
+
+    /// Wakes up to `count` waiters of the futex `key` whose bitset shares a
+    /// bit with `bitset`, in the order in which they started to wait.
+    ///
+    /// Returns how many waiters were woken.
+    pub fn wake(&self, key: u64, count: usize, bitset: u32) -> usize {
+        wake_in(
+            &mut self.buckets[Self::index(key)].lock(),
+            key,
+            count,
+            bitset,
+        )
+    }
+
+    /// Wakes up to `wake` waiters of the futex `from`, and moves up to
+    /// `requeue` of the others to the futex `to`. With `check`, nothing
+    /// happens unless its word holds its value.
+    ///
+    /// Returns how many waiters were woken and how many were requeued.
+    ///
+    /// # Errors
+    /// Returns [`EAGAIN`] if the word of `check` doesn't hold its value.
+    pub(crate) fn requeue(
+        &self,
+        from: u64,
+        to: u64,
+        wake: usize,
+        requeue: usize,
+        check: Option<(&AtomicU32, u32)>,
+    ) -> Result<(usize, usize), Errno> {
+        let (source, target) = (Self::index(from), Self::index(to));
+        // the buckets are locked in the order of their index, so that two
+        // requeues in opposite directions don't deadlock
+        let mut first = self.buckets[source.min(target)].lock();
+        let mut second = (source != target).then(|| self.buckets[source.max(target)].lock());
+        if let Some((word, expected)) = check
+            && word.load(SeqCst) != expected
+        {
+            return Err(EAGAIN);
+        }
+        let (source, mut target) = match &mut second {
+            None => (&mut *first, None),
+            Some(second) if source < target => (&mut *first, Some(&mut **second)),
+            Some(second) => (&mut **second, Some(&mut *first)),
+        };
+
+        let woken = wake_in(source, from, wake, FUTEX_BITSET_MATCH_ANY);
+        let mut requeued = 0;
+        let mut index = 0;
+        while index < source.len() && requeued < requeue {
+            if source[index].key.load(Relaxed) != from {
+                index += 1;
+                continue;
+            }
+            source[index].key.store(to, Relaxed);
+            requeued += 1;
+            match target.as_deref_mut() {
+                Some(target) => target.push_back(source.remove(index).unwrap()),
+                // the futexes share the bucket, so the waiter stays where
+                // it is
+                None => index += 1,
+            }
+        }
+        Ok((woken, requeued))
+    }
+}
+
+fn wake_in(bucket: &mut VecDeque<Arc<Waiter>>, key: u64, count: usize, bitset: u32) -> usize {
+    let mut woken = 0;
+    bucket.retain(|waiter| {
+        if woken == count || waiter.key.load(Relaxed) != key || waiter.bitset & bitset == 0 {
+            return true;
+        }
+        waiter.woken.store(true, Release);
+        waiter.waker.wake_by_ref();
+        woken += 1;
+        false
+    });
+    woken
+}
+
+/// Waits on or wakes the futex at `uaddr`. For the wait operations,
+/// `timeout` points to the timeout, which is relative for [`FUTEX_WAIT`]
+/// and measured against the time since boot for [`FUTEX_WAIT_BITSET`], and
+/// may be null. For the requeue operations, it is the number of waiters to
+/// move to the futex at `uaddr2`. `val3` is the bitset of the bitset
+/// operations, and the value that [`FUTEX_CMP_REQUEUE`] expects.
+///
+/// Futexes are identified by the physical address of their word, so they
+/// work across processes that share the memory, and
+/// [`FUTEX_PRIVATE_FLAG`] has no effect.
+///
+/// Returns zero for the wait operations, and how many waiters were woken,
+/// or woken and requeued for [`FUTEX_CMP_REQUEUE`], for the others.
+pub fn sys_futex<Cx: FutexAccess>(
+    cx: &Cx,
+    uaddr: UserspacePtr<u32>,
+    op: c_int,
+    val: u32,
+    timeout: usize,
+    uaddr2: usize,
+    val3: u32,
+) -> Result<usize, Errno> {
+    if op & FUTEX_CLOCK_REALTIME != 0 {
+        return Err(ENOSYS);
+    }
+    match op & !FUTEX_PRIVATE_FLAG {
+        FUTEX_WAIT => {
+            let deadline = read_timeout(timeout)?.map(|timeout| cx.now().saturating_add(timeout));
+            wait(cx, uaddr, val, FUTEX_BITSET_MATCH_ANY, deadline)
+        }
+        FUTEX_WAIT_BITSET => {
+            if val3 == 0 {
+                return Err(EINVAL);
+            }
+            wait(cx, uaddr, val, val3, read_timeout(timeout)?)
+        }
+        FUTEX_WAKE => wake(cx, uaddr, val, FUTEX_BITSET_MATCH_ANY),
+        FUTEX_WAKE_BITSET => {
+            if val3 == 0 {
+                return Err(EINVAL);
+            }
+            wake(cx, uaddr, val, val3)
+        }
+        FUTEX_REQUEUE => requeue(cx, uaddr, val, timeout, uaddr2, None).map(|(woken, _)| woken),
+        FUTEX_CMP_REQUEUE => requeue(cx, uaddr, val, timeout, uaddr2, Some(val3))
+            .map(|(woken, requeued)| woken + requeued),
+        _ => Err(ENOSYS),
+    }
+}
+
+/// The key and the word of the futex at `uaddr`.
+fn futex_word<'a, Cx: FutexAccess>(
+    cx: &Cx,
+    uaddr: UserspacePtr<u32>,
+) -> Result<(u64, &'a AtomicU32), Errno> {
+    if !uaddr.addr().is_multiple_of(align_of::<AtomicU32>()) {
+        return Err(EINVAL);
+    }
+    uaddr.validate_range(size_of::<u32>())?;
+    let key = cx.futex_key(uaddr).ok_or(EFAULT)?;
+    // Safety: the word is mapped and aligned, and it is only accessed
+    // atomically
+    let word = unsafe { AtomicU32::from_ptr(uaddr.as_ptr().cast_mut()) };
+    Ok((key, word))
+}
+
+/// Reads the `timespec` at `ptr`, which may be null.
+fn read_timeout(ptr: usize) -> Result<Option<Duration>, Errno> {
+    if ptr == 0 {
+        return Ok(None);
+    }
+    let timeout = unsafe { UserspacePtr::<TimeSpec>::try_from_usize(ptr) }?;
+    timeout.validate_range(size_of::<TimeSpec>())?;
+    let timeout = unsafe { timeout.as_ptr().read_unaligned() };
+    duration_from_timespec(&timeout).map(Some)
+}
+
+/// Converts the number of waiters to wake or requeue, which is a C `int`.
+fn count(val: u32) -> Result<usize, Errno> {
+    usize::try_from(val as i32).map_err(|_| EINVAL)
+}
+
+fn wait<Cx: FutexAccess>(
+    cx: &Cx,
+    uaddr: UserspacePtr<u32>,
+    expected: u32,
+    bitset: u32,
+    deadline: Option<Duration>,
+) -> Result<usize, Errno> {
+    let (key, word) = futex_word(cx, uaddr)?;
+    let futexes = cx.futexes();
+    let waiter = futexes.enqueue(key, word, expected, bitset, cx.waker())?;
+    loop {
+        if waiter.is_woken() {
+            return Ok(0);
+        }
+        let result = if deadline.is_some_and(|deadline| cx.now() >= deadline) {
+            Err(ETIMEDEOUT)
+        } else {
+            cx.wait(&waiter, deadline).map_err(|_| EINTR)
+        };
+        if let Err(e) = result {
+            // a wake that came in the meantime counts
+            return if futexes.dequeue(&waiter) {
+                Err(e)
+            } else {
+                Ok(0)
+            };
+        }
+    }
+}
+
+fn wake<Cx: FutexAccess>(
+    cx: &Cx,
+    uaddr: UserspacePtr<u32>,
+    val: u32,
+    bitset: u32,
+) -> Result<usize, Errno> {
+    let (key, _) = futex_word(cx, uaddr)?;
+    Ok(cx.futexes().wake(key, count(val)?, bitset))
+}
+
+fn requeue<Cx: FutexAccess>(
+    cx: &Cx,
+    uaddr: UserspacePtr<u32>,
+    val: u32,
+    val2: usize,
+    uaddr2: usize,
+    expected: Option<u32>,
+) -> Result<(usize, usize), Errno> {
+    let wake = count(val)?;
+    // the number of waiters to requeue is passed in place of the timeout
+    let requeue = count(val2 as u32)?;
+    let (from, word) = futex_word(cx, uaddr)?;
+    let (to, _) = futex_word(cx, unsafe { UserspacePtr::try_from_usize(uaddr2) }?)?;
+    let check = expected.map(|expected| (word, expected));
+    cx.futexes().requeue(from, to, wake, requeue, check)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+    use alloc::collections::BTreeMap;
+    use alloc::sync::Arc;
+    use alloc::task::Wake;
+    use core::cell::Cell;
+    use core::ffi::c_int;
+    use core::sync::atomic::Ordering::Relaxed;
+    use core::sync::atomic::{AtomicU32, AtomicUsize};
+    use core::task::Waker;
+    use core::time::Duration;
+
+    use kernel_abi::{
+        EAGAIN, EFAULT, EINTR, EINVAL, ENOSYS, ETIMEDEOUT, FUTEX_BITSET_MATCH_ANY,
+        FUTEX_CLOCK_REALTIME, FUTEX_CMP_REQUEUE, FUTEX_PRIVATE_FLAG, FUTEX_REQUEUE, FUTEX_WAIT,
+        FUTEX_WAIT_BITSET, FUTEX_WAKE, FUTEX_WAKE_BITSET, TimeSpec,
+    };
+
+    use crate::UserspacePtr;
+    use crate::access::{FutexAccess, Interrupted};
+    use crate::futex::{FutexTable, Waiter, sys_futex};
+
+    type OnWait = Box<dyn Fn(&TestFutexAccess)>;
+
+    /// Counts how often it was woken.
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.0.fetch_add(1, Relaxed);
+        }
+    }
+
+    /// Futex words are identified by their address, unless `aliases` maps
+    /// it to the address of another word, like a shared mapping. Every
+    /// wait takes 10 milliseconds and calls `on_wait`, unless it is
+    /// interrupted.
+    #[derive(Default)]
+    struct TestFutexAccess {
+        futexes: FutexTable,
+        aliases: BTreeMap<usize, usize>,
+        now: Cell<Duration>,
+        waits: Cell<usize>,
+        interrupted: bool,
+        on_wait: Option<OnWait>,
+    }
+
+    impl FutexAccess for TestFutexAccess {
+        fn futex_key(&self, addr: UserspacePtr<u32>) -> Option<u64> {
+            // the first page is never mapped
+            let addr = addr.addr();
+            (addr >= 0x1000).then(|| *self.aliases.get(&addr).unwrap_or(&addr) as u64)
+        }
+
+        fn futexes(&self) -> &FutexTable {
+            &self.futexes
+        }
+
+        fn now(&self) -> Duration {
+            self.now.get()
+        }
+
+        fn waker(&self) -> Waker {
+            Waker::noop().clone()
+        }
+
+        fn wait(&self, _: &Waiter, _: Option<Duration>) -> Result<(), Interrupted> {
+            if self.interrupted {
+                return Err(Interrupted);
+            }
+            self.waits.set(self.waits.get() + 1);
+            self.now.set(self.now.get() + Duration::from_millis(10));
+            if let Some(on_wait) = &self.on_wait {
+                on_wait(self);
+            }
+            Ok(())
+        }
+    }
+
+    fn ptr(word: &AtomicU32) -> UserspacePtr<u32> {
+        UserspacePtr::try_from(word.as_ptr().cast_const()).unwrap()
+    }
+
+    fn addr(word: &AtomicU32) -> usize {
+        word.as_ptr() as usize
+    }
+
+    fn futex(
+        cx: &TestFutexAccess,
+        word: &AtomicU32,
+        op: c_int,
+        val: u32,
+        timeout: Option<&TimeSpec>,
+    ) -> Result<usize, kernel_abi::Errno> {
+        let timeout = timeout.map_or(0, |timeout| core::ptr::from_ref(timeout) as usize);
+        sys_futex(cx, ptr(word), op, val, timeout, 0, FUTEX_BITSET_MATCH_ANY)
+    }
+
+    fn key(word: &AtomicU32) -> u64 {
+        addr(word) as u64
+    }
+
+    #[test]
+    fn test_wait_value_changed() {
+        let cx = TestFutexAccess::default();
+        let word = AtomicU32::new(1);
+        assert_eq!(Err(EAGAIN), futex(&cx, &word, FUTEX_WAIT, 0, None));
+        assert_eq!(0, cx.waits.get());
+        assert_eq!(0, cx.futexes.wake(key(&word), 1, FUTEX_BITSET_MATCH_ANY));
+    }
+
+    #[test]
+    fn test_wait_woken() {
+        let word = &*Box::leak(Box::new(AtomicU32::new(0)));
+        let word_addr = addr(word);
+        let woken = &*Box::leak(Box::new(Cell::new(None)));
+        let cx = TestFutexAccess {
+            on_wait: Some(Box::new(move |cx| {
+                let word = unsafe { UserspacePtr::try_from_usize(word_addr) }.unwrap();
+                woken.set(Some(sys_futex(
+                    cx,
+                    word,
+                    FUTEX_WAKE | FUTEX_PRIVATE_FLAG,
+                    1,
+                    0,
+                    0,
+                    0,
+                )));
+            })),
+            ..Default::default()
+        };
+        assert_eq!(
+            Ok(0),
+            futex(&cx, word, FUTEX_WAIT | FUTEX_PRIVATE_FLAG, 0, None)
+        );
+        assert_eq!(Some(Ok(1)), woken.get());
+        assert_eq!(1, cx.waits.get());
+    }
+
+    #[test]
+    fn test_wait_timeout() {
+        let cx = TestFutexAccess::default();
+        let word = AtomicU32::new(0);
+        let timeout = TimeSpec {
+            tv_sec: 0,
+            tv_nsec: 25_000_000,
+        };
+        assert_eq!(
+            Err(ETIMEDEOUT),
+            futex(&cx, &word, FUTEX_WAIT, 0, Some(&timeout))
+        );
+        assert_eq!(3, cx.waits.get());
+        // the waiter is gone
+        assert_eq!(0, cx.futexes.wake(key(&word), 1, FUTEX_BITSET_MATCH_ANY));
+    }
+
+    #[test]
+    fn test_wait_bitset() {
+        let word = &*Box::leak(Box::new(AtomicU32::new(0)));
+        let word_addr = addr(word);
+        let woken = &*Box::leak(Box::new(Cell::new(None)));
+        let cx = TestFutexAccess {
+            now: Cell::new(Duration::from_millis(100)),
+            on_wait: Some(Box::new(move |cx| {
+                let word = unsafe { UserspacePtr::try_from_usize(word_addr) }.unwrap();
+                woken.set(Some(sys_futex(cx, word, FUTEX_WAKE_BITSET, 1, 0, 0, 0b10)));
+            })),
+            ..Default::default()
+        };
+        // the timeout is absolute
+        let timeout = TimeSpec {
+            tv_sec: 0,
+            tv_nsec: 120_000_000,
+        };
+        let timeout_ptr = core::ptr::from_ref(&timeout) as usize;
+        assert_eq!(
+            Err(ETIMEDEOUT),
+            sys_futex(&cx, ptr(word), FUTEX_WAIT_BITSET, 0, timeout_ptr, 0, 0b01)
+        );
+        assert_eq!(2, cx.waits.get());
+        // the bitsets don't match
+        assert_eq!(Some(Ok(0)), woken.get());
+
+        assert_eq!(
+            Err(EINVAL),
+            sys_futex(&cx, ptr(word), FUTEX_WAIT_BITSET, 0, timeout_ptr, 0, 0)
+        );
+        assert_eq!(
+            Err(EINVAL),
+            sys_futex(&cx, ptr(word), FUTEX_WAKE_BITSET, 1, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    fn test_wait_interrupted() {
+        let cx = TestFutexAccess {
+            interrupted: true,
+            ..Default::default()
+        };
+        let word = AtomicU32::new(0);
+        assert_eq!(Err(EINTR), futex(&cx, &word, FUTEX_WAIT, 0, None));
+        assert_eq!(0, cx.futexes.wake(key(&word), 1, FUTEX_BITSET_MATCH_ANY));
+    }
+
+    #[test]
+    fn test_errors() {
+        let cx = TestFutexAccess::default();
+        let words = [AtomicU32::new(0), AtomicU32::new(0)];
+
+        let misaligned = unsafe { UserspacePtr::try_from_usize(addr(&words[0]) + 1) }.unwrap();
+        assert_eq!(
+            Err(EINVAL),
+            sys_futex(&cx, misaligned, FUTEX_WAKE, 1, 0, 0, 0)
+        );
+        let unmapped = unsafe { UserspacePtr::try_from_usize(0x100) }.unwrap();
+        assert_eq!(
+            Err(EFAULT),
+            sys_futex(&cx, unmapped, FUTEX_WAKE, 1, 0, 0, 0)
+        );
+        assert_eq!(Err(ENOSYS), futex(&cx, &words[0], 2, 0, None));
+        assert_eq!(
+            Err(ENOSYS),
+            futex(
+                &cx,
+                &words[0],
+                FUTEX_WAIT_BITSET | FUTEX_CLOCK_REALTIME,
+                0,
+                None
+            )
+        );
+        let invalid = TimeSpec {
+            tv_sec: 0,
+            tv_nsec: 1_000_000_000,
+        };
+        assert_eq!(
+            Err(EINVAL),
+            futex(&cx, &words[0], FUTEX_WAIT, 0, Some(&invalid))
+        );
+        assert_eq!(
+            Err(EINVAL),
+            futex(&cx, &words[0], FUTEX_WAKE, u32::MAX, None)
+        );
+        assert_eq!(
+            Err(EFAULT),
+            sys_futex(&cx, ptr(&words[0]), FUTEX_REQUEUE, 1, 1, 0x100, 0)
+        );
+    }
+
+    #[test]
+    fn test_wake_order_and_count() {
+        let cx = TestFutexAccess::default();
+        let word = AtomicU32::new(0);
+        let wakes = Arc::new(CountingWaker::default());
+        let waker = Waker::from(wakes.clone());
+        let waiters = (0..3)
+            .map(|_| {
+                cx.futexes
+                    .enqueue(key(&word), &word, 0, FUTEX_BITSET_MATCH_ANY, waker.clone())
+                    .unwrap()
+            })
+            .collect::<alloc::vec::Vec<_>>();
+        assert_eq!(Ok(2), futex(&cx, &word, FUTEX_WAKE, 2, None));
+        assert!(waiters[0].is_woken());
+        assert!(waiters[1].is_woken());
+        assert!(!waiters[2].is_woken());
+        assert_eq!(2, wakes.0.load(Relaxed));
+        assert!(cx.futexes.dequeue(&waiters[2]));
+        assert!(!cx.futexes.dequeue(&waiters[0]));
+    }
+
+    #[test]
+    fn test_shared_mapping() {
+        let words = [AtomicU32::new(0), AtomicU32::new(0)];
+        let cx = TestFutexAccess {
+            aliases: BTreeMap::from([(addr(&words[1]), addr(&words[0]))]),
+            ..Default::default()
+        };
+        let waiter = cx
+            .futexes
+            .enqueue(
+                key(&words[0]),
+                &words[0],
+                0,
+                FUTEX_BITSET_MATCH_ANY,
+                Waker::noop().clone(),
+            )
+            .unwrap();
+        assert_eq!(Ok(1), futex(&cx, &words[1], FUTEX_WAKE, 1, None));
+        assert!(waiter.is_woken());
+    }
+
+    #[test]
+    fn test_requeue() {
+        let cx = TestFutexAccess::default();
+        let words = [AtomicU32::new(7), AtomicU32::new(0)];
+        let waiters = (0..3)
+            .map(|_| {
+                cx.futexes
+                    .enqueue(
+                        key(&words[0]),
+                        &words[0],
+                        7,
+                        FUTEX_BITSET_MATCH_ANY,
+                        Waker::noop().clone(),
+                    )
+                    .unwrap()
+            })
+            .collect::<alloc::vec::Vec<_>>();
+
+        let second = addr(&words[1]);
+        assert_eq!(
+            Err(EAGAIN),
+            sys_futex(&cx, ptr(&words[0]), FUTEX_CMP_REQUEUE, 1, 1, second, 8)
+        );
+        assert!(waiters.iter().all(|waiter| !waiter.is_woken()));
+
+        // wakes the first waiter and moves the second one
+        assert_eq!(
+            Ok(1),
+            sys_futex(&cx, ptr(&words[0]), FUTEX_REQUEUE, 1, 1, second, 0)
+        );
+        assert!(waiters[0].is_woken());
+        assert_eq!(Ok(0), futex(&cx, &words[0], FUTEX_WAKE, 0, None));
+        assert_eq!(Ok(1), futex(&cx, &words[1], FUTEX_WAKE, 5, None));
+        assert!(waiters[1].is_woken());
+        assert!(!waiters[2].is_woken());
+
+        // counts both the woken and the requeued waiters
+        assert_eq!(
+            Ok(1),
+            sys_futex(&cx, ptr(&words[0]), FUTEX_CMP_REQUEUE, 0, 1, second, 7)
+        );
+        assert!(!waiters[2].is_woken());
+        assert_eq!(Ok(0), futex(&cx, &words[0], FUTEX_WAKE, 5, None));
+        assert_eq!(Ok(1), futex(&cx, &words[1], FUTEX_WAKE, 5, None));
+        assert!(waiters[2].is_woken());
+    }
+}
@@ -0,0 +1,85 @@
+use kernel_abi::Errno;
+use kernel_vfs::IoctlError;
+
+use crate::access::FileAccess;
+use crate::{UserspaceMutPtr, UserspacePtr};
+
+pub fn sys_ioctl<Cx: FileAccess>(
+    cx: &Cx,
+    fildes: Cx::Fd,
+    request: u64,
+    arg: usize,
+) -> Result<usize, Errno>
+where
+    Errno: From<Cx::IoctlError>,
+{
+    cx.ioctl(fildes, request, arg).map_err(Errno::from)
+}
+
+/// Checks that the ioctl argument `arg` points to a `T` in userspace.
+fn check_arg<T>(arg: usize) -> Result<(), IoctlError> {
+    let ptr =
+        unsafe { UserspacePtr::<T>::try_from_usize(arg) }.map_err(|_| IoctlError::BadAddress)?;
+    ptr.validate_range(size_of::<T>())
+        .map_err(|_| IoctlError::BadAddress)?;
+    if arg == 0 {
+        return Err(IoctlError::BadAddress);
+    }
+    Ok(())
+}
+
+/// Reads the value that the ioctl argument `arg` points to, in the address
+/// space of the calling process.
+///
+/// # Errors
+/// Returns [`IoctlError::BadAddress`] if `arg` doesn't point to userspace.
+pub fn read_arg<T: Copy>(arg: usize) -> Result<T, IoctlError> {
+    check_arg::<T>(arg)?;
+    let ptr =
+        unsafe { UserspacePtr::<T>::try_from_usize(arg) }.map_err(|_| IoctlError::BadAddress)?;
+    Ok(unsafe { ptr.as_ptr().read_unaligned() })
+}
+
+/// Writes `value` to where the ioctl argument `arg` points to, in the
+/// address space of the calling process.
+///
+/// # Errors
+/// Returns [`IoctlError::BadAddress`] if `arg` doesn't point to userspace.
+pub fn write_arg<T>(arg: usize, value: T) -> Result<(), IoctlError> {
+    check_arg::<T>(arg)?;
+    let mut ptr =
+        unsafe { UserspaceMutPtr::<T>::try_from_usize(arg) }.map_err(|_| IoctlError::BadAddress)?;
+    unsafe { ptr.as_mut_ptr().write_unaligned(value) };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::sync::Arc;
+    use alloc::vec;
+
+    use kernel_abi::{EBADF, ENOTTY, TCGETS};
+    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
+    use spin::mutex::Mutex;
+
+    use crate::access::FileAccess;
+    use crate::access::testing::{MemoryFile, MemoryFileAccess};
+    use crate::ioctl::sys_ioctl;
+
+    #[test]
+    fn test_ioctl_not_a_tty() {
+        let mut file_access = MemoryFileAccess::default();
+        file_access.files.insert(
+            AbsoluteOwnedPath::try_from("/foo.txt").unwrap(),
+            Arc::new(MemoryFile::new(vec![0_u8; 16])),
+        );
+        let cx = Mutex::new(file_access);
+
+        let info = cx
+            .file_info(AbsolutePath::try_new("/foo.txt").unwrap())
+            .unwrap();
+        let fd = cx.open(&info).unwrap();
+        assert_eq!(Err(ENOTTY), sys_ioctl(&cx, fd, TCGETS, 0));
+        assert_eq!(Err(EBADF), sys_ioctl(&cx, 5.into(), TCGETS, 0));
+    }
+}
@@ -3,8 +3,18 @@
 extern crate alloc;
 
 pub mod access;
+pub mod epoll;
 pub mod fcntl;
+pub mod futex;
+pub mod ioctl;
 pub mod mman;
+pub mod mount;
+pub mod poll;
+pub mod random;
+pub mod sched;
+pub mod socket;
+pub mod syslog;
+pub mod thread;
 pub mod unistd;
 
 mod ptr;
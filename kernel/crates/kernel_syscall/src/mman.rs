@@ -1,7 +1,10 @@
-use kernel_abi::{EINVAL, ENOMEM, Errno, MapFlags, ProtFlags};
+use kernel_abi::{EBADF, EINVAL, ENODEV, ENOMEM, Errno, MapFlags, ProtFlags};
 
 use crate::UserspacePtr;
-use crate::access::{AllocationStrategy, Location, MemoryRegionAccess};
+use crate::access::{AllocationStrategy, CreateMappingError, Location, MemoryRegionAccess};
+
+/// File mappings must start at a page boundary of the file.
+const PAGE_SIZE: usize = 4096;
 
 pub fn sys_mmap<Cx: MemoryRegionAccess>(
     cx: &Cx,
@@ -9,8 +12,8 @@ pub fn sys_mmap<Cx: MemoryRegionAccess>(
     len: usize,
     prot: i32,
     flags: i32,
-    _fd: i32,
-    _offset: usize,
+    fd: i32,
+    offset: usize,
 ) -> Result<usize, Errno> {
     // Validate size is non-zero
     if len == 0 {
@@ -19,11 +22,12 @@ pub fn sys_mmap<Cx: MemoryRegionAccess>(
 
     let flags = MapFlags::from_bits(flags).ok_or(EINVAL)?;
 
-    // For now, only support anonymous private mappings
-    if !flags.contains(MapFlags::ANONYMOUS) {
+    // Exactly one of MAP_SHARED and MAP_PRIVATE must be set
+    if flags.contains(MapFlags::SHARED) == flags.contains(MapFlags::PRIVATE) {
         return Err(EINVAL);
     }
-    if !flags.contains(MapFlags::PRIVATE) {
+    // For now, anonymous mappings are private and file mappings are shared
+    if flags.contains(MapFlags::ANONYMOUS) != flags.contains(MapFlags::PRIVATE) {
         return Err(EINVAL);
     }
 
@@ -49,17 +53,26 @@ pub fn sys_mmap<Cx: MemoryRegionAccess>(
         Location::Anywhere
     };
 
-    // We'll use eager allocation for now (as specified in requirements)
-    let allocation_strategy = AllocationStrategy::Eager;
-
     // Create the mapping and add it to the process's memory regions
     // The context is responsible for converting the mapping to a region
-    let mapped_addr = cx
-        .create_and_track_mapping(location, len, allocation_strategy)
-        .map_err(|e| match e {
-            crate::access::CreateMappingError::LocationAlreadyMapped => EINVAL,
-            crate::access::CreateMappingError::OutOfMemory => ENOMEM,
-        })?;
+    let mapped_addr = if flags.contains(MapFlags::ANONYMOUS) {
+        // We'll use eager allocation for now (as specified in requirements)
+        cx.create_and_track_mapping(location, len, AllocationStrategy::Eager)
+    } else {
+        if fd < 0 {
+            return Err(EBADF);
+        }
+        if !offset.is_multiple_of(PAGE_SIZE) {
+            return Err(EINVAL);
+        }
+        cx.create_and_track_file_mapping(location, len, fd, offset)
+    }
+    .map_err(|e| match e {
+        CreateMappingError::LocationAlreadyMapped | CreateMappingError::InvalidRange => EINVAL,
+        CreateMappingError::OutOfMemory => ENOMEM,
+        CreateMappingError::BadFileDescriptor => EBADF,
+        CreateMappingError::NotMappable => ENODEV,
+    })?;
 
     Ok(mapped_addr.addr())
 }
@@ -68,8 +81,9 @@ pub fn sys_mmap<Cx: MemoryRegionAccess>(
 mod tests {
     use alloc::sync::Arc;
     use alloc::vec::Vec;
+    use core::ffi::c_int;
 
-    use kernel_abi::{EINVAL, MapFlags, ProtFlags};
+    use kernel_abi::{EBADF, EINVAL, ENODEV, MapFlags, ProtFlags};
     use spin::mutex::Mutex;
 
     use crate::UserspacePtr;
@@ -145,6 +159,24 @@ mod tests {
             Ok(ptr)
         }
 
+        /// Only fd 3 can be mapped, and it is 8 KiB long.
+        fn create_and_track_file_mapping(
+            &self,
+            location: Location,
+            size: usize,
+            fd: c_int,
+            offset: usize,
+        ) -> Result<UserspacePtr<u8>, CreateMappingError> {
+            match fd {
+                3 if offset + size <= 8192 => {
+                    self.create_and_track_mapping(location, size, AllocationStrategy::Eager)
+                }
+                3 => Err(CreateMappingError::InvalidRange),
+                0..=2 => Err(CreateMappingError::NotMappable),
+                _ => Err(CreateMappingError::BadFileDescriptor),
+            }
+        }
+
         fn add_memory_region(&self, _region: Self::Region) {
             // Just a placeholder for testing
         }
@@ -262,4 +294,30 @@ mod tests {
 
         assert_eq!(result, Err(EINVAL));
     }
+
+    #[test]
+    fn test_mmap_shared_file() {
+        let cx = Arc::new(TestMemoryAccess::new());
+        let addr = unsafe { UserspacePtr::try_from_usize(0).unwrap() };
+        let prot = (ProtFlags::READ | ProtFlags::WRITE).bits();
+        let shared = MapFlags::SHARED.bits();
+
+        assert!(sys_mmap(&cx, addr, 8192, prot, shared, 3, 0).is_ok());
+        assert!(sys_mmap(&cx, addr, 4096, prot, shared, 3, 4096).is_ok());
+        assert_eq!(
+            Err(EINVAL),
+            sys_mmap(&cx, addr, 4096, prot, shared, 3, 8192)
+        );
+        assert_eq!(Err(EINVAL), sys_mmap(&cx, addr, 4096, prot, shared, 3, 100));
+        assert_eq!(Err(ENODEV), sys_mmap(&cx, addr, 4096, prot, shared, 1, 0));
+        assert_eq!(Err(EBADF), sys_mmap(&cx, addr, 4096, prot, shared, 5, 0));
+        assert_eq!(Err(EBADF), sys_mmap(&cx, addr, 4096, prot, shared, -1, 0));
+
+        // anonymous mappings can't be shared, and file mappings can't be
+        // private yet
+        let flags = (MapFlags::SHARED | MapFlags::ANONYMOUS).bits();
+        assert_eq!(Err(EINVAL), sys_mmap(&cx, addr, 4096, prot, flags, -1, 0));
+        let flags = (MapFlags::SHARED | MapFlags::PRIVATE).bits();
+        assert_eq!(Err(EINVAL), sys_mmap(&cx, addr, 4096, prot, flags, 3, 0));
+    }
 }
@@ -0,0 +1,359 @@
+use core::slice::from_raw_parts;
+
+use kernel_abi::{
+    EBUSY, EFAULT, EINVAL, ENAMETOOLONG, ENODEV, ENOENT, ENOTDIR, ENOTEMPTY, Errno, MS_MGC_MSK,
+    MS_MGC_VAL, MountFlags, PATH_MAX, UmountFlags,
+};
+use kernel_vfs::path::{AbsoluteOwnedPath, Path};
+
+use crate::UserspacePtr;
+use crate::access::{CwdAccess, MountAccess, MountError, UnmountError};
+
+pub fn sys_mount<Cx: CwdAccess + MountAccess>(
+    cx: &Cx,
+    source: UserspacePtr<u8>,
+    target: UserspacePtr<u8>,
+    fs_type: UserspacePtr<u8>,
+    flags: u64,
+    data: UserspacePtr<u8>,
+) -> Result<usize, Errno> {
+    let flags = if flags & MS_MGC_MSK == MS_MGC_VAL {
+        flags & !MS_MGC_MSK
+    } else {
+        flags
+    };
+    // like on Linux, unknown flags are ignored, and so are NOSUID, NODEV,
+    // NOEXEC, REC and SILENT, which have no effect here
+    let flags = MountFlags::from_bits_truncate(flags);
+    if flags.intersects(MountFlags::REMOUNT | MountFlags::MOVE) {
+        return Err(EINVAL);
+    }
+    let read_only = flags.contains(MountFlags::RDONLY);
+
+    let source = unsafe { read_str(source) }?;
+    let target = target_path(cx, target)?;
+
+    let result = if flags.contains(MountFlags::BIND) {
+        let source = source.filter(|s| !s.is_empty()).ok_or(ENOENT)?;
+        let source = cx.absolute_path(Path::new(source));
+        cx.bind(source.as_ref(), target.as_ref(), read_only)
+    } else {
+        let fs_type = unsafe { read_str(fs_type) }?.ok_or(EINVAL)?;
+        let data = unsafe { read_str(data) }?.unwrap_or_default();
+        cx.mount(source, target.as_ref(), fs_type, data, read_only)
+    };
+
+    result.map(|()| 0).map_err(|e| match e {
+        MountError::NotFound => ENOENT,
+        MountError::NotADirectory => ENOTDIR,
+        MountError::NotEmpty => ENOTEMPTY,
+        MountError::Busy => EBUSY,
+        MountError::UnknownFileSystemType => ENODEV,
+        MountError::InvalidSource => EINVAL,
+    })
+}
+
+pub fn sys_umount2<Cx: CwdAccess + MountAccess>(
+    cx: &Cx,
+    target: UserspacePtr<u8>,
+    flags: i32,
+) -> Result<usize, Errno> {
+    let flags = UmountFlags::from_bits(flags).ok_or(EINVAL)?;
+    let target = target_path(cx, target)?;
+
+    // FORCE is accepted, but open files keep a file system busy regardless
+    cx.unmount(target.as_ref(), flags.contains(UmountFlags::DETACH))
+        .map(|()| 0)
+        .map_err(|e| match e {
+            UnmountError::NotMounted => EINVAL,
+            UnmountError::Busy => EBUSY,
+        })
+}
+
+fn target_path<Cx: CwdAccess>(
+    cx: &Cx,
+    target: UserspacePtr<u8>,
+) -> Result<AbsoluteOwnedPath, Errno> {
+    let target = unsafe { read_str(target) }?.ok_or(EFAULT)?;
+    if target.is_empty() {
+        return Err(ENOENT);
+    }
+    Ok(cx.absolute_path(Path::new(target)))
+}
+
+/// Reads a NUL terminated UTF-8 string of at most [`PATH_MAX`] bytes.
+/// Returns `None` for a null pointer.
+///
+/// # Safety
+/// The caller must ensure that a non-null `ptr` points to a NUL terminated
+/// string, or to at least `PATH_MAX + 1` readable bytes.
+unsafe fn read_str<'a>(ptr: UserspacePtr<u8>) -> Result<Option<&'a str>, Errno> {
+    if ptr.as_ptr().is_null() {
+        return Ok(None);
+    }
+
+    let len = (0..=PATH_MAX)
+        .find(|&i| unsafe { *ptr.as_ptr().add(i) } == 0)
+        .ok_or(ENAMETOOLONG)?;
+    let bytes = unsafe { from_raw_parts(ptr.as_ptr(), len) };
+    core::str::from_utf8(bytes).map(Some).map_err(|_| EINVAL)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::ToOwned;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::ptr::null;
+
+    use kernel_abi::{EBUSY, EFAULT, EINVAL, ENODEV, ENOENT, MS_MGC_VAL, MountFlags, UmountFlags};
+    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
+    use spin::mutex::Mutex;
+    use spin::rwlock::RwLock;
+
+    use crate::UserspacePtr;
+    use crate::access::{CwdAccess, MountAccess, MountError, UnmountError};
+    use crate::mount::{sys_mount, sys_umount2};
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    enum Call {
+        Mount {
+            source: Option<String>,
+            target: String,
+            fs_type: String,
+            data: String,
+            read_only: bool,
+        },
+        Bind {
+            source: String,
+            target: String,
+            read_only: bool,
+        },
+        Unmount {
+            target: String,
+            detach: bool,
+        },
+    }
+
+    struct TestMountCx {
+        cwd: RwLock<AbsoluteOwnedPath>,
+        calls: Mutex<Vec<Call>>,
+        busy: bool,
+    }
+
+    impl TestMountCx {
+        fn new() -> Self {
+            Self {
+                cwd: RwLock::new(AbsoluteOwnedPath::try_from("/home").unwrap()),
+                calls: Mutex::new(Vec::new()),
+                busy: false,
+            }
+        }
+
+        fn calls(&self) -> Vec<Call> {
+            self.calls.lock().clone()
+        }
+    }
+
+    impl CwdAccess for TestMountCx {
+        fn current_working_directory(&self) -> &RwLock<AbsoluteOwnedPath> {
+            &self.cwd
+        }
+    }
+
+    impl MountAccess for TestMountCx {
+        fn mount(
+            &self,
+            source: Option<&str>,
+            target: &AbsolutePath,
+            fs_type: &str,
+            data: &str,
+            read_only: bool,
+        ) -> Result<(), MountError> {
+            if fs_type != "tmpfs" {
+                return Err(MountError::UnknownFileSystemType);
+            }
+            self.calls.lock().push(Call::Mount {
+                source: source.map(ToOwned::to_owned),
+                target: target.to_string(),
+                fs_type: fs_type.to_owned(),
+                data: data.to_owned(),
+                read_only,
+            });
+            Ok(())
+        }
+
+        fn bind(
+            &self,
+            source: &AbsolutePath,
+            target: &AbsolutePath,
+            read_only: bool,
+        ) -> Result<(), MountError> {
+            self.calls.lock().push(Call::Bind {
+                source: source.to_string(),
+                target: target.to_string(),
+                read_only,
+            });
+            Ok(())
+        }
+
+        fn unmount(&self, target: &AbsolutePath, detach: bool) -> Result<(), UnmountError> {
+            if self.busy && !detach {
+                return Err(UnmountError::Busy);
+            }
+            self.calls.lock().push(Call::Unmount {
+                target: target.to_string(),
+                detach,
+            });
+            Ok(())
+        }
+    }
+
+    fn ptr(s: &'static [u8]) -> UserspacePtr<u8> {
+        UserspacePtr::try_from(s.as_ptr()).unwrap()
+    }
+
+    fn null_ptr() -> UserspacePtr<u8> {
+        UserspacePtr::try_from(null()).unwrap()
+    }
+
+    #[test]
+    fn test_mount() {
+        let cx = TestMountCx::new();
+        let result = sys_mount(
+            &cx,
+            ptr(b"none\0"),
+            ptr(b"mnt\0"),
+            ptr(b"tmpfs\0"),
+            MountFlags::RDONLY.bits(),
+            ptr(b"size=1M\0"),
+        );
+        assert_eq!(Ok(0), result);
+        assert_eq!(
+            vec![Call::Mount {
+                source: Some("none".into()),
+                target: "/home/mnt".into(),
+                fs_type: "tmpfs".into(),
+                data: "size=1M".into(),
+                read_only: true,
+            }],
+            cx.calls()
+        );
+    }
+
+    #[test]
+    fn test_mount_ignored_flags() {
+        let cx = TestMountCx::new();
+        let mount = |flags| {
+            sys_mount(
+                &cx,
+                null_ptr(),
+                ptr(b"/mnt\0"),
+                ptr(b"tmpfs\0"),
+                flags,
+                null_ptr(),
+            )
+        };
+
+        assert_eq!(Ok(0), mount(MS_MGC_VAL | MountFlags::RDONLY.bits()));
+        let ignored = MountFlags::NOSUID
+            | MountFlags::NODEV
+            | MountFlags::NOEXEC
+            | MountFlags::REC
+            | MountFlags::SILENT;
+        assert_eq!(Ok(0), mount(ignored.bits()));
+        // unknown flags, MS_RELATIME for example
+        assert_eq!(Ok(0), mount(1 << 21));
+
+        let call = |read_only| Call::Mount {
+            source: None,
+            target: "/mnt".into(),
+            fs_type: "tmpfs".into(),
+            data: String::new(),
+            read_only,
+        };
+        assert_eq!(vec![call(true), call(false), call(false)], cx.calls());
+    }
+
+    #[test]
+    fn test_mount_errors() {
+        let cx = TestMountCx::new();
+        let mount =
+            |fs_type, flags| sys_mount(&cx, null_ptr(), ptr(b"/mnt\0"), fs_type, flags, null_ptr());
+
+        assert_eq!(
+            Err(EINVAL),
+            mount(ptr(b"tmpfs\0"), MountFlags::REMOUNT.bits())
+        );
+        assert_eq!(Err(EINVAL), mount(ptr(b"tmpfs\0"), MountFlags::MOVE.bits()));
+        assert_eq!(Err(EINVAL), mount(null_ptr(), 0));
+        assert_eq!(Err(ENODEV), mount(ptr(b"nope\0"), 0));
+        assert_eq!(
+            Err(ENOENT),
+            sys_mount(&cx, null_ptr(), ptr(b"\0"), ptr(b"tmpfs\0"), 0, null_ptr())
+        );
+        assert_eq!(
+            Err(EFAULT),
+            sys_mount(&cx, null_ptr(), null_ptr(), ptr(b"tmpfs\0"), 0, null_ptr())
+        );
+        assert!(cx.calls().is_empty());
+    }
+
+    #[test]
+    fn test_bind() {
+        let cx = TestMountCx::new();
+        let result = sys_mount(
+            &cx,
+            ptr(b"data\0"),
+            ptr(b"/srv\0"),
+            null_ptr(),
+            MountFlags::BIND.bits(),
+            null_ptr(),
+        );
+        assert_eq!(Ok(0), result);
+        assert_eq!(
+            vec![Call::Bind {
+                source: "/home/data".into(),
+                target: "/srv".into(),
+                read_only: false,
+            }],
+            cx.calls()
+        );
+
+        // a bind mount needs a source
+        let result = sys_mount(
+            &cx,
+            null_ptr(),
+            ptr(b"/srv\0"),
+            null_ptr(),
+            MountFlags::BIND.bits(),
+            null_ptr(),
+        );
+        assert_eq!(Err(ENOENT), result);
+    }
+
+    #[test]
+    fn test_umount2() {
+        let mut cx = TestMountCx::new();
+        cx.busy = true;
+
+        assert_eq!(Err(EBUSY), sys_umount2(&cx, ptr(b"/mnt\0"), 0));
+        assert_eq!(
+            Err(EBUSY),
+            sys_umount2(&cx, ptr(b"/mnt\0"), UmountFlags::FORCE.bits())
+        );
+        assert_eq!(Err(EINVAL), sys_umount2(&cx, ptr(b"/mnt\0"), 0x100));
+        assert_eq!(
+            Ok(0),
+            sys_umount2(&cx, ptr(b"/mnt\0"), UmountFlags::DETACH.bits())
+        );
+        assert_eq!(
+            vec![Call::Unmount {
+                target: "/mnt".into(),
+                detach: true,
+            }],
+            cx.calls()
+        );
+    }
+}
@@ -0,0 +1,545 @@
+use core::ffi::{c_int, c_short};
+use core::task::Waker;
+use core::time::Duration;
+
+use kernel_abi::{
+    EBADF, EINTR, EINVAL, Errno, FD_SETSIZE, FdSet, PollEvents, PollFd, TimeSpec, TimeVal,
+};
+use kernel_vfs::Readiness;
+
+use crate::access::{PollAccess, Pollable};
+
+/// The events that a file with `readiness` has, like on Linux.
+#[must_use]
+pub fn poll_events(readiness: Readiness) -> PollEvents {
+    let mut events = PollEvents::empty();
+    events.set(PollEvents::IN | PollEvents::RDNORM, readiness.readable);
+    events.set(PollEvents::OUT | PollEvents::WRNORM, readiness.writable);
+    events.set(PollEvents::HUP, readiness.hangup);
+    events.set(PollEvents::ERR, readiness.error);
+    events
+}
+
+/// Calls `ready` until it returns a count other than zero or `timeout`
+/// passed, and returns the last count. Without a timeout, this waits
+/// forever. `ready` registers the waker with the files that it checks.
+pub(crate) fn wait_until_ready<Cx: PollAccess>(
+    cx: &Cx,
+    timeout: Option<Duration>,
+    mut ready: impl FnMut(&Waker) -> usize,
+) -> Result<usize, Errno> {
+    let deadline = timeout.map(|timeout| cx.now().saturating_add(timeout));
+    let mut count = 0;
+    cx.wait_until(deadline, |waker| {
+        count = ready(waker);
+        count > 0
+    })
+    .map_err(|_| EINTR)?;
+    Ok(count)
+}
+
+/// Converts a timeout in milliseconds, where negative values mean no
+/// timeout.
+pub(crate) fn timeout_from_millis(timeout: c_int) -> Option<Duration> {
+    u64::try_from(timeout).ok().map(Duration::from_millis)
+}
+
+/// Converts a `timespec`, which must neither be negative nor have more
+/// than a second worth of nanoseconds.
+pub(crate) fn duration_from_timespec(timespec: &TimeSpec) -> Result<Duration, Errno> {
+    let secs = u64::try_from(timespec.tv_sec).map_err(|_| EINVAL)?;
+    let nanos = u32::try_from(timespec.tv_nsec)
+        .ok()
+        .filter(|&nanos| nanos < 1_000_000_000)
+        .ok_or(EINVAL)?;
+    Ok(Duration::new(secs, nanos))
+}
+
+/// Waits until one of the files of `fds` has one of the requested events,
+/// or until `timeout` milliseconds passed. A negative timeout waits
+/// forever. Errors and hangups are always reported, and file descriptors
+/// that aren't open are reported as [`PollEvents::NVAL`].
+///
+/// Returns how many entries of `fds` have events.
+pub fn sys_poll<Cx: PollAccess>(
+    cx: &Cx,
+    fds: &mut [PollFd],
+    timeout: c_int,
+) -> Result<usize, Errno> {
+    poll(cx, fds, timeout_from_millis(timeout))
+}
+
+/// Like [`sys_poll`], but with a more precise timeout. Signals can't be
+/// blocked, so there is no signal mask.
+pub fn sys_ppoll<Cx: PollAccess>(
+    cx: &Cx,
+    fds: &mut [PollFd],
+    timeout: Option<&TimeSpec>,
+) -> Result<usize, Errno> {
+    let timeout = timeout.map(duration_from_timespec).transpose()?;
+    poll(cx, fds, timeout)
+}
+
+fn poll<Cx: PollAccess>(
+    cx: &Cx,
+    fds: &mut [PollFd],
+    timeout: Option<Duration>,
+) -> Result<usize, Errno> {
+    wait_until_ready(cx, timeout, |waker| {
+        let mut count = 0;
+        for fd in fds.iter_mut() {
+            fd.revents = 0;
+            if fd.fd < 0 {
+                continue;
+            }
+            let requested = PollEvents::from_bits_truncate(u32::from(fd.events as u16))
+                | PollEvents::ERR
+                | PollEvents::HUP;
+            let events = match cx.pollable(fd.fd.into()) {
+                Some(file) => {
+                    file.register_waker(waker);
+                    poll_events(file.readiness()) & requested
+                }
+                None => PollEvents::NVAL,
+            };
+            if !events.is_empty() {
+                fd.revents = events.bits() as c_short;
+                count += 1;
+            }
+        }
+        count
+    })
+}
+
+/// Waits until one of the first `nfds` file descriptors of `read` can be
+/// read, or one of `write` can be written, or until `timeout` passed. There
+/// is never urgent data, so `except` is only cleared. The sets are replaced
+/// by the file descriptors that are ready, and `timeout` by the time that
+/// was left, like on Linux.
+///
+/// Returns how many file descriptors are ready, counting those in both
+/// sets twice.
+pub fn sys_select<Cx: PollAccess>(
+    cx: &Cx,
+    nfds: c_int,
+    read: Option<&mut FdSet>,
+    write: Option<&mut FdSet>,
+    except: Option<&mut FdSet>,
+    timeout: Option<&mut TimeVal>,
+) -> Result<usize, Errno> {
+    let nfds = usize::try_from(nfds)
+        .ok()
+        .filter(|&nfds| nfds <= FD_SETSIZE)
+        .ok_or(EINVAL)?;
+    let duration = match &timeout {
+        None => None,
+        Some(timeout) => {
+            let secs = u64::try_from(timeout.tv_sec).map_err(|_| EINVAL)?;
+            let micros = u64::try_from(timeout.tv_usec).map_err(|_| EINVAL)?;
+            Some(Duration::from_secs(secs).saturating_add(Duration::from_micros(micros)))
+        }
+    };
+
+    let requested_read = read.as_deref().copied().unwrap_or_default();
+    let requested_write = write.as_deref().copied().unwrap_or_default();
+    let requested_except = except.as_deref().copied().unwrap_or_default();
+    for fd in 0..nfds {
+        let requested = requested_read.contains(fd)
+            || requested_write.contains(fd)
+            || requested_except.contains(fd);
+        if requested && cx.pollable(fd_from_index(fd)).is_none() {
+            return Err(EBADF);
+        }
+    }
+
+    let start = cx.now();
+    let mut ready_read = FdSet::default();
+    let mut ready_write = FdSet::default();
+    let count = wait_until_ready(cx, duration, |waker| {
+        ready_read = FdSet::default();
+        ready_write = FdSet::default();
+        let mut count = 0;
+        for fd in 0..nfds {
+            if !requested_read.contains(fd) && !requested_write.contains(fd) {
+                continue;
+            }
+            // a file that was closed in the meantime is ready, so that the
+            // caller finds out
+            let readiness = cx
+                .pollable(fd_from_index(fd))
+                .map_or(Readiness::READY, |file| {
+                    file.register_waker(waker);
+                    file.readiness()
+                });
+            if requested_read.contains(fd)
+                && (readiness.readable || readiness.hangup || readiness.error)
+            {
+                ready_read.insert(fd);
+                count += 1;
+            }
+            if requested_write.contains(fd) && (readiness.writable || readiness.error) {
+                ready_write.insert(fd);
+                count += 1;
+            }
+        }
+        count
+    })?;
+
+    if let Some(read) = read {
+        *read = ready_read;
+    }
+    if let Some(write) = write {
+        *write = ready_write;
+    }
+    if let Some(except) = except {
+        *except = FdSet::default();
+    }
+    if let (Some(timeout), Some(duration)) = (timeout, duration) {
+        let left = duration.saturating_sub(cx.now().saturating_sub(start));
+        *timeout = TimeVal {
+            tv_sec: i64::try_from(left.as_secs()).unwrap_or(i64::MAX),
+            tv_usec: i64::from(left.subsec_micros()),
+        };
+    }
+    Ok(count)
+}
+
+fn fd_from_index<Fd: From<c_int>>(fd: usize) -> Fd {
+    // `fd` is less than `FD_SETSIZE`
+    Fd::from(fd as c_int)
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use alloc::boxed::Box;
+    use alloc::collections::BTreeMap;
+    use alloc::sync::Arc;
+    use core::cell::{Cell, RefCell};
+    use core::ffi::c_int;
+    use core::sync::atomic::AtomicU64;
+    use core::sync::atomic::Ordering::Relaxed;
+    use core::task::Waker;
+    use core::time::Duration;
+
+    use kernel_vfs::Readiness;
+    use spin::Mutex;
+
+    use crate::access::{Interrupted, PollAccess, Pollable};
+    use crate::epoll::Epoll;
+
+    pub struct TestFile {
+        readiness: Mutex<Readiness>,
+        generation: AtomicU64,
+        /// How often a waker was registered with the file.
+        registered: AtomicU64,
+        epoll: bool,
+    }
+
+    impl TestFile {
+        pub fn new(readiness: Readiness, epoll: bool) -> Arc<Self> {
+            Arc::new(Self {
+                readiness: Mutex::new(readiness),
+                generation: AtomicU64::new(0),
+                registered: AtomicU64::new(0),
+                epoll,
+            })
+        }
+
+        pub fn set(&self, readiness: Readiness) {
+            *self.readiness.lock() = readiness;
+        }
+
+        /// Marks the file as used, like a read or write does.
+        pub fn touch(&self) {
+            self.generation.fetch_add(1, Relaxed);
+        }
+
+        pub fn registered(&self) -> u64 {
+            self.registered.load(Relaxed)
+        }
+    }
+
+    impl Pollable for TestFile {
+        fn readiness(&self) -> Readiness {
+            *self.readiness.lock()
+        }
+
+        fn register_waker(&self, _waker: &Waker) {
+            self.registered.fetch_add(1, Relaxed);
+        }
+
+        fn generation(&self) -> u64 {
+            self.generation.load(Relaxed)
+        }
+
+        fn is_epoll(&self) -> bool {
+            self.epoll
+        }
+    }
+
+    pub type OnWait = Box<dyn Fn(&TestPollAccess)>;
+
+    /// Every wait takes 10 milliseconds and calls `on_wait`, unless it is
+    /// interrupted.
+    #[derive(Default)]
+    pub struct TestPollAccess {
+        pub files: RefCell<BTreeMap<c_int, Arc<TestFile>>>,
+        pub epolls: RefCell<BTreeMap<c_int, Epoll<TestFile>>>,
+        pub now: Cell<Duration>,
+        pub waits: Cell<usize>,
+        pub interrupted: bool,
+        pub on_wait: Option<OnWait>,
+    }
+
+    impl TestPollAccess {
+        pub fn add(&self, fd: c_int, readiness: Readiness) -> Arc<TestFile> {
+            let file = TestFile::new(readiness, false);
+            self.files.borrow_mut().insert(fd, file.clone());
+            file
+        }
+    }
+
+    impl PollAccess for TestPollAccess {
+        type Fd = c_int;
+        type File = TestFile;
+
+        fn pollable(&self, fd: c_int) -> Option<Arc<TestFile>> {
+            self.files.borrow().get(&fd).cloned()
+        }
+
+        fn create_epoll(&self) -> c_int {
+            let fd = (0..)
+                .find(|fd| !self.files.borrow().contains_key(fd))
+                .unwrap();
+            self.files
+                .borrow_mut()
+                .insert(fd, TestFile::new(Readiness::default(), true));
+            self.epolls.borrow_mut().insert(fd, Epoll::new());
+            fd
+        }
+
+        fn with_epoll<R>(
+            &self,
+            epfd: c_int,
+            f: impl FnOnce(&mut Epoll<TestFile>) -> R,
+        ) -> Option<R> {
+            self.epolls.borrow_mut().get_mut(&epfd).map(f)
+        }
+
+        fn now(&self) -> Duration {
+            self.now.get()
+        }
+
+        fn wait_until(
+            &self,
+            deadline: Option<Duration>,
+            mut ready: impl FnMut(&Waker) -> bool,
+        ) -> Result<(), Interrupted> {
+            loop {
+                if ready(Waker::noop()) || deadline.is_some_and(|deadline| self.now() >= deadline) {
+                    return Ok(());
+                }
+                if self.interrupted {
+                    return Err(Interrupted);
+                }
+                self.waits.set(self.waits.get() + 1);
+                self.now.set(self.now.get() + Duration::from_millis(10));
+                if let Some(on_wait) = &self.on_wait {
+                    on_wait(self);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use kernel_abi::{EBADF, EINTR, EINVAL, FdSet, PollEvents, PollFd, TimeSpec, TimeVal};
+    use kernel_vfs::Readiness;
+
+    use super::testing::TestPollAccess;
+    use super::*;
+
+    const READABLE: Readiness = Readiness {
+        readable: true,
+        writable: false,
+        hangup: false,
+        error: false,
+    };
+
+    fn pollfd(fd: c_int, events: PollEvents) -> PollFd {
+        PollFd {
+            fd,
+            events: events.bits() as c_short,
+            revents: -1,
+        }
+    }
+
+    fn revents(fd: &PollFd) -> PollEvents {
+        PollEvents::from_bits_truncate(u32::from(fd.revents as u16))
+    }
+
+    #[test]
+    fn test_poll_events() {
+        assert_eq!(PollEvents::empty(), poll_events(Readiness::default()));
+        assert_eq!(
+            PollEvents::IN | PollEvents::RDNORM | PollEvents::OUT | PollEvents::WRNORM,
+            poll_events(Readiness::READY)
+        );
+        let hangup = Readiness {
+            hangup: true,
+            error: true,
+            ..Readiness::default()
+        };
+        assert_eq!(PollEvents::HUP | PollEvents::ERR, poll_events(hangup));
+    }
+
+    #[test]
+    fn test_poll() {
+        let cx = TestPollAccess::default();
+        cx.add(3, READABLE);
+        cx.add(
+            4,
+            Readiness {
+                hangup: true,
+                ..Readiness::READY
+            },
+        );
+        let mut fds = [
+            pollfd(3, PollEvents::IN | PollEvents::OUT),
+            pollfd(3, PollEvents::OUT),
+            // hangups are reported even if they weren't requested
+            pollfd(4, PollEvents::OUT),
+            pollfd(5, PollEvents::IN),
+            pollfd(-1, PollEvents::IN),
+        ];
+        assert_eq!(Ok(3), sys_poll(&cx, &mut fds, -1));
+        assert_eq!(PollEvents::IN, revents(&fds[0]));
+        assert_eq!(PollEvents::empty(), revents(&fds[1]));
+        assert_eq!(PollEvents::OUT | PollEvents::HUP, revents(&fds[2]));
+        assert_eq!(PollEvents::NVAL, revents(&fds[3]));
+        assert_eq!(0, fds[4].revents);
+        assert_eq!(0, cx.waits.get());
+    }
+
+    #[test]
+    fn test_poll_timeout() {
+        let cx = TestPollAccess::default();
+        cx.add(3, Readiness::default());
+        let mut fds = [pollfd(3, PollEvents::IN)];
+        assert_eq!(Ok(0), sys_poll(&cx, &mut fds, 0));
+        assert_eq!(0, cx.waits.get());
+
+        assert_eq!(Ok(0), sys_poll(&cx, &mut fds, 25));
+        assert_eq!(3, cx.waits.get());
+        assert_eq!(0, fds[0].revents);
+
+        let timeout = TimeSpec {
+            tv_sec: 0,
+            tv_nsec: 20_000_000,
+        };
+        assert_eq!(Ok(0), sys_ppoll(&cx, &mut fds, Some(&timeout)));
+        assert_eq!(5, cx.waits.get());
+
+        let invalid = TimeSpec {
+            tv_sec: 0,
+            tv_nsec: 1_000_000_000,
+        };
+        assert_eq!(Err(EINVAL), sys_ppoll(&cx, &mut fds, Some(&invalid)));
+    }
+
+    #[test]
+    fn test_poll_waits() {
+        let mut cx = TestPollAccess::default();
+        cx.add(3, Readiness::default());
+        cx.on_wait = Some(Box::new(|cx: &TestPollAccess| {
+            if cx.waits.get() == 2 {
+                cx.files.borrow()[&3].set(READABLE);
+            }
+        }));
+        let mut fds = [pollfd(3, PollEvents::IN)];
+        assert_eq!(Ok(1), sys_ppoll(&cx, &mut fds, None));
+        assert_eq!(2, cx.waits.get());
+        assert_eq!(PollEvents::IN, revents(&fds[0]));
+        // the waker is registered before every check
+        assert_eq!(3, cx.files.borrow()[&3].registered());
+
+        cx.interrupted = true;
+        cx.files.borrow()[&3].set(Readiness::default());
+        assert_eq!(Err(EINTR), sys_poll(&cx, &mut fds, -1));
+    }
+
+    fn fd_set(fds: &[usize]) -> FdSet {
+        let mut set = FdSet::default();
+        for &fd in fds {
+            set.insert(fd);
+        }
+        set
+    }
+
+    #[test]
+    fn test_select() {
+        let cx = TestPollAccess::default();
+        cx.add(3, READABLE);
+        cx.add(4, Readiness::READY);
+        cx.add(5, Readiness::default());
+        let mut read = fd_set(&[3, 4, 5]);
+        let mut write = fd_set(&[3, 4]);
+        let mut except = fd_set(&[3]);
+        assert_eq!(
+            Ok(3),
+            sys_select(
+                &cx,
+                6,
+                Some(&mut read),
+                Some(&mut write),
+                Some(&mut except),
+                None
+            )
+        );
+        assert_eq!(fd_set(&[3, 4]), read);
+        assert_eq!(fd_set(&[4]), write);
+        assert_eq!(FdSet::default(), except);
+    }
+
+    #[test]
+    fn test_select_timeout() {
+        let cx = TestPollAccess::default();
+        cx.add(3, Readiness::default());
+        let mut read = fd_set(&[3]);
+        let mut timeout = TimeVal {
+            tv_sec: 0,
+            tv_usec: 50_000,
+        };
+        assert_eq!(
+            Ok(0),
+            sys_select(&cx, 4, Some(&mut read), None, None, Some(&mut timeout))
+        );
+        assert_eq!(FdSet::default(), read);
+        assert_eq!(5, cx.waits.get());
+        assert_eq!(TimeVal::default(), timeout);
+
+        // descriptors from `nfds` on are ignored
+        let mut read = fd_set(&[3]);
+        let mut timeout = TimeVal::default();
+        assert_eq!(
+            Ok(0),
+            sys_select(&cx, 3, Some(&mut read), None, None, Some(&mut timeout))
+        );
+    }
+
+    #[test]
+    fn test_select_invalid() {
+        let cx = TestPollAccess::default();
+        let mut read = fd_set(&[3]);
+        assert_eq!(
+            Err(EBADF),
+            sys_select(&cx, 4, Some(&mut read), None, None, None)
+        );
+        assert_eq!(fd_set(&[3]), read, "the sets are unchanged on errors");
+        assert_eq!(Err(EINVAL), sys_select(&cx, -1, None, None, None, None));
+        assert_eq!(Err(EINVAL), sys_select(&cx, 1025, None, None, None, None));
+    }
+}
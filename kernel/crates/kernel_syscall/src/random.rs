@@ -0,0 +1,107 @@
+use kernel_abi::{EAGAIN, EINTR, EINVAL, Errno, GetRandomFlags};
+
+use crate::access::RandomAccess;
+
+pub fn sys_getrandom<Cx: RandomAccess>(
+    cx: &Cx,
+    buf: &mut [u8],
+    flags: u32,
+) -> Result<usize, Errno> {
+    let flags = GetRandomFlags::from_bits(flags).ok_or(EINVAL)?;
+    if flags.contains(GetRandomFlags::RANDOM | GetRandomFlags::INSECURE) {
+        return Err(EINVAL);
+    }
+
+    if !flags.contains(GetRandomFlags::INSECURE) && !cx.is_seeded() {
+        if flags.contains(GetRandomFlags::NONBLOCK) {
+            return Err(EAGAIN);
+        }
+        cx.wait_until_seeded().map_err(|_| EINTR)?;
+    }
+
+    cx.fill_random(buf);
+    Ok(buf.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+
+    use kernel_abi::{EAGAIN, EINTR, EINVAL, GetRandomFlags};
+
+    use crate::access::{Interrupted, RandomAccess};
+    use crate::random::sys_getrandom;
+
+    struct TestRandomAccess {
+        seeded: Cell<bool>,
+        /// Whether a wait for the seed succeeds.
+        seeds_while_waiting: bool,
+    }
+
+    impl RandomAccess for TestRandomAccess {
+        fn is_seeded(&self) -> bool {
+            self.seeded.get()
+        }
+
+        fn wait_until_seeded(&self) -> Result<(), Interrupted> {
+            if self.seeds_while_waiting {
+                self.seeded.set(true);
+                Ok(())
+            } else {
+                Err(Interrupted)
+            }
+        }
+
+        fn fill_random(&self, buf: &mut [u8]) {
+            buf.fill(if self.seeded.get() { 0xaa } else { 0x55 });
+        }
+    }
+
+    fn cx(seeded: bool, seeds_while_waiting: bool) -> TestRandomAccess {
+        TestRandomAccess {
+            seeded: Cell::new(seeded),
+            seeds_while_waiting,
+        }
+    }
+
+    #[test]
+    fn test_getrandom() {
+        let mut buf = [0; 8];
+        assert_eq!(Ok(8), sys_getrandom(&cx(true, false), &mut buf, 0));
+        assert_eq!([0xaa; 8], buf);
+
+        let flags = GetRandomFlags::RANDOM | GetRandomFlags::NONBLOCK;
+        assert_eq!(
+            Ok(3),
+            sys_getrandom(&cx(true, false), &mut buf[..3], flags.bits())
+        );
+        assert_eq!(Ok(0), sys_getrandom(&cx(true, false), &mut [], 0));
+    }
+
+    #[test]
+    fn test_getrandom_unseeded() {
+        let mut buf = [0; 8];
+        assert_eq!(Ok(8), sys_getrandom(&cx(false, true), &mut buf, 0));
+        assert_eq!([0xaa; 8], buf);
+        assert_eq!(Err(EINTR), sys_getrandom(&cx(false, false), &mut buf, 0));
+
+        let nonblock = GetRandomFlags::NONBLOCK.bits();
+        assert_eq!(
+            Err(EAGAIN),
+            sys_getrandom(&cx(false, true), &mut buf, nonblock)
+        );
+
+        let insecure = GetRandomFlags::INSECURE.bits();
+        assert_eq!(Ok(8), sys_getrandom(&cx(false, false), &mut buf, insecure));
+        assert_eq!([0x55; 8], buf);
+    }
+
+    #[test]
+    fn test_getrandom_invalid_flags() {
+        let cx = cx(true, true);
+        let mut buf = [0; 8];
+        let flags = GetRandomFlags::RANDOM | GetRandomFlags::INSECURE;
+        assert_eq!(Err(EINVAL), sys_getrandom(&cx, &mut buf, flags.bits()));
+        assert_eq!(Err(EINVAL), sys_getrandom(&cx, &mut buf, 0x8));
+    }
+}
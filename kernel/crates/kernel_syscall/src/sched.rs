@@ -0,0 +1,374 @@
+use core::ffi::c_int;
+
+use kernel_abi::{EINVAL, EPERM, ESRCH, Errno, MAX_CPUS, NICE_MAX, PRIO_PROCESS, SchedParam};
+use kernel_sched::{Nice, Params, Policy};
+
+use crate::access::SchedAccess;
+
+/// The size of a CPU affinity mask in bytes.
+const MASK_SIZE: usize = MAX_CPUS / 8;
+
+/// Restricts the thread `tid`, or the current thread if `tid` is zero, to
+/// the CPUs in `mask`, which has one bit per CPU. The bits of CPUs that
+/// don't exist are ignored, but at least one CPU that exists must be in
+/// the mask.
+pub fn sys_sched_setaffinity<Cx: SchedAccess>(
+    cx: &Cx,
+    tid: c_int,
+    mask: &[u8],
+) -> Result<usize, Errno> {
+    if tid < 0 {
+        return Err(EINVAL);
+    }
+    let mut bytes = [0; MASK_SIZE];
+    let len = mask.len().min(MASK_SIZE);
+    bytes[..len].copy_from_slice(&mask[..len]);
+    let mask = u64::from_le_bytes(bytes) & all_cpus(cx);
+    if mask == 0 {
+        return Err(EINVAL);
+    }
+    cx.set_affinity(tid, mask).map_err(|_| ESRCH)?;
+    Ok(0)
+}
+
+/// Writes the CPU affinity mask of the thread `tid`, or of the current
+/// thread if `tid` is zero, to `mask`. Like on Linux, `mask` must have room
+/// for a bit for every CPU, and its length must be a multiple of the size
+/// of a `long`.
+///
+/// Returns the number of bytes that were written.
+pub fn sys_sched_getaffinity<Cx: SchedAccess>(
+    cx: &Cx,
+    tid: c_int,
+    mask: &mut [u8],
+) -> Result<usize, Errno> {
+    if tid < 0 || mask.len() * 8 < cx.cpu_count() || !mask.len().is_multiple_of(size_of::<u64>()) {
+        return Err(EINVAL);
+    }
+    let affinity = cx.affinity(tid).map_err(|_| ESRCH)?;
+    let len = mask.len().min(MASK_SIZE);
+    mask[..len].copy_from_slice(&affinity.to_le_bytes()[..len]);
+    Ok(len)
+}
+
+/// Lets other threads run before the current thread continues. Real-time
+/// threads only make way for threads with at least their priority, and
+/// normal threads for everything but idle threads.
+pub fn sys_sched_yield<Cx: SchedAccess>(cx: &Cx) -> Result<usize, Errno> {
+    cx.yield_now();
+    Ok(0)
+}
+
+/// Sets the scheduling policy and the real-time priority of the thread
+/// `tid`, or of the current thread if `tid` is zero. The nice value is
+/// kept, and is used again once the thread switches back to
+/// [`SCHED_OTHER`](kernel_abi::SCHED_OTHER).
+///
+/// Real-time policies would let a process starve every other process, so
+/// they are refused with [`EPERM`], like for an unprivileged process on
+/// Linux.
+pub fn sys_sched_setscheduler<Cx: SchedAccess>(
+    cx: &Cx,
+    tid: c_int,
+    policy: c_int,
+    param: &SchedParam,
+) -> Result<usize, Errno> {
+    if tid < 0 {
+        return Err(EINVAL);
+    }
+    let policy = Policy::try_from(policy).map_err(|_| EINVAL)?;
+    let nice = cx.params(tid).map_err(|_| ESRCH)?.nice();
+    let params = Params::new(policy, param.sched_priority, nice).map_err(|_| EINVAL)?;
+    if policy.is_real_time() {
+        return Err(EPERM);
+    }
+    cx.set_params(tid, params).map_err(|_| ESRCH)?;
+    Ok(0)
+}
+
+/// Returns the scheduling policy of the thread `tid`, or of the current
+/// thread if `tid` is zero.
+pub fn sys_sched_getscheduler<Cx: SchedAccess>(cx: &Cx, tid: c_int) -> Result<usize, Errno> {
+    if tid < 0 {
+        return Err(EINVAL);
+    }
+    let policy = cx.params(tid).map_err(|_| ESRCH)?.policy();
+    Ok(usize::try_from(c_int::from(policy)).expect("policies should be positive"))
+}
+
+/// Sets the nice value of the thread `who`, or of the current thread if
+/// `who` is zero. Nice values outside of the valid range are clamped.
+/// Negative nice values are refused with [`EPERM`], since they would give
+/// the thread more than its share of the CPU time.
+pub fn sys_setpriority<Cx: SchedAccess>(
+    cx: &Cx,
+    which: c_int,
+    who: c_int,
+    nice: c_int,
+) -> Result<usize, Errno> {
+    if which != PRIO_PROCESS {
+        return Err(EINVAL);
+    }
+    if who < 0 {
+        return Err(ESRCH);
+    }
+    let params = cx.params(who).map_err(|_| ESRCH)?;
+    let nice = Nice::new_clamped(nice);
+    if nice.get() < 0 {
+        return Err(EPERM);
+    }
+    cx.set_params(who, params.with_nice(nice))
+        .map_err(|_| ESRCH)?;
+    Ok(0)
+}
+
+/// Returns the nice value of the thread `who`, or of the current thread if
+/// `who` is zero. Like on Linux, the value is returned as `20 - nice`, from
+/// 1 to 40, so that it can't be mistaken for an error.
+pub fn sys_getpriority<Cx: SchedAccess>(cx: &Cx, which: c_int, who: c_int) -> Result<usize, Errno> {
+    if which != PRIO_PROCESS {
+        return Err(EINVAL);
+    }
+    if who < 0 {
+        return Err(ESRCH);
+    }
+    let nice = cx.params(who).map_err(|_| ESRCH)?.nice();
+    Ok(usize::try_from(NICE_MAX + 1 - nice.get()).expect("nice should be in range"))
+}
+
+/// The mask that contains every CPU.
+fn all_cpus<Cx: SchedAccess>(cx: &Cx) -> u64 {
+    match cx.cpu_count() {
+        count if count >= MAX_CPUS => u64::MAX,
+        count => (1 << count) - 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::collections::BTreeMap;
+    use core::cell::{Cell, RefCell};
+    use core::ffi::c_int;
+
+    use kernel_abi::{
+        EINVAL, EPERM, ESRCH, PRIO_PROCESS, SCHED_FIFO, SCHED_IDLE, SCHED_OTHER, SCHED_RR,
+        SchedParam,
+    };
+    use kernel_sched::{Nice, Params, Policy};
+
+    use crate::access::{NoSuchThread, SchedAccess};
+    use crate::sched::{
+        sys_getpriority, sys_sched_getaffinity, sys_sched_getscheduler, sys_sched_setaffinity,
+        sys_sched_setscheduler, sys_sched_yield, sys_setpriority,
+    };
+
+    /// The current thread is thread 1.
+    struct TestSchedAccess {
+        cpu_count: usize,
+        affinities: RefCell<BTreeMap<c_int, u64>>,
+        params: RefCell<BTreeMap<c_int, Params>>,
+        yields: Cell<usize>,
+    }
+
+    impl TestSchedAccess {
+        fn new(cpu_count: usize) -> Self {
+            Self {
+                cpu_count,
+                affinities: RefCell::new(BTreeMap::from([(1, u64::MAX), (2, 0b10)])),
+                params: RefCell::new(BTreeMap::from([
+                    (1, Params::default()),
+                    (2, Params::default()),
+                ])),
+                yields: Cell::new(0),
+            }
+        }
+
+        fn params_of(&self, tid: c_int) -> Params {
+            self.params.borrow()[&tid]
+        }
+    }
+
+    impl SchedAccess for TestSchedAccess {
+        fn cpu_count(&self) -> usize {
+            self.cpu_count
+        }
+
+        fn affinity(&self, tid: c_int) -> Result<u64, NoSuchThread> {
+            let tid = if tid == 0 { 1 } else { tid };
+            self.affinities
+                .borrow()
+                .get(&tid)
+                .copied()
+                .ok_or(NoSuchThread)
+        }
+
+        fn set_affinity(&self, tid: c_int, mask: u64) -> Result<(), NoSuchThread> {
+            let tid = if tid == 0 { 1 } else { tid };
+            let mut affinities = self.affinities.borrow_mut();
+            *affinities.get_mut(&tid).ok_or(NoSuchThread)? = mask;
+            Ok(())
+        }
+
+        fn params(&self, tid: c_int) -> Result<Params, NoSuchThread> {
+            let tid = if tid == 0 { 1 } else { tid };
+            self.params.borrow().get(&tid).copied().ok_or(NoSuchThread)
+        }
+
+        fn set_params(&self, tid: c_int, params: Params) -> Result<(), NoSuchThread> {
+            let tid = if tid == 0 { 1 } else { tid };
+            let mut all_params = self.params.borrow_mut();
+            *all_params.get_mut(&tid).ok_or(NoSuchThread)? = params;
+            Ok(())
+        }
+
+        fn yield_now(&self) {
+            self.yields.set(self.yields.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_set_and_get_affinity() {
+        let cx = TestSchedAccess::new(4);
+        assert_eq!(Ok(0), sys_sched_setaffinity(&cx, 0, &[0b0110]));
+        assert_eq!(Some(&0b0110), cx.affinities.borrow().get(&1));
+
+        let mut mask = [0xff; 16];
+        assert_eq!(Ok(8), sys_sched_getaffinity(&cx, 0, &mut mask));
+        assert_eq!([0b0110, 0, 0, 0, 0, 0, 0, 0], mask[..8]);
+        // the rest is left alone
+        assert_eq!([0xff; 8], mask[8..]);
+
+        assert_eq!(Ok(8), sys_sched_getaffinity(&cx, 2, &mut mask));
+        assert_eq!(0b10, mask[0]);
+    }
+
+    #[test]
+    fn test_set_affinity_ignores_missing_cpus() {
+        let cx = TestSchedAccess::new(2);
+        // a glibc `cpu_set_t` has 1024 bits
+        let mut mask = [0xff; 128];
+        mask[0] = 0b1101;
+        assert_eq!(Ok(0), sys_sched_setaffinity(&cx, 1, &mask));
+        assert_eq!(Some(&0b01), cx.affinities.borrow().get(&1));
+
+        let cx = TestSchedAccess::new(64);
+        assert_eq!(Ok(0), sys_sched_setaffinity(&cx, 1, &[0xff; 8]));
+        assert_eq!(Some(&u64::MAX), cx.affinities.borrow().get(&1));
+    }
+
+    #[test]
+    fn test_affinity_errors() {
+        let cx = TestSchedAccess::new(2);
+        // no CPU that exists
+        assert_eq!(Err(EINVAL), sys_sched_setaffinity(&cx, 0, &[0b100]));
+        assert_eq!(Err(EINVAL), sys_sched_setaffinity(&cx, 0, &[]));
+        assert_eq!(Err(ESRCH), sys_sched_setaffinity(&cx, 3, &[1]));
+        assert_eq!(Err(EINVAL), sys_sched_setaffinity(&cx, -1, &[1]));
+        assert_eq!(Some(&u64::MAX), cx.affinities.borrow().get(&1));
+
+        let mut mask = [0; 8];
+        assert_eq!(Err(ESRCH), sys_sched_getaffinity(&cx, 3, &mut mask));
+        assert_eq!(Err(EINVAL), sys_sched_getaffinity(&cx, -1, &mut mask));
+        // not a multiple of the size of a `long`
+        assert_eq!(Err(EINVAL), sys_sched_getaffinity(&cx, 0, &mut mask[..4]));
+        // too small for all CPUs
+        let cx = TestSchedAccess::new(65);
+        assert_eq!(Err(EINVAL), sys_sched_getaffinity(&cx, 0, &mut mask));
+    }
+
+    #[test]
+    fn test_set_and_get_scheduler() {
+        let cx = TestSchedAccess::new(1);
+        let param = SchedParam::default();
+        assert_eq!(Ok(0), sys_sched_setscheduler(&cx, 0, SCHED_IDLE, &param));
+        assert_eq!(
+            Params::new(Policy::Idle, 0, Nice::default()),
+            Ok(cx.params_of(1))
+        );
+        assert_eq!(Ok(SCHED_IDLE as usize), sys_sched_getscheduler(&cx, 0));
+        assert_eq!(Ok(SCHED_OTHER as usize), sys_sched_getscheduler(&cx, 2));
+
+        // real-time threads can only be created by the kernel
+        let rr = Params::new(Policy::RoundRobin, 10, Nice::default()).unwrap();
+        cx.set_params(2, rr).unwrap();
+        assert_eq!(Ok(SCHED_RR as usize), sys_sched_getscheduler(&cx, 2));
+        assert_eq!(Ok(0), sys_sched_setscheduler(&cx, 2, SCHED_OTHER, &param));
+        assert_eq!(Ok(SCHED_OTHER as usize), sys_sched_getscheduler(&cx, 2));
+
+        assert_eq!(Ok(0), sys_sched_yield(&cx));
+        assert_eq!(1, cx.yields.get());
+    }
+
+    #[test]
+    fn test_scheduler_errors() {
+        let cx = TestSchedAccess::new(1);
+        let param = SchedParam { sched_priority: 0 };
+        // real-time policies need a priority, and the others don't have one
+        assert_eq!(
+            Err(EINVAL),
+            sys_sched_setscheduler(&cx, 0, SCHED_FIFO, &param)
+        );
+        let param = SchedParam { sched_priority: 1 };
+        assert_eq!(
+            Err(EINVAL),
+            sys_sched_setscheduler(&cx, 0, SCHED_OTHER, &param)
+        );
+        // SCHED_BATCH
+        assert_eq!(Err(EINVAL), sys_sched_setscheduler(&cx, 0, 3, &param));
+        assert_eq!(
+            Err(EINVAL),
+            sys_sched_setscheduler(&cx, -1, SCHED_FIFO, &param)
+        );
+        assert_eq!(
+            Err(ESRCH),
+            sys_sched_setscheduler(&cx, 3, SCHED_FIFO, &param)
+        );
+        // real-time policies aren't permitted
+        assert_eq!(
+            Err(EPERM),
+            sys_sched_setscheduler(&cx, 0, SCHED_FIFO, &param)
+        );
+        let param = SchedParam { sched_priority: 99 };
+        assert_eq!(Err(EPERM), sys_sched_setscheduler(&cx, 2, SCHED_RR, &param));
+        assert_eq!(Params::default(), cx.params_of(1));
+        assert_eq!(Params::default(), cx.params_of(2));
+
+        assert_eq!(Err(ESRCH), sys_sched_getscheduler(&cx, 3));
+        assert_eq!(Err(EINVAL), sys_sched_getscheduler(&cx, -1));
+    }
+
+    #[test]
+    fn test_priority() {
+        let cx = TestSchedAccess::new(1);
+        assert_eq!(Ok(20), sys_getpriority(&cx, PRIO_PROCESS, 0));
+
+        assert_eq!(Ok(0), sys_setpriority(&cx, PRIO_PROCESS, 0, 5));
+        assert_eq!(Nice::new_clamped(5), cx.params_of(1).nice());
+        assert_eq!(Ok(15), sys_getpriority(&cx, PRIO_PROCESS, 0));
+
+        // out of range values are clamped
+        assert_eq!(Ok(0), sys_setpriority(&cx, PRIO_PROCESS, 2, 100));
+        assert_eq!(Ok(1), sys_getpriority(&cx, PRIO_PROCESS, 2));
+        assert_eq!(Ok(0), sys_setpriority(&cx, PRIO_PROCESS, 2, 0));
+
+        // the nice value survives another policy
+        let param = SchedParam::default();
+        assert_eq!(Ok(0), sys_sched_setscheduler(&cx, 0, SCHED_IDLE, &param));
+        assert_eq!(Ok(15), sys_getpriority(&cx, PRIO_PROCESS, 0));
+        assert_eq!(Policy::Idle, cx.params_of(1).policy());
+        assert_eq!(Ok(0), sys_sched_setscheduler(&cx, 0, SCHED_OTHER, &param));
+        assert_eq!(Nice::new_clamped(5), cx.params_of(1).nice());
+
+        // negative nice values aren't permitted
+        assert_eq!(Err(EPERM), sys_setpriority(&cx, PRIO_PROCESS, 0, -1));
+        assert_eq!(Err(EPERM), sys_setpriority(&cx, PRIO_PROCESS, 2, -100));
+        assert_eq!(Ok(15), sys_getpriority(&cx, PRIO_PROCESS, 0));
+        assert_eq!(Ok(20), sys_getpriority(&cx, PRIO_PROCESS, 2));
+
+        // there are no process groups or users
+        assert_eq!(Err(EINVAL), sys_setpriority(&cx, 1, 0, 0));
+        assert_eq!(Err(EINVAL), sys_getpriority(&cx, 2, 0));
+        assert_eq!(Err(ESRCH), sys_setpriority(&cx, PRIO_PROCESS, 3, 0));
+        assert_eq!(Err(ESRCH), sys_getpriority(&cx, PRIO_PROCESS, -1));
+    }
+}
@@ -0,0 +1,1301 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+use kernel_abi::{
+    AF_INET, AF_INET6, AF_UNIX, CMsgHdr, EACCES, EADDRINUSE, EADDRNOTAVAIL, EAFNOSUPPORT, EAGAIN,
+    EALREADY, EBADF, ECONNREFUSED, ECONNRESET, EDESTADDRREQ, EINPROGRESS, EINTR, EINVAL, EISCONN,
+    EMSGSIZE, ENETDOWN, ENETUNREACH, ENOBUFS, ENOENT, ENOPROTOOPT, ENOTCONN, ENOTSOCK, EOPNOTSUPP,
+    EPIPE, EPROTONOSUPPORT, EPROTOTYPE, EROFS, ESOCKTNOSUPPORT, Errno, IOV_MAX, IPPROTO_IP,
+    IPPROTO_TCP, IPPROTO_UDP, In6Addr, InAddr, MsgFlags, SCM_MAX_FD, SCM_RIGHTS, SHUT_RD,
+    SHUT_RDWR, SHUT_WR, SO_BROADCAST, SO_ERROR, SO_KEEPALIVE, SO_PEERCRED, SO_RCVBUF, SO_REUSEADDR,
+    SO_SNDBUF, SO_TYPE, SOCK_DGRAM, SOCK_STREAM, SOCK_TYPE_MASK, SOL_SOCKET, SaFamily, SockAddrIn,
+    SockAddrIn6, SockAddrUn, SockLen, SocketFlags, TCP_NODELAY, UCred, UNIX_PATH_MAX, cmsg_align,
+    cmsg_len, cmsg_space,
+};
+use kernel_vfs::path::OwnedPath;
+
+use crate::access::{
+    Shutdown, SocketAccess, SocketAddress, SocketDomain, SocketError, SocketOption, SocketType,
+    UnixAddress,
+};
+
+/// A buffer in userspace that receives a socket address, together with its
+/// length, which is updated to the length of the address. If the buffer is
+/// too small, the address is truncated.
+pub type AddressBuffer<'a> = (&'a mut [u8], &'a mut SockLen);
+
+/// The control buffer of `recvmsg`, together with its length, which is
+/// updated to the length of the control messages that were written.
+pub type ControlBuffer<'a> = (&'a mut [u8], &'a mut usize);
+
+impl From<SocketError> for Errno {
+    fn from(e: SocketError) -> Self {
+        match e {
+            SocketError::BadFileDescriptor => EBADF,
+            SocketError::NotASocket => ENOTSOCK,
+            SocketError::InvalidArgument => EINVAL,
+            SocketError::NotSupported => EOPNOTSUPP,
+            SocketError::InvalidOption => ENOPROTOOPT,
+            SocketError::AddressFamilyNotSupported => EAFNOSUPPORT,
+            SocketError::AddressInUse => EADDRINUSE,
+            SocketError::AddressNotAvailable => EADDRNOTAVAIL,
+            SocketError::DestinationRequired => EDESTADDRREQ,
+            SocketError::AlreadyConnected => EISCONN,
+            SocketError::NotConnected => ENOTCONN,
+            SocketError::AlreadyInProgress => EALREADY,
+            SocketError::InProgress => EINPROGRESS,
+            SocketError::ConnectionRefused => ECONNREFUSED,
+            SocketError::ConnectionReset => ECONNRESET,
+            SocketError::NotFound => ENOENT,
+            SocketError::ReadOnlyFileSystem => EROFS,
+            SocketError::WrongProtocolType => EPROTOTYPE,
+            SocketError::NetworkDown => ENETDOWN,
+            SocketError::NetworkUnreachable => ENETUNREACH,
+            SocketError::PermissionDenied => EACCES,
+            SocketError::BrokenPipe => EPIPE,
+            SocketError::MessageTooLarge => EMSGSIZE,
+            SocketError::NoBufferSpace => ENOBUFS,
+            SocketError::WouldBlock => EAGAIN,
+            SocketError::Interrupted => EINTR,
+        }
+    }
+}
+
+pub fn sys_socket<Cx: SocketAccess>(
+    cx: &Cx,
+    domain: c_int,
+    typ: c_int,
+    protocol: c_int,
+) -> Result<usize, Errno> {
+    let (domain, typ, flags) = socket_kind(domain, typ, protocol)?;
+    // file descriptors are never inherited, so CLOEXEC has no effect
+    let fd = cx.socket(domain, typ, flags.contains(SocketFlags::NONBLOCK))?;
+    fd_to_usize(fd)
+}
+
+/// Creates a pair of connected sockets, whose file descriptors are written
+/// to `fds`. Only Unix sockets can be created as a pair.
+pub fn sys_socketpair<Cx: SocketAccess>(
+    cx: &Cx,
+    domain: c_int,
+    typ: c_int,
+    protocol: c_int,
+    fds: &mut [c_int; 2],
+) -> Result<usize, Errno> {
+    let (domain, typ, flags) = socket_kind(domain, typ, protocol)?;
+    if domain != SocketDomain::Unix {
+        return Err(EOPNOTSUPP);
+    }
+    let (a, b) = cx.socket_pair(domain, typ, flags.contains(SocketFlags::NONBLOCK))?;
+    *fds = [a.into(), b.into()];
+    Ok(0)
+}
+
+/// Parses the arguments of `socket` and `socketpair`.
+fn socket_kind(
+    domain: c_int,
+    typ: c_int,
+    protocol: c_int,
+) -> Result<(SocketDomain, SocketType, SocketFlags), Errno> {
+    let flags = SocketFlags::from_bits(typ & !SOCK_TYPE_MASK).ok_or(EINVAL)?;
+    let domain = match domain {
+        AF_INET => SocketDomain::Inet,
+        AF_INET6 => SocketDomain::Inet6,
+        AF_UNIX => SocketDomain::Unix,
+        _ => return Err(EAFNOSUPPORT),
+    };
+    let typ = match (domain, typ & SOCK_TYPE_MASK, protocol) {
+        (SocketDomain::Inet | SocketDomain::Inet6, SOCK_STREAM, IPPROTO_IP | IPPROTO_TCP)
+        | (SocketDomain::Unix, SOCK_STREAM, 0) => SocketType::Stream,
+        (SocketDomain::Inet | SocketDomain::Inet6, SOCK_DGRAM, IPPROTO_IP | IPPROTO_UDP)
+        | (SocketDomain::Unix, SOCK_DGRAM, 0) => SocketType::Datagram,
+        (_, SOCK_STREAM | SOCK_DGRAM, _) => return Err(EPROTONOSUPPORT),
+        _ => return Err(ESOCKTNOSUPPORT),
+    };
+    Ok((domain, typ, flags))
+}
+
+pub fn sys_bind<Cx: SocketAccess>(cx: &Cx, fd: Cx::Fd, address: &[u8]) -> Result<usize, Errno> {
+    cx.bind(fd, read_address(address)?)?;
+    Ok(0)
+}
+
+pub fn sys_listen<Cx: SocketAccess>(cx: &Cx, fd: Cx::Fd, backlog: c_int) -> Result<usize, Errno> {
+    // like on Linux, a negative backlog means the default
+    let backlog = usize::try_from(backlog).unwrap_or(usize::MAX);
+    cx.listen(fd, backlog)?;
+    Ok(0)
+}
+
+pub fn sys_accept<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    address: Option<AddressBuffer>,
+) -> Result<usize, Errno> {
+    let (fd, peer) = cx.accept(fd)?;
+    if let Some(address) = address {
+        write_address(&peer, address);
+    }
+    fd_to_usize(fd)
+}
+
+pub fn sys_connect<Cx: SocketAccess>(cx: &Cx, fd: Cx::Fd, address: &[u8]) -> Result<usize, Errno> {
+    cx.connect(fd, read_address(address)?)?;
+    Ok(0)
+}
+
+pub fn sys_sendto<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    buf: &[u8],
+    flags: c_int,
+    address: Option<&[u8]>,
+) -> Result<usize, Errno> {
+    let flags = MsgFlags::from_bits(flags).ok_or(EINVAL)?;
+    if flags.contains(MsgFlags::PEEK) {
+        return Err(EINVAL);
+    }
+    let address = address.map(read_address).transpose()?;
+
+    // there are no signals that could be raised, so NOSIGNAL has no effect
+    cx.send_to(fd, buf, address, &[], flags.contains(MsgFlags::DONTWAIT))
+        .map_err(Errno::from)
+}
+
+/// Sends the concatenation of the buffers of `iov`, together with the
+/// files of the `SCM_RIGHTS` control messages in `control`. At most the
+/// size of the send buffer of the socket is sent at once, and datagrams
+/// that are larger are rejected.
+pub fn sys_sendmsg<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    address: Option<&[u8]>,
+    iov: &[&[u8]],
+    control: &[u8],
+    flags: c_int,
+) -> Result<usize, Errno> {
+    let flags = MsgFlags::from_bits(flags).ok_or(EINVAL)?;
+    if flags.contains(MsgFlags::PEEK) {
+        return Err(EINVAL);
+    }
+    if iov.len() > IOV_MAX {
+        return Err(EMSGSIZE);
+    }
+    let address = address.map(read_address).transpose()?;
+    let rights = read_rights(control)?
+        .into_iter()
+        .map(Cx::Fd::from)
+        .collect::<Vec<_>>();
+
+    let fd = fd.into();
+    let capacity = buffer_size(cx, fd, SocketOption::SendBuffer)?;
+    if total_len(iov.iter().map(|buf| buf.len())) > capacity
+        && cx.socket_option(fd.into(), SocketOption::Type)? == SOCK_DGRAM
+    {
+        return Err(EMSGSIZE);
+    }
+    let mut buf = Vec::new();
+    for part in iov {
+        let len = part.len().min(capacity - buf.len());
+        buf.extend_from_slice(&part[..len]);
+    }
+    cx.send_to(
+        fd.into(),
+        &buf,
+        address,
+        &rights,
+        flags.contains(MsgFlags::DONTWAIT),
+    )
+    .map_err(Errno::from)
+}
+
+pub fn sys_recvfrom<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    buf: &mut [u8],
+    flags: c_int,
+    address: Option<AddressBuffer>,
+) -> Result<usize, Errno> {
+    let flags = MsgFlags::from_bits(flags).ok_or(EINVAL)?;
+    // files that are passed to a socket that is read without `recvmsg` are
+    // closed
+    let received = cx.recv_from(
+        fd,
+        buf,
+        flags.contains(MsgFlags::PEEK),
+        flags.contains(MsgFlags::DONTWAIT),
+        0,
+    )?;
+    if let Some(address) = address {
+        write_sender(received.sender, address);
+    }
+    Ok(received.len)
+}
+
+/// Receives into the buffers of `iov` in order, and writes the files that
+/// were passed with the data as an `SCM_RIGHTS` control message into
+/// `control`. If the control buffer is too small for all of them, the
+/// others are closed and [`MsgFlags::CTRUNC`] is set in `msg_flags`.
+pub fn sys_recvmsg<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    address: Option<AddressBuffer>,
+    iov: &mut [&mut [u8]],
+    control: ControlBuffer,
+    flags: c_int,
+    msg_flags: &mut c_int,
+) -> Result<usize, Errno> {
+    let flags = MsgFlags::from_bits(flags).ok_or(EINVAL)?;
+    let (control, control_len) = control;
+    let max_rights = control.len().saturating_sub(cmsg_len(0)) / size_of::<c_int>();
+    let max_rights = max_rights.min(SCM_MAX_FD);
+    if iov.len() > IOV_MAX {
+        return Err(EMSGSIZE);
+    }
+
+    // the socket never has more data at once than fits into its receive
+    // buffer
+    let fd = fd.into();
+    let capacity = buffer_size(cx, fd, SocketOption::ReceiveBuffer)?;
+    let mut buf = vec![0; total_len(iov.iter().map(|buf| buf.len())).min(capacity)];
+    let received = cx.recv_from(
+        fd.into(),
+        &mut buf,
+        flags.contains(MsgFlags::PEEK),
+        flags.contains(MsgFlags::DONTWAIT),
+        max_rights,
+    )?;
+
+    let mut remaining = &buf[..received.len];
+    for buf in iov {
+        let len = buf.len().min(remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        remaining = &remaining[len..];
+    }
+    if let Some(address) = address {
+        write_sender(received.sender, address);
+    }
+
+    let mut out_flags = MsgFlags::empty();
+    if received.rights_truncated {
+        out_flags |= MsgFlags::CTRUNC;
+    }
+    let rights = received
+        .rights
+        .into_iter()
+        .map(Into::into)
+        .collect::<Vec<c_int>>();
+    *control_len = write_rights(&rights, control);
+    *msg_flags = out_flags.bits();
+    Ok(received.len)
+}
+
+/// The size of the send or receive buffer of a socket, which bounds the
+/// buffer that `sendmsg` and `recvmsg` copy the data through.
+fn buffer_size<Cx: SocketAccess>(cx: &Cx, fd: c_int, option: SocketOption) -> Result<usize, Errno> {
+    let size = cx.socket_option(fd.into(), option)?;
+    Ok(usize::try_from(size).unwrap_or(0))
+}
+
+fn total_len(lens: impl Iterator<Item = usize>) -> usize {
+    lens.fold(0, usize::saturating_add)
+}
+
+/// Writes the address of the sender, or an empty address if there is none.
+fn write_sender(sender: Option<SocketAddress>, (buf, len): AddressBuffer) {
+    match sender {
+        Some(sender) => write_address(&sender, (buf, len)),
+        // a stream has no sender address
+        None => *len = 0,
+    }
+}
+
+/// Reads the file descriptors of the `SCM_RIGHTS` control messages in
+/// `control`.
+///
+/// # Errors
+/// Returns [`EINVAL`] if a control message is malformed, of another type,
+/// or if there are more than [`SCM_MAX_FD`] file descriptors.
+pub fn read_rights(control: &[u8]) -> Result<Vec<c_int>, Errno> {
+    let mut rights = Vec::new();
+    let mut rest = control;
+    while rest.len() >= size_of::<CMsgHdr>() {
+        let header = unsafe { rest.as_ptr().cast::<CMsgHdr>().read_unaligned() };
+        if header.cmsg_len < cmsg_len(0) || header.cmsg_len > rest.len() {
+            return Err(EINVAL);
+        }
+        if header.cmsg_level != SOL_SOCKET || header.cmsg_type != SCM_RIGHTS {
+            return Err(EINVAL);
+        }
+        let data = &rest[cmsg_len(0)..header.cmsg_len];
+        let (fds, []) = data.as_chunks::<{ size_of::<c_int>() }>() else {
+            return Err(EINVAL);
+        };
+        rights.extend(fds.iter().map(|bytes| c_int::from_ne_bytes(*bytes)));
+        rest = &rest[cmsg_align(header.cmsg_len).min(rest.len())..];
+    }
+    if rights.len() > SCM_MAX_FD {
+        return Err(EINVAL);
+    }
+    Ok(rights)
+}
+
+/// Writes `rights` as an `SCM_RIGHTS` control message into `control`, as
+/// far as they fit, and returns the length of what was written.
+pub fn write_rights(rights: &[c_int], control: &mut [u8]) -> usize {
+    if rights.is_empty() || control.len() < cmsg_len(size_of::<c_int>()) {
+        return 0;
+    }
+    let fitting = rights
+        .len()
+        .min((control.len() - cmsg_len(0)) / size_of::<c_int>());
+    let header = CMsgHdr {
+        cmsg_len: cmsg_len(fitting * size_of::<c_int>()),
+        cmsg_level: SOL_SOCKET,
+        cmsg_type: SCM_RIGHTS,
+    };
+    unsafe {
+        control
+            .as_mut_ptr()
+            .cast::<CMsgHdr>()
+            .write_unaligned(header);
+    }
+    for (fd, bytes) in rights[..fitting].iter().zip(
+        control[cmsg_len(0)..]
+            .as_chunks_mut::<{ size_of::<c_int>() }>()
+            .0,
+    ) {
+        bytes.copy_from_slice(&fd.to_ne_bytes());
+    }
+    cmsg_space(fitting * size_of::<c_int>()).min(control.len())
+}
+
+pub fn sys_shutdown<Cx: SocketAccess>(cx: &Cx, fd: Cx::Fd, how: c_int) -> Result<usize, Errno> {
+    let how = match how {
+        SHUT_RD => Shutdown::Read,
+        SHUT_WR => Shutdown::Write,
+        SHUT_RDWR => Shutdown::Both,
+        _ => return Err(EINVAL),
+    };
+    cx.shutdown(fd, how)?;
+    Ok(0)
+}
+
+pub fn sys_setsockopt<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    level: c_int,
+    name: c_int,
+    value: &[u8],
+) -> Result<usize, Errno> {
+    let option = socket_option(level, name)?;
+    if matches!(option, SocketOption::Type | SocketOption::Error) {
+        return Err(ENOPROTOOPT);
+    }
+    let value = value
+        .first_chunk::<{ size_of::<c_int>() }>()
+        .map(|bytes| c_int::from_ne_bytes(*bytes))
+        .ok_or(EINVAL)?;
+    cx.set_socket_option(fd, option, value)?;
+    Ok(0)
+}
+
+/// Writes the value of the option into `value`, truncated to its length,
+/// and sets the length to the size of the value.
+pub fn sys_getsockopt<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    level: c_int,
+    name: c_int,
+    value: AddressBuffer,
+) -> Result<usize, Errno> {
+    if (level, name) == (SOL_SOCKET, SO_PEERCRED) {
+        let credentials = cx.peer_credentials(fd)?;
+        let credentials = UCred {
+            pid: i32::try_from(credentials.pid).map_err(|_| EINVAL)?,
+            uid: credentials.uid,
+            gid: credentials.gid,
+        };
+        let bytes = unsafe {
+            core::slice::from_raw_parts((&raw const credentials).cast::<u8>(), size_of::<UCred>())
+        };
+        write_truncated(bytes, value);
+        return Ok(0);
+    }
+
+    let option = socket_option(level, name)?;
+    let bytes = cx.socket_option(fd, option)?.to_ne_bytes();
+    write_truncated(&bytes, value);
+    Ok(0)
+}
+
+/// Copies `bytes` into the buffer, truncated to its length, and sets the
+/// length to the length of `bytes`.
+fn write_truncated(bytes: &[u8], (buf, len): AddressBuffer) {
+    let copied = buf.len().min(bytes.len());
+    buf[..copied].copy_from_slice(&bytes[..copied]);
+    *len = bytes.len() as SockLen;
+}
+
+pub fn sys_getsockname<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    address: AddressBuffer,
+) -> Result<usize, Errno> {
+    write_address(&cx.local_address(fd)?, address);
+    Ok(0)
+}
+
+pub fn sys_getpeername<Cx: SocketAccess>(
+    cx: &Cx,
+    fd: Cx::Fd,
+    address: AddressBuffer,
+) -> Result<usize, Errno> {
+    write_address(&cx.peer_address(fd)?, address);
+    Ok(0)
+}
+
+fn fd_to_usize<Fd: Into<c_int>>(fd: Fd) -> Result<usize, Errno> {
+    usize::try_from(fd.into()).map_err(Errno::from)
+}
+
+fn socket_option(level: c_int, name: c_int) -> Result<SocketOption, Errno> {
+    Ok(match (level, name) {
+        (SOL_SOCKET, SO_REUSEADDR) => SocketOption::ReuseAddress,
+        (SOL_SOCKET, SO_TYPE) => SocketOption::Type,
+        (SOL_SOCKET, SO_ERROR) => SocketOption::Error,
+        (SOL_SOCKET, SO_BROADCAST) => SocketOption::Broadcast,
+        (SOL_SOCKET, SO_SNDBUF) => SocketOption::SendBuffer,
+        (SOL_SOCKET, SO_RCVBUF) => SocketOption::ReceiveBuffer,
+        (SOL_SOCKET, SO_KEEPALIVE) => SocketOption::KeepAlive,
+        (IPPROTO_TCP, TCP_NODELAY) => SocketOption::NoDelay,
+        _ => return Err(ENOPROTOOPT),
+    })
+}
+
+/// Reads a `sockaddr` of any family.
+///
+/// # Errors
+/// Returns [`EINVAL`] if `buf` is too short for the address of its family,
+/// and [`EAFNOSUPPORT`] for unsupported families.
+pub fn read_address(buf: &[u8]) -> Result<SocketAddress, Errno> {
+    let family = buf
+        .first_chunk::<{ size_of::<SaFamily>() }>()
+        .map(|bytes| SaFamily::from_ne_bytes(*bytes))
+        .ok_or(EINVAL)?;
+    match c_int::from(family) {
+        AF_INET => {
+            if buf.len() < size_of::<SockAddrIn>() {
+                return Err(EINVAL);
+            }
+            let addr = unsafe { buf.as_ptr().cast::<SockAddrIn>().read_unaligned() };
+            Ok(SocketAddress::Inet(SocketAddrV4::new(
+                Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)),
+                u16::from_be(addr.sin_port),
+            )))
+        }
+        AF_INET6 => {
+            if buf.len() < size_of::<SockAddrIn6>() {
+                return Err(EINVAL);
+            }
+            let addr = unsafe { buf.as_ptr().cast::<SockAddrIn6>().read_unaligned() };
+            Ok(SocketAddress::Inet6(SocketAddrV6::new(
+                Ipv6Addr::from(addr.sin6_addr.s6_addr),
+                u16::from_be(addr.sin6_port),
+                u32::from_be(addr.sin6_flowinfo),
+                addr.sin6_scope_id,
+            )))
+        }
+        AF_UNIX => {
+            if buf.len() > size_of::<SockAddrUn>() {
+                return Err(EINVAL);
+            }
+            let path = &buf[size_of::<SaFamily>()..];
+            Ok(SocketAddress::Unix(match path {
+                [] => UnixAddress::Unnamed,
+                [0, name @ ..] => UnixAddress::Abstract(name.to_vec()),
+                path => {
+                    // the path ends with a null byte, unless it fills the
+                    // whole address
+                    let len = path.iter().position(|&b| b == 0).unwrap_or(path.len());
+                    let path = core::str::from_utf8(&path[..len]).map_err(|_| EINVAL)?;
+                    UnixAddress::Path(OwnedPath::new(path))
+                }
+            }))
+        }
+        _ => Err(EAFNOSUPPORT),
+    }
+}
+
+/// Writes `address` as a `sockaddr` into the buffer, see [`AddressBuffer`].
+pub fn write_address(address: &SocketAddress, buf: AddressBuffer) {
+    match address {
+        SocketAddress::Inet(addr) => {
+            let addr = SockAddrIn {
+                sin_family: AF_INET as SaFamily,
+                sin_port: addr.port().to_be(),
+                sin_addr: InAddr {
+                    s_addr: u32::from(*addr.ip()).to_be(),
+                },
+                sin_zero: [0; 8],
+            };
+            let bytes = unsafe {
+                core::slice::from_raw_parts((&raw const addr).cast::<u8>(), size_of::<SockAddrIn>())
+            };
+            write_truncated(bytes, buf);
+        }
+        SocketAddress::Inet6(addr) => {
+            let addr = SockAddrIn6 {
+                sin6_family: AF_INET6 as SaFamily,
+                sin6_port: addr.port().to_be(),
+                sin6_flowinfo: addr.flowinfo().to_be(),
+                sin6_addr: In6Addr {
+                    s6_addr: addr.ip().octets(),
+                },
+                sin6_scope_id: addr.scope_id(),
+            };
+            let bytes = unsafe {
+                core::slice::from_raw_parts(
+                    (&raw const addr).cast::<u8>(),
+                    size_of::<SockAddrIn6>(),
+                )
+            };
+            write_truncated(bytes, buf);
+        }
+        SocketAddress::Unix(addr) => {
+            // like on Linux, the length only covers the used part of the
+            // path, including the null byte that terminates a path
+            let mut bytes = Vec::with_capacity(size_of::<SockAddrUn>());
+            bytes.extend_from_slice(&(AF_UNIX as SaFamily).to_ne_bytes());
+            match addr {
+                UnixAddress::Unnamed => {}
+                UnixAddress::Path(path) => {
+                    bytes.extend_from_slice(path.as_str().as_bytes());
+                    bytes.push(0);
+                }
+                UnixAddress::Abstract(name) => {
+                    bytes.push(0);
+                    bytes.extend_from_slice(name);
+                }
+            }
+            bytes.truncate(size_of::<SaFamily>() + UNIX_PATH_MAX);
+            write_truncated(&bytes, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+    use core::ffi::c_int;
+    use core::net::{Ipv4Addr, Ipv6Addr, SocketAddrV4, SocketAddrV6};
+
+    use kernel_abi::{
+        AF_INET, AF_INET6, AF_UNIX, EAFNOSUPPORT, EAGAIN, EINVAL, ENOPROTOOPT, ENOTSOCK,
+        EOPNOTSUPP, EPROTONOSUPPORT, ESOCKTNOSUPPORT, IPPROTO_TCP, IPPROTO_UDP, MsgFlags, SO_ERROR,
+        SO_KEEPALIVE, SO_PEERCRED, SO_TYPE, SOCK_DGRAM, SOCK_RAW, SOCK_STREAM, SOL_SOCKET,
+        SockAddrIn, SockAddrIn6, SockLen, SocketFlags, TCP_NODELAY, UCred,
+    };
+
+    use super::*;
+    use crate::access::{Credentials, Received};
+
+    /// An address family that isn't supported, `AF_IPX`.
+    const AF_UNSUPPORTED: c_int = 4;
+
+    /// Records the calls, and fails for file descriptors other than 3.
+    #[derive(Default)]
+    struct TestSocketAccess {
+        calls: RefCell<Vec<Call>>,
+        sent: RefCell<Vec<u8>>,
+    }
+
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    enum Call {
+        Socket(SocketDomain, SocketType, bool),
+        SocketPair(SocketType, bool),
+        Bind(SocketAddress),
+        Listen(usize),
+        Connect(SocketAddress),
+        SendTo(usize, Option<SocketAddress>, Vec<c_int>, bool),
+        RecvFrom(usize, bool, bool, usize),
+        Shutdown(Shutdown),
+        SetOption(SocketOption, c_int),
+    }
+
+    const BUFFER_SIZE: c_int = 64;
+
+    const PEER: SocketAddrV4 = SocketAddrV4::new(Ipv4Addr::new(10, 0, 2, 2), 1234);
+
+    impl TestSocketAccess {
+        fn call(&self, fd: c_int, call: Call) -> Result<(), SocketError> {
+            if fd != 3 {
+                return Err(SocketError::NotASocket);
+            }
+            self.calls.borrow_mut().push(call);
+            Ok(())
+        }
+
+        fn calls(&self) -> Vec<Call> {
+            self.calls.borrow().clone()
+        }
+    }
+
+    impl SocketAccess for TestSocketAccess {
+        type Fd = c_int;
+
+        fn socket(
+            &self,
+            domain: SocketDomain,
+            typ: SocketType,
+            nonblocking: bool,
+        ) -> Result<c_int, SocketError> {
+            self.call(3, Call::Socket(domain, typ, nonblocking))?;
+            Ok(3)
+        }
+
+        fn socket_pair(
+            &self,
+            domain: SocketDomain,
+            typ: SocketType,
+            nonblocking: bool,
+        ) -> Result<(c_int, c_int), SocketError> {
+            assert_eq!(SocketDomain::Unix, domain);
+            self.call(3, Call::SocketPair(typ, nonblocking))?;
+            Ok((3, 4))
+        }
+
+        fn bind(&self, fd: c_int, address: SocketAddress) -> Result<(), SocketError> {
+            self.call(fd, Call::Bind(address))
+        }
+
+        fn listen(&self, fd: c_int, backlog: usize) -> Result<(), SocketError> {
+            self.call(fd, Call::Listen(backlog))
+        }
+
+        fn accept(&self, fd: c_int) -> Result<(c_int, SocketAddress), SocketError> {
+            self.call(fd, Call::Listen(0))?;
+            Ok((4, SocketAddress::Inet(PEER)))
+        }
+
+        fn connect(&self, fd: c_int, address: SocketAddress) -> Result<(), SocketError> {
+            self.call(fd, Call::Connect(address))
+        }
+
+        fn send_to(
+            &self,
+            fd: c_int,
+            buf: &[u8],
+            address: Option<SocketAddress>,
+            rights: &[c_int],
+            nonblocking: bool,
+        ) -> Result<usize, SocketError> {
+            self.call(
+                fd,
+                Call::SendTo(buf.len(), address, rights.to_vec(), nonblocking),
+            )?;
+            *self.sent.borrow_mut() = buf.to_vec();
+            Ok(buf.len())
+        }
+
+        /// Fills the buffer with ones, and passes three files.
+        fn recv_from(
+            &self,
+            fd: c_int,
+            buf: &mut [u8],
+            peek: bool,
+            nonblocking: bool,
+            max_rights: usize,
+        ) -> Result<Received<c_int>, SocketError> {
+            self.call(fd, Call::RecvFrom(buf.len(), peek, nonblocking, max_rights))?;
+            if nonblocking {
+                return Err(SocketError::WouldBlock);
+            }
+            buf.fill(1);
+            let rights = [5, 6, 7];
+            let received = rights.len().min(max_rights);
+            Ok(Received {
+                len: buf.len(),
+                sender: Some(SocketAddress::Inet(PEER)),
+                rights: rights[..received].to_vec(),
+                rights_truncated: received < rights.len(),
+            })
+        }
+
+        fn shutdown(&self, fd: c_int, how: Shutdown) -> Result<(), SocketError> {
+            self.call(fd, Call::Shutdown(how))
+        }
+
+        fn socket_option(&self, fd: c_int, option: SocketOption) -> Result<c_int, SocketError> {
+            self.call(fd, Call::SetOption(option, 0))?;
+            match option {
+                SocketOption::Type => Ok(SOCK_STREAM),
+                SocketOption::SendBuffer | SocketOption::ReceiveBuffer => Ok(BUFFER_SIZE),
+                _ => Ok(0),
+            }
+        }
+
+        fn set_socket_option(
+            &self,
+            fd: c_int,
+            option: SocketOption,
+            value: c_int,
+        ) -> Result<(), SocketError> {
+            self.call(fd, Call::SetOption(option, value))
+        }
+
+        fn local_address(&self, fd: c_int) -> Result<SocketAddress, SocketError> {
+            self.call(fd, Call::Listen(0))?;
+            Ok(SocketAddress::Inet(SocketAddrV4::new(
+                Ipv4Addr::new(10, 0, 2, 15),
+                7,
+            )))
+        }
+
+        fn peer_address(&self, fd: c_int) -> Result<SocketAddress, SocketError> {
+            self.call(fd, Call::Listen(0))?;
+            Ok(SocketAddress::Inet(PEER))
+        }
+
+        fn peer_credentials(&self, fd: c_int) -> Result<Credentials, SocketError> {
+            self.call(fd, Call::Listen(0))?;
+            Ok(Credentials {
+                pid: 42,
+                uid: 0,
+                gid: 0,
+            })
+        }
+    }
+
+    fn sockaddr_in(ip: [u8; 4], port: u16) -> [u8; size_of::<SockAddrIn>()] {
+        let mut buf = [0; size_of::<SockAddrIn>()];
+        buf[..2].copy_from_slice(&(AF_INET as u16).to_ne_bytes());
+        buf[2..4].copy_from_slice(&port.to_be_bytes());
+        buf[4..8].copy_from_slice(&ip);
+        buf
+    }
+
+    #[test]
+    fn test_socket() {
+        let cx = TestSocketAccess::default();
+        assert_eq!(Ok(3), sys_socket(&cx, AF_INET, SOCK_STREAM, 0));
+        assert_eq!(Ok(3), sys_socket(&cx, AF_INET, SOCK_STREAM, IPPROTO_TCP));
+        let nonblocking = SOCK_DGRAM | SocketFlags::NONBLOCK.bits();
+        assert_eq!(Ok(3), sys_socket(&cx, AF_INET, nonblocking, IPPROTO_UDP));
+        assert_eq!(
+            [
+                Call::Socket(SocketDomain::Inet, SocketType::Stream, false),
+                Call::Socket(SocketDomain::Inet, SocketType::Stream, false),
+                Call::Socket(SocketDomain::Inet, SocketType::Datagram, true),
+            ],
+            cx.calls()[..]
+        );
+
+        assert_eq!(Ok(3), sys_socket(&cx, AF_INET6, SOCK_DGRAM, 0));
+        assert_eq!(
+            Call::Socket(SocketDomain::Inet6, SocketType::Datagram, false),
+            cx.calls()[3]
+        );
+
+        assert_eq!(
+            Err(EAFNOSUPPORT),
+            sys_socket(&cx, AF_UNSUPPORTED, SOCK_STREAM, 0)
+        );
+        assert_eq!(
+            Err(EPROTONOSUPPORT),
+            sys_socket(&cx, AF_INET, SOCK_STREAM, IPPROTO_UDP)
+        );
+        assert_eq!(Err(ESOCKTNOSUPPORT), sys_socket(&cx, AF_INET, SOCK_RAW, 0));
+        assert_eq!(
+            Err(EINVAL),
+            sys_socket(&cx, AF_INET, SOCK_STREAM | 0x100, 0)
+        );
+    }
+
+    #[test]
+    fn test_read_address() {
+        let addr = sockaddr_in([10, 0, 2, 15], 8080);
+        assert_eq!(
+            Ok(SocketAddress::Inet(SocketAddrV4::new(
+                Ipv4Addr::new(10, 0, 2, 15),
+                8080
+            ))),
+            read_address(&addr)
+        );
+        assert_eq!(Err(EINVAL), read_address(&addr[..8]));
+        assert_eq!(Err(EINVAL), read_address(&addr[..1]));
+
+        let mut unsupported = addr;
+        unsupported[..2].copy_from_slice(&(AF_UNSUPPORTED as u16).to_ne_bytes());
+        assert_eq!(Err(EAFNOSUPPORT), read_address(&unsupported));
+    }
+
+    fn sockaddr_in6(ip: Ipv6Addr, port: u16, scope_id: u32) -> [u8; size_of::<SockAddrIn6>()] {
+        let mut buf = [0; size_of::<SockAddrIn6>()];
+        buf[..2].copy_from_slice(&(AF_INET6 as u16).to_ne_bytes());
+        buf[2..4].copy_from_slice(&port.to_be_bytes());
+        buf[8..24].copy_from_slice(&ip.octets());
+        buf[24..].copy_from_slice(&scope_id.to_ne_bytes());
+        buf
+    }
+
+    #[test]
+    fn test_inet6_address() {
+        let addr = sockaddr_in6(Ipv6Addr::LOCALHOST, 8080, 2);
+        let address = SocketAddress::Inet6(SocketAddrV6::new(Ipv6Addr::LOCALHOST, 8080, 0, 2));
+        assert_eq!(Ok(address.clone()), read_address(&addr));
+        assert_eq!(Err(EINVAL), read_address(&addr[..16]));
+
+        let mut buf = [0xff; 32];
+        let mut len: SockLen = 32;
+        write_address(&address, (&mut buf, &mut len));
+        assert_eq!(size_of::<SockAddrIn6>(), len as usize);
+        assert_eq!(addr, buf[..28]);
+        assert_eq!([0xff; 4], buf[28..]);
+    }
+
+    #[test]
+    fn test_write_address() {
+        let address = SocketAddress::Inet(SocketAddrV4::new(Ipv4Addr::new(10, 0, 2, 15), 8080));
+        let mut buf = [0xff; 20];
+        let mut len: SockLen = 20;
+        write_address(&address, (&mut buf, &mut len));
+        assert_eq!(size_of::<SockAddrIn>(), len as usize);
+        assert_eq!(sockaddr_in([10, 0, 2, 15], 8080), buf[..16]);
+        assert_eq!([0xff; 4], buf[16..]);
+
+        // the address is truncated, but the length is the full one
+        let mut buf = [0; 4];
+        let mut len: SockLen = 4;
+        write_address(&address, (&mut buf, &mut len));
+        assert_eq!(size_of::<SockAddrIn>(), len as usize);
+        assert_eq!(sockaddr_in([10, 0, 2, 15], 8080)[..4], buf);
+    }
+
+    #[test]
+    fn test_bind_listen_accept() {
+        let cx = TestSocketAccess::default();
+        let addr = sockaddr_in([0, 0, 0, 0], 7);
+        assert_eq!(Ok(0), sys_bind(&cx, 3, &addr));
+        assert_eq!(Ok(0), sys_listen(&cx, 3, 4));
+        assert_eq!(Ok(0), sys_listen(&cx, 3, -1));
+        assert_eq!(
+            [
+                Call::Bind(SocketAddress::Inet(SocketAddrV4::new(
+                    Ipv4Addr::UNSPECIFIED,
+                    7
+                ))),
+                Call::Listen(4),
+                Call::Listen(usize::MAX),
+            ],
+            cx.calls()[..]
+        );
+        assert_eq!(Err(ENOTSOCK), sys_bind(&cx, 0, &addr));
+
+        let mut buf = [0; 16];
+        let mut len: SockLen = 16;
+        assert_eq!(Ok(4), sys_accept(&cx, 3, Some((&mut buf, &mut len))));
+        assert_eq!(sockaddr_in([10, 0, 2, 2], 1234), buf);
+        assert_eq!(Ok(4), sys_accept(&cx, 3, None));
+    }
+
+    #[test]
+    fn test_send_and_receive() {
+        let cx = TestSocketAccess::default();
+        let addr = sockaddr_in([10, 0, 2, 2], 53);
+        assert_eq!(Ok(5), sys_sendto(&cx, 3, b"hello", 0, None));
+        let dontwait = MsgFlags::DONTWAIT.bits();
+        assert_eq!(Ok(2), sys_sendto(&cx, 3, b"hi", dontwait, Some(&addr)));
+        assert_eq!(
+            Err(EINVAL),
+            sys_sendto(&cx, 3, b"hi", MsgFlags::PEEK.bits(), None)
+        );
+        assert_eq!(
+            [
+                Call::SendTo(5, None, Vec::new(), false),
+                Call::SendTo(
+                    2,
+                    Some(SocketAddress::Inet(SocketAddrV4::new(
+                        Ipv4Addr::new(10, 0, 2, 2),
+                        53
+                    ))),
+                    Vec::new(),
+                    true
+                ),
+            ],
+            cx.calls()[..]
+        );
+
+        let mut buf = [0; 8];
+        let mut address = [0; 16];
+        let mut len: SockLen = 16;
+        assert_eq!(
+            Ok(8),
+            sys_recvfrom(&cx, 3, &mut buf, 0, Some((&mut address, &mut len)))
+        );
+        assert_eq!([1; 8], buf);
+        assert_eq!(sockaddr_in([10, 0, 2, 2], 1234), address);
+        assert_eq!(Err(EAGAIN), sys_recvfrom(&cx, 3, &mut buf, dontwait, None));
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let cx = TestSocketAccess::default();
+        assert_eq!(Ok(0), sys_shutdown(&cx, 3, SHUT_WR));
+        assert_eq!(Ok(0), sys_shutdown(&cx, 3, SHUT_RDWR));
+        assert_eq!(Err(EINVAL), sys_shutdown(&cx, 3, 3));
+        assert_eq!(
+            [
+                Call::Shutdown(Shutdown::Write),
+                Call::Shutdown(Shutdown::Both)
+            ],
+            cx.calls()[..]
+        );
+    }
+
+    #[test]
+    fn test_socket_options() {
+        let cx = TestSocketAccess::default();
+        let one = 1_i32.to_ne_bytes();
+        assert_eq!(
+            Ok(0),
+            sys_setsockopt(&cx, 3, IPPROTO_TCP, TCP_NODELAY, &one)
+        );
+        assert_eq!(
+            Ok(0),
+            sys_setsockopt(&cx, 3, SOL_SOCKET, SO_KEEPALIVE, &one)
+        );
+        assert_eq!(
+            [
+                Call::SetOption(SocketOption::NoDelay, 1),
+                Call::SetOption(SocketOption::KeepAlive, 1),
+            ],
+            cx.calls()[..]
+        );
+        assert_eq!(
+            Err(EINVAL),
+            sys_setsockopt(&cx, 3, SOL_SOCKET, SO_KEEPALIVE, &one[..2])
+        );
+        assert_eq!(
+            Err(ENOPROTOOPT),
+            sys_setsockopt(&cx, 3, SOL_SOCKET, SO_ERROR, &one)
+        );
+        assert_eq!(
+            Err(ENOPROTOOPT),
+            sys_setsockopt(&cx, 3, SOL_SOCKET, 1000, &one)
+        );
+
+        let mut value = [0; 8];
+        let mut len: SockLen = 8;
+        assert_eq!(
+            Ok(0),
+            sys_getsockopt(&cx, 3, SOL_SOCKET, SO_TYPE, (&mut value, &mut len))
+        );
+        assert_eq!(4, len);
+        assert_eq!(SOCK_STREAM.to_ne_bytes(), value[..4]);
+    }
+
+    #[test]
+    fn test_names() {
+        let cx = TestSocketAccess::default();
+        let mut buf = [0; 16];
+        let mut len: SockLen = 16;
+        assert_eq!(Ok(0), sys_getsockname(&cx, 3, (&mut buf, &mut len)));
+        assert_eq!(sockaddr_in([10, 0, 2, 15], 7), buf);
+        assert_eq!(Ok(0), sys_getpeername(&cx, 3, (&mut buf, &mut len)));
+        assert_eq!(sockaddr_in([10, 0, 2, 2], 1234), buf);
+    }
+
+    fn sockaddr_un(path: &[u8]) -> Vec<u8> {
+        let mut buf = (AF_UNIX as u16).to_ne_bytes().to_vec();
+        buf.extend_from_slice(path);
+        buf
+    }
+
+    fn control_message(rights: &[c_int]) -> Vec<u8> {
+        let mut buf = vec![0; cmsg_space(size_of_val(rights))];
+        assert_eq!(buf.len(), write_rights(rights, &mut buf));
+        buf
+    }
+
+    #[test]
+    fn test_unix_socket() {
+        let cx = TestSocketAccess::default();
+        assert_eq!(Ok(3), sys_socket(&cx, AF_UNIX, SOCK_STREAM, 0));
+        assert_eq!(Ok(3), sys_socket(&cx, AF_UNIX, SOCK_DGRAM, 0));
+        assert_eq!(
+            [
+                Call::Socket(SocketDomain::Unix, SocketType::Stream, false),
+                Call::Socket(SocketDomain::Unix, SocketType::Datagram, false),
+            ],
+            cx.calls()[..]
+        );
+        assert_eq!(
+            Err(EPROTONOSUPPORT),
+            sys_socket(&cx, AF_UNIX, SOCK_STREAM, IPPROTO_TCP)
+        );
+    }
+
+    #[test]
+    fn test_socketpair() {
+        let cx = TestSocketAccess::default();
+        let mut fds = [-1; 2];
+        let nonblocking = SOCK_DGRAM | SocketFlags::NONBLOCK.bits();
+        assert_eq!(
+            Ok(0),
+            sys_socketpair(&cx, AF_UNIX, nonblocking, 0, &mut fds)
+        );
+        assert_eq!([3, 4], fds);
+        assert_eq!(
+            [Call::SocketPair(SocketType::Datagram, true)],
+            cx.calls()[..]
+        );
+        assert_eq!(
+            Err(EOPNOTSUPP),
+            sys_socketpair(&cx, AF_INET, SOCK_STREAM, 0, &mut fds)
+        );
+        assert_eq!(
+            Err(EOPNOTSUPP),
+            sys_socketpair(&cx, AF_INET6, SOCK_DGRAM, 0, &mut fds)
+        );
+    }
+
+    #[test]
+    fn test_unix_address() {
+        let path = SocketAddress::Unix(UnixAddress::Path(OwnedPath::new("/run/init.sock")));
+        assert_eq!(
+            Ok(path.clone()),
+            read_address(&sockaddr_un(b"/run/init.sock\0"))
+        );
+        // the path doesn't have to be terminated if the length says where it
+        // ends
+        assert_eq!(
+            Ok(path.clone()),
+            read_address(&sockaddr_un(b"/run/init.sock"))
+        );
+        assert_eq!(
+            Ok(SocketAddress::Unix(UnixAddress::Abstract(
+                b"init\0".to_vec()
+            ))),
+            read_address(&sockaddr_un(b"\0init\0"))
+        );
+        assert_eq!(
+            Ok(SocketAddress::Unix(UnixAddress::Unnamed)),
+            read_address(&sockaddr_un(b""))
+        );
+        assert_eq!(Err(EINVAL), read_address(&sockaddr_un(&[b'a'; 109])));
+        assert_eq!(Err(EINVAL), read_address(&sockaddr_un(b"\xff")));
+
+        let mut buf = [0xff; 110];
+        let mut len: SockLen = 110;
+        write_address(&path, (&mut buf, &mut len));
+        assert_eq!(17, len);
+        assert_eq!(sockaddr_un(b"/run/init.sock\0"), buf[..17]);
+        assert_eq!(0xff, buf[17]);
+
+        write_address(
+            &SocketAddress::Unix(UnixAddress::Abstract(b"init".to_vec())),
+            (&mut buf, &mut len),
+        );
+        assert_eq!(7, len);
+        assert_eq!(sockaddr_un(b"\0init"), buf[..7]);
+
+        write_address(
+            &SocketAddress::Unix(UnixAddress::Unnamed),
+            (&mut buf, &mut len),
+        );
+        assert_eq!(2, len);
+    }
+
+    #[test]
+    fn test_sendmsg() {
+        let cx = TestSocketAccess::default();
+        let control = control_message(&[0, 1]);
+        assert_eq!(
+            Ok(11),
+            sys_sendmsg(&cx, 3, None, &[b"hello", b" ", b"world"], &control, 0)
+        );
+        assert_eq!(b"hello world", &cx.sent.borrow()[..]);
+        assert_eq!(Ok(0), sys_sendmsg(&cx, 3, None, &[], &[], 0));
+        assert_eq!(
+            [
+                Call::SetOption(SocketOption::SendBuffer, 0),
+                Call::SendTo(11, None, vec![0, 1], false),
+                Call::SetOption(SocketOption::SendBuffer, 0),
+                Call::SendTo(0, None, Vec::new(), false),
+            ],
+            cx.calls()[..]
+        );
+
+        // control messages of other types aren't supported
+        let mut other = control.clone();
+        other[size_of::<usize>() + size_of::<c_int>()] = 2;
+        assert_eq!(Err(EINVAL), sys_sendmsg(&cx, 3, None, &[b"x"], &other, 0));
+        // the length of a control message can't exceed the buffer
+        assert_eq!(
+            Err(EINVAL),
+            sys_sendmsg(&cx, 3, None, &[b"x"], &control[..cmsg_len(4)], 0)
+        );
+    }
+
+    #[test]
+    fn test_sendmsg_huge_iov() {
+        let cx = TestSocketAccess::default();
+        // only the send buffer is copied, however long the buffers are
+        let huge = vec![2; 1024 * 1024];
+        let iov = [&huge[..]; IOV_MAX];
+        assert_eq!(Ok(64), sys_sendmsg(&cx, 3, None, &iov, &[], 0));
+        assert_eq!([2; 64], cx.sent.borrow()[..]);
+
+        let iov = [&[0][..]; IOV_MAX + 1];
+        assert_eq!(Err(EMSGSIZE), sys_sendmsg(&cx, 3, None, &iov, &[], 0));
+    }
+
+    #[test]
+    fn test_read_rights() {
+        let mut control = control_message(&[3]);
+        control.extend(control_message(&[4, 5]));
+        assert_eq!(Ok(vec![3, 4, 5]), read_rights(&control));
+        assert_eq!(Ok(Vec::new()), read_rights(&[]));
+
+        let mut odd = control_message(&[3]);
+        odd[0] += 1;
+        assert_eq!(Err(EINVAL), read_rights(&odd));
+    }
+
+    #[test]
+    fn test_recvmsg() {
+        let cx = TestSocketAccess::default();
+        let mut a = [0; 3];
+        let mut b = [0; 2];
+        let mut control = [0xff; 64];
+        let mut control_len = control.len();
+        let mut msg_flags = -1;
+        assert_eq!(
+            Ok(5),
+            sys_recvmsg(
+                &cx,
+                3,
+                None,
+                &mut [&mut a, &mut b],
+                (&mut control, &mut control_len),
+                0,
+                &mut msg_flags,
+            )
+        );
+        assert_eq!([1; 3], a);
+        assert_eq!([1; 2], b);
+        assert_eq!(0, msg_flags);
+        assert_eq!(cmsg_space(12), control_len);
+        assert_eq!(Ok(vec![5, 6, 7]), read_rights(&control[..control_len]));
+        assert_eq!(
+            [
+                Call::SetOption(SocketOption::ReceiveBuffer, 0),
+                Call::RecvFrom(5, false, false, (64 - cmsg_len(0)) / 4)
+            ],
+            cx.calls()[..]
+        );
+
+        // only the files that fit are received
+        let mut control = [0; cmsg_len(4)];
+        let mut control_len = control.len();
+        assert_eq!(
+            Ok(3),
+            sys_recvmsg(
+                &cx,
+                3,
+                None,
+                &mut [&mut a],
+                (&mut control, &mut control_len),
+                0,
+                &mut msg_flags,
+            )
+        );
+        assert_eq!(MsgFlags::CTRUNC.bits(), msg_flags);
+        assert_eq!(Ok(vec![5]), read_rights(&control[..control_len]));
+
+        // without a control buffer, no files are received
+        let mut control_len = 0;
+        assert_eq!(
+            Ok(3),
+            sys_recvmsg(
+                &cx,
+                3,
+                None,
+                &mut [&mut a],
+                (&mut [], &mut control_len),
+                0,
+                &mut msg_flags,
+            )
+        );
+        assert_eq!(0, control_len);
+        assert_eq!(MsgFlags::CTRUNC.bits(), msg_flags);
+    }
+
+    #[test]
+    fn test_recvmsg_huge_iov() {
+        let cx = TestSocketAccess::default();
+        let mut huge = vec![0; 1024 * 1024];
+        let mut control_len = 0;
+        let mut msg_flags = 0;
+        assert_eq!(
+            Ok(64),
+            sys_recvmsg(
+                &cx,
+                3,
+                None,
+                &mut [&mut huge],
+                (&mut [], &mut control_len),
+                0,
+                &mut msg_flags,
+            )
+        );
+        assert_eq!(
+            [
+                Call::SetOption(SocketOption::ReceiveBuffer, 0),
+                Call::RecvFrom(64, false, false, 0)
+            ],
+            cx.calls()[..]
+        );
+        assert_eq!([1; 64], huge[..64]);
+        assert_eq!(0, huge[64]);
+
+        let mut iov = (0..=IOV_MAX).map(|_| &mut [][..]).collect::<Vec<_>>();
+        assert_eq!(
+            Err(EMSGSIZE),
+            sys_recvmsg(
+                &cx,
+                3,
+                None,
+                &mut iov,
+                (&mut [], &mut control_len),
+                0,
+                &mut msg_flags,
+            )
+        );
+    }
+
+    #[test]
+    fn test_peer_credentials() {
+        let cx = TestSocketAccess::default();
+        let mut buf = [0; size_of::<UCred>()];
+        let mut len = buf.len() as SockLen;
+        assert_eq!(
+            Ok(0),
+            sys_getsockopt(&cx, 3, SOL_SOCKET, SO_PEERCRED, (&mut buf, &mut len))
+        );
+        assert_eq!(size_of::<UCred>(), len as usize);
+        let credentials = unsafe { buf.as_ptr().cast::<UCred>().read_unaligned() };
+        assert_eq!(
+            UCred {
+                pid: 42,
+                uid: 0,
+                gid: 0
+            },
+            credentials
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use alloc::string::ToString;
+
+use kernel_abi::{
+    EINTR, EINVAL, ERANGE, Errno, SYSLOG_ACTION_CLEAR, SYSLOG_ACTION_CLOSE,
+    SYSLOG_ACTION_CONSOLE_LEVEL, SYSLOG_ACTION_CONSOLE_OFF, SYSLOG_ACTION_CONSOLE_ON,
+    SYSLOG_ACTION_GET_FILTER, SYSLOG_ACTION_OPEN, SYSLOG_ACTION_READ, SYSLOG_ACTION_READ_ALL,
+    SYSLOG_ACTION_READ_CLEAR, SYSLOG_ACTION_SET_FILTER, SYSLOG_ACTION_SIZE_BUFFER,
+    SYSLOG_ACTION_SIZE_UNREAD,
+};
+use kernel_kmsg::Filter;
+use log::LevelFilter;
+
+use crate::access::LogAccess;
+
+/// Whether `action` reads from or writes to the buffer that is passed to
+/// `syslog`. The other actions ignore it.
+#[must_use]
+pub fn syslog_uses_buffer(action: i32) -> bool {
+    matches!(
+        action,
+        SYSLOG_ACTION_READ
+            | SYSLOG_ACTION_READ_ALL
+            | SYSLOG_ACTION_READ_CLEAR
+            | SYSLOG_ACTION_SET_FILTER
+            | SYSLOG_ACTION_GET_FILTER
+    )
+}
+
+/// `buf` is empty for the actions that don't
+/// [use a buffer](syslog_uses_buffer). `len` is only used as the level of
+/// [`SYSLOG_ACTION_CONSOLE_LEVEL`].
+pub fn sys_syslog<Cx: LogAccess>(
+    cx: &Cx,
+    action: i32,
+    buf: &mut [u8],
+    len: usize,
+) -> Result<usize, Errno> {
+    match action {
+        SYSLOG_ACTION_CLOSE | SYSLOG_ACTION_OPEN => Ok(0),
+        SYSLOG_ACTION_READ => {
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            cx.read_log(buf).map_err(|_| EINTR)
+        }
+        SYSLOG_ACTION_READ_ALL => Ok(cx.read_log_all(buf)),
+        SYSLOG_ACTION_READ_CLEAR => {
+            let len = cx.read_log_all(buf);
+            cx.clear_log();
+            Ok(len)
+        }
+        SYSLOG_ACTION_CLEAR => {
+            cx.clear_log();
+            Ok(0)
+        }
+        SYSLOG_ACTION_CONSOLE_OFF | SYSLOG_ACTION_CONSOLE_ON => {
+            cx.set_console_enabled(action == SYSLOG_ACTION_CONSOLE_ON);
+            Ok(0)
+        }
+        SYSLOG_ACTION_CONSOLE_LEVEL => {
+            cx.set_console_level(console_level(len).ok_or(EINVAL)?);
+            Ok(0)
+        }
+        SYSLOG_ACTION_SIZE_UNREAD => Ok(cx.unread_log_len()),
+        SYSLOG_ACTION_SIZE_BUFFER => Ok(cx.log_buffer_len()),
+        SYSLOG_ACTION_SET_FILTER => {
+            let filter = str::from_utf8(buf)
+                .map_err(|_| EINVAL)?
+                .parse::<Filter>()
+                .map_err(|_| EINVAL)?;
+            cx.set_log_filter(filter);
+            Ok(0)
+        }
+        SYSLOG_ACTION_GET_FILTER => {
+            let filter = cx.log_filter().to_string();
+            let dst = buf.get_mut(..filter.len()).ok_or(ERANGE)?;
+            dst.copy_from_slice(filter.as_bytes());
+            Ok(filter.len())
+        }
+        _ => Err(EINVAL),
+    }
+}
+
+/// Converts a console level of `syslog`, below which records are printed,
+/// into the least important level that is printed. The priorities of the
+/// levels are 3 for errors, 4 for warnings, 6 for info and 7 for debug and
+/// trace records.
+fn console_level(level: usize) -> Option<LevelFilter> {
+    Some(match level {
+        1..=3 => LevelFilter::Off,
+        4 => LevelFilter::Error,
+        5 | 6 => LevelFilter::Warn,
+        7 => LevelFilter::Info,
+        8 => LevelFilter::Trace,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use core::cell::{Cell, RefCell};
+
+    use kernel_abi::{
+        EINTR, EINVAL, ERANGE, SYSLOG_ACTION_CLEAR, SYSLOG_ACTION_CONSOLE_LEVEL,
+        SYSLOG_ACTION_CONSOLE_OFF, SYSLOG_ACTION_CONSOLE_ON, SYSLOG_ACTION_GET_FILTER,
+        SYSLOG_ACTION_READ, SYSLOG_ACTION_READ_ALL, SYSLOG_ACTION_READ_CLEAR,
+        SYSLOG_ACTION_SET_FILTER, SYSLOG_ACTION_SIZE_BUFFER, SYSLOG_ACTION_SIZE_UNREAD,
+    };
+    use kernel_kmsg::Filter;
+    use log::LevelFilter;
+
+    use crate::access::{Interrupted, LogAccess};
+    use crate::syslog::sys_syslog;
+
+    /// A log of lines, where `read` is the number of lines that were read
+    /// with `read_log` and `cleared` the number of lines that were cleared.
+    #[derive(Default)]
+    struct TestLogAccess {
+        lines: Vec<&'static str>,
+        read: Cell<usize>,
+        cleared: Cell<usize>,
+        console: Cell<Option<(bool, LevelFilter)>>,
+        filter: RefCell<Option<Filter>>,
+    }
+
+    impl TestLogAccess {
+        fn copy(lines: &[&str], buf: &mut [u8]) -> usize {
+            let text = lines.concat();
+            let len = text.len().min(buf.len());
+            buf[..len].copy_from_slice(&text.as_bytes()[..len]);
+            len
+        }
+
+        fn console(&self) -> (bool, LevelFilter) {
+            self.console.get().unwrap_or((true, LevelFilter::Trace))
+        }
+    }
+
+    impl LogAccess for TestLogAccess {
+        fn read_log(&self, buf: &mut [u8]) -> Result<usize, Interrupted> {
+            if self.read.get() == self.lines.len() {
+                return Err(Interrupted);
+            }
+            let len = Self::copy(&self.lines[self.read.get()..], buf);
+            self.read.set(self.lines.len());
+            Ok(len)
+        }
+
+        fn read_log_all(&self, buf: &mut [u8]) -> usize {
+            Self::copy(&self.lines[self.cleared.get()..], buf)
+        }
+
+        fn clear_log(&self) {
+            self.cleared.set(self.lines.len());
+        }
+
+        fn unread_log_len(&self) -> usize {
+            self.lines[self.read.get()..].concat().len()
+        }
+
+        fn log_buffer_len(&self) -> usize {
+            1024
+        }
+
+        fn set_console_enabled(&self, enabled: bool) {
+            self.console.set(Some((enabled, self.console().1)));
+        }
+
+        fn set_console_level(&self, level: LevelFilter) {
+            self.console.set(Some((self.console().0, level)));
+        }
+
+        fn log_filter(&self) -> Filter {
+            self.filter.borrow().clone().unwrap_or_default()
+        }
+
+        fn set_log_filter(&self, filter: Filter) {
+            *self.filter.borrow_mut() = Some(filter);
+        }
+    }
+
+    fn cx() -> TestLogAccess {
+        TestLogAccess {
+            lines: ["a\n", "b\n"].into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_read() {
+        let cx = cx();
+        let mut buf = [0; 8];
+        assert_eq!(
+            Ok(4),
+            sys_syslog(&cx, SYSLOG_ACTION_SIZE_UNREAD, &mut [], 0)
+        );
+        assert_eq!(Ok(0), sys_syslog(&cx, SYSLOG_ACTION_READ, &mut [], 0));
+        assert_eq!(Ok(4), sys_syslog(&cx, SYSLOG_ACTION_READ, &mut buf, 8));
+        assert_eq!(b"a\nb\n", &buf[..4]);
+        assert_eq!(
+            Ok(0),
+            sys_syslog(&cx, SYSLOG_ACTION_SIZE_UNREAD, &mut [], 0)
+        );
+        assert_eq!(Err(EINTR), sys_syslog(&cx, SYSLOG_ACTION_READ, &mut buf, 8));
+
+        // reading everything doesn't depend on what was read before
+        assert_eq!(Ok(4), sys_syslog(&cx, SYSLOG_ACTION_READ_ALL, &mut buf, 8));
+        assert_eq!(
+            Ok(1024),
+            sys_syslog(&cx, SYSLOG_ACTION_SIZE_BUFFER, &mut [], 0)
+        );
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut buf = [0; 8];
+        let cx = cx();
+        assert_eq!(
+            Ok(4),
+            sys_syslog(&cx, SYSLOG_ACTION_READ_CLEAR, &mut buf, 8)
+        );
+        assert_eq!(Ok(0), sys_syslog(&cx, SYSLOG_ACTION_READ_ALL, &mut buf, 8));
+        assert_eq!(
+            Ok(4),
+            sys_syslog(&cx, SYSLOG_ACTION_SIZE_UNREAD, &mut [], 0)
+        );
+
+        assert_eq!(Ok(0), sys_syslog(&cx, SYSLOG_ACTION_CLEAR, &mut [], 0));
+        assert_eq!(Ok(0), sys_syslog(&cx, SYSLOG_ACTION_READ_ALL, &mut buf, 8));
+    }
+
+    #[test]
+    fn test_console() {
+        let cx = cx();
+        assert_eq!(
+            Ok(0),
+            sys_syslog(&cx, SYSLOG_ACTION_CONSOLE_OFF, &mut [], 0)
+        );
+        assert_eq!((false, LevelFilter::Trace), cx.console());
+        assert_eq!(
+            Ok(0),
+            sys_syslog(&cx, SYSLOG_ACTION_CONSOLE_LEVEL, &mut [], 5)
+        );
+        assert_eq!((false, LevelFilter::Warn), cx.console());
+        assert_eq!(Ok(0), sys_syslog(&cx, SYSLOG_ACTION_CONSOLE_ON, &mut [], 0));
+        assert_eq!((true, LevelFilter::Warn), cx.console());
+
+        for (level, expected) in [
+            (1, LevelFilter::Off),
+            (4, LevelFilter::Error),
+            (7, LevelFilter::Info),
+            (8, LevelFilter::Trace),
+        ] {
+            sys_syslog(&cx, SYSLOG_ACTION_CONSOLE_LEVEL, &mut [], level).unwrap();
+            assert_eq!(expected, cx.console().1);
+        }
+        for level in [0, 9] {
+            assert_eq!(
+                Err(EINVAL),
+                sys_syslog(&cx, SYSLOG_ACTION_CONSOLE_LEVEL, &mut [], level)
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let cx = cx();
+        let mut spec = *b"warn,kernel=debug";
+        assert_eq!(
+            Ok(0),
+            sys_syslog(&cx, SYSLOG_ACTION_SET_FILTER, &mut spec, 0)
+        );
+        assert_eq!(
+            Filter::new(LevelFilter::Warn).with_target("kernel", LevelFilter::Debug),
+            cx.log_filter()
+        );
+
+        let mut buf = [0; 32];
+        let len = sys_syslog(&cx, SYSLOG_ACTION_GET_FILTER, &mut buf, 32).unwrap();
+        assert_eq!("warn,kernel=debug", String::from_utf8_lossy(&buf[..len]));
+        assert_eq!(
+            Err(ERANGE),
+            sys_syslog(&cx, SYSLOG_ACTION_GET_FILTER, &mut buf[..4], 4)
+        );
+
+        let mut spec = *b"kernel=loud";
+        assert_eq!(
+            Err(EINVAL),
+            sys_syslog(&cx, SYSLOG_ACTION_SET_FILTER, &mut spec, 0)
+        );
+        assert_eq!(Err(EINVAL), sys_syslog(&cx, 11, &mut [], 0));
+    }
+}
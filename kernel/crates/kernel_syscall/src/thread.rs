@@ -0,0 +1,228 @@
+use core::ffi::c_int;
+
+use kernel_abi::{CloneArgs, CloneFlags, EAGAIN, EFAULT, EINVAL, ENOMEM, Errno};
+
+use crate::access::{NewThread, SpawnThreadError, ThreadAccess};
+use crate::{UserspaceMutPtr, UserspacePtr};
+
+/// Creates a thread in the current process, which starts with a call of
+/// `args.entry` with `args.arg`.
+///
+/// Returns the id of the new thread.
+pub fn sys_clone<Cx: ThreadAccess>(cx: &Cx, args: &CloneArgs) -> Result<usize, Errno> {
+    let flags = CloneFlags::from_bits(args.flags).ok_or(EINVAL)?;
+    // new processes are only created from executables
+    if !flags.contains(CloneFlags::VM | CloneFlags::SIGHAND | CloneFlags::THREAD) {
+        return Err(EINVAL);
+    }
+    if args.entry == 0 {
+        return Err(EINVAL);
+    }
+    let entry = unsafe { UserspacePtr::try_from_usize(args.entry as usize) }?;
+
+    let stack = match args.stack {
+        0 => None,
+        stack => {
+            let size = usize::try_from(args.stack_size).map_err(|_| EINVAL)?;
+            if size == 0 {
+                return Err(EINVAL);
+            }
+            let stack = unsafe { UserspacePtr::try_from_usize(stack as usize) }?;
+            stack.validate_range(size)?;
+            Some((stack, size))
+        }
+    };
+    let tls = flags
+        .contains(CloneFlags::SETTLS)
+        .then(|| unsafe { UserspacePtr::try_from_usize(args.tls as usize) })
+        .transpose()?;
+    let parent_tid = tid_ptr(flags, CloneFlags::PARENT_SETTID, args.parent_tid)?;
+    let child_tid = tid_ptr(flags, CloneFlags::CHILD_SETTID, args.child_tid)?;
+    let clear_child_tid = tid_ptr(flags, CloneFlags::CHILD_CLEARTID, args.child_tid)?;
+
+    let tid = cx
+        .spawn_thread(NewThread {
+            entry,
+            arg: args.arg as usize,
+            stack,
+            tls,
+            parent_tid,
+            child_tid,
+            clear_child_tid,
+        })
+        .map_err(|e| match e {
+            SpawnThreadError::OutOfMemory => ENOMEM,
+        })?;
+    usize::try_from(tid).map_err(|_| EAGAIN)
+}
+
+/// The thread id pointer at `addr`, if `flags` contain `flag`.
+fn tid_ptr(
+    flags: CloneFlags,
+    flag: CloneFlags,
+    addr: u64,
+) -> Result<Option<UserspaceMutPtr<c_int>>, Errno> {
+    if !flags.contains(flag) {
+        return Ok(None);
+    }
+    if addr == 0 || !addr.is_multiple_of(align_of::<c_int>() as u64) {
+        return Err(EFAULT);
+    }
+    let ptr = unsafe { UserspaceMutPtr::try_from_usize(addr as usize) }?;
+    Ok(Some(ptr))
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+    use core::ffi::c_int;
+
+    use kernel_abi::{CloneArgs, CloneFlags, EFAULT, EINVAL, ENOMEM};
+
+    use crate::access::{NewThread, SpawnThreadError, ThreadAccess};
+    use crate::thread::sys_clone;
+
+    /// The addresses of a [`NewThread`].
+    #[derive(Debug, Default, PartialEq, Eq)]
+    struct Spawned {
+        entry: usize,
+        arg: usize,
+        stack: Option<(usize, usize)>,
+        tls: Option<usize>,
+        parent_tid: Option<usize>,
+        child_tid: Option<usize>,
+        clear_child_tid: Option<usize>,
+    }
+
+    #[derive(Default)]
+    struct TestThreadAccess {
+        spawned: RefCell<Option<Spawned>>,
+        out_of_memory: bool,
+    }
+
+    impl ThreadAccess for TestThreadAccess {
+        fn spawn_thread(&self, thread: NewThread) -> Result<c_int, SpawnThreadError> {
+            if self.out_of_memory {
+                return Err(SpawnThreadError::OutOfMemory);
+            }
+            *self.spawned.borrow_mut() = Some(Spawned {
+                entry: thread.entry.addr(),
+                arg: thread.arg,
+                stack: thread.stack.map(|(stack, size)| (stack.addr(), size)),
+                tls: thread.tls.map(|tls| tls.addr()),
+                parent_tid: thread.parent_tid.map(|ptr| ptr.addr()),
+                child_tid: thread.child_tid.map(|ptr| ptr.addr()),
+                clear_child_tid: thread.clear_child_tid.map(|ptr| ptr.addr()),
+            });
+            Ok(7)
+        }
+    }
+
+    const THREAD: CloneFlags = CloneFlags::VM
+        .union(CloneFlags::FS)
+        .union(CloneFlags::FILES)
+        .union(CloneFlags::SIGHAND)
+        .union(CloneFlags::THREAD);
+
+    fn args(flags: CloneFlags) -> CloneArgs {
+        CloneArgs {
+            flags: flags.bits(),
+            entry: 0x40_1000,
+            arg: 42,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clone() {
+        let cx = TestThreadAccess::default();
+        assert_eq!(Ok(7), sys_clone(&cx, &args(THREAD)));
+        assert_eq!(
+            Some(Spawned {
+                entry: 0x40_1000,
+                arg: 42,
+                ..Default::default()
+            }),
+            cx.spawned.take()
+        );
+    }
+
+    #[test]
+    fn test_clone_with_everything() {
+        let cx = TestThreadAccess::default();
+        let args = CloneArgs {
+            stack: 0x10_0000,
+            stack_size: 0x4000,
+            tls: 0x20_0000,
+            parent_tid: 0x30_0000,
+            child_tid: 0x30_0004,
+            ..args(
+                THREAD
+                    | CloneFlags::SETTLS
+                    | CloneFlags::PARENT_SETTID
+                    | CloneFlags::CHILD_SETTID
+                    | CloneFlags::CHILD_CLEARTID,
+            )
+        };
+        assert_eq!(Ok(7), sys_clone(&cx, &args));
+        assert_eq!(
+            Some(Spawned {
+                entry: 0x40_1000,
+                arg: 42,
+                stack: Some((0x10_0000, 0x4000)),
+                tls: Some(0x20_0000),
+                parent_tid: Some(0x30_0000),
+                child_tid: Some(0x30_0004),
+                clear_child_tid: Some(0x30_0004),
+            }),
+            cx.spawned.take()
+        );
+    }
+
+    #[test]
+    fn test_clone_errors() {
+        let cx = TestThreadAccess::default();
+        // only threads
+        assert_eq!(Err(EINVAL), sys_clone(&cx, &args(CloneFlags::VM)));
+        assert_eq!(
+            Err(EINVAL),
+            sys_clone(&cx, &args(THREAD.difference(CloneFlags::SIGHAND)))
+        );
+        // unknown flags
+        let unknown = CloneArgs {
+            flags: THREAD.bits() | 1 << 40,
+            ..args(THREAD)
+        };
+        assert_eq!(Err(EINVAL), sys_clone(&cx, &unknown));
+        let no_entry = CloneArgs {
+            entry: 0,
+            ..args(THREAD)
+        };
+        assert_eq!(Err(EINVAL), sys_clone(&cx, &no_entry));
+        let empty_stack = CloneArgs {
+            stack: 0x10_0000,
+            ..args(THREAD)
+        };
+        assert_eq!(Err(EINVAL), sys_clone(&cx, &empty_stack));
+        let kernel_stack = CloneArgs {
+            stack: 0xffff_8000_0000_0000,
+            stack_size: 0x1000,
+            ..args(THREAD)
+        };
+        assert_eq!(Err(EINVAL), sys_clone(&cx, &kernel_stack));
+        let null_tid = args(THREAD | CloneFlags::CHILD_CLEARTID);
+        assert_eq!(Err(EFAULT), sys_clone(&cx, &null_tid));
+        let misaligned_tid = CloneArgs {
+            parent_tid: 0x30_0001,
+            ..args(THREAD | CloneFlags::PARENT_SETTID)
+        };
+        assert_eq!(Err(EFAULT), sys_clone(&cx, &misaligned_tid));
+        assert!(cx.spawned.borrow().is_none());
+
+        let cx = TestThreadAccess {
+            out_of_memory: true,
+            ..Default::default()
+        };
+        assert_eq!(Err(ENOMEM), sys_clone(&cx, &args(THREAD)));
+    }
+}
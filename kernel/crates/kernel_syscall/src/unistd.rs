@@ -1,6 +1,6 @@
 use core::slice::from_raw_parts_mut;
 
-use kernel_abi::{EINVAL, ERANGE, Errno};
+use kernel_abi::{EBADF, EINVAL, ERANGE, Errno};
 
 use crate::access::{CwdAccess, FileAccess};
 use crate::ptr::UserspaceMutPtr;
@@ -42,16 +42,24 @@ pub fn sys_write<Cx: FileAccess>(cx: &Cx, fildes: Cx::Fd, buf: &[u8]) -> Result<
     cx.write(fildes, buf).map_err(|_| EINVAL)
 }
 
+pub fn sys_close<Cx: FileAccess>(cx: &Cx, fildes: Cx::Fd) -> Result<usize, Errno> {
+    cx.close(fildes).map_err(|_| EBADF)?;
+    Ok(0)
+}
+
 #[cfg(test)]
 mod tests {
+    use alloc::sync::Arc;
     use alloc::vec;
 
-    use kernel_abi::{EINVAL, ERANGE};
-    use kernel_vfs::path::AbsoluteOwnedPath;
+    use kernel_abi::{EBADF, EINVAL, ERANGE};
+    use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath};
+    use spin::mutex::Mutex;
     use spin::rwlock::RwLock;
 
-    use crate::access::CwdAccess;
-    use crate::unistd::sys_getcwd;
+    use crate::access::testing::{MemoryFile, MemoryFileAccess};
+    use crate::access::{CwdAccess, FileAccess};
+    use crate::unistd::{sys_close, sys_getcwd, sys_read};
 
     #[test]
     fn test_getcwd() {
@@ -88,4 +96,22 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_close() {
+        let mut file_access = MemoryFileAccess::default();
+        file_access.files.insert(
+            AbsoluteOwnedPath::try_from("/foo.txt").unwrap(),
+            Arc::new(MemoryFile::new(vec![0_u8; 16])),
+        );
+        let cx = Mutex::new(file_access);
+
+        let info = cx
+            .file_info(AbsolutePath::try_new("/foo.txt").unwrap())
+            .unwrap();
+        let fd = cx.open(&info).unwrap();
+        assert_eq!(Ok(0), sys_close(&cx, fd.clone()));
+        assert_eq!(Err(EINVAL), sys_read(&cx, fd.clone(), &mut [0; 4]));
+        assert_eq!(Err(EBADF), sys_close(&cx, fd));
+    }
 }
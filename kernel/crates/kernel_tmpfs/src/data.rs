@@ -0,0 +1,174 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::collections::btree_map::Entry;
+
+/// The size of the chunks that file contents are stored in.
+pub const CHUNK_SIZE: usize = 4096;
+
+/// The contents of a regular file, as chunks by their index. Chunks that
+/// were never written to are not allocated and read as zeros, so writing
+/// far behind the end of a file costs no more than writing at its start.
+#[derive(Default)]
+pub(crate) struct FileData {
+    chunks: BTreeMap<usize, Box<[u8; CHUNK_SIZE]>>,
+    len: usize,
+}
+
+impl FileData {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn allocated_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Reads from `offset` into `buf` and returns the number of bytes read,
+    /// which is 0 at or after the end of the file.
+    pub fn read(&self, buf: &mut [u8], offset: usize) -> usize {
+        let len = buf.len().min(self.len.saturating_sub(offset));
+
+        let mut done = 0;
+        while done < len {
+            let pos = offset + done;
+            let chunk_offset = pos % CHUNK_SIZE;
+            let n = (CHUNK_SIZE - chunk_offset).min(len - done);
+            let dst = &mut buf[done..done + n];
+            match self.chunks.get(&(pos / CHUNK_SIZE)) {
+                Some(chunk) => dst.copy_from_slice(&chunk[chunk_offset..chunk_offset + n]),
+                None => dst.fill(0),
+            }
+            done += n;
+        }
+        len
+    }
+
+    /// Writes `buf` at `offset` and returns the number of bytes written.
+    /// Before a chunk is allocated, `may_allocate` is asked for permission.
+    /// If it refuses, the write stops early. Nothing is written if the file
+    /// would end beyond `usize::MAX`.
+    pub fn write(
+        &mut self,
+        buf: &[u8],
+        offset: usize,
+        mut may_allocate: impl FnMut() -> bool,
+    ) -> usize {
+        if offset.checked_add(buf.len()).is_none() {
+            return 0;
+        }
+
+        let mut done = 0;
+        while done < buf.len() {
+            let pos = offset + done;
+            let index = pos / CHUNK_SIZE;
+            let chunk_offset = pos % CHUNK_SIZE;
+            let n = (CHUNK_SIZE - chunk_offset).min(buf.len() - done);
+
+            let chunk = match self.chunks.entry(index) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    if !may_allocate() {
+                        break;
+                    }
+                    entry.insert(Box::new([0; CHUNK_SIZE]))
+                }
+            };
+            chunk[chunk_offset..chunk_offset + n].copy_from_slice(&buf[done..done + n]);
+            done += n;
+        }
+
+        if done > 0 {
+            self.len = self.len.max(offset + done);
+        }
+        done
+    }
+
+    /// Sets the length of the file and returns the number of chunks that
+    /// were freed. Growing the file doesn't allocate any chunks.
+    pub fn truncate(&mut self, len: usize) -> usize {
+        if len < self.len {
+            // the rest of the last chunk must read as zeros if the file grows again
+            let chunk_offset = len % CHUNK_SIZE;
+            if chunk_offset != 0
+                && let Some(chunk) = self.chunks.get_mut(&(len / CHUNK_SIZE))
+            {
+                chunk[chunk_offset..].fill(0);
+            }
+        }
+
+        let freed = self.chunks.split_off(&len.div_ceil(CHUNK_SIZE));
+        self.len = len;
+        freed.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::{CHUNK_SIZE, FileData};
+
+    #[test]
+    fn test_holes_read_as_zeros() {
+        let mut data = FileData::default();
+        assert_eq!(3, data.write(&[1, 2, 3], 2 * CHUNK_SIZE, || true));
+        assert_eq!(2 * CHUNK_SIZE + 3, data.len());
+        assert_eq!(1, data.allocated_chunks());
+
+        let mut buf = vec![0xFF; 4];
+        assert_eq!(4, data.read(&mut buf, 2 * CHUNK_SIZE - 1));
+        assert_eq!([0, 1, 2, 3], buf.as_slice());
+        assert_eq!(0, data.read(&mut buf, 2 * CHUNK_SIZE + 3));
+    }
+
+    #[test]
+    fn test_write_stops_when_allocation_is_refused() {
+        let mut data = FileData::default();
+        let mut remaining = 1;
+        let mut may_allocate = || {
+            let ok = remaining > 0;
+            remaining -= i32::from(ok);
+            ok
+        };
+
+        let buf = vec![7; CHUNK_SIZE + 10];
+        assert_eq!(CHUNK_SIZE - 5, data.write(&buf, 5, &mut may_allocate));
+        assert_eq!(CHUNK_SIZE, data.len());
+        assert_eq!(0, data.write(&buf, CHUNK_SIZE, &mut may_allocate));
+        assert_eq!(CHUNK_SIZE, data.len());
+    }
+
+    #[test]
+    fn test_write_at_huge_offset() {
+        let mut data = FileData::default();
+        let offset = (usize::MAX / CHUNK_SIZE - 1) * CHUNK_SIZE;
+        assert_eq!(3, data.write(&[1, 2, 3], offset, || true));
+        assert_eq!(offset + 3, data.len());
+        assert_eq!(1, data.allocated_chunks());
+
+        let mut buf = [0xFF; 4];
+        assert_eq!(4, data.read(&mut buf, offset - 1));
+        assert_eq!([0, 1, 2, 3], buf);
+
+        // the end of the file can't overflow
+        assert_eq!(0, data.write(&[1; 2 * CHUNK_SIZE], offset, || true));
+        assert_eq!(offset + 3, data.len());
+        assert_eq!(1, data.allocated_chunks());
+
+        assert_eq!(1, data.truncate(0));
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut data = FileData::default();
+        data.write(&[1; 2 * CHUNK_SIZE], 0, || true);
+
+        assert_eq!(1, data.truncate(10));
+        assert_eq!(0, data.truncate(CHUNK_SIZE));
+        assert_eq!(CHUNK_SIZE, data.len());
+
+        let mut buf = [0xFF; 2];
+        data.read(&mut buf, 9);
+        assert_eq!([1, 0], buf);
+    }
+}
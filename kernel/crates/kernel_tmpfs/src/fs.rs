@@ -0,0 +1,701 @@
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use kernel_vfs::fs::{DirEntry, FileSystem, FsHandle};
+use kernel_vfs::path::{AbsolutePath, OwnedPath, Path};
+use kernel_vfs::{
+    CloseError, CreateError, FileType, FsError, OpenError, ReadDirError, ReadError, ReadLinkError,
+    RemoveError, SetAttrError, Stat, StatError, Timespec, WriteError,
+};
+
+use crate::data::{CHUNK_SIZE, FileData};
+use crate::options::TmpFsOptions;
+
+const ROOT_INO: u64 = 1;
+
+/// An in-memory file system with files, directories, symbolic links and
+/// hard links.
+///
+/// Paths are not resolved through symbolic links, an intermediate symbolic
+/// link is treated like any other file that is not a directory.
+pub struct TmpFs {
+    inodes: BTreeMap<u64, Inode>,
+    open_files: BTreeMap<FsHandle, u64>,
+    next_ino: u64,
+    next_handle: u64,
+    /// The number of chunks that may be allocated, `None` if unlimited.
+    max_chunks: Option<usize>,
+    used_chunks: usize,
+    clock: fn() -> Timespec,
+}
+
+struct Inode {
+    kind: InodeKind,
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    nlink: usize,
+    /// The number of open handles. An inode without links is only removed
+    /// once it's no longer open.
+    open_count: usize,
+    atime: Timespec,
+    mtime: Timespec,
+    ctime: Timespec,
+}
+
+enum InodeKind {
+    File(FileData),
+    Directory(BTreeMap<String, u64>),
+    Symlink(OwnedPath),
+}
+
+impl InodeKind {
+    fn file_type(&self) -> FileType {
+        match self {
+            InodeKind::File(_) => FileType::RegularFile,
+            InodeKind::Directory(_) => FileType::Directory,
+            InodeKind::Symlink(_) => FileType::Symlink,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum LookupError {
+    NotFound,
+    NotADirectory,
+}
+
+impl From<LookupError> for CreateError {
+    fn from(e: LookupError) -> Self {
+        match e {
+            LookupError::NotFound => CreateError::NotFound,
+            LookupError::NotADirectory => CreateError::NotADirectory,
+        }
+    }
+}
+
+impl From<LookupError> for RemoveError {
+    fn from(e: LookupError) -> Self {
+        match e {
+            LookupError::NotFound => RemoveError::NotFound,
+            LookupError::NotADirectory => RemoveError::NotADirectory,
+        }
+    }
+}
+
+impl TmpFs {
+    /// Creates an empty file system. `clock` provides the timestamps of
+    /// the files.
+    #[must_use]
+    pub fn new(options: &TmpFsOptions, clock: fn() -> Timespec) -> Self {
+        let now = clock();
+        let root = Inode {
+            kind: InodeKind::Directory(BTreeMap::new()),
+            mode: options.mode,
+            uid: options.uid,
+            gid: options.gid,
+            nlink: 2,
+            open_count: 0,
+            atime: now,
+            mtime: now,
+            ctime: now,
+        };
+
+        Self {
+            inodes: BTreeMap::from([(ROOT_INO, root)]),
+            open_files: BTreeMap::new(),
+            next_ino: ROOT_INO + 1,
+            next_handle: 0,
+            max_chunks: options.size.map(|size| size.div_ceil(CHUNK_SIZE)),
+            used_chunks: 0,
+            clock,
+        }
+    }
+
+    /// The number of bytes that are allocated for file contents.
+    #[must_use]
+    pub fn used_bytes(&self) -> usize {
+        self.used_chunks * CHUNK_SIZE
+    }
+
+    fn inode(&self, ino: u64) -> &Inode {
+        self.inodes
+            .get(&ino)
+            .expect("directory entries should point to inodes")
+    }
+
+    fn inode_mut(&mut self, ino: u64) -> &mut Inode {
+        self.inodes
+            .get_mut(&ino)
+            .expect("directory entries should point to inodes")
+    }
+
+    fn handle_ino(&self, handle: FsHandle) -> Result<u64, FsError> {
+        self.open_files
+            .get(&handle)
+            .copied()
+            .ok_or(FsError::InvalidHandle)
+    }
+
+    fn lookup_in(&self, dir: u64, name: &str) -> Result<u64, LookupError> {
+        match &self.inode(dir).kind {
+            InodeKind::Directory(entries) => {
+                entries.get(name).copied().ok_or(LookupError::NotFound)
+            }
+            _ => Err(LookupError::NotADirectory),
+        }
+    }
+
+    fn lookup(&self, path: &AbsolutePath) -> Result<u64, LookupError> {
+        path.filenames()
+            .try_fold(ROOT_INO, |dir, name| self.lookup_in(dir, name))
+    }
+
+    /// Returns the directory that contains `path` and the last component
+    /// of `path`, or `None` if `path` is the root directory.
+    fn lookup_parent<'p>(
+        &self,
+        path: &'p AbsolutePath,
+    ) -> Result<Option<(u64, &'p str)>, LookupError> {
+        let mut names = path.filenames();
+        let Some(name) = names.next_back() else {
+            return Ok(None);
+        };
+        let parent = names.try_fold(ROOT_INO, |dir, name| self.lookup_in(dir, name))?;
+        match self.inode(parent).kind {
+            InodeKind::Directory(_) => Ok(Some((parent, name))),
+            _ => Err(LookupError::NotADirectory),
+        }
+    }
+
+    /// Adds a new inode with a single link at `path`.
+    fn create_inode(
+        &mut self,
+        path: &AbsolutePath,
+        kind: InodeKind,
+        mode: u32,
+    ) -> Result<u64, CreateError> {
+        let (parent, name) = self
+            .lookup_parent(path)?
+            .ok_or(CreateError::AlreadyExists)?;
+        if self.lookup_in(parent, name).is_ok() {
+            return Err(CreateError::AlreadyExists);
+        }
+
+        let is_dir = matches!(kind, InodeKind::Directory(_));
+        let now = (self.clock)();
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        self.inodes.insert(
+            ino,
+            Inode {
+                kind,
+                mode: mode & 0o7777,
+                uid: 0,
+                gid: 0,
+                nlink: if is_dir { 2 } else { 1 },
+                open_count: 0,
+                atime: now,
+                mtime: now,
+                ctime: now,
+            },
+        );
+        self.add_entry(parent, name, ino, now);
+        if is_dir {
+            self.inode_mut(parent).nlink += 1;
+        }
+        Ok(ino)
+    }
+
+    fn add_entry(&mut self, dir: u64, name: &str, ino: u64, now: Timespec) {
+        let dir = self.inode_mut(dir);
+        if let InodeKind::Directory(entries) = &mut dir.kind {
+            entries.insert(name.to_string(), ino);
+        }
+        dir.mtime = now;
+        dir.ctime = now;
+    }
+
+    fn remove_entry(&mut self, dir: u64, name: &str, now: Timespec) {
+        let dir = self.inode_mut(dir);
+        if let InodeKind::Directory(entries) = &mut dir.kind {
+            entries.remove(name);
+        }
+        dir.mtime = now;
+        dir.ctime = now;
+    }
+
+    /// Removes the inode if it has neither links nor open handles.
+    fn release(&mut self, ino: u64) {
+        let inode = self.inode(ino);
+        if inode.nlink > 0 || inode.open_count > 0 {
+            return;
+        }
+
+        if let Some(Inode {
+            kind: InodeKind::File(data),
+            ..
+        }) = self.inodes.remove(&ino)
+        {
+            self.used_chunks -= data.allocated_chunks();
+        }
+    }
+}
+
+impl FileSystem for TmpFs {
+    fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
+        let ino = self.lookup(path).map_err(|_| OpenError::NotFound)?;
+        self.inode_mut(ino).open_count += 1;
+
+        let handle = FsHandle::from(self.next_handle);
+        self.next_handle += 1;
+        self.open_files.insert(handle, ino);
+        Ok(handle)
+    }
+
+    fn close(&mut self, handle: FsHandle) -> Result<(), CloseError> {
+        let ino = self.open_files.remove(&handle).ok_or(CloseError::NotOpen)?;
+        self.inode_mut(ino).open_count -= 1;
+        self.release(ino);
+        Ok(())
+    }
+
+    fn read(
+        &mut self,
+        handle: FsHandle,
+        buf: &mut [u8],
+        offset: usize,
+    ) -> Result<usize, ReadError> {
+        let ino = self.handle_ino(handle)?;
+        let now = (self.clock)();
+        let inode = self.inode_mut(ino);
+        let InodeKind::File(data) = &inode.kind else {
+            return Err(ReadError::NotReadable);
+        };
+        if offset >= data.len() {
+            return Err(ReadError::EndOfFile);
+        }
+
+        let n = data.read(buf, offset);
+        inode.atime = now;
+        Ok(n)
+    }
+
+    fn write(&mut self, handle: FsHandle, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
+        let ino = self.handle_ino(handle)?;
+        let now = (self.clock)();
+        let Self {
+            inodes,
+            max_chunks,
+            used_chunks,
+            ..
+        } = self;
+        let inode = inodes
+            .get_mut(&ino)
+            .expect("open handles should point to inodes");
+        let InodeKind::File(data) = &mut inode.kind else {
+            return Err(WriteError::NotWritable);
+        };
+
+        let n = data.write(buf, offset, || {
+            if max_chunks.is_some_and(|max| *used_chunks >= max) {
+                return false;
+            }
+            *used_chunks += 1;
+            true
+        });
+        if n == 0 && !buf.is_empty() {
+            return Err(WriteError::NoSpace);
+        }
+        inode.mtime = now;
+        inode.ctime = now;
+        Ok(n)
+    }
+
+    fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
+        let ino = self.handle_ino(handle)?;
+        let inode = self.inode(ino);
+        *stat = Stat {
+            file_type: inode.kind.file_type(),
+            mode: inode.mode,
+            nlink: inode.nlink,
+            uid: inode.uid,
+            gid: inode.gid,
+            ino,
+            size: match &inode.kind {
+                InodeKind::File(data) => data.len(),
+                InodeKind::Directory(_) => 0,
+                InodeKind::Symlink(target) => target.len(),
+            },
+            atime: inode.atime,
+            mtime: inode.mtime,
+            ctime: inode.ctime,
+        };
+        Ok(())
+    }
+
+    fn read_dir(&mut self, handle: FsHandle) -> Result<Vec<DirEntry>, ReadDirError> {
+        let ino = self.handle_ino(handle)?;
+        let InodeKind::Directory(entries) = &self.inode(ino).kind else {
+            return Err(ReadDirError::NotADirectory);
+        };
+
+        let entries = entries
+            .iter()
+            .map(|(name, ino)| DirEntry {
+                name: name.clone(),
+                file_type: self.inode(*ino).kind.file_type(),
+            })
+            .collect();
+        let now = (self.clock)();
+        self.inode_mut(ino).atime = now;
+        Ok(entries)
+    }
+
+    fn create(&mut self, path: &AbsolutePath, mode: u32) -> Result<(), CreateError> {
+        self.create_inode(path, InodeKind::File(FileData::default()), mode)
+            .map(|_| ())
+    }
+
+    fn mkdir(&mut self, path: &AbsolutePath, mode: u32) -> Result<(), CreateError> {
+        self.create_inode(path, InodeKind::Directory(BTreeMap::new()), mode)
+            .map(|_| ())
+    }
+
+    fn symlink(&mut self, target: &Path, path: &AbsolutePath) -> Result<(), CreateError> {
+        self.create_inode(path, InodeKind::Symlink(target.to_owned()), 0o777)
+            .map(|_| ())
+    }
+
+    fn link(&mut self, existing: &AbsolutePath, path: &AbsolutePath) -> Result<(), CreateError> {
+        let ino = self.lookup(existing)?;
+        if matches!(self.inode(ino).kind, InodeKind::Directory(_)) {
+            return Err(CreateError::IsADirectory);
+        }
+        let (parent, name) = self
+            .lookup_parent(path)?
+            .ok_or(CreateError::AlreadyExists)?;
+        if self.lookup_in(parent, name).is_ok() {
+            return Err(CreateError::AlreadyExists);
+        }
+
+        let now = (self.clock)();
+        self.add_entry(parent, name, ino, now);
+        let inode = self.inode_mut(ino);
+        inode.nlink += 1;
+        inode.ctime = now;
+        Ok(())
+    }
+
+    fn unlink(&mut self, path: &AbsolutePath) -> Result<(), RemoveError> {
+        let (parent, name) = self.lookup_parent(path)?.ok_or(RemoveError::IsADirectory)?;
+        let ino = self.lookup_in(parent, name)?;
+        if matches!(self.inode(ino).kind, InodeKind::Directory(_)) {
+            return Err(RemoveError::IsADirectory);
+        }
+
+        let now = (self.clock)();
+        self.remove_entry(parent, name, now);
+        let inode = self.inode_mut(ino);
+        inode.nlink -= 1;
+        inode.ctime = now;
+        self.release(ino);
+        Ok(())
+    }
+
+    fn rmdir(&mut self, path: &AbsolutePath) -> Result<(), RemoveError> {
+        let (parent, name) = self.lookup_parent(path)?.ok_or(RemoveError::Busy)?;
+        let ino = self.lookup_in(parent, name)?;
+        match &self.inode(ino).kind {
+            InodeKind::Directory(entries) if !entries.is_empty() => {
+                return Err(RemoveError::NotEmpty);
+            }
+            InodeKind::Directory(_) => {}
+            _ => return Err(RemoveError::NotADirectory),
+        }
+
+        let now = (self.clock)();
+        self.remove_entry(parent, name, now);
+        self.inode_mut(parent).nlink -= 1;
+        let inode = self.inode_mut(ino);
+        inode.nlink = 0;
+        inode.ctime = now;
+        self.release(ino);
+        Ok(())
+    }
+
+    fn readlink(&mut self, path: &AbsolutePath) -> Result<OwnedPath, ReadLinkError> {
+        let ino = self.lookup(path).map_err(|_| ReadLinkError::NotFound)?;
+        match &self.inode(ino).kind {
+            InodeKind::Symlink(target) => Ok(target.clone()),
+            _ => Err(ReadLinkError::NotASymlink),
+        }
+    }
+
+    fn set_mode(&mut self, handle: FsHandle, mode: u32) -> Result<(), SetAttrError> {
+        let ino = self.handle_ino(handle)?;
+        let now = (self.clock)();
+        let inode = self.inode_mut(ino);
+        inode.mode = mode & 0o7777;
+        inode.ctime = now;
+        Ok(())
+    }
+
+    fn set_owner(&mut self, handle: FsHandle, uid: u32, gid: u32) -> Result<(), SetAttrError> {
+        let ino = self.handle_ino(handle)?;
+        let now = (self.clock)();
+        let inode = self.inode_mut(ino);
+        inode.uid = uid;
+        inode.gid = gid;
+        inode.ctime = now;
+        Ok(())
+    }
+
+    fn truncate(&mut self, handle: FsHandle, len: usize) -> Result<(), WriteError> {
+        let ino = self.handle_ino(handle)?;
+        let now = (self.clock)();
+        let inode = self.inode_mut(ino);
+        let InodeKind::File(data) = &mut inode.kind else {
+            return Err(WriteError::NotWritable);
+        };
+
+        let freed = data.truncate(len);
+        inode.mtime = now;
+        inode.ctime = now;
+        self.used_chunks -= freed;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use core::sync::atomic::AtomicI64;
+    use core::sync::atomic::Ordering::Relaxed;
+
+    use kernel_vfs::fs::{DirEntry, FileSystem};
+    use kernel_vfs::path::{AbsolutePath, Path};
+    use kernel_vfs::{
+        CreateError, FileType, ReadError, ReadLinkError, RemoveError, Stat, Timespec, WriteError,
+    };
+
+    use crate::{CHUNK_SIZE, TmpFs, TmpFsOptions};
+
+    /// A clock that advances by one second every time it's read.
+    fn clock() -> Timespec {
+        static SECS: AtomicI64 = AtomicI64::new(0);
+        Timespec {
+            secs: SECS.fetch_add(1, Relaxed),
+            nanos: 0,
+        }
+    }
+
+    fn path(s: &str) -> &AbsolutePath {
+        AbsolutePath::try_new(s).unwrap()
+    }
+
+    fn tmpfs() -> TmpFs {
+        TmpFs::new(&TmpFsOptions::default(), clock)
+    }
+
+    fn stat(fs: &mut TmpFs, p: &str) -> Stat {
+        let handle = fs.open(path(p)).unwrap();
+        let mut stat = Stat::default();
+        fs.stat(handle, &mut stat).unwrap();
+        fs.close(handle).unwrap();
+        stat
+    }
+
+    #[test]
+    fn test_create_write_read() {
+        let mut fs = tmpfs();
+        fs.mkdir(path("/dir"), 0o755).unwrap();
+        fs.create(path("/dir/file"), 0o644).unwrap();
+        assert_eq!(
+            Err(CreateError::AlreadyExists),
+            fs.create(path("/dir/file"), 0o644)
+        );
+        assert_eq!(
+            Err(CreateError::NotFound),
+            fs.create(path("/nope/file"), 0o644)
+        );
+        assert_eq!(
+            Err(CreateError::NotADirectory),
+            fs.create(path("/dir/file/x"), 0o644)
+        );
+
+        let handle = fs.open(path("/dir/file")).unwrap();
+        assert_eq!(Err(ReadError::EndOfFile), fs.read(handle, &mut [0; 4], 0));
+        assert_eq!(Ok(5), fs.write(handle, b"hello", 0));
+        assert_eq!(Ok(5), fs.write(handle, b"world", 10));
+
+        let mut buf = [0xFF; 15];
+        assert_eq!(Ok(15), fs.read(handle, &mut buf, 0));
+        assert_eq!(b"hello\0\0\0\0\0world", &buf);
+        assert_eq!(Err(ReadError::EndOfFile), fs.read(handle, &mut buf, 15));
+
+        let mut stat = Stat::default();
+        fs.stat(handle, &mut stat).unwrap();
+        assert_eq!(FileType::RegularFile, stat.file_type);
+        assert_eq!(0o644, stat.mode);
+        assert_eq!(1, stat.nlink);
+        assert_eq!(15, stat.size);
+    }
+
+    #[test]
+    fn test_read_dir() {
+        let mut fs = tmpfs();
+        fs.mkdir(path("/b"), 0o755).unwrap();
+        fs.create(path("/a"), 0o644).unwrap();
+        fs.symlink(Path::new("a"), path("/c")).unwrap();
+
+        let handle = fs.open(path("/")).unwrap();
+        assert_eq!(
+            Ok(vec![
+                DirEntry {
+                    name: "a".into(),
+                    file_type: FileType::RegularFile,
+                },
+                DirEntry {
+                    name: "b".into(),
+                    file_type: FileType::Directory,
+                },
+                DirEntry {
+                    name: "c".into(),
+                    file_type: FileType::Symlink,
+                },
+            ]),
+            fs.read_dir(handle)
+        );
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let options = TmpFsOptions {
+            size: Some(2 * CHUNK_SIZE),
+            ..TmpFsOptions::default()
+        };
+        let mut fs = TmpFs::new(&options, clock);
+        fs.create(path("/a"), 0o644).unwrap();
+        fs.create(path("/b"), 0o644).unwrap();
+        let a = fs.open(path("/a")).unwrap();
+        let b = fs.open(path("/b")).unwrap();
+
+        // holes don't count against the limit
+        assert_eq!(Ok(1), fs.write(a, &[1], 100 * CHUNK_SIZE));
+        assert_eq!(Ok(CHUNK_SIZE), fs.write(b, &[2; 2 * CHUNK_SIZE], 0));
+        assert_eq!(Err(WriteError::NoSpace), fs.write(b, &[3], CHUNK_SIZE));
+        assert_eq!(2 * CHUNK_SIZE, fs.used_bytes());
+
+        fs.truncate(a, 0).unwrap();
+        assert_eq!(Ok(1), fs.write(b, &[3], CHUNK_SIZE));
+        assert_eq!(Err(WriteError::NoSpace), fs.write(a, &[4], usize::MAX / 2));
+        assert_eq!(Err(WriteError::NoSpace), fs.write(a, &[4], usize::MAX));
+
+        // the space of a removed file is freed when it's closed
+        fs.unlink(path("/b")).unwrap();
+        assert_eq!(2 * CHUNK_SIZE, fs.used_bytes());
+        fs.close(b).unwrap();
+        assert_eq!(0, fs.used_bytes());
+    }
+
+    #[test]
+    fn test_hard_links() {
+        let mut fs = tmpfs();
+        fs.mkdir(path("/dir"), 0o755).unwrap();
+        fs.create(path("/a"), 0o644).unwrap();
+        fs.link(path("/a"), path("/dir/b")).unwrap();
+        assert_eq!(
+            Err(CreateError::IsADirectory),
+            fs.link(path("/dir"), path("/c"))
+        );
+
+        let a = fs.open(path("/a")).unwrap();
+        fs.write(a, b"shared", 0).unwrap();
+        fs.close(a).unwrap();
+        assert_eq!(2, stat(&mut fs, "/dir/b").nlink);
+        assert_eq!(stat(&mut fs, "/a").ino, stat(&mut fs, "/dir/b").ino);
+
+        fs.unlink(path("/a")).unwrap();
+        assert!(fs.open(path("/a")).is_err());
+        let b = fs.open(path("/dir/b")).unwrap();
+        let mut buf = [0; 6];
+        assert_eq!(Ok(6), fs.read(b, &mut buf, 0));
+        assert_eq!(b"shared", &buf);
+        assert_eq!(1, stat(&mut fs, "/dir/b").nlink);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut fs = tmpfs();
+        fs.mkdir(path("/dir"), 0o755).unwrap();
+        fs.create(path("/dir/file"), 0o644).unwrap();
+        assert_eq!(3, stat(&mut fs, "/").nlink);
+        assert_eq!(2, stat(&mut fs, "/dir").nlink);
+
+        assert_eq!(Err(RemoveError::IsADirectory), fs.unlink(path("/dir")));
+        assert_eq!(Err(RemoveError::NotEmpty), fs.rmdir(path("/dir")));
+        assert_eq!(Err(RemoveError::NotADirectory), fs.rmdir(path("/dir/file")));
+        assert_eq!(Err(RemoveError::NotFound), fs.unlink(path("/nope")));
+        assert_eq!(Err(RemoveError::Busy), fs.rmdir(path("/")));
+
+        fs.unlink(path("/dir/file")).unwrap();
+        fs.rmdir(path("/dir")).unwrap();
+        assert_eq!(2, stat(&mut fs, "/").nlink);
+        assert!(fs.open(path("/dir")).is_err());
+    }
+
+    #[test]
+    fn test_symlink() {
+        let mut fs = tmpfs();
+        fs.create(path("/file"), 0o644).unwrap();
+        fs.symlink(Path::new("../file"), path("/link")).unwrap();
+
+        assert_eq!(
+            Ok("../file"),
+            fs.readlink(path("/link")).as_ref().map(|p| p.as_str())
+        );
+        assert_eq!(Err(ReadLinkError::NotASymlink), fs.readlink(path("/file")));
+        let stat = stat(&mut fs, "/link");
+        assert_eq!(FileType::Symlink, stat.file_type);
+        assert_eq!(7, stat.size);
+    }
+
+    #[test]
+    fn test_permissions_and_timestamps() {
+        let options = TmpFsOptions {
+            mode: 0o700,
+            uid: 1000,
+            gid: 1000,
+            ..TmpFsOptions::default()
+        };
+        let mut fs = TmpFs::new(&options, clock);
+        let root = stat(&mut fs, "/");
+        assert_eq!((0o700, 1000, 1000), (root.mode, root.uid, root.gid));
+
+        fs.create(path("/file"), 0o100_644).unwrap();
+        let handle = fs.open(path("/file")).unwrap();
+        let mut before = Stat::default();
+        fs.stat(handle, &mut before).unwrap();
+        assert_eq!(0o644, before.mode);
+
+        fs.write(handle, b"x", 0).unwrap();
+        fs.set_mode(handle, 0o600).unwrap();
+        fs.set_owner(handle, 1, 2).unwrap();
+        let mut after = Stat::default();
+        fs.stat(handle, &mut after).unwrap();
+        assert_eq!((0o600, 1, 2), (after.mode, after.uid, after.gid));
+        assert!(after.mtime > before.mtime);
+        assert!(after.ctime > after.mtime);
+        assert_eq!(before.atime, after.atime);
+
+        fs.read(handle, &mut [0], 0).unwrap();
+        fs.stat(handle, &mut after).unwrap();
+        assert!(after.atime > before.atime);
+
+        // creating a file modifies the directory
+        assert!(stat(&mut fs, "/").mtime > root.mtime);
+    }
+}
@@ -0,0 +1,15 @@
+//! tmpfs, a writable file system that keeps all files in memory.
+//!
+//! File contents are stored in chunks of [`CHUNK_SIZE`] bytes, which are
+//! only allocated when written to. The number of allocated chunks is
+//! limited by the `size=` mount option, see [`TmpFsOptions`].
+#![no_std]
+extern crate alloc;
+
+mod data;
+mod fs;
+mod options;
+
+pub use data::CHUNK_SIZE;
+pub use fs::*;
+pub use options::*;
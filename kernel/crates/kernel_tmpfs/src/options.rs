@@ -0,0 +1,141 @@
+use alloc::string::{String, ToString};
+use core::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ParseOptionsError {
+    #[error("unknown option {0:?}")]
+    UnknownOption(String),
+    #[error("option {0} requires a value")]
+    MissingValue(&'static str),
+    #[error("invalid value for {0}")]
+    InvalidValue(&'static str),
+}
+
+/// The mount options of a tmpfs, parsed from a comma separated list such
+/// as `size=16M,mode=1777`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TmpFsOptions {
+    /// `size=`, the maximum number of bytes of file contents, rounded up to
+    /// whole chunks. The value may have a `k`, `m` or `g` suffix. `None`
+    /// means that the size is not limited.
+    pub size: Option<usize>,
+    /// `mode=`, the permission bits of the root directory in octal.
+    pub mode: u32,
+    /// `uid=`, the owner of the root directory.
+    pub uid: u32,
+    /// `gid=`, the group of the root directory.
+    pub gid: u32,
+}
+
+impl Default for TmpFsOptions {
+    fn default() -> Self {
+        Self {
+            size: None,
+            mode: 0o1777,
+            uid: 0,
+            gid: 0,
+        }
+    }
+}
+
+impl FromStr for TmpFsOptions {
+    type Err = ParseOptionsError;
+
+    fn from_str(options: &str) -> Result<Self, Self::Err> {
+        let mut result = Self::default();
+        for option in options.split(',').filter(|option| !option.is_empty()) {
+            let (key, value) = match option.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (option, None),
+            };
+
+            match key {
+                "size" => {
+                    let value = value.ok_or(ParseOptionsError::MissingValue("size"))?;
+                    result.size =
+                        Some(parse_size(value).ok_or(ParseOptionsError::InvalidValue("size"))?);
+                }
+                "mode" => {
+                    let value = value.ok_or(ParseOptionsError::MissingValue("mode"))?;
+                    result.mode = u32::from_str_radix(value, 8)
+                        .ok()
+                        .filter(|mode| *mode <= 0o7777)
+                        .ok_or(ParseOptionsError::InvalidValue("mode"))?;
+                }
+                "uid" => {
+                    let value = value.ok_or(ParseOptionsError::MissingValue("uid"))?;
+                    result.uid = value
+                        .parse()
+                        .map_err(|_| ParseOptionsError::InvalidValue("uid"))?;
+                }
+                "gid" => {
+                    let value = value.ok_or(ParseOptionsError::MissingValue("gid"))?;
+                    result.gid = value
+                        .parse()
+                        .map_err(|_| ParseOptionsError::InvalidValue("gid"))?;
+                }
+                _ => return Err(ParseOptionsError::UnknownOption(option.to_string())),
+            }
+        }
+        Ok(result)
+    }
+}
+
+fn parse_size(value: &str) -> Option<usize> {
+    let (number, factor) = match value.as_bytes().last()? {
+        b'k' | b'K' => (&value[..value.len() - 1], 1 << 10),
+        b'm' | b'M' => (&value[..value.len() - 1], 1 << 20),
+        b'g' | b'G' => (&value[..value.len() - 1], 1 << 30),
+        _ => (value, 1),
+    };
+    number.parse::<usize>().ok()?.checked_mul(factor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ParseOptionsError, TmpFsOptions};
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Ok(TmpFsOptions::default()), "".parse());
+        assert_eq!(
+            Ok(TmpFsOptions {
+                size: Some(16 << 20),
+                mode: 0o755,
+                uid: 1000,
+                gid: 100,
+            }),
+            "size=16M,mode=755,uid=1000,gid=100".parse()
+        );
+        assert_eq!(
+            Ok(Some(8192)),
+            "size=8192".parse::<TmpFsOptions>().map(|o| o.size)
+        );
+        assert_eq!(
+            Ok(Some(2 << 10)),
+            "size=2k".parse::<TmpFsOptions>().map(|o| o.size)
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Err(ParseOptionsError::InvalidValue("size")),
+            "size=12x".parse::<TmpFsOptions>()
+        );
+        assert_eq!(
+            Err(ParseOptionsError::InvalidValue("mode")),
+            "mode=10000".parse::<TmpFsOptions>()
+        );
+        assert_eq!(
+            Err(ParseOptionsError::MissingValue("uid")),
+            "uid".parse::<TmpFsOptions>()
+        );
+        assert_eq!(
+            Err(ParseOptionsError::UnknownOption("nr_inodes=4".into())),
+            "nr_inodes=4".parse::<TmpFsOptions>()
+        );
+    }
+}
@@ -0,0 +1,81 @@
+/// A fixed-size queue for bytes that a device received but the line
+/// discipline hasn't processed yet. It doesn't allocate, so it can be filled
+/// in an interrupt handler.
+#[derive(Debug)]
+pub struct InputBuffer<const N: usize> {
+    buf: [u8; N],
+    start: usize,
+    len: usize,
+}
+
+impl<const N: usize> Default for InputBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> InputBuffer<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Appends a byte. Returns `false` and drops the byte if the buffer is
+    /// full.
+    pub fn push(&mut self, byte: u8) -> bool {
+        if self.len == N {
+            return false;
+        }
+        self.buf[(self.start + self.len) % N] = byte;
+        self.len += 1;
+        true
+    }
+
+    pub fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.buf[self.start];
+        self.start = (self.start + 1) % N;
+        self.len -= 1;
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_pop() {
+        let mut buffer = InputBuffer::<3>::new();
+        assert_eq!(None, buffer.pop());
+
+        assert!(buffer.push(1));
+        assert!(buffer.push(2));
+        assert_eq!(Some(1), buffer.pop());
+        assert!(buffer.push(3));
+        assert!(buffer.push(4));
+        assert!(!buffer.push(5), "the buffer is full");
+        assert_eq!(3, buffer.len());
+
+        assert_eq!(Some(2), buffer.pop());
+        assert_eq!(Some(3), buffer.pop());
+        assert_eq!(Some(4), buffer.pop());
+        assert!(buffer.is_empty());
+    }
+}
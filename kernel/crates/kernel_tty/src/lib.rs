@@ -0,0 +1,12 @@
+//! The hardware independent part of terminals. A [`LineDiscipline`] sits
+//! between a character device and the processes that read from and write to
+//! it. It edits lines, echoes input and turns control characters into
+//! signals, depending on the [`Termios`](kernel_abi::Termios) attributes.
+#![no_std]
+extern crate alloc;
+
+mod input_buffer;
+mod line_discipline;
+
+pub use input_buffer::*;
+pub use line_discipline::*;
@@ -0,0 +1,426 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::mem::take;
+
+use kernel_abi::{
+    ECHO, ECHOCTL, ECHOE, ECHOK, ECHOKE, ECHONL, ICANON, ICRNL, IGNCR, INLCR, ISIG, NOFLSH, ONLCR,
+    OPOST, SIGINT, SIGQUIT, SIGTSTP, TcFlag, Termios, VEOF, VEOL, VERASE, VINTR, VKILL, VMIN,
+    VQUIT, VSUSP,
+};
+
+/// The maximum length of a line in canonical mode. Further input is dropped
+/// until the line is finished.
+pub const MAX_CANON: usize = 4095;
+
+/// A signal that was generated by a control character and should be sent
+/// to the foreground process group of the terminal.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Signal {
+    /// `VINTR`, usually ^C.
+    Interrupt,
+    /// `VQUIT`, usually ^\.
+    Quit,
+    /// `VSUSP`, usually ^Z.
+    Suspend,
+}
+
+impl Signal {
+    #[must_use]
+    pub fn number(self) -> c_int {
+        match self {
+            Signal::Interrupt => SIGINT,
+            Signal::Quit => SIGQUIT,
+            Signal::Suspend => SIGTSTP,
+        }
+    }
+}
+
+/// Processes the input and output of a terminal according to its
+/// [`Termios`].
+///
+/// In canonical mode, input is collected into lines that only become
+/// readable once they are terminated by a newline, `VEOL` or `VEOF`. `VERASE`
+/// and `VKILL` edit the current line. In non-canonical mode, every byte is
+/// readable as soon as it was received.
+#[derive(Debug, Default)]
+pub struct LineDiscipline {
+    termios: Termios,
+    /// The line that is currently being edited in canonical mode.
+    line: Vec<u8>,
+    /// The input that can be read. In canonical mode, every element is one
+    /// line, and an empty line marks the end of file. In non-canonical mode,
+    /// input is appended to the last element.
+    ready: VecDeque<Vec<u8>>,
+}
+
+impl LineDiscipline {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn termios(&self) -> &Termios {
+        &self.termios
+    }
+
+    /// Replaces the terminal attributes. When leaving canonical mode, the
+    /// line that is being edited becomes readable.
+    pub fn set_termios(&mut self, termios: Termios) {
+        let was_canonical = self.is_canonical();
+        self.termios = termios;
+        if was_canonical && !self.is_canonical() && !self.line.is_empty() {
+            let line = take(&mut self.line);
+            self.push_raw(&line);
+        }
+    }
+
+    #[must_use]
+    pub fn is_canonical(&self) -> bool {
+        self.lflag(ICANON)
+    }
+
+    /// Whether a call to [`Self::read`] would return data or the end of file.
+    #[must_use]
+    pub fn has_input(&self) -> bool {
+        !self.ready.is_empty()
+    }
+
+    /// Discards all input that has not been read yet.
+    pub fn flush_input(&mut self) {
+        self.line.clear();
+        self.ready.clear();
+    }
+
+    /// Handles a byte that was received by the device. Bytes that should be
+    /// echoed are appended to `echo`, already processed like output.
+    pub fn receive(&mut self, byte: u8, echo: &mut Vec<u8>) -> Option<Signal> {
+        let byte = match byte {
+            b'\r' if self.iflag(IGNCR) => return None,
+            b'\r' if self.iflag(ICRNL) => b'\n',
+            b'\n' if self.iflag(INLCR) => b'\r',
+            byte => byte,
+        };
+
+        if self.lflag(ISIG) {
+            let signal = if self.is_control_char(VINTR, byte) {
+                Some(Signal::Interrupt)
+            } else if self.is_control_char(VQUIT, byte) {
+                Some(Signal::Quit)
+            } else if self.is_control_char(VSUSP, byte) {
+                Some(Signal::Suspend)
+            } else {
+                None
+            };
+            if let Some(signal) = signal {
+                if !self.lflag(NOFLSH) {
+                    self.flush_input();
+                }
+                if self.lflag(ECHO) {
+                    self.echo(byte, echo);
+                }
+                return Some(signal);
+            }
+        }
+
+        if self.is_canonical() {
+            self.receive_canonical(byte, echo);
+        } else {
+            self.push_raw(&[byte]);
+            if self.lflag(ECHO) {
+                self.echo(byte, echo);
+            }
+        }
+        None
+    }
+
+    fn receive_canonical(&mut self, byte: u8, echo: &mut Vec<u8>) {
+        if self.is_control_char(VERASE, byte) {
+            if let Some(erased) = self.line.pop()
+                && self.lflag(ECHO)
+            {
+                if self.lflag(ECHOE) {
+                    self.echo_erase(erased, echo);
+                } else {
+                    self.echo(byte, echo);
+                }
+            }
+        } else if self.is_control_char(VKILL, byte) {
+            let line = take(&mut self.line);
+            if self.lflag(ECHO) {
+                if self.lflag(ECHOKE) {
+                    for &erased in line.iter().rev() {
+                        self.echo_erase(erased, echo);
+                    }
+                } else {
+                    self.echo(byte, echo);
+                    if self.lflag(ECHOK) {
+                        self.echo(b'\n', echo);
+                    }
+                }
+            }
+        } else if self.is_control_char(VEOF, byte) {
+            // the line is readable without a terminator, and an empty line
+            // is read as the end of file
+            let line = take(&mut self.line);
+            self.ready.push_back(line);
+        } else if byte == b'\n' || self.is_control_char(VEOL, byte) {
+            if self.lflag(ECHO) || (byte == b'\n' && self.lflag(ECHONL)) {
+                self.echo(byte, echo);
+            }
+            let mut line = take(&mut self.line);
+            line.push(byte);
+            self.ready.push_back(line);
+        } else if self.line.len() < MAX_CANON {
+            self.line.push(byte);
+            if self.lflag(ECHO) {
+                self.echo(byte, echo);
+            }
+        }
+    }
+
+    /// Reads input into `buf`. In canonical mode, at most one line is read.
+    ///
+    /// Returns `None` if there is no input yet and the reader should wait,
+    /// and `Some(0)` at the end of file.
+    pub fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        if self.is_canonical() {
+            let line = self.ready.front_mut()?;
+            let n = buf.len().min(line.len());
+            buf[..n].copy_from_slice(&line[..n]);
+            if n == line.len() {
+                self.ready.pop_front();
+            } else {
+                line.drain(..n);
+            }
+            return Some(n);
+        }
+
+        if self.ready.is_empty() {
+            // with VMIN == 0, a read doesn't wait for input
+            return (self.termios.c_cc[VMIN] == 0).then_some(0);
+        }
+        let mut n = 0;
+        while n < buf.len()
+            && let Some(chunk) = self.ready.front_mut()
+        {
+            let count = (buf.len() - n).min(chunk.len());
+            buf[n..n + count].copy_from_slice(&chunk[..count]);
+            chunk.drain(..count);
+            if chunk.is_empty() {
+                self.ready.pop_front();
+            }
+            n += count;
+        }
+        Some(n)
+    }
+
+    /// Appends `buf` to `out`, translating newlines if the output flags
+    /// request it.
+    pub fn output(&self, buf: &[u8], out: &mut Vec<u8>) {
+        if self.oflag(OPOST) && self.oflag(ONLCR) {
+            for &byte in buf {
+                if byte == b'\n' {
+                    out.extend_from_slice(b"\r\n");
+                } else {
+                    out.push(byte);
+                }
+            }
+        } else {
+            out.extend_from_slice(buf);
+        }
+    }
+
+    fn push_raw(&mut self, bytes: &[u8]) {
+        match self.ready.back_mut() {
+            Some(last) if !last.is_empty() => last.extend_from_slice(bytes),
+            _ => self.ready.push_back(bytes.to_vec()),
+        }
+    }
+
+    /// Echoes a byte, showing control characters as `^X` if `ECHOCTL` is
+    /// set.
+    fn echo(&self, byte: u8, echo: &mut Vec<u8>) {
+        if self.echoes_as_caret(byte) {
+            echo.extend_from_slice(&[b'^', byte ^ 0x40]);
+        } else {
+            self.output(&[byte], echo);
+        }
+    }
+
+    /// Removes an erased byte from the screen.
+    fn echo_erase(&self, erased: u8, echo: &mut Vec<u8>) {
+        let width = if self.echoes_as_caret(erased) { 2 } else { 1 };
+        for _ in 0..width {
+            echo.extend_from_slice(b"\x08 \x08");
+        }
+    }
+
+    fn echoes_as_caret(&self, byte: u8) -> bool {
+        self.lflag(ECHOCTL) && (byte < 0x20 || byte == 0x7f) && byte != b'\n' && byte != b'\t'
+    }
+
+    /// Whether `byte` is the control character at `index`. A control
+    /// character of 0 is disabled.
+    fn is_control_char(&self, index: usize, byte: u8) -> bool {
+        let c = self.termios.c_cc[index];
+        c != 0 && c == byte
+    }
+
+    fn iflag(&self, flag: TcFlag) -> bool {
+        self.termios.c_iflag & flag != 0
+    }
+
+    fn oflag(&self, flag: TcFlag) -> bool {
+        self.termios.c_oflag & flag != 0
+    }
+
+    fn lflag(&self, flag: TcFlag) -> bool {
+        self.termios.c_lflag & flag != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    fn receive_all(ldisc: &mut LineDiscipline, input: &[u8]) -> (Vec<u8>, Vec<Signal>) {
+        let mut echo = Vec::new();
+        let signals = input
+            .iter()
+            .filter_map(|&byte| ldisc.receive(byte, &mut echo))
+            .collect();
+        (echo, signals)
+    }
+
+    fn raw_termios() -> Termios {
+        let mut termios = Termios::default();
+        termios.c_lflag &= !(ICANON | ECHO);
+        termios
+    }
+
+    #[test]
+    fn test_canonical_line() {
+        let mut ldisc = LineDiscipline::new();
+        let (echo, signals) = receive_all(&mut ldisc, b"ls -l");
+        assert_eq!(b"ls -l", echo.as_slice());
+        assert!(signals.is_empty());
+
+        let mut buf = [0; 16];
+        assert_eq!(None, ldisc.read(&mut buf), "the line is not finished yet");
+
+        let (echo, _) = receive_all(&mut ldisc, b"\r");
+        assert_eq!(b"\r\n", echo.as_slice());
+        assert_eq!(Some(6), ldisc.read(&mut buf));
+        assert_eq!(b"ls -l\n", &buf[..6]);
+        assert_eq!(None, ldisc.read(&mut buf));
+    }
+
+    #[test]
+    fn test_canonical_reads_one_line_at_a_time() {
+        let mut ldisc = LineDiscipline::new();
+        receive_all(&mut ldisc, b"first\nsecond\n");
+
+        let mut buf = [0; 3];
+        assert_eq!(Some(3), ldisc.read(&mut buf));
+        assert_eq!(b"fir", &buf);
+        let mut buf = [0; 16];
+        assert_eq!(Some(3), ldisc.read(&mut buf));
+        assert_eq!(b"st\n", &buf[..3]);
+        assert_eq!(Some(7), ldisc.read(&mut buf));
+        assert_eq!(b"second\n", &buf[..7]);
+    }
+
+    #[test]
+    fn test_erase_and_kill() {
+        let mut ldisc = LineDiscipline::new();
+        let (echo, _) = receive_all(&mut ldisc, b"cay\x7ft");
+        assert_eq!(b"cay\x08 \x08t", echo.as_slice());
+
+        // a kill erases the whole line on the screen
+        let (echo, _) = receive_all(&mut ldisc, b"\x15");
+        assert_eq!(b"\x08 \x08".repeat(3), echo);
+
+        receive_all(&mut ldisc, b"cat\n");
+        let mut buf = [0; 16];
+        assert_eq!(Some(4), ldisc.read(&mut buf));
+        assert_eq!(b"cat\n", &buf[..4]);
+
+        // erasing at the start of a line does nothing
+        let (echo, _) = receive_all(&mut ldisc, b"\x7f");
+        assert!(echo.is_empty());
+    }
+
+    #[test]
+    fn test_end_of_file() {
+        let mut ldisc = LineDiscipline::new();
+        receive_all(&mut ldisc, b"abc\x04\x04");
+
+        let mut buf = [0; 16];
+        assert_eq!(Some(3), ldisc.read(&mut buf), "^D finishes the line");
+        assert_eq!(b"abc", &buf[..3]);
+        assert_eq!(Some(0), ldisc.read(&mut buf), "^D on an empty line is EOF");
+        assert_eq!(None, ldisc.read(&mut buf));
+    }
+
+    #[test]
+    fn test_signals() {
+        let mut ldisc = LineDiscipline::new();
+        let (echo, signals) = receive_all(&mut ldisc, b"sleep\x03");
+        assert_eq!(b"sleep^C", echo.as_slice());
+        assert_eq!(vec![Signal::Interrupt], signals);
+        assert!(!ldisc.has_input(), "a signal discards pending input");
+
+        let (_, signals) = receive_all(&mut ldisc, b"\x1c\x1a");
+        assert_eq!(vec![Signal::Quit, Signal::Suspend], signals);
+
+        let mut termios = Termios::default();
+        termios.c_lflag &= !ISIG;
+        ldisc.set_termios(termios);
+        let (_, signals) = receive_all(&mut ldisc, b"\x03\n");
+        assert!(signals.is_empty());
+        let mut buf = [0; 16];
+        assert_eq!(Some(2), ldisc.read(&mut buf));
+        assert_eq!(b"\x03\n", &buf[..2]);
+    }
+
+    #[test]
+    fn test_raw_mode() {
+        let mut ldisc = LineDiscipline::new();
+        receive_all(&mut ldisc, b"ab");
+        ldisc.set_termios(raw_termios());
+        assert!(ldisc.has_input(), "the partial line becomes readable");
+
+        let (echo, _) = receive_all(&mut ldisc, b"c\x7f\r");
+        assert!(echo.is_empty());
+
+        let mut buf = [0; 16];
+        assert_eq!(Some(5), ldisc.read(&mut buf));
+        assert_eq!(b"abc\x7f\n", &buf[..5]);
+        assert_eq!(None, ldisc.read(&mut buf), "VMIN is 1");
+
+        let mut termios = raw_termios();
+        termios.c_cc[VMIN] = 0;
+        ldisc.set_termios(termios);
+        assert_eq!(Some(0), ldisc.read(&mut buf));
+    }
+
+    #[test]
+    fn test_output() {
+        let mut ldisc = LineDiscipline::new();
+        let mut out = Vec::new();
+        ldisc.output(b"a\nb", &mut out);
+        assert_eq!(b"a\r\nb", out.as_slice());
+
+        let mut termios = Termios::default();
+        termios.c_oflag &= !OPOST;
+        ldisc.set_termios(termios);
+        out.clear();
+        ldisc.output(b"a\nb", &mut out);
+        assert_eq!(b"a\nb", out.as_slice());
+    }
+}
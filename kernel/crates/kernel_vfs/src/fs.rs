@@ -1,5 +1,12 @@
-use crate::path::AbsolutePath;
-use crate::{CloseError, OpenError, ReadError, Stat, StatError, WriteError};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::task::Waker;
+
+use crate::path::{AbsolutePath, OwnedPath, Path};
+use crate::{
+    CloseError, CreateError, FileType, IoctlError, MmapError, OpenError, ReadDirError, ReadError,
+    ReadLinkError, Readiness, RemoveError, SetAttrError, Stat, StatError, WriteError,
+};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct FsHandle(u64);
@@ -10,10 +17,18 @@ impl From<u64> for FsHandle {
     }
 }
 
+/// An entry of a directory, as returned by [`FileSystem::read_dir`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DirEntry {
+    pub name: String,
+    pub file_type: FileType,
+}
+
 pub trait FileSystem: Send + Sync {
     /// # Errors
-    /// Returns an error if the path does not point to a file, or if there
-    /// was an underlying error during opening (such as a hardware error).
+    /// Returns an error if the path does not point to a file or directory,
+    /// or if there was an underlying error during opening (such as a
+    /// hardware error).
     fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError>;
 
     /// # Errors
@@ -32,6 +47,9 @@ pub trait FileSystem: Send + Sync {
     /// # Errors
     /// Returns [`ReadError::EndOfFile`] if the end of the file is reached.
     ///
+    /// Returns [`ReadError::WouldBlock`] if the file is a device that has no
+    /// data yet. The caller may wait and try again.
+    ///
     /// Returns an error if the handle is invalid or already closed,
     /// or if there was an underlying error during reading (such as
     /// a hardware error).
@@ -41,4 +59,151 @@ pub trait FileSystem: Send + Sync {
     fn write(&mut self, handle: FsHandle, buf: &[u8], offset: usize) -> Result<usize, WriteError>;
 
     fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError>;
+
+    /// Returns the entries of the directory at the given `handle`,
+    /// not including `.` and `..`.
+    ///
+    /// # Errors
+    /// Returns [`ReadDirError::NotADirectory`] if the handle doesn't
+    /// refer to a directory.
+    fn read_dir(&mut self, handle: FsHandle) -> Result<Vec<DirEntry>, ReadDirError>;
+
+    // The following operations are optional, read-only file systems don't
+    // need to implement them. Paths are never resolved through symbolic
+    // links.
+
+    /// Creates an empty regular file with the permission bits `mode`.
+    ///
+    /// # Errors
+    /// Returns [`CreateError::AlreadyExists`] if `path` exists, or
+    /// [`CreateError::NotFound`] if its parent directory doesn't.
+    fn create(&mut self, path: &AbsolutePath, mode: u32) -> Result<(), CreateError> {
+        let _ = (path, mode);
+        Err(CreateError::Unsupported)
+    }
+
+    /// Creates an empty directory with the permission bits `mode`.
+    ///
+    /// # Errors
+    /// See [`FileSystem::create`].
+    fn mkdir(&mut self, path: &AbsolutePath, mode: u32) -> Result<(), CreateError> {
+        let _ = (path, mode);
+        Err(CreateError::Unsupported)
+    }
+
+    /// Creates a symbolic link at `path` that points to `target`.
+    ///
+    /// # Errors
+    /// See [`FileSystem::create`].
+    fn symlink(&mut self, target: &Path, path: &AbsolutePath) -> Result<(), CreateError> {
+        let _ = (target, path);
+        Err(CreateError::Unsupported)
+    }
+
+    /// Creates a hard link at `path` to the file at `existing`.
+    ///
+    /// # Errors
+    /// Returns [`CreateError::IsADirectory`] if `existing` is a directory,
+    /// otherwise see [`FileSystem::create`].
+    fn link(&mut self, existing: &AbsolutePath, path: &AbsolutePath) -> Result<(), CreateError> {
+        let _ = (existing, path);
+        Err(CreateError::Unsupported)
+    }
+
+    /// Removes the directory entry at `path`, which must not be a directory.
+    /// The file itself is removed once it has no links left and is no
+    /// longer open.
+    ///
+    /// # Errors
+    /// Returns [`RemoveError::IsADirectory`] if `path` is a directory.
+    fn unlink(&mut self, path: &AbsolutePath) -> Result<(), RemoveError> {
+        let _ = path;
+        Err(RemoveError::Unsupported)
+    }
+
+    /// Removes the empty directory at `path`.
+    ///
+    /// # Errors
+    /// Returns [`RemoveError::NotEmpty`] if the directory has entries.
+    fn rmdir(&mut self, path: &AbsolutePath) -> Result<(), RemoveError> {
+        let _ = path;
+        Err(RemoveError::Unsupported)
+    }
+
+    /// Returns the target of the symbolic link at `path`.
+    ///
+    /// # Errors
+    /// Returns [`ReadLinkError::NotASymlink`] if `path` is not a symbolic
+    /// link.
+    fn readlink(&mut self, path: &AbsolutePath) -> Result<OwnedPath, ReadLinkError> {
+        let _ = path;
+        Err(ReadLinkError::Unsupported)
+    }
+
+    /// Changes the permission bits of the file at the given `handle`.
+    ///
+    /// # Errors
+    /// Returns an error if the handle is invalid.
+    fn set_mode(&mut self, handle: FsHandle, mode: u32) -> Result<(), SetAttrError> {
+        let _ = (handle, mode);
+        Err(SetAttrError::Unsupported)
+    }
+
+    /// Changes the owner and group of the file at the given `handle`.
+    ///
+    /// # Errors
+    /// Returns an error if the handle is invalid.
+    fn set_owner(&mut self, handle: FsHandle, uid: u32, gid: u32) -> Result<(), SetAttrError> {
+        let _ = (handle, uid, gid);
+        Err(SetAttrError::Unsupported)
+    }
+
+    /// Sets the length of the file at the given `handle`, filling it with
+    /// zeros if it grows.
+    ///
+    /// # Errors
+    /// Returns [`WriteError::NoSpace`] if the file system is full.
+    fn truncate(&mut self, handle: FsHandle, len: usize) -> Result<(), WriteError> {
+        let _ = (handle, len);
+        Err(WriteError::Unsupported)
+    }
+
+    /// Performs the device specific operation `request` on the file at the
+    /// given `handle`. The meaning of `arg` depends on the request.
+    ///
+    /// # Errors
+    /// Returns [`IoctlError::Unsupported`] if the file doesn't support the
+    /// request.
+    fn ioctl(&mut self, handle: FsHandle, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        let _ = (handle, request, arg);
+        Err(IoctlError::Unsupported)
+    }
+
+    /// Returns the physical address of the `len` bytes at `offset` of the
+    /// file at the given `handle`, so that they can be mapped into an
+    /// address space. Only files that are backed by contiguous device
+    /// memory, like framebuffers, support this.
+    ///
+    /// # Errors
+    /// Returns [`MmapError::Unsupported`] if the file can't be mapped, and
+    /// [`MmapError::InvalidRange`] if the range exceeds the device memory.
+    fn mmap(&mut self, handle: FsHandle, offset: usize, len: usize) -> Result<u64, MmapError> {
+        let _ = (handle, offset, len);
+        Err(MmapError::Unsupported)
+    }
+
+    /// Returns whether the file at the given `handle` can be read or written
+    /// without blocking. Only devices block, so this is always
+    /// [`Readiness::READY`] unless the file system has devices.
+    fn poll(&mut self, handle: FsHandle) -> Readiness {
+        let _ = handle;
+        Readiness::READY
+    }
+
+    /// Wakes `waker` the next time that the result of
+    /// [`poll`](Self::poll) may change for the file at the given `handle`.
+    /// Files that are always ready never wake it.
+    fn register_waker(&mut self, handle: FsHandle, waker: &Waker) {
+        let _ = (handle, waker);
+    }
 }
@@ -1,4 +1,5 @@
 use core::borrow::Borrow;
+use core::fmt::{Display, Formatter};
 use core::ops::{Deref, DerefMut};
 
 use crate::path::{AbsolutePath, OwnedPath, PathNotAbsoluteError};
@@ -37,6 +38,12 @@ impl TryFrom<&str> for AbsoluteOwnedPath {
     }
 }
 
+impl Display for AbsoluteOwnedPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
 impl Deref for AbsoluteOwnedPath {
     type Target = OwnedPath;
 
@@ -4,12 +4,22 @@ use thiserror::Error;
 pub enum MountError {
     #[error("the mount point is already used by another mount")]
     AlreadyMounted,
+    #[error("the mount point does not exist")]
+    MountPointNotFound,
+    #[error("not a directory")]
+    NotADirectory,
+    #[error("the mount point is not empty")]
+    NotEmpty,
+    #[error("the source of the bind mount does not exist")]
+    SourceNotFound,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
 pub enum UnmountError {
     #[error("not mounted")]
     NotMounted,
+    #[error("the file system is busy")]
+    Busy,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
@@ -49,6 +59,8 @@ pub enum ReadError {
     ReadFailed,
     #[error("file is not readable")]
     NotReadable,
+    #[error("no data is available yet")]
+    WouldBlock,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
@@ -63,6 +75,12 @@ pub enum WriteError {
     WriteFailed,
     #[error("file is not writable")]
     NotWritable,
+    #[error("read-only file system")]
+    ReadOnlyFileSystem,
+    #[error("no space left on the file system")]
+    NoSpace,
+    #[error("not supported by the file system")]
+    Unsupported,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
@@ -74,3 +92,113 @@ pub enum StatError {
         FsError,
     ),
 }
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum ReadDirError {
+    #[error("{0}")]
+    FsError(
+        #[from]
+        #[source]
+        FsError,
+    ),
+    #[error("not a directory")]
+    NotADirectory,
+    #[error("read failed")]
+    ReadFailed,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum CreateError {
+    #[error("the file already exists")]
+    AlreadyExists,
+    #[error("the parent directory does not exist")]
+    NotFound,
+    #[error("not a directory")]
+    NotADirectory,
+    #[error("is a directory")]
+    IsADirectory,
+    #[error("no space left on the file system")]
+    NoSpace,
+    #[error("read-only file system")]
+    ReadOnlyFileSystem,
+    #[error("the paths are on different file systems")]
+    CrossDevice,
+    #[error("not supported by the file system")]
+    Unsupported,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum RemoveError {
+    #[error("not found")]
+    NotFound,
+    #[error("not a directory")]
+    NotADirectory,
+    #[error("is a directory")]
+    IsADirectory,
+    #[error("the directory is not empty")]
+    NotEmpty,
+    #[error("the file is a mount point")]
+    Busy,
+    #[error("read-only file system")]
+    ReadOnlyFileSystem,
+    #[error("not supported by the file system")]
+    Unsupported,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum ReadLinkError {
+    #[error("not found")]
+    NotFound,
+    #[error("not a symbolic link")]
+    NotASymlink,
+    #[error("not supported by the file system")]
+    Unsupported,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum SetAttrError {
+    #[error("{0}")]
+    FsError(
+        #[from]
+        #[source]
+        FsError,
+    ),
+    #[error("read-only file system")]
+    ReadOnlyFileSystem,
+    #[error("not supported by the file system")]
+    Unsupported,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum IoctlError {
+    #[error("{0}")]
+    FsError(
+        #[from]
+        #[source]
+        FsError,
+    ),
+    #[error("inappropriate ioctl for device")]
+    Unsupported,
+    #[error("invalid argument")]
+    InvalidArgument,
+    #[error("bad address")]
+    BadAddress,
+    #[error("operation not permitted")]
+    NotPermitted,
+    #[error("the device failed")]
+    DeviceError,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum MmapError {
+    #[error("{0}")]
+    FsError(
+        #[from]
+        #[source]
+        FsError,
+    ),
+    #[error("file can't be mapped")]
+    Unsupported,
+    #[error("range is outside of the file")]
+    InvalidRange,
+}
@@ -1,26 +1,99 @@
 use alloc::borrow::ToOwned;
 use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 pub use error::*;
 use spin::RwLock;
 
 use crate::fs::FileSystem;
 use crate::node::VfsNode;
-use crate::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
+use crate::path::{AbsoluteOwnedPath, AbsolutePath, OwnedPath, Path, ROOT};
 
 mod error;
 pub mod node;
+mod poll;
+pub use poll::*;
 mod stat;
 pub use stat::*;
 
 #[cfg(test)]
 pub mod testing;
 
-type Fs = Arc<RwLock<dyn FileSystem>>;
+pub type SharedFileSystem = Arc<RwLock<dyn FileSystem>>;
+
+/// Options that apply to a single mount of a file system.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct MountOptions {
+    /// The device or other source that the file system was mounted from.
+    /// This is purely informational.
+    pub source: String,
+    /// The name of the file system type. This is purely informational.
+    pub fs_type: String,
+    /// Whether writes through this mount are refused.
+    pub read_only: bool,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum UnmountMode {
+    /// Refuse to unmount a file system that still has open files or other
+    /// file systems mounted below it.
+    Normal,
+    /// Detach the mount and all mounts below it immediately. Files that
+    /// are still open remain usable, the file system is released after the
+    /// last one is closed.
+    Detach,
+}
+
+/// A mount as listed by [`Vfs::mounts`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MountInfo {
+    pub mount_point: AbsoluteOwnedPath,
+    /// The directory of the file system that is visible at the mount point,
+    /// `/` unless this is a bind mount.
+    pub root: AbsoluteOwnedPath,
+    pub options: MountOptions,
+}
+
+struct Mount {
+    fs: SharedFileSystem,
+    root: AbsoluteOwnedPath,
+    options: MountOptions,
+    /// Every node opened through this mount holds a clone of this, so the
+    /// mount is busy as long as there is more than one reference.
+    usage: Arc<()>,
+}
+
+impl Mount {
+    fn new(fs: SharedFileSystem, root: AbsoluteOwnedPath, options: MountOptions) -> Self {
+        Self {
+            fs,
+            root,
+            options,
+            usage: Arc::new(()),
+        }
+    }
+
+    fn is_busy(&self) -> bool {
+        Arc::strong_count(&self.usage) > 1
+    }
+
+    /// Translates a path below `mount_point` into the corresponding path
+    /// within the mounted file system.
+    fn fs_path(&self, mount_point: &AbsolutePath, path: &AbsolutePath) -> AbsoluteOwnedPath {
+        let mut fs_path = self.root.clone();
+        for component in path.filenames().skip(mount_point.filenames().count()) {
+            fs_path.push(component);
+        }
+        fs_path
+    }
+}
 
 pub struct Vfs {
-    file_systems: BTreeMap<AbsoluteOwnedPath, Fs>, // TODO: maybe a trie would be better here?
+    mounts: BTreeMap<AbsoluteOwnedPath, Mount>, // TODO: maybe a trie would be better here?
+    /// Lazily unmounted file systems that still have open files.
+    detached: Vec<Mount>,
 }
 
 impl Default for Vfs {
@@ -33,48 +106,151 @@ impl Vfs {
     #[must_use]
     pub const fn new() -> Self {
         Self {
-            file_systems: BTreeMap::new(),
+            mounts: BTreeMap::new(),
+            detached: Vec::new(),
         }
     }
 
-    /// Mounts a file system at the given mount point.
+    /// Mounts a file system at the given mount point with default options.
     /// The mount point must point to an empty directory.
     ///
     /// # Errors
-    /// This function returns an error if the mount point is already mounted,
-    /// not an empty directory or if another error occurs during mounting.
+    /// See [`Vfs::mount_shared`].
     pub fn mount<P, F>(&mut self, mount_point: P, fs: F) -> Result<(), MountError>
     where
         P: AsRef<AbsolutePath>,
         F: FileSystem + 'static,
+    {
+        self.mount_shared(
+            mount_point,
+            Arc::new(RwLock::new(fs)),
+            MountOptions::default(),
+        )
+    }
+
+    /// Mounts a file system at the given mount point. Unless the mount
+    /// point is `/`, it must be an existing, empty directory.
+    ///
+    /// # Errors
+    /// This function returns an error if the mount point is already mounted,
+    /// does not exist, or is not an empty directory.
+    pub fn mount_shared<P>(
+        &mut self,
+        mount_point: P,
+        fs: SharedFileSystem,
+        options: MountOptions,
+    ) -> Result<(), MountError>
+    where
+        P: AsRef<AbsolutePath>,
     {
         let mount_point = mount_point.as_ref();
-        let mount_point = mount_point.to_owned();
-        if self.file_systems.contains_key(&mount_point) {
-            return Err(MountError::AlreadyMounted);
+        self.check_mount_point(mount_point)?;
+
+        self.prune_detached();
+        self.mounts.insert(
+            mount_point.to_owned(),
+            Mount::new(fs, ROOT.to_owned(), options),
+        );
+        Ok(())
+    }
+
+    /// Makes the directory `source` additionally visible at `target`. The
+    /// bind mount shares the file system with the mount that contains
+    /// `source`, and is read-only if that mount is read-only or `read_only`
+    /// is set.
+    ///
+    /// # Errors
+    /// This function returns an error if `source` is not a directory, or if
+    /// `target` is not suitable as a mount point (see [`Vfs::mount_shared`]).
+    pub fn bind<S, T>(&mut self, source: S, target: T, read_only: bool) -> Result<(), MountError>
+    where
+        S: AsRef<AbsolutePath>,
+        T: AsRef<AbsolutePath>,
+    {
+        let source = source.as_ref();
+        let target = target.as_ref();
+
+        let node = self.open(source).map_err(|_| MountError::SourceNotFound)?;
+        let mut stat = Stat::default();
+        node.stat(&mut stat)
+            .map_err(|_| MountError::SourceNotFound)?;
+        if stat.file_type != FileType::Directory {
+            return Err(MountError::NotADirectory);
         }
+        drop(node);
+
+        self.check_mount_point(target)?;
 
-        // TODO: check whether the mount_point is a directory
+        let (mount_point, mount) = self.find_mount(source).ok_or(MountError::SourceNotFound)?;
+        let bind = Mount::new(
+            mount.fs.clone(),
+            mount.fs_path(mount_point, source),
+            MountOptions {
+                source: source.to_string(),
+                fs_type: mount.options.fs_type.clone(),
+                read_only: read_only || mount.options.read_only,
+            },
+        );
 
-        self.file_systems
-            .insert(mount_point, Arc::new(RwLock::new(fs)));
+        self.prune_detached();
+        self.mounts.insert(target.to_owned(), bind);
         Ok(())
     }
 
     /// Unmounts the file system at the given mount point.
     ///
     /// # Errors
-    /// This function returns an error if the mount point is not mounted,
-    /// or if another error occurs during unmounting.
-    pub fn unmount<P>(&mut self, mount_point: P) -> Result<(), UnmountError>
+    /// This function returns an error if the mount point is not mounted, or
+    /// if the file system is busy and `mode` is [`UnmountMode::Normal`].
+    pub fn unmount<P>(&mut self, mount_point: P, mode: UnmountMode) -> Result<(), UnmountError>
     where
         P: AsRef<AbsolutePath>,
     {
-        let owned = mount_point.as_ref().to_owned();
-        self.file_systems
-            .remove(&owned)
-            .map(|_| ())
-            .ok_or(UnmountError::NotMounted)
+        let mount_point = mount_point.as_ref();
+        self.prune_detached();
+
+        let mount = self
+            .mounts
+            .get(mount_point)
+            .ok_or(UnmountError::NotMounted)?;
+        let below = self
+            .mounts
+            .keys()
+            .filter(|p| is_below(p.as_ref(), mount_point))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        match mode {
+            UnmountMode::Normal => {
+                if mount.is_busy() || !below.is_empty() {
+                    return Err(UnmountError::Busy);
+                }
+                self.mounts.remove(mount_point);
+            }
+            UnmountMode::Detach => {
+                for path in below.iter().map(AsRef::as_ref).chain([mount_point]) {
+                    if let Some(mount) = self.mounts.remove(path)
+                        && mount.is_busy()
+                    {
+                        self.detached.push(mount);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all mounts, ordered by their mount point.
+    #[must_use]
+    pub fn mounts(&self) -> Vec<MountInfo> {
+        self.mounts
+            .iter()
+            .map(|(mount_point, mount)| MountInfo {
+                mount_point: mount_point.clone(),
+                root: mount.root.clone(),
+                options: mount.options.clone(),
+            })
+            .collect()
     }
 
     /// Opens a file at the given path.
@@ -89,42 +265,250 @@ impl Vfs {
         // FIXME: reuse already open VfsNodes
 
         let path = path.as_ref();
-        let (mount_path, fs) = self.find_mount(path).ok_or(OpenError::NotFound)?;
-        let relative_path = if mount_path == ROOT {
-            path
-        } else {
-            path.strip_prefix(&***mount_path).unwrap()
-        };
-        let relative_path = unsafe { AbsolutePath::new_unchecked((&relative_path).as_ref()) };
-        let mut guard = fs.write();
-        guard
-            .open(relative_path)
-            .map(|handle| VfsNode::new(path.to_owned(), handle, Arc::downgrade(&fs)))
-    }
-
-    fn find_mount<'a>(&'a self, path: &'a AbsolutePath) -> Option<(&'a AbsolutePath, Fs)> {
-        let mut current = path;
-        if let Some(fs) = self.file_systems.get(current) {
-            return Some((path, fs.clone()));
-        }
-        while let Some(parent) = current.parent() {
-            if let Some(fs) = self.file_systems.get(parent) {
-                return Some((parent, fs.clone()));
+        let (mount_point, mount) = self.find_mount(path).ok_or(OpenError::NotFound)?;
+        let fs_path = mount.fs_path(mount_point, path);
+        let mut guard = mount.fs.write();
+        guard.open(fs_path.as_ref()).map(|handle| {
+            VfsNode::new(
+                path.to_owned(),
+                handle,
+                Arc::downgrade(&mount.fs),
+                mount.usage.clone(),
+                mount.options.read_only,
+            )
+        })
+    }
+
+    /// Creates an empty regular file, see [`FileSystem::create`].
+    ///
+    /// # Errors
+    /// Returns an error if the file already exists, its parent directory
+    /// doesn't exist, or the file system is read-only.
+    pub fn create_file<P>(&self, path: P, mode: u32) -> Result<(), CreateError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let path = path.as_ref();
+        let (mount, fs_path) = self.writable_mount_for_create(path)?;
+        mount.fs.write().create(fs_path.as_ref(), mode)
+    }
+
+    /// Creates an empty directory, see [`FileSystem::mkdir`].
+    ///
+    /// # Errors
+    /// See [`Vfs::create_file`].
+    pub fn mkdir<P>(&self, path: P, mode: u32) -> Result<(), CreateError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let path = path.as_ref();
+        let (mount, fs_path) = self.writable_mount_for_create(path)?;
+        mount.fs.write().mkdir(fs_path.as_ref(), mode)
+    }
+
+    /// Creates a symbolic link at `path` that points to `target`.
+    ///
+    /// # Errors
+    /// See [`Vfs::create_file`].
+    pub fn symlink<T, P>(&self, target: T, path: P) -> Result<(), CreateError>
+    where
+        T: AsRef<Path>,
+        P: AsRef<AbsolutePath>,
+    {
+        let path = path.as_ref();
+        let (mount, fs_path) = self.writable_mount_for_create(path)?;
+        mount.fs.write().symlink(target.as_ref(), fs_path.as_ref())
+    }
+
+    /// Creates a hard link at `path` to the file at `existing`. Both paths
+    /// must be on the same mount.
+    ///
+    /// # Errors
+    /// Returns [`CreateError::CrossDevice`] if the paths are on different
+    /// mounts, otherwise see [`Vfs::create_file`].
+    pub fn link<E, P>(&self, existing: E, path: P) -> Result<(), CreateError>
+    where
+        E: AsRef<AbsolutePath>,
+        P: AsRef<AbsolutePath>,
+    {
+        let existing = existing.as_ref();
+        let path = path.as_ref();
+        let (mount, fs_path) = self.writable_mount_for_create(path)?;
+        let (existing_mount_point, existing_mount) =
+            self.find_mount(existing).ok_or(CreateError::NotFound)?;
+        if !core::ptr::eq(mount, existing_mount) {
+            return Err(CreateError::CrossDevice);
+        }
+
+        let existing = existing_mount.fs_path(existing_mount_point, existing);
+        mount.fs.write().link(existing.as_ref(), fs_path.as_ref())
+    }
+
+    /// Removes a file that is not a directory, see [`FileSystem::unlink`].
+    ///
+    /// # Errors
+    /// Returns [`RemoveError::Busy`] if `path` is a mount point.
+    pub fn unlink<P>(&self, path: P) -> Result<(), RemoveError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let path = path.as_ref();
+        let (mount, fs_path) = self.writable_mount_for_remove(path)?;
+        mount.fs.write().unlink(fs_path.as_ref())
+    }
+
+    /// Removes an empty directory, see [`FileSystem::rmdir`].
+    ///
+    /// # Errors
+    /// Returns [`RemoveError::Busy`] if `path` is a mount point.
+    pub fn rmdir<P>(&self, path: P) -> Result<(), RemoveError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let path = path.as_ref();
+        let (mount, fs_path) = self.writable_mount_for_remove(path)?;
+        mount.fs.write().rmdir(fs_path.as_ref())
+    }
+
+    /// Returns the target of the symbolic link at `path`.
+    ///
+    /// # Errors
+    /// Returns an error if `path` doesn't exist or is not a symbolic link.
+    pub fn readlink<P>(&self, path: P) -> Result<OwnedPath, ReadLinkError>
+    where
+        P: AsRef<AbsolutePath>,
+    {
+        let path = path.as_ref();
+        let (mount_point, mount) = self.find_mount(path).ok_or(ReadLinkError::NotFound)?;
+        let fs_path = mount.fs_path(mount_point, path);
+        mount.fs.write().readlink(fs_path.as_ref())
+    }
+
+    /// Returns the mount that a new file at `path` would be created in,
+    /// together with the path within that mount's file system.
+    fn writable_mount_for_create<'a>(
+        &'a self,
+        path: &'a AbsolutePath,
+    ) -> Result<(&'a Mount, AbsoluteOwnedPath), CreateError> {
+        if self.mounts.contains_key(path) {
+            return Err(CreateError::AlreadyExists);
+        }
+        let (mount_point, mount) = self.find_mount(path).ok_or(CreateError::NotFound)?;
+        if mount.options.read_only {
+            return Err(CreateError::ReadOnlyFileSystem);
+        }
+        Ok((mount, mount.fs_path(mount_point, path)))
+    }
+
+    fn writable_mount_for_remove<'a>(
+        &'a self,
+        path: &'a AbsolutePath,
+    ) -> Result<(&'a Mount, AbsoluteOwnedPath), RemoveError> {
+        if self.mounts.contains_key(path) {
+            return Err(RemoveError::Busy);
+        }
+        let (mount_point, mount) = self.find_mount(path).ok_or(RemoveError::NotFound)?;
+        if mount.options.read_only {
+            return Err(RemoveError::ReadOnlyFileSystem);
+        }
+        Ok((mount, mount.fs_path(mount_point, path)))
+    }
+
+    /// Checks that `mount_point` is not mounted yet and is either `/` or an
+    /// existing, empty directory.
+    fn check_mount_point(&self, mount_point: &AbsolutePath) -> Result<(), MountError> {
+        if self.mounts.contains_key(mount_point) {
+            return Err(MountError::AlreadyMounted);
+        }
+        if mount_point == ROOT {
+            return Ok(());
+        }
+
+        let node = self
+            .open(mount_point)
+            .map_err(|_| MountError::MountPointNotFound)?;
+        let mut stat = Stat::default();
+        node.stat(&mut stat)
+            .map_err(|_| MountError::MountPointNotFound)?;
+        if stat.file_type != FileType::Directory {
+            return Err(MountError::NotADirectory);
+        }
+        if !node
+            .read_dir()
+            .map_err(|_| MountError::NotADirectory)?
+            .is_empty()
+        {
+            return Err(MountError::NotEmpty);
+        }
+        Ok(())
+    }
+
+    /// Drops detached mounts whose files have all been closed.
+    fn prune_detached(&mut self) {
+        self.detached.retain(Mount::is_busy);
+    }
+
+    fn find_mount<'a>(&'a self, path: &'a AbsolutePath) -> Option<(&'a AbsolutePath, &'a Mount)> {
+        let mut current = Some(path);
+        while let Some(p) = current {
+            if let Some(mount) = self.mounts.get(p) {
+                return Some((p, mount));
             }
-            current = parent;
+            current = p.parent();
         }
-        self.file_systems.get(ROOT).cloned().map(|v| (ROOT, v))
+        self.mounts.get(ROOT).map(|mount| (ROOT, mount))
     }
 }
 
+/// Whether `path` is strictly below `ancestor`, comparing whole components.
+fn is_below(path: &AbsolutePath, ancestor: &AbsolutePath) -> bool {
+    let mut path = path.filenames();
+    for component in ancestor.filenames() {
+        if path.next() != Some(component) {
+            return false;
+        }
+    }
+    path.next().is_some()
+}
+
 #[cfg(test)]
 mod tests {
+    use alloc::sync::Arc;
     use alloc::vec;
     use alloc::vec::Vec;
 
+    use spin::RwLock;
+
     use crate::path::{AbsolutePath, ROOT};
     use crate::testing::TestFs;
-    use crate::{Stat, Vfs};
+    use crate::{
+        CreateError, MountError, MountOptions, RemoveError, Stat, UnmountError, UnmountMode, Vfs,
+        WriteError,
+    };
+
+    fn path(s: &str) -> &AbsolutePath {
+        AbsolutePath::try_new(s).unwrap()
+    }
+
+    /// A vfs with a root file system that contains the empty directories
+    /// `/mnt` and `/srv`, and the file `/etc/hostname`.
+    fn test_vfs() -> Vfs {
+        let mut fs = TestFs::default();
+        fs.insert_dir(path("/mnt"));
+        fs.insert_dir(path("/srv"));
+        fs.insert_file(path("/etc/hostname"), b"muffin".to_vec(), Stat::default());
+
+        let mut vfs = Vfs::new();
+        vfs.mount(ROOT, fs).unwrap();
+        vfs
+    }
+
+    fn data_fs() -> TestFs {
+        let mut fs = TestFs::default();
+        fs.insert_file(path("/data/file.txt"), vec![1, 2, 3], Stat::default());
+        fs.insert_dir(path("/sub"));
+        fs
+    }
 
     #[test]
     fn test_read() {
@@ -172,4 +556,155 @@ mod tests {
         vfs.mount(ROOT, fs).unwrap();
         assert!(vfs.mount(ROOT, TestFs::default()).is_err());
     }
+
+    #[test]
+    fn test_mount_point_must_be_empty_directory() {
+        let mut vfs = test_vfs();
+        assert_eq!(
+            Err(MountError::MountPointNotFound),
+            vfs.mount(path("/nope"), TestFs::default())
+        );
+        assert_eq!(
+            Err(MountError::NotADirectory),
+            vfs.mount(path("/etc/hostname"), TestFs::default())
+        );
+        assert_eq!(
+            Err(MountError::NotEmpty),
+            vfs.mount(path("/etc"), TestFs::default())
+        );
+
+        vfs.mount(path("/mnt"), data_fs()).unwrap();
+        assert_eq!(
+            Err(MountError::AlreadyMounted),
+            vfs.mount(path("/mnt"), TestFs::default())
+        );
+
+        let mut buf = [0_u8; 3];
+        vfs.open(path("/mnt/data/file.txt"))
+            .unwrap()
+            .read(&mut buf, 0)
+            .unwrap();
+        assert_eq!([1, 2, 3], buf);
+    }
+
+    #[test]
+    fn test_unmount_busy() {
+        let mut vfs = test_vfs();
+        vfs.mount(path("/mnt"), data_fs()).unwrap();
+
+        let node = vfs.open(path("/mnt/data/file.txt")).unwrap();
+        assert_eq!(
+            Err(UnmountError::Busy),
+            vfs.unmount(path("/mnt"), UnmountMode::Normal)
+        );
+        // the root file system has another file system mounted below it
+        assert_eq!(
+            Err(UnmountError::Busy),
+            vfs.unmount(ROOT, UnmountMode::Normal)
+        );
+
+        drop(node);
+        vfs.unmount(path("/mnt"), UnmountMode::Normal).unwrap();
+        assert_eq!(
+            Err(UnmountError::NotMounted),
+            vfs.unmount(path("/mnt"), UnmountMode::Normal)
+        );
+        assert!(vfs.open(path("/mnt/data/file.txt")).is_err());
+    }
+
+    #[test]
+    fn test_unmount_detach() {
+        let mut vfs = test_vfs();
+        vfs.mount(path("/mnt"), data_fs()).unwrap();
+        vfs.mount(path("/mnt/sub"), TestFs::default()).unwrap();
+        vfs.mount(path("/srv"), TestFs::default()).unwrap();
+
+        let node = vfs.open(path("/mnt/data/file.txt")).unwrap();
+        vfs.unmount(path("/mnt"), UnmountMode::Detach).unwrap();
+
+        // the mounts are gone, but the open file still works
+        assert!(vfs.open(path("/mnt/data/file.txt")).is_err());
+        assert!(vfs.open(path("/mnt/sub")).is_err());
+        let mut buf = [0_u8; 3];
+        node.read(&mut buf, 0).unwrap();
+        assert_eq!([1, 2, 3], buf);
+
+        assert_eq!(1, vfs.detached.len());
+        drop(node);
+        vfs.prune_detached();
+        assert!(vfs.detached.is_empty());
+        assert_eq!(2, vfs.mounts().len());
+    }
+
+    #[test]
+    fn test_bind() {
+        let mut vfs = test_vfs();
+        vfs.mount(path("/mnt"), data_fs()).unwrap();
+
+        assert_eq!(
+            Err(MountError::SourceNotFound),
+            vfs.bind(path("/nope"), path("/srv"), false)
+        );
+        assert_eq!(
+            Err(MountError::NotADirectory),
+            vfs.bind(path("/etc/hostname"), path("/srv"), false)
+        );
+
+        vfs.bind(path("/mnt/data"), path("/srv"), true).unwrap();
+        let node = vfs.open(path("/srv/file.txt")).unwrap();
+        let mut buf = [0_u8; 3];
+        node.read(&mut buf, 0).unwrap();
+        assert_eq!([1, 2, 3], buf);
+        assert_eq!(Err(WriteError::ReadOnlyFileSystem), node.write([0], 0));
+
+        // the original mount is still writable
+        let node = vfs.open(path("/mnt/data/file.txt")).unwrap();
+        assert_eq!(Ok(1), node.write([4], 0));
+
+        let mounts = vfs.mounts();
+        let bind = mounts
+            .iter()
+            .find(|m| m.mount_point.as_str() == "/srv")
+            .unwrap();
+        assert_eq!("/data", bind.root.as_str());
+        assert_eq!("/mnt/data", bind.options.source);
+        assert!(bind.options.read_only);
+    }
+
+    #[test]
+    fn test_modify_through_mounts() {
+        let mut vfs = test_vfs();
+        vfs.mount_shared(
+            path("/mnt"),
+            Arc::new(RwLock::new(data_fs())),
+            MountOptions {
+                read_only: true,
+                ..MountOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            Err(CreateError::ReadOnlyFileSystem),
+            vfs.mkdir(path("/mnt/new"), 0o755)
+        );
+        assert_eq!(
+            Err(RemoveError::ReadOnlyFileSystem),
+            vfs.unlink(path("/mnt/data/file.txt"))
+        );
+        assert_eq!(Err(RemoveError::Busy), vfs.rmdir(path("/mnt")));
+        assert_eq!(
+            Err(CreateError::AlreadyExists),
+            vfs.create_file(path("/mnt"), 0o644)
+        );
+        assert_eq!(
+            Err(CreateError::CrossDevice),
+            vfs.link(path("/mnt/data/file.txt"), path("/etc/file.txt"))
+        );
+        // the test file system doesn't support creating files
+        assert_eq!(
+            Err(CreateError::Unsupported),
+            vfs.create_file(path("/etc/new"), 0o644)
+        );
+    }
 }
@@ -1,13 +1,18 @@
 use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
 use core::fmt::{Debug, Formatter};
 use core::ops::Deref;
+use core::task::Waker;
 
 use spin::RwLock;
 
-use crate::fs::{FileSystem, FsHandle};
-use crate::path::AbsoluteOwnedPath;
+use crate::fs::{DirEntry, FileSystem, FsHandle};
+use crate::path::{AbsoluteOwnedPath, AbsolutePath};
 use crate::vfs::stat::Stat;
-use crate::{FsError, ReadError, StatError, WriteError};
+use crate::{
+    FsError, IoctlError, MmapError, ReadDirError, ReadError, Readiness, SetAttrError, StatError,
+    WriteError,
+};
 
 #[derive(Clone)]
 pub struct VfsNode {
@@ -27,6 +32,9 @@ pub struct Inner {
     path: AbsoluteOwnedPath,
     fs_handle: FsHandle,
     fs: Weak<RwLock<dyn FileSystem>>,
+    /// Keeps the mount that this node was opened through busy.
+    _mount_usage: Arc<()>,
+    read_only: bool,
 }
 
 impl Drop for Inner {
@@ -51,16 +59,26 @@ impl VfsNode {
         path: AbsoluteOwnedPath,
         fs_handle: FsHandle,
         fs: Weak<RwLock<dyn FileSystem>>,
+        mount_usage: Arc<()>,
+        read_only: bool,
     ) -> Self {
         Self {
             inner: Arc::new(Inner {
                 path,
                 fs_handle,
                 fs,
+                _mount_usage: mount_usage,
+                read_only,
             }),
         }
     }
 
+    /// The path that the node was opened with.
+    #[must_use]
+    pub fn path(&self) -> &AbsolutePath {
+        self.inner.path.as_ref()
+    }
+
     /// Reads up to `buf.len()` bytes from the file at the given
     /// `offset` into `buf` and returns the number of bytes read.
     ///
@@ -79,10 +97,19 @@ impl VfsNode {
         guard.read(self.fs_handle, buf, offset)
     }
 
+    /// Writes `buf` to the file at the given `offset`.
+    ///
+    /// # Errors
+    /// Returns [`WriteError::ReadOnlyFileSystem`] if the node was opened
+    /// through a read-only mount.
     pub fn write<B>(&self, buf: B, offset: usize) -> Result<usize, WriteError>
     where
         B: AsRef<[u8]>,
     {
+        if self.read_only {
+            return Err(WriteError::ReadOnlyFileSystem);
+        }
+
         let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
         let buf = buf.as_ref();
 
@@ -96,6 +123,108 @@ impl VfsNode {
         let mut guard = fs.write();
         guard.stat(self.fs_handle, stat)
     }
+
+    /// Returns the entries of the directory, see [`FileSystem::read_dir`].
+    pub fn read_dir(&self) -> Result<Vec<DirEntry>, ReadDirError> {
+        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+
+        let mut guard = fs.write();
+        guard.read_dir(self.fs_handle)
+    }
+
+    /// Sets the length of the file, see [`FileSystem::truncate`].
+    ///
+    /// # Errors
+    /// Returns [`WriteError::ReadOnlyFileSystem`] if the node was opened
+    /// through a read-only mount.
+    pub fn truncate(&self, len: usize) -> Result<(), WriteError> {
+        if self.read_only {
+            return Err(WriteError::ReadOnlyFileSystem);
+        }
+
+        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+
+        let mut guard = fs.write();
+        guard.truncate(self.fs_handle, len)
+    }
+
+    /// Changes the permission bits of the file.
+    ///
+    /// # Errors
+    /// Returns [`SetAttrError::ReadOnlyFileSystem`] if the node was opened
+    /// through a read-only mount.
+    pub fn set_mode(&self, mode: u32) -> Result<(), SetAttrError> {
+        if self.read_only {
+            return Err(SetAttrError::ReadOnlyFileSystem);
+        }
+
+        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+
+        let mut guard = fs.write();
+        guard.set_mode(self.fs_handle, mode)
+    }
+
+    /// Changes the owner and group of the file.
+    ///
+    /// # Errors
+    /// Returns [`SetAttrError::ReadOnlyFileSystem`] if the node was opened
+    /// through a read-only mount.
+    pub fn set_owner(&self, uid: u32, gid: u32) -> Result<(), SetAttrError> {
+        if self.read_only {
+            return Err(SetAttrError::ReadOnlyFileSystem);
+        }
+
+        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+
+        let mut guard = fs.write();
+        guard.set_owner(self.fs_handle, uid, gid)
+    }
+
+    /// Performs a device specific operation, see [`FileSystem::ioctl`].
+    pub fn ioctl(&self, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+
+        let mut guard = fs.write();
+        guard.ioctl(self.fs_handle, request, arg)
+    }
+
+    /// Returns the physical address of device memory, see
+    /// [`FileSystem::mmap`].
+    pub fn mmap(&self, offset: usize, len: usize) -> Result<u64, MmapError> {
+        let fs = self.fs.upgrade().ok_or(FsError::FileSystemNotOpen)?;
+
+        let mut guard = fs.write();
+        guard.mmap(self.fs_handle, offset, len)
+    }
+
+    /// Returns whether the file can be read or written without blocking, see
+    /// [`FileSystem::poll`]. Operations on a node whose file system is gone
+    /// fail immediately, so it is reported as ready with an error.
+    #[must_use]
+    pub fn poll(&self) -> Readiness {
+        let Some(fs) = self.fs.upgrade() else {
+            return Readiness {
+                error: true,
+                ..Readiness::READY
+            };
+        };
+
+        let mut guard = fs.write();
+        guard.poll(self.fs_handle)
+    }
+
+    /// Wakes `waker` the next time that the result of [`poll`](Self::poll)
+    /// may change, see [`FileSystem::register_waker`].
+    pub fn register_waker(&self, waker: &Waker) {
+        if let Some(fs) = self.fs.upgrade() {
+            fs.write().register_waker(self.fs_handle, waker);
+        }
+    }
+
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,25 @@
+/// Whether a file can be read or written without blocking, see
+/// [`FileSystem::poll`](crate::fs::FileSystem::poll).
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Readiness {
+    /// A read returns data, the end of the file or an error without
+    /// blocking.
+    pub readable: bool,
+    /// A write doesn't block.
+    pub writable: bool,
+    /// The other end is gone, like the peer of a connection or the device of
+    /// a terminal. Reads return what is left and then the end of the file.
+    pub hangup: bool,
+    /// An error is pending, which the next operation returns.
+    pub error: bool,
+}
+
+impl Readiness {
+    /// Files that never block, like regular files and directories.
+    pub const READY: Self = Self {
+        readable: true,
+        writable: true,
+        hangup: false,
+        error: false,
+    };
+}
@@ -1,4 +1,38 @@
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum FileType {
+    #[default]
+    RegularFile,
+    Directory,
+    Symlink,
+    CharDevice,
+    BlockDevice,
+    Fifo,
+    Socket,
+}
+
+/// A point in time as seconds and nanoseconds since the Unix epoch.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timespec {
+    pub secs: i64,
+    pub nanos: u32,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Stat {
+    pub file_type: FileType,
+    /// The permission bits, for example `0o644`.
+    pub mode: u32,
+    /// The number of hard links to the file.
+    pub nlink: usize,
+    pub uid: u32,
+    pub gid: u32,
+    /// The inode number, which is unique within a file system.
+    pub ino: u64,
     pub size: usize,
+    /// The time of the last access.
+    pub atime: Timespec,
+    /// The time of the last modification of the content.
+    pub mtime: Timespec,
+    /// The time of the last change of the content or the metadata.
+    pub ctime: Timespec,
 }
@@ -1,35 +1,57 @@
 use alloc::borrow::ToOwned;
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
 
 use spin::RwLock;
 
-use crate::fs::{FileSystem, FsHandle};
-use crate::path::{AbsoluteOwnedPath, AbsolutePath};
-use crate::{CloseError, FsError, OpenError, ReadError, Stat, StatError, WriteError};
+use crate::fs::{DirEntry, FileSystem, FsHandle};
+use crate::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
+use crate::{
+    CloseError, FileType, FsError, OpenError, ReadDirError, ReadError, Stat, StatError, WriteError,
+};
 
+/// An in-memory file system. The root directory always exists, parent
+/// directories of inserted files are created implicitly.
 #[derive(Default)]
 pub struct TestFs {
     handle_counter: AtomicU64,
     files: BTreeMap<AbsoluteOwnedPath, RwLock<Vec<u8>>>,
+    directories: BTreeSet<AbsoluteOwnedPath>,
     stats: BTreeMap<AbsoluteOwnedPath, Stat>,
     open_files: BTreeMap<FsHandle, AbsoluteOwnedPath>,
 }
 
 impl TestFs {
     pub fn insert_file(&mut self, path: impl AsRef<AbsolutePath>, data: Vec<u8>, stat: Stat) {
-        let path = path.as_ref().to_owned();
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            self.insert_dir(parent);
+        }
+        let path = path.to_owned();
         self.files.insert(path.clone(), RwLock::new(data));
         self.stats.insert(path, stat);
     }
+
+    pub fn insert_dir(&mut self, path: impl AsRef<AbsolutePath>) {
+        let mut current = Some(path.as_ref());
+        while let Some(dir) = current {
+            self.directories.insert(dir.to_owned());
+            current = dir.parent();
+        }
+    }
+
+    fn is_dir(&self, path: &AbsolutePath) -> bool {
+        path == ROOT || self.directories.contains(path)
+    }
 }
 
 impl FileSystem for TestFs {
     fn open(&mut self, path: &AbsolutePath) -> Result<FsHandle, OpenError> {
         let owned = path.to_owned();
-        if self.files.contains_key(&owned) {
+        if self.files.contains_key(&owned) || self.is_dir(path) {
             let handle = FsHandle::from(self.handle_counter.fetch_add(1, Relaxed));
             self.open_files.insert(handle, owned.clone());
             Ok(handle)
@@ -53,8 +75,8 @@ impl FileSystem for TestFs {
     ) -> Result<usize, ReadError> {
         let path = self.open_files.get(&handle).ok_or(FsError::InvalidHandle)?;
 
-        // file can't be deleted while it's open, so if we have a handle, it must exist in `self.files`
-        let file = self.files.get(path).unwrap();
+        // file can't be deleted while it's open, so if it's not in `self.files`, it's a directory
+        let file = self.files.get(path).ok_or(ReadError::NotReadable)?;
 
         let guard = file.read();
         let data = guard.as_slice();
@@ -72,8 +94,8 @@ impl FileSystem for TestFs {
     fn write(&mut self, handle: FsHandle, buf: &[u8], offset: usize) -> Result<usize, WriteError> {
         let path = self.open_files.get(&handle).ok_or(FsError::InvalidHandle)?;
 
-        // file can't be deleted while it's open, so if we have a handle, it must exist in `self.files`
-        let file = self.files.get(path).unwrap();
+        // file can't be deleted while it's open, so if it's not in `self.files`, it's a directory
+        let file = self.files.get(path).ok_or(WriteError::NotWritable)?;
 
         let mut guard = file.write();
         let file_len = guard.len();
@@ -86,17 +108,66 @@ impl FileSystem for TestFs {
         Ok(buf.len())
     }
 
-    fn stat(&mut self, _handle: FsHandle, _stat: &mut Stat) -> Result<(), StatError> {
-        todo!()
+    fn stat(&mut self, handle: FsHandle, stat: &mut Stat) -> Result<(), StatError> {
+        let path = self.open_files.get(&handle).ok_or(FsError::InvalidHandle)?;
+        match self.files.get(path) {
+            Some(file) => {
+                *stat = self.stats.get(path).cloned().unwrap_or_default();
+                stat.size = file.read().len();
+            }
+            None => {
+                *stat = Stat {
+                    file_type: FileType::Directory,
+                    ..Stat::default()
+                };
+            }
+        }
+        Ok(())
+    }
+
+    fn read_dir(&mut self, handle: FsHandle) -> Result<Vec<DirEntry>, ReadDirError> {
+        let path: &AbsolutePath = self
+            .open_files
+            .get(&handle)
+            .ok_or(FsError::InvalidHandle)?
+            .as_ref();
+        if !self.is_dir(path) {
+            return Err(ReadDirError::NotADirectory);
+        }
+
+        let is_child = |p: &AbsoluteOwnedPath| {
+            let p: &AbsolutePath = p.as_ref();
+            p != path && p.parent().unwrap_or(ROOT) == path
+        };
+        let dirs = self
+            .directories
+            .iter()
+            .filter(|p| is_child(p))
+            .map(|p| (p, FileType::Directory));
+        let files = self
+            .files
+            .keys()
+            .filter(|p| is_child(p))
+            .map(|p| (p, FileType::RegularFile));
+        Ok(dirs
+            .chain(files)
+            .map(|(p, file_type)| DirEntry {
+                name: p.file_name().unwrap().to_string(),
+                file_type,
+            })
+            .collect())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::CloseError;
-    use crate::fs::FileSystem;
-    use crate::path::{AbsoluteOwnedPath, AbsolutePath};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    use crate::fs::{DirEntry, FileSystem};
+    use crate::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
     use crate::testing::TestFs;
+    use crate::{CloseError, FileType, ReadDirError, Stat};
 
     #[test]
     fn test_open_close() {
@@ -112,4 +183,44 @@ mod tests {
         assert!(fs.close(handle).is_ok());
         assert_eq!(Err(CloseError::NotOpen), fs.close(handle));
     }
+
+    #[test]
+    fn test_read_dir() {
+        let mut fs = TestFs::default();
+        fs.insert_file(
+            AbsolutePath::try_new("/foo/bar.txt").unwrap(),
+            Vec::new(),
+            Stat::default(),
+        );
+        fs.insert_dir(AbsolutePath::try_new("/foo/baz").unwrap());
+
+        let handle = fs.open(ROOT).unwrap();
+        assert_eq!(
+            vec![DirEntry {
+                name: "foo".into(),
+                file_type: FileType::Directory,
+            }],
+            fs.read_dir(handle).unwrap()
+        );
+
+        let handle = fs.open(AbsolutePath::try_new("/foo").unwrap()).unwrap();
+        assert_eq!(
+            vec![
+                DirEntry {
+                    name: "baz".into(),
+                    file_type: FileType::Directory,
+                },
+                DirEntry {
+                    name: "bar.txt".into(),
+                    file_type: FileType::RegularFile,
+                },
+            ],
+            fs.read_dir(handle).unwrap()
+        );
+
+        let handle = fs
+            .open(AbsolutePath::try_new("/foo/bar.txt").unwrap())
+            .unwrap();
+        assert_eq!(Err(ReadDirError::NotADirectory), fs.read_dir(handle));
+    }
 }
@@ -3,11 +3,13 @@ use core::ops::{Deref, DerefMut};
 use acpi::{InterruptModel, PlatformInfo};
 use conquer_once::spin::OnceCell;
 use spin::Mutex;
+use x2apic::ioapic::{IrqFlags, IrqMode, RedirectionTableEntry};
 use x86_64::PhysAddr;
 use x86_64::instructions::port::Port;
 use x86_64::structures::paging::{Page, PageTableFlags, PhysFrame, Size4KiB};
 
 use crate::acpi::acpi_tables;
+use crate::mcore::context::ExecutionContext;
 use crate::mem::address_space::AddressSpace;
 use crate::mem::virt::{OwnedSegment, VirtualMemoryAllocator, VirtualMemoryHigherHalf};
 
@@ -81,6 +83,45 @@ pub fn init() {
     IO_APIC.init_once(|| Mutex::new(ioapic));
 }
 
+/// Routes the ISA interrupt line `irq` to `vector` on the current CPU and
+/// unmasks it. ISA interrupts are edge triggered and active high.
+///
+/// This assumes that the ACPI tables don't override the line, which holds
+/// for the legacy devices that we use on QEMU.
+pub fn enable_isa_irq(irq: u8, vector: u8) {
+    enable_irq(irq, vector, IrqFlags::empty());
+}
+
+/// Routes the PCI interrupt line `irq` to `vector` on the current CPU and
+/// unmasks it. PCI interrupts are level triggered and active low.
+///
+/// The line is the one that the firmware wrote into the configuration space
+/// of the device, which is also the IOAPIC pin on QEMU. The ACPI routing
+/// tables are not consulted.
+pub fn enable_pci_irq(irq: u8, vector: u8) {
+    enable_irq(
+        irq,
+        vector,
+        IrqFlags::LEVEL_TRIGGERED | IrqFlags::LOW_ACTIVE,
+    );
+}
+
+fn enable_irq(irq: u8, vector: u8, flags: IrqFlags) {
+    let lapic_id = ExecutionContext::load().lapic_id();
+
+    let mut entry = RedirectionTableEntry::default();
+    entry.set_mode(IrqMode::Fixed);
+    entry.set_flags(flags);
+    entry.set_vector(vector);
+    entry.set_dest(u8::try_from(lapic_id).expect("invalid lapic id"));
+
+    let mut io_apic = io_apic().lock();
+    unsafe {
+        io_apic.set_table_entry(irq, entry);
+        io_apic.enable_irq(irq);
+    }
+}
+
 #[allow(clippy::similar_names)]
 fn disable_8259() {
     unsafe {
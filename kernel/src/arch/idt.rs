@@ -1,31 +1,38 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::alloc::Layout;
 use core::arch::asm;
 use core::arch::x86_64::{_fxrstor, _fxsave};
 use core::fmt::{Debug, Formatter};
 use core::mem::transmute;
+use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
 
 use kernel_memapi::{Guarded, Location, MemoryApi, UserAccessible};
 use log::{error, warn};
+use spin::RwLock;
 use x86_64::PrivilegeLevel;
 use x86_64::instructions::{hlt, interrupts};
 use x86_64::registers::control::Cr2;
 use x86_64::registers::debug::{Dr6, Dr7};
 use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
 
-use crate::UsizeExt;
 use crate::arch::gdt;
 use crate::mcore::context::ExecutionContext;
 use crate::mcore::mtask::process::mem::MemoryRegion;
 use crate::mcore::mtask::task::FxArea;
+use crate::mcore::mtask::wait;
 use crate::mem::memapi::LowerHalfMemoryApi;
 use crate::syscall::dispatch_syscall;
+use crate::{UsizeExt, random, tty};
 
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum InterruptIndex {
     /// 32
     Timer = 0x20,
+    /// 36, ISA IRQ 4
+    Serial = 0x24,
     /// 49
     LapicErr = 0x31,
     Syscall = 0x80,
@@ -43,6 +50,83 @@ impl InterruptIndex {
     }
 }
 
+/// The vector of the first device interrupt. Pin `n` of the IOAPIC is
+/// routed to vector `DEVICE_IRQ_BASE + n` once a driver registers a handler
+/// for it.
+pub const DEVICE_IRQ_BASE: u8 = 0x40;
+/// The number of pins of the IOAPIC.
+pub const DEVICE_IRQS: u8 = 24;
+
+const DEBUG_VECTOR: u8 = 1;
+const BREAKPOINT_VECTOR: u8 = 3;
+const DEVICE_NOT_AVAILABLE_VECTOR: u8 = 7;
+const PAGE_FAULT_VECTOR: u8 = 14;
+
+/// The number of times each interrupt vector was handled, on all CPUs.
+static INTERRUPT_COUNTS: [AtomicU64; 256] = [const { AtomicU64::new(0) }; 256];
+
+fn count_interrupt(vector: u8) {
+    INTERRUPT_COUNTS[usize::from(vector)].fetch_add(1, Relaxed);
+    random::add_interrupt_timing(vector);
+}
+
+type DeviceIrqHandler = Box<dyn Fn() + Send + Sync>;
+
+/// The handlers of device interrupts, indexed by IOAPIC pin. Lines may be
+/// shared by several devices, so every handler of a line is called.
+static DEVICE_IRQ_HANDLERS: [RwLock<Vec<DeviceIrqHandler>>; DEVICE_IRQS as usize] =
+    [const { RwLock::new(Vec::new()) }; DEVICE_IRQS as usize];
+
+/// Registers a handler for the device interrupt line `irq`. The handler runs
+/// in the interrupt handler, so it must neither allocate nor wait for locks
+/// that are held with interrupts enabled. It is also called for interrupts
+/// of other devices on the same line, and has to check whether its device
+/// raised the interrupt.
+///
+/// # Panics
+/// Panics if `irq` is not below [`DEVICE_IRQS`].
+pub fn register_device_irq_handler(irq: u8, handler: impl Fn() + Send + Sync + 'static) {
+    let handler = Box::new(handler);
+    interrupts::without_interrupts(|| {
+        DEVICE_IRQ_HANDLERS[usize::from(irq)].write().push(handler);
+    });
+}
+
+/// Returns every interrupt vector that was handled at least once, together
+/// with the number of times it was handled.
+pub fn interrupt_counts() -> impl Iterator<Item = (u8, u64)> {
+    (0..=u8::MAX)
+        .map(|vector| (vector, INTERRUPT_COUNTS[usize::from(vector)].load(Relaxed)))
+        .filter(|&(_, count)| count > 0)
+}
+
+/// Returns a human readable name of the interrupt vector, if it is handled
+/// by the kernel.
+#[must_use]
+pub fn interrupt_name(vector: u8) -> Option<&'static str> {
+    Some(match vector {
+        DEBUG_VECTOR => "debug",
+        BREAKPOINT_VECTOR => "breakpoint",
+        DEVICE_NOT_AVAILABLE_VECTOR => "device not available",
+        PAGE_FAULT_VECTOR => "page fault",
+        v if v == InterruptIndex::Timer.as_u8() => "timer",
+        v if v == InterruptIndex::Serial.as_u8() => "serial",
+        v if v == InterruptIndex::LapicErr.as_u8() => "lapic error",
+        v if v == InterruptIndex::Syscall.as_u8() => "syscall",
+        v if v == InterruptIndex::Spurious.as_u8() => "spurious",
+        v if (DEVICE_IRQ_BASE..DEVICE_IRQ_BASE + DEVICE_IRQS).contains(&v) => "device",
+        _ => return None,
+    })
+}
+
+/// Creates the handlers of the device interrupt lines, which have to be
+/// separate functions to know their vector.
+macro_rules! device_interrupt_handlers {
+    ($($irq:literal)*) => {
+        [$(device_interrupt_handler::<$irq> as extern "x86-interrupt" fn(InterruptStackFrame)),*]
+    };
+}
+
 pub fn create_idt() -> InterruptDescriptorTable {
     let mut idt = InterruptDescriptorTable::new();
 
@@ -70,9 +154,17 @@ pub fn create_idt() -> InterruptDescriptorTable {
         .set_handler_fn(stack_segment_fault_handler);
 
     idt[InterruptIndex::Timer.as_u8()].set_handler_fn(timer_interrupt_handler);
+    idt[InterruptIndex::Serial.as_u8()].set_handler_fn(serial_interrupt_handler);
     idt[InterruptIndex::LapicErr.as_u8()].set_handler_fn(lapic_err_interrupt_handler);
     idt[InterruptIndex::Spurious.as_u8()].set_handler_fn(spurious_interrupt_handler);
 
+    let device_handlers = device_interrupt_handlers!(
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23
+    );
+    for (vector, handler) in (DEVICE_IRQ_BASE..).zip(device_handlers) {
+        idt[vector].set_handler_fn(handler);
+    }
+
     unsafe {
         idt[InterruptIndex::Syscall.as_u8()]
             .set_handler_fn(transmute::<
@@ -141,6 +233,8 @@ pub extern "sysv64" fn syscall_handler_impl(
     _stack_frame: &mut InterruptStackFrame,
     regs: &mut SyscallRegisters,
 ) {
+    count_interrupt(InterruptIndex::Syscall.as_u8());
+
     // The registers order follow the System V ABI convention
     let n = regs.rax;
     let arg1 = regs.rdi;
@@ -155,17 +249,52 @@ pub extern "sysv64" fn syscall_handler_impl(
     regs.rax = result as usize; // save result
 }
 
-extern "x86-interrupt" fn timer_interrupt_handler(_stack_frame: InterruptStackFrame) {
+extern "x86-interrupt" fn timer_interrupt_handler(stack_frame: InterruptStackFrame) {
+    count_interrupt(InterruptIndex::Timer.as_u8());
     unsafe {
         end_of_interrupt();
     }
 
+    wait::wake_sleepers();
+
     let ctx = ExecutionContext::load();
+
+    // a task that was interrupted in userspace is terminated here if its
+    // process was killed by a signal, so that it doesn't run any further
+    if stack_frame.code_segment.rpl() == PrivilegeLevel::Ring3 {
+        let task = ctx.current_task();
+        let process = task.process();
+        if let Some(signal) = process.pending_terminating_signal() {
+            let _ = process.exit_code().write().get_or_insert(128 + signal);
+            task.set_should_terminate(true);
+        }
+    }
+
     unsafe {
         ctx.scheduler_mut().reschedule();
     }
 }
 
+extern "x86-interrupt" fn serial_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    count_interrupt(InterruptIndex::Serial.as_u8());
+    tty::serial::handle_interrupt();
+    unsafe {
+        end_of_interrupt();
+    }
+}
+
+extern "x86-interrupt" fn device_interrupt_handler<const IRQ: u8>(
+    _stack_frame: InterruptStackFrame,
+) {
+    count_interrupt(DEVICE_IRQ_BASE + IRQ);
+    for handler in DEVICE_IRQ_HANDLERS[usize::from(IRQ)].read().iter() {
+        handler();
+    }
+    unsafe {
+        end_of_interrupt();
+    }
+}
+
 extern "x86-interrupt" fn lapic_err_interrupt_handler(stack_frame: InterruptStackFrame) {
     panic!("EXCEPTION: LAPIC ERROR\n{:#?}", stack_frame);
 }
@@ -206,6 +335,7 @@ extern "x86-interrupt" fn page_fault_handler(
     stack_frame: InterruptStackFrame,
     error_code: PageFaultErrorCode,
 ) {
+    count_interrupt(PAGE_FAULT_VECTOR);
     let accessed_address = Cr2::read().ok();
 
     // if we know the address...
@@ -257,7 +387,7 @@ extern "x86-interrupt" fn page_fault_handler(
                         // TODO: allocate new physical page, map it and add it to the lazy memory
                         // region
                     }
-                    MemoryRegion::Mapped(_mapped_memory_region) => {
+                    MemoryRegion::Mapped(_) | MemoryRegion::Device(_) => {
                         error!(
                             "invalid memory access in process '{}' task '{}', terminating...",
                             process.name(),
@@ -308,6 +438,7 @@ extern "x86-interrupt" fn stack_segment_fault_handler(
 }
 
 extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
+    count_interrupt(BREAKPOINT_VECTOR);
     warn!("BREAKPOINT:\n{stack_frame:#?}");
     warn!("halting...");
     loop {
@@ -316,6 +447,7 @@ extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
 }
 
 extern "x86-interrupt" fn debug_handler(stack_frame: InterruptStackFrame) {
+    count_interrupt(DEBUG_VECTOR);
     warn!("DEBUG:\n{stack_frame:#?}");
     let dr6_flags = Dr6::read();
     warn!("DR6 flags: {dr6_flags:#?}");
@@ -324,6 +456,7 @@ extern "x86-interrupt" fn debug_handler(stack_frame: InterruptStackFrame) {
 }
 
 extern "x86-interrupt" fn device_not_available_handler(_stack_frame: InterruptStackFrame) {
+    count_interrupt(DEVICE_NOT_AVAILABLE_VECTOR);
     let cx = ExecutionContext::load();
     let current_task = cx.current_task();
 
@@ -0,0 +1,46 @@
+use conquer_once::spin::OnceCell;
+use kernel_cmdline::BootConfig;
+use log::{debug, info, warn};
+
+static BOOT_CONFIG: OnceCell<BootConfig> = OnceCell::uninit();
+
+/// Returns the configuration parsed from the kernel command line.
+///
+/// # Panics
+/// Panics if the command line has not been parsed yet.
+#[must_use]
+pub fn boot_config() -> &'static BootConfig {
+    BOOT_CONFIG
+        .get()
+        .expect("kernel command line should be parsed")
+}
+
+pub(crate) fn init() {
+    let cmdline = raw_cmdline();
+    info!("kernel command line: {cmdline:?}");
+
+    let config = BootConfig::parse(cmdline).unwrap_or_else(|e| {
+        warn!("ignoring malformed kernel command line: {e}");
+        BootConfig::default()
+    });
+    for option in &config.unknown {
+        debug!("ignoring unknown kernel command line option {option:?}");
+    }
+
+    BOOT_CONFIG.init_once(|| config);
+}
+
+#[cfg(target_arch = "x86_64")]
+fn raw_cmdline() -> &'static str {
+    use crate::limine::KERNEL_FILE_REQUEST;
+
+    KERNEL_FILE_REQUEST
+        .get_response()
+        .and_then(|resp| resp.file().string().to_str().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn raw_cmdline() -> &'static str {
+    "" // TODO: get the command line from the device tree
+}
@@ -1,28 +1,43 @@
 use alloc::collections::BTreeMap;
 use alloc::format;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
 
 use kernel_devfs::BlockDeviceFile;
 use kernel_device::RegisterDeviceError;
 use kernel_device::block::BlockDevice;
+use kernel_device::partition::{
+    Partition, PartitionBlockDevice, PartitionSelector, read_partition_table,
+};
 use kernel_vfs::path::AbsoluteOwnedPath;
+use log::{info, warn};
 use spin::RwLock;
 
 use crate::driver::KernelDeviceId;
 use crate::file::devfs::devfs;
 
-#[allow(clippy::type_complexity)] // refactoring into types doesn't provide benefits here
-static BLOCK_DEVICES: RwLock<
-    BTreeMap<u64, Arc<RwLock<dyn BlockDevice<KernelDeviceId, 512> + Send + Sync>>>,
-> = RwLock::new(BTreeMap::new());
+pub type SharedBlockDevice = Arc<RwLock<dyn BlockDevice<KernelDeviceId, 512> + Send + Sync>>;
+
+static BLOCK_DEVICES: RwLock<BTreeMap<u64, SharedBlockDevice>> = RwLock::new(BTreeMap::new());
 static BLOCK_DEVICE_COUNTER: AtomicU64 = AtomicU64::new(0);
 
+/// Partitions of the registered block devices, keyed by the id of the
+/// containing device and the partition number.
+static PARTITIONS: RwLock<BTreeMap<(u64, usize), PartitionEntry>> = RwLock::new(BTreeMap::new());
+
+#[derive(Clone)]
+pub struct PartitionEntry {
+    pub partition: Partition,
+    pub device: SharedBlockDevice,
+}
+
 pub struct BlockDevices;
 
 impl BlockDevices {
-    // TODO: add documentation
+    /// Registers the device as `/dev/blkN`. If the device carries an MBR
+    /// or GPT, every partition is additionally registered as `/dev/blkNpM`.
     #[allow(clippy::missing_errors_doc)]
     #[allow(clippy::missing_panics_doc)]
     pub fn register_block_device<D>(device: Arc<RwLock<D>>) -> Result<(), RegisterDeviceError>
@@ -31,21 +46,88 @@ impl BlockDevices {
     {
         let id = BLOCK_DEVICE_COUNTER.fetch_add(1, Relaxed);
         let _ = BLOCK_DEVICES.write().insert(id, device.clone());
+        register_devfs_file(&format!("/blk{id}"), device.clone());
 
-        let path = AbsoluteOwnedPath::try_from(format!("/blk{id}").as_ref()).unwrap();
-        devfs()
-            .write()
-            .register_file(path.as_ref(), {
-                move || Ok(BlockDeviceFile::new(device.clone()))
-            })
-            .unwrap();
+        match read_partition_table(&mut device.clone()) {
+            Ok(Some(table)) => {
+                info!(
+                    "found {:?} partition table with {} partitions on blk{id}",
+                    table.kind,
+                    table.partitions.len()
+                );
+                for partition in table.partitions {
+                    Self::register_partition(id, device.clone(), partition);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("failed to read partition table of blk{id}: {e}"),
+        }
 
         Ok(())
     }
 
-    pub fn by_id(
-        id: u64,
-    ) -> Option<Arc<RwLock<dyn BlockDevice<KernelDeviceId, 512> + Send + Sync>>> {
+    fn register_partition<D>(disk_id: u64, disk: Arc<RwLock<D>>, partition: Partition)
+    where
+        D: BlockDevice<KernelDeviceId, 512> + Send + Sync + 'static,
+    {
+        let number = partition.number;
+        let device = Arc::new(RwLock::new(PartitionBlockDevice::new(
+            KernelDeviceId::new(),
+            disk,
+            &partition,
+        )));
+        register_devfs_file(&format!("/blk{disk_id}p{number}"), device.clone());
+
+        PARTITIONS
+            .write()
+            .insert((disk_id, number), PartitionEntry { partition, device });
+    }
+
+    pub fn by_id(id: u64) -> Option<SharedBlockDevice> {
         BLOCK_DEVICES.read().get(&id).cloned()
     }
+
+    /// Returns partition `number` of the block device with the given id.
+    pub fn partition(disk_id: u64, number: usize) -> Option<PartitionEntry> {
+        PARTITIONS.read().get(&(disk_id, number)).cloned()
+    }
+
+    /// Returns the first partition, across all block devices, that matches
+    /// the selector, i.e. has the given GPT partition GUID or label.
+    pub fn find_partition(selector: &PartitionSelector) -> Option<PartitionEntry> {
+        PARTITIONS
+            .read()
+            .values()
+            .find(|entry| entry.partition.matches(selector))
+            .cloned()
+    }
+
+    /// Returns the ids of all registered block devices, not including
+    /// partitions.
+    pub fn ids() -> Vec<u64> {
+        BLOCK_DEVICES.read().keys().copied().collect()
+    }
+
+    /// Returns all partitions, keyed by the id of the containing device and
+    /// the partition number.
+    pub fn partitions() -> Vec<((u64, usize), PartitionEntry)> {
+        PARTITIONS
+            .read()
+            .iter()
+            .map(|(k, v)| (*k, v.clone()))
+            .collect()
+    }
+}
+
+fn register_devfs_file<D>(name: &str, device: Arc<RwLock<D>>)
+where
+    D: BlockDevice<KernelDeviceId, 512> + Send + Sync + 'static,
+{
+    let path = AbsoluteOwnedPath::try_from(name).unwrap();
+    devfs()
+        .write()
+        .register_file(path.as_ref(), {
+            move || Ok(BlockDeviceFile::new(device.clone()))
+        })
+        .unwrap();
 }
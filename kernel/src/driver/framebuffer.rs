@@ -0,0 +1,164 @@
+//! Framebuffers, which userspace can draw on through `/dev/fbN`. The device
+//! files follow the Linux fbdev interface, so programs map the framebuffer
+//! with `mmap` and query its layout with `FBIOGET_VSCREENINFO` and
+//! `FBIOGET_FSCREENINFO`. Devices that don't scan out their memory by
+//! themselves need an `FBIO_FLUSH` to show the changes.
+
+use alloc::format;
+use alloc::sync::Arc;
+use core::ffi::c_ulong;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering::Relaxed;
+
+use kernel_abi::{
+    FB_TYPE_PACKED_PIXELS, FB_VISUAL_TRUECOLOR, FBIO_FLUSH, FBIOGET_FSCREENINFO,
+    FBIOGET_VSCREENINFO, FbBitfield, FbFixScreenInfo, FbVarScreenInfo,
+};
+use kernel_devfs::DevFile;
+use kernel_device::RegisterDeviceError;
+use kernel_device::framebuffer::FramebufferDevice;
+use kernel_syscall::ioctl::write_arg;
+use kernel_vfs::path::AbsoluteOwnedPath;
+use kernel_vfs::{FileType, IoctlError, MmapError, ReadError, Stat, StatError, WriteError};
+use log::info;
+use spin::RwLock;
+use x86_64::structures::paging::{PageSize, Size4KiB};
+
+use crate::driver::KernelDeviceId;
+use crate::driver::raw::RawDevices;
+use crate::file::devfs::devfs;
+use crate::{U64Ext, UsizeExt};
+
+pub struct Framebuffers;
+
+impl Framebuffers {
+    /// Registers the device as a raw device and creates the next free
+    /// `/dev/fbN` for it.
+    ///
+    /// # Errors
+    /// Returns an error if the device is already registered.
+    pub fn register<D>(device: Arc<RwLock<D>>) -> Result<(), RegisterDeviceError>
+    where
+        D: FramebufferDevice<KernelDeviceId> + Send + Sync + 'static,
+    {
+        static NEXT_INDEX: AtomicU32 = AtomicU32::new(0);
+
+        RawDevices::register_raw_device(device.clone())?;
+
+        let index = NEXT_INDEX.fetch_add(1, Relaxed);
+        let path = AbsoluteOwnedPath::try_from(format!("/fb{index}").as_str()).unwrap();
+        devfs()
+            .write()
+            .register_file(path.as_ref(), move || {
+                Ok(FramebufferFile {
+                    device: device.clone(),
+                })
+            })
+            .expect("framebuffer index should be unique");
+        info!("registered framebuffer as /dev/fb{index}");
+        Ok(())
+    }
+}
+
+/// An open `/dev/fbN`. The framebuffer can only be accessed by mapping it.
+struct FramebufferFile<D> {
+    device: Arc<RwLock<D>>,
+}
+
+impl<D> FramebufferFile<D>
+where
+    D: FramebufferDevice<KernelDeviceId>,
+{
+    /// The size of the device memory in bytes.
+    fn memory_size(&self) -> usize {
+        let range = self.device.read().physical_memory();
+        range.count() * Size4KiB::SIZE.into_usize()
+    }
+
+    fn var_screen_info(&self) -> FbVarScreenInfo {
+        let info = self.device.read().info();
+        let channel = |offset| FbBitfield {
+            offset: u32::from(offset),
+            length: 8,
+            msb_right: 0,
+        };
+        FbVarScreenInfo {
+            xres: saturate(info.width),
+            yres: saturate(info.height),
+            xres_virtual: saturate(info.width),
+            yres_virtual: saturate(info.height),
+            bits_per_pixel: u32::from(info.bits_per_pixel),
+            red: channel(info.red_offset),
+            green: channel(info.green_offset),
+            blue: channel(info.blue_offset),
+            // the physical size of the screen is unknown
+            height: u32::MAX,
+            width: u32::MAX,
+            ..FbVarScreenInfo::default()
+        }
+    }
+
+    fn fix_screen_info(&self) -> FbFixScreenInfo {
+        let device = self.device.read();
+        let info = device.info();
+        let mut id = [0; 16];
+        let len = info.name.len().min(id.len() - 1);
+        id[..len].copy_from_slice(&info.name.as_bytes()[..len]);
+        FbFixScreenInfo {
+            id,
+            smem_start: device.physical_memory().start.start_address().as_u64() as c_ulong,
+            smem_len: saturate(self.memory_size()),
+            type_: FB_TYPE_PACKED_PIXELS,
+            visual: FB_VISUAL_TRUECOLOR,
+            line_length: saturate(info.pitch),
+            ..FbFixScreenInfo::default()
+        }
+    }
+}
+
+impl<D> DevFile for FramebufferFile<D>
+where
+    D: FramebufferDevice<KernelDeviceId> + Send + Sync,
+{
+    fn read(&mut self, _: &mut [u8], _: usize) -> Result<usize, ReadError> {
+        Err(ReadError::NotReadable)
+    }
+
+    fn write(&mut self, _: &[u8], _: usize) -> Result<usize, WriteError> {
+        Err(WriteError::NotWritable)
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.file_type = FileType::CharDevice;
+        stat.size = self.memory_size();
+        Ok(())
+    }
+
+    fn ioctl(&mut self, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        match request {
+            FBIOGET_VSCREENINFO => write_arg(arg, self.var_screen_info())?,
+            FBIOGET_FSCREENINFO => write_arg(arg, self.fix_screen_info())?,
+            FBIO_FLUSH => self
+                .device
+                .write()
+                .flush()
+                .map_err(|_| IoctlError::DeviceError)?,
+            _ => return Err(IoctlError::Unsupported),
+        }
+        Ok(0)
+    }
+
+    fn mmap(&mut self, offset: usize, len: usize) -> Result<u64, MmapError> {
+        let end = offset.checked_add(len).ok_or(MmapError::InvalidRange)?;
+        if end > self.memory_size() {
+            return Err(MmapError::InvalidRange);
+        }
+        let start = self.device.read().physical_memory().start.start_address();
+        Ok(start.as_u64() + offset.into_u64())
+    }
+}
+
+/// Converts a size for the fbdev structures, which only have 32 bits for it.
+fn saturate(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
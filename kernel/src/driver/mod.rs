@@ -5,6 +5,8 @@ use core::sync::atomic::Ordering::Relaxed;
 use kernel_device::DeviceId;
 
 pub mod block;
+pub mod framebuffer;
+pub mod net;
 pub mod pci;
 pub mod raw;
 pub mod virtio;
@@ -0,0 +1,46 @@
+//! Network devices, which send and receive Ethernet frames. Drivers queue
+//! received frames in their interrupt handlers and wake the network task
+//! with [`frames_received`](crate::net::frames_received), which takes them
+//! from the queue.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use kernel_device::network::NetworkDevice;
+use log::info;
+use spin::RwLock;
+
+use crate::driver::KernelDeviceId;
+
+pub type SharedNetworkDevice = Arc<RwLock<dyn NetworkDevice<KernelDeviceId> + Send + Sync>>;
+
+/// The registered devices together with their name.
+static NETWORK_DEVICES: RwLock<Vec<(String, SharedNetworkDevice)>> = RwLock::new(Vec::new());
+
+pub struct NetworkDevices;
+
+impl NetworkDevices {
+    /// Registers the device under the next free name `ethN` and returns the
+    /// name.
+    pub fn register<D>(device: Arc<RwLock<D>>) -> String
+    where
+        D: NetworkDevice<KernelDeviceId> + Send + Sync + 'static,
+    {
+        let mac_address = device.read().mac_address();
+        let name = {
+            let mut devices = NETWORK_DEVICES.write();
+            let name = format!("eth{}", devices.len());
+            devices.push((name.clone(), device));
+            name
+        };
+        info!("registered network device {name} with mac address {mac_address}");
+        name
+    }
+
+    /// Returns all registered devices together with their name.
+    pub fn all() -> Vec<(String, SharedNetworkDevice)> {
+        NETWORK_DEVICES.read().clone()
+    }
+}
@@ -1,17 +1,26 @@
 use alloc::boxed::Box;
 use alloc::string::ToString;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::error::Error;
 
-use kernel_pci::PciAddress;
 use kernel_pci::config::{ConfigKey, ConfigurationAccess, PortCam, ReadConfig, WriteConfig};
+use kernel_pci::{Bar, PciAddress};
 use linkme::distributed_slice;
 use log::{Level, debug, error, log_enabled, trace};
+use spin::RwLock;
+use thiserror::Error;
 use virtio_drivers::transport::pci::bus::DeviceFunction;
 
+use crate::apic;
+use crate::arch::idt::{DEVICE_IRQ_BASE, DEVICE_IRQS, register_device_irq_handler};
+
 #[distributed_slice]
 pub static PCI_DRIVERS: [PciDriverDescriptor] = [..];
 
+/// All PCI functions that were found during [`init`].
+static PCI_FUNCTIONS: RwLock<Vec<PciFunction>> = RwLock::new(Vec::new());
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum PciDriverType {
     Generic,
@@ -26,6 +35,90 @@ pub struct PciDriverDescriptor {
     pub init: fn(PciAddress, Box<dyn ConfigurationAccess>) -> Result<(), Box<dyn Error>>,
 }
 
+/// A PCI function as it was found during enumeration.
+#[derive(Debug, Clone)]
+pub struct PciFunction {
+    pub address: PciAddress,
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub revision_id: u8,
+    /// The BARs as they were configured before a driver was initialized.
+    pub bars: Vec<(u8, Bar)>,
+    pub interrupt_line: u8,
+    pub interrupt_pin: u8,
+    /// The name of the driver that was successfully initialized for this
+    /// function.
+    pub driver: Option<&'static str>,
+}
+
+impl PciFunction {
+    fn read<C: ConfigurationAccess>(address: PciAddress, cam: &C) -> Self {
+        Self {
+            address,
+            vendor_id: address.vendor_id(cam),
+            device_id: address.device_id(cam),
+            class: address.class(cam),
+            subclass: address.subclass(cam),
+            prog_if: address.prog_if(cam),
+            revision_id: address.revision_id(cam),
+            bars: address.bars(cam),
+            interrupt_line: address.interrupt_line(cam),
+            interrupt_pin: address.interrupt_pin(cam),
+            driver: None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum InterruptError {
+    #[error("the function has no interrupt line")]
+    NoInterruptLine,
+}
+
+/// The bit in the command register that keeps a function from asserting its
+/// interrupt pin.
+const COMMAND_INTERRUPT_DISABLE: u16 = 1 << 10;
+
+/// Allows the function to assert its interrupt pin and returns the interrupt
+/// line that the firmware assigned to it. The line stays masked until a
+/// handler is [registered](register_interrupt_handler).
+///
+/// # Errors
+/// Returns an error if the function doesn't use an interrupt line, or if
+/// the line is not one that the IOAPIC has.
+pub fn enable_interrupt_line(
+    addr: PciAddress,
+    cam: &dyn ConfigurationAccess,
+) -> Result<u8, InterruptError> {
+    let irq = addr.interrupt_line(cam);
+    if addr.interrupt_pin(cam) == 0 || irq >= DEVICE_IRQS {
+        return Err(InterruptError::NoInterruptLine);
+    }
+    let command: u16 = cam.read_config(addr, ConfigKey::COMMAND);
+    cam.write_config(
+        addr,
+        ConfigKey::COMMAND,
+        command & !COMMAND_INTERRUPT_DISABLE,
+    );
+    Ok(irq)
+}
+
+/// Calls `handler` whenever the interrupt line `irq` is raised, and unmasks
+/// the line. The line may be shared with other functions, see
+/// [`register_device_irq_handler`].
+pub fn register_interrupt_handler(irq: u8, handler: impl Fn() + Send + Sync + 'static) {
+    register_device_irq_handler(irq, handler);
+    apic::enable_pci_irq(irq, DEVICE_IRQ_BASE + irq);
+}
+
+/// Returns all PCI functions that were found during enumeration.
+pub fn pci_functions() -> Vec<PciFunction> {
+    PCI_FUNCTIONS.read().clone()
+}
+
 /// # Panics
 ///
 /// Panics if there are multiple specific or multiple generic drivers that would match
@@ -40,6 +133,18 @@ pub fn init() {
     let cam = unsafe { PortCam::new() };
 
     unsafe { iterate_all(&cam) }.for_each(|addr| {
+        let mut function = PciFunction::read(addr, &cam);
+
+        // lines are shared and level triggered, so a function without a
+        // handler would keep raising the interrupt of the drivers that
+        // share its line
+        let command: u16 = cam.read_config(addr, ConfigKey::COMMAND);
+        cam.write_config(
+            addr,
+            ConfigKey::COMMAND,
+            command | COMMAND_INTERRUPT_DISABLE,
+        );
+
         let driver = PCI_DRIVERS
             .iter()
             .fold(None, |res: Option<&PciDriverDescriptor>, driver| {
@@ -69,13 +174,16 @@ pub fn init() {
         if let Some(driver) = driver {
             debug!("found driver {} for device {}", driver.name, addr);
             let device_string = addr.to_string();
-            if let Err(e) = (driver.init)(addr, Box::new(cam.clone())) {
-                error!(
+            match (driver.init)(addr, Box::new(cam.clone())) {
+                Ok(()) => function.driver = Some(driver.name),
+                Err(e) => error!(
                     "failed to init driver {} for device {}: {}",
                     driver.name, device_string, e
-                );
+                ),
             }
         }
+
+        PCI_FUNCTIONS.write().push(function);
     });
 }
 
@@ -1,4 +1,5 @@
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 
 use kernel_device::RegisterDeviceError;
 use kernel_device::raw::{RawDevice, RawDeviceRegistry};
@@ -12,13 +13,21 @@ static RAW_DEVICES: RwLock<RawDeviceRegistry<KernelDeviceId>> =
 pub struct RawDevices;
 
 impl RawDevices {
-    #[allow(clippy::missing_errors_doc)] // TODO: add documentation
+    /// Registers a device whose memory can be accessed directly. The device
+    /// only gets a file in devfs if it is a framebuffer, see
+    /// [`Framebuffers::register`](crate::driver::framebuffer::Framebuffers::register).
+    ///
+    /// # Errors
+    /// Returns an error if the device is already registered.
     pub fn register_raw_device<D>(device: Arc<RwLock<D>>) -> Result<(), RegisterDeviceError>
     where
         D: RawDevice<KernelDeviceId> + 'static,
     {
-        RAW_DEVICES.write().register_device(device)?;
-        // TODO: create a vfs entry for this device
-        Ok(())
+        RAW_DEVICES.write().register_device(device)
+    }
+
+    /// Returns all registered raw devices.
+    pub fn all() -> Vec<Arc<RwLock<dyn RawDevice<KernelDeviceId>>>> {
+        RAW_DEVICES.read().all_devices().cloned().collect()
     }
 }
@@ -2,12 +2,16 @@ use alloc::boxed::Box;
 use alloc::sync::Arc;
 use core::error::Error;
 use core::fmt::{Debug, Formatter};
+use core::slice;
 
 use kernel_device::Device;
+use kernel_device::framebuffer::{FlushError, FramebufferDevice, FramebufferInfo};
 use kernel_device::raw::RawDevice;
+use kernel_fbcon::{Canvas, Color, LinearFramebuffer, PixelFormat};
 use kernel_pci::PciAddress;
 use kernel_pci::config::ConfigurationAccess;
 use linkme::distributed_slice;
+use log::warn;
 use spin::Mutex;
 use spin::rwlock::RwLock;
 use virtio_drivers::device::gpu::VirtIOGpu;
@@ -16,12 +20,12 @@ use x86_64::VirtAddr;
 use x86_64::structures::paging::frame::PhysFrameRangeInclusive;
 use x86_64::structures::paging::{PhysFrame, Size4KiB};
 
-use crate::UsizeExt;
 use crate::driver::KernelDeviceId;
+use crate::driver::framebuffer::Framebuffers;
 use crate::driver::pci::{PCI_DRIVERS, PciDriverDescriptor, PciDriverType};
-use crate::driver::raw::RawDevices;
 use crate::driver::virtio::hal::{HalImpl, transport};
 use crate::mem::address_space::AddressSpace;
+use crate::{UsizeExt, fbcon};
 
 #[distributed_slice(PCI_DRIVERS)]
 static VIRTIO_GPU: PciDriverDescriptor = PciDriverDescriptor {
@@ -47,20 +51,14 @@ fn virtio_init(addr: PciAddress, cam: Box<dyn ConfigurationAccess>) -> Result<()
     let fb = gpu.setup_framebuffer()?;
     let buffer_virtual_addr = VirtAddr::from_ptr(fb);
     let buffer_len = fb.len();
-    for y in 0..height {
-        for x in 0..width {
-            let idx = (y * width + x) * 4;
-
-            // truncation intended if happens (which it shouldn't unless we're dealing with really large resolutions
-            #[allow(clippy::cast_possible_truncation)]
-            {
-                fb[idx] = x as u8;
-                fb[idx + 1] = y as u8;
-                fb[idx + 2] = (x + y) as u8;
-            }
-        }
-    }
-    gpu.flush()?;
+    // the framebuffer lives as long as the device, which is never dropped
+    let buffer = unsafe { slice::from_raw_parts_mut(fb.as_mut_ptr(), buffer_len) };
+    let gpu = Arc::new(Mutex::new(gpu));
+
+    fbcon::set_canvas(GpuCanvas {
+        framebuffer: LinearFramebuffer::new(buffer, width, height, width * 4, PixelFormat::BGRX),
+        gpu: gpu.clone(),
+    });
 
     let phys_addr = AddressSpace::kernel()
         .translate(buffer_virtual_addr)
@@ -71,21 +69,60 @@ fn virtio_init(addr: PciAddress, cam: Box<dyn ConfigurationAccess>) -> Result<()
 
     let device = VirtIoRawDevice {
         id: KernelDeviceId::new(),
-        _inner: Arc::new(Mutex::new(gpu)),
+        gpu,
         physical_memory,
+        width,
+        height,
     };
     let device = Arc::new(RwLock::new(device));
 
-    RawDevices::register_raw_device(device)?;
+    Framebuffers::register(device)?;
 
     Ok(())
 }
 
+/// The framebuffer of the GPU as a canvas for the console. Changes are only
+/// shown after a flush.
+struct GpuCanvas {
+    framebuffer: LinearFramebuffer<'static>,
+    gpu: Arc<Mutex<VirtIOGpu<HalImpl, PciTransport>>>,
+}
+
+impl Canvas for GpuCanvas {
+    fn width(&self) -> usize {
+        self.framebuffer.width()
+    }
+
+    fn height(&self) -> usize {
+        self.framebuffer.height()
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        self.framebuffer.set_pixel(x, y, color);
+    }
+
+    fn scroll_up(&mut self, rows: usize, fill: Color) {
+        self.framebuffer.scroll_up(rows, fill);
+    }
+
+    fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Color) {
+        self.framebuffer.fill_rect(x, y, width, height, color);
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.gpu.lock().flush() {
+            warn!("failed to flush virtio-gpu framebuffer: {e:?}");
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct VirtIoRawDevice {
     id: KernelDeviceId,
-    _inner: Arc<Mutex<VirtIOGpu<HalImpl, PciTransport>>>,
+    gpu: Arc<Mutex<VirtIOGpu<HalImpl, PciTransport>>>,
     physical_memory: PhysFrameRangeInclusive,
+    width: usize,
+    height: usize,
 }
 
 impl Debug for VirtIoRawDevice {
@@ -93,6 +130,8 @@ impl Debug for VirtIoRawDevice {
         f.debug_struct("VirtIoRawDevice")
             .field("id", &self.id)
             .field("physical_memory", &self.physical_memory)
+            .field("width", &self.width)
+            .field("height", &self.height)
             .finish_non_exhaustive()
     }
 }
@@ -108,3 +147,26 @@ impl RawDevice<KernelDeviceId> for VirtIoRawDevice {
         self.physical_memory
     }
 }
+
+impl FramebufferDevice<KernelDeviceId> for VirtIoRawDevice {
+    fn info(&self) -> FramebufferInfo {
+        let format = PixelFormat::BGRX;
+        FramebufferInfo {
+            name: "virtio-gpu",
+            width: self.width,
+            height: self.height,
+            pitch: self.width * format.bytes_per_pixel,
+            bits_per_pixel: 32,
+            red_offset: format.red_shift,
+            green_offset: format.green_shift,
+            blue_offset: format.blue_shift,
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), FlushError> {
+        self.gpu.lock().flush().map_err(|e| {
+            warn!("failed to flush virtio-gpu framebuffer: {e:?}");
+            FlushError::DeviceError
+        })
+    }
+}
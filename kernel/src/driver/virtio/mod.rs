@@ -1,3 +1,5 @@
 mod block;
 mod gpu;
 mod hal;
+mod net;
+mod rng;
@@ -0,0 +1,159 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::error::Error;
+
+use kernel_device::Device;
+use kernel_device::network::{
+    ETHERNET_HEADER_LEN, MacAddress, NetworkDevice, ReceiveError, ReceiveQueue, TransmitError,
+};
+use kernel_pci::PciAddress;
+use kernel_pci::config::ConfigurationAccess;
+use linkme::distributed_slice;
+use spin::Mutex;
+use spin::rwlock::RwLock;
+use virtio_drivers::device::net::{TxBuffer, VirtIONet};
+use virtio_drivers::transport::pci::PciTransport;
+use x86_64::instructions::interrupts;
+
+use crate::driver::KernelDeviceId;
+use crate::driver::net::NetworkDevices;
+use crate::driver::pci::{
+    PCI_DRIVERS, PciDriverDescriptor, PciDriverType, enable_interrupt_line,
+    register_interrupt_handler,
+};
+use crate::driver::virtio::hal::{HalImpl, transport};
+use crate::net;
+
+#[distributed_slice(PCI_DRIVERS)]
+static VIRTIO_NET: PciDriverDescriptor = PciDriverDescriptor {
+    name: "virtio-net",
+    typ: PciDriverType::Specific,
+    probe: virtio_probe,
+    init: virtio_init,
+};
+
+/// The number of buffers in each of the virtqueues.
+const QUEUE_SIZE: usize = 16;
+
+/// The size of the receive buffers, including the virtio header in front of
+/// the frame.
+const BUFFER_LEN: usize = 2048;
+
+/// The device only reports an MTU if the driver negotiates it, which
+/// `virtio_drivers` doesn't, so the Ethernet default applies.
+const MTU: usize = 1500;
+
+/// The number of received frames that are kept until the network stack
+/// takes them.
+const RECEIVE_QUEUE_LEN: usize = 64;
+
+type VirtIoNet = VirtIONet<HalImpl, PciTransport, QUEUE_SIZE>;
+
+fn virtio_probe(addr: PciAddress, cam: &dyn ConfigurationAccess) -> bool {
+    addr.vendor_id(cam) == 0x1af4
+        && (addr.device_id(cam) == 0x1041
+            || ((0x1000..=0x103f).contains(&addr.device_id(cam)) && addr.subsystem_id(cam) == 0x01))
+}
+
+#[allow(clippy::needless_pass_by_value)] // signature is required like this
+fn virtio_init(addr: PciAddress, cam: Box<dyn ConfigurationAccess>) -> Result<(), Box<dyn Error>> {
+    let irq = enable_interrupt_line(addr, cam.as_ref())?;
+    let transport = transport(addr, cam);
+
+    let mut net = VirtIoNet::new(transport, BUFFER_LEN)?;
+    let mac_address = MacAddress(net.mac_address());
+    net.enable_interrupts();
+    let inner = Arc::new(Mutex::new(Inner {
+        net,
+        queue: ReceiveQueue::new(RECEIVE_QUEUE_LEN, ETHERNET_HEADER_LEN + MTU),
+    }));
+
+    let handler_inner = inner.clone();
+    register_interrupt_handler(irq, move || {
+        // everyone else holds the lock with interrupts disabled, so it is
+        // only held by another CPU, and the interrupt is raised again until
+        // it is acknowledged
+        if let Some(mut inner) = handler_inner.try_lock()
+            && inner.receive_frames()
+        {
+            net::frames_received();
+        }
+    });
+    // frames that arrived before the line was unmasked didn't raise an
+    // interrupt
+    interrupts::without_interrupts(|| inner.lock().receive_frames());
+
+    let device = VirtIoNetDevice {
+        id: KernelDeviceId::new(),
+        mac_address,
+        inner,
+    };
+    NetworkDevices::register(Arc::new(RwLock::new(device)));
+
+    Ok(())
+}
+
+struct Inner {
+    net: VirtIoNet,
+    queue: ReceiveQueue,
+}
+
+impl Inner {
+    /// Moves the received frames from the virtqueue to the receive queue,
+    /// and returns whether there were any. This is called in the interrupt
+    /// handler and doesn't allocate.
+    fn receive_frames(&mut self) -> bool {
+        let _ = self.net.ack_interrupt();
+        let mut received = false;
+        while let Ok(buffer) = self.net.receive() {
+            self.queue.push(buffer.packet());
+            // the buffer is only returned to the device, this can't fail
+            let _ = self.net.recycle_rx_buffer(buffer);
+            received = true;
+        }
+        received
+    }
+}
+
+/// A virtio network card. Frames are received in the interrupt handler, so
+/// the lock of the device state is only taken with interrupts disabled.
+pub struct VirtIoNetDevice {
+    id: KernelDeviceId,
+    mac_address: MacAddress,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Device<KernelDeviceId> for VirtIoNetDevice {
+    fn id(&self) -> KernelDeviceId {
+        self.id
+    }
+}
+
+impl NetworkDevice<KernelDeviceId> for VirtIoNetDevice {
+    fn mac_address(&self) -> MacAddress {
+        self.mac_address
+    }
+
+    fn mtu(&self) -> usize {
+        MTU
+    }
+
+    /// The link status is only reported if the driver negotiates it, which
+    /// `virtio_drivers` doesn't, so the link is always considered up.
+    fn link_up(&self) -> bool {
+        true
+    }
+
+    fn transmit(&mut self, frame: &[u8]) -> Result<(), TransmitError> {
+        if frame.len() > ETHERNET_HEADER_LEN + MTU {
+            return Err(TransmitError::FrameTooLarge);
+        }
+        let buffer = TxBuffer::from(frame);
+        interrupts::without_interrupts(|| self.inner.lock().net.send(buffer))
+            .map_err(|_| TransmitError::DeviceError)
+    }
+
+    fn receive(&mut self, buf: &mut [u8]) -> Result<usize, ReceiveError> {
+        interrupts::without_interrupts(|| self.inner.lock().queue.pop(buf))
+    }
+}
@@ -0,0 +1,45 @@
+use alloc::boxed::Box;
+use core::error::Error;
+
+use kernel_pci::PciAddress;
+use kernel_pci::config::ConfigurationAccess;
+use linkme::distributed_slice;
+use log::info;
+use virtio_drivers::device::rng::VirtIORng;
+
+use crate::driver::pci::{PCI_DRIVERS, PciDriverDescriptor, PciDriverType};
+use crate::driver::virtio::hal::{HalImpl, transport};
+use crate::random;
+
+#[distributed_slice(PCI_DRIVERS)]
+static VIRTIO_RNG: PciDriverDescriptor = PciDriverDescriptor {
+    name: "virtio-rng",
+    typ: PciDriverType::Specific,
+    probe: virtio_probe,
+    init: virtio_init,
+};
+
+/// The number of bytes that are requested from the device to seed the
+/// random number generator.
+const SEED_LEN: usize = 64;
+
+fn virtio_probe(addr: PciAddress, cam: &dyn ConfigurationAccess) -> bool {
+    addr.vendor_id(cam) == 0x1af4
+        && (addr.device_id(cam) == 0x1044
+            || ((0x1000..=0x103f).contains(&addr.device_id(cam)) && addr.subsystem_id(cam) == 0x04))
+}
+
+/// Feeds the entropy of the device into the random number generator. The
+/// device is only used once, the generator doesn't need more than a seed.
+#[allow(clippy::needless_pass_by_value)] // signature is required like this
+fn virtio_init(addr: PciAddress, cam: Box<dyn ConfigurationAccess>) -> Result<(), Box<dyn Error>> {
+    let transport = transport(addr, cam);
+
+    let mut rng = VirtIORng::<HalImpl, _>::new(transport)?;
+    let mut seed = [0; SEED_LEN];
+    let len = rng.request_entropy(&mut seed)?;
+    random::add_entropy(&seed[..len], len * 8);
+    info!("added {len} bytes from virtio-rng to the random number generator");
+
+    Ok(())
+}
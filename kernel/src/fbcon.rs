@@ -0,0 +1,78 @@
+//! The text console on the screen, which shows the kernel log. It draws on
+//! the framebuffer that Limine set up until a GPU driver provides its own
+//! framebuffer with [`set_canvas`].
+
+use alloc::boxed::Box;
+use core::fmt::{self, Write};
+use core::slice;
+
+use kernel_fbcon::{Canvas, Console, LinearFramebuffer, PixelFormat};
+use log::{info, warn};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::U64Ext;
+use crate::limine::FRAMEBUFFER_REQUEST;
+
+type BoxedCanvas = Box<dyn Canvas + Send>;
+
+static CONSOLE: Mutex<Option<Console<BoxedCanvas>>> = Mutex::new(None);
+
+/// Starts the console on the first framebuffer that Limine provides, if
+/// there is one with a supported pixel format.
+pub fn init() {
+    let Some(fb) = FRAMEBUFFER_REQUEST
+        .get_response()
+        .and_then(|response| response.framebuffers().next())
+    else {
+        info!("no framebuffer, not starting the console");
+        return;
+    };
+
+    let mask_sizes = [
+        fb.red_mask_size(),
+        fb.green_mask_size(),
+        fb.blue_mask_size(),
+    ];
+    if !matches!(fb.bpp(), 24 | 32) || mask_sizes.iter().any(|&size| size != 8) {
+        warn!(
+            "unsupported framebuffer format with {} bits per pixel",
+            fb.bpp()
+        );
+        return;
+    }
+    let format = PixelFormat {
+        bytes_per_pixel: usize::from(fb.bpp() / 8),
+        red_shift: fb.red_mask_shift(),
+        green_shift: fb.green_mask_shift(),
+        blue_shift: fb.blue_mask_shift(),
+    };
+
+    let width = fb.width().into_usize();
+    let height = fb.height().into_usize();
+    let pitch = fb.pitch().into_usize();
+    // the framebuffer is mapped in the higher half direct map and is
+    // never unmapped
+    let buffer = unsafe { slice::from_raw_parts_mut(fb.addr(), height * pitch) };
+    set_canvas(LinearFramebuffer::new(buffer, width, height, pitch, format));
+    info!("console started on {width}x{height} framebuffer");
+}
+
+/// Moves the console to another canvas. The content of the old canvas is
+/// not carried over.
+pub fn set_canvas(canvas: impl Canvas + Send + 'static) {
+    let console = Console::new(Box::new(canvas) as BoxedCanvas);
+    interrupts::without_interrupts(|| *CONSOLE.lock() = Some(console));
+}
+
+#[doc(hidden)]
+pub fn internal_print(args: fmt::Arguments) {
+    // like on the serial interface, interrupts are disabled so that
+    // interrupt handlers can print
+    interrupts::without_interrupts(|| {
+        if let Some(console) = CONSOLE.lock().as_mut() {
+            let _ = console.write_fmt(args);
+            console.flush();
+        }
+    });
+}
@@ -1,10 +1,25 @@
-use core::fmt::Write;
+use alloc::sync::Arc;
 
 use conquer_once::spin::OnceCell;
-use kernel_devfs::{ArcLockedDevFs, Null, Serial};
+use kernel_devfs::ArcLockedDevFs;
+use kernel_vfs::SharedFileSystem;
 use kernel_vfs::path::AbsolutePath;
+use linkme::distributed_slice;
+use spin::RwLock;
 
-use crate::serial_print;
+use crate::driver::block::SharedBlockDevice;
+use crate::file::fs_type::{ConstructError, FILESYSTEM_TYPES, FileSystemType};
+use crate::log::KmsgFile;
+use crate::random::RandomFile;
+use crate::tty::{TtyFile, pty, serial};
+
+#[distributed_slice(FILESYSTEM_TYPES)]
+static DEVFS_TYPE: FileSystemType = FileSystemType {
+    name: "devfs",
+    requires_device: false,
+    probe: devfs_probe,
+    construct: devfs_construct,
+};
 
 static DEVFS: OnceCell<ArcLockedDevFs> = OnceCell::uninit();
 
@@ -17,36 +32,54 @@ pub fn init() {
     let devfs = ArcLockedDevFs::new();
     {
         let mut guard = devfs.write();
+        // the standard streams of processes started by the kernel are
+        // connected to the console, which is the serial terminal
+        for path in [
+            "/ttyS0", "/serial", "/console", "/stdin", "/stdout", "/stderr",
+        ] {
+            guard
+                .register_file(AbsolutePath::try_new(path).unwrap(), || {
+                    Ok(TtyFile::new(serial::tty().clone()))
+                })
+                .expect("should be able to register serial terminal");
+        }
         guard
-            .register_file(AbsolutePath::try_new("/serial").unwrap(), || {
-                Ok(Serial::<SerialWrite>::default())
-            })
-            .expect("should be able to register serial file");
-
-        // TODO: implement proper STDIO
+            .register_file(AbsolutePath::try_new("/ptmx").unwrap(), pty::open_master)
+            .expect("should be able to register ptmx");
         guard
-            .register_file(AbsolutePath::try_new("/stdin").unwrap(), || Ok(Null))
-            .expect("should be able to register stdin");
+            .register_file(AbsolutePath::try_new("/kmsg").unwrap(), || {
+                Ok(KmsgFile::default())
+            })
+            .expect("should be able to register kmsg");
         guard
-            .register_file(AbsolutePath::try_new("/stdout").unwrap(), || {
-                Ok(Serial::<SerialWrite>::default())
+            .register_file(AbsolutePath::try_new("/random").unwrap(), || {
+                Ok(RandomFile::random())
             })
-            .expect("should be able to register stdout");
+            .expect("should be able to register random");
         guard
-            .register_file(AbsolutePath::try_new("/stderr").unwrap(), || {
-                Ok(Serial::<SerialWrite>::default())
+            .register_file(AbsolutePath::try_new("/urandom").unwrap(), || {
+                Ok(RandomFile::urandom())
             })
-            .expect("should be able to register stderr");
+            .expect("should be able to register urandom");
+        // devpts is mounted here
+        guard
+            .register_directory(AbsolutePath::try_new("/pts").unwrap())
+            .expect("should be able to register pts directory");
     }
     DEVFS.init_once(|| devfs);
 }
 
-#[derive(Default)]
-struct SerialWrite;
+fn devfs_probe(_device: Option<&SharedBlockDevice>, _options: &str) -> bool {
+    false
+}
 
-impl Write for SerialWrite {
-    fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        serial_print!("{s}");
-        Ok(())
+/// All mounts of devfs share the same tree of device files.
+fn devfs_construct(
+    _device: Option<SharedBlockDevice>,
+    options: &str,
+) -> Result<SharedFileSystem, ConstructError> {
+    if !options.is_empty() {
+        return Err(ConstructError::InvalidOption(options.into()));
     }
+    Ok(Arc::new(RwLock::new(devfs().clone())))
 }
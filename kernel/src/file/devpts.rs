@@ -0,0 +1,43 @@
+//! The `/dev/pts` file system, which holds the slaves of the
+//! pseudo-terminals (see [`crate::tty::pty`]).
+
+use alloc::sync::Arc;
+
+use conquer_once::spin::Lazy;
+use kernel_devfs::ArcLockedDevFs;
+use kernel_vfs::SharedFileSystem;
+use linkme::distributed_slice;
+use spin::RwLock;
+
+use crate::driver::block::SharedBlockDevice;
+use crate::file::fs_type::{ConstructError, FILESYSTEM_TYPES, FileSystemType};
+
+#[distributed_slice(FILESYSTEM_TYPES)]
+static DEVPTS_TYPE: FileSystemType = FileSystemType {
+    name: "devpts",
+    requires_device: false,
+    probe: devpts_probe,
+    construct: devpts_construct,
+};
+
+static DEVPTS: Lazy<ArcLockedDevFs> = Lazy::new(ArcLockedDevFs::empty);
+
+#[must_use]
+pub fn devpts() -> &'static ArcLockedDevFs {
+    &DEVPTS
+}
+
+fn devpts_probe(_device: Option<&SharedBlockDevice>, _options: &str) -> bool {
+    false
+}
+
+/// Like devfs, all mounts of devpts share the same slaves.
+fn devpts_construct(
+    _device: Option<SharedBlockDevice>,
+    options: &str,
+) -> Result<SharedFileSystem, ConstructError> {
+    if !options.is_empty() {
+        return Err(ConstructError::InvalidOption(options.into()));
+    }
+    Ok(Arc::new(RwLock::new(devpts().clone())))
+}
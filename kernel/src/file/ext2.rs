@@ -1,17 +1,101 @@
 use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
 use alloc::collections::BTreeMap;
+use alloc::string::ToString;
 use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::error::Error;
 use core::ops::{Deref, DerefMut};
 use core::sync::atomic::AtomicU64;
 use core::sync::atomic::Ordering::Relaxed;
 
 use ext2::{Ext2Fs, Inode, InodeAddress, Type};
 use filesystem::BlockDevice;
-use kernel_vfs::fs::{FileSystem, FsHandle};
+use kernel_device::block::{BlockBuf, BlockDevice as _};
+use kernel_vfs::fs::{DirEntry, FileSystem, FsHandle};
 use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, Path};
-use kernel_vfs::{CloseError, FsError, OpenError, ReadError, Stat, StatError, WriteError};
+use kernel_vfs::{
+    CloseError, FileType, FsError, OpenError, ReadDirError, ReadError, SharedFileSystem, Stat,
+    StatError, WriteError,
+};
+use linkme::distributed_slice;
 use spin::RwLock;
 
+use crate::driver::block::SharedBlockDevice;
+use crate::file::fs_type::{ConstructError, FILESYSTEM_TYPES, FileSystemType};
+
+#[distributed_slice(FILESYSTEM_TYPES)]
+static EXT2_TYPE: FileSystemType = FileSystemType {
+    name: "ext2",
+    requires_device: true,
+    probe: ext2_probe,
+    construct: ext2_construct,
+};
+
+/// Checks the magic number of the superblock, which starts at byte 1024.
+fn ext2_probe(device: Option<&SharedBlockDevice>, _options: &str) -> bool {
+    const MAGIC_OFFSET: usize = 1024 + 56;
+
+    let Some(device) = device else {
+        return false;
+    };
+    let mut buf = BlockBuf::new();
+    if device
+        .write()
+        .read_block(MAGIC_OFFSET / 512, &mut buf)
+        .is_err()
+    {
+        return false;
+    }
+    let offset = MAGIC_OFFSET % 512;
+    u16::from_le_bytes([buf[offset], buf[offset + 1]]) == 0xEF53
+}
+
+fn ext2_construct(
+    device: Option<SharedBlockDevice>,
+    options: &str,
+) -> Result<SharedFileSystem, ConstructError> {
+    if !options.is_empty() {
+        return Err(ConstructError::InvalidOption(options.into()));
+    }
+    let device = device.ok_or(ConstructError::DeviceRequired)?;
+    let fs = Ext2Fs::try_new(ArcLockedBlockDevice(device))
+        .map_err(|_| ConstructError::InvalidFileSystem)?;
+    Ok(Arc::new(RwLock::new(VirtualExt2Fs::from(fs))))
+}
+
+/// Adapts a registered block device to the block device interface of the
+/// ext2 implementation.
+pub struct ArcLockedBlockDevice(pub SharedBlockDevice);
+
+impl BlockDevice for ArcLockedBlockDevice {
+    type Error = Box<dyn Error>;
+
+    fn sector_size(&self) -> usize {
+        512
+    }
+
+    fn sector_count(&self) -> usize {
+        self.0.read().block_count()
+    }
+
+    fn read_sector(&self, sector_index: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut read_buf = BlockBuf::new();
+        self.0.write().read_block(sector_index, &mut read_buf)?;
+        buf.copy_from_slice(&read_buf[..]);
+        Ok(buf.len())
+    }
+
+    fn write_sector(&mut self, sector_index: usize, buf: &[u8]) -> Result<usize, Self::Error> {
+        let mut write_buf = BlockBuf::new();
+        write_buf.copy_from_slice(buf);
+        self.0
+            .write()
+            .write_block(sector_index, &write_buf)
+            .map(|()| buf.len())
+    }
+}
+
 pub struct VirtualExt2Fs<T> {
     ext2fs: Ext2Fs<T>,
     handles: BTreeMap<FsHandle, Arc<(AbsoluteOwnedPath, RwLock<VirtualExt2Inode>)>>,
@@ -108,12 +192,51 @@ where
         let guard = inode.read();
         match &guard.inner {
             Inner::RegularFile(file) => {
+                stat.file_type = FileType::RegularFile;
                 stat.size = file.len();
             }
-            _ => todo!(),
+            Inner::Directory(_) => {
+                stat.file_type = FileType::Directory;
+            }
         }
         Ok(())
     }
+
+    fn read_dir(&mut self, handle: FsHandle) -> Result<Vec<DirEntry>, ReadDirError> {
+        let inode = &self.handles.get(&handle).ok_or(FsError::InvalidHandle)?.1;
+
+        let guard = inode.read();
+        let Inner::Directory(dir) = &guard.inner else {
+            return Err(ReadDirError::NotADirectory);
+        };
+
+        let mut entries = Vec::new();
+        for entry in self
+            .ext2fs
+            .list_dir(dir)
+            .map_err(|_| ReadDirError::ReadFailed)?
+        {
+            let Some(name) = entry
+                .name()
+                .filter(|&name| name != "." && name != "..")
+                .map(ToString::to_string)
+            else {
+                continue;
+            };
+            let (_, inode) = self
+                .ext2fs
+                .resolve_dir_entry(entry)
+                .map_err(|_| ReadDirError::ReadFailed)?;
+            // other inode types can't be opened by this driver yet
+            let file_type = if inode.typ() == Type::Directory {
+                FileType::Directory
+            } else {
+                FileType::RegularFile
+            };
+            entries.push(DirEntry { name, file_type });
+        }
+        Ok(entries)
+    }
 }
 
 impl<T> VirtualExt2Fs<T>
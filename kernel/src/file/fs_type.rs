@@ -0,0 +1,149 @@
+//! The registry of file system types that can be mounted. Drivers register
+//! their types in [`FILESYSTEM_TYPES`], similar to PCI drivers.
+
+use alloc::string::{String, ToString};
+
+use kernel_cmdline::RootSource;
+use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{MountError, MountOptions, SharedFileSystem};
+use linkme::distributed_slice;
+use log::{Level, log_enabled, trace};
+use thiserror::Error;
+
+use crate::driver::block::{BlockDevices, SharedBlockDevice};
+use crate::file::vfs;
+
+#[distributed_slice]
+pub static FILESYSTEM_TYPES: [FileSystemType] = [..];
+
+pub struct FileSystemType {
+    pub name: &'static str,
+    /// Whether the file system is backed by a block device. Types that
+    /// aren't backed by a device ignore the source of a mount.
+    pub requires_device: bool,
+    /// Returns whether the device contains a file system of this type.
+    /// This is used to detect the type if none is given.
+    pub probe: fn(Option<&SharedBlockDevice>, &str) -> bool,
+    /// Creates a new instance of the file system from the device and the
+    /// comma separated, file system specific options.
+    pub construct: fn(Option<SharedBlockDevice>, &str) -> Result<SharedFileSystem, ConstructError>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum ConstructError {
+    #[error("a source device is required")]
+    DeviceRequired,
+    #[error("the device doesn't contain a valid file system")]
+    InvalidFileSystem,
+    #[error("invalid option {0:?}")]
+    InvalidOption(String),
+    #[error("the source doesn't exist")]
+    SourceNotFound,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+pub enum MountFsError {
+    #[error("unknown file system type {0:?}")]
+    UnknownType(String),
+    #[error("device {0} not found")]
+    DeviceNotFound(String),
+    #[error("can't detect the file system type of {0}")]
+    UnknownFileSystem(String),
+    #[error("{0}")]
+    Construct(#[from] ConstructError),
+    #[error("{0}")]
+    Mount(#[from] MountError),
+}
+
+impl FileSystemType {
+    #[must_use]
+    pub fn by_name(name: &str) -> Option<&'static Self> {
+        FILESYSTEM_TYPES.iter().find(|fs_type| fs_type.name == name)
+    }
+
+    /// Returns the first device backed type whose probe accepts the device.
+    #[must_use]
+    pub fn detect(device: &SharedBlockDevice, options: &str) -> Option<&'static Self> {
+        FILESYSTEM_TYPES
+            .iter()
+            .filter(|fs_type| fs_type.requires_device)
+            .find(|fs_type| (fs_type.probe)(Some(device), options))
+    }
+}
+
+pub fn init() {
+    if log_enabled!(Level::Trace) {
+        FILESYSTEM_TYPES
+            .iter()
+            .for_each(|fs_type| trace!("have file system type: {}", fs_type.name));
+    }
+}
+
+/// Mounts a new instance of a file system at `mount_point`. For device
+/// backed types, `source` takes the same values as the `root=` option. If
+/// `fs_type` is `None`, the type is detected by probing the device.
+///
+/// # Errors
+/// Returns an error if the type is unknown or can't be detected, if the
+/// device can't be found or doesn't contain a valid file system, or if the
+/// file system can't be mounted at `mount_point`.
+pub fn mount(
+    mount_point: &AbsolutePath,
+    source: &str,
+    fs_type: Option<&str>,
+    options: &str,
+    read_only: bool,
+) -> Result<(), MountFsError> {
+    let fs_type = match fs_type {
+        Some(name) => {
+            FileSystemType::by_name(name).ok_or_else(|| MountFsError::UnknownType(name.into()))?
+        }
+        None => FileSystemType::detect(&source_device(source)?, options)
+            .ok_or_else(|| MountFsError::UnknownFileSystem(source.into()))?,
+    };
+
+    let device = if fs_type.requires_device {
+        Some(source_device(source)?)
+    } else {
+        None
+    };
+    let fs = (fs_type.construct)(device, options)?;
+
+    vfs().write().mount_shared(
+        mount_point,
+        fs,
+        MountOptions {
+            source: source.to_string(),
+            fs_type: fs_type.name.to_string(),
+            read_only,
+        },
+    )?;
+    Ok(())
+}
+
+/// Returns the block device or partition that is described by `source`.
+#[must_use]
+pub fn find_device(source: &RootSource) -> Option<SharedBlockDevice> {
+    match source {
+        RootSource::Device {
+            disk,
+            partition: None,
+        } => BlockDevices::by_id(*disk),
+        RootSource::Device {
+            disk,
+            partition: Some(number),
+        } => BlockDevices::partition(*disk, *number).map(|entry| entry.device),
+        RootSource::Partition(selector) => {
+            BlockDevices::find_partition(selector).map(|entry| entry.device)
+        }
+        RootSource::Initramfs => None,
+    }
+}
+
+fn source_device(source: &str) -> Result<SharedBlockDevice, MountFsError> {
+    source
+        .parse::<RootSource>()
+        .ok()
+        .and_then(|source| find_device(&source))
+        .ok_or_else(|| MountFsError::DeviceNotFound(source.into()))
+}
@@ -0,0 +1,169 @@
+//! The initramfs, a cpio newc archive that the bootloader loads as a module
+//! with the command line `initramfs`. Mounting it unpacks the archive into a
+//! new tmpfs, so machines without a disk can boot from it.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::slice::from_raw_parts;
+
+use kernel_cpio::{Archive, EntryType, ReadError};
+use kernel_tmpfs::{TmpFs, TmpFsOptions};
+use kernel_vfs::fs::FileSystem;
+use kernel_vfs::path::{AbsoluteOwnedPath, Path};
+use kernel_vfs::{CreateError, OpenError, SharedFileSystem, WriteError};
+use linkme::distributed_slice;
+use log::{info, warn};
+use spin::RwLock;
+use thiserror::Error;
+
+use crate::U64Ext;
+use crate::driver::block::SharedBlockDevice;
+use crate::file::fs_type::{ConstructError, FILESYSTEM_TYPES, FileSystemType};
+use crate::file::tmpfs::now;
+use crate::limine::MODULE_REQUEST;
+
+/// The command line of the module that contains the initramfs.
+const MODULE_CMDLINE: &str = "initramfs";
+
+#[distributed_slice(FILESYSTEM_TYPES)]
+static INITRAMFS_TYPE: FileSystemType = FileSystemType {
+    name: "initramfs",
+    requires_device: false,
+    probe: initramfs_probe,
+    construct: initramfs_construct,
+};
+
+/// Returns the contents of the initramfs module, if the bootloader loaded
+/// one.
+#[must_use]
+pub fn initramfs() -> Option<&'static [u8]> {
+    let module = MODULE_REQUEST
+        .get_response()?
+        .modules()
+        .iter()
+        .find(|module| module.string().to_str() == Ok(MODULE_CMDLINE))?;
+    Some(unsafe {
+        // Safety: modules are kept mapped in the higher half, see
+        // `mem::address_space`.
+        from_raw_parts(module.addr(), module.size().into_usize())
+    })
+}
+
+fn initramfs_probe(_device: Option<&SharedBlockDevice>, _options: &str) -> bool {
+    false
+}
+
+/// Unpacks the initramfs into a new tmpfs. The options are passed on to
+/// tmpfs.
+fn initramfs_construct(
+    _device: Option<SharedBlockDevice>,
+    options: &str,
+) -> Result<SharedFileSystem, ConstructError> {
+    let options = if options.is_empty() {
+        "mode=755"
+    } else {
+        options
+    };
+    let tmpfs_options = options
+        .parse::<TmpFsOptions>()
+        .map_err(|_| ConstructError::InvalidOption(options.into()))?;
+    let data = initramfs().ok_or(ConstructError::SourceNotFound)?;
+
+    let mut fs = TmpFs::new(&tmpfs_options, now);
+    let count = unpack(&mut fs, data).map_err(|e| {
+        warn!("failed to unpack initramfs: {e}");
+        ConstructError::InvalidFileSystem
+    })?;
+    info!(
+        "unpacked {count} entries from initramfs ({} KiB)",
+        data.len() / 1024
+    );
+
+    Ok(Arc::new(RwLock::new(fs)))
+}
+
+#[derive(Debug, Error)]
+enum UnpackError {
+    #[error("invalid archive: {0}")]
+    Archive(#[from] ReadError),
+    #[error("invalid path {0:?}")]
+    InvalidPath(String),
+    #[error("symlink target of {0} is not valid UTF-8")]
+    InvalidSymlink(AbsoluteOwnedPath),
+    #[error("failed to create {0}: {1}")]
+    Create(AbsoluteOwnedPath, CreateError),
+    #[error("failed to open {0}: {1}")]
+    Open(AbsoluteOwnedPath, OpenError),
+    #[error("failed to write {0}: {1}")]
+    Write(AbsoluteOwnedPath, WriteError),
+}
+
+/// Creates every entry of the archive in `fs` and returns the number of
+/// entries. Entries that aren't directories, regular files or symlinks are
+/// skipped.
+fn unpack(fs: &mut impl FileSystem, data: &[u8]) -> Result<usize, UnpackError> {
+    let mut count = 0;
+    for entry in Archive::new(data).entries() {
+        let entry = entry?;
+        let name = entry.name.trim_start_matches("./").trim_start_matches('/');
+        if name.is_empty() || name == "." {
+            continue;
+        }
+        let path = AbsoluteOwnedPath::try_from(format!("/{name}").as_str())
+            .map_err(|_| UnpackError::InvalidPath(name.into()))?;
+
+        match entry.entry_type() {
+            EntryType::Directory => match fs.mkdir(path.as_ref(), entry.permissions()) {
+                Ok(()) | Err(CreateError::AlreadyExists) => {}
+                Err(e) => return Err(UnpackError::Create(path, e)),
+            },
+            EntryType::RegularFile => {
+                if let Err(e) = fs.create(path.as_ref(), entry.permissions()) {
+                    return Err(UnpackError::Create(path, e));
+                }
+                write_file(fs, &path, entry.data)?;
+            }
+            EntryType::Symlink => {
+                let Ok(target) = core::str::from_utf8(entry.data) else {
+                    return Err(UnpackError::InvalidSymlink(path));
+                };
+                if let Err(e) = fs.symlink(Path::new(target), path.as_ref()) {
+                    return Err(UnpackError::Create(path, e));
+                }
+            }
+            EntryType::Other => {
+                warn!("skipping special file {path} in initramfs");
+                continue;
+            }
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn write_file(
+    fs: &mut impl FileSystem,
+    path: &AbsoluteOwnedPath,
+    mut data: &[u8],
+) -> Result<(), UnpackError> {
+    let handle = fs
+        .open(path.as_ref())
+        .map_err(|e| UnpackError::Open(path.clone(), e))?;
+    let mut offset = 0;
+    let result = loop {
+        if data.is_empty() {
+            break Ok(());
+        }
+        match fs.write(handle, data, offset) {
+            Ok(0) => break Err(UnpackError::Write(path.clone(), WriteError::NoSpace)),
+            Ok(n) => {
+                offset += n;
+                data = &data[n..];
+            }
+            Err(e) => break Err(UnpackError::Write(path.clone(), e)),
+        }
+    };
+    let _ = fs.close(handle);
+    result
+}
@@ -1,15 +1,27 @@
-use core::ops::Deref;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
 use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::Waker;
 
-use kernel_vfs::Vfs;
+use kernel_syscall::access::Pollable;
+use kernel_syscall::epoll::Epoll;
 use kernel_vfs::node::VfsNode;
-use kernel_vfs::path::AbsolutePath;
-use spin::RwLock;
+use kernel_vfs::{Readiness, Vfs};
+use spin::{Mutex, RwLock};
 
-use crate::file::devfs::devfs;
+use crate::net::socket::Socket;
+use crate::net::unix::UnixSocket;
 
 pub mod devfs;
+pub mod devpts;
 pub mod ext2;
+pub mod fs_type;
+pub mod initramfs;
+pub mod procfs;
+pub mod root;
+pub mod sysfs;
+pub mod tmpfs;
 
 static VFS: RwLock<Vfs> = RwLock::new(Vfs::new());
 
@@ -18,26 +30,51 @@ pub fn vfs() -> &'static RwLock<Vfs> {
     &VFS
 }
 
+/// Initializes devfs and the file system types. File systems are mounted
+/// later, when the root file system is mounted (see [`root::mount_root`]).
 pub fn init() {
     devfs::init();
-
-    VFS.write()
-        .mount(AbsolutePath::try_new("/dev").unwrap(), devfs().clone())
-        .expect("should be able to mount devfs");
+    fs_type::init();
 }
 
 #[derive(Debug)]
 pub struct OpenFileDescription {
     position: AtomicU64,
-    node: VfsNode,
+    /// Counts the reads and writes, see [`Pollable::generation`].
+    generation: AtomicU64,
+    file: OpenFile,
+}
+
+/// What an open file description refers to.
+#[derive(Debug, Clone)]
+pub enum OpenFile {
+    Node(VfsNode),
+    Socket(Arc<Socket>),
+    UnixSocket(Arc<UnixSocket>),
+    Epoll(Arc<Mutex<Epoll<OpenFileDescription>>>),
 }
 
 impl From<VfsNode> for OpenFileDescription {
     fn from(node: VfsNode) -> Self {
-        Self {
-            position: AtomicU64::new(0),
-            node,
-        }
+        Self::new(OpenFile::Node(node))
+    }
+}
+
+impl From<Arc<Socket>> for OpenFileDescription {
+    fn from(socket: Arc<Socket>) -> Self {
+        Self::new(OpenFile::Socket(socket))
+    }
+}
+
+impl From<Arc<UnixSocket>> for OpenFileDescription {
+    fn from(socket: Arc<UnixSocket>) -> Self {
+        Self::new(OpenFile::UnixSocket(socket))
+    }
+}
+
+impl From<Arc<Mutex<Epoll<OpenFileDescription>>>> for OpenFileDescription {
+    fn from(epoll: Arc<Mutex<Epoll<OpenFileDescription>>>) -> Self {
+        Self::new(OpenFile::Epoll(epoll))
     }
 }
 
@@ -46,21 +83,104 @@ impl Clone for OpenFileDescription {
         let position = self.position.load(Ordering::Relaxed);
         Self {
             position: AtomicU64::new(position),
-            node: self.node.clone(),
+            generation: AtomicU64::new(0),
+            file: self.file.clone(),
         }
     }
 }
 
-impl Deref for OpenFileDescription {
-    type Target = VfsNode;
-
-    fn deref(&self) -> &Self::Target {
-        &self.node
+impl OpenFileDescription {
+    fn new(file: OpenFile) -> Self {
+        Self {
+            position: AtomicU64::new(0),
+            generation: AtomicU64::new(0),
+            file,
+        }
     }
-}
 
-impl OpenFileDescription {
     pub fn position(&self) -> &AtomicU64 {
         &self.position
     }
+
+    pub fn file(&self) -> &OpenFile {
+        &self.file
+    }
+
+    /// The node of the file, unless the description refers to a socket.
+    pub fn node(&self) -> Option<&VfsNode> {
+        match &self.file {
+            OpenFile::Node(node) => Some(node),
+            OpenFile::Socket(_) | OpenFile::UnixSocket(_) | OpenFile::Epoll(_) => None,
+        }
+    }
+
+    pub fn socket(&self) -> Option<&Arc<Socket>> {
+        match &self.file {
+            OpenFile::Socket(socket) => Some(socket),
+            OpenFile::Node(_) | OpenFile::UnixSocket(_) | OpenFile::Epoll(_) => None,
+        }
+    }
+
+    pub fn unix_socket(&self) -> Option<&Arc<UnixSocket>> {
+        match &self.file {
+            OpenFile::UnixSocket(socket) => Some(socket),
+            OpenFile::Node(_) | OpenFile::Socket(_) | OpenFile::Epoll(_) => None,
+        }
+    }
+
+    pub fn epoll(&self) -> Option<&Arc<Mutex<Epoll<OpenFileDescription>>>> {
+        match &self.file {
+            OpenFile::Epoll(epoll) => Some(epoll),
+            OpenFile::Node(_) | OpenFile::Socket(_) | OpenFile::UnixSocket(_) => None,
+        }
+    }
+
+    /// Counts a read or write, or another operation that may consume what
+    /// made the file ready, like accepting a connection.
+    pub fn count_io(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The path of the file, or a name like `socket:[id]` for files that
+    /// aren't in the file system, like on Linux.
+    pub fn name(&self) -> String {
+        match &self.file {
+            OpenFile::Node(node) => node.path().to_string(),
+            OpenFile::Socket(socket) => format!("socket:[{}]", socket.id()),
+            OpenFile::UnixSocket(socket) => format!("socket:[{}]", socket.id()),
+            OpenFile::Epoll(_) => "anon_inode:[eventpoll]".to_string(),
+        }
+    }
+}
+
+impl Pollable for OpenFileDescription {
+    /// An epoll instance is readable when it has events.
+    fn readiness(&self) -> Readiness {
+        match &self.file {
+            OpenFile::Node(node) => node.poll(),
+            OpenFile::Socket(socket) => socket.poll(),
+            OpenFile::UnixSocket(socket) => socket.poll(),
+            OpenFile::Epoll(epoll) => Readiness {
+                readable: epoll.lock().is_ready(),
+                ..Readiness::default()
+            },
+        }
+    }
+
+    fn register_waker(&self, waker: &Waker) {
+        match &self.file {
+            OpenFile::Node(node) => node.register_waker(waker),
+            OpenFile::Socket(socket) => socket.register_waker(waker),
+            OpenFile::UnixSocket(socket) => socket.register_waker(waker),
+            OpenFile::Epoll(epoll) => epoll.lock().register_waker(waker),
+        }
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    fn is_epoll(&self) -> bool {
+        matches!(self.file, OpenFile::Epoll(_))
+    }
 }
@@ -0,0 +1,359 @@
+//! The `/proc` file system. Its contents are generated from the process tree
+//! and the kernel state whenever a node is opened.
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::fmt::{Display, Write};
+use core::sync::atomic::Ordering::Relaxed;
+
+use kernel_pseudofs::{Provider, PseudoFs, PseudoNode, entry};
+use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{FileType, SharedFileSystem};
+use linkme::distributed_slice;
+use raw_cpuid::CpuId;
+use spin::RwLock;
+
+use crate::UsizeExt;
+use crate::arch::idt::{interrupt_counts, interrupt_name};
+use crate::driver::block::SharedBlockDevice;
+use crate::file::fs_type::{ConstructError, FILESYSTEM_TYPES, FileSystemType};
+use crate::file::vfs;
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::cpu_count;
+use crate::mcore::mtask::process::Process;
+use crate::mcore::mtask::process::mem::MemoryRegion;
+use crate::mcore::mtask::process::tree::process_tree;
+use crate::mem::heap::Heap;
+use crate::mem::phys::PhysicalMemory;
+use crate::net::dns_servers;
+use crate::time::uptime;
+
+#[distributed_slice(FILESYSTEM_TYPES)]
+static PROCFS_TYPE: FileSystemType = FileSystemType {
+    name: "proc",
+    requires_device: false,
+    probe: procfs_probe,
+    construct: procfs_construct,
+};
+
+fn procfs_probe(_device: Option<&SharedBlockDevice>, _options: &str) -> bool {
+    false
+}
+
+fn procfs_construct(
+    _device: Option<SharedBlockDevice>,
+    options: &str,
+) -> Result<SharedFileSystem, ConstructError> {
+    if !options.is_empty() {
+        return Err(ConstructError::InvalidOption(options.into()));
+    }
+    Ok(Arc::new(RwLock::new(PseudoFs::new(ProcProvider))))
+}
+
+const GLOBAL_FILES: [&str; 5] = ["cpuinfo", "interrupts", "meminfo", "mounts", "uptime"];
+const NET_FILES: [&str; 1] = ["resolv.conf"];
+const PROCESS_FILES: [&str; 4] = ["cmdline", "maps", "stat", "status"];
+const PROCESS_SYMLINKS: [&str; 2] = ["cwd", "exe"];
+const TASK_FILES: [&str; 2] = ["stat", "status"];
+
+struct ProcProvider;
+
+impl Provider for ProcProvider {
+    fn node(&self, path: &AbsolutePath) -> Option<PseudoNode> {
+        let components = path.filenames().collect::<Vec<_>>();
+        match components.as_slice() {
+            [] => Some(root_dir()),
+            ["self"] => {
+                let pid = ExecutionContext::load().current_process().pid();
+                Some(PseudoNode::symlink(format!("/proc/{pid}")))
+            }
+            // symlinks are only followed at the end of a path, so the files
+            // below `self` are served directly
+            ["self", rest @ ..] => process_node(ExecutionContext::load().current_process(), rest),
+            ["cpuinfo"] => Some(PseudoNode::file(cpuinfo())),
+            ["interrupts"] => Some(PseudoNode::file(interrupts())),
+            ["meminfo"] => Some(PseudoNode::file(meminfo())),
+            ["mounts"] => Some(PseudoNode::file(mounts())),
+            ["uptime"] => Some(PseudoNode::file(uptime_file())),
+            ["net"] => Some(PseudoNode::Directory(
+                NET_FILES
+                    .iter()
+                    .map(|name| entry(name, FileType::RegularFile))
+                    .collect(),
+            )),
+            ["net", "resolv.conf"] => Some(PseudoNode::file(resolv_conf())),
+            [pid, rest @ ..] => process_node(&find_process(pid)?, rest),
+        }
+    }
+}
+
+fn process_node(process: &Process, components: &[&str]) -> Option<PseudoNode> {
+    Some(match components {
+        [] => PseudoNode::Directory(
+            PROCESS_FILES
+                .iter()
+                .map(|name| entry(name, FileType::RegularFile))
+                .chain(
+                    PROCESS_SYMLINKS
+                        .iter()
+                        .map(|name| entry(name, FileType::Symlink)),
+                )
+                .chain(["fd", "task"].map(|name| entry(name, FileType::Directory)))
+                .collect(),
+        ),
+        ["cmdline"] => PseudoNode::file(process_cmdline(process)),
+        ["maps"] => PseudoNode::file(process_maps(process)),
+        ["stat"] => PseudoNode::file(process_stat(process, process.pid(), process.name())),
+        ["status"] => PseudoNode::file(process_status(process)),
+        ["cwd"] => PseudoNode::symlink(process.current_working_directory().read().to_string()),
+        ["exe"] => PseudoNode::symlink(process.executable_path()?.to_string()),
+        ["fd"] => PseudoNode::Directory(
+            process
+                .file_descriptors()
+                .read()
+                .keys()
+                .map(|&fd| entry(&c_int::from(fd).to_string(), FileType::Symlink))
+                .collect(),
+        ),
+        ["fd", fd] => {
+            let fd = fd.parse::<c_int>().ok()?;
+            let guard = process.file_descriptors().read();
+            let descriptor = guard.get(&fd.into())?;
+            PseudoNode::symlink(descriptor.file_description().name())
+        }
+        ["task"] => PseudoNode::Directory(
+            process
+                .tasks()
+                .read()
+                .keys()
+                .map(|tid| entry(&tid.to_string(), FileType::Directory))
+                .collect(),
+        ),
+        ["task", tid, rest @ ..] => {
+            let tid = tid.parse::<u64>().ok()?;
+            let name = process
+                .tasks()
+                .read()
+                .iter()
+                .find(|&(id, _)| *id == tid)
+                .map(|(_, name)| name.clone())?;
+            match rest {
+                [] => PseudoNode::Directory(
+                    TASK_FILES
+                        .iter()
+                        .map(|name| entry(name, FileType::RegularFile))
+                        .collect(),
+                ),
+                ["stat"] => PseudoNode::file(process_stat(process, tid, &name)),
+                ["status"] => PseudoNode::file(format!(
+                    "Name:\t{name}\nTid:\t{tid}\nTgid:\t{}\n",
+                    process.pid()
+                )),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    })
+}
+
+fn find_process(pid: &str) -> Option<Arc<Process>> {
+    let pid = pid.parse::<u64>().ok()?;
+    process_tree()
+        .read()
+        .processes
+        .iter()
+        .find(|&(id, _)| *id == pid)
+        .map(|(_, process)| process.clone())
+}
+
+fn root_dir() -> PseudoNode {
+    let pids = process_tree()
+        .read()
+        .processes
+        .keys()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    PseudoNode::Directory(
+        pids.iter()
+            .map(|pid| entry(pid, FileType::Directory))
+            .chain(Some(entry("self", FileType::Symlink)))
+            .chain(Some(entry("net", FileType::Directory)))
+            .chain(
+                GLOBAL_FILES
+                    .iter()
+                    .map(|name| entry(name, FileType::RegularFile)),
+            )
+            .collect(),
+    )
+}
+
+/// A single line with the 52 fields of `/proc/<pid>/stat` on Linux, see
+/// proc(5). Fields that aren't tracked here are 0.
+fn process_stat(process: &Process, id: impl Display, name: &str) -> String {
+    let vsize = process
+        .memory_regions()
+        .with_regions(|regions| regions.iter().map(MemoryRegion::size).sum::<usize>());
+
+    // the fields after the state, field 4 (ppid) is at index 0
+    let mut fields = [0_u64; 49];
+    fields[0] = process.ppid().into();
+    fields[1] = process.pgid().into();
+    // minflt
+    fields[6] = process.telemetry().page_faults.load(Relaxed).into_u64();
+    // num_threads
+    fields[16] = process.tasks().read().len().into_u64();
+    fields[19] = vsize.into_u64();
+
+    let mut stat = format!("{id} ({name}) {}", process_state(process));
+    for field in fields {
+        let _ = write!(stat, " {field}");
+    }
+    stat.push('\n');
+    stat
+}
+
+fn process_status(process: &Process) -> String {
+    format!(
+        "Name:\t{}\nState:\t{}\nPid:\t{}\nPPid:\t{}\nThreads:\t{}\nPageFaults:\t{}\n",
+        process.name(),
+        process_state(process),
+        process.pid(),
+        process.ppid(),
+        process.tasks().read().len(),
+        process.telemetry().page_faults.load(Relaxed),
+    )
+}
+
+fn process_state(process: &Process) -> char {
+    if process.exit_code().read().is_some() {
+        'Z'
+    } else {
+        'R'
+    }
+}
+
+/// The arguments of the process are not kept after it was started, so this
+/// only contains the executable path, or the name of the process if it
+/// wasn't started from an executable.
+fn process_cmdline(process: &Process) -> String {
+    let mut cmdline = process
+        .executable_path()
+        .map_or_else(|| process.name().to_owned(), ToString::to_string);
+    cmdline.push('\0');
+    cmdline
+}
+
+/// One line per memory region, of the form `start-end kind [path]`.
+fn process_maps(process: &Process) -> String {
+    process.memory_regions().with_regions(|regions| {
+        let mut maps = String::new();
+        for region in regions {
+            let start = region.addr().as_u64();
+            let end = start + region.size().into_u64();
+            let _ = match region {
+                MemoryRegion::Lazy(_) => writeln!(maps, "{start:016x}-{end:016x} lazy"),
+                MemoryRegion::Mapped(_) => writeln!(maps, "{start:016x}-{end:016x} mapped"),
+                MemoryRegion::FileBacked(region) => writeln!(
+                    maps,
+                    "{start:016x}-{end:016x} file {}",
+                    region.node().path()
+                ),
+                MemoryRegion::Device(region) => writeln!(
+                    maps,
+                    "{start:016x}-{end:016x} device {}",
+                    region.node().path()
+                ),
+            };
+        }
+        maps
+    })
+}
+
+fn meminfo() -> String {
+    // physical memory is counted in 4 KiB frames
+    let frame_kib = 4;
+    format!(
+        "MemTotal:\t{} kB\nMemFree:\t{} kB\nHeapTotal:\t{} kB\nHeapUsed:\t{} kB\nHeapFree:\t{} kB\n",
+        PhysicalMemory::usable_frames() * frame_kib,
+        PhysicalMemory::free_frames() * frame_kib,
+        Heap::size() / 1024,
+        Heap::used() / 1024,
+        Heap::free() / 1024,
+    )
+}
+
+fn cpuinfo() -> String {
+    let cpuid = CpuId::new();
+    let vendor = cpuid
+        .get_vendor_info()
+        .map(|info| info.as_str().to_owned())
+        .unwrap_or_default();
+    let model_name = cpuid
+        .get_processor_brand_string()
+        .map(|brand| brand.as_str().trim().to_owned())
+        .unwrap_or_default();
+    let (family, model, stepping) = cpuid
+        .get_feature_info()
+        .map(|info| (info.family_id(), info.model_id(), info.stepping_id()))
+        .unwrap_or_default();
+
+    let mut cpuinfo = String::new();
+    for processor in 0..cpu_count() {
+        let _ = write!(
+            cpuinfo,
+            "processor\t: {processor}\nvendor_id\t: {vendor}\ncpu family\t: {family}\nmodel\t\t: {model}\nmodel name\t: {model_name}\nstepping\t: {stepping}\n\n"
+        );
+    }
+    cpuinfo
+}
+
+fn uptime_file() -> String {
+    let uptime = uptime();
+    format!("{}.{:02}\n", uptime.as_secs(), uptime.subsec_millis() / 10)
+}
+
+/// One line per mount, of the form `source mount_point type rw|ro 0 0`.
+fn mounts() -> String {
+    let mut mounts = String::new();
+    for mount in vfs().read().mounts() {
+        let _ = writeln!(
+            mounts,
+            "{} {} {} {} 0 0",
+            if mount.options.source.is_empty() {
+                "none"
+            } else {
+                &mount.options.source
+            },
+            mount.mount_point,
+            mount.options.fs_type,
+            if mount.options.read_only { "ro" } else { "rw" }
+        );
+    }
+    mounts
+}
+
+fn interrupts() -> String {
+    let mut interrupts = String::new();
+    for (vector, count) in interrupt_counts() {
+        let _ = writeln!(
+            interrupts,
+            "{vector:>3}: {count:>10}  {}",
+            interrupt_name(vector).unwrap_or("")
+        );
+    }
+    interrupts
+}
+
+/// The DNS servers of the network stack in the format of
+/// `/etc/resolv.conf`, one `nameserver` line per server.
+fn resolv_conf() -> String {
+    let mut resolv_conf = String::new();
+    for server in dns_servers() {
+        let _ = writeln!(resolv_conf, "nameserver {server}");
+    }
+    resolv_conf
+}
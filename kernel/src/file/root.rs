@@ -0,0 +1,116 @@
+use alloc::string::ToString;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::{Acquire, Release};
+
+use kernel_cmdline::{BootConfig, RootSource};
+use kernel_vfs::path::{AbsolutePath, ROOT};
+use log::{info, warn};
+use thiserror::Error;
+
+use crate::file::fs_type::{self, MountFsError};
+use crate::file::initramfs::initramfs;
+
+/// The root device that is used if the command line doesn't specify one
+/// and the bootloader didn't load an initramfs.
+const DEFAULT_ROOT: RootSource = RootSource::Device {
+    disk: 0,
+    partition: None,
+};
+
+static ROOT_MOUNTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Eq, PartialEq, Error)]
+#[error("failed to mount root file system {root}: {error}")]
+pub struct MountRootError {
+    pub root: RootSource,
+    #[source]
+    pub error: MountFsError,
+}
+
+/// Mounts the root file system at `/`, as described by the `root=`,
+/// `rootfstype=` and `ro` options of the given config, devfs at `/dev`, procfs
+/// at `/proc`, sysfs at `/sys` and tmpfs at `/tmp` and `/run`.
+/// If no root is given, the initramfs is used if the bootloader loaded one,
+/// otherwise the first block device. If no file system type is given, it is
+/// detected from the device.
+///
+/// # Errors
+/// Returns an error if the root device can't be found, or if it doesn't
+/// contain a file system of the requested type.
+pub fn mount_root(config: &BootConfig) -> Result<(), MountRootError> {
+    let root = config.root.clone().unwrap_or_else(|| {
+        if initramfs().is_some() {
+            RootSource::Initramfs
+        } else {
+            DEFAULT_ROOT
+        }
+    });
+    let root_fs_type = config
+        .root_fs_type
+        .as_deref()
+        .or((root == RootSource::Initramfs).then_some("initramfs"));
+    info!(
+        "mounting {root} as {} at / ({})",
+        root_fs_type.unwrap_or("auto"),
+        if config.read_only { "ro" } else { "rw" }
+    );
+
+    fs_type::mount(ROOT, &root.to_string(), root_fs_type, "", config.read_only)
+        .map_err(|error| MountRootError { root, error })?;
+    ROOT_MOUNTED.store(true, Release);
+
+    if let Err(e) = fs_type::mount(
+        AbsolutePath::try_new("/dev").unwrap(),
+        "devfs",
+        Some("devfs"),
+        "",
+        false,
+    ) {
+        warn!("failed to mount devfs at /dev: {e}");
+    }
+    if let Err(e) = fs_type::mount(
+        AbsolutePath::try_new("/dev/pts").unwrap(),
+        "devpts",
+        Some("devpts"),
+        "",
+        false,
+    ) {
+        warn!("failed to mount devpts at /dev/pts: {e}");
+    }
+    if let Err(e) = fs_type::mount(
+        AbsolutePath::try_new("/proc").unwrap(),
+        "proc",
+        Some("proc"),
+        "",
+        false,
+    ) {
+        warn!("failed to mount procfs at /proc: {e}");
+    }
+    if let Err(e) = fs_type::mount(
+        AbsolutePath::try_new("/sys").unwrap(),
+        "sysfs",
+        Some("sysfs"),
+        "",
+        false,
+    ) {
+        warn!("failed to mount sysfs at /sys: {e}");
+    }
+    for (mount_point, options) in [("/tmp", "mode=1777"), ("/run", "mode=755")] {
+        if let Err(e) = fs_type::mount(
+            AbsolutePath::try_new(mount_point).unwrap(),
+            "tmpfs",
+            Some("tmpfs"),
+            options,
+            false,
+        ) {
+            warn!("failed to mount tmpfs at {mount_point}: {e}");
+        }
+    }
+
+    Ok(())
+}
+
+#[must_use]
+pub fn is_root_mounted() -> bool {
+    ROOT_MOUNTED.load(Acquire)
+}
@@ -0,0 +1,202 @@
+//! The `/sys` file system, which describes the devices that the kernel knows
+//! about. Its contents are generated whenever a node is opened.
+//!
+//! The layout is
+//! - `bus/pci/devices/<address>/` with `vendor`, `device`, `class`,
+//!   `revision`, `resources`, `irq` and, if a driver is bound, `driver`
+//! - `bus/pci/drivers/<driver>/` with a symlink to every bound device
+//! - `block/<name>/` with `size` (in sectors) and `sector_size`
+//! - `devices/raw/<id>/` with `physical_memory`
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use kernel_device::Device;
+use kernel_device::block::BlockDevice;
+use kernel_pseudofs::{Provider, PseudoFs, PseudoNode, entry};
+use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{FileType, SharedFileSystem};
+use linkme::distributed_slice;
+use spin::RwLock;
+use x86_64::structures::paging::{PageSize, Size4KiB};
+
+use crate::driver::block::{BlockDevices, SharedBlockDevice};
+use crate::driver::pci::{PCI_DRIVERS, PciFunction, pci_functions};
+use crate::driver::raw::RawDevices;
+use crate::file::fs_type::{ConstructError, FILESYSTEM_TYPES, FileSystemType};
+
+#[distributed_slice(FILESYSTEM_TYPES)]
+static SYSFS_TYPE: FileSystemType = FileSystemType {
+    name: "sysfs",
+    requires_device: false,
+    probe: sysfs_probe,
+    construct: sysfs_construct,
+};
+
+fn sysfs_probe(_device: Option<&SharedBlockDevice>, _options: &str) -> bool {
+    false
+}
+
+fn sysfs_construct(
+    _device: Option<SharedBlockDevice>,
+    options: &str,
+) -> Result<SharedFileSystem, ConstructError> {
+    if !options.is_empty() {
+        return Err(ConstructError::InvalidOption(options.into()));
+    }
+    Ok(Arc::new(RwLock::new(PseudoFs::new(SysProvider))))
+}
+
+/// The sector size of all block devices, see [`SharedBlockDevice`].
+const SECTOR_SIZE: usize = 512;
+
+const PCI_DEVICE_FILES: [&str; 6] = ["class", "device", "irq", "resources", "revision", "vendor"];
+const BLOCK_DEVICE_FILES: [&str; 2] = ["sector_size", "size"];
+
+struct SysProvider;
+
+impl Provider for SysProvider {
+    fn node(&self, path: &AbsolutePath) -> Option<PseudoNode> {
+        let components = path.filenames().collect::<Vec<_>>();
+        Some(match components.as_slice() {
+            [] => directory(["block", "bus", "devices"], FileType::Directory),
+            ["bus"] => directory(["pci"], FileType::Directory),
+            ["bus", "pci"] => directory(["devices", "drivers"], FileType::Directory),
+            ["bus", "pci", "devices"] => directory(
+                pci_functions()
+                    .iter()
+                    .map(|function| function.address.to_string()),
+                FileType::Directory,
+            ),
+            ["bus", "pci", "devices", address, rest @ ..] => {
+                pci_device_node(&find_pci_function(address)?, rest)?
+            }
+            ["bus", "pci", "drivers"] => {
+                directory(PCI_DRIVERS.iter().map(|d| d.name), FileType::Directory)
+            }
+            ["bus", "pci", "drivers", name] => {
+                let driver = PCI_DRIVERS.iter().find(|d| d.name == *name)?;
+                directory(
+                    pci_functions()
+                        .iter()
+                        .filter(|function| function.driver == Some(driver.name))
+                        .map(|function| function.address.to_string()),
+                    FileType::Symlink,
+                )
+            }
+            ["bus", "pci", "drivers", name, address] => {
+                let function = find_pci_function(address)?;
+                if function.driver != Some(*name) {
+                    return None;
+                }
+                PseudoNode::symlink(format!("/sys/bus/pci/devices/{address}"))
+            }
+            ["block"] => directory(
+                block_devices().into_iter().map(|(name, _)| name),
+                FileType::Directory,
+            ),
+            ["block", name, rest @ ..] => {
+                let (_, device) = block_devices().into_iter().find(|(n, _)| n == name)?;
+                match rest {
+                    [] => directory(BLOCK_DEVICE_FILES, FileType::RegularFile),
+                    ["size"] => PseudoNode::file(format!("{}\n", device.read().block_count())),
+                    ["sector_size"] => PseudoNode::file(format!("{SECTOR_SIZE}\n")),
+                    _ => return None,
+                }
+            }
+            ["devices"] => directory(["raw"], FileType::Directory),
+            ["devices", "raw"] => directory(
+                RawDevices::all()
+                    .iter()
+                    .map(|device| device.read().id().to_string()),
+                FileType::Directory,
+            ),
+            ["devices", "raw", id, rest @ ..] => {
+                let device = RawDevices::all()
+                    .into_iter()
+                    .find(|device| device.read().id().to_string() == *id)?;
+                match rest {
+                    [] => directory(["physical_memory"], FileType::RegularFile),
+                    ["physical_memory"] => {
+                        let range = device.read().physical_memory();
+                        let start = range.start.start_address().as_u64();
+                        let end = range.end.start_address().as_u64() + Size4KiB::SIZE - 1;
+                        PseudoNode::file(format!("{start:#018x}-{end:#018x}\n"))
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        })
+    }
+}
+
+fn pci_device_node(function: &PciFunction, components: &[&str]) -> Option<PseudoNode> {
+    Some(match components {
+        [] => PseudoNode::Directory(
+            PCI_DEVICE_FILES
+                .iter()
+                .map(|name| entry(name, FileType::RegularFile))
+                .chain(function.driver.map(|_| entry("driver", FileType::Symlink)))
+                .collect(),
+        ),
+        ["vendor"] => PseudoNode::file(format!("{:#06x}\n", function.vendor_id)),
+        ["device"] => PseudoNode::file(format!("{:#06x}\n", function.device_id)),
+        ["class"] => PseudoNode::file(format!(
+            "{:#08x}\n",
+            (u32::from(function.class) << 16)
+                | (u32::from(function.subclass) << 8)
+                | u32::from(function.prog_if)
+        )),
+        ["revision"] => PseudoNode::file(format!("{:#04x}\n", function.revision_id)),
+        ["irq"] => PseudoNode::file(format!(
+            "{} {}\n",
+            function.interrupt_line, function.interrupt_pin
+        )),
+        ["resources"] => {
+            let mut resources = String::new();
+            for (index, bar) in &function.bars {
+                let _ = writeln!(resources, "{index} {bar}");
+            }
+            PseudoNode::file(resources)
+        }
+        ["driver"] => PseudoNode::symlink(format!("/sys/bus/pci/drivers/{}", function.driver?)),
+        _ => return None,
+    })
+}
+
+fn find_pci_function(address: &str) -> Option<PciFunction> {
+    pci_functions()
+        .into_iter()
+        .find(|function| function.address.to_string() == address)
+}
+
+/// Returns all block devices and partitions together with their names,
+/// which are the same as in devfs.
+fn block_devices() -> Vec<(String, SharedBlockDevice)> {
+    BlockDevices::ids()
+        .into_iter()
+        .filter_map(|id| Some((format!("blk{id}"), BlockDevices::by_id(id)?)))
+        .chain(
+            BlockDevices::partitions()
+                .into_iter()
+                .map(|((id, number), entry)| (format!("blk{id}p{number}"), entry.device)),
+        )
+        .collect()
+}
+
+fn directory<I, S>(names: I, file_type: FileType) -> PseudoNode
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    PseudoNode::Directory(
+        names
+            .into_iter()
+            .map(|name| entry(name.as_ref(), file_type))
+            .collect(),
+    )
+}
@@ -0,0 +1,42 @@
+use alloc::sync::Arc;
+
+use jiff::Timestamp;
+use kernel_tmpfs::{TmpFs, TmpFsOptions};
+use kernel_vfs::{SharedFileSystem, Timespec};
+use linkme::distributed_slice;
+use spin::RwLock;
+
+use crate::driver::block::SharedBlockDevice;
+use crate::file::fs_type::{ConstructError, FILESYSTEM_TYPES, FileSystemType};
+use crate::time::TimestampExt;
+
+#[distributed_slice(FILESYSTEM_TYPES)]
+static TMPFS_TYPE: FileSystemType = FileSystemType {
+    name: "tmpfs",
+    requires_device: false,
+    probe: tmpfs_probe,
+    construct: tmpfs_construct,
+};
+
+fn tmpfs_probe(_device: Option<&SharedBlockDevice>, _options: &str) -> bool {
+    false
+}
+
+fn tmpfs_construct(
+    _device: Option<SharedBlockDevice>,
+    options: &str,
+) -> Result<SharedFileSystem, ConstructError> {
+    let options = options
+        .parse::<TmpFsOptions>()
+        .map_err(|_| ConstructError::InvalidOption(options.into()))?;
+    Ok(Arc::new(RwLock::new(TmpFs::new(&options, now))))
+}
+
+/// The clock of all tmpfs instances.
+pub(crate) fn now() -> Timespec {
+    let now = Timestamp::now();
+    Timespec {
+        secs: now.as_second(),
+        nanos: now.subsec_nanosecond().unsigned_abs(),
+    }
+}
@@ -22,6 +22,11 @@ pub fn hpet() -> &'static RwLock<Hpet<'static>> {
     HPET.get().unwrap()
 }
 
+/// Like [`hpet`], but returns `None` if the HPET is not initialized yet.
+pub fn try_hpet() -> Option<&'static RwLock<Hpet<'static>>> {
+    HPET.get()
+}
+
 #[allow(clippy::missing_panics_doc)]
 pub fn init() {
     let acpi_tables = acpi_tables();
@@ -6,6 +6,7 @@ extern crate alloc;
 
 use ::log::info;
 use conquer_once::spin::OnceCell;
+use kernel_kmsg::Filter;
 
 use crate::driver::pci;
 #[cfg(target_arch = "x86_64")]
@@ -17,7 +18,9 @@ mod acpi;
 mod apic;
 mod arch;
 pub mod backtrace;
+pub mod cmdline;
 pub mod driver;
+pub mod fbcon;
 pub mod file;
 #[cfg(target_arch = "x86_64")]
 pub mod hpet;
@@ -26,11 +29,15 @@ pub mod limine;
 mod log;
 pub mod mcore;
 pub mod mem;
+pub mod net;
+pub mod random;
+pub mod rescue;
 mod serial;
 #[cfg(target_arch = "x86_64")]
 pub mod sse;
 pub mod syscall;
 pub mod time;
+pub mod tty;
 
 static BOOT_TIME_SECONDS: OnceCell<u64> = OnceCell::uninit();
 
@@ -48,18 +55,29 @@ pub fn init() {
 
     log::init();
     mem::init();
-    
+    fbcon::init();
+    // the command line is parsed into heap allocated values
+    cmdline::init();
+    log::set_filter(
+        cmdline::boot_config()
+            .log_level
+            .map_or_else(Filter::default, Filter::new),
+    );
+
     #[cfg(target_arch = "x86_64")]
     {
         acpi::init();
         apic::init();
         hpet::init();
+        random::init();
     }
-    
+
     backtrace::init();
     mcore::init();
+    tty::serial::init();
     file::init();
     pci::init();
+    net::init();
 
     info!("kernel initialized");
 }
@@ -88,4 +106,4 @@ impl UsizeExt for usize {
     fn into_u64(self) -> u64 {
         self as u64
     }
-}
\ No newline at end of file
+}
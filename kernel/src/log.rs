@@ -1,51 +1,223 @@
-use log::{Level, Metadata, Record};
+//! The kernel logger. Records are kept in a ring buffer, which can be read
+//! through `/dev/kmsg` and `syslog`, and are printed on the serial interface
+//! and the screen.
+
+use alloc::string::String;
+use alloc::vec;
+use core::sync::atomic::Ordering::Relaxed;
+use core::sync::atomic::{AtomicBool, AtomicUsize};
+use core::task::Waker;
+use core::{fmt, mem};
+
+use kernel_devfs::DevFile;
+use kernel_kmsg::{Filter, LogBuffer, MAX_FORMATTED_LEN, ReadKmsgError, split_priority};
+use kernel_vfs::{FileType, ReadError, Readiness, Stat, StatError, WriteError};
+use log::{Level, LevelFilter, Metadata, Record};
+use spin::{Mutex, RwLock};
+use x86_64::instructions::interrupts;
 
 use crate::mcore::context::ExecutionContext;
-use crate::serial_println;
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::{fbcon, serial, time};
+
+/// The number of records that are kept before the oldest are overwritten.
+const LOG_RECORDS: usize = 1024;
+
+struct Log {
+    buffer: LogBuffer<LOG_RECORDS>,
+    /// The position of reads with `syslog`, which is shared by all
+    /// processes.
+    syslog_seq: u64,
+    /// Records before this are hidden from `syslog` reads of the whole log.
+    clear_seq: u64,
+}
+
+static LOG: Mutex<Log> = Mutex::new(Log {
+    buffer: LogBuffer::new(),
+    syslog_seq: 0,
+    clear_seq: 0,
+});
+
+/// The tasks that wait for new records, through `/dev/kmsg` or `syslog`.
+static READERS: WaitQueue = WaitQueue::new();
+
+/// Everything is logged until the filter from the command line is set,
+/// which needs the heap.
+static FILTER: RwLock<Filter> = RwLock::new(Filter::new(LevelFilter::Trace));
+
+/// The least important level that is printed, as `LevelFilter as usize`.
+static CONSOLE_LEVEL: AtomicUsize = AtomicUsize::new(LevelFilter::Trace as usize);
+static CONSOLE_ENABLED: AtomicBool = AtomicBool::new(true);
 
 pub(crate) fn init() {
-    log::set_logger(&SerialLogger).unwrap();
-    log::set_max_level(::log::LevelFilter::Trace);
+    log::set_logger(&KernelLogger).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+}
+
+pub fn filter() -> Filter {
+    FILTER.read().clone()
+}
+
+/// Replaces the filter that decides which records are logged at all.
+pub fn set_filter(filter: Filter) {
+    log::set_max_level(filter.max_level());
+    let old = interrupts::without_interrupts(|| mem::replace(&mut *FILTER.write(), filter));
+    // freeing memory takes the heap lock, so it must not happen while other
+    // CPUs may wait for the filter with the heap locked
+    drop(old);
+}
+
+pub fn set_console_level(level: LevelFilter) {
+    CONSOLE_LEVEL.store(level as usize, Relaxed);
+}
+
+pub fn set_console_enabled(enabled: bool) {
+    CONSOLE_ENABLED.store(enabled, Relaxed);
+}
+
+fn console_enabled(level: Level) -> bool {
+    CONSOLE_ENABLED.load(Relaxed) && level as usize <= CONSOLE_LEVEL.load(Relaxed)
+}
+
+/// Reads the record at `*seq` in the format of `/dev/kmsg` and advances
+/// `*seq` past it.
+///
+/// # Errors
+/// Returns an error if there is no record at `*seq` yet or if it doesn't fit
+/// into `buf`.
+pub fn read_kmsg(seq: &mut u64, buf: &mut [u8]) -> Result<usize, ReadKmsgError> {
+    // `buf` may be user memory, and the page fault handler logs, so the
+    // record is copied out of the buffer first
+    let mut record = [0; MAX_FORMATTED_LEN];
+    let mut next_seq = *seq;
+    let len =
+        interrupts::without_interrupts(|| LOG.lock().buffer.read_kmsg(&mut next_seq, &mut record))?;
+    buf.get_mut(..len)
+        .ok_or(ReadKmsgError::BufferTooSmall)?
+        .copy_from_slice(&record[..len]);
+    *seq = next_seq;
+    Ok(len)
+}
+
+/// Whether there is a record at `seq` or after it, so that
+/// [`read_kmsg`] doesn't fail with [`ReadKmsgError::Empty`].
+pub fn has_kmsg(seq: u64) -> bool {
+    interrupts::without_interrupts(|| LOG.lock().buffer.iter_from(seq).next().is_some())
+}
+
+/// Reads whole records that weren't read with this function before in the
+/// format of `syslog`. Returns zero if there are no new records.
+pub fn read_syslog(buf: &mut [u8]) -> usize {
+    read_with_bounce_buffer(buf, |log, bounce| {
+        log.buffer.read_syslog(&mut log.syslog_seq, bounce)
+    })
+}
+
+/// Reads the newest records since the log was last cleared in the format of
+/// `syslog`.
+pub fn read_syslog_all(buf: &mut [u8]) -> usize {
+    read_with_bounce_buffer(buf, |log, bounce| {
+        log.buffer.read_syslog_tail(log.clear_seq, bounce)
+    })
+}
+
+/// Reads with the log locked into a kernel buffer, which is then copied into
+/// `buf`, like in [`read_kmsg`].
+fn read_with_bounce_buffer(
+    buf: &mut [u8],
+    read: impl FnOnce(&mut Log, &mut [u8]) -> usize,
+) -> usize {
+    let capacity = syslog_capacity();
+    let mut bounce = vec![0; buf.len().min(capacity)];
+    let len = interrupts::without_interrupts(|| read(&mut LOG.lock(), &mut bounce));
+    buf[..len].copy_from_slice(&bounce[..len]);
+    len
+}
+
+/// Hides the current records from [`read_syslog_all`].
+pub fn clear_syslog() {
+    interrupts::without_interrupts(|| {
+        let mut log = LOG.lock();
+        log.clear_seq = log.buffer.next_seq();
+    });
+}
+
+/// The number of bytes that [`read_syslog`] has left to read.
+pub fn syslog_unread_len() -> usize {
+    interrupts::without_interrupts(|| {
+        let log = LOG.lock();
+        log.buffer.syslog_len(log.syslog_seq)
+    })
+}
+
+/// The number of bytes that the log holds in the format of `syslog`.
+pub fn syslog_capacity() -> usize {
+    interrupts::without_interrupts(|| LOG.lock().buffer.syslog_capacity())
 }
 
-pub struct SerialLogger;
+/// Wakes `waker` when the next record is logged.
+pub fn register_waker(waker: &Waker) {
+    READERS.register(waker);
+}
 
-impl SerialLogger {}
+pub struct KernelLogger;
 
-impl log::Log for SerialLogger {
+impl log::Log for KernelLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() < Level::Trace || metadata.target().starts_with("kernel")
+        FILTER.read().enabled(metadata.target(), metadata.level())
     }
 
     fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) {
-            let color = match record.level() {
-                Level::Error => "\x1b[1;31m",
-                Level::Warn => "\x1b[1;33m",
-                Level::Info => "\x1b[1;94m",
-                Level::Debug => "\x1b[1;30m",
-                Level::Trace => "\x1b[1;90m",
-            };
-
-            if let Some(ctx) = ExecutionContext::try_load() {
-                serial_println!(
-                    "{}{:5}\x1b[0m cpu{} pid{:3} [{}] {}",
-                    color,
-                    record.level(),
-                    ctx.cpu_id(),
-                    ctx.pid(),
-                    record.target(),
-                    record.args()
-                );
-            } else {
-                serial_println!(
-                    "{}{:5}\x1b[0m boot [{}] {}",
-                    color,
-                    record.level(),
-                    record.target(),
-                    record.args()
-                );
-            }
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let ctx = ExecutionContext::try_load();
+        let cpu = ctx.map(ExecutionContext::cpu_id);
+        let pid = ctx.map(ExecutionContext::pid);
+        // records are kept without allocating, so that the heap can log
+        interrupts::without_interrupts(|| {
+            LOG.lock().buffer.push(
+                time::try_uptime().unwrap_or_default(),
+                record.level(),
+                cpu,
+                pid.map(u64::from),
+                record.target(),
+                *record.args(),
+            );
+        });
+        // waking the readers doesn't allocate either, and without readers
+        // it doesn't even take the lock of the queue
+        READERS.wake_all();
+
+        if !console_enabled(record.level()) {
+            return;
+        }
+        let color = match record.level() {
+            Level::Error => "\x1b[1;31m",
+            Level::Warn => "\x1b[1;33m",
+            Level::Info => "\x1b[1;94m",
+            Level::Debug => "\x1b[1;30m",
+            Level::Trace => "\x1b[1;90m",
+        };
+        if let (Some(cpu), Some(pid)) = (cpu, pid) {
+            print(format_args!(
+                "{}{:5}\x1b[0m cpu{} pid{:3} [{}] {}\n",
+                color,
+                record.level(),
+                cpu,
+                pid,
+                record.target(),
+                record.args()
+            ));
+        } else {
+            print(format_args!(
+                "{}{:5}\x1b[0m boot [{}] {}\n",
+                color,
+                record.level(),
+                record.target(),
+                record.args()
+            ));
         }
     }
 
@@ -53,3 +225,56 @@ impl log::Log for SerialLogger {
         // no-op
     }
 }
+
+/// Log messages go to the serial interface and are mirrored on the screen.
+fn print(args: fmt::Arguments) {
+    serial::internal_print(args);
+    fbcon::internal_print(args);
+}
+
+/// `/dev/kmsg`. Every open file reads the log from the oldest record on,
+/// one record per read. Writes are logged with the target `user` and an
+/// optional priority like `<6>` in front.
+#[derive(Default)]
+pub struct KmsgFile {
+    seq: u64,
+}
+
+impl DevFile for KmsgFile {
+    fn read(&mut self, buf: &mut [u8], _: usize) -> Result<usize, ReadError> {
+        read_kmsg(&mut self.seq, buf).map_err(|e| match e {
+            ReadKmsgError::Empty => ReadError::WouldBlock,
+            ReadKmsgError::BufferTooSmall => ReadError::ReadFailed,
+        })
+    }
+
+    fn write(&mut self, buf: &[u8], _: usize) -> Result<usize, WriteError> {
+        let message = String::from_utf8_lossy(buf);
+        let (level, message) = split_priority(message.trim_end_matches('\n'));
+        log::logger().log(
+            &Record::builder()
+                .level(level.unwrap_or(Level::Info))
+                .target("user")
+                .args(format_args!("{message}"))
+                .build(),
+        );
+        Ok(buf.len())
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.file_type = FileType::CharDevice;
+        stat.size = 0;
+        Ok(())
+    }
+
+    fn poll(&mut self) -> Readiness {
+        Readiness {
+            readable: has_kmsg(self.seq),
+            ..Readiness::READY
+        }
+    }
+
+    fn register_waker(&mut self, waker: &Waker) {
+        register_waker(waker);
+    }
+}
@@ -2,24 +2,20 @@
 #![no_main]
 extern crate alloc;
 
-use alloc::boxed::Box;
 use alloc::sync::Arc;
-use core::error::Error;
 use core::panic::PanicInfo;
 
-use ext2::Ext2Fs;
-use kernel::driver::KernelDeviceId;
-use kernel::driver::block::BlockDevices;
-use kernel::file::ext2::VirtualExt2Fs;
+use kernel::cmdline::boot_config;
+use kernel::file::root::mount_root;
 use kernel::file::vfs;
 #[cfg(target_arch = "x86_64")]
 use kernel::limine::BASE_REVISION;
-use kernel::mcore;
 use kernel::mcore::mtask::process::Process;
-use kernel_device::block::{BlockBuf, BlockDevice};
-use kernel_vfs::path::{AbsolutePath, ROOT};
+use kernel::{mcore, rescue};
+use kernel_cmdline::BootConfig;
+use kernel_vfs::path::AbsoluteOwnedPath;
 use log::{error, info};
-use spin::RwLock;
+use thiserror::Error;
 #[cfg(target_arch = "x86_64")]
 use x86_64::instructions::hlt;
 
@@ -39,62 +35,45 @@ unsafe extern "C" fn main() -> ! {
 
     kernel::init();
 
-    {
-        info!("mounting root filesystem");
-        let root_block_device = BlockDevices::by_id(0).expect("should have block device with id 0");
-        let root_block_device = ArcLockedBlockDevice(root_block_device);
-        vfs()
-            .write()
-            .mount(
-                ROOT,
-                VirtualExt2Fs::from(
-                    Ext2Fs::try_new(root_block_device).expect("should be able to create ext2fs"),
-                ),
-            )
-            .expect("should be able to mount ext2fs at /");
+    let mut config = boot_config().clone();
+    if let Err(e) = mount_root(&config) {
+        error!("{e}");
+        rescue::run(&mut config, &e);
     }
 
-    {
+    loop {
         info!("starting init process...");
-        let init_path = AbsolutePath::try_new("/bin/init").unwrap();
-        let _ = vfs().read().open(init_path).expect("should have /bin/init");
-        let proc = Process::create_from_executable(Process::root(), init_path).unwrap();
-        info!("started process pid={}", proc.pid());
+        match start_init(&config) {
+            Ok(proc) => {
+                info!("started process pid={}", proc.pid());
+                break;
+            }
+            Err(e) => {
+                error!("{e}");
+                rescue::run(&mut config, &e);
+            }
+        }
     }
 
     mcore::turn_idle()
 }
 
-struct ArcLockedBlockDevice<const N: usize>(
-    Arc<RwLock<dyn BlockDevice<KernelDeviceId, N> + Send + Sync>>,
-);
-
-impl<const N: usize> filesystem::BlockDevice for ArcLockedBlockDevice<N> {
-    type Error = Box<dyn Error>;
-
-    fn sector_size(&self) -> usize {
-        N
-    }
-
-    fn sector_count(&self) -> usize {
-        self.0.read().block_count()
-    }
-
-    fn read_sector(&self, sector_index: usize, buf: &mut [u8]) -> Result<usize, Self::Error> {
-        let mut read_buf = BlockBuf::new();
-        self.0.write().read_block(sector_index, &mut read_buf)?;
-        buf.copy_from_slice(&read_buf[..]);
-        Ok(buf.len())
-    }
+#[derive(Debug, Error)]
+enum StartInitError {
+    #[error("init executable {0} not found")]
+    NotFound(AbsoluteOwnedPath),
+    #[error("failed to start init executable {0}")]
+    Spawn(AbsoluteOwnedPath),
+}
 
-    fn write_sector(&mut self, sector_index: usize, buf: &[u8]) -> Result<usize, Self::Error> {
-        let mut write_buf = BlockBuf::new();
-        write_buf.copy_from_slice(buf);
-        self.0
-            .write()
-            .write_block(sector_index, &write_buf)
-            .map(|()| buf.len())
-    }
+fn start_init(config: &BootConfig) -> Result<Arc<Process>, StartInitError> {
+    let init_path = AbsoluteOwnedPath::try_from(config.init_path()).unwrap();
+    let _ = vfs()
+        .read()
+        .open(&init_path)
+        .map_err(|_| StartInitError::NotFound(init_path.clone()))?;
+    Process::create_from_executable(Process::root(), &init_path)
+        .map_err(|_| StartInitError::Spawn(init_path))
 }
 
 #[panic_handler]
@@ -126,4 +105,4 @@ fn handle_panic(info: &PanicInfo) {
             error!("error capturing backtrace: {e:?}");
         }
     }
-}
\ No newline at end of file
+}
@@ -10,6 +10,7 @@ use crate::arch::gdt::Selectors;
 use crate::mcore::lapic::Lapic;
 use crate::mcore::mtask::process::{Process, ProcessId};
 use crate::mcore::mtask::scheduler::Scheduler;
+use crate::mcore::mtask::scheduler::run_queue::RunQueue;
 use crate::mcore::mtask::task::Task;
 
 #[derive(Debug)]
@@ -24,6 +25,9 @@ pub struct ExecutionContext {
     _idt: &'static InterruptDescriptorTable,
 
     scheduler: UnsafeCell<Scheduler>,
+    /// The tasks that are ready to run on this CPU, which other CPUs queue
+    /// tasks to and take tasks from.
+    run_queue: RunQueue,
 }
 
 impl ExecutionContext {
@@ -41,7 +45,8 @@ impl ExecutionContext {
             _gdt: gdt,
             sel,
             _idt: idt,
-            scheduler: UnsafeCell::new(Scheduler::new_cpu_local()),
+            scheduler: UnsafeCell::new(Scheduler::new_cpu_local(cpu.id as usize)),
+            run_queue: RunQueue::new(),
         }
     }
 
@@ -82,6 +87,11 @@ impl ExecutionContext {
         &self.sel
     }
 
+    #[must_use]
+    pub fn run_queue(&self) -> &RunQueue {
+        &self.run_queue
+    }
+
     /// Creates and returns a mutable reference to the scheduler.
     ///
     /// # Safety
@@ -1,7 +1,9 @@
 use alloc::boxed::Box;
-use core::sync::atomic::Ordering::{Acquire, Release};
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
 
 use log::{info, trace};
+use spin::Once;
 use x86_64::instructions::segmentation::{CS, DS, SS};
 use x86_64::instructions::tables::load_tss;
 use x86_64::instructions::{hlt, interrupts};
@@ -17,13 +19,19 @@ use crate::arch::idt::create_idt;
 use crate::limine::MP_REQUEST;
 use crate::mcore::context::ExecutionContext;
 use crate::mcore::mtask::scheduler::cleanup::TaskCleanup;
-use crate::mcore::mtask::scheduler::global::GlobalTaskQueue;
 use crate::sse;
 
 pub mod context;
 mod lapic;
 pub mod mtask;
 
+static CPU_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of CPUs that were started by [`init`].
+pub fn cpu_count() -> usize {
+    CPU_COUNT.load(Relaxed)
+}
+
 #[allow(clippy::missing_panics_doc)]
 pub fn init() {
     let resp = unsafe {
@@ -42,7 +50,7 @@ pub fn init() {
         cpu.extra.store(cr3_val, Release);
     });
 
-    GlobalTaskQueue::init();
+    CPU_COUNT.store(resp.cpus().len(), Relaxed);
 
     // then call the `cpu_init` function on each CPU (no-op on bootstrap CPU)
     resp.cpus().iter().skip(1).for_each(|cpu| {
@@ -94,8 +102,10 @@ unsafe extern "C" fn cpu_init_and_return(cpu: &limine::mp::Cpu) {
 
     init_interrupts();
 
-    // load it back and print a message
+    // load it back, make its run queue available to the other CPUs and
+    // print a message
     let ctx = ExecutionContext::load();
+    ctx.run_queue().register(ctx.cpu_id());
     info!("cpu {} initialized", ctx.cpu_id());
 
     interrupts::enable();
@@ -109,33 +119,30 @@ unsafe extern "C" fn cpu_init_and_idle(cpu: &limine::mp::Cpu) -> ! {
 
 /// Makes the current task an idle task.
 ///
-/// This adapts the current task priority and affinity.
+/// The task is pinned to this CPU already, since it started the CPU, and
+/// from now on only runs if there is nothing else to run.
 pub fn turn_idle() -> ! {
-    // This is an idle-task now.
-    // TODO: pin this task to this CPU
-    // TODO: make this task lowest (idle) priority, so that it doesn't get scheduled if there are any other tasks
+    let ctx = ExecutionContext::load();
+    interrupts::without_interrupts(|| unsafe {
+        // Safety: the scheduler isn't used by an interrupt handler meanwhile
+        ctx.scheduler_mut().set_idle_task();
+    });
     loop {
         hlt();
     }
 }
 
+/// Initializes the IO APIC, which masks all interrupt lines. This only
+/// happens once, so that CPUs that start late don't mask lines that
+/// drivers have enabled already.
 fn init_interrupts() {
-    let mut io_apic = io_apic().lock();
-    unsafe {
-        const OFFSET: u8 = 32;
-        io_apic.init(OFFSET);
-
-        // TODO: redirect interrupt vectors
-
-        // for vector in 0..u8::MAX - OFFSET {
-        //     let mut entry = RedirectionTableEntry::default();
-        //     entry.set_mode(IrqMode::Fixed);
-        //     entry.set_flags(IrqFlags::LEVEL_TRIGGERED | IrqFlags::LOW_ACTIVE);
-        //     entry.set_vector(vector);
-        //     entry.set_dest(u8::try_from(lapic_id).expect("invalid lapic id"));
-        //
-        //     io_apic.set_table_entry(vector, entry);
-        //     io_apic.enable_irq(vector);
-        // }
-    }
+    static IO_APIC_INIT: Once = Once::new();
+
+    IO_APIC_INIT.call_once(|| {
+        let mut io_apic = io_apic().lock();
+        unsafe {
+            const OFFSET: u8 = 32;
+            io_apic.init(OFFSET);
+        }
+    });
 }
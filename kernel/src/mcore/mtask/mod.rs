@@ -1,3 +1,4 @@
 pub mod process;
 pub mod scheduler;
 pub mod task;
+pub mod wait;
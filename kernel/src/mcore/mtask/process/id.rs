@@ -20,6 +20,12 @@ where
     }
 }
 
+impl From<ProcessId> for u64 {
+    fn from(pid: ProcessId) -> Self {
+        pid.0
+    }
+}
+
 impl !Default for ProcessId {}
 
 impl ProcessId {
@@ -42,6 +42,15 @@ impl MemoryRegions {
             .map(f)
     }
 
+    /// Calls `f` with all memory regions of this process, in the order
+    /// in which they were added.
+    pub fn with_regions<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[MemoryRegion]) -> R,
+    {
+        f(&self.regions.lock())
+    }
+
     pub fn is_memory_region_at_address(&self, addr: VirtAddr) -> bool {
         self.regions
             .lock()
@@ -69,6 +78,12 @@ pub enum MemoryRegion {
     ///
     /// - [`FileBackedMemoryRegion`]
     FileBacked(FileBackedMemoryRegion),
+    /// A memory region that maps the memory of a device, like a
+    /// framebuffer. The memory belongs to the device and is shared
+    /// with everyone else who maps it.
+    ///
+    /// - [`DeviceMemoryRegion`]
+    Device(DeviceMemoryRegion),
 }
 
 impl MemoryRegion {
@@ -79,6 +94,7 @@ impl MemoryRegion {
             MemoryRegion::FileBacked(file_backed_memory_region) => {
                 file_backed_memory_region.region.segment.start
             }
+            MemoryRegion::Device(device_memory_region) => device_memory_region.segment.start,
         }
     }
 
@@ -89,6 +105,7 @@ impl MemoryRegion {
             MemoryRegion::FileBacked(file_backed_memory_region) => {
                 file_backed_memory_region.region.size
             }
+            MemoryRegion::Device(device_memory_region) => device_memory_region.size,
         }
     }
 
@@ -155,8 +172,50 @@ pub struct FileBackedMemoryRegion {
     node: VfsNode,
 }
 
+impl FileBackedMemoryRegion {
+    /// The file that backs this memory region.
+    pub fn node(&self) -> &VfsNode {
+        &self.node
+    }
+}
+
 impl Drop for FileBackedMemoryRegion {
     fn drop(&mut self) {
         todo!("deallocate physical memory")
     }
 }
+
+#[derive(Debug)]
+pub struct DeviceMemoryRegion {
+    segment: OwnedSegment<'static>,
+    size: usize,
+    physical_frames: PhysFrameRangeInclusive,
+    node: VfsNode,
+}
+
+impl DeviceMemoryRegion {
+    pub fn new(
+        segment: OwnedSegment<'static>,
+        size: usize,
+        physical_frames: PhysFrameRangeInclusive,
+        node: VfsNode,
+    ) -> Self {
+        Self {
+            segment,
+            size,
+            physical_frames,
+            node,
+        }
+    }
+
+    /// The device file that this memory region maps.
+    pub fn node(&self) -> &VfsNode {
+        &self.node
+    }
+
+    /// The device memory that is mapped. It is not deallocated when the
+    /// region is dropped.
+    pub fn physical_frames(&self) -> PhysFrameRangeInclusive {
+        self.physical_frames
+    }
+}
@@ -4,17 +4,21 @@ use alloc::collections::BTreeMap;
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
 use core::alloc::Layout;
-use core::ffi::c_void;
+use core::arch::asm;
+use core::ffi::{c_int, c_void};
 use core::fmt::{Debug, Formatter};
 use core::ptr;
+use core::sync::atomic::Ordering::Relaxed;
+use core::sync::atomic::{AtomicU64, AtomicUsize};
 
 use conquer_once::spin::OnceCell;
+use kernel_abi::{SIGHUP, SIGINT, SIGKILL, SIGQUIT, SIGTERM};
 use kernel_elfloader::{ElfFile, ElfLoader};
 use kernel_memapi::{Allocation, Guarded, Location, MemoryApi, UserAccessible};
 use kernel_vfs::Stat;
 use kernel_vfs::path::{AbsoluteOwnedPath, AbsolutePath, ROOT};
 use kernel_virtual_memory::VirtualMemoryManager;
-use log::debug;
+use log::{debug, trace};
 use spin::RwLock;
 use thiserror::Error;
 use x86_64::VirtAddr;
@@ -29,9 +33,10 @@ use crate::mcore::mtask::process::fd::{FdNum, FileDescriptor, FileDescriptorFlag
 use crate::mcore::mtask::process::mem::MemoryRegions;
 use crate::mcore::mtask::process::telemetry::Telemetry;
 use crate::mcore::mtask::process::tree::process_tree;
-use crate::mcore::mtask::task::{HigherHalfStack, StackAllocationError, Task};
+use crate::mcore::mtask::task::{HigherHalfStack, StackAllocationError, Task, TaskHandle, TaskId};
 use crate::mem::address_space::AddressSpace;
-use crate::mem::memapi::{Executable, LowerHalfAllocation, LowerHalfMemoryApi};
+use crate::mem::memapi::{Executable, LowerHalfAllocation, LowerHalfMemoryApi, Writable};
+use crate::tty::Tty;
 use crate::{U64Ext, UsizeExt};
 
 pub mod fd;
@@ -40,23 +45,39 @@ pub use id::*;
 pub mod mem;
 pub mod telemetry;
 
-use crate::mcore::mtask::scheduler::global::GlobalTaskQueue;
+use crate::mcore::mtask::scheduler::run_queue;
 use crate::mem::virt::VirtualMemoryAllocator;
 
 pub mod tree;
 
 static ROOT_PROCESS: OnceCell<Arc<Process>> = OnceCell::uninit();
 
+/// The signals that can be delivered. Only the default actions are
+/// supported, so these are the signals that terminate the process.
+const TERMINATING_SIGNALS: [c_int; 5] = [SIGHUP, SIGINT, SIGQUIT, SIGKILL, SIGTERM];
+
+/// The number of pages of the userspace stacks that the kernel allocates.
+const USER_STACK_PAGES: usize = 256;
+
 pub struct Process {
     pid: ProcessId,
     name: String,
 
     ppid: RwLock<ProcessId>,
+    /// The process group, identified by the pid of its leader.
+    pgid: RwLock<ProcessId>,
+    controlling_terminal: RwLock<Option<Arc<Tty>>>,
+    /// A bit mask of the signals that were sent to the process, but haven't
+    /// been delivered yet.
+    pending_signals: AtomicU64,
 
     exit_code: RwLock<Option<i32>>,
 
     executable_path: Option<AbsoluteOwnedPath>,
     executable_file_data: RwLock<Option<LowerHalfAllocation<Executable>>>,
+    /// The layout and the initial contents of the thread-local storage of
+    /// every thread, if the executable has any.
+    tls_image: RwLock<Option<(Layout, Box<[u8]>)>>,
     current_working_directory: RwLock<AbsoluteOwnedPath>,
 
     address_space: Option<AddressSpace>,
@@ -66,6 +87,13 @@ pub struct Process {
 
     memory_regions: MemoryRegions,
 
+    /// The ids and names of the tasks that belong to this process.
+    tasks: RwLock<BTreeMap<TaskId, String>>,
+    /// The handles of the tasks, which are woken when a signal arrives.
+    task_handles: RwLock<BTreeMap<TaskId, TaskHandle>>,
+    /// The number of threads that run in userspace and haven't exited yet.
+    live_threads: AtomicUsize,
+
     file_descriptors: RwLock<BTreeMap<FdNum, FileDescriptor>>,
 }
 
@@ -77,9 +105,13 @@ impl Process {
                 pid,
                 name: "root".to_string(),
                 ppid: RwLock::new(pid),
+                pgid: RwLock::new(pid),
+                controlling_terminal: RwLock::new(None),
+                pending_signals: AtomicU64::new(0),
                 exit_code: RwLock::new(None),
                 executable_path: None,
                 executable_file_data: RwLock::new(None),
+                tls_image: RwLock::new(None),
                 current_working_directory: RwLock::new(ROOT.to_owned()),
                 address_space: None,
                 lower_half_memory: Arc::new(RwLock::new(VirtualMemoryManager::new(
@@ -88,6 +120,9 @@ impl Process {
                 ))),
                 telemetry: Telemetry::default(),
                 memory_regions: MemoryRegions::new(),
+                tasks: RwLock::new(BTreeMap::new()),
+                task_handles: RwLock::new(BTreeMap::new()),
+                live_threads: AtomicUsize::new(0),
                 file_descriptors: RwLock::new(BTreeMap::new()),
             });
             process_tree().write().processes.insert(pid, root.clone());
@@ -103,14 +138,25 @@ impl Process {
         let pid = ProcessId::new();
         let parent_pid = parent.pid;
         let address_space = AddressSpace::new();
+        // children of the kernel start their own process group, like init
+        // does on other systems
+        let pgid = if parent_pid.is_root() {
+            pid
+        } else {
+            parent.pgid()
+        };
 
         let process = Self {
             pid,
             name,
             ppid: RwLock::new(parent_pid),
+            pgid: RwLock::new(pgid),
+            controlling_terminal: RwLock::new(parent.controlling_terminal.read().clone()),
+            pending_signals: AtomicU64::new(0),
             exit_code: RwLock::new(None),
             executable_path: executable_path.map(|x| x.as_ref().to_owned()),
             executable_file_data: RwLock::new(None),
+            tls_image: RwLock::new(None),
             current_working_directory: RwLock::new(parent.current_working_directory.read().clone()),
             address_space: Some(address_space),
             lower_half_memory: Arc::new(RwLock::new(VirtualMemoryManager::new(
@@ -119,6 +165,9 @@ impl Process {
             ))),
             telemetry: Telemetry::default(),
             memory_regions: MemoryRegions::new(),
+            tasks: RwLock::new(BTreeMap::new()),
+            task_handles: RwLock::new(BTreeMap::new()),
+            live_threads: AtomicUsize::new(0),
             file_descriptors: RwLock::new(BTreeMap::new()),
         };
 
@@ -159,11 +208,105 @@ impl Process {
 
         let kstack = HigherHalfStack::allocate(16, trampoline, ptr::null_mut(), Task::exit)?;
         let main_task = Task::create_with_stack(&process, kstack);
-        GlobalTaskQueue::enqueue(Box::pin(main_task));
+        process.live_threads.fetch_add(1, Relaxed);
+        run_queue::enqueue(Box::pin(main_task));
 
         Ok(process)
     }
 
+    /// Creates a thread that enters userspace at `entry` with `arg` as its
+    /// first argument. The caller has to enqueue the returned task.
+    ///
+    /// If `stack_top` or `fs_base` are `None`, a stack or thread-local storage
+    /// is allocated. This must be called in the address space of this process.
+    ///
+    /// # Errors
+    /// Returns an error if the stacks or the thread-local storage can't be
+    /// allocated.
+    pub fn create_thread(
+        self: &Arc<Self>,
+        entry: VirtAddr,
+        arg: u64,
+        stack_top: Option<VirtAddr>,
+        fs_base: Option<VirtAddr>,
+    ) -> Result<Task, CreateThreadError> {
+        let ustack = match stack_top {
+            Some(_) => None,
+            None => Some(
+                self.allocate_user_stack()
+                    .ok_or(CreateThreadError::UserStackAllocationError)?,
+            ),
+        };
+        let tls = match fs_base {
+            Some(_) => None,
+            None => self.allocate_tls()?,
+        };
+        let stack_top = stack_top
+            .or_else(|| {
+                ustack
+                    .as_ref()
+                    .map(|ustack| ustack.start() + ustack.len().into_u64())
+            })
+            .expect("should have a userspace stack");
+        let fs_base = fs_base.or_else(|| tls.as_ref().map(LowerHalfAllocation::start));
+
+        let start = Box::into_raw(Box::new(ThreadStart {
+            entry,
+            arg,
+            // the stack looks like `entry` was called, which must not return
+            rsp: stack_top.align_down(16_u64) - 8,
+        }));
+        let kstack = HigherHalfStack::allocate(16, thread_trampoline, start.cast(), Task::exit)
+            .inspect_err(|_| drop(unsafe { Box::from_raw(start) }))?;
+
+        let task = Task::create_with_stack(self, kstack);
+        *task.ustack().write() = ustack;
+        *task.tls().write() = tls;
+        task.set_fs_base(fs_base.map_or(0, VirtAddr::as_u64));
+        self.live_threads.fetch_add(1, Relaxed);
+        Ok(task)
+    }
+
+    /// Records that a thread of this process exited, and returns whether it
+    /// was the last one.
+    pub fn exit_thread(&self) -> bool {
+        self.live_threads.fetch_sub(1, Relaxed) == 1
+    }
+
+    fn allocate_user_stack(self: &Arc<Self>) -> Option<LowerHalfAllocation<Writable>> {
+        LowerHalfMemoryApi::new(self.clone()).allocate(
+            Location::Anywhere,
+            Layout::from_size_align(
+                Size4KiB::SIZE.into_usize() * USER_STACK_PAGES,
+                Size4KiB::SIZE.into_usize(),
+            )
+            .unwrap(),
+            UserAccessible::Yes,
+            Guarded::Yes,
+        )
+    }
+
+    /// Allocates thread-local storage from the TLS image of the executable,
+    /// or returns `None` if it has none.
+    fn allocate_tls(
+        self: &Arc<Self>,
+    ) -> Result<Option<LowerHalfAllocation<Writable>>, CreateThreadError> {
+        let guard = self.tls_image.read();
+        let Some((layout, image)) = guard.as_ref() else {
+            return Ok(None);
+        };
+        let mut tls = LowerHalfMemoryApi::new(self.clone())
+            .allocate(
+                Location::Anywhere,
+                *layout,
+                UserAccessible::Yes,
+                Guarded::No,
+            )
+            .ok_or(CreateThreadError::TlsAllocationError)?;
+        tls.as_mut().copy_from_slice(image);
+        Ok(Some(tls))
+    }
+
     pub fn exit_code(&self) -> &RwLock<Option<i32>> {
         &self.exit_code
     }
@@ -176,10 +319,43 @@ impl Process {
         *self.ppid.read()
     }
 
+    pub fn pgid(&self) -> ProcessId {
+        *self.pgid.read()
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// The terminal that generates signals for this process, which is
+    /// inherited from the parent.
+    pub fn controlling_terminal(&self) -> &RwLock<Option<Arc<Tty>>> {
+        &self.controlling_terminal
+    }
+
+    /// Marks `signal` as pending. Pending signals are delivered before the
+    /// process returns to userspace.
+    pub fn send_signal(&self, signal: c_int) {
+        if !TERMINATING_SIGNALS.contains(&signal) {
+            // the default action of the other signals is to ignore them,
+            // like SIGWINCH, or to stop the process, which isn't supported
+            // yet
+            trace!("ignoring signal {signal} for process {}", self.pid);
+            return;
+        }
+        self.pending_signals.fetch_or(1 << signal, Relaxed);
+        // blocked tasks notice the signal when they wake up
+        for handle in self.task_handles.read().values() {
+            handle.wake();
+        }
+    }
+
+    /// Returns the lowest pending signal, which terminates the process.
+    pub fn pending_terminating_signal(&self) -> Option<c_int> {
+        let pending = self.pending_signals.load(Relaxed);
+        (pending != 0).then(|| pending.trailing_zeros() as c_int)
+    }
+
     pub fn file_descriptors(&self) -> &RwLock<BTreeMap<FdNum, FileDescriptor>> {
         &self.file_descriptors
     }
@@ -205,6 +381,19 @@ impl Process {
     pub fn telemetry(&self) -> &Telemetry {
         &self.telemetry
     }
+
+    pub fn executable_path(&self) -> Option<&AbsolutePath> {
+        self.executable_path.as_deref()
+    }
+
+    /// The ids and names of the tasks that belong to this process.
+    pub fn tasks(&self) -> &RwLock<BTreeMap<TaskId, String>> {
+        &self.tasks
+    }
+
+    pub fn task_handles(&self) -> &RwLock<BTreeMap<TaskId, TaskHandle>> {
+        &self.task_handles
+    }
 }
 
 impl Debug for Process {
@@ -242,6 +431,48 @@ pub enum CreateProcessError {
     StackAllocationError(#[from] StackAllocationError),
 }
 
+#[derive(Debug, Error)]
+pub enum CreateThreadError {
+    #[error("failed to allocate stack")]
+    StackAllocationError(#[from] StackAllocationError),
+    #[error("failed to allocate userspace stack")]
+    UserStackAllocationError,
+    #[error("failed to allocate thread-local storage")]
+    TlsAllocationError,
+}
+
+/// Where a thread enters userspace, passed to [`thread_trampoline`].
+struct ThreadStart {
+    entry: VirtAddr,
+    arg: u64,
+    rsp: VirtAddr,
+}
+
+extern "C" fn thread_trampoline(arg: *mut c_void) {
+    let ThreadStart { entry, arg, rsp } = *unsafe { Box::from_raw(arg.cast::<ThreadStart>()) };
+    let sel = ExecutionContext::load().selectors();
+
+    // like `InterruptStackFrameValue::iretq`, but with the argument of
+    // `entry` in rdi
+    unsafe {
+        asm!(
+            "push {ss}",
+            "push {stack}",
+            "push {rflags}",
+            "push {cs}",
+            "push {rip}",
+            "iretq",
+            ss = in(reg) u64::from(sel.user_data.0),
+            stack = in(reg) rsp.as_u64(),
+            rflags = in(reg) RFlags::INTERRUPT_FLAG.bits(),
+            cs = in(reg) u64::from(sel.user_code.0),
+            rip = in(reg) entry.as_u64(),
+            in("rdi") arg,
+            options(noreturn),
+        );
+    }
+}
+
 extern "C" fn trampoline(_arg: *mut c_void) {
     let ctx = ExecutionContext::load();
     let current_task = ctx.scheduler().current_task();
@@ -294,19 +525,17 @@ extern "C" fn trampoline(_arg: *mut c_void) {
         .expect("should be able to load elf file");
 
     if let Some(master_tls) = elf_image.tls_allocation() {
-        let mut tls_alloc = memapi
-            .allocate(
-                Location::Anywhere,
-                master_tls.layout(),
-                UserAccessible::Yes,
-                Guarded::No,
-            )
-            .expect("should be able to allocate TLS data");
-
-        let slice = tls_alloc.as_mut();
-        slice.copy_from_slice(master_tls.as_ref());
+        // other threads copy their TLS from the image after the executable
+        // was loaded
+        *current_process.tls_image.write() =
+            Some((master_tls.layout(), master_tls.as_ref().into()));
+        let tls_alloc = current_process
+            .allocate_tls()
+            .expect("should be able to allocate TLS data")
+            .expect("should have a TLS image");
 
         FsBase::write(tls_alloc.start());
+        current_task.set_fs_base(tls_alloc.start().as_u64());
 
         {
             let mut guard = current_task.tls().write();
@@ -315,18 +544,8 @@ extern "C" fn trampoline(_arg: *mut c_void) {
         }
     }
 
-    let mut memapi = LowerHalfMemoryApi::new(current_process.clone());
-    let ustack_allocation = memapi
-        .allocate(
-            Location::Anywhere,
-            Layout::from_size_align(
-                Size4KiB::SIZE.into_usize() * 256,
-                Size4KiB::SIZE.into_usize(),
-            )
-            .unwrap(),
-            UserAccessible::Yes,
-            Guarded::Yes,
-        )
+    let ustack_allocation = current_process
+        .allocate_user_stack()
         .expect("should be able to allocate userspace stack");
 
     let ustack_rsp = ustack_allocation.start() + ustack_allocation.len().into_u64();
@@ -348,42 +567,6 @@ extern "C" fn trampoline(_arg: *mut c_void) {
     debug!("stack_ptr: {:p}", ustack_rsp.as_ptr::<u8>());
     debug!("code_ptr: {:p}", code_ptr as *const u8);
 
-    {
-        let mut guard = current_process.file_descriptors.write();
-
-        let devnull = vfs()
-            .read()
-            .open(AbsolutePath::try_new("/dev/null").unwrap())
-            .expect("should be able to open /dev/null");
-        let devnull_ofd = Arc::new(OpenFileDescription::from(devnull));
-        guard.insert(
-            0.into(),
-            FileDescriptor::new(0.into(), FileDescriptorFlags::empty(), devnull_ofd.clone()),
-        );
-
-        let devserial = vfs()
-            .read()
-            .open(AbsolutePath::try_new("/dev/serial").unwrap())
-            .expect("should be able to open /dev/serial");
-        let devserial_ofd = Arc::new(OpenFileDescription::from(devserial));
-        guard.insert(
-            1.into(),
-            FileDescriptor::new(
-                1.into(),
-                FileDescriptorFlags::empty(),
-                devserial_ofd.clone(),
-            ),
-        );
-        guard.insert(
-            2.into(),
-            FileDescriptor::new(
-                2.into(),
-                FileDescriptorFlags::empty(),
-                devserial_ofd.clone(),
-            ),
-        );
-    }
-
     let isfv = InterruptStackFrameValue::new(
         VirtAddr::new(code_ptr as u64),
         sel.user_code,
@@ -392,4 +575,4 @@ extern "C" fn trampoline(_arg: *mut c_void) {
         sel.user_data,
     );
     unsafe { isfv.iretq() };
-}
\ No newline at end of file
+}
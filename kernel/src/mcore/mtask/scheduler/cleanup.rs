@@ -9,7 +9,7 @@ use x86_64::instructions::hlt;
 
 use crate::mcore::mtask::process::Process;
 use crate::mcore::mtask::process::tree::process_tree;
-use crate::mcore::mtask::scheduler::global::GlobalTaskQueue;
+use crate::mcore::mtask::scheduler::run_queue;
 use crate::mcore::mtask::task::{Task, TaskQueue};
 
 static TASK_CLEANUP_QUEUE: OnceCell<TaskQueue> = OnceCell::uninit();
@@ -31,7 +31,7 @@ impl TaskCleanup {
         let cleanup_task = Task::create_new(Process::root(), Self::cleanup_tasks, ptr::null_mut())
             .expect("should be able to create cleanup task");
         info!("cleanup task created with id {}", cleanup_task.id());
-        GlobalTaskQueue::enqueue(Box::pin(cleanup_task));
+        run_queue::enqueue(Box::pin(cleanup_task));
     }
 
     pub fn enqueue(task: Pin<Box<Task>>) {
@@ -54,4 +54,4 @@ impl TaskCleanup {
             hlt();
         }
     }
-}
\ No newline at end of file
+}
@@ -4,27 +4,47 @@ use core::arch::x86_64::_fxsave;
 use core::cell::UnsafeCell;
 use core::mem::swap;
 use core::pin::Pin;
+use core::time::Duration;
 
 use cleanup::TaskCleanup;
+use x86_64::VirtAddr;
 use x86_64::instructions::interrupts;
 use x86_64::registers::model_specific::FsBase;
 
-use crate::mcore::mtask::scheduler::global::GlobalTaskQueue;
+use crate::mcore::mtask::scheduler::run_queue::RunQueue;
 use crate::mcore::mtask::scheduler::switch::switch_impl;
-use crate::mcore::mtask::task::Task;
+use crate::mcore::mtask::task::{State, Task, TaskId};
+use crate::time::try_uptime;
 
 pub mod cleanup;
-pub mod global;
+pub mod run_queue;
 mod switch;
 
+/// How many reschedules there are between two balancings of the load
+/// between the CPUs.
+const BALANCE_INTERVAL: u32 = 16;
+
 #[derive(Debug)]
 pub struct Scheduler {
+    /// The CPU that this scheduler runs tasks on.
+    cpu: usize,
     /// The task that is currently executing in this scheduler.
     current_task: Pin<Box<Task>>,
     /// The task this scheduler last switched away from. We need this to
     /// eliminate the race condition between re-queueing a task and
     /// actually switching away from it.
     zombie_task: Option<Pin<Box<Task>>>,
+    /// The id of the idle task of this CPU, see [`Scheduler::set_idle_task`].
+    idle_task_id: Option<TaskId>,
+    /// The idle task while it doesn't run. It isn't queued, so that it only
+    /// runs if there is nothing else to run, and never on another CPU.
+    idle_task: Option<Pin<Box<Task>>>,
+    /// The reschedules until the next balancing.
+    balance_countdown: u32,
+    /// The uptime when the current task was last charged for its runtime.
+    last_charged: Duration,
+    /// How long the current task ran since it was switched to.
+    ran: Duration,
     /// A dummy location that is a placeholder for the switch code to write the old stack
     /// pointer to if the old task is terminated.
     dummy_old_stack_ptr: UnsafeCell<usize>,
@@ -32,31 +52,83 @@ pub struct Scheduler {
 
 impl Scheduler {
     #[must_use]
-    pub fn new_cpu_local() -> Self {
+    pub fn new_cpu_local(cpu: usize) -> Self {
         let current_task = Box::pin(unsafe { Task::create_current() });
+        // the task that starts the CPU becomes its idle task, so it stays
+        current_task.handle().set_affinity(1 << cpu);
         Self {
+            cpu,
             current_task,
             zombie_task: None,
+            idle_task_id: None,
+            idle_task: None,
+            balance_countdown: BALANCE_INTERVAL,
+            last_charged: try_uptime().unwrap_or_default(),
+            ran: Duration::ZERO,
             dummy_old_stack_ptr: UnsafeCell::new(0),
         }
     }
 
+    /// Makes the current task the idle task of this CPU, which only runs
+    /// when there is nothing else to run.
+    pub fn set_idle_task(&mut self) {
+        assert!(
+            self.current_task.may_run_on(self.cpu),
+            "the idle task should be pinned to its cpu"
+        );
+        self.idle_task_id = Some(self.current_task.id());
+    }
+
+    /// Switches to the task that should run next, if the current task
+    /// can't continue or is preempted.
+    ///
     /// # Safety
     /// Trivially unsafe. If you don't know why, please don't call this function.
     pub unsafe fn reschedule(&mut self) {
+        unsafe { self.switch_to_next(false) };
+    }
+
+    /// Like [`Scheduler::reschedule`], but the current task makes way for
+    /// every task that it yields to, see
+    /// [`Entity::yields_to`](kernel_sched::Entity::yields_to).
+    ///
+    /// # Safety
+    /// See [`Scheduler::reschedule`].
+    pub unsafe fn yield_now(&mut self) {
+        unsafe { self.switch_to_next(true) };
+    }
+
+    unsafe fn switch_to_next(&mut self, yielding: bool) {
         assert!(!interrupts::are_enabled());
 
         // in theory, we could move this to the end of this function, but I'd rather not do this right now
         if let Some(zombie_task) = self.zombie_task.take() {
             if zombie_task.should_terminate() {
                 TaskCleanup::enqueue(zombie_task);
-            } else {
-                GlobalTaskQueue::enqueue(zombie_task);
+            } else if Some(zombie_task.id()) == self.idle_task_id {
+                self.idle_task = Some(zombie_task);
+            } else if let Some(zombie_task) = Task::park(zombie_task) {
+                // a blocked task is kept until it is woken, now that it
+                // doesn't run on this CPU anymore
+                run_queue::enqueue(zombie_task);
             }
         }
 
+        self.balance_countdown -= 1;
+        if self.balance_countdown == 0 {
+            self.balance_countdown = BALANCE_INTERVAL;
+            run_queue::balance(self.cpu);
+        }
+
+        // the HPET is started before the CPUs, see `crate::init`
+        let now = try_uptime().unwrap_or_default();
+        let ran = now.saturating_sub(self.last_charged);
+        self.last_charged = now;
+        self.ran += ran;
+        self.current_task.charge(ran);
+
         let (next_task, cr3_value) = {
-            let Some(next_task) = self.next_task() else {
+            let Some(next_task) = self.next_task(yielding) else {
                 return;
             };
 
@@ -64,7 +136,10 @@ impl Scheduler {
             (next_task, cr3_value)
         };
 
+        next_task.set_state(State::Running);
         let mut old_task = self.swap_current_task(next_task);
+        old_task.set_last_cpu(self.cpu);
+        self.ran = Duration::ZERO;
         let old_stack_ptr = if old_task.should_terminate() {
             self.dummy_old_stack_ptr.get()
         } else {
@@ -81,10 +156,9 @@ impl Scheduler {
             }
         }
 
-        if let Some(guard) = self.current_task.tls().try_read()
-            && let Some(tls) = guard.as_ref()
-        {
-            FsBase::write(tls.start());
+        let fs_base = self.current_task.fs_base();
+        if fs_base != 0 {
+            FsBase::write(VirtAddr::new(fs_base));
         }
 
         assert!(self.zombie_task.is_none());
@@ -120,8 +194,41 @@ impl Scheduler {
         next_task
     }
 
-    #[allow(clippy::unused_self)]
-    fn next_task(&self) -> Option<Pin<Box<Task>>> {
-        GlobalTaskQueue::dequeue()
+    /// Takes the task that should run instead of the current one from the
+    /// queue of this CPU, if any. If the current task can't continue, any
+    /// task runs, and a task from another CPU if there is none here. The
+    /// idle task only runs if there is no task at all.
+    fn next_task(&mut self, yielding: bool) -> Option<Pin<Box<Task>>> {
+        let queue = RunQueue::of_cpu(self.cpu)?;
+        for _ in 0..queue.len() {
+            let Some(task) = queue.take_misplaced(self.cpu) else {
+                break;
+            };
+            run_queue::enqueue(task);
+        }
+
+        let current = &self.current_task;
+        let is_idle = Some(current.id()) == self.idle_task_id;
+        if is_idle
+            || current.state().is_blocked()
+            || current.should_terminate()
+            || !current.may_run_on(self.cpu)
+        {
+            if queue.is_empty() {
+                run_queue::steal(self.cpu);
+            }
+            return queue
+                .pick(self.cpu, |_| true)
+                .or_else(|| if is_idle { None } else { self.idle_task.take() });
+        }
+
+        let (current, ran) = (current.entity(), self.ran);
+        queue.pick(self.cpu, |next| {
+            if yielding {
+                current.yields_to(next)
+            } else {
+                current.is_preempted_by(ran, next)
+            }
+        })
     }
 }
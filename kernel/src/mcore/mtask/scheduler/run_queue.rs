@@ -0,0 +1,241 @@
+//! The run queues of the CPUs. Every CPU runs the tasks in its own queue,
+//! so that tasks stay on the CPU whose caches they warmed up, and moves
+//! tasks from the busiest CPU to itself when it has nothing to do, or
+//! periodically when the load is uneven (see [`balance`]).
+//!
+//! The queues are intrusive lists, since tasks are queued by wakers in
+//! interrupt handlers, which must not allocate. The scheduling classes of
+//! the tasks decide which queued task runs next, see [`RunQueue::pick`].
+
+use alloc::boxed::Box;
+use core::pin::Pin;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::Relaxed;
+
+use conquer_once::spin::OnceCell;
+use cordyceps::List;
+use kernel_abi::MAX_CPUS;
+use kernel_sched::{Entity, QueueState};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::mcore::mtask::task::Task;
+
+static RUN_QUEUES: [OnceCell<&'static RunQueue>; MAX_CPUS] =
+    [const { OnceCell::uninit() }; MAX_CPUS];
+
+/// The tasks that are ready to run on one CPU.
+#[derive(Debug)]
+pub struct RunQueue {
+    inner: Mutex<Inner>,
+    /// The number of queued tasks, which other CPUs read without locking
+    /// the queue.
+    len: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Inner {
+    tasks: List<Task>,
+    state: QueueState,
+}
+
+impl RunQueue {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                tasks: List::new(),
+                state: QueueState::default(),
+            }),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Makes this the run queue of `cpu`, which tasks can be queued to from
+    /// now on.
+    ///
+    /// # Panics
+    /// Panics if `cpu` already has a run queue, or can't have one.
+    pub fn register(&'static self, cpu: usize) {
+        RUN_QUEUES
+            .get(cpu)
+            .expect("cpu id should be lower than MAX_CPUS")
+            .init_once(|| self);
+    }
+
+    /// The run queue of `cpu`, if it was started already.
+    #[must_use]
+    pub fn of_cpu(cpu: usize) -> Option<&'static Self> {
+        RUN_QUEUES.get(cpu)?.get().copied()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.load(Relaxed)
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Tasks are also queued in interrupt handlers, so the queue is only
+    /// locked with interrupts disabled.
+    fn with_inner<R>(&self, f: impl FnOnce(&mut Inner) -> R) -> R {
+        interrupts::without_interrupts(|| {
+            let mut inner = self.inner.lock();
+            let result = f(&mut inner);
+            self.len.store(inner.tasks.len(), Relaxed);
+            result
+        })
+    }
+
+    /// Queues `task`. `migrated` is set if the task comes from another CPU,
+    /// see [`SchedClass::place`](kernel_sched::SchedClass::place).
+    fn push(&self, task: Pin<Box<Task>>, migrated: bool) {
+        self.with_inner(|inner| {
+            task.place(&inner.state, migrated);
+            inner.tasks.push_back(task);
+        });
+    }
+
+    /// Takes the task that should run next on `cpu`, if `take` agrees. Of
+    /// the tasks that run before none of the others, the one that was
+    /// queued first is taken.
+    #[must_use]
+    pub fn pick(&self, cpu: usize, take: impl FnOnce(&Entity) -> bool) -> Option<Pin<Box<Task>>> {
+        self.with_inner(|inner| {
+            let (index, entity) = best(&mut inner.tasks, cpu)?;
+            if !take(&entity) {
+                return None;
+            }
+            entity.class().pick(&entity, &mut inner.state);
+            remove_at(&mut inner.tasks, index)
+        })
+    }
+
+    /// Takes the task that should run first among those that may run on
+    /// `cpu`, to move it there.
+    fn steal(&self, cpu: usize) -> Option<Pin<Box<Task>>> {
+        self.with_inner(|inner| {
+            let (index, _) = best(&mut inner.tasks, cpu)?;
+            remove_at(&mut inner.tasks, index)
+        })
+    }
+
+    /// Takes the task that was queued first among those that may not run on
+    /// `cpu`, which happens if the affinity of a task changes while it is
+    /// queued.
+    #[must_use]
+    pub fn take_misplaced(&self, cpu: usize) -> Option<Pin<Box<Task>>> {
+        self.with_inner(|inner| {
+            let mut cursor = inner.tasks.cursor_front_mut();
+            loop {
+                if !cursor.current()?.may_run_on(cpu) {
+                    return cursor.remove_current();
+                }
+                cursor.move_next();
+            }
+        })
+    }
+}
+
+impl Default for RunQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The position and the entity of the task that should run first among
+/// those in `tasks` that may run on `cpu`.
+fn best(tasks: &mut List<Task>, cpu: usize) -> Option<(usize, Entity)> {
+    let mut best: Option<(usize, Entity)> = None;
+    let mut cursor = tasks.cursor_front_mut();
+    let mut index = 0;
+    while let Some(task) = cursor.current() {
+        if task.may_run_on(cpu) {
+            let entity = task.entity();
+            if best.is_none_or(|(_, best)| entity.runs_before(&best)) {
+                best = Some((index, entity));
+            }
+        }
+        cursor.move_next();
+        index += 1;
+    }
+    best
+}
+
+fn remove_at(tasks: &mut List<Task>, index: usize) -> Option<Pin<Box<Task>>> {
+    let mut cursor = tasks.cursor_front_mut();
+    for _ in 0..index {
+        cursor.move_next();
+    }
+    cursor.remove_current()
+}
+
+/// The CPUs that were started, with their run queues.
+fn run_queues() -> impl Iterator<Item = (usize, &'static RunQueue)> {
+    RUN_QUEUES
+        .iter()
+        .enumerate()
+        .filter_map(|(cpu, queue)| Some((cpu, *queue.get()?)))
+}
+
+/// Queues `task` to run on the CPU that it ran on last, so that it finds
+/// its data in the caches, or on the least busy CPU if it may not run there
+/// anymore.
+///
+/// # Panics
+/// Panics if no CPU was started yet.
+pub fn enqueue(task: Pin<Box<Task>>) {
+    let (cpu, queue) = task
+        .last_cpu()
+        .filter(|&cpu| task.may_run_on(cpu))
+        .and_then(|cpu| Some((cpu, RunQueue::of_cpu(cpu)?)))
+        .or_else(|| least_busy(|cpu| task.may_run_on(cpu)))
+        // the CPUs that the task may run on are still starting
+        .or_else(|| least_busy(|_| true))
+        .expect("a cpu should be started before tasks are queued");
+    let migrated = task.last_cpu() != Some(cpu);
+    queue.push(task, migrated);
+}
+
+fn least_busy(allowed: impl Fn(usize) -> bool) -> Option<(usize, &'static RunQueue)> {
+    run_queues()
+        .filter(|&(cpu, _)| allowed(cpu))
+        .min_by_key(|(_, queue)| queue.len())
+}
+
+/// The queue with the most tasks, other than the one of `cpu`.
+fn busiest(cpu: usize) -> Option<&'static RunQueue> {
+    run_queues()
+        .filter(|&(other, queue)| other != cpu && !queue.is_empty())
+        .max_by_key(|(_, queue)| queue.len())
+        .map(|(_, queue)| queue)
+}
+
+/// Moves a task that may run on `cpu` from the busiest other CPU to the
+/// queue of `cpu`. This is how a CPU that has nothing to do finds work.
+pub fn steal(cpu: usize) {
+    let (Some(queue), Some(busiest)) = (RunQueue::of_cpu(cpu), busiest(cpu)) else {
+        return;
+    };
+    if let Some(task) = busiest.steal(cpu) {
+        queue.push(task, true);
+    }
+}
+
+/// Moves tasks from the busiest CPU to `cpu` until both have about the same
+/// number of tasks. Only one queue is locked at a time.
+pub fn balance(cpu: usize) {
+    let (Some(queue), Some(busiest)) = (RunQueue::of_cpu(cpu), busiest(cpu)) else {
+        return;
+    };
+    let imbalance = busiest.len().saturating_sub(queue.len()) / 2;
+    for _ in 0..imbalance {
+        let Some(task) = busiest.steal(cpu) else {
+            break;
+        };
+        queue.push(task, true);
+    }
+}
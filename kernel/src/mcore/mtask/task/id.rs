@@ -20,6 +20,12 @@ where
     }
 }
 
+impl From<TaskId> for u64 {
+    fn from(tid: TaskId) -> Self {
+        tid.0
+    }
+}
+
 impl !Default for TaskId {}
 
 impl TaskId {
@@ -5,11 +5,14 @@ use alloc::sync::Arc;
 use core::ffi::c_void;
 use core::pin::Pin;
 use core::ptr::NonNull;
-use core::sync::atomic::AtomicBool;
 use core::sync::atomic::Ordering::Relaxed;
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
+use core::task::Waker;
+use core::time::Duration;
 
-use cordyceps::Linked;
 use cordyceps::mpsc_queue::Links;
+use cordyceps::{Linked, list};
+use kernel_sched::{Entity, Params, QueueState};
 use log::trace;
 use spin::RwLock;
 use x86_64::instructions::hlt;
@@ -21,6 +24,9 @@ use crate::mem::memapi::{LowerHalfAllocation, Writable};
 
 mod id;
 pub use id::*;
+mod parking;
+use parking::Parking;
+pub use parking::TaskHandle;
 mod queue;
 pub use queue::*;
 mod stack;
@@ -44,7 +50,13 @@ pub struct Task {
     /// If this task is currently running, then this value is not the current stack pointer.
     /// This must be set during the context switch.
     last_stack_ptr: Pin<Box<usize>>,
-    state: State,
+    /// The state, the CPU affinity and the scheduling parameters of the
+    /// task, which the wakers and handles of the task share.
+    parking: Arc<Parking>,
+    /// The CPU that the task ran on last, or [`NO_CPU`] if it didn't run yet.
+    last_cpu: AtomicUsize,
+    /// The virtual runtime of the task, see [`Entity::vruntime`].
+    vruntime: AtomicU64,
     /// The kernel stack of the task. Every task starts with a stack in the higher half.
     /// Userspace tasks will then allocate a stack in the lower half, which will be stored in
     /// `ustack`.
@@ -53,11 +65,20 @@ pub struct Task {
     /// The user stack of the task. This is only set if the task is a userspace task.
     ustack: RwLock<Option<LowerHalfAllocation<Writable>>>,
     tls: RwLock<Option<LowerHalfAllocation<Writable>>>,
+    /// The FS base of the task in userspace, which points to its thread-local
+    /// storage, or zero if it has none.
+    fs_base: AtomicU64,
+    /// The address that zero is written to when the task exits, or zero.
+    clear_child_tid: AtomicUsize,
     fx_area: RwLock<Option<LowerHalfAllocation<Writable>>>,
 
     links: Links<Self>,
+    run_queue_links: list::Links<Self>,
 }
 
+/// The [`Task::last_cpu`] of a task that didn't run yet.
+const NO_CPU: usize = usize::MAX;
+
 #[repr(C, align(16))]
 pub(crate) struct FxArea {
     data: [u8; 512],
@@ -65,6 +86,13 @@ pub(crate) struct FxArea {
 
 impl Unpin for Task {}
 
+impl Drop for Task {
+    fn drop(&mut self) {
+        self.process.tasks().write().remove(&self.tid);
+        self.process.task_handles().write().remove(&self.tid);
+    }
+}
+
 unsafe impl Linked<Links<Self>> for Task {
     type Handle = Pin<Box<Self>>;
 
@@ -82,6 +110,23 @@ unsafe impl Linked<Links<Self>> for Task {
     }
 }
 
+unsafe impl Linked<list::Links<Self>> for Task {
+    type Handle = Pin<Box<Self>>;
+
+    fn into_ptr(r: Self::Handle) -> NonNull<Self> {
+        NonNull::from(Box::leak(Pin::into_inner(r)))
+    }
+
+    unsafe fn from_ptr(ptr: NonNull<Self>) -> Self::Handle {
+        unsafe { Pin::new(Box::from_raw(ptr.as_ptr())) }
+    }
+
+    unsafe fn links(ptr: NonNull<Self>) -> NonNull<list::Links<Self>> {
+        let links = unsafe { &raw mut (*ptr.as_ptr()).run_queue_links };
+        unsafe { NonNull::new_unchecked(links) }
+    }
+}
+
 impl Task {
     /// Creates a new stack in the specified process. Stack will be allocated immediately in the
     /// current address space.
@@ -101,8 +146,13 @@ impl Task {
         let tid = TaskId::new();
         let name = format!("task-{tid}");
         let process = process.clone();
+        process.tasks().write().insert(tid, name.clone());
         let should_terminate = AtomicBool::new(false);
-        let state = State::Ready;
+        let parking = Arc::new(Parking::new(State::Ready));
+        process
+            .task_handles()
+            .write()
+            .insert(tid, TaskHandle::new(parking.clone()));
         let last_stack_ptr = Box::pin(stack.initial_rsp().as_u64().into_usize());
         let links = Links::default();
         Self {
@@ -111,12 +161,17 @@ impl Task {
             process,
             should_terminate,
             last_stack_ptr,
-            state,
+            parking,
+            last_cpu: AtomicUsize::new(NO_CPU),
+            vruntime: AtomicU64::new(0),
             kstack: Some(stack),
             ustack: RwLock::new(None),
             tls: RwLock::new(None),
+            fs_base: AtomicU64::new(0),
+            clear_child_tid: AtomicUsize::new(0),
             fx_area: RwLock::new(None),
             links,
+            run_queue_links: list::Links::new(),
         }
     }
 
@@ -126,7 +181,7 @@ impl Task {
         let process = Process::root().clone();
         let should_terminate = AtomicBool::new(false);
         let last_stack_ptr = Box::pin(0);
-        let state = State::Finished;
+        let parking = Arc::new(Parking::new(State::Finished));
         let links = Links::new_stub();
         Self {
             tid,
@@ -134,12 +189,17 @@ impl Task {
             process,
             should_terminate,
             last_stack_ptr,
-            state,
+            parking,
+            last_cpu: AtomicUsize::new(NO_CPU),
+            vruntime: AtomicU64::new(0),
             kstack: None,
             ustack: RwLock::new(None),
             tls: RwLock::new(None),
+            fs_base: AtomicU64::new(0),
+            clear_child_tid: AtomicUsize::new(0),
             fx_area: RwLock::new(None),
             links,
+            run_queue_links: list::Links::new(),
         }
     }
 
@@ -177,21 +237,31 @@ impl Task {
         let tid = TaskId::new();
         let name = format!("task-{tid}");
         let process = Process::root().clone();
+        process.tasks().write().insert(tid, name.clone());
         let should_terminate = AtomicBool::new(false);
         let last_stack_ptr = Box::pin(0);
-        let state = State::Running;
+        let parking = Arc::new(Parking::new(State::Running));
+        process
+            .task_handles()
+            .write()
+            .insert(tid, TaskHandle::new(parking.clone()));
         Self {
             tid,
             name,
             process,
             should_terminate,
             last_stack_ptr,
-            state,
+            parking,
+            last_cpu: AtomicUsize::new(NO_CPU),
+            vruntime: AtomicU64::new(0),
             kstack: None,
             ustack: RwLock::new(None),
             tls: RwLock::new(None),
+            fs_base: AtomicU64::new(0),
+            clear_child_tid: AtomicUsize::new(0),
             fx_area: RwLock::new(None),
             links: Links::default(),
+            run_queue_links: list::Links::new(),
         }
     }
 
@@ -216,7 +286,79 @@ impl Task {
     }
 
     pub fn state(&self) -> State {
-        self.state
+        self.parking.state()
+    }
+
+    /// Sets the state of the current task, which only the task itself and
+    /// the scheduler do. Wakers make it ready with [`Task::waker`].
+    pub(in crate::mcore::mtask) fn set_state(&self, state: State) {
+        self.parking.set_state(state);
+    }
+
+    /// Returns a waker that makes the task ready to run again if it is
+    /// blocked or sleeping.
+    pub fn waker(&self) -> Waker {
+        Waker::from(self.parking.clone())
+    }
+
+    /// Returns a handle that others can wake the task with and change its
+    /// CPU affinity with.
+    pub fn handle(&self) -> TaskHandle {
+        TaskHandle::new(self.parking.clone())
+    }
+
+    /// The CPUs that the task may run on, one bit per CPU.
+    pub fn affinity(&self) -> u64 {
+        self.parking.affinity()
+    }
+
+    pub fn may_run_on(&self, cpu: usize) -> bool {
+        u32::try_from(cpu)
+            .ok()
+            .and_then(|cpu| 1_u64.checked_shl(cpu))
+            .is_some_and(|bit| self.affinity() & bit != 0)
+    }
+
+    /// The CPU that the task ran on last, unless it didn't run yet.
+    pub fn last_cpu(&self) -> Option<usize> {
+        Some(self.last_cpu.load(Relaxed)).filter(|&cpu| cpu != NO_CPU)
+    }
+
+    pub(in crate::mcore::mtask) fn set_last_cpu(&self, cpu: usize) {
+        self.last_cpu.store(cpu, Relaxed);
+    }
+
+    pub fn params(&self) -> Params {
+        self.parking.params()
+    }
+
+    /// What the scheduling classes know about the task.
+    pub fn entity(&self) -> Entity {
+        Entity::new(self.params(), self.vruntime.load(Relaxed))
+    }
+
+    /// Accounts that the task ran for `ran`.
+    pub(in crate::mcore::mtask) fn charge(&self, ran: Duration) {
+        self.update_entity(|entity| entity.class().charge(entity, ran));
+    }
+
+    /// Prepares the task for being queued in the run queue with `queue`, see
+    /// [`SchedClass::place`](kernel_sched::SchedClass::place).
+    pub(in crate::mcore::mtask) fn place(&self, queue: &QueueState, migrated: bool) {
+        self.update_entity(|entity| entity.class().place(entity, queue, migrated));
+    }
+
+    fn update_entity(&self, f: impl FnOnce(&mut Entity)) {
+        let mut entity = self.entity();
+        f(&mut entity);
+        self.vruntime.store(entity.vruntime, Relaxed);
+    }
+
+    /// Parks `task` if it is blocked, see [`Parking::park`]. Returns the task
+    /// if it should be queued to run.
+    pub(in crate::mcore::mtask) fn park(task: Pin<Box<Self>>) -> Option<Pin<Box<Self>>> {
+        let parking = task.parking.clone();
+        parking.park(task)
     }
 
     pub fn kstack(&self) -> &Option<HigherHalfStack> {
@@ -231,6 +373,22 @@ impl Task {
         &self.tls
     }
 
+    pub fn fs_base(&self) -> u64 {
+        self.fs_base.load(Relaxed)
+    }
+
+    pub fn set_fs_base(&self, fs_base: u64) {
+        self.fs_base.store(fs_base, Relaxed);
+    }
+
+    pub fn clear_child_tid(&self) -> usize {
+        self.clear_child_tid.load(Relaxed)
+    }
+
+    pub fn set_clear_child_tid(&self, clear_child_tid: usize) {
+        self.clear_child_tid.store(clear_child_tid, Relaxed);
+    }
+
     pub fn fx_area(&self) -> &RwLock<Option<LowerHalfAllocation<Writable>>> {
         &self.fx_area
     }
@@ -0,0 +1,158 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use core::pin::Pin;
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering::Relaxed;
+use core::task::Waker;
+
+use kernel_sched::Params;
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::mcore::mtask::scheduler::run_queue;
+use crate::mcore::mtask::task::{State, Task};
+
+/// The state and the scheduling settings of a task, which are shared with
+/// the wakers and the [`TaskHandle`]s of the task.
+///
+/// A task that blocks is only parked here once the scheduler switched away
+/// from it, see [`Parking::park`]. A wake-up that comes before that makes
+/// the task ready again, so that the scheduler requeues it instead.
+#[derive(Debug)]
+pub(super) struct Parking {
+    inner: Mutex<Inner>,
+    /// The CPUs that the task may run on, one bit per CPU.
+    affinity: AtomicU64,
+    params: Mutex<Params>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: State,
+    /// The task while it is blocked and not running.
+    parked: Option<Pin<Box<Task>>>,
+}
+
+impl Parking {
+    pub(super) fn new(state: State) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                state,
+                parked: None,
+            }),
+            affinity: AtomicU64::new(u64::MAX),
+            params: Mutex::new(Params::default()),
+        }
+    }
+
+    pub(super) fn affinity(&self) -> u64 {
+        self.affinity.load(Relaxed)
+    }
+
+    pub(super) fn set_affinity(&self, affinity: u64) {
+        self.affinity.store(affinity, Relaxed);
+    }
+
+    /// The run queues read the parameters with interrupts disabled, so they
+    /// are only locked with interrupts disabled.
+    pub(super) fn params(&self) -> Params {
+        interrupts::without_interrupts(|| *self.params.lock())
+    }
+
+    pub(super) fn set_params(&self, params: Params) {
+        interrupts::without_interrupts(|| *self.params.lock() = params);
+    }
+
+    pub(super) fn state(&self) -> State {
+        interrupts::without_interrupts(|| self.inner.lock().state)
+    }
+
+    pub(super) fn set_state(&self, state: State) {
+        interrupts::without_interrupts(|| self.inner.lock().state = state);
+    }
+
+    /// Keeps `task` until it is woken if it is blocked, or returns it so
+    /// that it can be queued to run.
+    pub(super) fn park(&self, task: Pin<Box<Task>>) -> Option<Pin<Box<Task>>> {
+        let mut inner = self.inner.lock();
+        if inner.state.is_blocked() {
+            assert!(inner.parked.is_none(), "task should only be parked once");
+            inner.parked = Some(task);
+            None
+        } else {
+            inner.state = State::Ready;
+            Some(task)
+        }
+    }
+}
+
+impl Wake for Parking {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    /// Makes the task ready if it is blocked, and queues it if it was
+    /// parked already.
+    fn wake_by_ref(self: &Arc<Self>) {
+        let parked = interrupts::without_interrupts(|| {
+            let mut inner = self.inner.lock();
+            if !inner.state.is_blocked() {
+                return None;
+            }
+            inner.state = State::Ready;
+            inner.parked.take()
+        });
+        if let Some(task) = parked {
+            run_queue::enqueue(task);
+        }
+    }
+}
+
+/// A handle to a task that others than the owner of the task use to wake it
+/// and to change how it is scheduled.
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    parking: Arc<Parking>,
+}
+
+impl TaskHandle {
+    pub(super) fn new(parking: Arc<Parking>) -> Self {
+        Self { parking }
+    }
+
+    /// Makes the task ready to run again if it is blocked or sleeping.
+    pub fn wake(&self) {
+        self.parking.wake_by_ref();
+    }
+
+    #[must_use]
+    pub fn waker(&self) -> Waker {
+        Waker::from(self.parking.clone())
+    }
+
+    /// The CPUs that the task may run on, one bit per CPU.
+    #[must_use]
+    pub fn affinity(&self) -> u64 {
+        self.parking.affinity()
+    }
+
+    /// Restricts the task to the CPUs in `affinity`. A queued task moves
+    /// when its CPU dequeues it, and a running task with the next
+    /// reschedule.
+    pub fn set_affinity(&self, affinity: u64) {
+        assert_ne!(affinity, 0, "a task should be able to run somewhere");
+        self.parking.set_affinity(affinity);
+    }
+
+    #[must_use]
+    pub fn params(&self) -> Params {
+        self.parking.params()
+    }
+
+    /// Changes the scheduling parameters of the task, which take effect with
+    /// the next reschedule.
+    pub fn set_params(&self, params: Params) {
+        self.parking.set_params(params);
+    }
+}
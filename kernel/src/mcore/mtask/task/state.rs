@@ -1,6 +1,19 @@
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum State {
+    /// The task is in a run queue, or about to be put into one.
     Ready,
     Running,
+    /// The task waits until it is woken.
+    Blocked,
+    /// The task waits until it is woken or a deadline passed.
+    Sleeping,
     Finished,
 }
+
+impl State {
+    /// Whether the task waits to be woken.
+    #[must_use]
+    pub fn is_blocked(self) -> bool {
+        matches!(self, Self::Blocked | Self::Sleeping)
+    }
+}
@@ -0,0 +1,220 @@
+//! Blocking the current task until something happens.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::mem;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::SeqCst;
+use core::task::Waker;
+use core::time::Duration;
+
+use spin::Mutex;
+use thiserror::Error;
+use x86_64::instructions::interrupts;
+
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::mtask::task::{State, Task, TaskId};
+use crate::time;
+
+/// The tasks that sleep until a deadline, by their deadline.
+static SLEEPERS: Mutex<BTreeMap<(Duration, TaskId), Waker>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Error)]
+pub enum WaitError {
+    #[error("interrupted by a signal")]
+    Interrupted,
+    #[error("timed out")]
+    TimedOut,
+}
+
+/// Tasks that wait until a condition holds, which are woken when it may
+/// have changed.
+#[derive(Debug, Default)]
+pub struct WaitQueue {
+    wakers: Mutex<Vec<Waker>>,
+    /// The length of `wakers`, so that waking an empty queue doesn't take
+    /// the lock.
+    len: AtomicUsize,
+}
+
+impl WaitQueue {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            wakers: Mutex::new(Vec::new()),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Blocks the current task until `condition` holds, see [`block_until`].
+    /// The condition is checked again whenever the queue is woken.
+    ///
+    /// # Errors
+    /// Returns an error if the wait was interrupted by a signal or
+    /// `deadline` passed.
+    pub fn wait_until(
+        &self,
+        deadline: Option<Duration>,
+        mut condition: impl FnMut() -> bool,
+    ) -> Result<(), WaitError> {
+        let waker = ExecutionContext::load().current_task().waker();
+        let result = block_until(deadline, || {
+            // a wake-up takes the waker out of the queue, so it is queued
+            // again before every check
+            self.register(&waker);
+            condition()
+        });
+        interrupts::without_interrupts(|| {
+            let mut wakers = self.wakers.lock();
+            wakers.retain(|queued| !queued.will_wake(&waker));
+            self.len.store(wakers.len(), SeqCst);
+        });
+        result
+    }
+
+    /// Queues `waker` until the queue is woken, unless it is queued
+    /// already. The queue grows without holding its lock, since allocating
+    /// may log, and logging wakes a queue.
+    pub fn register(&self, waker: &Waker) {
+        let mut spare = Vec::new();
+        loop {
+            let full = interrupts::without_interrupts(|| {
+                let mut wakers = self.wakers.lock();
+                if wakers.iter().any(|queued| queued.will_wake(waker)) {
+                    return None;
+                }
+                if wakers.len() == wakers.capacity() {
+                    if spare.capacity() <= wakers.len() {
+                        return Some(wakers.len());
+                    }
+                    spare.append(&mut wakers);
+                    mem::swap(&mut *wakers, &mut spare);
+                }
+                wakers.push(waker.clone());
+                self.len.store(wakers.len(), SeqCst);
+                None
+            });
+            match full {
+                // the old buffer is freed without the lock as well
+                None => return,
+                Some(len) => spare = Vec::with_capacity((len * 2).max(4)),
+            }
+        }
+    }
+
+    /// Wakes all waiting tasks, which check their condition again. This
+    /// neither allocates nor frees memory, so it can be called from
+    /// interrupt handlers.
+    pub fn wake_all(&self) {
+        if self.len.load(SeqCst) == 0 {
+            return;
+        }
+        interrupts::without_interrupts(|| {
+            let mut wakers = self.wakers.lock();
+            self.len.store(0, SeqCst);
+            for waker in wakers.drain(..) {
+                waker.wake();
+            }
+        });
+    }
+}
+
+/// Blocks the current task until `condition` holds, see [`block_until`].
+/// `condition` gets the waker of the task, and registers it with the queues
+/// of everything that it checks before checking it. The waker stays in
+/// these queues until they are woken, which may wake the task once more
+/// after the wait ended.
+///
+/// # Errors
+/// Returns an error if the wait was interrupted by a signal or `deadline`
+/// passed.
+pub fn wait_until(
+    deadline: Option<Duration>,
+    mut condition: impl FnMut(&Waker) -> bool,
+) -> Result<(), WaitError> {
+    let waker = ExecutionContext::load().current_task().waker();
+    block_until(deadline, || condition(&waker))
+}
+
+/// Blocks the current task until `condition` holds, `deadline` passed, or
+/// the process of the task received a terminating signal. The task has to
+/// be woken with its [`Task::waker`] whenever `condition` may have changed.
+///
+/// The task is marked as blocked before `condition` is checked, so a
+/// wake-up between the check and the switch to another task isn't lost.
+/// This must be called with interrupts disabled, like syscalls are handled.
+///
+/// # Errors
+/// Returns an error if the wait was interrupted by a signal or `deadline`
+/// passed.
+pub fn block_until(
+    deadline: Option<Duration>,
+    mut condition: impl FnMut() -> bool,
+) -> Result<(), WaitError> {
+    assert!(!interrupts::are_enabled());
+    let task = ExecutionContext::load().current_task();
+    loop {
+        task.set_state(match deadline {
+            Some(_) => State::Sleeping,
+            None => State::Blocked,
+        });
+        let result = if condition() {
+            Some(Ok(()))
+        } else if task.process().pending_terminating_signal().is_some() {
+            Some(Err(WaitError::Interrupted))
+        } else if deadline.is_some_and(|deadline| time::uptime() >= deadline) {
+            Some(Err(WaitError::TimedOut))
+        } else {
+            None
+        };
+        if let Some(result) = result {
+            task.set_state(State::Running);
+            return result;
+        }
+        switch_away(task, deadline);
+    }
+}
+
+/// Runs other tasks until `task`, the current one, is woken.
+fn switch_away(task: &Task, deadline: Option<Duration>) {
+    let sleeper = deadline.map(|deadline| {
+        let key = (deadline, task.id());
+        interrupts::without_interrupts(|| SLEEPERS.lock().insert(key, task.waker()));
+        key
+    });
+    while task.state().is_blocked() {
+        // the task may continue on another CPU
+        unsafe { ExecutionContext::load().scheduler_mut().reschedule() };
+        if task.state().is_blocked() {
+            // there was nothing else to run, and an interrupt may wake the
+            // task or switch away from it
+            interrupts::enable_and_hlt();
+            interrupts::disable();
+        }
+    }
+    task.set_state(State::Running);
+    if let Some(key) = sleeper {
+        interrupts::without_interrupts(|| SLEEPERS.lock().remove(&key));
+    }
+}
+
+/// Wakes the tasks whose deadline passed. This is called by the timer
+/// interrupt handler, so the tasks remove their entries themselves, which
+/// may free memory.
+pub fn wake_sleepers() {
+    // another CPU is waking them already, or a task is going to sleep, in
+    // which case they are woken with the next tick
+    let Some(sleepers) = SLEEPERS.try_lock() else {
+        return;
+    };
+    let Some(now) = time::try_uptime() else {
+        return;
+    };
+    for waker in sleepers
+        .iter()
+        .take_while(|((deadline, _), _)| *deadline <= now)
+        .map(|(_, waker)| waker)
+    {
+        waker.wake_by_ref();
+    }
+}
@@ -441,7 +441,6 @@ impl AddressSpace {
         self.inner.write().remap_range(pages.into(), &f)
     }
 
-    #[allow(dead_code)]
     pub fn translate(&self, vaddr: VirtAddr) -> Option<PhysAddr> {
         self.inner.read().translate(vaddr)
     }
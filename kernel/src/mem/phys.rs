@@ -132,6 +132,28 @@ impl PhysicalMemory {
     {
         allocator().lock().deallocate_frames(range);
     }
+
+    /// Returns the number of usable 4 KiB frames, whether allocated or not.
+    ///
+    /// Returns 0 while the stage 1 allocator is active, since it doesn't
+    /// keep track of individual frames.
+    pub fn usable_frames() -> usize {
+        match &*allocator().lock() {
+            MultiStageAllocator::Stage1(_) => 0,
+            MultiStageAllocator::Stage2(a) => a.usable_frames(),
+        }
+    }
+
+    /// Returns the number of free 4 KiB frames.
+    ///
+    /// Returns 0 while the stage 1 allocator is active, since it doesn't
+    /// keep track of individual frames.
+    pub fn free_frames() -> usize {
+        match &*allocator().lock() {
+            MultiStageAllocator::Stage1(_) => 0,
+            MultiStageAllocator::Stage2(a) => a.free_frames(),
+        }
+    }
 }
 
 unsafe impl x86_64::structures::paging::FrameAllocator<Size4KiB> for PhysicalMemory {
@@ -0,0 +1,45 @@
+//! The DHCP client of the network device, which is smoltcp's DHCP socket.
+//! Until the first lease, the interface uses the configuration of QEMU's
+//! user mode network. The lease is renewed by the socket, and if it is
+//! lost, the network device has no address until the next one.
+
+use alloc::vec::Vec;
+
+use log::{info, warn};
+use smoltcp::socket::dhcpv4::{self, Event};
+
+use crate::net::NetworkStack;
+
+impl NetworkStack {
+    /// Applies the configuration that the DHCP client acquired since the
+    /// last poll.
+    pub(super) fn poll_dhcp(&mut self) {
+        let Some(handle) = self.dhcp else {
+            return;
+        };
+        let (address, router, dns_servers) =
+            match self.sockets.get_mut::<dhcpv4::Socket>(handle).poll() {
+                None => return,
+                Some(Event::Configured(config)) => (
+                    config.address,
+                    config.router,
+                    config.dns_servers.iter().copied().collect::<Vec<_>>(),
+                ),
+                Some(Event::Deconfigured) => {
+                    warn!("lost the DHCP lease");
+                    self.configure(None, None, Vec::new());
+                    return;
+                }
+            };
+
+        info!("DHCP lease for address {address}");
+        match router {
+            Some(router) => info!("default gateway {router}"),
+            None => info!("no default gateway"),
+        }
+        for server in &dns_servers {
+            info!("DNS server {server}");
+        }
+        self.configure(Some(address), router, dns_servers);
+    }
+}
@@ -0,0 +1,121 @@
+//! The loopback interface `lo`, which delivers the frames that the stack
+//! sends to one of its own addresses back to the stack, instead of passing
+//! them to the network device. This works without a network device, so
+//! sockets can always talk to `127.0.0.1` and `::1`.
+//!
+//! ARP requests and IPv6 neighbor solicitations for local addresses are
+//! looped back as well, so the stack answers them itself and learns that
+//! its own addresses belong to its own hardware address.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use smoltcp::wire::{
+    ArpPacket, EthernetFrame, EthernetProtocol, IpAddress, IpCidr, Ipv4Address, Ipv4Cidr,
+    Ipv4Packet, Ipv6Address, Ipv6Cidr, Ipv6Packet,
+};
+
+/// The addresses of the loopback interface.
+pub const LOOPBACK_CIDRS: [IpCidr; 2] = [
+    IpCidr::Ipv4(Ipv4Cidr::new(Ipv4Address::new(127, 0, 0, 1), 8)),
+    IpCidr::Ipv6(Ipv6Cidr::new(Ipv6Address::LOCALHOST, 128)),
+];
+
+/// How many frames can be queued before further frames are dropped, like
+/// on a device whose queue is full.
+const QUEUE_LEN: usize = 64;
+
+#[derive(Default)]
+pub struct Loopback {
+    frames: VecDeque<Vec<u8>>,
+    /// The addresses of the stack, in addition to the loopback addresses.
+    addresses: Vec<IpAddress>,
+}
+
+impl Loopback {
+    pub fn set_addresses(&mut self, addresses: Vec<IpAddress>) {
+        self.addresses = addresses;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Queues `frame` if it is addressed to the stack itself, and returns
+    /// whether it was taken.
+    pub fn send(&mut self, frame: &[u8]) -> bool {
+        if !self.is_for_local_address(frame) {
+            return false;
+        }
+        if self.frames.len() < QUEUE_LEN {
+            self.frames.push_back(frame.to_vec());
+        }
+        true
+    }
+
+    /// Takes the oldest queued frame and copies it into `buf`, truncated to
+    /// the length of `buf`.
+    pub fn receive(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let frame = self.frames.pop_front()?;
+        let len = frame.len().min(buf.len());
+        buf[..len].copy_from_slice(&frame[..len]);
+        Some(len)
+    }
+
+    fn is_for_local_address(&self, frame: &[u8]) -> bool {
+        let Ok(frame) = EthernetFrame::new_checked(frame) else {
+            return false;
+        };
+        let destination = match frame.ethertype() {
+            EthernetProtocol::Ipv4 => {
+                let Ok(packet) = Ipv4Packet::new_checked(frame.payload()) else {
+                    return false;
+                };
+                IpAddress::Ipv4(packet.dst_addr())
+            }
+            EthernetProtocol::Ipv6 => {
+                let Ok(packet) = Ipv6Packet::new_checked(frame.payload()) else {
+                    return false;
+                };
+                IpAddress::Ipv6(packet.dst_addr())
+            }
+            EthernetProtocol::Arp => {
+                let Ok(packet) = ArpPacket::new_checked(frame.payload()) else {
+                    return false;
+                };
+                let Ok(address) = <[u8; 4]>::try_from(packet.target_protocol_addr()) else {
+                    return false;
+                };
+                IpAddress::Ipv4(Ipv4Address::from(address))
+            }
+            _ => return false,
+        };
+        LOOPBACK_CIDRS
+            .iter()
+            .any(|cidr| cidr.contains_addr(&destination))
+            || self.addresses.iter().any(|&address| {
+                address == destination
+                    || matches!(
+                        (address, destination),
+                        (IpAddress::Ipv6(address), IpAddress::Ipv6(destination))
+                            if solicited_node(address) == destination
+                    )
+            })
+    }
+}
+
+/// The multicast address that neighbor solicitations for `address` are
+/// sent to, `ff02::1:ff00:0/104` with the last 24 bits of `address`.
+fn solicited_node(address: Ipv6Address) -> Ipv6Address {
+    let octets = address.octets();
+    Ipv6Address::new(
+        0xff02,
+        0,
+        0,
+        0,
+        0,
+        1,
+        0xff00 | u16::from(octets[13]),
+        u16::from_be_bytes([octets[14], octets[15]]),
+    )
+}
@@ -0,0 +1,451 @@
+//! The TCP/IP stack, which is smoltcp on top of the first network device
+//! and the [loopback interface](loopback). A kernel task polls the
+//! interface, which moves frames between the devices and the sockets,
+//! answers ARP requests and pings, and runs the TCP timers. The task sleeps
+//! until a device received frames or the next timer is due. Socket
+//! operations poll the interface as well, so that data is sent without
+//! waiting for the task.
+//!
+//! There is a single smoltcp interface, which has the address of the
+//! network device as well as `127.0.0.1/8` and `::1/128`, and frames that it sends to its
+//! own addresses are looped back before they reach the device. Without a
+//! network device, the interface only has the loopback addresses.
+//!
+//! The address of the network device, the default route and the DNS
+//! servers are configured [through DHCP](dhcp). The DNS servers are
+//! published in `/proc/net/resolv.conf` for resolvers in userspace.
+//!
+//! [Unix sockets](unix) don't use the stack.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::time::Duration;
+
+use conquer_once::spin::OnceCell;
+use kernel_device::network::ETHERNET_HEADER_LEN;
+use kernel_syscall::access::SocketError;
+use log::{info, trace};
+use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
+use smoltcp::phy::{self, DeviceCapabilities, Medium};
+use smoltcp::socket::{dhcpv4, tcp};
+use smoltcp::time::Instant;
+use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr, Ipv4Address, Ipv4Cidr};
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::driver::net::{NetworkDevices, SharedNetworkDevice};
+use crate::mcore::mtask::process::Process;
+use crate::mcore::mtask::scheduler::run_queue;
+use crate::mcore::mtask::task::Task;
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::net::loopback::{LOOPBACK_CIDRS, Loopback};
+use crate::{random, time};
+
+pub mod dhcp;
+pub mod loopback;
+pub mod socket;
+pub mod unix;
+
+/// The address that QEMU's user mode network assigns to the guest, which
+/// is used until the address is configured through DHCP.
+const ADDRESS: Ipv4Address = Ipv4Address::new(10, 0, 2, 15);
+const PREFIX_LEN: u8 = 24;
+const GATEWAY: Ipv4Address = Ipv4Address::new(10, 0, 2, 2);
+const DNS_SERVER: Ipv4Address = Ipv4Address::new(10, 0, 2, 3);
+
+/// The hardware address of the interface if there is no network device,
+/// which is a locally administered one.
+const LOOPBACK_HARDWARE_ADDRESS: EthernetAddress = EthernetAddress([0x02, 0, 0, 0, 0, 0]);
+/// The MTU of the interface if there is no network device.
+const LOOPBACK_MTU: usize = 1500;
+/// How often the interface is polled at most while there are looped back
+/// frames.
+const LOOPBACK_POLLS: usize = 8;
+
+/// The ports that are picked for sockets that aren't bound to one.
+const EPHEMERAL_PORTS: core::ops::RangeInclusive<u16> = 49152..=65535;
+
+static STACK: OnceCell<Mutex<NetworkStack>> = OnceCell::uninit();
+
+/// The network task, which waits until [`PENDING`] is set or the next
+/// timer is due.
+static POLL_TASK: WaitQueue = WaitQueue::new();
+/// Set when the stack has to be polled before the network task would poll
+/// it anyway, because frames were received or a timer moved forward.
+static PENDING: AtomicBool = AtomicBool::new(false);
+/// When the network task polls the stack next, in microseconds since boot.
+static NEXT_POLL: AtomicU64 = AtomicU64::new(u64::MAX);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+}
+
+pub struct NetworkStack {
+    iface: Interface,
+    device: DeviceAdapter,
+    sockets: SocketSet<'static>,
+    /// The ports that sockets are bound to.
+    ports: BTreeSet<(Protocol, u16)>,
+    next_ephemeral_port: u16,
+    /// TCP connections that were closed by their socket, but are still
+    /// exchanging their last segments. They are removed once they are
+    /// closed.
+    closing: Vec<SocketHandle>,
+    /// The DHCP client, if there is a network device.
+    dhcp: Option<SocketHandle>,
+    dns_servers: Vec<Ipv4Address>,
+}
+
+/// Creates the interface for the first network device and the loopback
+/// interface, and starts the task that polls it.
+pub fn init() {
+    let network_device = NetworkDevices::all().into_iter().next();
+    if let Some((name, _)) = &network_device {
+        info!("configuring {name} through DHCP");
+    } else {
+        info!("no network device, only the loopback interface is available");
+    }
+
+    let has_network_device = network_device.is_some();
+    let mut device = DeviceAdapter::new(network_device.map(|(_, device)| device));
+    let mut config = Config::new(HardwareAddress::Ethernet(device.hardware_address));
+    let mut seed = [0; 8];
+    random::fill_bytes(&mut seed);
+    config.random_seed = u64::from_ne_bytes(seed);
+
+    let iface = Interface::new(config, &mut device, now());
+    let mut stack = NetworkStack {
+        iface,
+        device,
+        sockets: SocketSet::new(Vec::new()),
+        ports: BTreeSet::new(),
+        next_ephemeral_port: *EPHEMERAL_PORTS.start(),
+        closing: Vec::new(),
+        dhcp: None,
+        dns_servers: Vec::new(),
+    };
+    if has_network_device {
+        stack.configure(
+            Some(Ipv4Cidr::new(ADDRESS, PREFIX_LEN)),
+            Some(GATEWAY),
+            vec![DNS_SERVER],
+        );
+        stack.dhcp = Some(stack.sockets.add(dhcpv4::Socket::new()));
+    } else {
+        stack.configure(None, None, Vec::new());
+    }
+    STACK.init_once(|| Mutex::new(stack));
+
+    let task = Task::create_new(Process::root(), poll_task, ptr::null_mut())
+        .expect("should be able to create network task");
+    info!("network task created with id {}", task.id());
+    run_queue::enqueue(Box::pin(task));
+}
+
+/// Runs `f` with the locked stack, after polling the interface for frames
+/// that arrived in the meantime, and polls it again afterwards to send what
+/// `f` queued.
+///
+/// # Errors
+/// Returns [`SocketError::NetworkDown`] if there is no interface.
+pub fn with_stack<T>(f: impl FnOnce(&mut NetworkStack) -> T) -> Result<T, SocketError> {
+    let stack = STACK.get().ok_or(SocketError::NetworkDown)?;
+    Ok(interrupts::without_interrupts(|| {
+        let mut stack = stack.lock();
+        stack.poll();
+        let result = f(&mut stack);
+        stack.poll();
+        // `f` may have started a timer, like the retransmission of data
+        if stack.next_poll() < NEXT_POLL.load(Ordering::Relaxed) {
+            wake_poll_task();
+        }
+        result
+    }))
+}
+
+/// Wakes the network task after a device received frames. This is called
+/// by the interrupt handlers of drivers, so it doesn't allocate.
+pub fn frames_received() {
+    wake_poll_task();
+}
+
+fn wake_poll_task() {
+    PENDING.store(true, Ordering::Relaxed);
+    POLL_TASK.wake_all();
+}
+
+/// The DNS servers of the interface, in order of preference.
+pub fn dns_servers() -> Vec<Ipv4Address> {
+    with_stack(|stack| stack.dns_servers.clone()).unwrap_or_default()
+}
+
+/// Returns a number that identifies a new socket, like the inode number of
+/// a file.
+fn next_socket_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+extern "C" fn poll_task(_: *mut c_void) {
+    loop {
+        PENDING.store(false, Ordering::Relaxed);
+        let next_poll = with_stack(|stack| {
+            let next_poll = stack.next_poll();
+            NEXT_POLL.store(next_poll, Ordering::Relaxed);
+            next_poll
+        })
+        .expect("should have a stack, which is created before the task");
+        let deadline = (next_poll != u64::MAX).then(|| Duration::from_micros(next_poll));
+        // a timeout is the timer that is due, and the task isn't
+        // interrupted by signals
+        let _ = interrupts::without_interrupts(|| {
+            POLL_TASK.wait_until(deadline, || PENDING.load(Ordering::Relaxed))
+        });
+    }
+}
+
+fn now() -> Instant {
+    Instant::from_micros(i64::try_from(time::uptime().as_micros()).unwrap_or(i64::MAX))
+}
+
+impl NetworkStack {
+    /// When the stack has to be polled next for its timers, in microseconds
+    /// since boot, or `u64::MAX` if no timer is running.
+    fn next_poll(&mut self) -> u64 {
+        self.iface
+            .poll_at(now(), &self.sockets)
+            .map_or(u64::MAX, |at| u64::try_from(at.total_micros()).unwrap_or(0))
+    }
+
+    fn poll(&mut self) {
+        // looped back frames are answered by the stack itself, so poll again
+        // until the exchange settles, within reason. Sockets that changed
+        // wake the tasks that wait for them.
+        for _ in 0..LOOPBACK_POLLS {
+            self.iface.poll(now(), &mut self.device, &mut self.sockets);
+            if self.device.loopback.is_empty() {
+                break;
+            }
+        }
+
+        self.poll_dhcp();
+
+        let sockets = &mut self.sockets;
+        self.closing.retain(|&handle| {
+            let state = sockets.get::<tcp::Socket>(handle).state();
+            let closed = matches!(state, tcp::State::Closed | tcp::State::TimeWait);
+            if closed {
+                sockets.remove(handle);
+            }
+            !closed
+        });
+    }
+
+    /// Sets the address of the network device, the default route and the
+    /// DNS servers. The loopback addresses are always there.
+    fn configure(
+        &mut self,
+        address: Option<Ipv4Cidr>,
+        gateway: Option<Ipv4Address>,
+        dns_servers: Vec<Ipv4Address>,
+    ) {
+        // the first address is the source address for destinations that
+        // aren't in the network of any address, so the loopback addresses
+        // come last
+        let addresses = address
+            .map(IpCidr::Ipv4)
+            .into_iter()
+            .chain(LOOPBACK_CIDRS)
+            .collect::<Vec<_>>();
+        self.iface.update_ip_addrs(|ip_addrs| {
+            ip_addrs.clear();
+            for address in &addresses {
+                ip_addrs
+                    .push(*address)
+                    .expect("should have space for all addresses");
+            }
+        });
+        self.device
+            .loopback
+            .set_addresses(addresses.iter().map(IpCidr::address).collect());
+
+        let routes = self.iface.routes_mut();
+        match gateway {
+            Some(gateway) => {
+                routes
+                    .add_default_ipv4_route(gateway)
+                    .expect("should have space for the default route");
+            }
+            None => {
+                routes.remove_default_ipv4_route();
+            }
+        }
+
+        self.dns_servers = dns_servers;
+    }
+
+    /// Whether `addr` is an address that sockets can be bound to.
+    fn is_local_address(&self, addr: IpAddress) -> bool {
+        addr.is_unspecified() || self.iface.has_ip_addr(addr)
+    }
+
+    /// Reserves `port` for a socket, or a free ephemeral port if `port` is
+    /// zero, and returns the port.
+    fn reserve_port(&mut self, protocol: Protocol, port: u16) -> Result<u16, SocketError> {
+        if port != 0 {
+            return if self.ports.insert((protocol, port)) {
+                Ok(port)
+            } else {
+                Err(SocketError::AddressInUse)
+            };
+        }
+
+        for _ in EPHEMERAL_PORTS {
+            let port = self.next_ephemeral_port;
+            self.next_ephemeral_port = if port == *EPHEMERAL_PORTS.end() {
+                *EPHEMERAL_PORTS.start()
+            } else {
+                port + 1
+            };
+            if self.ports.insert((protocol, port)) {
+                return Ok(port);
+            }
+        }
+        Err(SocketError::AddressInUse)
+    }
+
+    fn release_port(&mut self, protocol: Protocol, port: u16) {
+        self.ports.remove(&(protocol, port));
+    }
+
+    /// Closes the TCP connection gracefully and removes it once the last
+    /// segments are exchanged.
+    fn close_tcp(&mut self, handle: SocketHandle) {
+        self.sockets.get_mut::<tcp::Socket>(handle).close();
+        self.closing.push(handle);
+    }
+}
+
+/// Passes frames between smoltcp and a [`NetworkDevice`], through buffers
+/// that are reused for every frame. Frames for local addresses are passed
+/// to the [`Loopback`] instead.
+///
+/// [`NetworkDevice`]: kernel_device::network::NetworkDevice
+struct DeviceAdapter {
+    device: Option<SharedNetworkDevice>,
+    loopback: Loopback,
+    hardware_address: EthernetAddress,
+    mtu: usize,
+    rx_buffer: Vec<u8>,
+    tx_buffer: Vec<u8>,
+}
+
+impl DeviceAdapter {
+    fn new(device: Option<SharedNetworkDevice>) -> Self {
+        let (hardware_address, mtu) = match &device {
+            Some(device) => {
+                let device = device.read();
+                (EthernetAddress(device.mac_address().0), device.mtu())
+            }
+            None => (LOOPBACK_HARDWARE_ADDRESS, LOOPBACK_MTU),
+        };
+        Self {
+            device,
+            loopback: Loopback::default(),
+            hardware_address,
+            mtu,
+            rx_buffer: vec![0; ETHERNET_HEADER_LEN + mtu],
+            tx_buffer: vec![0; ETHERNET_HEADER_LEN + mtu],
+        }
+    }
+}
+
+impl phy::Device for DeviceAdapter {
+    type RxToken<'a>
+        = RxToken<'a>
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TxToken<'a>
+    where
+        Self: 'a;
+
+    fn receive(&mut self, _: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let len = self.loopback.receive(&mut self.rx_buffer).or_else(|| {
+            let device = self.device.as_ref()?;
+            device.write().receive(&mut self.rx_buffer).ok()
+        })?;
+        Some((
+            RxToken(&self.rx_buffer[..len]),
+            TxToken {
+                device: self.device.as_ref(),
+                loopback: &mut self.loopback,
+                buffer: &mut self.tx_buffer,
+            },
+        ))
+    }
+
+    fn transmit(&mut self, _: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TxToken {
+            device: self.device.as_ref(),
+            loopback: &mut self.loopback,
+            buffer: &mut self.tx_buffer,
+        })
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut capabilities = DeviceCapabilities::default();
+        capabilities.medium = Medium::Ethernet;
+        // for Ethernet, smoltcp counts the header into the MTU
+        capabilities.max_transmission_unit = ETHERNET_HEADER_LEN + self.mtu;
+        capabilities
+    }
+}
+
+struct RxToken<'a>(&'a [u8]);
+
+impl phy::RxToken for RxToken<'_> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(self.0)
+    }
+}
+
+struct TxToken<'a> {
+    device: Option<&'a SharedNetworkDevice>,
+    loopback: &'a mut Loopback,
+    buffer: &'a mut Vec<u8>,
+}
+
+impl phy::TxToken for TxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        if self.buffer.len() < len {
+            self.buffer.resize(len, 0);
+        }
+        let frame = &mut self.buffer[..len];
+        let result = f(frame);
+        if self.loopback.send(frame) {
+            return result;
+        }
+        // without a network device, there is nowhere to send the frame to
+        if let Some(device) = self.device
+            && let Err(e) = device.write().transmit(frame)
+        {
+            // lost frames are recovered by the protocols
+            trace!("failed to transmit frame: {e}");
+        }
+        result
+    }
+}
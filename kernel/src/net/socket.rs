@@ -0,0 +1,929 @@
+//! Sockets of the TCP/IP stack, which are what socket file descriptors
+//! refer to. Their operations never wait, but fail with
+//! [`SocketError::WouldBlock`], and the syscalls wait and try again.
+//!
+//! Like on Linux, IPv6 sockets also talk to IPv4 peers, whose addresses
+//! are mapped into IPv6 ones (`::ffff:a.b.c.d`), while IPv4 sockets only
+//! talk IPv4.
+
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::net::{IpAddr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+
+use kernel_abi::{Errno, SOCK_DGRAM, SOCK_STREAM};
+use kernel_syscall::access::{Shutdown, SocketAddress, SocketError, SocketOption, SocketType};
+use kernel_vfs::Readiness;
+use smoltcp::iface::SocketHandle;
+use smoltcp::socket::{tcp, udp};
+use smoltcp::time::Duration;
+use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint, IpVersion, Ipv4Address, Ipv6Address};
+use spin::Mutex;
+
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::net::{NetworkStack, Protocol, next_socket_id, with_stack};
+
+const TCP_BUFFER_SIZE: usize = 32 * 1024;
+const UDP_BUFFER_SIZE: usize = 16 * 1024;
+/// How many datagrams the buffers of a UDP socket hold.
+const UDP_BUFFER_PACKETS: usize = 32;
+/// The most connections that a listening socket establishes before they
+/// are accepted, since every one of them needs its own buffers.
+const MAX_BACKLOG: usize = 16;
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(75);
+/// The UDP header, which has to fit into the MTU together with the IP
+/// header and the payload, since datagrams aren't fragmented.
+const UDP_HEADER_LEN: usize = 8;
+
+#[derive(Debug)]
+pub struct Socket {
+    id: u64,
+    /// The address family, `AF_INET` or `AF_INET6`.
+    version: IpVersion,
+    typ: SocketType,
+    nonblocking: AtomicBool,
+    inner: Mutex<Inner>,
+    waiters: Arc<Waiters>,
+    /// Wakes `waiters`. It is registered with the sockets of the stack,
+    /// which wake it when they change.
+    waker: Waker,
+}
+
+/// The tasks that wait for a socket to change.
+#[derive(Debug, Default)]
+struct Waiters(WaitQueue);
+
+impl Wake for Waiters {
+    fn wake(self: Arc<Self>) {
+        self.0.wake_all();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.wake_all();
+    }
+}
+
+#[derive(Debug)]
+enum Inner {
+    Tcp(TcpSocket),
+    Udp(UdpSocket),
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct Options {
+    reuse_address: bool,
+    broadcast: bool,
+    keep_alive: bool,
+    no_delay: bool,
+}
+
+#[derive(Debug, Default)]
+struct TcpSocket {
+    state: TcpState,
+    /// The port that is reserved for the socket. Accepted connections share
+    /// the port of the listening socket, and don't reserve it.
+    port: Option<u16>,
+    /// The address that the socket is bound to, which is `None` for all
+    /// local addresses.
+    address: Option<IpAddress>,
+    options: Options,
+    /// Whether the connection was started, but isn't established yet.
+    connecting: bool,
+    /// The error of a non-blocking `connect`, for [`SocketOption::Error`].
+    error: Option<SocketError>,
+    shut_read: bool,
+    shut_write: bool,
+}
+
+#[derive(Debug, Default)]
+enum TcpState {
+    #[default]
+    Unconnected,
+    /// Sockets that are listening for a connection each, and that are
+    /// replaced when their connection is accepted.
+    Listening(Vec<SocketHandle>),
+    Connected(SocketHandle),
+}
+
+#[derive(Debug, Default)]
+struct UdpSocket {
+    /// The socket of the stack, which is created when the socket is bound,
+    /// either explicitly or by sending.
+    handle: Option<SocketHandle>,
+    port: Option<u16>,
+    address: Option<IpAddress>,
+    /// The address that was connected to, which is the default destination
+    /// and the only source that datagrams are received from.
+    remote: Option<IpEndpoint>,
+    options: Options,
+    shut_read: bool,
+    shut_write: bool,
+}
+
+impl Socket {
+    pub fn new(version: IpVersion, typ: SocketType, nonblocking: bool) -> Self {
+        let inner = match typ {
+            SocketType::Stream => Inner::Tcp(TcpSocket::default()),
+            SocketType::Datagram => Inner::Udp(UdpSocket::default()),
+        };
+        let waiters = Arc::new(Waiters::default());
+        Self {
+            id: next_socket_id(),
+            version,
+            typ,
+            nonblocking: AtomicBool::new(nonblocking),
+            inner: Mutex::new(inner),
+            waker: Waker::from(waiters.clone()),
+            waiters,
+        }
+    }
+
+    /// A number that identifies the socket, like the inode number of a
+    /// file.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    pub fn bind(&self, address: SocketAddr) -> Result<(), SocketError> {
+        let IpEndpoint { addr, port } = self.endpoint(address)?;
+        let address = (!addr.is_unspecified()).then_some(addr);
+        match &mut *self.inner.lock() {
+            Inner::Tcp(tcp) => {
+                if tcp.port.is_some() || !matches!(tcp.state, TcpState::Unconnected) {
+                    return Err(SocketError::InvalidArgument);
+                }
+                let port = with_stack(|stack| {
+                    if !stack.is_local_address(addr) {
+                        return Err(SocketError::AddressNotAvailable);
+                    }
+                    stack.reserve_port(Protocol::Tcp, port)
+                })??;
+                tcp.port = Some(port);
+                tcp.address = address;
+                Ok(())
+            }
+            Inner::Udp(udp) => {
+                if udp.handle.is_some() {
+                    return Err(SocketError::InvalidArgument);
+                }
+                with_stack(|stack| udp.bind(stack, address, port))?.map(|_| ())
+            }
+        }
+    }
+
+    pub fn listen(&self, backlog: usize) -> Result<(), SocketError> {
+        let Inner::Tcp(tcp) = &mut *self.inner.lock() else {
+            return Err(SocketError::NotSupported);
+        };
+        match tcp.state {
+            TcpState::Unconnected => {}
+            // like on Linux, listening again only changes the backlog,
+            // which is fixed here
+            TcpState::Listening(_) => return Ok(()),
+            TcpState::Connected(_) => return Err(SocketError::InvalidArgument),
+        }
+
+        let handles = with_stack(|stack| {
+            let port = match tcp.port {
+                Some(port) => port,
+                None => stack.reserve_port(Protocol::Tcp, 0)?,
+            };
+            tcp.port = Some(port);
+            let endpoint = listen_endpoint(tcp.address, port);
+            (0..backlog.clamp(1, MAX_BACKLOG))
+                .map(|_| listener(stack, endpoint, tcp.options))
+                .collect::<Result<Vec<_>, _>>()
+        })??;
+        tcp.state = TcpState::Listening(handles);
+        Ok(())
+    }
+
+    /// Takes an established connection from a listening socket, and
+    /// returns a socket for it together with the address of the peer.
+    pub fn accept(&self) -> Result<(Socket, SocketAddress), SocketError> {
+        let Inner::Tcp(tcp) = &mut *self.inner.lock() else {
+            return Err(SocketError::NotSupported);
+        };
+        let TcpState::Listening(handles) = &mut tcp.state else {
+            return Err(SocketError::InvalidArgument);
+        };
+        let endpoint = listen_endpoint(tcp.address, tcp.port.unwrap_or_default());
+        let options = tcp.options;
+        let version = self.version;
+
+        let (handle, local, peer) = with_stack(|stack| {
+            for handle in handles.iter_mut() {
+                let socket = stack.sockets.get_mut::<tcp::Socket>(*handle);
+                match socket.state() {
+                    tcp::State::Listen | tcp::State::SynReceived => {}
+                    // the connection was reset before it was accepted
+                    tcp::State::Closed => {
+                        socket
+                            .listen(endpoint)
+                            .map_err(|_| SocketError::InvalidArgument)?;
+                    }
+                    _ => {
+                        let local = socket.local_endpoint();
+                        let peer = socket.remote_endpoint();
+                        if peer.is_some_and(|peer| !accepts(version, peer)) {
+                            // an IPv4 socket that is bound to all addresses
+                            // resets IPv6 connections, and listens again
+                            // once they are closed
+                            socket.abort();
+                            continue;
+                        }
+                        let connection =
+                            core::mem::replace(handle, listener(stack, endpoint, options)?);
+                        return Ok((connection, local, peer));
+                    }
+                }
+            }
+            Err(SocketError::WouldBlock)
+        })??;
+
+        let socket = Socket::new(version, SocketType::Stream, false);
+        *socket.inner.lock() = Inner::Tcp(TcpSocket {
+            state: TcpState::Connected(handle),
+            address: local.map(|local| local.addr),
+            options,
+            ..TcpSocket::default()
+        });
+        let peer = peer.unwrap_or(IpEndpoint::new(unspecified(version), 0));
+        Ok((socket, socket_address(version, peer)))
+    }
+
+    /// Starts to connect a stream socket, which is established once
+    /// [`finish_connect`](Self::finish_connect) succeeds. Datagram sockets
+    /// are connected right away.
+    pub fn connect(&self, address: SocketAddr) -> Result<(), SocketError> {
+        let remote = self.endpoint(address)?;
+        match &mut *self.inner.lock() {
+            Inner::Tcp(tcp) => {
+                match tcp.state {
+                    TcpState::Unconnected => {}
+                    TcpState::Listening(_) => return Err(SocketError::InvalidArgument),
+                    TcpState::Connected(_) if tcp.connecting => {
+                        return match tcp.finish_connect() {
+                            Ok(()) => Err(SocketError::AlreadyConnected),
+                            Err(SocketError::WouldBlock) => Err(SocketError::AlreadyInProgress),
+                            Err(e) => Err(e),
+                        };
+                    }
+                    TcpState::Connected(_) => return Err(SocketError::AlreadyConnected),
+                }
+
+                let handle = with_stack(|stack| {
+                    let port = match tcp.port {
+                        Some(port) => port,
+                        None => stack.reserve_port(Protocol::Tcp, 0)?,
+                    };
+                    tcp.port = Some(port);
+                    let mut socket = tcp_socket(tcp.options);
+                    socket
+                        .connect(
+                            stack.iface.context(),
+                            remote,
+                            listen_endpoint(tcp.address, port),
+                        )
+                        .map_err(|e| match e {
+                            tcp::ConnectError::InvalidState => SocketError::AlreadyConnected,
+                            tcp::ConnectError::Unaddressable => SocketError::AddressNotAvailable,
+                        })?;
+                    Ok(stack.sockets.add(socket))
+                })??;
+                tcp.state = TcpState::Connected(handle);
+                tcp.connecting = true;
+                tcp.error = None;
+                Ok(())
+            }
+            Inner::Udp(udp) => {
+                if udp.handle.is_none() {
+                    with_stack(|stack| udp.bind(stack, None, 0))??;
+                }
+                udp.remote = Some(remote);
+                Ok(())
+            }
+        }
+    }
+
+    /// Fails with [`SocketError::WouldBlock`] until the connection that
+    /// [`connect`](Self::connect) started is established.
+    pub fn finish_connect(&self) -> Result<(), SocketError> {
+        match &mut *self.inner.lock() {
+            Inner::Tcp(tcp) => tcp.finish_connect(),
+            Inner::Udp(_) => Ok(()),
+        }
+    }
+
+    /// Sends `buf` to `address`, or to the connected peer if there is no
+    /// address, and returns how much of it was sent.
+    pub fn send(&self, buf: &[u8], address: Option<SocketAddr>) -> Result<usize, SocketError> {
+        match &mut *self.inner.lock() {
+            Inner::Tcp(tcp) => {
+                // like on Linux, the address of a stream is ignored
+                let TcpState::Connected(handle) = tcp.state else {
+                    return Err(SocketError::NotConnected);
+                };
+                if tcp.shut_write {
+                    return Err(SocketError::BrokenPipe);
+                }
+                let reset = tcp.was_reset();
+                with_stack(|stack| {
+                    let socket = stack.sockets.get_mut::<tcp::Socket>(handle);
+                    match socket.state() {
+                        tcp::State::SynSent | tcp::State::SynReceived => {
+                            Err(SocketError::WouldBlock)
+                        }
+                        tcp::State::Closed if reset => Err(SocketError::ConnectionReset),
+                        _ if !socket.may_send() => Err(SocketError::BrokenPipe),
+                        _ if buf.is_empty() => Ok(0),
+                        _ if !socket.can_send() => Err(SocketError::WouldBlock),
+                        _ => socket.send_slice(buf).map_err(|_| SocketError::BrokenPipe),
+                    }
+                })?
+            }
+            Inner::Udp(udp) => {
+                if udp.shut_write {
+                    return Err(SocketError::BrokenPipe);
+                }
+                let remote = address
+                    .map(|address| self.endpoint(address))
+                    .transpose()?
+                    .or(udp.remote)
+                    .ok_or(SocketError::DestinationRequired)?;
+                if remote.addr == IpAddress::Ipv4(Ipv4Address::BROADCAST) && !udp.options.broadcast
+                {
+                    return Err(SocketError::PermissionDenied);
+                }
+                with_stack(|stack| {
+                    let handle = match udp.handle {
+                        Some(handle) => handle,
+                        None => udp.bind(stack, None, 0)?,
+                    };
+                    let headers_len = ip_header_len(remote.addr.version()) + UDP_HEADER_LEN;
+                    if buf.len() > stack.device.mtu.saturating_sub(headers_len) {
+                        return Err(SocketError::MessageTooLarge);
+                    }
+                    let socket = stack.sockets.get_mut::<udp::Socket>(handle);
+                    if buf.len() > socket.payload_send_capacity() {
+                        return Err(SocketError::NoBufferSpace);
+                    }
+                    socket.send_slice(buf, remote).map_err(|e| match e {
+                        udp::SendError::Unaddressable => SocketError::InvalidArgument,
+                        udp::SendError::BufferFull => SocketError::WouldBlock,
+                    })?;
+                    Ok(buf.len())
+                })?
+            }
+        }
+    }
+
+    /// Receives into `buf` and returns how much was received, together with
+    /// the sender of a datagram. Datagrams that don't fit into `buf` are
+    /// truncated, and zero bytes are received at the end of a stream.
+    pub fn recv(
+        &self,
+        buf: &mut [u8],
+        peek: bool,
+    ) -> Result<(usize, Option<SocketAddress>), SocketError> {
+        match &mut *self.inner.lock() {
+            Inner::Tcp(tcp) => {
+                let TcpState::Connected(handle) = tcp.state else {
+                    return Err(SocketError::NotConnected);
+                };
+                if tcp.shut_read {
+                    return Ok((0, None));
+                }
+                let reset = tcp.was_reset();
+                let len = with_stack(|stack| {
+                    let socket = stack.sockets.get_mut::<tcp::Socket>(handle);
+                    match socket.state() {
+                        tcp::State::SynSent | tcp::State::SynReceived => {
+                            Err(SocketError::WouldBlock)
+                        }
+                        _ if socket.can_recv() => {
+                            let result = if peek {
+                                socket.peek_slice(buf)
+                            } else {
+                                socket.recv_slice(buf)
+                            };
+                            result.map_err(|_| SocketError::NotConnected)
+                        }
+                        tcp::State::Closed if reset => Err(SocketError::ConnectionReset),
+                        // the peer closed the connection
+                        _ if !socket.may_recv() => Ok(0),
+                        _ => Err(SocketError::WouldBlock),
+                    }
+                })??;
+                Ok((len, None))
+            }
+            Inner::Udp(udp) => {
+                if udp.shut_read {
+                    return Ok((0, None));
+                }
+                let handle = udp.handle.ok_or(SocketError::WouldBlock)?;
+                let remote = udp.remote;
+                let version = self.version;
+                with_stack(|stack| {
+                    let socket = stack.sockets.get_mut::<udp::Socket>(handle);
+                    loop {
+                        let (data, sender) = if peek {
+                            socket
+                                .peek()
+                                .map(|(data, metadata)| (data, metadata.endpoint))
+                        } else {
+                            socket
+                                .recv()
+                                .map(|(data, metadata)| (data, metadata.endpoint))
+                        }
+                        .map_err(|_| SocketError::WouldBlock)?;
+                        if remote.is_some_and(|remote| remote != sender)
+                            || !accepts(version, sender)
+                        {
+                            if peek {
+                                let _ = socket.recv();
+                            }
+                            continue;
+                        }
+                        let len = data.len().min(buf.len());
+                        buf[..len].copy_from_slice(&data[..len]);
+                        return Ok((len, Some(socket_address(version, sender))));
+                    }
+                })?
+            }
+        }
+    }
+
+    /// Returns whether the socket can be read or written without blocking,
+    /// which for a listening socket means that a connection can be
+    /// accepted.
+    pub fn poll(&self) -> Readiness {
+        let readiness = match &mut *self.inner.lock() {
+            Inner::Tcp(tcp) => tcp.poll(),
+            Inner::Udp(udp) => udp.poll(self.version),
+        };
+        // without a stack, every operation fails right away
+        readiness.unwrap_or(Readiness {
+            error: true,
+            ..Readiness::READY
+        })
+    }
+
+    /// Wakes `waker` the next time that the result of [`poll`](Self::poll)
+    /// may change. The sockets of the stack only wake once, so this
+    /// registers with them again.
+    pub fn register_waker(&self, waker: &Waker) {
+        self.waiters.0.register(waker);
+        let inner = self.inner.lock();
+        let _ = with_stack(|stack| match &*inner {
+            Inner::Tcp(tcp) => match &tcp.state {
+                TcpState::Unconnected => {}
+                TcpState::Listening(handles) => {
+                    for &handle in handles {
+                        let socket = stack.sockets.get_mut::<tcp::Socket>(handle);
+                        socket.register_recv_waker(&self.waker);
+                    }
+                }
+                &TcpState::Connected(handle) => {
+                    let socket = stack.sockets.get_mut::<tcp::Socket>(handle);
+                    socket.register_recv_waker(&self.waker);
+                    socket.register_send_waker(&self.waker);
+                }
+            },
+            Inner::Udp(udp) => {
+                if let Some(handle) = udp.handle {
+                    let socket = stack.sockets.get_mut::<udp::Socket>(handle);
+                    socket.register_recv_waker(&self.waker);
+                    socket.register_send_waker(&self.waker);
+                }
+            }
+        });
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), SocketError> {
+        let (read, write) = match how {
+            Shutdown::Read => (true, false),
+            Shutdown::Write => (false, true),
+            Shutdown::Both => (true, true),
+        };
+        match &mut *self.inner.lock() {
+            Inner::Tcp(tcp) => {
+                let TcpState::Connected(handle) = tcp.state else {
+                    return Err(SocketError::NotConnected);
+                };
+                tcp.shut_read |= read;
+                if write && !tcp.shut_write {
+                    tcp.shut_write = true;
+                    // sends the FIN once the data that was sent is acknowledged
+                    with_stack(|stack| stack.sockets.get_mut::<tcp::Socket>(handle).close())?;
+                }
+                self.waiters.0.wake_all();
+                Ok(())
+            }
+            Inner::Udp(udp) => {
+                if udp.remote.is_none() {
+                    return Err(SocketError::NotConnected);
+                }
+                udp.shut_read |= read;
+                udp.shut_write |= write;
+                self.waiters.0.wake_all();
+                Ok(())
+            }
+        }
+    }
+
+    pub fn option(&self, option: SocketOption) -> Result<c_int, SocketError> {
+        let mut inner = self.inner.lock();
+        let options = match &mut *inner {
+            Inner::Tcp(tcp) => {
+                if option == SocketOption::Error {
+                    if tcp.connecting {
+                        let _ = tcp.finish_connect();
+                    }
+                    return Ok(tcp.error.take().map_or(0, |e| Errno::from(e).into()));
+                }
+                tcp.options
+            }
+            Inner::Udp(udp) => udp.options,
+        };
+        let buffer_size = match self.typ {
+            SocketType::Stream => TCP_BUFFER_SIZE,
+            SocketType::Datagram => UDP_BUFFER_SIZE,
+        };
+        Ok(match option {
+            SocketOption::ReuseAddress => c_int::from(options.reuse_address),
+            SocketOption::Type => match self.typ {
+                SocketType::Stream => SOCK_STREAM,
+                SocketType::Datagram => SOCK_DGRAM,
+            },
+            SocketOption::Error => 0,
+            SocketOption::Broadcast => c_int::from(options.broadcast),
+            SocketOption::SendBuffer | SocketOption::ReceiveBuffer => {
+                c_int::try_from(buffer_size).unwrap_or(c_int::MAX)
+            }
+            SocketOption::KeepAlive => c_int::from(options.keep_alive),
+            SocketOption::NoDelay => match self.typ {
+                SocketType::Stream => c_int::from(options.no_delay),
+                SocketType::Datagram => return Err(SocketError::InvalidOption),
+            },
+        })
+    }
+
+    /// Sets an option. The sizes of the buffers are fixed, so setting them
+    /// has no effect.
+    pub fn set_option(&self, option: SocketOption, value: c_int) -> Result<(), SocketError> {
+        let value = value != 0;
+        match &mut *self.inner.lock() {
+            Inner::Tcp(tcp) => {
+                match option {
+                    SocketOption::ReuseAddress => tcp.options.reuse_address = value,
+                    SocketOption::Broadcast => tcp.options.broadcast = value,
+                    SocketOption::KeepAlive => tcp.options.keep_alive = value,
+                    SocketOption::NoDelay => tcp.options.no_delay = value,
+                    SocketOption::SendBuffer | SocketOption::ReceiveBuffer => return Ok(()),
+                    SocketOption::Type | SocketOption::Error => {
+                        return Err(SocketError::InvalidOption);
+                    }
+                }
+                let handles = match &tcp.state {
+                    TcpState::Unconnected => return Ok(()),
+                    TcpState::Listening(handles) => handles.clone(),
+                    TcpState::Connected(handle) => vec![*handle],
+                };
+                let options = tcp.options;
+                with_stack(|stack| {
+                    for handle in handles {
+                        apply_options(stack.sockets.get_mut::<tcp::Socket>(handle), options);
+                    }
+                })
+            }
+            Inner::Udp(udp) => {
+                match option {
+                    SocketOption::ReuseAddress => udp.options.reuse_address = value,
+                    SocketOption::Broadcast => udp.options.broadcast = value,
+                    SocketOption::KeepAlive => udp.options.keep_alive = value,
+                    SocketOption::SendBuffer | SocketOption::ReceiveBuffer => {}
+                    SocketOption::Type | SocketOption::Error | SocketOption::NoDelay => {
+                        return Err(SocketError::InvalidOption);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn local_address(&self) -> Result<SocketAddress, SocketError> {
+        let (address, port) = match &*self.inner.lock() {
+            Inner::Tcp(tcp) => {
+                if let TcpState::Connected(handle) = tcp.state
+                    && let Some(local) = with_stack(|stack| {
+                        stack.sockets.get::<tcp::Socket>(handle).local_endpoint()
+                    })?
+                {
+                    return Ok(socket_address(self.version, local));
+                }
+                (tcp.address, tcp.port)
+            }
+            Inner::Udp(udp) => (udp.address, udp.port),
+        };
+        let address = address.unwrap_or(unspecified(self.version));
+        Ok(socket_address(
+            self.version,
+            IpEndpoint::new(address, port.unwrap_or_default()),
+        ))
+    }
+
+    pub fn peer_address(&self) -> Result<SocketAddress, SocketError> {
+        let remote = match &*self.inner.lock() {
+            Inner::Tcp(tcp) => match tcp.state {
+                TcpState::Connected(handle) if !tcp.connecting => {
+                    with_stack(|stack| stack.sockets.get::<tcp::Socket>(handle).remote_endpoint())?
+                }
+                _ => None,
+            },
+            Inner::Udp(udp) => udp.remote,
+        };
+        remote
+            .map(|remote| socket_address(self.version, remote))
+            .ok_or(SocketError::NotConnected)
+    }
+
+    /// Converts an address of the family of the socket into an endpoint,
+    /// with IPv4-mapped addresses converted into IPv4 ones.
+    fn endpoint(&self, address: SocketAddr) -> Result<IpEndpoint, SocketError> {
+        let addr = match (self.version, address.ip()) {
+            (IpVersion::Ipv4, IpAddr::V4(addr)) => IpAddress::Ipv4(addr),
+            (IpVersion::Ipv6, IpAddr::V6(addr)) => addr
+                .to_ipv4_mapped()
+                .map_or(IpAddress::Ipv6(addr), IpAddress::Ipv4),
+            _ => return Err(SocketError::AddressFamilyNotSupported),
+        };
+        Ok(IpEndpoint::new(addr, address.port()))
+    }
+}
+
+impl TcpSocket {
+    fn finish_connect(&mut self) -> Result<(), SocketError> {
+        let TcpState::Connected(handle) = self.state else {
+            return Err(SocketError::NotConnected);
+        };
+        if !self.connecting {
+            return Ok(());
+        }
+        let state = with_stack(|stack| {
+            let state = stack.sockets.get::<tcp::Socket>(handle).state();
+            if state == tcp::State::Closed {
+                stack.sockets.remove(handle);
+            }
+            state
+        })?;
+        match state {
+            tcp::State::SynSent | tcp::State::SynReceived => Err(SocketError::WouldBlock),
+            tcp::State::Closed => {
+                // the port stays reserved for the next attempt
+                self.state = TcpState::Unconnected;
+                self.connecting = false;
+                self.error = Some(SocketError::ConnectionRefused);
+                Err(SocketError::ConnectionRefused)
+            }
+            _ => {
+                self.connecting = false;
+                Ok(())
+            }
+        }
+    }
+
+    fn poll(&mut self) -> Result<Readiness, SocketError> {
+        if self.connecting {
+            let _ = self.finish_connect();
+        }
+        let error = self.error.is_some();
+        let handle = match &self.state {
+            // like on Linux, a socket that isn't connected is hung up, and
+            // a failed connection attempt leaves it that way
+            TcpState::Unconnected => {
+                return Ok(Readiness {
+                    writable: true,
+                    hangup: true,
+                    error,
+                    ..Readiness::default()
+                });
+            }
+            TcpState::Listening(handles) => {
+                let readable = with_stack(|stack| {
+                    handles.iter().any(|&handle| {
+                        !matches!(
+                            stack.sockets.get::<tcp::Socket>(handle).state(),
+                            tcp::State::Listen | tcp::State::SynReceived | tcp::State::Closed
+                        )
+                    })
+                })?;
+                return Ok(Readiness {
+                    readable,
+                    ..Readiness::default()
+                });
+            }
+            &TcpState::Connected(handle) => handle,
+        };
+        let reset = self.was_reset();
+        with_stack(|stack| {
+            let socket = stack.sockets.get::<tcp::Socket>(handle);
+            let state = socket.state();
+            if matches!(state, tcp::State::SynSent | tcp::State::SynReceived) {
+                return Readiness::default();
+            }
+            let closed = state == tcp::State::Closed;
+            Readiness {
+                readable: self.shut_read || socket.can_recv() || !socket.may_recv(),
+                writable: self.shut_write || socket.can_send() || !socket.may_send(),
+                hangup: closed || (!socket.may_recv() && self.shut_write),
+                error: closed && reset,
+            }
+        })
+    }
+
+    /// Whether the connection was reset if it is closed. Otherwise, it can
+    /// only be closed after it was shut down for writing.
+    fn was_reset(&self) -> bool {
+        !self.shut_write
+    }
+}
+
+impl UdpSocket {
+    fn poll(&mut self, version: IpVersion) -> Result<Readiness, SocketError> {
+        let Some(handle) = self.handle else {
+            return Ok(Readiness {
+                readable: self.shut_read,
+                writable: true,
+                ..Readiness::default()
+            });
+        };
+        let remote = self.remote;
+        with_stack(|stack| {
+            let socket = stack.sockets.get_mut::<udp::Socket>(handle);
+            // datagrams from others than the connected peer are dropped, like
+            // when they are received
+            while let Ok((_, metadata)) = socket.peek()
+                && (remote.is_some_and(|remote| remote != metadata.endpoint)
+                    || !accepts(version, metadata.endpoint))
+            {
+                let _ = socket.recv();
+            }
+            Readiness {
+                readable: self.shut_read || socket.can_recv(),
+                writable: self.shut_write || socket.can_send(),
+                ..Readiness::default()
+            }
+        })
+    }
+
+    fn bind(
+        &mut self,
+        stack: &mut NetworkStack,
+        address: Option<IpAddress>,
+        port: u16,
+    ) -> Result<SocketHandle, SocketError> {
+        if address.is_some_and(|address| !stack.is_local_address(address)) {
+            return Err(SocketError::AddressNotAvailable);
+        }
+        let port = stack.reserve_port(Protocol::Udp, port)?;
+
+        let mut socket = udp::Socket::new(
+            udp::PacketBuffer::new(
+                vec![udp::PacketMetadata::EMPTY; UDP_BUFFER_PACKETS],
+                vec![0; UDP_BUFFER_SIZE],
+            ),
+            udp::PacketBuffer::new(
+                vec![udp::PacketMetadata::EMPTY; UDP_BUFFER_PACKETS],
+                vec![0; UDP_BUFFER_SIZE],
+            ),
+        );
+        if socket.bind(listen_endpoint(address, port)).is_err() {
+            stack.release_port(Protocol::Udp, port);
+            return Err(SocketError::InvalidArgument);
+        }
+
+        let handle = stack.sockets.add(socket);
+        self.handle = Some(handle);
+        self.port = Some(port);
+        self.address = address;
+        Ok(handle)
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        let inner = self.inner.get_mut();
+        // the stack exists, since userspace only runs after it was created
+        let _ = with_stack(|stack| match inner {
+            Inner::Tcp(tcp) => {
+                match &tcp.state {
+                    TcpState::Unconnected => {}
+                    TcpState::Listening(handles) => {
+                        for &handle in handles {
+                            stack.sockets.remove(handle);
+                        }
+                    }
+                    &TcpState::Connected(handle) => {
+                        if tcp.connecting {
+                            stack.sockets.remove(handle);
+                        } else {
+                            stack.close_tcp(handle);
+                        }
+                    }
+                }
+                if let Some(port) = tcp.port {
+                    stack.release_port(Protocol::Tcp, port);
+                }
+            }
+            Inner::Udp(udp) => {
+                if let Some(handle) = udp.handle {
+                    stack.sockets.remove(handle);
+                }
+                if let Some(port) = udp.port {
+                    stack.release_port(Protocol::Udp, port);
+                }
+            }
+        });
+    }
+}
+
+fn tcp_socket(options: Options) -> tcp::Socket<'static> {
+    let mut socket = tcp::Socket::new(
+        tcp::SocketBuffer::new(vec![0; TCP_BUFFER_SIZE]),
+        tcp::SocketBuffer::new(vec![0; TCP_BUFFER_SIZE]),
+    );
+    apply_options(&mut socket, options);
+    socket
+}
+
+fn apply_options(socket: &mut tcp::Socket, options: Options) {
+    socket.set_nagle_enabled(!options.no_delay);
+    socket.set_keep_alive(options.keep_alive.then_some(KEEP_ALIVE_INTERVAL));
+}
+
+fn listener(
+    stack: &mut NetworkStack,
+    endpoint: IpListenEndpoint,
+    options: Options,
+) -> Result<SocketHandle, SocketError> {
+    let mut socket = tcp_socket(options);
+    socket
+        .listen(endpoint)
+        .map_err(|_| SocketError::InvalidArgument)?;
+    Ok(stack.sockets.add(socket))
+}
+
+fn listen_endpoint(address: Option<IpAddress>, port: u16) -> IpListenEndpoint {
+    IpListenEndpoint {
+        addr: address,
+        port,
+    }
+}
+
+/// The address of `endpoint` for a socket of the family `version`.
+fn socket_address(version: IpVersion, endpoint: IpEndpoint) -> SocketAddress {
+    match (version, endpoint.addr) {
+        (IpVersion::Ipv4, IpAddress::Ipv4(addr)) => {
+            SocketAddress::Inet(SocketAddrV4::new(addr, endpoint.port))
+        }
+        (IpVersion::Ipv6, IpAddress::Ipv4(addr)) => SocketAddress::Inet6(SocketAddrV6::new(
+            addr.to_ipv6_mapped(),
+            endpoint.port,
+            0,
+            0,
+        )),
+        (_, IpAddress::Ipv6(addr)) => {
+            SocketAddress::Inet6(SocketAddrV6::new(addr, endpoint.port, 0, 0))
+        }
+    }
+}
+
+/// Whether a socket of the family `version` takes packets from `endpoint`.
+/// Sockets that are bound to all addresses receive IPv4 as well as IPv6
+/// packets, but only IPv6 sockets can represent the addresses of both.
+fn accepts(version: IpVersion, endpoint: IpEndpoint) -> bool {
+    version == IpVersion::Ipv6 || endpoint.addr.version() == IpVersion::Ipv4
+}
+
+fn unspecified(version: IpVersion) -> IpAddress {
+    match version {
+        IpVersion::Ipv4 => IpAddress::Ipv4(Ipv4Address::UNSPECIFIED),
+        IpVersion::Ipv6 => IpAddress::Ipv6(Ipv6Address::UNSPECIFIED),
+    }
+}
+
+fn ip_header_len(version: IpVersion) -> usize {
+    match version {
+        IpVersion::Ipv4 => 20,
+        IpVersion::Ipv6 => 40,
+    }
+}
@@ -0,0 +1,681 @@
+//! Unix domain sockets, which connect processes on the same machine
+//! without the TCP/IP stack. They are bound to a path in the file system
+//! or to a name in the abstract namespace, and can pass open files to their
+//! peer along with the data.
+//!
+//! Like [`Socket`](crate::net::socket::Socket)s, their operations never
+//! wait, but fail with [`SocketError::WouldBlock`].
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::format;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+
+use kernel_abi::{SOCK_DGRAM, SOCK_STREAM};
+use kernel_syscall::access::{
+    Credentials, Shutdown, SocketAddress, SocketError, SocketOption, SocketType, UnixAddress,
+};
+use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{CreateError, Readiness};
+use spin::Mutex;
+
+use crate::file::{OpenFileDescription, vfs};
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::net::next_socket_id;
+
+/// How many bytes of a stream can be sent before they are received.
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+/// How many bytes of datagrams can be sent before they are received, which
+/// is also the largest datagram.
+const DATAGRAM_BUFFER_SIZE: usize = 16 * 1024;
+/// How many datagrams can be sent before they are received.
+const DATAGRAM_QUEUE_LEN: usize = 32;
+/// The most connections that wait for a listening socket to accept them.
+const MAX_BACKLOG: usize = 128;
+
+/// The open files that are passed along with the data of a message.
+pub type Rights = Vec<Arc<OpenFileDescription>>;
+
+/// The sockets that are bound to a name. Paths are absolute.
+static NAMES: Mutex<BTreeMap<UnixAddress, Weak<UnixSocket>>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug)]
+pub struct UnixSocket {
+    id: u64,
+    typ: SocketType,
+    nonblocking: AtomicBool,
+    /// The credentials of the process that created the socket, or of the
+    /// listening socket for an accepted connection.
+    credentials: Credentials,
+    inner: Mutex<Inner>,
+    /// The tasks that wait for the socket to change, like for data in its
+    /// queue, or for room in it to send.
+    waiters: WaitQueue,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    address: Option<UnixAddress>,
+    state: State,
+    /// What was sent to the socket and wasn't received yet.
+    queue: VecDeque<Message>,
+    /// The bytes of data in `queue`.
+    queued: usize,
+    options: Options,
+    shut_read: bool,
+    shut_write: bool,
+    /// Whether the peer of a stream closed it or shut it down for writing,
+    /// after which nothing is added to `queue` anymore.
+    peer_closed: bool,
+}
+
+/// The options that only exist for compatibility, since they don't have an
+/// effect on Unix sockets.
+#[derive(Debug, Default, Copy, Clone)]
+struct Options {
+    reuse_address: bool,
+    broadcast: bool,
+    keep_alive: bool,
+}
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Unconnected,
+    /// Connections that were established, but not accepted yet. They are
+    /// the sockets that are returned by `accept`.
+    Listening {
+        backlog: usize,
+        pending: VecDeque<Arc<UnixSocket>>,
+    },
+    Connected(Peer),
+}
+
+#[derive(Debug)]
+struct Peer {
+    socket: Weak<UnixSocket>,
+    address: Option<UnixAddress>,
+    credentials: Credentials,
+}
+
+/// What was sent to a socket. Streams keep the boundaries of messages
+/// that pass files, so that the files are received with the data that was
+/// sent with them.
+#[derive(Debug)]
+struct Message {
+    data: Vec<u8>,
+    rights: Rights,
+    /// The address of the sender of a datagram.
+    sender: Option<UnixAddress>,
+}
+
+impl UnixSocket {
+    pub fn new(typ: SocketType, nonblocking: bool, credentials: Credentials) -> Self {
+        Self {
+            id: next_socket_id(),
+            typ,
+            nonblocking: AtomicBool::new(nonblocking),
+            credentials,
+            inner: Mutex::new(Inner::default()),
+            waiters: WaitQueue::new(),
+        }
+    }
+
+    /// Creates two sockets that are connected to each other.
+    pub fn pair(
+        typ: SocketType,
+        nonblocking: bool,
+        credentials: Credentials,
+    ) -> (Arc<Self>, Arc<Self>) {
+        let a = Arc::new(Self::new(typ, nonblocking, credentials));
+        let b = Arc::new(Self::new(typ, nonblocking, credentials));
+        a.inner.lock().state = State::Connected(Peer::new(&b, None));
+        b.inner.lock().state = State::Connected(Peer::new(&a, None));
+        (a, b)
+    }
+
+    /// A number that identifies the socket, like the inode number of a
+    /// file.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn is_nonblocking(&self) -> bool {
+        self.nonblocking.load(Ordering::Relaxed)
+    }
+
+    /// Binds the socket to `address`, whose path has to be absolute. A
+    /// file is created at the path, which has to be removed before the
+    /// path can be bound again. An unnamed address binds the socket to a
+    /// unique name in the abstract namespace.
+    pub fn bind(self: &Arc<Self>, address: UnixAddress) -> Result<(), SocketError> {
+        let mut inner = self.inner.lock();
+        if inner.address.is_some() {
+            return Err(SocketError::InvalidArgument);
+        }
+        let address = match address {
+            UnixAddress::Unnamed => UnixAddress::Abstract(format!("{:05x}", self.id).into_bytes()),
+            address => address,
+        };
+
+        let mut names = NAMES.lock();
+        match &address {
+            UnixAddress::Unnamed => unreachable!("unnamed addresses are replaced above"),
+            UnixAddress::Path(path) => {
+                let path = AbsolutePath::try_new(path.as_str())
+                    .map_err(|_| SocketError::InvalidArgument)?;
+                vfs().read().create_file(path, 0o755).map_err(|e| match e {
+                    CreateError::AlreadyExists | CreateError::IsADirectory => {
+                        SocketError::AddressInUse
+                    }
+                    CreateError::NotFound | CreateError::NotADirectory => SocketError::NotFound,
+                    CreateError::ReadOnlyFileSystem => SocketError::ReadOnlyFileSystem,
+                    CreateError::NoSpace => SocketError::NoBufferSpace,
+                    CreateError::CrossDevice | CreateError::Unsupported => {
+                        SocketError::NotSupported
+                    }
+                })?;
+            }
+            UnixAddress::Abstract(_) => {
+                if names
+                    .get(&address)
+                    .is_some_and(|socket| socket.strong_count() > 0)
+                {
+                    return Err(SocketError::AddressInUse);
+                }
+            }
+        }
+        // a socket that is still bound to a path whose file was removed
+        // can't be found anymore
+        names.insert(address.clone(), Arc::downgrade(self));
+        inner.address = Some(address);
+        Ok(())
+    }
+
+    pub fn listen(&self, backlog: usize) -> Result<(), SocketError> {
+        if self.typ != SocketType::Stream {
+            return Err(SocketError::NotSupported);
+        }
+        let mut inner = self.inner.lock();
+        if inner.address.is_none() {
+            return Err(SocketError::InvalidArgument);
+        }
+        let backlog = backlog.clamp(1, MAX_BACKLOG);
+        match &mut inner.state {
+            State::Unconnected => {}
+            // listening again only changes the backlog
+            State::Listening { backlog: b, .. } => {
+                *b = backlog;
+                return Ok(());
+            }
+            State::Connected(_) => return Err(SocketError::InvalidArgument),
+        }
+        inner.state = State::Listening {
+            backlog,
+            pending: VecDeque::new(),
+        };
+        Ok(())
+    }
+
+    /// Takes an established connection from a listening socket, and
+    /// returns it together with the address of the peer.
+    pub fn accept(&self) -> Result<(Arc<UnixSocket>, SocketAddress), SocketError> {
+        let mut inner = self.inner.lock();
+        let State::Listening { pending, .. } = &mut inner.state else {
+            return Err(SocketError::InvalidArgument);
+        };
+        let socket = pending.pop_front().ok_or(SocketError::WouldBlock)?;
+        drop(inner);
+        // a connection that didn't fit into the backlog may be established
+        self.waiters.wake_all();
+        let peer = socket.peer_address()?;
+        Ok((socket, peer))
+    }
+
+    /// Connects the socket to the socket that is bound to `address`. A
+    /// stream is established right away, unless the backlog of the
+    /// listening socket is full. Datagram sockets only remember the peer as
+    /// the default destination.
+    pub fn connect(self: &Arc<Self>, address: &UnixAddress) -> Result<(), SocketError> {
+        let target = lookup(address)?;
+        if target.typ != self.typ {
+            return Err(SocketError::WrongProtocolType);
+        }
+
+        let mut inner = self.inner.lock();
+        if self.typ == SocketType::Datagram {
+            inner.state = State::Connected(Peer::new(&target, Some(address.clone())));
+            return Ok(());
+        }
+        match inner.state {
+            State::Unconnected => {}
+            State::Listening { .. } => return Err(SocketError::InvalidArgument),
+            State::Connected(_) => return Err(SocketError::AlreadyConnected),
+        }
+
+        // a listening socket never locks another socket, so this can't
+        // deadlock
+        let mut target_inner = target.inner.lock();
+        let State::Listening { backlog, pending } = &mut target_inner.state else {
+            return Err(SocketError::ConnectionRefused);
+        };
+        if pending.len() >= *backlog {
+            return Err(SocketError::WouldBlock);
+        }
+        let server = Arc::new(Self::new(self.typ, false, target.credentials));
+        {
+            let mut server_inner = server.inner.lock();
+            server_inner.address = Some(address.clone());
+            server_inner.state = State::Connected(Peer {
+                socket: Arc::downgrade(self),
+                address: inner.address.clone(),
+                credentials: self.credentials,
+            });
+        }
+        inner.state = State::Connected(Peer::new(&server, Some(address.clone())));
+        pending.push_back(server);
+        target.waiters.wake_all();
+        Ok(())
+    }
+
+    /// Sends `buf` and the files of `rights` to `address`, or to the
+    /// connected peer if there is no address, and returns how much of `buf`
+    /// was sent.
+    pub fn send(
+        &self,
+        buf: &[u8],
+        address: Option<&UnixAddress>,
+        rights: &[Arc<OpenFileDescription>],
+    ) -> Result<usize, SocketError> {
+        // the receiver is locked after the sender is unlocked, since the
+        // receiver could be sending to the sender at the same time
+        let (peer, sender) = {
+            let inner = self.inner.lock();
+            if inner.shut_write {
+                return Err(SocketError::BrokenPipe);
+            }
+            let peer = match &inner.state {
+                State::Connected(peer) => Some(peer.socket.upgrade()),
+                _ => None,
+            };
+            (peer, inner.address.clone())
+        };
+
+        match self.typ {
+            SocketType::Stream => {
+                // like on Linux, the address of a stream is ignored
+                let peer = peer
+                    .ok_or(SocketError::NotConnected)?
+                    .ok_or(SocketError::BrokenPipe)?;
+                let mut inner = peer.inner.lock();
+                if inner.shut_read {
+                    return Err(SocketError::BrokenPipe);
+                }
+                if buf.is_empty() {
+                    return Ok(0);
+                }
+                let len = buf.len().min(STREAM_BUFFER_SIZE - inner.queued);
+                if len == 0 {
+                    return Err(SocketError::WouldBlock);
+                }
+                inner.push(Message {
+                    data: buf[..len].to_vec(),
+                    rights: rights.to_vec(),
+                    sender: None,
+                });
+                drop(inner);
+                peer.waiters.wake_all();
+                Ok(len)
+            }
+            SocketType::Datagram => {
+                let receiver = match address {
+                    Some(address) => lookup(address)?,
+                    None => peer
+                        .ok_or(SocketError::DestinationRequired)?
+                        .ok_or(SocketError::ConnectionRefused)?,
+                };
+                if receiver.typ != SocketType::Datagram {
+                    return Err(SocketError::WrongProtocolType);
+                }
+                if buf.len() > DATAGRAM_BUFFER_SIZE {
+                    return Err(SocketError::MessageTooLarge);
+                }
+                let mut inner = receiver.inner.lock();
+                if inner.shut_read {
+                    return Err(SocketError::ConnectionRefused);
+                }
+                if inner.queue.len() >= DATAGRAM_QUEUE_LEN
+                    || inner.queued + buf.len() > DATAGRAM_BUFFER_SIZE
+                {
+                    return Err(SocketError::WouldBlock);
+                }
+                inner.push(Message {
+                    data: buf.to_vec(),
+                    rights: rights.to_vec(),
+                    sender,
+                });
+                drop(inner);
+                receiver.waiters.wake_all();
+                Ok(buf.len())
+            }
+        }
+    }
+
+    /// Receives into `buf` and returns how much was received, together with
+    /// the sender of a datagram and the files that were sent with the data.
+    /// Datagrams that don't fit into `buf` are truncated, and zero bytes
+    /// are received at the end of a stream.
+    pub fn recv(
+        &self,
+        buf: &mut [u8],
+        peek: bool,
+    ) -> Result<(usize, Option<SocketAddress>, Rights), SocketError> {
+        let mut inner = self.inner.lock();
+        if inner.shut_read {
+            return Ok((0, None, Vec::new()));
+        }
+        match self.typ {
+            SocketType::Stream => {
+                if !matches!(inner.state, State::Connected(_)) {
+                    return Err(SocketError::NotConnected);
+                }
+                if inner.queue.is_empty() {
+                    return if inner.peer_closed {
+                        Ok((0, None, Vec::new()))
+                    } else {
+                        Err(SocketError::WouldBlock)
+                    };
+                }
+                let (len, rights) = inner.read_stream(buf, peek);
+                drop(inner);
+                if !peek {
+                    // the peer may send again
+                    self.waiters.wake_all();
+                }
+                Ok((len, None, rights))
+            }
+            SocketType::Datagram => {
+                let message = if peek {
+                    inner.queue.front().map(|message| Message {
+                        data: message.data.clone(),
+                        rights: message.rights.clone(),
+                        sender: message.sender.clone(),
+                    })
+                } else {
+                    inner.pop()
+                };
+                drop(inner);
+                let message = message.ok_or(SocketError::WouldBlock)?;
+                if !peek {
+                    self.waiters.wake_all();
+                }
+                let len = message.data.len().min(buf.len());
+                buf[..len].copy_from_slice(&message.data[..len]);
+                let sender = message.sender.unwrap_or(UnixAddress::Unnamed);
+                Ok((len, Some(SocketAddress::Unix(sender)), message.rights))
+            }
+        }
+    }
+
+    /// Returns whether the socket can be read or written without blocking,
+    /// which for a listening socket means that a connection can be
+    /// accepted. Like in [`send`](Self::send), the peer is locked after the
+    /// socket is unlocked.
+    pub fn poll(&self) -> Readiness {
+        let (readable, shut_write, peer_closed, peer) = {
+            let inner = self.inner.lock();
+            let peer = match &inner.state {
+                State::Unconnected => None,
+                State::Listening { pending, .. } => {
+                    return Readiness {
+                        readable: !pending.is_empty(),
+                        ..Readiness::default()
+                    };
+                }
+                State::Connected(peer) => Some(peer.socket.upgrade()),
+            };
+            let readable = inner.shut_read
+                || !inner.queue.is_empty()
+                || (self.typ == SocketType::Stream && inner.peer_closed);
+            (readable, inner.shut_write, inner.peer_closed, peer)
+        };
+
+        let (writable, hangup) = match (self.typ, peer) {
+            // like on Linux, a stream that isn't connected is hung up
+            (SocketType::Stream, None) => (true, true),
+            (_, None | Some(None)) => (true, self.typ == SocketType::Stream),
+            (_, Some(Some(_))) if shut_write => (true, peer_closed),
+            (SocketType::Stream, Some(Some(peer))) => {
+                let peer = peer.inner.lock();
+                (peer.shut_read || peer.queued < STREAM_BUFFER_SIZE, false)
+            }
+            (SocketType::Datagram, Some(Some(peer))) => {
+                let peer = peer.inner.lock();
+                let room =
+                    peer.queue.len() < DATAGRAM_QUEUE_LEN && peer.queued < DATAGRAM_BUFFER_SIZE;
+                (peer.shut_read || room, false)
+            }
+        };
+        Readiness {
+            readable,
+            writable,
+            hangup,
+            error: false,
+        }
+    }
+
+    /// Wakes `waker` the next time that the result of [`poll`](Self::poll)
+    /// may change. Sending waits for room in the queue of the peer, so the
+    /// waker is also registered with the peer.
+    pub fn register_waker(&self, waker: &Waker) {
+        self.waiters.register(waker);
+        let peer = match &self.inner.lock().state {
+            State::Connected(peer) => peer.socket.upgrade(),
+            _ => None,
+        };
+        if let Some(peer) = peer {
+            peer.waiters.register(waker);
+        }
+    }
+
+    /// Like [`register_waker`](Self::register_waker), but also registers
+    /// `waker` with the socket that [`send`](Self::send) or
+    /// [`connect`](Self::connect) to `address` wait for.
+    pub fn register_send_waker(&self, address: Option<&UnixAddress>, waker: &Waker) {
+        self.register_waker(waker);
+        if let Some(target) = address.and_then(|address| lookup(address).ok()) {
+            target.waiters.register(waker);
+        }
+    }
+
+    pub fn shutdown(&self, how: Shutdown) -> Result<(), SocketError> {
+        let (read, write) = match how {
+            Shutdown::Read => (true, false),
+            Shutdown::Write => (false, true),
+            Shutdown::Both => (true, true),
+        };
+        let peer = {
+            let mut inner = self.inner.lock();
+            let State::Connected(peer) = &inner.state else {
+                return Err(SocketError::NotConnected);
+            };
+            let peer = peer.socket.upgrade();
+            inner.shut_read |= read;
+            inner.shut_write |= write;
+            peer
+        };
+        if let Some(peer) = peer {
+            if write && self.typ == SocketType::Stream {
+                peer.inner.lock().peer_closed = true;
+            }
+            peer.waiters.wake_all();
+        }
+        self.waiters.wake_all();
+        Ok(())
+    }
+
+    pub fn option(&self, option: SocketOption) -> Result<c_int, SocketError> {
+        let options = self.inner.lock().options;
+        let buffer_size = match self.typ {
+            SocketType::Stream => STREAM_BUFFER_SIZE,
+            SocketType::Datagram => DATAGRAM_BUFFER_SIZE,
+        };
+        Ok(match option {
+            SocketOption::ReuseAddress => c_int::from(options.reuse_address),
+            SocketOption::Type => match self.typ {
+                SocketType::Stream => SOCK_STREAM,
+                SocketType::Datagram => SOCK_DGRAM,
+            },
+            // connections are established or refused right away
+            SocketOption::Error => 0,
+            SocketOption::Broadcast => c_int::from(options.broadcast),
+            SocketOption::SendBuffer | SocketOption::ReceiveBuffer => {
+                c_int::try_from(buffer_size).unwrap_or(c_int::MAX)
+            }
+            SocketOption::KeepAlive => c_int::from(options.keep_alive),
+            SocketOption::NoDelay => return Err(SocketError::InvalidOption),
+        })
+    }
+
+    /// Sets an option. The sizes of the buffers are fixed, so setting them
+    /// has no effect.
+    pub fn set_option(&self, option: SocketOption, value: c_int) -> Result<(), SocketError> {
+        let value = value != 0;
+        let options = &mut self.inner.lock().options;
+        match option {
+            SocketOption::ReuseAddress => options.reuse_address = value,
+            SocketOption::Broadcast => options.broadcast = value,
+            SocketOption::KeepAlive => options.keep_alive = value,
+            SocketOption::SendBuffer | SocketOption::ReceiveBuffer => {}
+            SocketOption::Type | SocketOption::Error | SocketOption::NoDelay => {
+                return Err(SocketError::InvalidOption);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn local_address(&self) -> SocketAddress {
+        let address = self.inner.lock().address.clone();
+        SocketAddress::Unix(address.unwrap_or(UnixAddress::Unnamed))
+    }
+
+    pub fn peer_address(&self) -> Result<SocketAddress, SocketError> {
+        match &self.inner.lock().state {
+            State::Connected(peer) => Ok(SocketAddress::Unix(
+                peer.address.clone().unwrap_or(UnixAddress::Unnamed),
+            )),
+            _ => Err(SocketError::NotConnected),
+        }
+    }
+
+    pub fn peer_credentials(&self) -> Result<Credentials, SocketError> {
+        match &self.inner.lock().state {
+            State::Connected(peer) => Ok(peer.credentials),
+            _ => Err(SocketError::NotConnected),
+        }
+    }
+}
+
+impl Drop for UnixSocket {
+    fn drop(&mut self) {
+        let this: *const Self = self;
+        let inner = self.inner.get_mut();
+        if let Some(address) = &inner.address {
+            let mut names = NAMES.lock();
+            if names
+                .get(address)
+                .is_some_and(|socket| core::ptr::eq(socket.as_ptr(), this))
+            {
+                names.remove(address);
+            }
+        }
+        if self.typ == SocketType::Stream
+            && let State::Connected(peer) = &inner.state
+            && let Some(peer) = peer.socket.upgrade()
+        {
+            peer.inner.lock().peer_closed = true;
+            peer.waiters.wake_all();
+        }
+    }
+}
+
+impl Peer {
+    fn new(socket: &Arc<UnixSocket>, address: Option<UnixAddress>) -> Self {
+        Self {
+            socket: Arc::downgrade(socket),
+            address,
+            credentials: socket.credentials,
+        }
+    }
+}
+
+impl Inner {
+    fn push(&mut self, message: Message) {
+        self.queued += message.data.len();
+        self.queue.push_back(message);
+    }
+
+    fn pop(&mut self) -> Option<Message> {
+        let message = self.queue.pop_front()?;
+        self.queued -= message.data.len();
+        Some(message)
+    }
+
+    /// Reads the data of consecutive messages into `buf`, up to the next
+    /// message that passes files, and returns the files of the first
+    /// message.
+    fn read_stream(&mut self, buf: &mut [u8], peek: bool) -> (usize, Rights) {
+        let mut len = 0;
+        let mut rights = Vec::new();
+        let mut index = 0;
+        while len < buf.len()
+            && let Some(message) = self.queue.get_mut(index)
+        {
+            if !message.rights.is_empty() {
+                if len > 0 {
+                    break;
+                }
+                rights = if peek {
+                    message.rights.clone()
+                } else {
+                    core::mem::take(&mut message.rights)
+                };
+            }
+            let n = message.data.len().min(buf.len() - len);
+            buf[len..len + n].copy_from_slice(&message.data[..n]);
+            len += n;
+            if peek {
+                index += 1;
+            } else {
+                message.data.drain(..n);
+                self.queued -= n;
+                if message.data.is_empty() {
+                    self.queue.pop_front();
+                }
+            }
+        }
+        (len, rights)
+    }
+}
+
+/// Finds the socket that is bound to `address`, whose path has to be
+/// absolute.
+fn lookup(address: &UnixAddress) -> Result<Arc<UnixSocket>, SocketError> {
+    match address {
+        UnixAddress::Unnamed => return Err(SocketError::InvalidArgument),
+        UnixAddress::Path(path) => {
+            let path =
+                AbsolutePath::try_new(path.as_str()).map_err(|_| SocketError::InvalidArgument)?;
+            vfs().read().open(path).map_err(|_| SocketError::NotFound)?;
+        }
+        UnixAddress::Abstract(_) => {}
+    }
+    NAMES
+        .lock()
+        .get(address)
+        .and_then(Weak::upgrade)
+        .ok_or(SocketError::ConnectionRefused)
+}
@@ -0,0 +1,265 @@
+//! The random number generator of the kernel, which backs `/dev/random`,
+//! `/dev/urandom` and `getrandom`. It is seeded from RDSEED and RDRAND if
+//! the CPU has them, from the jitter of the time stamp counter and from the
+//! HPET, and keeps collecting entropy from the timing of interrupts and from
+//! drivers like virtio-rng.
+
+use core::arch::asm;
+use core::sync::atomic::Ordering::Relaxed;
+use core::sync::atomic::{AtomicU32, AtomicU64};
+use core::task::Waker;
+
+use kernel_devfs::DevFile;
+use kernel_random::Rng;
+use kernel_vfs::{FileType, ReadError, Readiness, Stat, StatError, WriteError};
+use log::{info, warn};
+use raw_cpuid::CpuId;
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::hpet::hpet;
+use crate::mcore::mtask::wait::WaitQueue;
+
+static RNG: Mutex<Rng> = Mutex::new(Rng::new());
+
+/// The tasks that wait until the generator is seeded.
+static SEEDED: WaitQueue = WaitQueue::new();
+
+/// Interrupt timings are collected here without taking the lock of the
+/// generator, and only mixed in after every [`INTERRUPTS_PER_BIT`]
+/// interrupts.
+static INTERRUPT_POOL: AtomicU64 = AtomicU64::new(0);
+static INTERRUPT_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Interrupts are regular, so many of them are needed for one bit of
+/// entropy.
+const INTERRUPTS_PER_BIT: u32 = 64;
+
+/// How often RDSEED and RDRAND are retried before giving up, as recommended
+/// by Intel.
+const RETRIES: usize = 10;
+
+/// The largest amount of random bytes that is generated at once, with the
+/// lock held and interrupts disabled.
+const MAX_CHUNK: usize = 4096;
+
+pub fn init() {
+    add_cpu_entropy();
+    add_timing_entropy();
+    if is_seeded() {
+        info!("random number generator seeded");
+    } else {
+        warn!("random number generator is not seeded yet");
+    }
+}
+
+/// Mixes `data` into the generator and credits it with `bits` of entropy.
+pub fn add_entropy(data: &[u8], bits: usize) {
+    let seeded = interrupts::without_interrupts(|| seed(&mut RNG.lock(), data, bits));
+    if seeded {
+        SEEDED.wake_all();
+    }
+}
+
+/// Mixes `data` into `rng` and returns whether that seeded it.
+fn seed(rng: &mut Rng, data: &[u8], bits: usize) -> bool {
+    let was_seeded = rng.is_seeded();
+    rng.add_entropy(data, bits);
+    !was_seeded && rng.is_seeded()
+}
+
+/// Records the time of an interrupt. This is called from interrupt
+/// handlers, so it neither allocates nor waits for the lock.
+pub fn add_interrupt_timing(vector: u8) {
+    let count = INTERRUPT_COUNT.fetch_add(1, Relaxed) + 1;
+    let sample = rdtsc() ^ (u64::from(vector) << 56);
+    INTERRUPT_POOL.fetch_xor(sample.rotate_left(count * 7 % 64), Relaxed);
+
+    if count % INTERRUPTS_PER_BIT == 0 {
+        // another CPU may be using the generator, in which case the
+        // collected timings are kept for the next attempt
+        let seeded = RNG.try_lock().is_some_and(|mut rng| {
+            let pool = INTERRUPT_POOL.swap(0, Relaxed);
+            seed(&mut rng, &pool.to_le_bytes(), 1)
+        });
+        // waking the readers doesn't allocate
+        if seeded {
+            SEEDED.wake_all();
+        }
+    }
+}
+
+/// Whether the generator collected enough entropy for its output to be
+/// unpredictable.
+#[must_use]
+pub fn is_seeded() -> bool {
+    interrupts::without_interrupts(|| RNG.lock().is_seeded())
+}
+
+/// Wakes `waker` once the generator is seeded.
+pub fn register_waker(waker: &Waker) {
+    SEEDED.register(waker);
+}
+
+/// Fills `buf` with random bytes, whether or not the generator is seeded.
+pub fn fill_bytes(buf: &mut [u8]) {
+    for chunk in buf.chunks_mut(MAX_CHUNK) {
+        interrupts::without_interrupts(|| RNG.lock().fill_bytes(chunk));
+    }
+}
+
+fn add_cpu_entropy() {
+    let cpuid = CpuId::new();
+    let has_rdseed = cpuid
+        .get_extended_feature_info()
+        .is_some_and(|info| info.has_rdseed());
+    let has_rdrand = cpuid
+        .get_feature_info()
+        .is_some_and(|info| info.has_rdrand());
+
+    let mut seed = [0_u64; 8];
+    let mut bits = 0;
+    for word in &mut seed {
+        let value = has_rdseed
+            .then(rdseed)
+            .flatten()
+            .or_else(|| has_rdrand.then(rdrand).flatten());
+        if let Some(value) = value {
+            *word = value;
+            bits += 64;
+        }
+    }
+    if bits == 0 {
+        warn!("the cpu has no random number generator");
+        return;
+    }
+
+    let mut bytes = [0; 64];
+    for (dst, word) in bytes.as_chunks_mut::<8>().0.iter_mut().zip(seed) {
+        *dst = word.to_le_bytes();
+    }
+    add_entropy(&bytes, bits);
+}
+
+/// Samples how long reads of the HPET take, measured with the time stamp
+/// counter. The duration of the memory mapped reads varies a little, and
+/// one bit of entropy is credited for every eight samples.
+fn add_timing_entropy() {
+    const SAMPLES: usize = 256;
+
+    let hpet = hpet().read();
+    let mut bytes = [0; SAMPLES];
+    let mut last = rdtsc();
+    for byte in &mut bytes {
+        let counter = hpet.main_counter_value();
+        let now = rdtsc();
+        // the low bits change the most
+        *byte = (now.wrapping_sub(last) ^ counter) as u8;
+        last = now;
+    }
+    add_entropy(&bytes, SAMPLES / 8);
+}
+
+fn rdtsc() -> u64 {
+    let (low, high): (u32, u32);
+    unsafe {
+        asm!(
+            "rdtsc",
+            out("eax") low,
+            out("edx") high,
+            options(nomem, nostack, preserves_flags),
+        );
+    }
+    (u64::from(high) << 32) | u64::from(low)
+}
+
+/// Reads a seed from the hardware entropy source. Must only be called if
+/// the CPU supports RDSEED.
+fn rdseed() -> Option<u64> {
+    (0..RETRIES).find_map(|_| {
+        let value: u64;
+        let ok: u8;
+        unsafe {
+            asm!(
+                "rdseed {}",
+                "setc {}",
+                out(reg) value,
+                out(reg_byte) ok,
+                options(nomem, nostack),
+            );
+        }
+        (ok == 1).then_some(value)
+    })
+}
+
+/// Reads a random number from the hardware generator. Must only be called
+/// if the CPU supports RDRAND.
+fn rdrand() -> Option<u64> {
+    (0..RETRIES).find_map(|_| {
+        let value: u64;
+        let ok: u8;
+        unsafe {
+            asm!(
+                "rdrand {}",
+                "setc {}",
+                out(reg) value,
+                out(reg_byte) ok,
+                options(nomem, nostack),
+            );
+        }
+        (ok == 1).then_some(value)
+    })
+}
+
+/// `/dev/random` and `/dev/urandom`. Reading `/dev/random` waits until the
+/// generator is seeded, after that both are the same. Writes are mixed into
+/// the generator, but don't count as entropy.
+pub struct RandomFile {
+    blocking: bool,
+}
+
+impl RandomFile {
+    #[must_use]
+    pub fn random() -> Self {
+        Self { blocking: true }
+    }
+
+    #[must_use]
+    pub fn urandom() -> Self {
+        Self { blocking: false }
+    }
+}
+
+impl DevFile for RandomFile {
+    fn read(&mut self, buf: &mut [u8], _: usize) -> Result<usize, ReadError> {
+        if self.blocking && !is_seeded() {
+            return Err(ReadError::WouldBlock);
+        }
+        fill_bytes(buf);
+        Ok(buf.len())
+    }
+
+    fn write(&mut self, buf: &[u8], _: usize) -> Result<usize, WriteError> {
+        add_entropy(buf, 0);
+        Ok(buf.len())
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.file_type = FileType::CharDevice;
+        stat.size = 0;
+        Ok(())
+    }
+
+    fn poll(&mut self) -> Readiness {
+        Readiness {
+            readable: !self.blocking || is_seeded(),
+            ..Readiness::READY
+        }
+    }
+
+    fn register_waker(&mut self, waker: &Waker) {
+        if self.blocking {
+            register_waker(waker);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! A minimal interactive shell on the serial console, used when the
+//! system can't be booted as configured, e.g. because the root file system
+//! can't be mounted.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Display;
+
+use kernel_cmdline::{BootConfig, RootSource};
+use kernel_device::block::BlockDevice;
+use kernel_vfs::path::AbsoluteOwnedPath;
+use x86_64::instructions::{hlt, interrupts};
+
+use crate::driver::block::BlockDevices;
+use crate::file::root::{is_root_mounted, mount_root};
+use crate::{serial_print, serial_println, tty};
+
+const HELP: &str = "\
+commands:
+  help                      show this help
+  devices                   list block devices and partitions
+  config                    show the boot configuration
+  mount <root> [fstype]     mount the root file system, <root> takes the
+                            same values as the root= option
+  init <path>               set the path of the init executable
+  boot                      continue booting
+  halt                      halt the system";
+
+/// Runs the rescue shell until the user continues booting. The shell only
+/// returns if a root file system is mounted. Changes made through the
+/// shell are reflected in `config`.
+pub fn run(config: &mut BootConfig, reason: &dyn Display) {
+    serial_println!();
+    serial_println!("boot failed: {reason}");
+    serial_println!("entering rescue shell, type 'help' for a list of commands");
+
+    loop {
+        serial_print!("rescue> ");
+        let line = read_line();
+        let mut args = line.split_whitespace();
+        let Some(command) = args.next() else {
+            continue;
+        };
+
+        match command {
+            "help" => serial_println!("{HELP}"),
+            "devices" => list_devices(),
+            "config" => serial_println!("{config:#?}"),
+            "mount" => {
+                let Some(root) = args.next() else {
+                    serial_println!("usage: mount <root> [fstype]");
+                    continue;
+                };
+                match root.parse::<RootSource>() {
+                    Ok(root) => {
+                        config.root = Some(root);
+                        config.root_fs_type = args.next().map(ToString::to_string);
+                        match mount_root(config) {
+                            Ok(()) => serial_println!("mounted root file system"),
+                            Err(e) => serial_println!("{e}"),
+                        }
+                    }
+                    Err(e) => serial_println!("{e}"),
+                }
+            }
+            "init" => match args.next().map(AbsoluteOwnedPath::try_from) {
+                Some(Ok(path)) => config.init = Some(path),
+                _ => serial_println!("usage: init <absolute path>"),
+            },
+            "boot" => {
+                if is_root_mounted() {
+                    return;
+                }
+                serial_println!("no root file system mounted");
+            }
+            "halt" => {
+                serial_println!("system halted");
+                interrupts::disable();
+                loop {
+                    hlt();
+                }
+            }
+            _ => serial_println!("unknown command: {command}"),
+        }
+    }
+}
+
+fn list_devices() {
+    for id in BlockDevices::ids() {
+        if let Some(device) = BlockDevices::by_id(id) {
+            serial_println!("/dev/blk{id}: {} blocks", device.read().block_count());
+        }
+    }
+    for ((disk, number), entry) in BlockDevices::partitions() {
+        let partition = &entry.partition;
+        serial_print!(
+            "/dev/blk{disk}p{number}: {} blocks at {}",
+            partition.block_count,
+            partition.start_block
+        );
+        if let Some(guid) = &partition.guid {
+            serial_print!(", PARTUUID={guid}");
+        }
+        if let Some(label) = &partition.label {
+            serial_print!(", PARTLABEL={label:?}");
+        }
+        serial_println!();
+    }
+}
+
+/// Reads a line from the serial console, echoing the input and handling
+/// backspace.
+fn read_line() -> String {
+    let tty = tty::serial::tty();
+    let mut buf = [0; 256];
+    let mut line = Vec::new();
+    loop {
+        tty::serial::poll_input();
+        match tty.read(&mut buf) {
+            // the line discipline echoes and handles erasing
+            Ok(len) => {
+                line.extend_from_slice(&buf[..len]);
+                if len == 0 || line.ends_with(b"\n") {
+                    return String::from_utf8_lossy(&line).trim().to_string();
+                }
+            }
+            Err(_) => hlt(),
+        }
+    }
+}
@@ -25,6 +25,28 @@ pub fn internal_print(args: core::fmt::Arguments) {
     });
 }
 
+/// Writes bytes to the serial interface as they are, without interpreting
+/// backspace or delete.
+pub fn write_bytes(bytes: &[u8]) {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        let mut port = SERIAL1.lock();
+        for &byte in bytes {
+            port.send_raw(byte);
+        }
+    });
+}
+
+/// Returns the next byte that was received by the serial interface, if
+/// there is one. Input is normally read through the serial terminal, see
+/// [`crate::tty::serial`].
+pub fn try_read_byte() -> Option<u8> {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| SERIAL1.lock().try_receive().ok())
+}
+
 /// Prints to the host through the serial interface.
 #[macro_export]
 macro_rules! serial_print {
@@ -1,19 +1,31 @@
 use alloc::sync::Arc;
+use core::ffi::c_int;
 use core::sync::atomic::Ordering::Relaxed;
 
+use kernel_abi::{EBADF, EFAULT, EINVAL, EIO, ENOTTY, EPERM, Errno};
 use kernel_syscall::access::{CwdAccess, FileAccess};
 use kernel_vfs::node::VfsNode;
 use kernel_vfs::path::AbsolutePath;
+use kernel_vfs::{IoctlError, ReadError};
 use spin::rwlock::RwLock;
 
 use crate::U64Ext;
-use crate::file::{OpenFileDescription, vfs};
+use crate::file::{OpenFile, OpenFileDescription, vfs};
 use crate::mcore::context::ExecutionContext;
 use crate::mcore::mtask::process::Process;
 use crate::mcore::mtask::process::fd::{FdNum, FileDescriptor, FileDescriptorFlags};
 use crate::mcore::mtask::task::Task;
+use crate::mcore::mtask::wait;
 
+mod futex;
+mod log;
 mod mem;
+mod mount;
+mod poll;
+mod random;
+mod sched;
+mod socket;
+mod thread;
 
 pub struct KernelAccess<'a> {
     _task: &'a Task,
@@ -32,6 +44,35 @@ impl<'a> KernelAccess<'a> {
     }
 }
 
+impl KernelAccess<'_> {
+    /// Adds a file descriptor for `ofd` with the lowest free number.
+    fn insert_file_descriptor(&self, ofd: impl Into<Arc<OpenFileDescription>>) -> FdNum {
+        let mut fds = self.process.file_descriptors().write();
+        let num = fds
+            .keys()
+            .fold(0, |acc, &fd| {
+                if acc == Into::<i32>::into(fd) {
+                    acc + 1
+                } else {
+                    acc
+                }
+            })
+            .into();
+        let fd = FileDescriptor::new(num, FileDescriptorFlags::empty(), ofd.into());
+
+        fds.insert(num, fd);
+        num
+    }
+
+    /// Counts a read or write of `fd`, see
+    /// [`OpenFileDescription::count_io`].
+    fn count_io(&self, fd: FdNum) {
+        if let Some(descriptor) = self.process.file_descriptors().read().get(&fd) {
+            descriptor.file_description().count_io();
+        }
+    }
+}
+
 impl CwdAccess for KernelAccess<'_> {
     fn current_working_directory(&self) -> &RwLock<kernel_vfs::path::AbsoluteOwnedPath> {
         self.process.current_working_directory()
@@ -51,6 +92,7 @@ impl FileAccess for KernelAccess<'_> {
     type ReadError = ();
     type WriteError = ();
     type CloseError = ();
+    type IoctlError = Errno;
 
     fn file_info(&self, path: &AbsolutePath) -> Option<Self::FileInfo> {
         Some(FileInfo {
@@ -59,50 +101,119 @@ impl FileAccess for KernelAccess<'_> {
     }
 
     fn open(&self, info: &Self::FileInfo) -> Result<Self::Fd, ()> {
-        let ofd = OpenFileDescription::from(info.node.clone());
-        let num = self
-            .process
-            .file_descriptors()
-            .read()
-            .keys()
-            .fold(0, |acc, &fd| {
-                if acc == Into::<i32>::into(fd) {
-                    acc + 1
-                } else {
-                    acc
-                }
-            })
-            .into();
-        let fd = FileDescriptor::new(num, FileDescriptorFlags::empty(), ofd.into());
-
-        self.process.file_descriptors().write().insert(num, fd);
-
-        Ok(num)
+        Ok(self.insert_file_descriptor(OpenFileDescription::from(info.node.clone())))
     }
 
     fn read(&self, fd: Self::Fd, buf: &mut [u8]) -> Result<usize, ()> {
-        let fds = self.process.file_descriptors();
-        let guard = fds.read();
-
-        let desc = guard.get(&fd).ok_or(())?;
-        let ofd = desc.file_description();
+        // don't hold the lock on the file descriptors while waiting for input
+        let ofd = {
+            let fds = self.process.file_descriptors();
+            let guard = fds.read();
+            guard.get(&fd).ok_or(())?.file_description().clone()
+        };
+        ofd.count_io();
+        let node = match ofd.file() {
+            OpenFile::Node(node) => node,
+            OpenFile::Socket(socket) => {
+                return self
+                    .block_on(
+                        socket.is_nonblocking(),
+                        |waker| socket.register_waker(waker),
+                        || socket.recv(buf, false),
+                    )
+                    .map(|(len, _)| len)
+                    .map_err(|_| ());
+            }
+            // files that are passed to a socket that is read without
+            // `recvmsg` are closed
+            OpenFile::UnixSocket(socket) => {
+                return self
+                    .block_on(
+                        socket.is_nonblocking(),
+                        |waker| socket.register_waker(waker),
+                        || socket.recv(buf, false),
+                    )
+                    .map(|(len, _, _)| len)
+                    .map_err(|_| ());
+            }
+            OpenFile::Epoll(_) => return Err(()),
+        };
         let offset = ofd.position().fetch_add(buf.len() as u64, Relaxed); // TODO: respect file max len
-        ofd.read(buf, offset.into_usize()).map_err(|_| ())
+        // the read is interrupted if the process is being terminated, which
+        // happens before returning to userspace
+        let mut result = None;
+        wait::wait_until(None, |waker| {
+            node.register_waker(waker);
+            match node.read(&mut *buf, offset.into_usize()) {
+                Err(ReadError::WouldBlock) => false,
+                done => {
+                    result = Some(done);
+                    true
+                }
+            }
+        })
+        .map_err(|_| ())?;
+        result
+            .expect("should have a result once the wait succeeded")
+            .map_err(|_| ())
     }
 
     fn write(&self, fd: Self::Fd, buf: &[u8]) -> Result<usize, ()> {
-        let fds = self.process.file_descriptors();
-        let guard = fds.read();
-
-        let desc = guard.get(&fd).ok_or(())?;
-        let ofd = desc.file_description();
-        let offset = ofd.position().fetch_add(buf.len() as u64, Relaxed); // TODO: respect file max len
-        ofd.write(buf, offset.into_usize()).map_err(|_| ())
+        // don't hold the lock on the file descriptors while waiting for
+        // space in a socket
+        let ofd = {
+            let fds = self.process.file_descriptors();
+            let guard = fds.read();
+            guard.get(&fd).ok_or(())?.file_description().clone()
+        };
+        ofd.count_io();
+        match ofd.file() {
+            OpenFile::Node(node) => {
+                let offset = ofd.position().fetch_add(buf.len() as u64, Relaxed); // TODO: respect file max len
+                node.write(buf, offset.into_usize()).map_err(|_| ())
+            }
+            OpenFile::Socket(socket) => self
+                .block_on(
+                    socket.is_nonblocking(),
+                    |waker| socket.register_waker(waker),
+                    || socket.send(buf, None),
+                )
+                .map_err(|_| ()),
+            OpenFile::UnixSocket(socket) => self
+                .block_on(
+                    socket.is_nonblocking(),
+                    |waker| socket.register_waker(waker),
+                    || socket.send(buf, None, &[]),
+                )
+                .map_err(|_| ()),
+            OpenFile::Epoll(_) => Err(()),
+        }
     }
 
     fn close(&self, fd: Self::Fd) -> Result<(), ()> {
-        self.process.file_descriptors().write().remove(&fd);
-        Ok(())
+        self.process
+            .file_descriptors()
+            .write()
+            .remove(&fd)
+            .map(|_| ())
+            .ok_or(())
+    }
+
+    fn ioctl(&self, fd: Self::Fd, request: u64, arg: usize) -> Result<usize, Errno> {
+        let ofd = {
+            let fds = self.process.file_descriptors();
+            let guard = fds.read();
+            guard.get(&fd).ok_or(EBADF)?.file_description().clone()
+        };
+        let node = ofd.node().ok_or(ENOTTY)?;
+        node.ioctl(request, arg).map_err(|e| match e {
+            IoctlError::FsError(_) => EBADF,
+            IoctlError::Unsupported => ENOTTY,
+            IoctlError::InvalidArgument => EINVAL,
+            IoctlError::BadAddress => EFAULT,
+            IoctlError::NotPermitted => EPERM,
+            IoctlError::DeviceError => EIO,
+        })
     }
 }
 
@@ -135,6 +246,30 @@ impl kernel_syscall::access::MemoryRegionAccess for KernelAccess<'_> {
         Ok(addr)
     }
 
+    fn create_and_track_file_mapping(
+        &self,
+        location: kernel_syscall::access::Location,
+        size: usize,
+        fd: c_int,
+        offset: usize,
+    ) -> Result<kernel_syscall::UserspacePtr<u8>, kernel_syscall::access::CreateMappingError> {
+        let ofd = {
+            let fds = self.process.file_descriptors();
+            let guard = fds.read();
+            guard
+                .get(&fd.into())
+                .ok_or(kernel_syscall::access::CreateMappingError::BadFileDescriptor)?
+                .file_description()
+                .clone()
+        };
+
+        let region_handle = self.create_device_mapping(location, size, &ofd, offset)?;
+        let addr = region_handle.addr;
+        self.add_memory_region(region_handle);
+
+        Ok(addr)
+    }
+
     fn add_memory_region(&self, region: Self::Region) {
         self.process.memory_regions().add_region(region.inner);
     }
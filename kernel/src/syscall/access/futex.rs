@@ -0,0 +1,45 @@
+use core::task::Waker;
+use core::time::Duration;
+
+use kernel_syscall::UserspacePtr;
+use kernel_syscall::access::{FutexAccess, Interrupted};
+use kernel_syscall::futex::{FutexTable, Waiter};
+use x86_64::VirtAddr;
+
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::mtask::wait::{self, WaitError};
+use crate::syscall::access::KernelAccess;
+use crate::time;
+
+/// The waiters of the futexes of all processes, since futexes in shared
+/// memory are shared as well.
+static FUTEXES: FutexTable = FutexTable::new();
+
+impl FutexAccess for KernelAccess<'_> {
+    fn futex_key(&self, addr: UserspacePtr<u32>) -> Option<u64> {
+        self.process
+            .address_space()
+            .translate(VirtAddr::new(addr.addr() as u64))
+            .map(|addr| addr.as_u64())
+    }
+
+    fn futexes(&self) -> &FutexTable {
+        &FUTEXES
+    }
+
+    fn now(&self) -> Duration {
+        time::uptime()
+    }
+
+    fn waker(&self) -> Waker {
+        ExecutionContext::load().current_task().waker()
+    }
+
+    fn wait(&self, waiter: &Waiter, deadline: Option<Duration>) -> Result<(), Interrupted> {
+        match wait::block_until(deadline, || waiter.is_woken()) {
+            // the caller checks the deadline itself
+            Ok(()) | Err(WaitError::TimedOut) => Ok(()),
+            Err(WaitError::Interrupted) => Err(Interrupted),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use ::log::LevelFilter;
+use kernel_kmsg::Filter;
+use kernel_syscall::access::{Interrupted, LogAccess};
+
+use crate::log;
+use crate::mcore::mtask::wait;
+use crate::syscall::access::KernelAccess;
+
+impl LogAccess for KernelAccess<'_> {
+    fn read_log(&self, buf: &mut [u8]) -> Result<usize, Interrupted> {
+        let mut len = 0;
+        wait::wait_until(None, |waker| {
+            log::register_waker(waker);
+            len = log::read_syslog(buf);
+            len > 0
+        })
+        .map_err(|_| Interrupted)?;
+        Ok(len)
+    }
+
+    fn read_log_all(&self, buf: &mut [u8]) -> usize {
+        log::read_syslog_all(buf)
+    }
+
+    fn clear_log(&self) {
+        log::clear_syslog();
+    }
+
+    fn unread_log_len(&self) -> usize {
+        log::syslog_unread_len()
+    }
+
+    fn log_buffer_len(&self) -> usize {
+        log::syslog_capacity()
+    }
+
+    fn set_console_enabled(&self, enabled: bool) {
+        log::set_console_enabled(enabled);
+    }
+
+    fn set_console_level(&self, level: LevelFilter) {
+        log::set_console_level(level);
+    }
+
+    fn log_filter(&self) -> Filter {
+        log::filter()
+    }
+
+    fn set_log_filter(&self, filter: Filter) {
+        log::set_filter(filter);
+    }
+}
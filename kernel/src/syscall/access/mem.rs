@@ -2,12 +2,15 @@ use kernel_syscall::UserspacePtr;
 use kernel_syscall::access::{
     AllocationStrategy, CreateMappingError, Location, Mapping, MemoryAccess,
 };
+use kernel_vfs::MmapError;
 use kernel_virtual_memory::Segment;
-use x86_64::VirtAddr;
-use x86_64::structures::paging::{PageSize, PageTableFlags, Size4KiB};
+use x86_64::structures::paging::frame::PhysFrameRangeInclusive;
+use x86_64::structures::paging::{PageSize, PageTableFlags, PhysFrame, Size4KiB};
+use x86_64::{PhysAddr, VirtAddr};
 
 use crate::UsizeExt;
-use crate::mcore::mtask::process::mem::{MappedMemoryRegion, MemoryRegion};
+use crate::file::OpenFileDescription;
+use crate::mcore::mtask::process::mem::{DeviceMemoryRegion, MappedMemoryRegion, MemoryRegion};
 use crate::mem::phys::PhysicalMemory;
 use crate::mem::virt::{OwnedSegment, VirtualMemoryAllocator};
 use crate::syscall::access::{KernelAccess, KernelMemoryRegionHandle};
@@ -27,23 +30,8 @@ impl MemoryAccess for KernelAccess<'_> {
             "only eager allocation is supported"
         );
 
-        let page_aligned_size = size.next_multiple_of(Size4KiB::SIZE as usize);
-        let page_count = page_aligned_size / Size4KiB::SIZE as usize;
-
-        let segment = if let Location::Fixed(addr) = location {
-            self.process
-                .vmm()
-                .mark_as_reserved(Segment::new(
-                    VirtAddr::from_ptr(addr.as_ptr()),
-                    page_aligned_size.into_u64(),
-                ))
-                .map_err(|_| CreateMappingError::LocationAlreadyMapped)?
-        } else {
-            self.process
-                .vmm()
-                .reserve(page_count)
-                .ok_or(CreateMappingError::OutOfMemory)?
-        };
+        let page_count = size.div_ceil(Size4KiB::SIZE as usize);
+        let segment = self.reserve_segment(location, page_count)?;
 
         // Allocate physical frames and map them
         // TODO: Optimize by using 2MiB and 1GiB frames when possible instead of only 4KiB frames
@@ -71,11 +59,86 @@ impl MemoryAccess for KernelAccess<'_> {
     }
 }
 
+impl KernelAccess<'_> {
+    /// Reserves `page_count` pages in the address space of the process,
+    /// at the given location.
+    fn reserve_segment(
+        &self,
+        location: Location,
+        page_count: usize,
+    ) -> Result<OwnedSegment<'static>, CreateMappingError> {
+        if let Location::Fixed(addr) = location {
+            self.process
+                .vmm()
+                .mark_as_reserved(Segment::new(
+                    VirtAddr::from_ptr(addr.as_ptr()),
+                    (page_count * Size4KiB::SIZE as usize).into_u64(),
+                ))
+                .map_err(|_| CreateMappingError::LocationAlreadyMapped)
+        } else {
+            self.process
+                .vmm()
+                .reserve(page_count)
+                .ok_or(CreateMappingError::OutOfMemory)
+        }
+    }
+
+    /// Maps the device memory behind the file description `ofd` into the
+    /// process, see [`MemoryRegionAccess::create_and_track_file_mapping`].
+    ///
+    /// [`MemoryRegionAccess::create_and_track_file_mapping`]: kernel_syscall::access::MemoryRegionAccess::create_and_track_file_mapping
+    pub(super) fn create_device_mapping(
+        &self,
+        location: Location,
+        size: usize,
+        ofd: &OpenFileDescription,
+        offset: usize,
+    ) -> Result<KernelMemoryRegionHandle, CreateMappingError> {
+        let node = ofd.node().ok_or(CreateMappingError::NotMappable)?;
+        let page_count = size.div_ceil(Size4KiB::SIZE as usize);
+        let phys_addr = node
+            .mmap(offset, page_count * Size4KiB::SIZE as usize)
+            .map_err(|e| match e {
+                MmapError::FsError(_) => CreateMappingError::BadFileDescriptor,
+                MmapError::Unsupported => CreateMappingError::NotMappable,
+                MmapError::InvalidRange => CreateMappingError::InvalidRange,
+            })?;
+        let start = PhysFrame::<Size4KiB>::from_start_address(PhysAddr::new(phys_addr))
+            .map_err(|_| CreateMappingError::InvalidRange)?;
+        let frames = PhysFrameRangeInclusive {
+            start,
+            end: start + (page_count - 1).into_u64(),
+        };
+
+        let segment = self.reserve_segment(location, page_count)?;
+        self.process
+            .address_space()
+            .map_range::<Size4KiB>(
+                &*segment,
+                frames.into_iter(),
+                PageTableFlags::PRESENT
+                    | PageTableFlags::WRITABLE
+                    | PageTableFlags::USER_ACCESSIBLE
+                    | PageTableFlags::NO_EXECUTE,
+            )
+            .map_err(|_| CreateMappingError::OutOfMemory)?;
+
+        let addr = segment
+            .start
+            .as_ptr::<u8>()
+            .try_into()
+            .expect("device mapping should be located in user space");
+        let inner =
+            MemoryRegion::Device(DeviceMemoryRegion::new(segment, size, frames, node.clone()));
+        Ok(KernelMemoryRegionHandle { addr, size, inner })
+    }
+}
+
 pub struct KernelMapping {
     addr: VirtAddr,
     size: usize,
     segment: OwnedSegment<'static>,
-    physical_frames: x86_64::structures::paging::frame::PhysFrameRangeInclusive<Size4KiB>,
+    physical_frames: PhysFrameRangeInclusive<Size4KiB>,
 }
 
 impl KernelMapping {
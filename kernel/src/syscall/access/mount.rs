@@ -0,0 +1,69 @@
+use kernel_syscall::access::{MountAccess, MountError, UnmountError};
+use kernel_vfs::UnmountMode;
+use kernel_vfs::path::AbsolutePath;
+
+use crate::file::fs_type::{self, MountFsError};
+use crate::file::vfs;
+use crate::syscall::access::KernelAccess;
+
+impl MountAccess for KernelAccess<'_> {
+    fn mount(
+        &self,
+        source: Option<&str>,
+        target: &AbsolutePath,
+        fs_type: &str,
+        data: &str,
+        read_only: bool,
+    ) -> Result<(), MountError> {
+        fs_type::mount(
+            target,
+            source.unwrap_or("none"),
+            Some(fs_type),
+            data,
+            read_only,
+        )
+        .map_err(|e| match e {
+            MountFsError::UnknownType(_) => MountError::UnknownFileSystemType,
+            MountFsError::DeviceNotFound(_) => MountError::NotFound,
+            MountFsError::UnknownFileSystem(_) | MountFsError::Construct(_) => {
+                MountError::InvalidSource
+            }
+            MountFsError::Mount(e) => map_vfs_mount_error(e),
+        })
+    }
+
+    fn bind(
+        &self,
+        source: &AbsolutePath,
+        target: &AbsolutePath,
+        read_only: bool,
+    ) -> Result<(), MountError> {
+        vfs()
+            .write()
+            .bind(source, target, read_only)
+            .map_err(map_vfs_mount_error)
+    }
+
+    fn unmount(&self, target: &AbsolutePath, detach: bool) -> Result<(), UnmountError> {
+        let mode = if detach {
+            UnmountMode::Detach
+        } else {
+            UnmountMode::Normal
+        };
+        vfs().write().unmount(target, mode).map_err(|e| match e {
+            kernel_vfs::UnmountError::NotMounted => UnmountError::NotMounted,
+            kernel_vfs::UnmountError::Busy => UnmountError::Busy,
+        })
+    }
+}
+
+fn map_vfs_mount_error(e: kernel_vfs::MountError) -> MountError {
+    match e {
+        kernel_vfs::MountError::AlreadyMounted => MountError::Busy,
+        kernel_vfs::MountError::MountPointNotFound | kernel_vfs::MountError::SourceNotFound => {
+            MountError::NotFound
+        }
+        kernel_vfs::MountError::NotADirectory => MountError::NotADirectory,
+        kernel_vfs::MountError::NotEmpty => MountError::NotEmpty,
+    }
+}
@@ -0,0 +1,58 @@
+use alloc::sync::Arc;
+use core::task::Waker;
+use core::time::Duration;
+
+use kernel_syscall::access::{Interrupted, PollAccess};
+use kernel_syscall::epoll::Epoll;
+use spin::Mutex;
+
+use crate::file::OpenFileDescription;
+use crate::mcore::mtask::process::fd::FdNum;
+use crate::mcore::mtask::wait::{self, WaitError};
+use crate::syscall::access::KernelAccess;
+use crate::time;
+
+impl PollAccess for KernelAccess<'_> {
+    type Fd = FdNum;
+    type File = OpenFileDescription;
+
+    fn pollable(&self, fd: Self::Fd) -> Option<Arc<Self::File>> {
+        self.process
+            .file_descriptors()
+            .read()
+            .get(&fd)
+            .map(|descriptor| descriptor.file_description().clone())
+    }
+
+    fn create_epoll(&self) -> Self::Fd {
+        self.insert_file_descriptor(OpenFileDescription::from(Arc::new(
+            Mutex::new(Epoll::new()),
+        )))
+    }
+
+    fn with_epoll<R>(
+        &self,
+        epfd: Self::Fd,
+        f: impl FnOnce(&mut Epoll<Self::File>) -> R,
+    ) -> Option<R> {
+        // don't hold the file descriptor lock while `f` runs
+        let ofd = self.pollable(epfd)?;
+        let epoll = ofd.epoll()?;
+        Some(f(&mut epoll.lock()))
+    }
+
+    fn now(&self) -> Duration {
+        time::uptime()
+    }
+
+    fn wait_until(
+        &self,
+        deadline: Option<Duration>,
+        ready: impl FnMut(&Waker) -> bool,
+    ) -> Result<(), Interrupted> {
+        match wait::wait_until(deadline, ready) {
+            Ok(()) | Err(WaitError::TimedOut) => Ok(()),
+            Err(WaitError::Interrupted) => Err(Interrupted),
+        }
+    }
+}
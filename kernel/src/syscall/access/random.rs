@@ -0,0 +1,23 @@
+use kernel_syscall::access::{Interrupted, RandomAccess};
+
+use crate::mcore::mtask::wait;
+use crate::random;
+use crate::syscall::access::KernelAccess;
+
+impl RandomAccess for KernelAccess<'_> {
+    fn is_seeded(&self) -> bool {
+        random::is_seeded()
+    }
+
+    fn wait_until_seeded(&self) -> Result<(), Interrupted> {
+        wait::wait_until(None, |waker| {
+            random::register_waker(waker);
+            random::is_seeded()
+        })
+        .map_err(|_| Interrupted)
+    }
+
+    fn fill_random(&self, buf: &mut [u8]) {
+        random::fill_bytes(buf);
+    }
+}
@@ -0,0 +1,64 @@
+use core::ffi::c_int;
+
+use kernel_sched::Params;
+use kernel_syscall::access::{NoSuchThread, SchedAccess};
+
+use crate::mcore;
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::mtask::task::TaskHandle;
+use crate::syscall::access::KernelAccess;
+
+impl SchedAccess for KernelAccess<'_> {
+    fn cpu_count(&self) -> usize {
+        mcore::cpu_count()
+    }
+
+    fn affinity(&self, tid: c_int) -> Result<u64, NoSuchThread> {
+        Ok(self.task_handle(tid)?.affinity())
+    }
+
+    fn set_affinity(&self, tid: c_int, mask: u64) -> Result<(), NoSuchThread> {
+        self.task_handle(tid)?.set_affinity(mask);
+
+        // the current task moves to another CPU right away if it may not
+        // stay on this one
+        let ctx = ExecutionContext::load();
+        if !ctx.current_task().may_run_on(ctx.cpu_id()) {
+            unsafe { ctx.scheduler_mut().reschedule() };
+        }
+        Ok(())
+    }
+
+    fn params(&self, tid: c_int) -> Result<Params, NoSuchThread> {
+        Ok(self.task_handle(tid)?.params())
+    }
+
+    fn set_params(&self, tid: c_int, params: Params) -> Result<(), NoSuchThread> {
+        self.task_handle(tid)?.set_params(params);
+        Ok(())
+    }
+
+    fn yield_now(&self) {
+        unsafe { ExecutionContext::load().scheduler_mut().yield_now() };
+    }
+}
+
+impl KernelAccess<'_> {
+    /// The handle of the task `tid` of the current process, or of the
+    /// current task if `tid` is zero. The tasks of other processes, and of
+    /// the kernel, can't be found, so that a process can only change how
+    /// its own threads are scheduled.
+    fn task_handle(&self, tid: c_int) -> Result<TaskHandle, NoSuchThread> {
+        if tid == 0 {
+            return Ok(ExecutionContext::load().current_task().handle());
+        }
+        let tid = u64::try_from(tid).map_err(|_| NoSuchThread)?;
+        self.process
+            .task_handles()
+            .read()
+            .iter()
+            .find(|(id, _)| **id == tid)
+            .map(|(_, handle)| handle.clone())
+            .ok_or(NoSuchThread)
+    }
+}
@@ -0,0 +1,368 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::net::SocketAddr;
+use core::task::Waker;
+
+use kernel_syscall::access::{
+    Credentials, CwdAccess, Received, Shutdown, SocketAccess, SocketAddress, SocketDomain,
+    SocketError, SocketOption, SocketType, UnixAddress,
+};
+use kernel_vfs::path::OwnedPath;
+use smoltcp::wire::IpVersion;
+
+use crate::file::{OpenFile, OpenFileDescription};
+use crate::mcore::mtask::process::fd::FdNum;
+use crate::mcore::mtask::wait;
+use crate::net::socket::Socket;
+use crate::net::unix::UnixSocket;
+use crate::syscall::access::KernelAccess;
+
+/// The socket that a file descriptor refers to.
+enum AnySocket {
+    Inet(Arc<Socket>),
+    Unix(Arc<UnixSocket>),
+}
+
+impl AnySocket {
+    fn is_nonblocking(&self) -> bool {
+        match self {
+            Self::Inet(socket) => socket.is_nonblocking(),
+            Self::Unix(socket) => socket.is_nonblocking(),
+        }
+    }
+}
+
+impl KernelAccess<'_> {
+    fn socket_of(&self, fd: FdNum) -> Result<AnySocket, SocketError> {
+        let fds = self.process.file_descriptors();
+        let guard = fds.read();
+        let descriptor = guard.get(&fd).ok_or(SocketError::BadFileDescriptor)?;
+        match descriptor.file_description().file() {
+            OpenFile::Socket(socket) => Ok(AnySocket::Inet(socket.clone())),
+            OpenFile::UnixSocket(socket) => Ok(AnySocket::Unix(socket.clone())),
+            OpenFile::Node(_) | OpenFile::Epoll(_) => Err(SocketError::NotASocket),
+        }
+    }
+
+    /// The credentials of the current process. There are no users, so
+    /// everything runs as root.
+    fn credentials(&self) -> Credentials {
+        Credentials {
+            pid: self.process.pid().into(),
+            uid: 0,
+            gid: 0,
+        }
+    }
+
+    /// Resolves the path of a Unix socket address against the working
+    /// directory.
+    fn resolve(&self, address: UnixAddress) -> UnixAddress {
+        match address {
+            UnixAddress::Path(path) => {
+                UnixAddress::Path(OwnedPath::new(self.absolute_path(&path).as_str()))
+            }
+            address => address,
+        }
+    }
+
+    /// Calls `f` until it doesn't fail with [`SocketError::WouldBlock`],
+    /// unless `nonblocking` is set. Before every call, `register` registers
+    /// the waker of the task with the sockets that `f` waits for.
+    pub(super) fn block_on<T>(
+        &self,
+        nonblocking: bool,
+        register: impl Fn(&Waker),
+        mut f: impl FnMut() -> Result<T, SocketError>,
+    ) -> Result<T, SocketError> {
+        if nonblocking {
+            return f();
+        }
+        let mut result = None;
+        wait::wait_until(None, |waker| {
+            register(waker);
+            match f() {
+                Err(SocketError::WouldBlock) => false,
+                done => {
+                    result = Some(done);
+                    true
+                }
+            }
+        })
+        .map_err(|_| SocketError::Interrupted)?;
+        result.expect("should have a result once the wait succeeded")
+    }
+}
+
+impl SocketAccess for KernelAccess<'_> {
+    type Fd = FdNum;
+
+    fn socket(
+        &self,
+        domain: SocketDomain,
+        typ: SocketType,
+        nonblocking: bool,
+    ) -> Result<Self::Fd, SocketError> {
+        let ofd = match domain {
+            SocketDomain::Inet => {
+                OpenFileDescription::from(Arc::new(Socket::new(IpVersion::Ipv4, typ, nonblocking)))
+            }
+            SocketDomain::Inet6 => {
+                OpenFileDescription::from(Arc::new(Socket::new(IpVersion::Ipv6, typ, nonblocking)))
+            }
+            SocketDomain::Unix => OpenFileDescription::from(Arc::new(UnixSocket::new(
+                typ,
+                nonblocking,
+                self.credentials(),
+            ))),
+        };
+        Ok(self.insert_file_descriptor(ofd))
+    }
+
+    fn socket_pair(
+        &self,
+        domain: SocketDomain,
+        typ: SocketType,
+        nonblocking: bool,
+    ) -> Result<(Self::Fd, Self::Fd), SocketError> {
+        if domain != SocketDomain::Unix {
+            return Err(SocketError::NotSupported);
+        }
+        let (a, b) = UnixSocket::pair(typ, nonblocking, self.credentials());
+        Ok((
+            self.insert_file_descriptor(OpenFileDescription::from(a)),
+            self.insert_file_descriptor(OpenFileDescription::from(b)),
+        ))
+    }
+
+    fn bind(&self, fd: Self::Fd, address: SocketAddress) -> Result<(), SocketError> {
+        match (self.socket_of(fd)?, address) {
+            (AnySocket::Inet(socket), address) => socket.bind(inet_address(address)?),
+            (AnySocket::Unix(socket), SocketAddress::Unix(address)) => {
+                socket.bind(self.resolve(address))
+            }
+            (AnySocket::Unix(_), _) => Err(SocketError::AddressFamilyNotSupported),
+        }
+    }
+
+    fn listen(&self, fd: Self::Fd, backlog: usize) -> Result<(), SocketError> {
+        match self.socket_of(fd)? {
+            AnySocket::Inet(socket) => socket.listen(backlog),
+            AnySocket::Unix(socket) => socket.listen(backlog),
+        }
+    }
+
+    fn accept(&self, fd: Self::Fd) -> Result<(Self::Fd, SocketAddress), SocketError> {
+        let socket = self.socket_of(fd)?;
+        self.count_io(fd);
+        let nonblocking = socket.is_nonblocking();
+        let (ofd, peer) = match socket {
+            AnySocket::Inet(socket) => {
+                let (connection, peer) = self.block_on(
+                    nonblocking,
+                    |waker| socket.register_waker(waker),
+                    || socket.accept(),
+                )?;
+                (OpenFileDescription::from(Arc::new(connection)), peer)
+            }
+            AnySocket::Unix(socket) => {
+                let (connection, peer) = self.block_on(
+                    nonblocking,
+                    |waker| socket.register_waker(waker),
+                    || socket.accept(),
+                )?;
+                (OpenFileDescription::from(connection), peer)
+            }
+        };
+        Ok((self.insert_file_descriptor(ofd), peer))
+    }
+
+    fn connect(&self, fd: Self::Fd, address: SocketAddress) -> Result<(), SocketError> {
+        match (self.socket_of(fd)?, address) {
+            (AnySocket::Inet(socket), address) => {
+                socket.connect(inet_address(address)?)?;
+                if socket.is_nonblocking() {
+                    // the result is available through `SocketOption::Error`
+                    return socket.finish_connect().map_err(|e| match e {
+                        SocketError::WouldBlock => SocketError::InProgress,
+                        e => e,
+                    });
+                }
+                self.block_on(
+                    false,
+                    |waker| socket.register_waker(waker),
+                    || socket.finish_connect(),
+                )
+            }
+            (AnySocket::Unix(socket), SocketAddress::Unix(address)) => {
+                // connections are established right away, unless the
+                // backlog of the listening socket is full
+                let address = self.resolve(address);
+                self.block_on(
+                    socket.is_nonblocking(),
+                    |waker| socket.register_send_waker(Some(&address), waker),
+                    || socket.connect(&address),
+                )
+            }
+            (AnySocket::Unix(_), _) => Err(SocketError::AddressFamilyNotSupported),
+        }
+    }
+
+    fn send_to(
+        &self,
+        fd: Self::Fd,
+        buf: &[u8],
+        address: Option<SocketAddress>,
+        rights: &[Self::Fd],
+        nonblocking: bool,
+    ) -> Result<usize, SocketError> {
+        let socket = self.socket_of(fd)?;
+        self.count_io(fd);
+        let nonblocking = nonblocking || socket.is_nonblocking();
+        match socket {
+            AnySocket::Inet(socket) => {
+                if !rights.is_empty() {
+                    return Err(SocketError::NotSupported);
+                }
+                let address = address.map(inet_address).transpose()?;
+                self.block_on(
+                    nonblocking,
+                    |waker| socket.register_waker(waker),
+                    || socket.send(buf, address),
+                )
+            }
+            AnySocket::Unix(socket) => {
+                let address = match address {
+                    Some(SocketAddress::Unix(address)) => Some(self.resolve(address)),
+                    Some(SocketAddress::Inet(_) | SocketAddress::Inet6(_)) => {
+                        return Err(SocketError::AddressFamilyNotSupported);
+                    }
+                    None => None,
+                };
+                let rights = {
+                    let fds = self.process.file_descriptors();
+                    let guard = fds.read();
+                    rights
+                        .iter()
+                        .map(|fd| {
+                            guard
+                                .get(fd)
+                                .map(|descriptor| descriptor.file_description().clone())
+                                .ok_or(SocketError::BadFileDescriptor)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?
+                };
+                self.block_on(
+                    nonblocking,
+                    |waker| socket.register_send_waker(address.as_ref(), waker),
+                    || socket.send(buf, address.as_ref(), &rights),
+                )
+            }
+        }
+    }
+
+    fn recv_from(
+        &self,
+        fd: Self::Fd,
+        buf: &mut [u8],
+        peek: bool,
+        nonblocking: bool,
+        max_rights: usize,
+    ) -> Result<Received<Self::Fd>, SocketError> {
+        let socket = self.socket_of(fd)?;
+        self.count_io(fd);
+        let nonblocking = nonblocking || socket.is_nonblocking();
+        match socket {
+            AnySocket::Inet(socket) => {
+                let (len, sender) = self.block_on(
+                    nonblocking,
+                    |waker| socket.register_waker(waker),
+                    || socket.recv(buf, peek),
+                )?;
+                Ok(Received {
+                    len,
+                    sender,
+                    rights: Vec::new(),
+                    rights_truncated: false,
+                })
+            }
+            AnySocket::Unix(socket) => {
+                let (len, sender, mut files) = self.block_on(
+                    nonblocking,
+                    |waker| socket.register_waker(waker),
+                    || socket.recv(buf, peek),
+                )?;
+                // the files that don't fit are closed when they are dropped
+                let rights_truncated = files.len() > max_rights;
+                files.truncate(max_rights);
+                let rights = files
+                    .into_iter()
+                    .map(|ofd| self.insert_file_descriptor(ofd))
+                    .collect();
+                Ok(Received {
+                    len,
+                    sender,
+                    rights,
+                    rights_truncated,
+                })
+            }
+        }
+    }
+
+    fn shutdown(&self, fd: Self::Fd, how: Shutdown) -> Result<(), SocketError> {
+        match self.socket_of(fd)? {
+            AnySocket::Inet(socket) => socket.shutdown(how),
+            AnySocket::Unix(socket) => socket.shutdown(how),
+        }
+    }
+
+    fn socket_option(&self, fd: Self::Fd, option: SocketOption) -> Result<c_int, SocketError> {
+        match self.socket_of(fd)? {
+            AnySocket::Inet(socket) => socket.option(option),
+            AnySocket::Unix(socket) => socket.option(option),
+        }
+    }
+
+    fn set_socket_option(
+        &self,
+        fd: Self::Fd,
+        option: SocketOption,
+        value: c_int,
+    ) -> Result<(), SocketError> {
+        match self.socket_of(fd)? {
+            AnySocket::Inet(socket) => socket.set_option(option, value),
+            AnySocket::Unix(socket) => socket.set_option(option, value),
+        }
+    }
+
+    fn local_address(&self, fd: Self::Fd) -> Result<SocketAddress, SocketError> {
+        match self.socket_of(fd)? {
+            AnySocket::Inet(socket) => socket.local_address(),
+            AnySocket::Unix(socket) => Ok(socket.local_address()),
+        }
+    }
+
+    fn peer_address(&self, fd: Self::Fd) -> Result<SocketAddress, SocketError> {
+        match self.socket_of(fd)? {
+            AnySocket::Inet(socket) => socket.peer_address(),
+            AnySocket::Unix(socket) => socket.peer_address(),
+        }
+    }
+
+    fn peer_credentials(&self, fd: Self::Fd) -> Result<Credentials, SocketError> {
+        match self.socket_of(fd)? {
+            AnySocket::Inet(_) => Err(SocketError::InvalidOption),
+            AnySocket::Unix(socket) => socket.peer_credentials(),
+        }
+    }
+}
+
+/// The address of an IPv4 or IPv6 socket, which is checked against the
+/// family of the socket by the socket itself.
+fn inet_address(address: SocketAddress) -> Result<SocketAddr, SocketError> {
+    match address {
+        SocketAddress::Inet(address) => Ok(address.into()),
+        SocketAddress::Inet6(address) => Ok(address.into()),
+        SocketAddress::Unix(_) => Err(SocketError::AddressFamilyNotSupported),
+    }
+}
@@ -0,0 +1,70 @@
+use alloc::boxed::Box;
+use core::ffi::c_int;
+
+use kernel_abi::FUTEX_BITSET_MATCH_ANY;
+use kernel_syscall::UserspacePtr;
+use kernel_syscall::access::{FutexAccess, NewThread, SpawnThreadError, ThreadAccess};
+use x86_64::VirtAddr;
+
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::mtask::scheduler::run_queue;
+use crate::syscall::access::KernelAccess;
+
+impl ThreadAccess for KernelAccess<'_> {
+    fn spawn_thread(&self, thread: NewThread) -> Result<c_int, SpawnThreadError> {
+        let NewThread {
+            entry,
+            arg,
+            stack,
+            tls,
+            parent_tid,
+            child_tid,
+            clear_child_tid,
+        } = thread;
+
+        let task = self
+            .process
+            .create_thread(
+                VirtAddr::new(entry.addr() as u64),
+                arg as u64,
+                stack.map(|(stack, size)| VirtAddr::new((stack.addr() + size) as u64)),
+                tls.map(|tls| VirtAddr::new(tls.addr() as u64)),
+            )
+            .map_err(|_| SpawnThreadError::OutOfMemory)?;
+        let tid = c_int::try_from(u64::from(task.id())).expect("task ids should fit into c_int");
+
+        // the thread mustn't run before its id was written
+        for mut ptr in [parent_tid, child_tid].into_iter().flatten() {
+            unsafe { ptr.as_mut_ptr().write(tid) };
+        }
+        if let Some(ptr) = clear_child_tid {
+            task.set_clear_child_tid(ptr.addr());
+        }
+        // like on Linux, threads are scheduled like the thread that created
+        // them
+        task.handle()
+            .set_params(ExecutionContext::load().current_task().params());
+        run_queue::enqueue(Box::pin(task));
+        Ok(tid)
+    }
+}
+
+impl KernelAccess<'_> {
+    /// Writes zero to the thread id at `addr`, which the exiting thread was
+    /// created with [`CloneFlags::CHILD_CLEARTID`] for, and wakes up a
+    /// thread that joins it.
+    ///
+    /// [`CloneFlags::CHILD_CLEARTID`]: kernel_abi::CloneFlags::CHILD_CLEARTID
+    pub fn clear_child_tid(&self, addr: usize) {
+        let Ok(ptr) = (unsafe { UserspacePtr::<u32>::try_from_usize(addr) }) else {
+            return;
+        };
+        // a thread id that was unmapped in the meantime is ignored, like on
+        // Linux
+        let Some(key) = self.futex_key(ptr) else {
+            return;
+        };
+        unsafe { (addr as *mut c_int).write_volatile(0) };
+        self.futexes().wake(key, 1, FUTEX_BITSET_MATCH_ANY);
+    }
+}
@@ -1,18 +1,41 @@
+use alloc::vec::Vec;
 use core::slice::{from_raw_parts, from_raw_parts_mut};
 use core::ops::Neg;
 
 
 use access::KernelAccess;
-use kernel_abi::{EINVAL, Errno, syscall_name};
+use kernel_abi::{
+    CloneArgs, EFAULT, EINVAL, EMSGSIZE, EpollEvent, Errno, FdSet, IOV_MAX, IoVec, MsgHdr, PollFd,
+    SIGKILL, SchedParam, SockLen, TimeSpec, TimeVal, syscall_name,
+};
 use kernel_syscall::access::FileAccess;
+use kernel_syscall::epoll::{sys_epoll_create1, sys_epoll_ctl, sys_epoll_wait};
 use kernel_syscall::fcntl::sys_open;
+use kernel_syscall::futex::sys_futex;
+use kernel_syscall::ioctl::sys_ioctl;
 use kernel_syscall::mman::sys_mmap;
-use kernel_syscall::unistd::{sys_getcwd, sys_read, sys_write};
+use kernel_syscall::mount::{sys_mount, sys_umount2};
+use kernel_syscall::poll::{sys_poll, sys_ppoll, sys_select};
+use kernel_syscall::random::sys_getrandom;
+use kernel_syscall::sched::{
+    sys_getpriority, sys_sched_getaffinity, sys_sched_getscheduler, sys_sched_setaffinity,
+    sys_sched_setscheduler, sys_sched_yield, sys_setpriority,
+};
+use kernel_syscall::socket::{
+    AddressBuffer, sys_accept, sys_bind, sys_connect, sys_getpeername, sys_getsockname,
+    sys_getsockopt, sys_listen, sys_recvfrom, sys_recvmsg, sys_sendmsg, sys_sendto,
+    sys_setsockopt, sys_shutdown, sys_socket, sys_socketpair,
+};
+use kernel_syscall::syslog::{sys_syslog, syslog_uses_buffer};
+use kernel_syscall::thread::sys_clone;
+use kernel_syscall::unistd::{sys_close, sys_getcwd, sys_read, sys_write};
 use kernel_syscall::{UserspaceMutPtr, UserspacePtr};
 use log::{error, trace};
 
+use crate::mcore::mtask::process::fd::FdNum;
+
 #[cfg(target_arch = "x86_64")]
-use x86_64::instructions::hlt;
+use x86_64::instructions::{hlt, interrupts};
 
 #[cfg(not(target_arch = "x86_64"))]
 fn hlt() {
@@ -40,20 +63,48 @@ pub fn dispatch_syscall(
     );
 
     let result: Result<usize, Errno> = match n {
-        kernel_abi::SYS_EXIT => {
-            let status = i32::try_from(arg1).unwrap_or(0);
-            let task = crate::mcore::context::ExecutionContext::load().current_task();
-            let process = task.process();
-            *process.exit_code().write() = Some(status);
-            task.set_should_terminate(true);
-            loop {
-                hlt();
-            }
-        }
+        kernel_abi::SYS_ACCEPT => dispatch_sys_accept(arg1, arg2, arg3),
+        kernel_abi::SYS_BIND => dispatch_sys_bind(arg1, arg2, arg3),
+        kernel_abi::SYS_CLONE => dispatch_sys_clone(arg1, arg2),
+        kernel_abi::SYS_CLOSE => dispatch_sys_close(arg1),
+        kernel_abi::SYS_CONNECT => dispatch_sys_connect(arg1, arg2, arg3),
+        kernel_abi::SYS_EPOLL_CREATE1 => dispatch_sys_epoll_create1(arg1),
+        kernel_abi::SYS_EPOLL_CTL => dispatch_sys_epoll_ctl(arg1, arg2, arg3, arg4),
+        kernel_abi::SYS_EPOLL_WAIT => dispatch_sys_epoll_wait(arg1, arg2, arg3, arg4),
+        kernel_abi::SYS_EXIT => exit_current_thread(i32::try_from(arg1).unwrap_or(0)),
+        kernel_abi::SYS_EXIT_GROUP => exit_group(i32::try_from(arg1).unwrap_or(0)),
+        kernel_abi::SYS_FUTEX => dispatch_sys_futex(arg1, arg2, arg3, arg4, arg5, arg6),
         kernel_abi::SYS_GETCWD => dispatch_sys_getcwd(arg1, arg2),
+        kernel_abi::SYS_GETPEERNAME => dispatch_sys_getpeername(arg1, arg2, arg3),
+        kernel_abi::SYS_GETPRIORITY => dispatch_sys_getpriority(arg1, arg2),
+        kernel_abi::SYS_GETRANDOM => dispatch_sys_getrandom(arg1, arg2, arg3),
+        kernel_abi::SYS_GETSOCKNAME => dispatch_sys_getsockname(arg1, arg2, arg3),
+        kernel_abi::SYS_GETSOCKOPT => dispatch_sys_getsockopt(arg1, arg2, arg3, arg4, arg5),
+        kernel_abi::SYS_IOCTL => dispatch_sys_ioctl(arg1, arg2, arg3),
+        kernel_abi::SYS_LISTEN => dispatch_sys_listen(arg1, arg2),
         kernel_abi::SYS_MMAP => dispatch_sys_mmap(arg1, arg2, arg3, arg4, arg5, arg6),
+        kernel_abi::SYS_MOUNT => dispatch_sys_mount(arg1, arg2, arg3, arg4, arg5),
         kernel_abi::SYS_OPEN => dispatch_sys_open(arg1, arg2, arg3, arg4),
+        kernel_abi::SYS_POLL => dispatch_sys_poll(arg1, arg2, arg3),
+        kernel_abi::SYS_PPOLL => dispatch_sys_ppoll(arg1, arg2, arg3, arg4),
         kernel_abi::SYS_READ => dispatch_sys_read(arg1, arg2, arg3),
+        kernel_abi::SYS_RECVFROM => dispatch_sys_recvfrom(arg1, arg2, arg3, arg4, arg5, arg6),
+        kernel_abi::SYS_RECVMSG => dispatch_sys_recvmsg(arg1, arg2, arg3),
+        kernel_abi::SYS_SCHED_GETAFFINITY => dispatch_sys_sched_getaffinity(arg1, arg2, arg3),
+        kernel_abi::SYS_SCHED_GETSCHEDULER => dispatch_sys_sched_getscheduler(arg1),
+        kernel_abi::SYS_SCHED_SETAFFINITY => dispatch_sys_sched_setaffinity(arg1, arg2, arg3),
+        kernel_abi::SYS_SCHED_SETSCHEDULER => dispatch_sys_sched_setscheduler(arg1, arg2, arg3),
+        kernel_abi::SYS_SCHED_YIELD => sys_sched_yield(&KernelAccess::new()),
+        kernel_abi::SYS_SELECT => dispatch_sys_select(arg1, arg2, arg3, arg4, arg5),
+        kernel_abi::SYS_SENDMSG => dispatch_sys_sendmsg(arg1, arg2, arg3),
+        kernel_abi::SYS_SENDTO => dispatch_sys_sendto(arg1, arg2, arg3, arg4, arg5, arg6),
+        kernel_abi::SYS_SETPRIORITY => dispatch_sys_setpriority(arg1, arg2, arg3),
+        kernel_abi::SYS_SETSOCKOPT => dispatch_sys_setsockopt(arg1, arg2, arg3, arg4, arg5),
+        kernel_abi::SYS_SHUTDOWN => dispatch_sys_shutdown(arg1, arg2),
+        kernel_abi::SYS_SOCKET => dispatch_sys_socket(arg1, arg2, arg3),
+        kernel_abi::SYS_SOCKETPAIR => dispatch_sys_socketpair(arg1, arg2, arg3, arg4),
+        kernel_abi::SYS_SYSLOG => dispatch_sys_syslog(arg1, arg2, arg3),
+        kernel_abi::SYS_UMOUNT2 => dispatch_sys_umount2(arg1, arg2),
         kernel_abi::SYS_WRITE => dispatch_sys_write(arg1, arg2, arg3),
         _ => {
             error!("unimplemented syscall: {} ({n})", syscall_name(n));
@@ -63,6 +114,12 @@ pub fn dispatch_syscall(
         }
     };
 
+    // deliver signals before returning to userspace
+    let process = crate::mcore::context::ExecutionContext::load().current_process();
+    if let Some(signal) = process.pending_terminating_signal() {
+        exit_group(128 + signal);
+    }
+
     match result {
         Ok(ret) => {
             trace!("syscall {} ({n}) returned {ret}", syscall_name(n));
@@ -75,6 +132,42 @@ pub fn dispatch_syscall(
     }
 }
 
+/// Exits the current thread. If it was the last thread of the current
+/// process, the process exits with `status`.
+fn exit_current_thread(status: i32) -> ! {
+    let task = crate::mcore::context::ExecutionContext::load().current_task();
+    let process = task.process();
+    let clear_child_tid = task.clear_child_tid();
+    if clear_child_tid != 0 {
+        KernelAccess::new().clear_child_tid(clear_child_tid);
+    }
+    if process.exit_thread() {
+        let _ = process.exit_code().write().get_or_insert(status);
+    }
+    terminate_current_task()
+}
+
+/// Sets the exit code of the current process, unless it already has one,
+/// and exits all of its threads.
+fn exit_group(status: i32) -> ! {
+    let process = crate::mcore::context::ExecutionContext::load().current_process();
+    let _ = process.exit_code().write().get_or_insert(status);
+    // the other threads are terminated before they return to userspace
+    process.send_signal(SIGKILL);
+    terminate_current_task()
+}
+
+/// Waits for the scheduler to terminate the current task.
+fn terminate_current_task() -> ! {
+    let task = crate::mcore::context::ExecutionContext::load().current_task();
+    task.set_should_terminate(true);
+    #[cfg(target_arch = "x86_64")]
+    interrupts::enable();
+    loop {
+        hlt();
+    }
+}
+
 unsafe fn slice_from_ptr_and_len<'a, T>(ptr: usize, len: usize) -> Result<&'a [T], Errno> {
     if ptr == 0 || len == 0 {
         return Err(EINVAL);
@@ -98,6 +191,40 @@ fn dispatch_sys_getcwd(path: usize, size: usize) -> Result<usize, Errno> {
     sys_getcwd(&cx, path, size)
 }
 
+fn dispatch_sys_getrandom(buf: usize, buflen: usize, flags: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let flags = u32::try_from(flags).map_err(|_| EINVAL)?;
+    if buflen == 0 {
+        return sys_getrandom(&cx, &mut [], flags);
+    }
+    let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(buf)? };
+    ptr.validate_range(buflen)?;
+    let slice = unsafe { slice_from_ptr_and_len_mut(buf, buflen) }?;
+    sys_getrandom(&cx, slice, flags)
+}
+
+fn dispatch_sys_syslog(action: usize, buf: usize, len: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let action = i32::try_from(action).map_err(|_| EINVAL)?;
+    if !syslog_uses_buffer(action) || len == 0 {
+        return sys_syslog(&cx, action, &mut [], len);
+    }
+    let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(buf)? };
+    ptr.validate_range(len)?;
+    let slice = unsafe { slice_from_ptr_and_len_mut(buf, len) }?;
+    sys_syslog(&cx, action, slice, len)
+}
+
+fn dispatch_sys_ioctl(fd: usize, request: usize, arg: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let fd = i32::try_from(fd).map_err(|_| EINVAL)?;
+    let fd = <KernelAccess as FileAccess>::Fd::from(fd);
+    sys_ioctl(&cx, fd, request as u64, arg)
+}
+
 fn dispatch_sys_mmap(
     addr: usize,
     len: usize,
@@ -115,6 +242,30 @@ fn dispatch_sys_mmap(
     sys_mmap(&cx, addr, len, prot, flags, fd, offset)
 }
 
+fn dispatch_sys_mount(
+    source: usize,
+    target: usize,
+    fs_type: usize,
+    flags: usize,
+    data: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let source = unsafe { UserspacePtr::try_from_usize(source)? };
+    let target = unsafe { UserspacePtr::try_from_usize(target)? };
+    let fs_type = unsafe { UserspacePtr::try_from_usize(fs_type)? };
+    let data = unsafe { UserspacePtr::try_from_usize(data)? };
+    sys_mount(&cx, source, target, fs_type, flags as u64, data)
+}
+
+fn dispatch_sys_umount2(target: usize, flags: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let target = unsafe { UserspacePtr::try_from_usize(target)? };
+    let flags = i32::try_from(flags)?;
+    sys_umount2(&cx, target, flags)
+}
+
 fn dispatch_sys_open(
     path: usize,
     path_len: usize,
@@ -145,4 +296,441 @@ fn dispatch_sys_write(fd: usize, buf: usize, nbyte: usize) -> Result<usize, Errn
 
     let slice = unsafe { slice_from_ptr_and_len(buf, nbyte) }?;
     sys_write(&cx, fd, slice)
-}
\ No newline at end of file
+}
+
+fn dispatch_sys_close(fd: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let fd = i32::try_from(fd).map_err(|_| EINVAL)?;
+    let fd = <KernelAccess as FileAccess>::Fd::from(fd);
+    sys_close(&cx, fd)
+}
+
+fn socket_fd(fd: usize) -> Result<FdNum, Errno> {
+    let fd = i32::try_from(fd).map_err(|_| EINVAL)?;
+    Ok(fd.into())
+}
+
+/// Creates the buffer that a socket address is written to. Its length is
+/// read from and written back to the `socklen_t` at `len`. A null `addr`
+/// means that the caller doesn't want the address.
+unsafe fn address_buffer_from_ptrs<'a>(
+    addr: usize,
+    len: usize,
+) -> Result<Option<AddressBuffer<'a>>, Errno> {
+    if addr == 0 {
+        return Ok(None);
+    }
+    let mut len = unsafe { UserspaceMutPtr::<SockLen>::try_from_usize(len)? };
+    let len = unsafe { len.as_mut_ptr().as_mut() }.ok_or(EFAULT)?;
+    let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(addr)? };
+    ptr.validate_range(*len as usize)?;
+    let buf = unsafe { from_raw_parts_mut(addr as *mut u8, *len as usize) };
+    Ok(Some((buf, len)))
+}
+
+/// Creates a slice of `len` elements in userspace, which may be empty.
+unsafe fn user_slice<'a, T>(ptr: usize, len: usize) -> Result<&'a [T], Errno> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    let user = unsafe { UserspacePtr::<T>::try_from_usize(ptr)? };
+    user.validate_range(len.checked_mul(size_of::<T>()).ok_or(EINVAL)?)?;
+    unsafe { slice_from_ptr_and_len(ptr, len) }
+}
+
+/// Creates a mutable slice of `len` elements in userspace, which may be
+/// empty.
+unsafe fn user_slice_mut<'a, T>(ptr: usize, len: usize) -> Result<&'a mut [T], Errno> {
+    if len == 0 {
+        return Ok(&mut []);
+    }
+    let user = unsafe { UserspacePtr::<T>::try_from_usize(ptr)? };
+    user.validate_range(len.checked_mul(size_of::<T>()).ok_or(EINVAL)?)?;
+    unsafe { slice_from_ptr_and_len_mut(ptr, len) }
+}
+
+/// A `T` in userspace that may be omitted with a null pointer.
+unsafe fn user_ref_mut<'a, T>(ptr: usize) -> Result<Option<&'a mut T>, Errno> {
+    if ptr == 0 {
+        return Ok(None);
+    }
+    let user = unsafe { UserspacePtr::<T>::try_from_usize(ptr)? };
+    user.validate_range(size_of::<T>())?;
+    Ok(unsafe { (ptr as *mut T).as_mut() })
+}
+
+/// The `msghdr` of `sendmsg` and `recvmsg`, whose pointers aren't checked
+/// yet.
+unsafe fn msghdr_from_ptr<'a>(msg: usize) -> Result<&'a mut MsgHdr, Errno> {
+    let ptr = unsafe { UserspacePtr::<MsgHdr>::try_from_usize(msg)? };
+    ptr.validate_range(size_of::<MsgHdr>())?;
+    unsafe { (msg as *mut MsgHdr).as_mut() }.ok_or(EFAULT)
+}
+
+fn dispatch_sys_socket(domain: usize, typ: usize, protocol: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let domain = i32::try_from(domain)?;
+    let typ = i32::try_from(typ)?;
+    let protocol = i32::try_from(protocol)?;
+    sys_socket(&cx, domain, typ, protocol)
+}
+
+fn dispatch_sys_socketpair(
+    domain: usize,
+    typ: usize,
+    protocol: usize,
+    fds: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let domain = i32::try_from(domain)?;
+    let typ = i32::try_from(typ)?;
+    let protocol = i32::try_from(protocol)?;
+    let mut ptr = unsafe { UserspaceMutPtr::<[i32; 2]>::try_from_usize(fds)? };
+    let fds = unsafe { ptr.as_mut_ptr().as_mut() }.ok_or(EFAULT)?;
+    sys_socketpair(&cx, domain, typ, protocol, fds)
+}
+
+fn dispatch_sys_bind(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(addr)? };
+    ptr.validate_range(len)?;
+    let address = unsafe { slice_from_ptr_and_len(addr, len) }?;
+    sys_bind(&cx, socket_fd(fd)?, address)
+}
+
+fn dispatch_sys_listen(fd: usize, backlog: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    sys_listen(&cx, socket_fd(fd)?, backlog as i32)
+}
+
+fn dispatch_sys_accept(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let address = unsafe { address_buffer_from_ptrs(addr, len) }?;
+    sys_accept(&cx, socket_fd(fd)?, address)
+}
+
+fn dispatch_sys_connect(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(addr)? };
+    ptr.validate_range(len)?;
+    let address = unsafe { slice_from_ptr_and_len(addr, len) }?;
+    sys_connect(&cx, socket_fd(fd)?, address)
+}
+
+fn dispatch_sys_sendto(
+    fd: usize,
+    buf: usize,
+    len: usize,
+    flags: usize,
+    addr: usize,
+    addr_len: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let flags = i32::try_from(flags)?;
+    let slice: &[u8] = if len == 0 {
+        &[]
+    } else {
+        let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(buf)? };
+        ptr.validate_range(len)?;
+        unsafe { slice_from_ptr_and_len(buf, len) }?
+    };
+    let address = if addr == 0 {
+        None
+    } else {
+        let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(addr)? };
+        ptr.validate_range(addr_len)?;
+        Some(unsafe { slice_from_ptr_and_len(addr, addr_len) }?)
+    };
+    sys_sendto(&cx, socket_fd(fd)?, slice, flags, address)
+}
+
+fn dispatch_sys_recvfrom(
+    fd: usize,
+    buf: usize,
+    len: usize,
+    flags: usize,
+    addr: usize,
+    addr_len: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let flags = i32::try_from(flags)?;
+    let slice: &mut [u8] = if len == 0 {
+        &mut []
+    } else {
+        let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(buf)? };
+        ptr.validate_range(len)?;
+        unsafe { slice_from_ptr_and_len_mut(buf, len) }?
+    };
+    let address = unsafe { address_buffer_from_ptrs(addr, addr_len) }?;
+    sys_recvfrom(&cx, socket_fd(fd)?, slice, flags, address)
+}
+
+fn dispatch_sys_sendmsg(fd: usize, msg: usize, flags: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let flags = i32::try_from(flags)?;
+    let msg = unsafe { msghdr_from_ptr(msg) }?;
+    if msg.msg_iovlen > IOV_MAX {
+        return Err(EMSGSIZE);
+    }
+    let address = if msg.msg_name.is_null() {
+        None
+    } else {
+        Some(unsafe { user_slice(msg.msg_name as usize, msg.msg_namelen as usize) }?)
+    };
+    let iov = unsafe { user_slice::<IoVec>(msg.msg_iov as usize, msg.msg_iovlen) }?
+        .iter()
+        .map(|iov| unsafe { user_slice(iov.iov_base as usize, iov.iov_len) })
+        .collect::<Result<Vec<&[u8]>, _>>()?;
+    let control = unsafe { user_slice(msg.msg_control as usize, msg.msg_controllen) }?;
+    sys_sendmsg(&cx, socket_fd(fd)?, address, &iov, control, flags)
+}
+
+fn dispatch_sys_recvmsg(fd: usize, msg: usize, flags: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let flags = i32::try_from(flags)?;
+    let msg = unsafe { msghdr_from_ptr(msg) }?;
+    if msg.msg_iovlen > IOV_MAX {
+        return Err(EMSGSIZE);
+    }
+    let address = if msg.msg_name.is_null() {
+        None
+    } else {
+        let buf = unsafe { user_slice_mut(msg.msg_name as usize, msg.msg_namelen as usize) }?;
+        Some((buf, &mut msg.msg_namelen))
+    };
+    let mut iov = unsafe { user_slice::<IoVec>(msg.msg_iov as usize, msg.msg_iovlen) }?
+        .iter()
+        .map(|iov| unsafe { user_slice_mut(iov.iov_base as usize, iov.iov_len) })
+        .collect::<Result<Vec<&mut [u8]>, _>>()?;
+    let control = unsafe { user_slice_mut(msg.msg_control as usize, msg.msg_controllen) }?;
+    sys_recvmsg(
+        &cx,
+        socket_fd(fd)?,
+        address,
+        &mut iov,
+        (control, &mut msg.msg_controllen),
+        flags,
+        &mut msg.msg_flags,
+    )
+}
+
+fn dispatch_sys_shutdown(fd: usize, how: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let how = i32::try_from(how)?;
+    sys_shutdown(&cx, socket_fd(fd)?, how)
+}
+
+fn dispatch_sys_setsockopt(
+    fd: usize,
+    level: usize,
+    name: usize,
+    value: usize,
+    len: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let level = i32::try_from(level)?;
+    let name = i32::try_from(name)?;
+    let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(value)? };
+    ptr.validate_range(len)?;
+    let value = unsafe { slice_from_ptr_and_len(value, len) }?;
+    sys_setsockopt(&cx, socket_fd(fd)?, level, name, value)
+}
+
+fn dispatch_sys_getsockopt(
+    fd: usize,
+    level: usize,
+    name: usize,
+    value: usize,
+    len: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let level = i32::try_from(level)?;
+    let name = i32::try_from(name)?;
+    let value = unsafe { address_buffer_from_ptrs(value, len) }?.ok_or(EFAULT)?;
+    sys_getsockopt(&cx, socket_fd(fd)?, level, name, value)
+}
+
+fn dispatch_sys_getsockname(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let address = unsafe { address_buffer_from_ptrs(addr, len) }?.ok_or(EFAULT)?;
+    sys_getsockname(&cx, socket_fd(fd)?, address)
+}
+
+fn dispatch_sys_getpeername(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let address = unsafe { address_buffer_from_ptrs(addr, len) }?.ok_or(EFAULT)?;
+    sys_getpeername(&cx, socket_fd(fd)?, address)
+}
+
+fn dispatch_sys_poll(fds: usize, nfds: usize, timeout: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let fds = unsafe { user_slice_mut::<PollFd>(fds, nfds) }?;
+    // the timeout is a C `int`, so only the lower half of the register counts
+    sys_poll(&cx, fds, timeout as i32)
+}
+
+fn dispatch_sys_ppoll(
+    fds: usize,
+    nfds: usize,
+    timeout: usize,
+    _sigmask: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let fds = unsafe { user_slice_mut::<PollFd>(fds, nfds) }?;
+    let timeout = unsafe { user_ref_mut::<TimeSpec>(timeout) }?;
+    sys_ppoll(&cx, fds, timeout.as_deref())
+}
+
+fn dispatch_sys_select(
+    nfds: usize,
+    read: usize,
+    write: usize,
+    except: usize,
+    timeout: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let read = unsafe { user_ref_mut::<FdSet>(read) }?;
+    let write = unsafe { user_ref_mut::<FdSet>(write) }?;
+    let except = unsafe { user_ref_mut::<FdSet>(except) }?;
+    let timeout = unsafe { user_ref_mut::<TimeVal>(timeout) }?;
+    sys_select(&cx, nfds as i32, read, write, except, timeout)
+}
+
+fn dispatch_sys_epoll_create1(flags: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    sys_epoll_create1(&cx, i32::try_from(flags)?)
+}
+
+fn dispatch_sys_epoll_ctl(epfd: usize, op: usize, fd: usize, event: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let epfd = i32::try_from(epfd).map_err(|_| EINVAL)?;
+    let op = i32::try_from(op)?;
+    let fd = i32::try_from(fd).map_err(|_| EINVAL)?;
+    let event = unsafe { user_ref_mut::<EpollEvent>(event) }?.map(|event| *event);
+    sys_epoll_ctl(&cx, epfd, op, fd, event)
+}
+
+fn dispatch_sys_epoll_wait(
+    epfd: usize,
+    events: usize,
+    maxevents: usize,
+    timeout: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let epfd = i32::try_from(epfd).map_err(|_| EINVAL)?;
+    let maxevents = usize::try_from(maxevents as i32).map_err(|_| EINVAL)?;
+    let events = unsafe { user_slice_mut::<EpollEvent>(events, maxevents) }?;
+    sys_epoll_wait(&cx, epfd, events, timeout as i32)
+}
+
+fn dispatch_sys_futex(
+    uaddr: usize,
+    op: usize,
+    val: usize,
+    timeout: usize,
+    uaddr2: usize,
+    val3: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let uaddr = unsafe { UserspacePtr::try_from_usize(uaddr)? };
+    let op = i32::try_from(op)?;
+    // the values are C `int`s, so only the lower half of the registers counts
+    sys_futex(&cx, uaddr, op, val as u32, timeout, uaddr2, val3 as u32)
+}
+
+fn dispatch_sys_clone(args: usize, size: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    if size < size_of::<CloneArgs>() {
+        return Err(EINVAL);
+    }
+    let args = unsafe { user_ref_mut::<CloneArgs>(args) }?.ok_or(EFAULT)?;
+    sys_clone(&cx, args)
+}
+
+fn dispatch_sys_sched_setaffinity(tid: usize, len: usize, mask: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let tid = i32::try_from(tid).map_err(|_| EINVAL)?;
+    if len == 0 {
+        return sys_sched_setaffinity(&cx, tid, &[]);
+    }
+    let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(mask)? };
+    ptr.validate_range(len)?;
+    let slice = unsafe { slice_from_ptr_and_len(mask, len) }?;
+    sys_sched_setaffinity(&cx, tid, slice)
+}
+
+fn dispatch_sys_sched_getaffinity(tid: usize, len: usize, mask: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let tid = i32::try_from(tid).map_err(|_| EINVAL)?;
+    if len == 0 {
+        return sys_sched_getaffinity(&cx, tid, &mut []);
+    }
+    let ptr = unsafe { UserspacePtr::<u8>::try_from_usize(mask)? };
+    ptr.validate_range(len)?;
+    let slice = unsafe { slice_from_ptr_and_len_mut(mask, len) }?;
+    sys_sched_getaffinity(&cx, tid, slice)
+}
+
+fn dispatch_sys_sched_setscheduler(
+    tid: usize,
+    policy: usize,
+    param: usize,
+) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let tid = i32::try_from(tid).map_err(|_| EINVAL)?;
+    let policy = i32::try_from(policy).map_err(|_| EINVAL)?;
+    let param = unsafe { user_ref_mut::<SchedParam>(param) }?.ok_or(EINVAL)?;
+    sys_sched_setscheduler(&cx, tid, policy, param)
+}
+
+fn dispatch_sys_sched_getscheduler(tid: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let tid = i32::try_from(tid).map_err(|_| EINVAL)?;
+    sys_sched_getscheduler(&cx, tid)
+}
+
+fn dispatch_sys_setpriority(which: usize, who: usize, nice: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let which = i32::try_from(which).map_err(|_| EINVAL)?;
+    let who = i32::try_from(who).map_err(|_| EINVAL)?;
+    // nice values are negative, and come sign-extended or not
+    let nice = nice as i32;
+    sys_setpriority(&cx, which, who, nice)
+}
+
+fn dispatch_sys_getpriority(which: usize, who: usize) -> Result<usize, Errno> {
+    let cx = KernelAccess::new();
+
+    let which = i32::try_from(which).map_err(|_| EINVAL)?;
+    let who = i32::try_from(who).map_err(|_| EINVAL)?;
+    sys_getpriority(&cx, which, who)
+}
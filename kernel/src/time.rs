@@ -1,7 +1,9 @@
+use core::time::Duration;
+
 use jiff::Timestamp;
 
 use crate::BOOT_TIME_SECONDS;
-use crate::hpet::hpet;
+use crate::hpet::{hpet, try_hpet};
 
 pub trait TimestampExt {
     fn now() -> Self;
@@ -19,3 +21,23 @@ impl TimestampExt for Timestamp {
         .unwrap()
     }
 }
+
+/// Returns the time that has passed since the HPET was started.
+///
+/// # Panics
+/// Panics if the HPET is not initialized yet.
+pub fn uptime() -> Duration {
+    try_uptime().expect("hpet should be initialized")
+}
+
+/// Like [`uptime`], but returns `None` if the HPET is not initialized yet.
+pub fn try_uptime() -> Option<Duration> {
+    let (counter, period_fs) = {
+        let hpet = try_hpet()?.read();
+        (hpet.main_counter_value(), hpet.period_femtoseconds())
+    };
+    let nanos = u128::from(counter) * u128::from(period_fs) / 1_000_000;
+    Some(Duration::from_nanos(
+        u64::try_from(nanos).expect("uptime should fit into u64 nanoseconds"),
+    ))
+}
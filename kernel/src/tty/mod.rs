@@ -0,0 +1,304 @@
+//! Terminals. A [`Tty`] connects a [`LineDiscipline`] to the driver of a
+//! character device and remembers the session and the foreground process
+//! group that it is the controlling terminal of.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::Ordering::Relaxed;
+use core::task::Waker;
+
+use kernel_abi::{
+    SIGHUP, SIGWINCH, TCGETS, TCSETS, TCSETSF, TCSETSW, TIOCGPGRP, TIOCGWINSZ, TIOCSCTTY,
+    TIOCSPGRP, TIOCSWINSZ, Termios, Winsize,
+};
+use kernel_devfs::DevFile;
+use kernel_syscall::ioctl::{read_arg, write_arg};
+use kernel_tty::LineDiscipline;
+use kernel_vfs::{FileType, IoctlError, ReadError, Readiness, Stat, StatError, WriteError};
+use log::debug;
+use spin::{Mutex, RwLock};
+use x86_64::instructions::interrupts;
+
+use crate::mcore::context::ExecutionContext;
+use crate::mcore::mtask::process::ProcessId;
+use crate::mcore::mtask::process::tree::process_tree;
+use crate::mcore::mtask::wait::WaitQueue;
+
+pub mod pty;
+pub mod serial;
+
+/// The device below a terminal.
+pub trait TtyDriver: Send + Sync {
+    /// Sends bytes to the device, after they went through output
+    /// processing.
+    fn write(&self, buf: &[u8]);
+}
+
+pub struct Tty {
+    name: String,
+    driver: Box<dyn TtyDriver>,
+    ldisc: Mutex<LineDiscipline>,
+    /// The session leader that this is the controlling terminal of.
+    session: RwLock<Option<ProcessId>>,
+    /// The process group that receives the signals generated by control
+    /// characters.
+    foreground: RwLock<Option<ProcessId>>,
+    winsize: RwLock<Winsize>,
+    /// Set when the device went away, after which reads return end of file
+    /// and writes fail.
+    hung_up: AtomicBool,
+    /// The tasks that wait for input or for the hangup.
+    readers: WaitQueue,
+}
+
+impl Tty {
+    pub fn new(name: impl Into<String>, driver: impl TtyDriver + 'static) -> Self {
+        Self {
+            name: name.into(),
+            driver: Box::new(driver),
+            ldisc: Mutex::new(LineDiscipline::new()),
+            session: RwLock::new(None),
+            foreground: RwLock::new(None),
+            winsize: RwLock::new(Winsize::default()),
+            hung_up: AtomicBool::new(false),
+            readers: WaitQueue::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The line discipline is also used by the task that handles input, so
+    /// it is only locked with interrupts disabled.
+    fn with_ldisc<R>(&self, f: impl FnOnce(&mut LineDiscipline) -> R) -> R {
+        interrupts::without_interrupts(|| f(&mut self.ldisc.lock()))
+    }
+
+    /// Passes bytes that the device received through the line discipline,
+    /// echoes them and sends the signals that they generate.
+    pub fn receive(&self, bytes: &[u8]) {
+        let mut echo = Vec::new();
+        let signals = self.with_ldisc(|ldisc| {
+            bytes
+                .iter()
+                .filter_map(|&byte| ldisc.receive(byte, &mut echo))
+                .collect::<Vec<_>>()
+        });
+        if !echo.is_empty() {
+            self.driver.write(&echo);
+        }
+        for signal in signals {
+            self.signal_foreground(signal.number());
+        }
+        self.readers.wake_all();
+    }
+
+    /// Reads input, see [`LineDiscipline::read`].
+    ///
+    /// # Errors
+    /// Returns [`ReadError::WouldBlock`] if there is no input yet.
+    pub fn read(&self, buf: &mut [u8]) -> Result<usize, ReadError> {
+        if self.hung_up.load(Relaxed) {
+            return Ok(0);
+        }
+        self.with_ldisc(|ldisc| ldisc.read(buf))
+            .ok_or(ReadError::WouldBlock)
+    }
+
+    /// Writes output through the line discipline to the device.
+    ///
+    /// # Errors
+    /// Returns [`WriteError::WriteFailed`] if the terminal was hung up.
+    pub fn write(&self, buf: &[u8]) -> Result<usize, WriteError> {
+        if self.hung_up.load(Relaxed) {
+            return Err(WriteError::WriteFailed);
+        }
+        let mut out = Vec::with_capacity(buf.len());
+        self.with_ldisc(|ldisc| ldisc.output(buf, &mut out));
+        self.driver.write(&out);
+        Ok(buf.len())
+    }
+
+    /// Returns whether input can be read. Output is written synchronously,
+    /// so writes never block. A terminal that was hung up is ready for
+    /// everything, because reads and writes return immediately.
+    pub fn poll(&self) -> Readiness {
+        if self.hung_up.load(Relaxed) {
+            return Readiness {
+                hangup: true,
+                ..Readiness::READY
+            };
+        }
+        Readiness {
+            readable: self.with_ldisc(|ldisc| ldisc.has_input()),
+            ..Readiness::READY
+        }
+    }
+
+    /// Wakes `waker` when input arrives or the terminal is hung up.
+    pub fn register_waker(&self, waker: &Waker) {
+        self.readers.register(waker);
+    }
+
+    /// Disconnects the terminal from its device and sends `SIGHUP` to the
+    /// foreground process group.
+    pub fn hangup(&self) {
+        if !self.hung_up.swap(true, Relaxed) {
+            self.signal_foreground(SIGHUP);
+            self.readers.wake_all();
+        }
+    }
+
+    fn signal_foreground(&self, signal: c_int) {
+        let Some(pgid) = *self.foreground.read() else {
+            debug!(
+                "{}: no foreground process group for signal {signal}",
+                self.name
+            );
+            return;
+        };
+        for process in process_tree().read().processes.values() {
+            if process.pgid() == pgid {
+                process.send_signal(signal);
+            }
+        }
+    }
+
+    /// Handles the terminal ioctls for the current process.
+    ///
+    /// # Errors
+    /// Returns [`IoctlError::Unsupported`] for requests that are not
+    /// terminal requests, and for requests that need this to be the
+    /// controlling terminal if it isn't.
+    pub fn ioctl(self: &Arc<Self>, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        match request {
+            TCGETS => {
+                let termios = self.with_ldisc(|ldisc| *ldisc.termios());
+                write_arg(arg, termios)?;
+            }
+            TCSETS | TCSETSW | TCSETSF => {
+                // output is written synchronously, so there is never any
+                // output to wait for
+                let termios = read_arg::<Termios>(arg)?;
+                self.with_ldisc(|ldisc| {
+                    if request == TCSETSF {
+                        ldisc.flush_input();
+                    }
+                    ldisc.set_termios(termios);
+                });
+            }
+            TIOCGWINSZ => write_arg(arg, *self.winsize.read())?,
+            TIOCSWINSZ => {
+                let winsize = read_arg::<Winsize>(arg)?;
+                let old = core::mem::replace(&mut *self.winsize.write(), winsize);
+                if old != winsize {
+                    self.signal_foreground(SIGWINCH);
+                }
+            }
+            TIOCSCTTY => self.set_controlling_terminal()?,
+            TIOCGPGRP => {
+                self.check_controlling_terminal()?;
+                let pgid = self.foreground.read().map_or(0, u64::from);
+                write_arg(
+                    arg,
+                    c_int::try_from(pgid).map_err(|_| IoctlError::InvalidArgument)?,
+                )?;
+            }
+            TIOCSPGRP => {
+                self.check_controlling_terminal()?;
+                let pgid = read_arg::<c_int>(arg)?;
+                let pgid = u64::try_from(pgid).map_err(|_| IoctlError::InvalidArgument)?;
+                let pgid = process_tree()
+                    .read()
+                    .processes
+                    .values()
+                    .map(|process| process.pgid())
+                    .find(|id| *id == pgid)
+                    .ok_or(IoctlError::NotPermitted)?;
+                *self.foreground.write() = Some(pgid);
+            }
+            _ => return Err(IoctlError::Unsupported),
+        }
+        Ok(0)
+    }
+
+    /// Makes this the controlling terminal of the current process, which
+    /// becomes the session leader, and puts its process group into the
+    /// foreground.
+    fn set_controlling_terminal(self: &Arc<Self>) -> Result<(), IoctlError> {
+        let process = ExecutionContext::load().current_process();
+        let mut ctty = process.controlling_terminal().write();
+        if let Some(current) = ctty.as_ref() {
+            return if Arc::ptr_eq(current, self) {
+                Ok(())
+            } else {
+                Err(IoctlError::NotPermitted)
+            };
+        }
+
+        let mut session = self.session.write();
+        if let Some(leader) = *session
+            && process_tree().read().processes.contains_key(&leader)
+        {
+            return Err(IoctlError::NotPermitted);
+        }
+        *session = Some(process.pid());
+        *self.foreground.write() = Some(process.pgid());
+        *ctty = Some(self.clone());
+        Ok(())
+    }
+
+    fn check_controlling_terminal(self: &Arc<Self>) -> Result<(), IoctlError> {
+        let process = ExecutionContext::load().current_process();
+        match process.controlling_terminal().read().as_ref() {
+            Some(ctty) if Arc::ptr_eq(ctty, self) => Ok(()),
+            _ => Err(IoctlError::Unsupported),
+        }
+    }
+}
+
+/// A device file that is backed by a [`Tty`].
+pub struct TtyFile {
+    tty: Arc<Tty>,
+}
+
+impl TtyFile {
+    #[must_use]
+    pub fn new(tty: Arc<Tty>) -> Self {
+        Self { tty }
+    }
+}
+
+impl DevFile for TtyFile {
+    fn read(&mut self, buf: &mut [u8], _: usize) -> Result<usize, ReadError> {
+        self.tty.read(buf)
+    }
+
+    fn write(&mut self, buf: &[u8], _: usize) -> Result<usize, WriteError> {
+        self.tty.write(buf)
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.file_type = FileType::CharDevice;
+        stat.size = 0;
+        Ok(())
+    }
+
+    fn ioctl(&mut self, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        self.tty.ioctl(request, arg)
+    }
+
+    fn poll(&mut self) -> Readiness {
+        self.tty.poll()
+    }
+
+    fn register_waker(&mut self, waker: &Waker) {
+        self.tty.register_waker(waker);
+    }
+}
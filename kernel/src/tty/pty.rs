@@ -0,0 +1,159 @@
+//! Pseudo-terminals. Opening `/dev/ptmx` allocates a pair of a master and a
+//! slave. The slave is a [`Tty`] that appears as `/dev/pts/N`. Whatever is
+//! written to the master is input for the slave's line discipline, and the
+//! output of the slave can be read from the master.
+
+use alloc::collections::{BTreeSet, VecDeque};
+use alloc::format;
+use alloc::sync::Arc;
+use core::ffi::{c_int, c_uint};
+use core::task::Waker;
+
+use kernel_abi::{TIOCGPTN, TIOCSPTLCK};
+use kernel_devfs::DevFile;
+use kernel_syscall::ioctl::{read_arg, write_arg};
+use kernel_vfs::path::AbsoluteOwnedPath;
+use kernel_vfs::{
+    FileType, IoctlError, OpenError, ReadError, Readiness, Stat, StatError, WriteError,
+};
+use log::warn;
+use spin::Mutex;
+
+use crate::file::devpts::devpts;
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::tty::{Tty, TtyDriver, TtyFile};
+
+/// Writes to the slave don't block yet, so output that the master doesn't
+/// read is dropped once this much is buffered.
+const MAX_OUTPUT: usize = 64 * 1024;
+
+/// The numbers of the pseudo-terminals that are in use.
+static INDICES: Mutex<BTreeSet<u32>> = Mutex::new(BTreeSet::new());
+
+/// Allocates a new pseudo-terminal and registers its slave in devpts.
+///
+/// # Errors
+/// Returns an error if the slave can't be registered.
+pub fn open_master() -> Result<PtyMaster, OpenError> {
+    let index = {
+        let mut indices = INDICES.lock();
+        let index = (0..).find(|i| !indices.contains(i)).unwrap();
+        indices.insert(index);
+        index
+    };
+
+    let output = Arc::new(Output::default());
+    let tty = Arc::new(Tty::new(
+        format!("pts/{index}"),
+        PtyDriver {
+            output: output.clone(),
+        },
+    ));
+    let master = PtyMaster { index, tty, output };
+
+    let slave = master.tty.clone();
+    devpts()
+        .write()
+        .register_file(master.slave_path().as_ref(), move || {
+            Ok(TtyFile::new(slave.clone()))
+        })
+        .map_err(|_| OpenError::NotFound)?;
+    Ok(master)
+}
+
+/// The output of the slave, which the master reads.
+#[derive(Default)]
+struct Output {
+    bytes: Mutex<VecDeque<u8>>,
+    /// The tasks that wait until the master can be read.
+    readers: WaitQueue,
+}
+
+struct PtyDriver {
+    output: Arc<Output>,
+}
+
+impl TtyDriver for PtyDriver {
+    fn write(&self, buf: &[u8]) {
+        {
+            let mut bytes = self.output.bytes.lock();
+            let len = buf.len().min(MAX_OUTPUT - bytes.len());
+            bytes.extend(&buf[..len]);
+        }
+        self.output.readers.wake_all();
+    }
+}
+
+/// The master side of a pseudo-terminal. Closing it hangs up the slave.
+pub struct PtyMaster {
+    index: u32,
+    tty: Arc<Tty>,
+    output: Arc<Output>,
+}
+
+impl PtyMaster {
+    /// The path of the slave, relative to devpts.
+    fn slave_path(&self) -> AbsoluteOwnedPath {
+        AbsoluteOwnedPath::try_from(format!("/{}", self.index).as_str()).unwrap()
+    }
+}
+
+impl DevFile for PtyMaster {
+    fn read(&mut self, buf: &mut [u8], _: usize) -> Result<usize, ReadError> {
+        let mut output = self.output.bytes.lock();
+        if output.is_empty() {
+            return Err(ReadError::WouldBlock);
+        }
+        let len = buf.len().min(output.len());
+        for (dst, src) in buf.iter_mut().zip(output.drain(..len)) {
+            *dst = src;
+        }
+        Ok(len)
+    }
+
+    fn write(&mut self, buf: &[u8], _: usize) -> Result<usize, WriteError> {
+        self.tty.receive(buf);
+        Ok(buf.len())
+    }
+
+    fn stat(&mut self, stat: &mut Stat) -> Result<(), StatError> {
+        stat.file_type = FileType::CharDevice;
+        stat.size = 0;
+        Ok(())
+    }
+
+    /// Besides the pseudo-terminal requests, the master accepts the requests
+    /// of the slave, for example to set the window size.
+    fn ioctl(&mut self, request: u64, arg: usize) -> Result<usize, IoctlError> {
+        match request {
+            TIOCGPTN => write_arg::<c_uint>(arg, self.index)?,
+            // slaves are never locked, so unlocking them has no effect
+            TIOCSPTLCK => {
+                read_arg::<c_int>(arg)?;
+            }
+            _ => return self.tty.ioctl(request, arg),
+        }
+        Ok(0)
+    }
+
+    fn poll(&mut self) -> Readiness {
+        Readiness {
+            readable: !self.output.bytes.lock().is_empty(),
+            ..Readiness::READY
+        }
+    }
+
+    fn register_waker(&mut self, waker: &Waker) {
+        self.output.readers.register(waker);
+    }
+}
+
+impl Drop for PtyMaster {
+    fn drop(&mut self) {
+        self.tty.hangup();
+        if let Err(e) = devpts().write().unregister(self.slave_path().as_ref()) {
+            warn!("failed to unregister {}: {e}", self.tty.name());
+        }
+        INDICES.lock().remove(&self.index);
+    }
+}
@@ -0,0 +1,107 @@
+//! The terminal on the first serial port, which QEMU connects to stdio. The
+//! UART raises ISA IRQ 4 when it received data. The interrupt handler only
+//! moves the data into a buffer and wakes a kernel task, which passes it on
+//! to the line discipline, which may allocate.
+
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::ffi::c_void;
+use core::ptr;
+
+use conquer_once::spin::Lazy;
+use kernel_tty::InputBuffer;
+use log::info;
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::apic;
+use crate::arch::idt::InterruptIndex;
+use crate::mcore::mtask::process::Process;
+use crate::mcore::mtask::scheduler::run_queue;
+use crate::mcore::mtask::task::Task;
+use crate::mcore::mtask::wait::WaitQueue;
+use crate::serial::{try_read_byte, write_bytes};
+use crate::tty::{Tty, TtyDriver};
+
+/// The ISA interrupt line of the first serial port.
+const IRQ: u8 = 4;
+
+static SERIAL_TTY: Lazy<Arc<Tty>> = Lazy::new(|| Arc::new(Tty::new("ttyS0", SerialDriver)));
+
+/// The bytes that the interrupt handler received, but the input task hasn't
+/// processed yet.
+static INPUT: Mutex<InputBuffer<256>> = Mutex::new(InputBuffer::new());
+
+/// The input task, which waits for [`INPUT`] to be filled.
+static INPUT_TASK: WaitQueue = WaitQueue::new();
+
+/// The terminal on the first serial port.
+pub fn tty() -> &'static Arc<Tty> {
+    &SERIAL_TTY
+}
+
+/// Starts the input task and enables the interrupt of the serial port.
+pub fn init() {
+    let task = Task::create_new(Process::root(), input_task, ptr::null_mut())
+        .expect("should be able to create serial input task");
+    info!("serial input task created with id {}", task.id());
+    run_queue::enqueue(Box::pin(task));
+
+    apic::enable_isa_irq(IRQ, InterruptIndex::Serial.as_u8());
+}
+
+/// Called by the interrupt handler. Reading the received data also clears
+/// the interrupt in the UART.
+pub fn handle_interrupt() {
+    let received = {
+        let mut input = INPUT.lock();
+        let mut received = false;
+        while let Some(byte) = try_read_byte() {
+            // if the input task can't keep up, input is dropped
+            let _ = input.push(byte);
+            received = true;
+        }
+        received
+    };
+    if received {
+        INPUT_TASK.wake_all();
+    }
+}
+
+/// Passes the received input to the line discipline. This also reads
+/// from the UART directly, so that input can be read on CPUs that don't
+/// receive the interrupt.
+pub fn poll_input() {
+    let mut buf = [0; 256];
+    let len = interrupts::without_interrupts(|| {
+        handle_interrupt();
+        let mut input = INPUT.lock();
+        let mut len = 0;
+        while let Some(byte) = input.pop() {
+            buf[len] = byte;
+            len += 1;
+        }
+        len
+    });
+    if len > 0 {
+        tty().receive(&buf[..len]);
+    }
+}
+
+extern "C" fn input_task(_: *mut c_void) {
+    loop {
+        poll_input();
+        // the task isn't interrupted by signals
+        let _ = interrupts::without_interrupts(|| {
+            INPUT_TASK.wait_until(None, || !INPUT.lock().is_empty())
+        });
+    }
+}
+
+struct SerialDriver;
+
+impl TtyDriver for SerialDriver {
+    fn write(&self, buf: &[u8]) {
+        write_bytes(buf);
+    }
+}
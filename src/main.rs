@@ -5,6 +5,7 @@ static BOOTABLE_ISO: &str = env!("BOOTABLE_ISO");
 static OVMF_CODE: &str = env!("OVMF_X86_64_CODE");
 static OVMF_VARS: &str = env!("OVMF_X86_64_VARS");
 static DISK_IMAGE: &str = env!("DISK_IMAGE");
+static INITRAMFS: &str = env!("INITRAMFS");
 
 #[derive(Parser)]
 struct Args {
@@ -25,12 +26,29 @@ struct Args {
         default_value = "4G"
     )]
     mem: String,
+    #[arg(
+        long,
+        help = "Don't attach the disk image, the system boots from the initramfs"
+    )]
+    no_disk: bool,
+    #[arg(
+        long,
+        help = "Don't attach a network card with QEMU's user mode network"
+    )]
+    no_network: bool,
+    #[arg(
+        long,
+        help = "Forward a port of the host to the guest ('tcp::5555-:7' etc.)",
+        default_value = "tcp::5555-:7"
+    )]
+    forward: Vec<String>,
 }
 
 fn main() {
     println!("KERNEL_BINARY: {KERNEL_BINARY}");
     println!("BOOTABLE_ISO: {BOOTABLE_ISO}");
     println!("DISK_IMAGE: {DISK_IMAGE}");
+    println!("INITRAMFS: {INITRAMFS}");
 
     let args = Args::parse();
 
@@ -104,12 +122,26 @@ continue"
     cmd.arg("-smp");
     cmd.arg(args.smp.to_string());
 
-    cmd.arg("-drive");
-    cmd.arg(format!(
-        "id=virtio-disk0,file={DISK_IMAGE},format=raw,if=none"
-    ));
-    cmd.arg("-device");
-    cmd.arg("virtio-blk-pci,drive=virtio-disk0");
+    if !args.no_disk {
+        cmd.arg("-drive");
+        cmd.arg(format!(
+            "id=virtio-disk0,file={DISK_IMAGE},format=raw,if=none"
+        ));
+        cmd.arg("-device");
+        cmd.arg("virtio-blk-pci,drive=virtio-disk0");
+    }
+
+    if !args.no_network {
+        // the user mode network needs no setup on the host
+        let mut netdev = String::from("user,id=net0");
+        for forward in &args.forward {
+            netdev.push_str(&format!(",hostfwd={forward}"));
+        }
+        cmd.arg("-netdev");
+        cmd.arg(netdev);
+        cmd.arg("-device");
+        cmd.arg("virtio-net-pci,netdev=net0");
+    }
 
     #[cfg(all(target_arch = "x86_64", target_os = "linux"))]
     {
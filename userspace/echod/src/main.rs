@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+use minilib::{
+    AF_INET, SO_REUSEADDR, SOCK_STREAM, SOL_SOCKET, SockAddrIn, accept, bind, close, exit, listen,
+    recvfrom, sendto, setsockopt, socket, write,
+};
+
+/// The port of the echo protocol (RFC 862).
+const PORT: u16 = 7;
+
+/// Echoes everything that is sent to it over TCP, one connection at a time.
+#[unsafe(no_mangle)]
+pub extern "C" fn _start() {
+    let fd = socket(AF_INET, SOCK_STREAM, 0);
+    if fd < 0 {
+        fail(b"echod: socket failed\n");
+    }
+    setsockopt(fd, SOL_SOCKET, SO_REUSEADDR, 1);
+    if bind(fd, &SockAddrIn::new([0; 4], PORT)) < 0 {
+        fail(b"echod: bind failed\n");
+    }
+    if listen(fd, 4) < 0 {
+        fail(b"echod: listen failed\n");
+    }
+    write(1, b"echod: listening on port 7\n");
+
+    loop {
+        let connection = accept(fd, None);
+        if connection < 0 {
+            continue;
+        }
+        echo(connection);
+        close(connection);
+    }
+}
+
+fn echo(fd: i32) {
+    let mut buf = [0; 1024];
+    loop {
+        let len = recvfrom(fd, &mut buf, 0, None);
+        if len <= 0 {
+            return;
+        }
+        let mut data = &buf[..len as usize];
+        while !data.is_empty() {
+            let sent = sendto(fd, data, 0, None);
+            if sent <= 0 {
+                return;
+            }
+            data = &data[sent as usize..];
+        }
+    }
+}
+
+fn fail(message: &[u8]) -> ! {
+    write(2, message);
+    exit(1);
+}
+
+#[cfg(not(test))]
+#[panic_handler]
+fn panic(_info: &::core::panic::PanicInfo) -> ! {
+    loop {}
+}
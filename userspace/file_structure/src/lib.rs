@@ -1,8 +1,20 @@
 pub const STRUCTURE: Dir<'static> = Dir::new(
     "",
     &[
-        Dir::new("bin", &[], &[File::new("init", Kind::Executable)]),
-        Dir::new("dev", &[Dir::new("fd", &[], &[])], &[]),
+        Dir::new(
+            "bin",
+            &[],
+            &[
+                File::new("echod", Kind::Executable),
+                File::new("init", Kind::Executable),
+                File::new("nettest", Kind::Executable),
+            ],
+        ),
+        Dir::new("dev", &[], &[]),
+        Dir::new("proc", &[], &[]),
+        Dir::new("run", &[], &[]),
+        Dir::new("sys", &[], &[]),
+        Dir::new("tmp", &[], &[]),
         Dir::new("var", &[Dir::new("tmp", &[], &[])], &[]),
     ],
     &[],
@@ -3,9 +3,19 @@
 use core::arch::asm;
 use core::arch::x86_64::_mm_pause;
 use core::ffi::c_int;
+use core::sync::atomic::AtomicU32;
 
+mod netdb;
+mod sync;
+mod thread;
+
+pub use netdb::{EAI_AGAIN, EAI_FAIL, EAI_NONAME, EAI_SYSTEM, getaddrinfo};
+pub use sync::{Condvar, Mutex};
+pub use thread::{Thread, exit_thread};
+
+/// Exits all threads of the process.
 pub fn exit(code: i32) -> ! {
-    syscall1(1, code as usize);
+    syscall1(69, code as usize);
     loop {
         unsafe {
             _mm_pause();
@@ -13,6 +23,13 @@ pub fn exit(code: i32) -> ! {
     }
 }
 
+const O_RDONLY: c_int = 1 << 16;
+
+/// Opens the file at `path` for reading.
+pub fn open(path: &[u8]) -> c_int {
+    syscall4(3, path.as_ptr() as usize, path.len(), O_RDONLY as usize, 0) as i32
+}
+
 pub fn read(fd: c_int, buf: &mut [u8]) -> c_int {
     syscall3(36, fd as usize, buf.as_mut_ptr() as usize, buf.len()) as i32
 }
@@ -21,6 +38,375 @@ pub fn write(fd: c_int, buf: &[u8]) -> c_int {
     syscall3(37, fd as usize, buf.as_ptr() as usize, buf.len()) as i32
 }
 
+pub fn ioctl(fd: c_int, request: u64, arg: usize) -> c_int {
+    syscall3(44, fd as usize, request as usize, arg) as i32
+}
+
+pub fn getrandom(buf: &mut [u8], flags: u32) -> isize {
+    syscall3(45, buf.as_mut_ptr() as usize, buf.len(), flags as usize) as isize
+}
+
+pub fn syslog(action: c_int, buf: &mut [u8]) -> isize {
+    syscall3(46, action as usize, buf.as_mut_ptr() as usize, buf.len()) as isize
+}
+
+/// `syslog` with `SYSLOG_ACTION_CONSOLE_LEVEL`, which takes the level in
+/// place of the buffer length.
+pub fn syslog_console_level(level: usize) -> isize {
+    syscall3(46, 8, 0, level) as isize
+}
+
+pub fn close(fd: c_int) -> c_int {
+    syscall1(40, fd as usize) as i32
+}
+
+const FUTEX_WAIT: c_int = 0;
+const FUTEX_WAKE: c_int = 1;
+const FUTEX_PRIVATE_FLAG: c_int = 128;
+
+/// Waits until `word` is woken with [`futex_wake`], unless it doesn't hold
+/// `expected`.
+pub fn futex_wait(word: &AtomicU32, expected: u32) -> c_int {
+    syscall4(
+        67,
+        word.as_ptr() as usize,
+        (FUTEX_WAIT | FUTEX_PRIVATE_FLAG) as usize,
+        expected as usize,
+        0,
+    ) as i32
+}
+
+/// Wakes up to `count` threads that wait on `word`, and returns how many
+/// were woken.
+pub fn futex_wake(word: &AtomicU32, count: i32) -> c_int {
+    syscall3(
+        67,
+        word.as_ptr() as usize,
+        (FUTEX_WAKE | FUTEX_PRIVATE_FLAG) as usize,
+        count as usize,
+    ) as i32
+}
+
+/// Restricts the thread `tid`, or the calling thread if `tid` is zero, to
+/// the CPUs in `mask`, one bit per CPU.
+pub fn sched_setaffinity(tid: c_int, mask: u64) -> c_int {
+    syscall3(70, tid as usize, size_of::<u64>(), &raw const mask as usize) as i32
+}
+
+/// Writes the CPUs that the thread `tid`, or the calling thread if `tid` is
+/// zero, may run on to `mask`, one bit per CPU.
+pub fn sched_getaffinity(tid: c_int, mask: &mut u64) -> c_int {
+    syscall3(
+        71,
+        tid as usize,
+        size_of::<u64>(),
+        mask as *mut u64 as usize,
+    ) as i32
+}
+
+pub const SCHED_OTHER: c_int = 0;
+pub const SCHED_FIFO: c_int = 1;
+pub const SCHED_RR: c_int = 2;
+pub const SCHED_IDLE: c_int = 5;
+const PRIO_PROCESS: c_int = 0;
+
+/// Lets other threads run before the calling thread continues.
+pub fn sched_yield() -> c_int {
+    syscall0(72) as i32
+}
+
+/// Sets the scheduling policy of the thread `tid`, or of the calling thread
+/// if `tid` is zero. `priority` is from 1 to 99 for [`SCHED_FIFO`] and
+/// [`SCHED_RR`], and zero otherwise. Only the kernel may use the real-time
+/// policies, so switching to them fails with `EPERM`.
+pub fn sched_setscheduler(tid: c_int, policy: c_int, priority: c_int) -> c_int {
+    syscall3(
+        73,
+        tid as usize,
+        policy as usize,
+        &raw const priority as usize,
+    ) as i32
+}
+
+/// The scheduling policy of the thread `tid`, or of the calling thread if
+/// `tid` is zero.
+pub fn sched_getscheduler(tid: c_int) -> c_int {
+    syscall1(74, tid as usize) as i32
+}
+
+/// Sets the nice value of the thread `tid`, or of the calling thread if
+/// `tid` is zero. Negative nice values fail with `EPERM`.
+pub fn setpriority(tid: c_int, nice: c_int) -> c_int {
+    syscall3(75, PRIO_PROCESS as usize, tid as usize, nice as usize) as i32
+}
+
+/// Returns `20 - nice` for the nice value of the thread `tid`, or of the
+/// calling thread if `tid` is zero, like the system call does, so that it
+/// can't be mistaken for a negative errno.
+pub fn getpriority(tid: c_int) -> c_int {
+    syscall2(76, PRIO_PROCESS as usize, tid as usize) as i32
+}
+
+/// Adds `inc` to the nice value of the calling thread, and returns the new
+/// nice value.
+pub fn nice(inc: c_int) -> c_int {
+    setpriority(0, 20 - getpriority(0) + inc);
+    20 - getpriority(0)
+}
+
+pub const AF_UNIX: c_int = 1;
+pub const AF_INET: c_int = 2;
+pub const SOCK_STREAM: c_int = 1;
+pub const SOCK_DGRAM: c_int = 2;
+pub const SOCK_NONBLOCK: c_int = 0o4000;
+pub const SOL_SOCKET: c_int = 1;
+pub const SO_REUSEADDR: c_int = 2;
+pub const SHUT_WR: c_int = 1;
+
+/// An IPv4 address and port, both in network byte order.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SockAddrIn {
+    pub sin_family: u16,
+    pub sin_port: u16,
+    pub sin_addr: u32,
+    pub sin_zero: [u8; 8],
+}
+
+impl SockAddrIn {
+    pub fn new(addr: [u8; 4], port: u16) -> Self {
+        Self {
+            sin_family: AF_INET as u16,
+            sin_port: port.to_be(),
+            sin_addr: u32::from_be_bytes(addr).to_be(),
+            sin_zero: [0; 8],
+        }
+    }
+
+    pub fn addr(&self) -> [u8; 4] {
+        u32::from_be(self.sin_addr).to_be_bytes()
+    }
+
+    pub fn port(&self) -> u16 {
+        u16::from_be(self.sin_port)
+    }
+}
+
+const SOCK_ADDR_IN_LEN: usize = size_of::<SockAddrIn>();
+
+pub fn socket(domain: c_int, typ: c_int, protocol: c_int) -> c_int {
+    syscall3(47, domain as usize, typ as usize, protocol as usize) as i32
+}
+
+pub fn bind(fd: c_int, addr: &SockAddrIn) -> c_int {
+    syscall3(48, fd as usize, addr as *const _ as usize, SOCK_ADDR_IN_LEN) as i32
+}
+
+pub fn listen(fd: c_int, backlog: c_int) -> c_int {
+    syscall2(49, fd as usize, backlog as usize) as i32
+}
+
+/// Accepts a connection and writes the address of the peer into `addr`.
+pub fn accept(fd: c_int, addr: Option<&mut SockAddrIn>) -> c_int {
+    let mut len = SOCK_ADDR_IN_LEN as u32;
+    let addr = addr.map_or(0, |addr| addr as *mut _ as usize);
+    syscall3(50, fd as usize, addr, &raw mut len as usize) as i32
+}
+
+pub fn connect(fd: c_int, addr: &SockAddrIn) -> c_int {
+    syscall3(51, fd as usize, addr as *const _ as usize, SOCK_ADDR_IN_LEN) as i32
+}
+
+pub fn sendto(fd: c_int, buf: &[u8], flags: c_int, addr: Option<&SockAddrIn>) -> isize {
+    let (addr, len) = addr.map_or((0, 0), |addr| (addr as *const _ as usize, SOCK_ADDR_IN_LEN));
+    syscall6(
+        52,
+        fd as usize,
+        buf.as_ptr() as usize,
+        buf.len(),
+        flags as usize,
+        addr,
+        len,
+    ) as isize
+}
+
+/// Receives into `buf` and writes the address of the sender into `addr`.
+pub fn recvfrom(fd: c_int, buf: &mut [u8], flags: c_int, addr: Option<&mut SockAddrIn>) -> isize {
+    let mut len = SOCK_ADDR_IN_LEN as u32;
+    let addr = addr.map_or(0, |addr| addr as *mut _ as usize);
+    syscall6(
+        53,
+        fd as usize,
+        buf.as_mut_ptr() as usize,
+        buf.len(),
+        flags as usize,
+        addr,
+        &raw mut len as usize,
+    ) as isize
+}
+
+pub fn shutdown(fd: c_int, how: c_int) -> c_int {
+    syscall2(54, fd as usize, how as usize) as i32
+}
+
+pub fn setsockopt(fd: c_int, level: c_int, name: c_int, value: c_int) -> c_int {
+    syscall5(
+        55,
+        fd as usize,
+        level as usize,
+        name as usize,
+        &raw const value as usize,
+        size_of::<c_int>(),
+    ) as i32
+}
+
+pub fn socketpair(domain: c_int, typ: c_int, protocol: c_int, fds: &mut [c_int; 2]) -> c_int {
+    syscall4(
+        59,
+        domain as usize,
+        typ as usize,
+        protocol as usize,
+        fds.as_mut_ptr() as usize,
+    ) as i32
+}
+
+#[repr(C)]
+struct IoVec {
+    base: *mut u8,
+    len: usize,
+}
+
+#[repr(C)]
+struct MsgHdr {
+    name: *mut u8,
+    namelen: u32,
+    iov: *mut IoVec,
+    iovlen: usize,
+    control: *mut u8,
+    controllen: usize,
+    flags: c_int,
+}
+
+/// A control message that carries a single file descriptor.
+#[repr(C)]
+#[derive(Default)]
+struct RightsMessage {
+    len: usize,
+    level: c_int,
+    typ: c_int,
+    fd: c_int,
+    _pad: u32,
+}
+
+const SCM_RIGHTS: c_int = 1;
+
+/// Sends `buf` together with the file descriptor `fd_to_send` over the
+/// Unix socket `fd`.
+pub fn send_fd(fd: c_int, buf: &[u8], fd_to_send: c_int) -> isize {
+    let mut iov = IoVec {
+        base: buf.as_ptr() as *mut u8,
+        len: buf.len(),
+    };
+    let mut control = RightsMessage {
+        len: 20,
+        level: SOL_SOCKET,
+        typ: SCM_RIGHTS,
+        fd: fd_to_send,
+        _pad: 0,
+    };
+    let msg = MsgHdr {
+        name: core::ptr::null_mut(),
+        namelen: 0,
+        iov: &raw mut iov,
+        iovlen: 1,
+        control: &raw mut control as *mut u8,
+        controllen: size_of::<RightsMessage>(),
+        flags: 0,
+    };
+    syscall3(60, fd as usize, &raw const msg as usize, 0) as isize
+}
+
+/// Receives into `buf` from the Unix socket `fd`, and returns the length
+/// and the file descriptor that came with the data, if any.
+pub fn recv_fd(fd: c_int, buf: &mut [u8]) -> (isize, Option<c_int>) {
+    let mut iov = IoVec {
+        base: buf.as_mut_ptr(),
+        len: buf.len(),
+    };
+    let mut control = RightsMessage::default();
+    let mut msg = MsgHdr {
+        name: core::ptr::null_mut(),
+        namelen: 0,
+        iov: &raw mut iov,
+        iovlen: 1,
+        control: &raw mut control as *mut u8,
+        controllen: size_of::<RightsMessage>(),
+        flags: 0,
+    };
+    let len = syscall3(61, fd as usize, &raw mut msg as usize, 0) as isize;
+    let received =
+        (len >= 0 && msg.controllen >= 20 && control.typ == SCM_RIGHTS).then_some(control.fd);
+    (len, received)
+}
+
+pub const POLLIN: i16 = 0x001;
+pub const POLLOUT: i16 = 0x004;
+pub const POLLERR: i16 = 0x008;
+pub const POLLHUP: i16 = 0x010;
+
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PollFd {
+    pub fd: c_int,
+    pub events: i16,
+    pub revents: i16,
+}
+
+/// Waits until one of `fds` has events, or until `timeout` milliseconds
+/// passed. A negative timeout waits forever.
+pub fn poll(fds: &mut [PollFd], timeout: c_int) -> c_int {
+    syscall3(24, fds.as_mut_ptr() as usize, fds.len(), timeout as usize) as i32
+}
+
+pub const EPOLL_CTL_ADD: c_int = 1;
+pub const EPOLL_CTL_DEL: c_int = 2;
+pub const EPOLL_CTL_MOD: c_int = 3;
+pub const EPOLLIN: u32 = 0x001;
+pub const EPOLLOUT: u32 = 0x004;
+pub const EPOLLONESHOT: u32 = 1 << 30;
+pub const EPOLLET: u32 = 1 << 31;
+
+#[repr(C, packed)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct EpollEvent {
+    pub events: u32,
+    pub data: u64,
+}
+
+pub fn epoll_create1(flags: c_int) -> c_int {
+    syscall1(64, flags as usize) as i32
+}
+
+pub fn epoll_ctl(epfd: c_int, op: c_int, fd: c_int, event: &EpollEvent) -> c_int {
+    syscall4(
+        65,
+        epfd as usize,
+        op as usize,
+        fd as usize,
+        event as *const EpollEvent as usize,
+    ) as i32
+}
+
+pub fn epoll_wait(epfd: c_int, events: &mut [EpollEvent], timeout: c_int) -> c_int {
+    syscall4(
+        66,
+        epfd as usize,
+        events.as_mut_ptr() as usize,
+        events.len(),
+        timeout as usize,
+    ) as i32
+}
+
 pub fn syscall0(n: usize) -> usize {
     let mut result;
     unsafe {
@@ -88,3 +474,69 @@ pub fn syscall3(n: usize, arg1: usize, arg2: usize, arg3: usize) -> usize {
     }
     result
 }
+
+// with more arguments, the registers that the compiler picks for the
+// operands would be overwritten before they are read, so the arguments
+// are passed in their registers directly
+pub fn syscall4(n: usize, arg1: usize, arg2: usize, arg3: usize, arg4: usize) -> usize {
+    let result;
+    unsafe {
+        asm!(
+        "int 0x80",
+        inlateout("rax") n => result,
+        in("rdi") arg1,
+        in("rsi") arg2,
+        in("rdx") arg3,
+        in("rcx") arg4,
+        );
+    }
+    result
+}
+
+pub fn syscall5(
+    n: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+    arg5: usize,
+) -> usize {
+    let result;
+    unsafe {
+        asm!(
+        "int 0x80",
+        inlateout("rax") n => result,
+        in("rdi") arg1,
+        in("rsi") arg2,
+        in("rdx") arg3,
+        in("rcx") arg4,
+        in("r8") arg5,
+        );
+    }
+    result
+}
+
+pub fn syscall6(
+    n: usize,
+    arg1: usize,
+    arg2: usize,
+    arg3: usize,
+    arg4: usize,
+    arg5: usize,
+    arg6: usize,
+) -> usize {
+    let result;
+    unsafe {
+        asm!(
+        "int 0x80",
+        inlateout("rax") n => result,
+        in("rdi") arg1,
+        in("rsi") arg2,
+        in("rdx") arg3,
+        in("rcx") arg4,
+        in("r8") arg5,
+        in("r9") arg6,
+        );
+    }
+    result
+}
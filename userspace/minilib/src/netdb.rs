@@ -0,0 +1,276 @@
+//! A DNS stub resolver, which asks the DNS servers in
+//! `/proc/net/resolv.conf` for the IPv4 addresses of a name.
+
+use core::ffi::c_int;
+
+use crate::{
+    AF_INET, SOCK_DGRAM, SOCK_NONBLOCK, SockAddrIn, close, connect, getrandom, open, read,
+    recvfrom, sendto, socket,
+};
+
+/// The name is unknown.
+pub const EAI_NONAME: c_int = -2;
+/// The DNS servers didn't answer.
+pub const EAI_AGAIN: c_int = -3;
+/// The DNS servers failed to answer, or there are none.
+pub const EAI_FAIL: c_int = -4;
+/// A syscall failed.
+pub const EAI_SYSTEM: c_int = -11;
+
+const RESOLV_CONF: &[u8] = b"/proc/net/resolv.conf";
+const MAX_SERVERS: usize = 3;
+/// How many addresses of an answer are kept.
+const MAX_ADDRESSES: usize = 16;
+const DNS_PORT: u16 = 53;
+/// The largest message over UDP.
+const MAX_MESSAGE_LEN: usize = 512;
+const MAX_NAME_LEN: usize = 253;
+const MAX_LABEL_LEN: usize = 63;
+const HEADER_LEN: usize = 12;
+/// How often each server is asked before the next one is tried.
+const ATTEMPTS: usize = 2;
+/// How long an answer is waited for, in hundredths of a second.
+const TIMEOUT: u64 = 200;
+
+const TYPE_A: u16 = 1;
+const CLASS_IN: u16 = 1;
+const FLAG_RESPONSE: u16 = 1 << 15;
+const FLAG_RECURSION_DESIRED: u16 = 1 << 8;
+const RCODE_NAME_ERROR: u16 = 3;
+
+/// Resolves `node` to IPv4 addresses, which are written into `addresses`
+/// together with `port`. `node` is either a dotted address, `localhost` or
+/// a name that is looked up through DNS.
+///
+/// Returns the number of addresses, or one of the `EAI_*` errors.
+pub fn getaddrinfo(node: &str, port: u16, addresses: &mut [SockAddrIn]) -> c_int {
+    if let Some(addr) = parse_ipv4(node) {
+        return fill(&[addr], port, addresses);
+    }
+    if node.eq_ignore_ascii_case("localhost") {
+        return fill(&[[127, 0, 0, 1]], port, addresses);
+    }
+
+    let mut query = [0; MAX_MESSAGE_LEN];
+    let Some(query_len) = write_query(node, &mut query) else {
+        return EAI_NONAME;
+    };
+    let mut servers = [[0; 4]; MAX_SERVERS];
+    let server_count = read_servers(&mut servers);
+    if server_count == 0 {
+        return EAI_FAIL;
+    }
+
+    let mut result = EAI_FAIL;
+    for server in &servers[..server_count] {
+        for _ in 0..ATTEMPTS {
+            let mut id = [0; 2];
+            getrandom(&mut id, 0);
+            query[..2].copy_from_slice(&id);
+
+            let mut answer = [0; MAX_MESSAGE_LEN];
+            let answer_len = match exchange(*server, &query[..query_len], &mut answer) {
+                Ok(len) => len,
+                Err(e) => {
+                    result = e;
+                    continue;
+                }
+            };
+            let mut found = [[0; 4]; MAX_ADDRESSES];
+            match parse_answer(&answer[..answer_len], id, &mut found) {
+                Ok(0) => return EAI_NONAME,
+                Ok(count) => return fill(&found[..count], port, addresses),
+                Err(e) if e == EAI_NONAME => return e,
+                Err(e) => result = e,
+            }
+        }
+    }
+    result
+}
+
+fn fill(found: &[[u8; 4]], port: u16, addresses: &mut [SockAddrIn]) -> c_int {
+    let count = found.len().min(addresses.len());
+    for (address, addr) in addresses.iter_mut().zip(found) {
+        *address = SockAddrIn::new(*addr, port);
+    }
+    count as c_int
+}
+
+fn parse_ipv4(node: &str) -> Option<[u8; 4]> {
+    let mut addr = [0; 4];
+    let mut parts = node.split('.');
+    for byte in &mut addr {
+        *byte = parts.next()?.parse().ok()?;
+    }
+    parts.next().is_none().then_some(addr)
+}
+
+/// Reads the addresses of the `nameserver` lines of `/proc/net/resolv.conf`
+/// and returns how many there are.
+fn read_servers(servers: &mut [[u8; 4]; MAX_SERVERS]) -> usize {
+    let fd = open(RESOLV_CONF);
+    if fd < 0 {
+        return 0;
+    }
+    let mut buf = [0; 512];
+    let len = read(fd, &mut buf);
+    close(fd);
+    let Ok(content) = str::from_utf8(&buf[..len.max(0) as usize]) else {
+        return 0;
+    };
+
+    let mut count = 0;
+    for line in content.lines() {
+        if count == MAX_SERVERS {
+            break;
+        }
+        if let Some(address) = line.strip_prefix("nameserver")
+            && let Some(address) = parse_ipv4(address.trim())
+        {
+            servers[count] = address;
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Writes a query for the A records of `name` with a zero id into `buf`,
+/// and returns its length, or `None` if `name` isn't a valid name.
+fn write_query(name: &str, buf: &mut [u8; MAX_MESSAGE_LEN]) -> Option<usize> {
+    let name = name.strip_suffix('.').unwrap_or(name);
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return None;
+    }
+
+    buf[..HEADER_LEN].fill(0);
+    buf[2..4].copy_from_slice(&FLAG_RECURSION_DESIRED.to_be_bytes());
+    // one question
+    buf[4..6].copy_from_slice(&1_u16.to_be_bytes());
+
+    let mut len = HEADER_LEN;
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > MAX_LABEL_LEN {
+            return None;
+        }
+        buf[len] = label.len() as u8;
+        buf[len + 1..len + 1 + label.len()].copy_from_slice(label.as_bytes());
+        len += 1 + label.len();
+    }
+    buf[len] = 0;
+    buf[len + 1..len + 3].copy_from_slice(&TYPE_A.to_be_bytes());
+    buf[len + 3..len + 5].copy_from_slice(&CLASS_IN.to_be_bytes());
+    Some(len + 5)
+}
+
+/// Sends `query` to `server` and waits for an answer until the timeout.
+fn exchange(server: [u8; 4], query: &[u8], answer: &mut [u8]) -> Result<usize, c_int> {
+    let fd = socket(AF_INET, SOCK_DGRAM | SOCK_NONBLOCK, 0);
+    if fd < 0 {
+        return Err(EAI_SYSTEM);
+    }
+    let result = exchange_on(fd, server, query, answer);
+    close(fd);
+    result
+}
+
+fn exchange_on(
+    fd: c_int,
+    server: [u8; 4],
+    query: &[u8],
+    answer: &mut [u8],
+) -> Result<usize, c_int> {
+    // only answers from the server are received on a connected socket
+    if connect(fd, &SockAddrIn::new(server, DNS_PORT)) < 0 {
+        return Err(EAI_SYSTEM);
+    }
+    if sendto(fd, query, 0, None) < 0 {
+        return Err(EAI_SYSTEM);
+    }
+    let deadline = uptime().ok_or(EAI_SYSTEM)? + TIMEOUT;
+    loop {
+        let len = recvfrom(fd, answer, 0, None);
+        if len >= 0 {
+            return Ok(len as usize);
+        }
+        if uptime().ok_or(EAI_SYSTEM)? >= deadline {
+            return Err(EAI_AGAIN);
+        }
+    }
+}
+
+/// The time since boot in hundredths of a second, from `/proc/uptime`.
+fn uptime() -> Option<u64> {
+    let fd = open(b"/proc/uptime");
+    if fd < 0 {
+        return None;
+    }
+    let mut buf = [0; 32];
+    let len = read(fd, &mut buf);
+    close(fd);
+    let content = str::from_utf8(&buf[..len.max(0) as usize]).ok()?;
+    let (secs, centis) = content.trim().split_once('.')?;
+    Some(secs.parse::<u64>().ok()? * 100 + centis.parse::<u64>().ok()?)
+}
+
+/// Parses the answer to the query with `id` and writes the addresses of
+/// its A records into `found`, as far as they fit. Returns the number of
+/// addresses.
+fn parse_answer(answer: &[u8], id: [u8; 2], found: &mut [[u8; 4]]) -> Result<usize, c_int> {
+    if answer.len() < HEADER_LEN || answer[..2] != id {
+        return Err(EAI_FAIL);
+    }
+    let flags = u16_at(answer, 2).ok_or(EAI_FAIL)?;
+    if flags & FLAG_RESPONSE == 0 {
+        return Err(EAI_FAIL);
+    }
+    match flags & 0xf {
+        0 => {}
+        RCODE_NAME_ERROR => return Err(EAI_NONAME),
+        _ => return Err(EAI_FAIL),
+    }
+    let questions = u16_at(answer, 4).ok_or(EAI_FAIL)?;
+    let answers = u16_at(answer, 6).ok_or(EAI_FAIL)?;
+
+    let mut offset = HEADER_LEN;
+    for _ in 0..questions {
+        // the name, type and class
+        offset = skip_name(answer, offset).ok_or(EAI_FAIL)? + 4;
+    }
+
+    let mut count = 0;
+    for _ in 0..answers {
+        offset = skip_name(answer, offset).ok_or(EAI_FAIL)?;
+        let typ = u16_at(answer, offset).ok_or(EAI_FAIL)?;
+        let class = u16_at(answer, offset + 2).ok_or(EAI_FAIL)?;
+        // the type, class and TTL come before the length of the data
+        let data_len = usize::from(u16_at(answer, offset + 8).ok_or(EAI_FAIL)?);
+        let data = answer
+            .get(offset + 10..offset + 10 + data_len)
+            .ok_or(EAI_FAIL)?;
+        // the CNAME records that lead to the A records are skipped
+        if typ == TYPE_A && class == CLASS_IN && count < found.len() {
+            found[count] = data.try_into().map_err(|_| EAI_FAIL)?;
+            count += 1;
+        }
+        offset += 10 + data_len;
+    }
+    Ok(count)
+}
+
+/// Returns the offset after the possibly compressed name at `offset`.
+fn skip_name(message: &[u8], mut offset: usize) -> Option<usize> {
+    loop {
+        let len = *message.get(offset)?;
+        match len {
+            0 => return Some(offset + 1),
+            // a pointer to the rest of the name ends it
+            len if len & 0xc0 == 0xc0 => return Some(offset + 2),
+            len => offset += 1 + usize::from(len),
+        }
+    }
+}
+
+fn u16_at(message: &[u8], offset: usize) -> Option<u16> {
+    let bytes = message.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
@@ -0,0 +1,87 @@
+//! A mutex and a condition variable like those of pthreads, which only
+//! enter the kernel to wait and to wake waiters.
+
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+use crate::{futex_wait, futex_wake};
+
+const UNLOCKED: u32 = 0;
+const LOCKED: u32 = 1;
+/// Locked, and other threads may be waiting.
+const CONTENDED: u32 = 2;
+
+#[derive(Debug, Default)]
+pub struct Mutex {
+    state: AtomicU32,
+}
+
+impl Mutex {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            state: AtomicU32::new(UNLOCKED),
+        }
+    }
+
+    pub fn lock(&self) {
+        if self.try_lock() {
+            return;
+        }
+        // once someone waited, the lock is taken as contended, since
+        // there may be more waiters that the unlock has to wake
+        while self.state.swap(CONTENDED, Acquire) != UNLOCKED {
+            futex_wait(&self.state, CONTENDED);
+        }
+    }
+
+    pub fn try_lock(&self) -> bool {
+        self.state
+            .compare_exchange(UNLOCKED, LOCKED, Acquire, Relaxed)
+            .is_ok()
+    }
+
+    pub fn unlock(&self) {
+        if self.state.swap(UNLOCKED, Release) == CONTENDED {
+            futex_wake(&self.state, 1);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Condvar {
+    /// Changes with every signal, so that a wait that starts after the
+    /// mutex was unlocked doesn't miss one.
+    seq: AtomicU32,
+}
+
+impl Condvar {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            seq: AtomicU32::new(0),
+        }
+    }
+
+    /// Unlocks `mutex`, waits until the condition variable is signaled and
+    /// locks `mutex` again. Like with pthreads, the wait may end without a
+    /// signal.
+    pub fn wait(&self, mutex: &Mutex) {
+        let seq = self.seq.load(Relaxed);
+        mutex.unlock();
+        futex_wait(&self.seq, seq);
+        mutex.lock();
+    }
+
+    /// Wakes one waiter.
+    pub fn signal(&self) {
+        self.seq.fetch_add(1, Relaxed);
+        futex_wake(&self.seq, 1);
+    }
+
+    /// Wakes all waiters.
+    pub fn broadcast(&self) {
+        self.seq.fetch_add(1, Relaxed);
+        futex_wake(&self.seq, i32::MAX);
+    }
+}
@@ -0,0 +1,88 @@
+//! Threads that share the address space and the file descriptors of the
+//! process, which can be joined like those of pthreads.
+
+use core::arch::x86_64::_mm_pause;
+use core::ffi::c_int;
+use core::sync::atomic::AtomicU32;
+use core::sync::atomic::Ordering::Acquire;
+
+use crate::{futex_wait, syscall1, syscall2};
+
+const CLONE_VM: u64 = 0x100;
+const CLONE_FS: u64 = 0x200;
+const CLONE_FILES: u64 = 0x400;
+const CLONE_SIGHAND: u64 = 0x800;
+const CLONE_THREAD: u64 = 0x10000;
+const CLONE_PARENT_SETTID: u64 = 0x10_0000;
+const CLONE_CHILD_CLEARTID: u64 = 0x20_0000;
+
+#[repr(C)]
+#[derive(Default)]
+struct CloneArgs {
+    flags: u64,
+    entry: u64,
+    arg: u64,
+    stack: u64,
+    stack_size: u64,
+    tls: u64,
+    parent_tid: u64,
+    child_tid: u64,
+}
+
+/// A thread, whose id is zero until it was spawned and once it exited.
+#[derive(Debug, Default)]
+pub struct Thread {
+    tid: AtomicU32,
+}
+
+impl Thread {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            tid: AtomicU32::new(0),
+        }
+    }
+
+    /// Starts the thread with a call of `entry` with `arg`. The kernel
+    /// allocates its stack and its thread-local storage.
+    ///
+    /// Returns the id of the thread, or a negative error number.
+    pub fn spawn(&'static self, entry: extern "C" fn(usize) -> !, arg: usize) -> c_int {
+        let tid = self.tid.as_ptr() as u64;
+        let args = CloneArgs {
+            flags: CLONE_VM
+                | CLONE_FS
+                | CLONE_FILES
+                | CLONE_SIGHAND
+                | CLONE_THREAD
+                | CLONE_PARENT_SETTID
+                | CLONE_CHILD_CLEARTID,
+            entry: entry as usize as u64,
+            arg: arg as u64,
+            parent_tid: tid,
+            child_tid: tid,
+            ..Default::default()
+        };
+        syscall2(68, &raw const args as usize, size_of::<CloneArgs>()) as i32
+    }
+
+    /// Waits until the thread exited.
+    pub fn join(&self) {
+        loop {
+            let tid = self.tid.load(Acquire);
+            if tid == 0 {
+                return;
+            }
+            futex_wait(&self.tid, tid);
+        }
+    }
+}
+
+/// Exits the current thread. The process exits with `code` if this was its
+/// last thread.
+pub fn exit_thread(code: i32) -> ! {
+    syscall1(1, code as usize);
+    loop {
+        _mm_pause();
+    }
+}
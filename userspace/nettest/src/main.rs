@@ -0,0 +1,223 @@
+#![no_std]
+#![no_main]
+
+use kernel_abi::{EAGAIN, EALREADY, EINPROGRESS, EISCONN, Errno};
+use minilib::{
+    AF_INET, AF_UNIX, EPOLL_CTL_ADD, EPOLLET, EPOLLIN, EpollEvent, POLLIN, POLLOUT, PollFd,
+    SOCK_DGRAM, SOCK_NONBLOCK, SOCK_STREAM, SockAddrIn, accept, bind, close, connect,
+    epoll_create1, epoll_ctl, epoll_wait, exit, getaddrinfo, listen, poll, read, recv_fd, recvfrom,
+    send_fd, sendto, socket, socketpair, write,
+};
+
+const LOCALHOST: [u8; 4] = [127, 0, 0, 1];
+
+/// Exercises TCP and UDP over the loopback interface, which works without
+/// a network device, and Unix sockets.
+#[unsafe(no_mangle)]
+pub extern "C" fn _start() {
+    tcp();
+    udp();
+    unix();
+    readiness();
+    resolve();
+    write(1, b"nettest: ok\n");
+    exit(0);
+}
+
+fn tcp() {
+    let listener = socket(AF_INET, SOCK_STREAM, 0);
+    check(listener >= 0, b"tcp socket");
+    let address = SockAddrIn::new(LOCALHOST, 7777);
+    check(bind(listener, &address) == 0, b"tcp bind");
+    check(listen(listener, 1) == 0, b"tcp listen");
+
+    // the client doesn't block in connect, so that the connection can be
+    // accepted from the same task
+    let client = socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0);
+    check(client >= 0, b"tcp client socket");
+    let result = connect(client, &address);
+    check(
+        result == 0 || is(result as isize, EINPROGRESS),
+        b"tcp connect",
+    );
+
+    let mut peer = SockAddrIn::default();
+    let server = accept(listener, Some(&mut peer));
+    check(server >= 0, b"tcp accept");
+    check(peer.addr() == LOCALHOST, b"tcp peer address");
+
+    loop {
+        let result = connect(client, &address);
+        if is(result as isize, EISCONN) {
+            break;
+        }
+        check(is(result as isize, EALREADY), b"tcp connect completion");
+    }
+
+    check(sendto(client, b"ping", 0, None) == 4, b"tcp send");
+    let mut buf = [0; 16];
+    let len = recvfrom(server, &mut buf, 0, None);
+    check(&buf[..len.max(0) as usize] == b"ping", b"tcp receive");
+
+    check(sendto(server, b"pong", 0, None) == 4, b"tcp reply");
+    let len = loop {
+        let len = recvfrom(client, &mut buf, 0, None);
+        if !is(len, EAGAIN) {
+            break len;
+        }
+    };
+    check(&buf[..len.max(0) as usize] == b"pong", b"tcp receive reply");
+
+    // the peer sees the end of the stream once the server is closed
+    check(close(server) == 0, b"tcp close");
+    let len = loop {
+        let len = recvfrom(client, &mut buf, 0, None);
+        if !is(len, EAGAIN) {
+            break len;
+        }
+    };
+    check(len == 0, b"tcp end of stream");
+
+    close(client);
+    close(listener);
+}
+
+fn udp() {
+    let a = socket(AF_INET, SOCK_DGRAM, 0);
+    let b = socket(AF_INET, SOCK_DGRAM, 0);
+    check(a >= 0 && b >= 0, b"udp socket");
+    let address_a = SockAddrIn::new(LOCALHOST, 7778);
+    let address_b = SockAddrIn::new(LOCALHOST, 7779);
+    check(bind(a, &address_a) == 0, b"udp bind");
+    check(bind(b, &address_b) == 0, b"udp bind");
+
+    check(
+        sendto(a, b"datagram", 0, Some(&address_b)) == 8,
+        b"udp send",
+    );
+    let mut buf = [0; 16];
+    let mut sender = SockAddrIn::default();
+    let len = recvfrom(b, &mut buf, 0, Some(&mut sender));
+    check(&buf[..len.max(0) as usize] == b"datagram", b"udp receive");
+    check(sender.port() == 7778, b"udp sender address");
+
+    close(a);
+    close(b);
+}
+
+fn unix() {
+    let mut fds = [0; 2];
+    check(
+        socketpair(AF_UNIX, SOCK_STREAM, 0, &mut fds) == 0,
+        b"unix socketpair",
+    );
+    let mut inner = [0; 2];
+    check(
+        socketpair(AF_UNIX, SOCK_DGRAM, 0, &mut inner) == 0,
+        b"unix datagram socketpair",
+    );
+
+    // pass one end of the datagram pair through the stream pair
+    check(send_fd(fds[0], b"fd", inner[1]) == 2, b"unix send fd");
+    close(inner[1]);
+    let mut buf = [0; 16];
+    let (len, received) = recv_fd(fds[1], &mut buf);
+    check(&buf[..len.max(0) as usize] == b"fd", b"unix receive fd");
+    let Some(received) = received else {
+        check(false, b"unix rights");
+        return;
+    };
+
+    check(sendto(inner[0], b"hello", 0, None) == 5, b"unix send");
+    let len = read(received, &mut buf);
+    check(&buf[..len.max(0) as usize] == b"hello", b"unix receive");
+
+    close(received);
+    close(inner[0]);
+    close(fds[0]);
+    close(fds[1]);
+}
+
+/// Waits for a Unix socket with `poll` and with an edge-triggered epoll
+/// instance.
+fn readiness() {
+    let mut fds = [0; 2];
+    check(
+        socketpair(AF_UNIX, SOCK_STREAM, 0, &mut fds) == 0,
+        b"readiness socketpair",
+    );
+
+    let mut pollfds = [PollFd {
+        fd: fds[1],
+        events: POLLIN | POLLOUT,
+        revents: 0,
+    }];
+    check(poll(&mut pollfds, 0) == 1, b"poll writable");
+    check(pollfds[0].revents == POLLOUT, b"poll not readable");
+    check(write(fds[0], b"x") == 1, b"readiness write");
+    check(poll(&mut pollfds, -1) == 1, b"poll");
+    check(pollfds[0].revents == POLLIN | POLLOUT, b"poll readable");
+
+    let epfd = epoll_create1(0);
+    check(epfd >= 0, b"epoll_create1");
+    let event = EpollEvent {
+        events: EPOLLIN | EPOLLET,
+        data: 42,
+    };
+    check(
+        epoll_ctl(epfd, EPOLL_CTL_ADD, fds[1], &event) == 0,
+        b"epoll_ctl",
+    );
+    let mut events = [EpollEvent::default(); 4];
+    check(epoll_wait(epfd, &mut events, -1) == 1, b"epoll_wait");
+    let data = events[0].data;
+    check(data == 42, b"epoll data");
+    // the edge was reported, so nothing is left until more data arrives
+    check(epoll_wait(epfd, &mut events, 0) == 0, b"epoll edge");
+    let mut buf = [0; 4];
+    check(read(fds[1], &mut buf) == 1, b"readiness read");
+    check(write(fds[0], b"y") == 1, b"readiness write");
+    check(epoll_wait(epfd, &mut events, 0) == 1, b"epoll next edge");
+
+    close(epfd);
+    close(fds[0]);
+    close(fds[1]);
+}
+
+/// Resolves the names that don't need a DNS server.
+fn resolve() {
+    let mut addresses = [SockAddrIn::default(); 2];
+    check(
+        getaddrinfo("localhost", 80, &mut addresses) == 1,
+        b"resolve localhost",
+    );
+    check(
+        addresses[0].addr() == LOCALHOST && addresses[0].port() == 80,
+        b"localhost address",
+    );
+    check(
+        getaddrinfo("10.0.2.2", 53, &mut addresses) == 1,
+        b"resolve dotted address",
+    );
+    check(addresses[0].addr() == [10, 0, 2, 2], b"dotted address");
+}
+
+/// Whether a syscall failed with `errno`.
+fn is(result: isize, errno: Errno) -> bool {
+    result == -isize::from(errno)
+}
+
+fn check(ok: bool, what: &[u8]) {
+    if !ok {
+        write(2, b"nettest: failed: ");
+        write(2, what);
+        write(2, b"\n");
+        exit(1);
+    }
+}
+
+#[cfg(not(test))]
+#[panic_handler]
+fn panic(_info: &::core::panic::PanicInfo) -> ! {
+    loop {}
+}